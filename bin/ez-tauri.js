@@ -23,7 +23,7 @@ program
   .command('create')
   .description('Create a new EZ Tauri project')
   .argument('<project-name>', 'Name of the project')
-  .option('-t, --template <type>', 'Template type (minimal, full, database)', 'full')
+  .option('-t, --template <type>', 'Template type (minimal, full, database, offline)', 'full')
   .option('-pm, --package-manager <type>', 'Package manager (npm, yarn, pnpm)', 'npm')
   .option('--no-install', 'Skip npm install')
   .option('--no-git', 'Skip git init')
@@ -32,7 +32,7 @@ program
 program
   .command('init')
   .description('Initialize EZ Tauri in current directory')
-  .option('-t, --template <type>', 'Template type (minimal, full, database)', 'full')
+  .option('-t, --template <type>', 'Template type (minimal, full, database, offline)', 'full')
   .option('-pm, --package-manager <type>', 'Package manager (npm, yarn, pnpm)', 'npm')
   .option('--no-install', 'Skip npm install')
   .action(initProject);
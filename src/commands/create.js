@@ -81,6 +81,9 @@ export async function createProject(projectName, options) {
       console.log(chalk.cyan('\nDatabase Setup (if using database features):'));
       console.log(chalk.white('  1. docker-compose up -d'));
       console.log(chalk.white('  2. Configure your .env file'));
+    } else if (config.template === 'offline') {
+      console.log(chalk.cyan('\nDatabase:'));
+      console.log(chalk.white('  Data is stored in a SQLite file in the app data directory; no setup needed.'));
     }
 
     console.log(chalk.cyan('\nUseful Commands:'));
@@ -118,12 +121,17 @@ async function gatherProjectConfig(projectName, options) {
           name: 'Database - Includes PostgreSQL setup',
           value: 'database',
           description: 'Full template + PostgreSQL + Docker + Migrations'
+        },
+        {
+          name: 'Offline - Full template with a bundled SQLite database',
+          value: 'offline',
+          description: 'Full template + SQLite in the app data dir, no external services'
         }
       ]
     });
   } else {
     // Validate template option
-    const validTemplates = ['full', 'minimal', 'database'];
+    const validTemplates = ['full', 'minimal', 'database', 'offline'];
     if (!validTemplates.includes(options.template)) {
       console.error(chalk.red(`❌ Invalid template: ${options.template}`));
       console.log(chalk.yellow(`Valid templates: ${validTemplates.join(', ')}`));
@@ -8,11 +8,33 @@ import ora from 'ora';
 const __dirname = path.dirname(fileURLToPath(import.meta.url));
 const templatesDir = path.join(__dirname, '../../templates');
 
+// Profiles are variants of a template directory that swap backend features
+// instead of shipping a second copy of the template.
+const templateProfiles = {
+  offline: {
+    base: 'full',
+    // Bundled SQLite in the app data dir; no PostgreSQL, Redis, or Docker.
+    // Users, sessions, and logs work as with PostgreSQL; the frontend API
+    // rejects PostgreSQL-only features (OAuth, audit log, impersonation, ...)
+    cargoFeatures: ['sqlite', 'stronghold', 'rate-limiter'],
+    removePaths: [
+      'Dockerfile',
+      'docker-compose.yml',
+      'docker-compose.prod.yml',
+      'database',
+      'scripts/ez-db.mjs',
+      'scripts/ez-docker.mjs'
+    ],
+    removeScripts: /^(db|docker):/
+  }
+};
+
 export async function copyTemplate(templateType, targetPath, config) {
   const spinner = ora(`Copying ${templateType} template...`).start();
 
   try {
-    const templatePath = path.join(templatesDir, templateType);
+    const profile = templateProfiles[templateType];
+    const templatePath = path.join(templatesDir, profile ? profile.base : templateType);
 
     // Check if template exists
     if (!await fs.pathExists(templatePath)) {
@@ -25,6 +47,10 @@ export async function copyTemplate(templateType, targetPath, config) {
     // Copy template files
     await fs.copy(templatePath, targetPath);
 
+    if (profile) {
+      await applyProfile(targetPath, profile);
+    }
+
     // Process template files (replace placeholders)
     await processTemplateFiles(targetPath, config);
 
@@ -35,6 +61,31 @@ export async function copyTemplate(templateType, targetPath, config) {
   }
 }
 
+async function applyProfile(projectPath, profile) {
+  for (const relativePath of profile.removePaths) {
+    await fs.remove(path.join(projectPath, relativePath));
+  }
+
+  const packageJsonPath = path.join(projectPath, 'package.json');
+  if (await fs.pathExists(packageJsonPath)) {
+    const packageJson = await fs.readJson(packageJsonPath);
+    for (const script of Object.keys(packageJson.scripts || {})) {
+      if (profile.removeScripts.test(script)) {
+        delete packageJson.scripts[script];
+      }
+    }
+    await fs.writeJson(packageJsonPath, packageJson, { spaces: 2 });
+  }
+
+  const cargoTomlPath = path.join(projectPath, 'src-tauri', 'Cargo.toml');
+  if (await fs.pathExists(cargoTomlPath)) {
+    let cargoContent = await fs.readFile(cargoTomlPath, 'utf-8');
+    const features = profile.cargoFeatures.map(feature => `"${feature}"`).join(', ');
+    cargoContent = cargoContent.replace(/^default = \[.*\]$/m, `default = [${features}]`);
+    await fs.writeFile(cargoTomlPath, cargoContent);
+  }
+}
+
 async function processTemplateFiles(projectPath, config) {
   const packageJsonPath = path.join(projectPath, 'package.json');
 
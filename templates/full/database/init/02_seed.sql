@@ -16,7 +16,7 @@
 -- =====================================================================
 -- SAMPLE USERS
 -- =====================================================================
--- Test user accounts with bcrypt-hashed passwords
+-- Test user accounts with bcrypt-hashed passwords (upgraded to Argon2id on first login)
 INSERT INTO users (email, username, password_hash, first_name, last_name) VALUES
     ('admin@example.com', 'admin', '$2b$12$LQv3c1yqBWVHxkd0LHAkCOYz6TtxMQJqhN8/LewdBPj/Go2b/jRWu', 'Admin', 'User'),   -- Administrator account
     ('user@example.com', 'user', '$2b$12$LQv3c1yqBWVHxkd0LHAkCOYz6TtxMQJqhN8/LewdBPj/Go2b/jRWu', 'Test', 'User'),    -- Standard test user
@@ -1,6 +1,56 @@
 //! Build script for Tauri application.
 
+use std::env;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 /// Executes the Tauri build process.
 fn main() {
+    export_build_info();
     tauri_build::build()
 }
+
+/// Exposes the git commit, build time, profile, and target to
+/// `src/build_info.rs` as `EZ_*` compile-time variables.
+///
+/// The commit is empty outside a git checkout. `SOURCE_DATE_EPOCH` overrides
+/// the build time for reproducible builds.
+fn export_build_info() {
+    let commit = git(&["rev-parse", "--short=12", "HEAD"]).unwrap_or_default();
+    let timestamp = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        });
+
+    println!("cargo:rustc-env=EZ_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=EZ_BUILD_TIMESTAMP={}", timestamp);
+    println!(
+        "cargo:rustc-env=EZ_BUILD_PROFILE={}",
+        env::var("PROFILE").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=EZ_BUILD_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+
+    // Commits and checkouts append to the HEAD reflog.
+    if let Some(reflog) = git(&["rev-parse", "--git-path", "logs/HEAD"]) {
+        println!("cargo:rerun-if-changed={}", reflog);
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+/// Runs git with `args`, returning its trimmed output on success.
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
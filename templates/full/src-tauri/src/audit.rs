@@ -0,0 +1,245 @@
+//! Append-only audit trail for security-sensitive operations.
+//!
+//! Each entry records the action, who performed it, and which user it
+//! affected. Both are referenced by id without a foreign key, so the record
+//! of an erasure outlives the erased account.
+
+use crate::database::filter::FilterBuilder;
+use crate::database::get_pool_ref;
+use crate::errors::{AppResult, ErrorCode, IntoAppError};
+use crate::models::{AuditEntry, AuditQuery, SortDirection};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+/// Audit action recorded when a user's personal data is erased.
+pub const USER_DATA_ERASED: &str = "user_data_erased";
+
+/// Audit action recorded when a user's personal data is exported.
+pub const PERSONAL_DATA_EXPORTED: &str = "personal_data_exported";
+
+/// Audit action recorded when a user account is created.
+pub const USER_CREATED: &str = "user_created";
+
+/// Audit action recorded when a user's profile or status changes.
+pub const USER_UPDATED: &str = "user_updated";
+
+/// Audit action recorded when a user account is deleted.
+pub const USER_DELETED: &str = "user_deleted";
+
+/// Audit action recorded when a guest account gets credentials or is merged
+/// into an existing account.
+pub const GUEST_UPGRADED: &str = "guest_upgraded";
+
+/// Audit action recorded when a user schedules the deletion of their account.
+pub const ACCOUNT_DELETION_REQUESTED: &str = "account_deletion_requested";
+
+/// Audit action recorded when a scheduled account deletion is called off.
+pub const ACCOUNT_DELETION_CANCELLED: &str = "account_deletion_cancelled";
+
+/// Audit action recorded when an admin starts a session as another user.
+pub const IMPERSONATION_STARTED: &str = "impersonation_started";
+
+/// Audit action recorded when an impersonation session is ended.
+pub const IMPERSONATION_ENDED: &str = "impersonation_ended";
+
+/// Audit action recorded when a used remember-me refresh token is presented
+/// again and its session is revoked.
+pub const REFRESH_TOKEN_REUSED: &str = "refresh_token_reused";
+
+/// Audit action recorded when a user changes their password.
+pub const PASSWORD_CHANGED: &str = "password_changed";
+
+/// Audit action recorded when a sign-in succeeds.
+pub const LOGIN_SUCCEEDED: &str = "login_succeeded";
+
+/// Audit action recorded when a sign-in is rejected.
+pub const LOGIN_FAILED: &str = "login_failed";
+
+/// Audit action recorded when a user's settings change.
+pub const SETTINGS_CHANGED: &str = "settings_changed";
+
+/// Audit action recorded when a file or directory is deleted.
+pub const FILE_DELETED: &str = "file_deleted";
+
+/// Appends an audit entry using `executor`, so it can join a transaction.
+pub async fn record<'e>(
+    executor: impl PgExecutor<'e>,
+    action: &str,
+    user_id: Option<Uuid>,
+    details: serde_json::Value,
+) -> AppResult<()> {
+    record_by(executor, None, action, user_id, details).await
+}
+
+/// Appends an audit entry for an action performed by `actor_id`.
+pub async fn record_by<'e>(
+    executor: impl PgExecutor<'e>,
+    actor_id: Option<Uuid>,
+    action: &str,
+    user_id: Option<Uuid>,
+    details: serde_json::Value,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO audit_log (action, actor_id, user_id, details) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(action)
+    .bind(actor_id)
+    .bind(user_id)
+    .bind(details)
+    .execute(executor)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    Ok(())
+}
+
+/// Appends an audit entry on the shared pool for an action that already
+/// happened; failures are logged, not returned.
+pub async fn record_detached(
+    actor_id: Option<Uuid>,
+    action: &str,
+    user_id: Option<Uuid>,
+    details: serde_json::Value,
+) {
+    let result = async {
+        let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+        record_by(pool.as_ref(), actor_id, action, user_id, details).await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to record audit entry '{}': {}", action, e);
+    }
+}
+
+/// Lists entries matching `query`, newest first.
+pub async fn list<'e>(
+    executor: impl PgExecutor<'e>,
+    query: AuditQuery,
+) -> AppResult<Vec<AuditEntry>> {
+    let AuditQuery {
+        action,
+        actor_id,
+        user_id,
+        from,
+        to,
+        limit,
+        offset,
+    } = query;
+    let limit = limit.unwrap_or(100).clamp(1, 1_000);
+    let offset = offset.unwrap_or(0).max(0);
+
+    let mut filter = FilterBuilder::new(
+        "SELECT id,
+                action,
+                actor_id,
+                user_id,
+                COALESCE(details, '{}') AS details,
+                created_at
+         FROM audit_log",
+    );
+    filter
+        .eq_opt("action", action)
+        .eq_opt("actor_id", actor_id)
+        .eq_opt("user_id", user_id)
+        .range("created_at", from, to)
+        .order_by(&"created_at", SortDirection::Desc, Some("id"))
+        .paginate(limit, offset);
+
+    filter
+        .into_builder()
+        .build_query_as::<AuditEntry>()
+        .fetch_all(executor)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use anyhow::Result as AnyResult;
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[tokio::test]
+    #[serial]
+    async fn lists_matching_entries_newest_first() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let admin = Uuid::new_v4();
+        let user = Uuid::new_v4();
+
+        record_by(
+            pool.as_ref(),
+            Some(admin),
+            USER_CREATED,
+            Some(user),
+            json!({}),
+        )
+        .await?;
+        record_by(
+            pool.as_ref(),
+            Some(user),
+            PASSWORD_CHANGED,
+            Some(user),
+            json!({}),
+        )
+        .await?;
+        record(
+            pool.as_ref(),
+            LOGIN_FAILED,
+            None,
+            json!({ "email": "x@example.com" }),
+        )
+        .await?;
+
+        let all = list(pool.as_ref(), AuditQuery::default()).await?;
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].action, LOGIN_FAILED);
+
+        let by_user = list(
+            pool.as_ref(),
+            AuditQuery {
+                user_id: Some(user),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(by_user.len(), 2);
+
+        let by_admin = list(
+            pool.as_ref(),
+            AuditQuery {
+                actor_id: Some(admin),
+                action: Some(USER_CREATED.to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(by_admin.len(), 1);
+        assert_eq!(by_admin[0].user_id, Some(user));
+
+        let paged = list(
+            pool.as_ref(),
+            AuditQuery {
+                limit: Some(1),
+                offset: Some(1),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(paged.len(), 1);
+        assert_eq!(paged[0].id, all[1].id);
+
+        let future = list(
+            pool.as_ref(),
+            AuditQuery {
+                from: Some(chrono::Utc::now() + chrono::Duration::hours(1)),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert!(future.is_empty());
+        Ok(())
+    }
+}
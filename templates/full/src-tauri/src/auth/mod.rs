@@ -0,0 +1,8 @@
+//! Sign-in through external identity providers, pluggable backends for
+//! password sign-in, and protection of password sign-in against guessing.
+
+#[cfg(feature = "database")]
+pub mod oauth;
+#[cfg(feature = "database")]
+pub mod provider;
+pub mod throttle;
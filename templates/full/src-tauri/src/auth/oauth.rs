@@ -0,0 +1,710 @@
+//! OAuth2 / OpenID Connect sign-in using the authorization-code flow with
+//! PKCE.
+//!
+//! [`start`] opens the provider's authorization page in the system browser
+//! with a loopback redirect to the embedded server's
+//! [`OAUTH_CALLBACK_ROUTE`] and waits for that redirect in the background.
+//! [`complete`] exchanges the returned code for an access token, reads the
+//! profile from the userinfo endpoint, and signs in the linked local user,
+//! creating one on first sign-in. An existing account is linked by email
+//! only when the provider reports the address as verified.
+
+use crate::config::{self, OAuthSettings};
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::events::{self, AppEvent};
+use crate::models::{LoginResponse, PublicUser, User};
+use crate::password;
+use crate::proxy;
+use crate::repository::{NewUser, PgUserRepository, UserRepository};
+use crate::server::{self, OAUTH_CALLBACK_ROUTE};
+use crate::session::{self, random_token};
+use crate::validation::{validate_email, validate_optional_name};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+use tokio::sync::{broadcast, oneshot};
+use uuid::Uuid;
+
+/// How long the user has to finish signing in at the provider.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Timeout for each request to the provider.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Attempts at finding a free username before giving up.
+const USERNAME_ATTEMPTS: usize = 5;
+
+/// Logins waiting for their redirect, keyed by `state`.
+static PENDING: Lazy<Mutex<HashMap<String, PendingLogin>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct PendingLogin {
+    settings: OAuthSettings,
+    endpoints: Endpoints,
+    verifier: String,
+    redirect_uri: String,
+    started_at: Instant,
+    /// Query parameters of the redirect, once it arrives.
+    callback: oneshot::Receiver<HashMap<String, String>>,
+}
+
+/// Provider endpoints used by the flow.
+#[derive(Debug, Clone, PartialEq)]
+struct Endpoints {
+    authorization: String,
+    token: String,
+    userinfo: String,
+}
+
+/// Subset of an OpenID Connect discovery document.
+#[derive(Debug, Default, Deserialize)]
+struct Discovery {
+    authorization_endpoint: Option<String>,
+    token_endpoint: Option<String>,
+    userinfo_endpoint: Option<String>,
+}
+
+/// Claims read from the userinfo endpoint.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct Profile {
+    pub(crate) sub: String,
+    pub(crate) email: Option<String>,
+    #[serde(default)]
+    pub(crate) email_verified: bool,
+    pub(crate) preferred_username: Option<String>,
+    pub(crate) given_name: Option<String>,
+    pub(crate) family_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Error body of a rejected token request.
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// A sign-in started by [`start`]; pass `state` to [`complete`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthLoginStart {
+    pub state: String,
+    /// Page opened in the browser, for showing to the user if opening failed.
+    pub authorization_url: String,
+}
+
+/// Opens the provider's sign-in page and starts listening for its redirect.
+pub async fn start(app: &AppHandle) -> AppResult<OAuthLoginStart> {
+    let config = config::current();
+    let settings = config.oauth.clone().ok_or_else(|| {
+        AppError::new(
+            ErrorCode::ConfigurationError,
+            "OAuth sign-in is not configured; set OAUTH_CLIENT_ID",
+        )
+    })?;
+    let endpoints = resolve_endpoints(&settings).await?;
+
+    let address = server::start_server(config.local_server_port.unwrap_or(0))
+        .await
+        .into_app_error(ErrorCode::NetworkError)?;
+    let redirect_uri = format!("http://{}{}", address, OAUTH_CALLBACK_ROUTE);
+
+    let state = random_token();
+    let verifier = random_token();
+    let challenge = code_challenge(&verifier);
+    let authorization_url = reqwest::Url::parse_with_params(
+        &endpoints.authorization,
+        &[
+            ("response_type", "code"),
+            ("client_id", settings.client_id.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("scope", settings.scopes.as_str()),
+            ("state", state.as_str()),
+            ("code_challenge", challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|e| {
+        AppError::new(
+            ErrorCode::ConfigurationError,
+            format!("Invalid OAuth authorization endpoint: {}", e),
+        )
+    })?;
+
+    // Subscribe before the browser opens so a fast redirect is not missed.
+    let callback = wait_for_callback(state.clone(), events::subscribe());
+    {
+        let mut pending = PENDING
+            .lock()
+            .map_err(|_| AppError::internal_error("OAuth login registry is poisoned"))?;
+        pending.retain(|_, login| login.started_at.elapsed() < LOGIN_TIMEOUT);
+        pending.insert(
+            state.clone(),
+            PendingLogin {
+                settings,
+                endpoints,
+                verifier,
+                redirect_uri,
+                started_at: Instant::now(),
+                callback,
+            },
+        );
+    }
+
+    if let Err(e) = app
+        .opener()
+        .open_url(authorization_url.as_str(), None::<&str>)
+    {
+        tracing::warn!("Failed to open the browser for OAuth sign-in: {}", e);
+    }
+
+    Ok(OAuthLoginStart {
+        state,
+        authorization_url: authorization_url.to_string(),
+    })
+}
+
+/// Waits for the redirect of the login identified by `state`, then signs in
+/// the linked user and starts a session.
+///
+/// Fails with `TOKEN_EXPIRED` when the user did not finish in time and
+/// `UNAUTHORIZED` when the provider declined or the state is unknown.
+pub async fn complete(pool: &PgPool, state: &str) -> AppResult<LoginResponse> {
+    let login = PENDING
+        .lock()
+        .map_err(|_| AppError::internal_error("OAuth login registry is poisoned"))?
+        .remove(state)
+        .ok_or_else(|| AppError::unauthorized("Unknown or expired sign-in attempt"))?;
+    let query = login
+        .callback
+        .await
+        .map_err(|_| AppError::new(ErrorCode::TokenExpired, "Sign-in was not completed in time"))?;
+
+    if let Some(error) = query.get("error") {
+        let reason = query.get("error_description").unwrap_or(error);
+        return Err(AppError::unauthorized(format!(
+            "Sign-in was declined: {}",
+            reason
+        )));
+    }
+    let code = query.get("code").ok_or_else(|| {
+        AppError::invalid_input("code", "The provider did not return an authorization code")
+    })?;
+
+    let access_token = exchange_code(&login, code).await?;
+    let profile = fetch_profile(&login.endpoints.userinfo, &access_token).await?;
+    let user = link_or_create(pool, &login.settings.provider, profile).await?;
+
+    let session = session::start(pool, user.id, false).await?;
+    tracing::info!(
+        "User {} signed in with {}",
+        user.id,
+        login.settings.provider
+    );
+    Ok(LoginResponse {
+        user: PublicUser::from(user),
+        session,
+    })
+}
+
+/// Signs in with the resource owner password grant, for providers that
+/// still allow it, and returns the linked user. `None` means the provider
+/// rejected the credentials.
+pub(crate) async fn sign_in_with_password(
+    pool: &PgPool,
+    settings: &OAuthSettings,
+    username: &str,
+    password: &str,
+) -> AppResult<Option<User>> {
+    let endpoints = resolve_endpoints(settings).await?;
+    let mut form = vec![
+        ("grant_type", "password"),
+        ("username", username),
+        ("password", password),
+        ("client_id", settings.client_id.as_str()),
+        ("scope", settings.scopes.as_str()),
+    ];
+    if let Some(secret) = &settings.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+
+    let response = client_for(&endpoints.token)?
+        .post(&endpoints.token)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(provider_error)?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return match serde_json::from_str::<TokenError>(&body) {
+            // Wrong credentials; any other error means the client or grant
+            // is misconfigured.
+            Ok(error) if error.error == "invalid_grant" => Ok(None),
+            Ok(error) => Err(AppError::new(
+                ErrorCode::ConfigurationError,
+                format!(
+                    "Password grant failed: {}",
+                    error.error_description.unwrap_or(error.error)
+                ),
+            )),
+            Err(_) => Err(AppError::new(
+                ErrorCode::ExternalServiceUnavailable,
+                format!("Password grant failed ({}): {}", status, body.trim()),
+            )),
+        };
+    }
+
+    let tokens: TokenResponse = response
+        .json()
+        .await
+        .into_app_error(ErrorCode::InvalidFormat)?;
+    let profile = fetch_profile(&endpoints.userinfo, &tokens.access_token).await?;
+    link_or_create(pool, &settings.provider, profile)
+        .await
+        .map(Some)
+}
+
+/// Forwards the first redirect carrying `state` to the returned receiver.
+/// The sender is dropped after [`LOGIN_TIMEOUT`] without a redirect.
+fn wait_for_callback(
+    state: String,
+    mut events: broadcast::Receiver<AppEvent>,
+) -> oneshot::Receiver<HashMap<String, String>> {
+    let (sender, receiver) = oneshot::channel();
+    tauri::async_runtime::spawn(async move {
+        let redirect = async {
+            loop {
+                match events.recv().await {
+                    Ok(AppEvent::ServerRequest { request, .. })
+                        if request.route == OAUTH_CALLBACK_ROUTE
+                            && request.query.get("state") == Some(&state) =>
+                    {
+                        return Some(request.query);
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        };
+
+        if let Ok(Some(query)) = tokio::time::timeout(LOGIN_TIMEOUT, redirect).await {
+            let _ = sender.send(query);
+        }
+    });
+    receiver
+}
+
+/// S256 PKCE challenge for `verifier`.
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+async fn resolve_endpoints(settings: &OAuthSettings) -> AppResult<Endpoints> {
+    let discovery = match &settings.issuer {
+        Some(issuer) => {
+            let url = format!("{}/.well-known/openid-configuration", issuer);
+            client_for(&url)?
+                .get(&url)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(provider_error)?
+                .json::<Discovery>()
+                .await
+                .into_app_error(ErrorCode::InvalidFormat)?
+        }
+        None => Discovery::default(),
+    };
+    merge_endpoints(settings, discovery)
+}
+
+/// Combines explicitly configured endpoints with discovered ones.
+fn merge_endpoints(settings: &OAuthSettings, discovery: Discovery) -> AppResult<Endpoints> {
+    let pick = |explicit: &Option<String>, discovered: Option<String>, name: &str| {
+        explicit.clone().or(discovered).ok_or_else(|| {
+            AppError::new(
+                ErrorCode::ConfigurationError,
+                format!(
+                    "OAuth {} endpoint is not configured; set OAUTH_ISSUER or the endpoint URL",
+                    name
+                ),
+            )
+        })
+    };
+
+    Ok(Endpoints {
+        authorization: pick(
+            &settings.authorize_url,
+            discovery.authorization_endpoint,
+            "authorization",
+        )?,
+        token: pick(&settings.token_url, discovery.token_endpoint, "token")?,
+        userinfo: pick(
+            &settings.userinfo_url,
+            discovery.userinfo_endpoint,
+            "userinfo",
+        )?,
+    })
+}
+
+async fn exchange_code(login: &PendingLogin, code: &str) -> AppResult<String> {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", login.redirect_uri.as_str()),
+        ("client_id", login.settings.client_id.as_str()),
+        ("code_verifier", login.verifier.as_str()),
+    ];
+    if let Some(secret) = &login.settings.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+
+    let response = client_for(&login.endpoints.token)?
+        .post(&login.endpoints.token)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(provider_error)?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::unauthorized(format!(
+            "Token exchange failed ({}): {}",
+            status,
+            body.trim()
+        )));
+    }
+
+    let tokens: TokenResponse = response
+        .json()
+        .await
+        .into_app_error(ErrorCode::InvalidFormat)?;
+    Ok(tokens.access_token)
+}
+
+async fn fetch_profile(userinfo_url: &str, access_token: &str) -> AppResult<Profile> {
+    let response = client_for(userinfo_url)?
+        .get(userinfo_url)
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(provider_error)?;
+    if !response.status().is_success() {
+        return Err(AppError::unauthorized(format!(
+            "Failed to read the user profile ({})",
+            response.status()
+        )));
+    }
+
+    response
+        .json()
+        .await
+        .into_app_error(ErrorCode::InvalidFormat)
+}
+
+/// Returns the user linked to the provider identity, linking an existing
+/// account or creating a new one on first sign-in.
+pub(crate) async fn link_or_create(
+    pool: &PgPool,
+    provider: &str,
+    profile: Profile,
+) -> AppResult<User> {
+    let repo = PgUserRepository::new(pool);
+    let linked: Option<Uuid> = sqlx::query_scalar(
+        "SELECT user_id FROM oauth_identities WHERE provider = $1 AND subject = $2",
+    )
+    .bind(provider)
+    .bind(&profile.sub)
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    if let Some(user_id) = linked {
+        let user = repo
+            .find(user_id)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?
+            .ok_or_else(|| AppError::not_found("User"))?;
+        if !user.is_active {
+            return Err(AppError::unauthorized("This account is disabled"));
+        }
+        return Ok(user);
+    }
+
+    let email = profile.email.as_deref().ok_or_else(|| {
+        AppError::invalid_input("email", "The provider did not share an email address")
+    })?;
+    let email =
+        validate_email(email).map_err(|e| AppError::invalid_input("email", e.to_string()))?;
+
+    let existing = repo
+        .find_active_by_email(&email)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    let mut user = match existing {
+        Some(_) if !profile.email_verified => {
+            return Err(AppError::unauthorized(
+                "An account with this email already exists and the provider has not verified the address",
+            ))
+        }
+        Some(user) => user,
+        None => create_user(pool, &repo, email, &profile).await?,
+    };
+
+    sqlx::query(
+        "INSERT INTO oauth_identities (user_id, provider, subject)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (provider, subject) DO NOTHING",
+    )
+    .bind(user.id)
+    .bind(provider)
+    .bind(&profile.sub)
+    .execute(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    if profile.email_verified && !user.email_verified {
+        sqlx::query("UPDATE users SET email_verified = true WHERE id = $1 AND email = $2")
+            .bind(user.id)
+            .bind(&user.email)
+            .execute(pool)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+        user.email_verified = true;
+    }
+
+    tracing::info!("Linked {} identity to user {}", provider, user.id);
+    Ok(user)
+}
+
+/// Creates an account for a first sign-in. It gets a random password, so
+/// until one is set it can only sign in through the provider.
+async fn create_user(
+    pool: &PgPool,
+    repo: &PgUserRepository<'_>,
+    email: String,
+    profile: &Profile,
+) -> AppResult<User> {
+    let base = username_base(profile.preferred_username.as_deref(), &email);
+    let mut username = None;
+    for attempt in 0..USERNAME_ATTEMPTS {
+        let candidate = if attempt == 0 {
+            base.clone()
+        } else {
+            format!("{}_{}", base, &Uuid::new_v4().simple().to_string()[..6])
+        };
+        let taken: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)")
+                .bind(&candidate)
+                .fetch_one(pool)
+                .await
+                .into_app_error(ErrorCode::DatabaseQuery)?;
+        if !taken {
+            username = Some(candidate);
+            break;
+        }
+    }
+    let username =
+        username.ok_or_else(|| AppError::internal_error("Could not find a free username"))?;
+
+    repo.insert(NewUser {
+        email,
+        username,
+        password_hash: password::hash(&random_token())?,
+        first_name: validate_optional_name(profile.given_name.as_deref())
+            .ok()
+            .flatten(),
+        last_name: validate_optional_name(profile.family_name.as_deref())
+            .ok()
+            .flatten(),
+        is_active: true,
+        is_guest: false,
+    })
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)
+}
+
+/// Derives a valid username (3-50 letters, digits, or underscores) from the
+/// provider's preferred username or the email's local part. The result is
+/// at most 43 characters so a suffix can be added on collisions.
+fn username_base(preferred: Option<&str>, email: &str) -> String {
+    let source = preferred
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+    let mut base: String = source
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(43)
+        .collect();
+    if base.len() < 3 {
+        base = format!("user_{}", base);
+    }
+    base
+}
+
+fn client_for(url: &str) -> AppResult<reqwest::Client> {
+    let proxy = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| proxy::proxy_for(&url))
+        .and_then(|proxy| reqwest::Proxy::all(&proxy).ok());
+    let builder = reqwest::Client::builder().timeout(REQUEST_TIMEOUT);
+    let builder = match proxy {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder.no_proxy(),
+    };
+
+    builder
+        .build()
+        .map_err(|e| AppError::internal_error(format!("Failed to build HTTP client: {}", e)))
+}
+
+fn provider_error(e: reqwest::Error) -> AppError {
+    AppError::new(
+        ErrorCode::ExternalServiceUnavailable,
+        format!("OAuth provider request failed: {}", e),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::test_support::UserFactory;
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    fn settings() -> OAuthSettings {
+        OAuthSettings {
+            provider: "example".to_string(),
+            client_id: "client".to_string(),
+            client_secret: None,
+            issuer: None,
+            authorize_url: Some("https://id.example.com/authorize".to_string()),
+            token_url: None,
+            userinfo_url: None,
+            scopes: "openid email".to_string(),
+        }
+    }
+
+    fn profile(sub: &str, email: &str, verified: bool) -> Profile {
+        Profile {
+            sub: sub.to_string(),
+            email: Some(email.to_string()),
+            email_verified: verified,
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn code_challenge_matches_rfc_7636_example() {
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-1mB92DLrQkDlbWzt4ES1tGRiHxwb9M"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn explicit_endpoints_take_precedence_over_discovery() {
+        let endpoints = merge_endpoints(
+            &settings(),
+            Discovery {
+                authorization_endpoint: Some("https://discovered/authorize".to_string()),
+                token_endpoint: Some("https://discovered/token".to_string()),
+                userinfo_endpoint: Some("https://discovered/userinfo".to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(endpoints.authorization, "https://id.example.com/authorize");
+        assert_eq!(endpoints.token, "https://discovered/token");
+
+        let error = merge_endpoints(&settings(), Discovery::default()).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::ConfigurationError));
+    }
+
+    #[test]
+    fn derives_valid_usernames() {
+        assert_eq!(
+            username_base(Some("ada.lovelace"), "x@example.com"),
+            "ada_lovelace"
+        );
+        assert_eq!(username_base(None, "grace@example.com"), "grace");
+        assert_eq!(username_base(None, "al@example.com"), "user_al");
+        assert_eq!(
+            username_base(Some("a".repeat(80).as_str()), "x@example.com").len(),
+            43
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn first_sign_in_creates_a_user_and_later_ones_reuse_it() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let created = link_or_create(
+            pool.as_ref(),
+            "example",
+            profile("sub-1", "ada@example.com", true),
+        )
+        .await?;
+        assert_eq!(created.username, "ada");
+        assert!(created.email_verified);
+
+        let again = link_or_create(
+            pool.as_ref(),
+            "example",
+            profile("sub-1", "changed@example.com", true),
+        )
+        .await?;
+        assert_eq!(again.id, created.id);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn existing_accounts_link_only_with_verified_emails() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+
+        let error = link_or_create(
+            pool.as_ref(),
+            "example",
+            profile("sub-2", &user.email, false),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error.code, ErrorCode::Unauthorized));
+
+        let linked = link_or_create(
+            pool.as_ref(),
+            "example",
+            profile("sub-2", &user.email, true),
+        )
+        .await?;
+        assert_eq!(linked.id, user.id);
+        Ok(())
+    }
+}
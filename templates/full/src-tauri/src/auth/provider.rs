@@ -0,0 +1,328 @@
+//! Pluggable identity backends for password sign-in.
+//!
+//! `authenticate_user` throttles attempts, audits them, and starts the
+//! session, and leaves checking the credentials to the active
+//! [`AuthProvider`], which `AUTH_PROVIDER` selects: [`DatabaseProvider`]
+//! verifies the stored password hashes, `LdapProvider` binds to a directory
+//! as the user (with the `ldap` feature), and [`OidcProvider`] uses the
+//! OAuth password grant. External identities are linked to a local user the
+//! way browser OAuth sign-in links them, creating one on first sign-in.
+//!
+//! Apps with another identity backend implement the trait and install it
+//! with [`set_provider`].
+
+use crate::config::{self, AppConfig, AuthProviderKind, OAuthSettings};
+use crate::errors::{AppError, ErrorCode};
+use crate::handlers::users::authenticate_user_with;
+use crate::models::{LoginRequest, PublicUser};
+use crate::repository::PgUserRepository;
+use crate::validation::validate_email;
+use once_cell::sync::Lazy;
+use sqlx::PgPool;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, PoisonError, RwLock};
+
+#[cfg(feature = "ldap")]
+pub use ldap::LdapProvider;
+
+/// Future returned by [`AuthProvider::authenticate`].
+pub type AuthFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Option<PublicUser>, String>> + Send + 'a>>;
+
+/// Checks the credentials of a password sign-in.
+pub trait AuthProvider: Send + Sync {
+    /// Short name used in logs and audit entries, e.g. `ldap`.
+    fn name(&self) -> &str;
+
+    /// Returns the active local user the credentials belong to, or `None`
+    /// when the backend rejects them.
+    fn authenticate<'a>(
+        &'a self,
+        pool: &'a PgPool,
+        email: &'a str,
+        password: &'a str,
+    ) -> AuthFuture<'a>;
+}
+
+/// Provider installed with [`set_provider`], used instead of the configured one.
+static INSTALLED: Lazy<RwLock<Option<Arc<dyn AuthProvider>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Checks future sign-ins with `provider` instead of the configured one.
+pub fn set_provider(provider: Arc<dyn AuthProvider>) {
+    tracing::info!("Using the '{}' authentication provider", provider.name());
+    *INSTALLED.write().unwrap_or_else(PoisonError::into_inner) = Some(provider);
+}
+
+/// The installed provider, or the one the configuration selects.
+pub fn current() -> Result<Arc<dyn AuthProvider>, String> {
+    let installed = INSTALLED
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    match installed {
+        Some(provider) => Ok(provider),
+        None => configured(&config::current()).map_err(|e| e.message),
+    }
+}
+
+/// Builds the provider `config.auth_provider` selects, failing when its
+/// settings are missing.
+fn configured(config: &AppConfig) -> Result<Arc<dyn AuthProvider>, AppError> {
+    match config.auth_provider {
+        AuthProviderKind::Database => Ok(Arc::new(DatabaseProvider)),
+        #[cfg(feature = "ldap")]
+        AuthProviderKind::Ldap => {
+            let settings = config.ldap.clone().ok_or_else(|| {
+                AppError::new(
+                    ErrorCode::ConfigurationError,
+                    "LDAP sign-in is not configured; set LDAP_URL and LDAP_BIND_DN",
+                )
+            })?;
+            Ok(Arc::new(LdapProvider::new(settings)))
+        }
+        #[cfg(not(feature = "ldap"))]
+        AuthProviderKind::Ldap => Err(AppError::new(
+            ErrorCode::ConfigurationError,
+            "LDAP sign-in requires the ldap feature",
+        )),
+        AuthProviderKind::Oidc => {
+            let settings = config.oauth.clone().ok_or_else(|| {
+                AppError::new(
+                    ErrorCode::ConfigurationError,
+                    "OIDC sign-in is not configured; set OAUTH_CLIENT_ID",
+                )
+            })?;
+            Ok(Arc::new(OidcProvider::new(settings)))
+        }
+    }
+}
+
+/// Verifies the password hashes of local users.
+pub struct DatabaseProvider;
+
+impl AuthProvider for DatabaseProvider {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    fn authenticate<'a>(
+        &'a self,
+        pool: &'a PgPool,
+        email: &'a str,
+        password: &'a str,
+    ) -> AuthFuture<'a> {
+        Box::pin(async move {
+            let credentials = LoginRequest {
+                email: email.to_string(),
+                password: password.to_string(),
+                device_id: None,
+                remember_me: false,
+            };
+            authenticate_user_with(&PgUserRepository::new(pool), credentials).await
+        })
+    }
+}
+
+/// Exchanges the credentials for a token with the OAuth password grant and
+/// signs in the user linked to the returned profile.
+///
+/// Only for providers that still allow the grant; most recommend browser
+/// sign-in instead.
+pub struct OidcProvider {
+    settings: OAuthSettings,
+}
+
+impl OidcProvider {
+    pub fn new(settings: OAuthSettings) -> Self {
+        Self { settings }
+    }
+}
+
+impl AuthProvider for OidcProvider {
+    fn name(&self) -> &str {
+        "oidc"
+    }
+
+    fn authenticate<'a>(
+        &'a self,
+        pool: &'a PgPool,
+        email: &'a str,
+        password: &'a str,
+    ) -> AuthFuture<'a> {
+        Box::pin(async move {
+            let email = validate_email(email).map_err(|e| format!("Invalid email: {}", e))?;
+            let user = super::oauth::sign_in_with_password(pool, &self.settings, &email, password)
+                .await
+                .map_err(|e| e.message)?;
+            Ok(user.map(PublicUser::from))
+        })
+    }
+}
+
+#[cfg(feature = "ldap")]
+mod ldap {
+    use super::{AuthFuture, AuthProvider};
+    use crate::auth::oauth::{link_or_create, Profile};
+    use crate::config::LdapSettings;
+    use crate::errors::{AppError, AppResult, ErrorCode};
+    use crate::models::PublicUser;
+    use crate::validation::validate_email;
+    use ldap3::{LdapConnAsync, Scope, SearchEntry};
+    use sqlx::PgPool;
+
+    /// Result code of a bind with a wrong DN or password.
+    const INVALID_CREDENTIALS: u32 = 49;
+
+    /// Binds to an LDAP directory as the user and reads their entry.
+    pub struct LdapProvider {
+        settings: LdapSettings,
+    }
+
+    impl LdapProvider {
+        pub fn new(settings: LdapSettings) -> Self {
+            Self { settings }
+        }
+
+        /// Returns the user's directory entry, or `None` when the directory
+        /// rejects the credentials.
+        async fn bind(&self, email: &str, password: &str) -> AppResult<Option<Profile>> {
+            // A bind without a password is an unauthenticated bind, which
+            // many directories accept.
+            if password.is_empty() {
+                return Ok(None);
+            }
+
+            let dn = bind_dn(&self.settings.bind_dn, email);
+            let (connection, mut ldap) = LdapConnAsync::new(&self.settings.url)
+                .await
+                .map_err(directory_error)?;
+            ldap3::drive!(connection);
+
+            let bound = ldap
+                .simple_bind(&dn, password)
+                .await
+                .map_err(directory_error)?;
+            if bound.rc == INVALID_CREDENTIALS {
+                return Ok(None);
+            }
+            bound.success().map_err(directory_error)?;
+
+            let attributes = vec![
+                self.settings.email_attribute.as_str(),
+                "uid",
+                "givenName",
+                "sn",
+            ];
+            let (entries, _) = ldap
+                .search(&dn, Scope::Base, "(objectClass=*)", attributes)
+                .await
+                .and_then(|result| result.success())
+                .map_err(directory_error)?;
+            let _ = ldap.unbind().await;
+
+            let entry = entries
+                .into_iter()
+                .next()
+                .map(SearchEntry::construct)
+                .ok_or_else(|| AppError::not_found("Directory entry"))?;
+            let first = |name: &str| {
+                entry
+                    .attrs
+                    .get(name)
+                    .and_then(|values| values.first())
+                    .cloned()
+            };
+            Ok(Some(Profile {
+                sub: entry.dn.clone(),
+                email: first(&self.settings.email_attribute).or_else(|| Some(email.to_string())),
+                // Directory addresses are managed by its administrators.
+                email_verified: true,
+                preferred_username: first("uid"),
+                given_name: first("givenName"),
+                family_name: first("sn"),
+            }))
+        }
+    }
+
+    impl AuthProvider for LdapProvider {
+        fn name(&self) -> &str {
+            "ldap"
+        }
+
+        fn authenticate<'a>(
+            &'a self,
+            pool: &'a PgPool,
+            email: &'a str,
+            password: &'a str,
+        ) -> AuthFuture<'a> {
+            Box::pin(async move {
+                let email = validate_email(email).map_err(|e| format!("Invalid email: {}", e))?;
+                let Some(profile) = self.bind(&email, password).await.map_err(|e| e.message)?
+                else {
+                    return Ok(None);
+                };
+                let user = link_or_create(pool, "ldap", profile)
+                    .await
+                    .map_err(|e| e.message)?;
+                Ok(Some(PublicUser::from(user)))
+            })
+        }
+    }
+
+    /// Fills the `{username}` and `{email}` placeholders of `template` with
+    /// escaped values.
+    fn bind_dn(template: &str, email: &str) -> String {
+        let username = email.split('@').next().unwrap_or_default();
+        template
+            .replace("{username}", &ldap3::dn_escape(username))
+            .replace("{email}", &ldap3::dn_escape(email))
+    }
+
+    fn directory_error(e: ldap3::LdapError) -> AppError {
+        AppError::new(
+            ErrorCode::ExternalServiceUnavailable,
+            format!("LDAP request failed: {}", e),
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn escapes_the_bind_dn_placeholders() {
+            assert_eq!(
+                bind_dn(
+                    "uid={username},ou=people,dc=example,dc=com",
+                    "jane@example.com"
+                ),
+                "uid=jane,ou=people,dc=example,dc=com"
+            );
+            let dn = bind_dn("mail={email},dc=example", "a,b@example.com");
+            assert!(dn.ends_with(",dc=example"));
+            assert!(!dn.contains("a,b"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_the_configured_provider() {
+        let mut config = AppConfig::from_env();
+        config.auth_provider = AuthProviderKind::Database;
+        assert_eq!(configured(&config).unwrap().name(), "database");
+
+        config.auth_provider = AuthProviderKind::Oidc;
+        config.oauth = None;
+        let error = configured(&config).err().unwrap();
+        assert!(matches!(error.code, ErrorCode::ConfigurationError));
+
+        config.auth_provider = AuthProviderKind::Ldap;
+        config.ldap = None;
+        assert!(configured(&config).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Brute-force protection for password sign-in.
+//!
+//! Failed sign-ins are counted per email and per device id, apart from the
+//! command rate limiter, whose quotas are shared by every command. Once a
+//! key reaches [`LoginThrottle::free_attempts`] failures it is locked for the
+//! base delay, doubled with each further failure up to the maximum, and a
+//! successful sign-in clears both counters.
+//!
+//! Counters are kept in Redis when it is available, so lockouts survive a
+//! restart, and in memory otherwise. Keys hold hashes of the email and
+//! device id rather than the values themselves.
+
+use crate::cache;
+use crate::config::{self, LoginThrottle};
+use crate::session::hash_token;
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+const KEY_PREFIX: &str = "login_throttle:";
+
+/// Counters used while Redis is unavailable.
+static MEMORY: Lazy<Mutex<HashMap<String, Failures>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Recent failed sign-ins for one email or device.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Failures {
+    count: u32,
+    last_failure: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl Failures {
+    /// Adds a failure at `now`, starting over when the last one is older
+    /// than the reset window.
+    fn record(previous: Option<Failures>, policy: &LoginThrottle, now: DateTime<Utc>) -> Self {
+        let count = previous
+            .filter(|failures| !failures.is_stale(policy, now))
+            .map_or(0, |failures| failures.count)
+            .saturating_add(1);
+        Self {
+            count,
+            last_failure: now,
+            locked_until: lockout(policy, count).map(|delay| now + delay),
+        }
+    }
+
+    fn is_stale(&self, policy: &LoginThrottle, now: DateTime<Utc>) -> bool {
+        now - self.last_failure > Duration::minutes(policy.reset_after_minutes as i64)
+    }
+
+    /// Seconds until sign-in is allowed again, while locked.
+    fn retry_after(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.locked_until
+            .filter(|until| *until > now)
+            .map(|until| (until - now).num_seconds().max(1))
+    }
+}
+
+/// How long a key is locked after its `count`th failure.
+fn lockout(policy: &LoginThrottle, count: u32) -> Option<Duration> {
+    let doublings = count.checked_sub(policy.free_attempts.max(1))?;
+    let seconds = policy
+        .base_delay_seconds
+        .checked_mul(1u64.checked_shl(doublings).unwrap_or(u64::MAX))
+        .unwrap_or(u64::MAX)
+        .min(policy.max_delay_seconds);
+    Some(Duration::seconds(seconds as i64))
+}
+
+/// Fails while the email or device is locked out, saying when to retry.
+pub fn check(email: &str, device_id: Option<&str>) -> Result<(), String> {
+    let now = Utc::now();
+    let retry_after = keys(email, device_id)
+        .iter()
+        .filter_map(|key| load(key)?.retry_after(now))
+        .max();
+    match retry_after {
+        Some(seconds) => Err(format!(
+            "Too many failed sign-in attempts. Try again in {} seconds.",
+            seconds
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Counts a failed sign-in against the email and device.
+pub fn record_failure(email: &str, device_id: Option<&str>) {
+    let config = config::current();
+    let policy = &config.login_throttle;
+    let now = Utc::now();
+    for key in keys(email, device_id) {
+        let failures = Failures::record(load(&key), policy, now);
+        if failures.locked_until.is_some() {
+            tracing::warn!(
+                "Locking sign-in after {} failed attempts ({})",
+                failures.count,
+                key
+            );
+        }
+        store(&key, failures, policy);
+    }
+}
+
+/// Clears the counters of the email and device after a successful sign-in.
+pub fn record_success(email: &str, device_id: Option<&str>) {
+    for key in keys(email, device_id) {
+        clear(&key);
+    }
+}
+
+fn keys(email: &str, device_id: Option<&str>) -> Vec<String> {
+    let mut keys = vec![format!(
+        "{}email:{}",
+        KEY_PREFIX,
+        hash_token(&email.trim().to_lowercase())
+    )];
+    if let Some(device_id) = device_id.map(str::trim).filter(|id| !id.is_empty()) {
+        keys.push(format!("{}device:{}", KEY_PREFIX, hash_token(device_id)));
+    }
+    keys
+}
+
+fn load(key: &str) -> Option<Failures> {
+    if cache::is_redis_available() {
+        return cache::get_cache(key).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read sign-in throttle state: {}", e);
+            None
+        });
+    }
+    MEMORY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(key)
+        .cloned()
+}
+
+fn store(key: &str, failures: Failures, policy: &LoginThrottle) {
+    if cache::is_redis_available() {
+        let ttl_seconds = policy.reset_after_minutes * 60;
+        if let Err(e) = cache::set_cache(key, &failures, Some(ttl_seconds)) {
+            tracing::warn!("Failed to save sign-in throttle state: {}", e);
+        }
+        return;
+    }
+    let mut memory = MEMORY.lock().unwrap_or_else(PoisonError::into_inner);
+    memory.retain(|_, entry| !entry.is_stale(policy, failures.last_failure));
+    memory.insert(key.to_string(), failures);
+}
+
+fn clear(key: &str) {
+    if cache::is_redis_available() {
+        if let Err(e) = cache::delete_cache(key) {
+            tracing::warn!("Failed to clear sign-in throttle state: {}", e);
+        }
+        return;
+    }
+    MEMORY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(key);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> LoginThrottle {
+        LoginThrottle {
+            free_attempts: 3,
+            base_delay_seconds: 2,
+            max_delay_seconds: 30,
+            reset_after_minutes: 10,
+        }
+    }
+
+    #[test]
+    fn lockouts_double_up_to_the_maximum() {
+        let policy = policy();
+        let expected = [
+            None,
+            None,
+            Some(2),
+            Some(4),
+            Some(8),
+            Some(16),
+            Some(30),
+            Some(30),
+        ];
+        for (count, seconds) in (1..).zip(expected) {
+            assert_eq!(lockout(&policy, count), seconds.map(Duration::seconds));
+        }
+        assert_eq!(lockout(&policy, u32::MAX), Some(Duration::seconds(30)));
+    }
+
+    #[test]
+    fn failures_start_over_after_the_reset_window() {
+        let policy = policy();
+        let now = Utc::now();
+        let mut failures = None;
+        for _ in 0..3 {
+            failures = Some(Failures::record(failures, &policy, now));
+        }
+        let locked = failures.unwrap();
+        assert_eq!(locked.count, 3);
+        assert_eq!(locked.retry_after(now), Some(2));
+        assert_eq!(locked.retry_after(now + Duration::seconds(3)), None);
+
+        let later = now + Duration::minutes(11);
+        let fresh = Failures::record(Some(locked), &policy, later);
+        assert_eq!(fresh.count, 1);
+        assert_eq!(fresh.locked_until, None);
+    }
+
+    #[test]
+    fn success_clears_email_and_device_counters() {
+        let email = format!("{}@example.com", uuid::Uuid::new_v4());
+        let device = uuid::Uuid::new_v4().to_string();
+        let attempts = config::current().login_throttle.free_attempts;
+        for _ in 0..attempts {
+            assert!(check(&email, Some(&device)).is_ok());
+            record_failure(&email, Some(&device));
+        }
+
+        assert!(check(&email.to_uppercase(), None).is_err());
+        assert!(check("other@example.com", Some(&device)).is_err());
+        record_success(&email, Some(&device));
+        assert!(check(&email, Some(&device)).is_ok());
+    }
+}
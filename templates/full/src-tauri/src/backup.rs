@@ -0,0 +1,481 @@
+//! Scheduled backups of the database, local state, config, and vault.
+//!
+//! A backup is a versioned zip archive under the application data directory
+//! holding a JSON dump of every application table, the local state store, a
+//! secret-free config snapshot, and a copy of the Stronghold snapshot (which
+//! is already encrypted with the vault password). Old archives are pruned to
+//! the configured retention, and restore replaces the tables in a single
+//! transaction after taking a safety backup.
+
+use crate::config;
+use crate::database::get_pool_ref;
+use crate::database::locks::{with_advisory_lock, BACKUP_LOCK};
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::logging;
+use crate::operations;
+use crate::state_store;
+use crate::storage_devices;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+
+/// Archive layout version written to the manifest.
+const BACKUP_VERSION: u32 = 1;
+
+/// Tables included in a backup, parents before children so restore can
+/// insert in this order and delete in reverse.
+const BACKUP_TABLES: [&str; 10] = [
+    "workspaces",
+    "users",
+    "workspace_members",
+    "groups",
+    "group_memberships",
+    "user_settings",
+    "app_logs",
+    "notifications",
+    "sync_changes",
+    "sync_state",
+];
+
+/// Stronghold snapshot file name inside the app data directory.
+const VAULT_FILE: &str = "vault.hold";
+
+/// Archive entry holding the Stronghold snapshot.
+const VAULT_ENTRY: &str = "vault/vault.hold";
+
+/// Folder on a removable device that exported backups are copied into.
+const DEVICE_EXPORT_DIR: &str = "ez-tauri-backups";
+
+/// Manifest describing a backup archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub app_version: String,
+    /// Row count per table.
+    pub tables: BTreeMap<String, usize>,
+    pub state_entries: usize,
+    pub includes_vault: bool,
+}
+
+/// A backup archive on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub name: String,
+    pub size_bytes: u64,
+    pub manifest: BackupManifest,
+}
+
+/// Result of restoring a backup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSummary {
+    pub name: String,
+    pub tables: BTreeMap<String, usize>,
+    pub state_entries: usize,
+    pub vault_restored: bool,
+    /// Backup taken immediately before restoring.
+    pub safety_backup: Option<String>,
+}
+
+/// Directory holding backup archives.
+pub fn backup_dir() -> PathBuf {
+    logging::app_data_dir("backups")
+}
+
+/// Location of the Stronghold snapshot created by the frontend.
+pub fn vault_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join(VAULT_FILE))
+}
+
+/// Resolves a backup name to its path, rejecting anything outside the backup directory.
+fn backup_path(name: &str) -> AppResult<PathBuf> {
+    let valid = name.starts_with("backup-")
+        && name.ends_with(".zip")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    if !valid {
+        return Err(AppError::invalid_input("name", "Invalid backup name"));
+    }
+    Ok(backup_dir().join(name))
+}
+
+/// Writes a new backup archive and prunes old ones beyond `retention`.
+///
+/// Fails while another instance sharing the database holds [`BACKUP_LOCK`].
+pub async fn create_backup(
+    app_version: &str,
+    vault_path: Option<&Path>,
+    retention: usize,
+) -> AppResult<BackupInfo> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    with_advisory_lock(pool.as_ref(), BACKUP_LOCK, None, || {
+        write_backup(app_version, vault_path, retention)
+    })
+    .await
+}
+
+async fn write_backup(
+    app_version: &str,
+    vault_path: Option<&Path>,
+    retention: usize,
+) -> AppResult<BackupInfo> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+
+    let total = BACKUP_TABLES.len() as u64;
+    let mut dumps = Vec::with_capacity(BACKUP_TABLES.len());
+    for table in BACKUP_TABLES {
+        operations::check_cancelled()?;
+        operations::report(
+            dumps.len() as u64,
+            Some(total),
+            Some(&format!("Backing up {}", table)),
+        );
+        let rows: serde_json::Value = sqlx::query_scalar(&format!(
+            "SELECT COALESCE(json_agg(t), '[]'::json) FROM {} t",
+            table
+        ))
+        .fetch_one(pool.as_ref())
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+        dumps.push((table, rows));
+    }
+    operations::check_cancelled()?;
+
+    let state = state_store::entries("")?;
+    let vault = match vault_path {
+        Some(path) if path.is_file() => Some(fs::read(path).into_app_error(ErrorCode::FileRead)?),
+        _ => None,
+    };
+
+    let manifest = BackupManifest {
+        version: BACKUP_VERSION,
+        created_at: Utc::now(),
+        app_version: app_version.to_string(),
+        tables: dumps
+            .iter()
+            .map(|(table, rows)| {
+                let count = rows.as_array().map(Vec::len).unwrap_or(0);
+                (table.to_string(), count)
+            })
+            .collect(),
+        state_entries: state.len(),
+        includes_vault: vault.is_some(),
+    };
+
+    let dir = backup_dir();
+    fs::create_dir_all(&dir).into_app_error(ErrorCode::DirectoryCreate)?;
+    let name = format!(
+        "backup-{}.zip",
+        manifest.created_at.format("%Y%m%d%H%M%S%3f")
+    );
+    let path = dir.join(&name);
+
+    let file = File::create(&path).into_app_error(ErrorCode::FileWrite)?;
+    let mut archive = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let mut add = |name: &str, bytes: &[u8]| -> AppResult<()> {
+        archive
+            .start_file(name, options)
+            .into_app_error(ErrorCode::FileWrite)?;
+        archive
+            .write_all(bytes)
+            .into_app_error(ErrorCode::FileWrite)
+    };
+
+    add("manifest.json", &to_json_bytes(&manifest)?)?;
+    for (table, rows) in &dumps {
+        add(&format!("database/{}.json", table), &to_json_bytes(rows)?)?;
+    }
+    add("state.json", &to_json_bytes(&state)?)?;
+    add("config.json", &to_json_bytes(&config_snapshot())?)?;
+    if let Some(vault) = &vault {
+        add(VAULT_ENTRY, vault)?;
+    }
+    archive.finish().into_app_error(ErrorCode::FileWrite)?;
+
+    let pruned = prune(retention)?;
+    tracing::info!("Created backup {} ({} older backups pruned)", name, pruned);
+
+    Ok(BackupInfo {
+        size_bytes: fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0),
+        name,
+        manifest,
+    })
+}
+
+/// Lists backups, newest first.
+pub fn list_backups() -> AppResult<Vec<BackupInfo>> {
+    let mut backups: Vec<BackupInfo> = archive_names()?
+        .into_iter()
+        .filter_map(|name| {
+            let path = backup_dir().join(&name);
+            let manifest = File::open(&path)
+                .ok()
+                .and_then(|file| zip::ZipArchive::new(file).ok())
+                .and_then(|mut archive| read_entry(&mut archive, "manifest.json").ok())
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())?;
+
+            Some(BackupInfo {
+                size_bytes: fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0),
+                name,
+                manifest,
+            })
+        })
+        .collect();
+
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Copies the backup `name` into a folder on the removable device mounted at
+/// `mount_point`, returning the written path.
+pub fn export_to_device(name: &str, mount_point: &str) -> AppResult<String> {
+    let source = backup_path(name)?;
+    if !source.is_file() {
+        return Err(AppError::not_found("Backup"));
+    }
+    let dir = storage_devices::removable_mount(mount_point)?.join(DEVICE_EXPORT_DIR);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::file_error("create", dir.display().to_string(), e.to_string()))?;
+
+    let destination = dir.join(name);
+    let display = destination.display().to_string();
+    fs::copy(&source, &destination)
+        .map_err(|e| AppError::file_error("write", display.clone(), e.to_string()))?;
+    tracing::info!("Exported backup {} to {}", name, display);
+    Ok(display)
+}
+
+/// Restores the backup `name`, optionally replacing the vault snapshot.
+///
+/// Holds [`BACKUP_LOCK`] for the whole restore, including the safety backup.
+pub async fn restore_backup(
+    name: &str,
+    app_version: &str,
+    vault_path: Option<&Path>,
+    retention: usize,
+) -> AppResult<RestoreSummary> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    with_advisory_lock(pool.as_ref(), BACKUP_LOCK, None, || {
+        restore_archive(name, app_version, vault_path, retention)
+    })
+    .await
+}
+
+async fn restore_archive(
+    name: &str,
+    app_version: &str,
+    vault_path: Option<&Path>,
+    retention: usize,
+) -> AppResult<RestoreSummary> {
+    let path = backup_path(name)?;
+    if !path.is_file() {
+        return Err(AppError::not_found("Backup"));
+    }
+
+    let file = File::open(&path).into_app_error(ErrorCode::FileRead)?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| {
+        AppError::new(
+            ErrorCode::InvalidFormat,
+            format!("Not a valid backup archive: {}", e),
+        )
+    })?;
+
+    let manifest: BackupManifest =
+        serde_json::from_slice(&read_entry(&mut archive, "manifest.json")?).map_err(|e| {
+            AppError::new(ErrorCode::InvalidFormat, format!("Invalid manifest: {}", e))
+        })?;
+    if manifest.version > BACKUP_VERSION {
+        return Err(AppError::new(
+            ErrorCode::InvalidFormat,
+            format!(
+                "Backup version {} is newer than supported version {}",
+                manifest.version, BACKUP_VERSION
+            ),
+        ));
+    }
+
+    let mut dumps = Vec::new();
+    for table in BACKUP_TABLES {
+        let entry = format!("database/{}.json", table);
+        // Tables added after the backup was taken are restored empty.
+        let rows: serde_json::Value = match read_entry(&mut archive, &entry) {
+            Ok(bytes) => serde_json::from_slice(&bytes).into_app_error(ErrorCode::InvalidFormat)?,
+            Err(_) => serde_json::Value::Array(Vec::new()),
+        };
+        dumps.push((table, rows));
+    }
+    let state: BTreeMap<String, serde_json::Value> =
+        serde_json::from_slice(&read_entry(&mut archive, "state.json")?)
+            .into_app_error(ErrorCode::InvalidFormat)?;
+    let vault = if manifest.includes_vault && vault_path.is_some() {
+        Some(read_entry(&mut archive, VAULT_ENTRY)?)
+    } else {
+        None
+    };
+
+    let safety_backup = match write_backup(app_version, vault_path, retention + 1).await {
+        Ok(info) => Some(info.name),
+        Err(e) => {
+            tracing::warn!("Failed to create safety backup before restore: {}", e);
+            None
+        }
+    };
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    for table in BACKUP_TABLES.iter().rev() {
+        sqlx::query(&format!("DELETE FROM {}", table))
+            .execute(&mut *tx)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+    }
+
+    // Cancelling before the commit rolls the restore back.
+    let total = dumps.len() as u64;
+    let mut tables = BTreeMap::new();
+    for (table, rows) in &dumps {
+        operations::check_cancelled()?;
+        operations::report(
+            tables.len() as u64,
+            Some(total),
+            Some(&format!("Restoring {}", table)),
+        );
+        let result = sqlx::query(&format!(
+            "INSERT INTO {0} SELECT * FROM json_populate_recordset(NULL::{0}, $1::json)",
+            table
+        ))
+        .bind(rows)
+        .execute(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+        tables.insert(table.to_string(), result.rows_affected() as usize);
+    }
+
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+
+    for (key, value) in &state {
+        state_store::set(key, value.clone())?;
+    }
+
+    let vault_restored = match (vault, vault_path) {
+        (Some(bytes), Some(path)) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).into_app_error(ErrorCode::DirectoryCreate)?;
+            }
+            fs::write(path, bytes).into_app_error(ErrorCode::FileWrite)?;
+            true
+        }
+        _ => false,
+    };
+
+    tracing::info!("Restored backup {}", name);
+    Ok(RestoreSummary {
+        name: name.to_string(),
+        tables,
+        state_entries: state.len(),
+        vault_restored,
+        safety_backup,
+    })
+}
+
+/// Deletes the oldest archives beyond `retention`; returns how many were removed.
+fn prune(retention: usize) -> AppResult<usize> {
+    let names = archive_names()?;
+    let excess = names.len().saturating_sub(retention.max(1));
+
+    for name in &names[..excess] {
+        if let Err(e) = fs::remove_file(backup_dir().join(name)) {
+            tracing::warn!("Failed to remove old backup {}: {}", name, e);
+        }
+    }
+    Ok(excess)
+}
+
+/// Backup archive names, oldest first (names sort by timestamp).
+fn archive_names() -> AppResult<Vec<String>> {
+    let dir = backup_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .into_app_error(ErrorCode::FileRead)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| backup_path(name).is_ok())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Non-secret configuration recorded for diagnostics; never restored.
+fn config_snapshot() -> serde_json::Value {
+    let config = config::current();
+    serde_json::json!({
+        "environment": format!("{:?}", config.environment).to_lowercase(),
+        "updateChannel": format!("{:?}", config.update_channel).to_lowercase(),
+        "localServerPort": config.local_server_port,
+        "syncConfigured": config.sync_endpoint.is_some(),
+        "syncIntervalSeconds": config.sync_interval_seconds,
+        "backupIntervalHours": config.backup_interval_hours,
+        "backupRetention": config.backup_retention,
+    })
+}
+
+fn to_json_bytes<T: Serialize + ?Sized>(value: &T) -> AppResult<Vec<u8>> {
+    serde_json::to_vec_pretty(value).into_app_error(ErrorCode::InternalError)
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> AppResult<Vec<u8>> {
+    let mut entry = archive.by_name(name).map_err(|_| {
+        AppError::new(
+            ErrorCode::InvalidFormat,
+            format!("Backup is missing {}", name),
+        )
+    })?;
+    let mut bytes = Vec::new();
+    entry
+        .read_to_end(&mut bytes)
+        .into_app_error(ErrorCode::FileRead)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backup_names_are_confined_to_the_backup_dir() {
+        assert!(backup_path("backup-20260101120000000.zip").is_ok());
+        assert!(backup_path("../backup-1.zip").is_err());
+        assert!(backup_path("backup-1/../../etc.zip").is_err());
+        assert!(backup_path("export.zip").is_err());
+    }
+
+    #[test]
+    fn restore_order_keeps_parents_first() {
+        let position = |table: &str| BACKUP_TABLES.iter().position(|t| *t == table).unwrap();
+        assert!(position("workspaces") < position("workspace_members"));
+        assert!(position("groups") < position("group_memberships"));
+        assert!(position("users") < position("user_settings"));
+        assert!(position("users") < position("app_logs"));
+    }
+}
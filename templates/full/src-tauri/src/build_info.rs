@@ -0,0 +1,86 @@
+//! Version and build details fixed at compile time.
+//!
+//! `build.rs` provides the git commit, build time, profile, and target
+//! triple; the version comes from `Cargo.toml` and the feature list from the
+//! enabled cargo features. Feedback reports include them, and the frontend
+//! reads them with `get_app_version_info` for the about screen and update
+//! prompts.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Cargo features this build may have enabled, in `Cargo.toml` order.
+const FEATURES: &[(&str, bool)] = &[
+    ("database", cfg!(feature = "database")),
+    ("sqlite", cfg!(feature = "sqlite")),
+    ("cache", cfg!(feature = "cache")),
+    ("stronghold", cfg!(feature = "stronghold")),
+    ("rate-limiter", cfg!(feature = "rate-limiter")),
+    ("scripting", cfg!(feature = "scripting")),
+    ("ldap", cfg!(feature = "ldap")),
+    ("demo", cfg!(feature = "demo")),
+];
+
+/// How and from what this binary was built.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppVersionInfo {
+    /// Semantic version from `Cargo.toml`.
+    pub version: String,
+    /// Abbreviated commit hash; `None` when built outside a git checkout.
+    pub git_commit: Option<String>,
+    pub build_date: Option<DateTime<Utc>>,
+    /// Cargo profile, `debug` or `release`.
+    pub profile: String,
+    /// Target triple, e.g. `x86_64-pc-windows-msvc`.
+    pub target: String,
+    pub features: Vec<String>,
+}
+
+/// Returns the details of the running binary.
+pub fn current() -> AppVersionInfo {
+    AppVersionInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: non_empty(env!("EZ_GIT_COMMIT")),
+        build_date: env!("EZ_BUILD_TIMESTAMP")
+            .parse::<i64>()
+            .ok()
+            .filter(|&seconds| seconds > 0)
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0)),
+        profile: env!("EZ_BUILD_PROFILE").to_string(),
+        target: env!("EZ_BUILD_TARGET").to_string(),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name.to_string())
+            .collect(),
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    Some(value.to_string()).filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_package_version_and_enabled_features() {
+        let info = current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            info.profile,
+            if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+        );
+        assert_eq!(
+            info.features.contains(&"database".to_string()),
+            cfg!(feature = "database")
+        );
+        assert!(info.build_date.is_some());
+    }
+}
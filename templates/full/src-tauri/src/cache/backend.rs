@@ -0,0 +1,112 @@
+//! Redis connection used by the cache when the `cache` feature is enabled.
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use redis::{Client, Connection};
+use std::sync::Mutex;
+
+/// Global Redis client instance.
+static REDIS_CLIENT: OnceCell<Option<Client>> = OnceCell::new();
+
+/// Global Redis connection wrapped in a mutex for thread safety.
+static REDIS_CONNECTION: OnceCell<Mutex<Option<Connection>>> = OnceCell::new();
+
+/// Connects to `redis_url`, or records that caching is off when unset.
+pub fn connect(redis_url: Option<&str>) -> Result<()> {
+    if let Some(redis_url) = redis_url {
+        let client = Client::open(redis_url)?;
+        let connection = client.get_connection()?;
+
+        REDIS_CLIENT.set(Some(client)).map_err(|_| anyhow::anyhow!("Failed to set Redis client"))?;
+        REDIS_CONNECTION.set(Mutex::new(Some(connection))).map_err(|_| anyhow::anyhow!("Failed to set Redis connection"))?;
+
+        tracing::info!("Redis initialized successfully");
+    } else {
+        REDIS_CLIENT.set(None).map_err(|_| anyhow::anyhow!("Failed to set Redis client"))?;
+        REDIS_CONNECTION.set(Mutex::new(None)).map_err(|_| anyhow::anyhow!("Failed to set Redis connection"))?;
+
+        tracing::info!("Redis not configured - running without caching");
+    }
+
+    Ok(())
+}
+
+/// Checks if a Redis client was created.
+pub fn is_available() -> bool {
+    REDIS_CLIENT.get().map_or(false, |client| client.is_some())
+}
+
+/// Runs `f` with the connection; returns `None` when there is none.
+fn with_connection<T>(f: impl FnOnce(&mut Connection) -> Result<T>) -> Result<Option<T>> {
+    let connection_guard = REDIS_CONNECTION.get()
+        .ok_or_else(|| anyhow::anyhow!("Redis not initialized"))?;
+
+    let mut connection = connection_guard.lock().unwrap();
+
+    match *connection {
+        Some(ref mut conn) => f(conn).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Stores `serialized` under `key`, expiring after `ttl_seconds` if given.
+/// Returns whether a connection was available.
+pub fn set(key: &str, serialized: String, ttl_seconds: Option<u64>) -> Result<bool> {
+    with_connection(|conn| {
+        if let Some(ttl) = ttl_seconds {
+            redis::cmd("SETEX")
+                .arg(key)
+                .arg(ttl)
+                .arg(serialized)
+                .execute(conn);
+        } else {
+            redis::cmd("SET")
+                .arg(key)
+                .arg(serialized)
+                .execute(conn);
+        }
+        Ok(())
+    })
+    .map(|written| written.is_some())
+}
+
+/// Reads the serialized value under `key`. The outer `None` means no
+/// connection was available.
+pub fn get(key: &str) -> Result<Option<Option<String>>> {
+    with_connection(|conn| Ok(redis::cmd("GET").arg(key).query(conn)?))
+}
+
+/// Deletes `key`.
+pub fn delete(key: &str) -> Result<()> {
+    with_connection(|conn| {
+        redis::cmd("DEL")
+            .arg(key)
+            .execute(conn);
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+/// Deletes every key starting with `prefix`, returning how many were removed.
+pub fn delete_by_prefix(prefix: &str) -> Result<usize> {
+    with_connection(|conn| {
+        let keys: Vec<String> = redis::Commands::scan_match::<_, String>(conn, format!("{}*", prefix))?.collect();
+        if keys.is_empty() {
+            return Ok(0);
+        }
+        let removed: usize = redis::cmd("DEL").arg(&keys).query(conn)?;
+        Ok(removed)
+    })
+    .map(Option::unwrap_or_default)
+}
+
+/// Checks if `key` exists.
+pub fn exists(key: &str) -> Result<bool> {
+    with_connection(|conn| {
+        let result: bool = redis::cmd("EXISTS")
+            .arg(key)
+            .query(conn)?;
+        Ok(result)
+    })
+    .map(Option::unwrap_or_default)
+}
@@ -1,46 +1,62 @@
 //! Redis caching functionality with graceful fallback when unavailable.
+//!
+//! Redis is compiled only with the `cache` cargo feature. Without it the
+//! cache is never available: reads miss and writes are skipped, exactly as
+//! when `REDIS_URL` is unset.
 
-use anyhow::Result;
-use once_cell::sync::OnceCell;
-use redis::{Client, Connection};
-use std::sync::Mutex;
-use crate::config::AppConfig;
+#[cfg(feature = "cache")]
+mod backend;
 
-/// Global Redis client instance.
-static REDIS_CLIENT: OnceCell<Option<Client>> = OnceCell::new();
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::config;
+
+/// Cache operation counters exposed through metrics.
+static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+static CACHE_WRITES: AtomicU64 = AtomicU64::new(0);
+static CACHE_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+/// Snapshot of cache availability and operation counters.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub available: bool,
+    pub hits: u64,
+    pub misses: u64,
+    pub writes: u64,
+    pub errors: u64,
+}
 
-/// Global Redis connection wrapped in a mutex for thread safety.
-static REDIS_CONNECTION: OnceCell<Mutex<Option<Connection>>> = OnceCell::new();
+/// Returns cache operation counters since startup.
+pub fn cache_stats() -> CacheStats {
+    CacheStats {
+        available: is_redis_available(),
+        hits: CACHE_HITS.load(Ordering::Relaxed),
+        misses: CACHE_MISSES.load(Ordering::Relaxed),
+        writes: CACHE_WRITES.load(Ordering::Relaxed),
+        errors: CACHE_ERRORS.load(Ordering::Relaxed),
+    }
+}
 
 /// Initializes Redis connection if configured, otherwise runs without caching.
 pub fn initialize_redis() -> Result<()> {
-    let config = AppConfig::from_env();
-
-    if let Some(redis_url) = &config.redis_url {
-        let client = Client::open(redis_url.as_str())?;
-        let connection = client.get_connection()?;
-
-        REDIS_CLIENT.set(Some(client)).map_err(|_| anyhow::anyhow!("Failed to set Redis client"))?;
-        REDIS_CONNECTION.set(Mutex::new(Some(connection))).map_err(|_| anyhow::anyhow!("Failed to set Redis connection"))?;
-
-        tracing::info!("Redis initialized successfully");
-    } else {
-        REDIS_CLIENT.set(None).map_err(|_| anyhow::anyhow!("Failed to set Redis client"))?;
-        REDIS_CONNECTION.set(Mutex::new(None)).map_err(|_| anyhow::anyhow!("Failed to set Redis connection"))?;
-
-        tracing::info!("Redis not configured - running without caching");
-    }
-
-    Ok(())
+    let config = config::current();
+    backend::connect(config.redis_url.as_deref())
 }
 
 /// Checks if Redis is available for caching operations.
 pub fn is_redis_available() -> bool {
-    REDIS_CLIENT.get().map_or(false, |client| client.is_some())
+    backend::is_available()
 }
 
 /// Sets a value in the cache with optional TTL (time-to-live).
 ///
+/// Without a TTL the configured default for the key's namespace applies.
+/// Values whose serialized form exceeds the configured maximum size are
+/// rejected so unbounded writes cannot exhaust Redis memory.
+///
 /// Silently succeeds if Redis is unavailable, allowing the application
 /// to continue functioning without caching.
 pub fn set_cache<T: serde::Serialize>(key: &str, value: &T, ttl_seconds: Option<u64>) -> Result<()> {
@@ -48,28 +64,30 @@ pub fn set_cache<T: serde::Serialize>(key: &str, value: &T, ttl_seconds: Option<
         return Ok(());
     }
 
-    let connection_guard = REDIS_CONNECTION.get()
-        .ok_or_else(|| anyhow::anyhow!("Redis not initialized"))?;
+    let config = config::current();
+    let policy = &config.cache_policy;
+    let serialized = serde_json::to_string(value)?;
+    check_value_size(key, serialized.len(), policy.max_value_bytes)?;
+    let ttl_seconds = ttl_seconds.or_else(|| policy.ttl_for(key));
 
-    let mut connection = connection_guard.lock().unwrap();
+    if backend::set(key, serialized, ttl_seconds)? {
+        CACHE_WRITES.fetch_add(1, Ordering::Relaxed);
+    }
 
-    if let Some(ref mut conn) = *connection {
-        let serialized = serde_json::to_string(value)?;
+    Ok(())
+}
 
-        if let Some(ttl) = ttl_seconds {
-            redis::cmd("SETEX")
-                .arg(key)
-                .arg(ttl)
-                .arg(serialized)
-                .execute(conn);
-        } else {
-            redis::cmd("SET")
-                .arg(key)
-                .arg(serialized)
-                .execute(conn);
-        }
+/// Fails when a serialized value for `key` is larger than `max_bytes`.
+fn check_value_size(key: &str, size: usize, max_bytes: usize) -> Result<()> {
+    if size > max_bytes {
+        CACHE_ERRORS.fetch_add(1, Ordering::Relaxed);
+        anyhow::bail!(
+            "Value for '{}' is {} bytes, exceeding the {} byte cache limit",
+            key,
+            size,
+            max_bytes
+        );
     }
-
     Ok(())
 }
 
@@ -79,20 +97,21 @@ pub fn get_cache<T: for<'de> serde::Deserialize<'de>>(key: &str) -> Result<Optio
         return Ok(None);
     }
 
-    let connection_guard = REDIS_CONNECTION.get()
-        .ok_or_else(|| anyhow::anyhow!("Redis not initialized"))?;
-
-    let mut connection = connection_guard.lock().unwrap();
-
-    if let Some(ref mut conn) = *connection {
-        let result: Option<String> = redis::cmd("GET")
-            .arg(key)
-            .query(conn)?;
+    let reply = match backend::get(key) {
+        Ok(reply) => reply,
+        Err(e) => {
+            CACHE_ERRORS.fetch_add(1, Ordering::Relaxed);
+            return Err(e);
+        }
+    };
 
+    if let Some(result) = reply {
         if let Some(serialized) = result {
+            CACHE_HITS.fetch_add(1, Ordering::Relaxed);
             let deserialized: T = serde_json::from_str(&serialized)?;
             return Ok(Some(deserialized));
         }
+        CACHE_MISSES.fetch_add(1, Ordering::Relaxed);
     }
 
     Ok(None)
@@ -104,18 +123,23 @@ pub fn delete_cache(key: &str) -> Result<()> {
         return Ok(());
     }
 
-    let connection_guard = REDIS_CONNECTION.get()
-        .ok_or_else(|| anyhow::anyhow!("Redis not initialized"))?;
+    backend::delete(key)
+}
 
-    let mut connection = connection_guard.lock().unwrap();
+/// Returns the key prefix for cache entries holding a user's data.
+///
+/// Entries stored under this prefix are removed when the user's data is erased.
+pub fn user_key_prefix(user_id: uuid::Uuid) -> String {
+    format!("user:{}:", user_id)
+}
 
-    if let Some(ref mut conn) = *connection {
-        redis::cmd("DEL")
-            .arg(key)
-            .execute(conn);
+/// Deletes every key starting with `prefix`, returning how many were removed.
+pub fn delete_by_prefix(prefix: &str) -> Result<usize> {
+    if !is_redis_available() {
+        return Ok(0);
     }
 
-    Ok(())
+    backend::delete_by_prefix(prefix)
 }
 
 /// Checks if a key exists in the cache.
@@ -124,17 +148,54 @@ pub fn cache_exists(key: &str) -> Result<bool> {
         return Ok(false);
     }
 
-    let connection_guard = REDIS_CONNECTION.get()
-        .ok_or_else(|| anyhow::anyhow!("Redis not initialized"))?;
+    backend::exists(key)
+}
+
+/// Stand-in for the Redis backend when the `cache` feature is disabled.
+#[cfg(not(feature = "cache"))]
+mod backend {
+    use anyhow::Result;
+
+    pub fn connect(redis_url: Option<&str>) -> Result<()> {
+        if redis_url.is_some() {
+            tracing::warn!("REDIS_URL is set but caching is not enabled in this build");
+        }
+        Ok(())
+    }
+
+    pub fn is_available() -> bool {
+        false
+    }
+
+    pub fn set(_key: &str, _serialized: String, _ttl_seconds: Option<u64>) -> Result<bool> {
+        Ok(false)
+    }
+
+    pub fn get(_key: &str) -> Result<Option<Option<String>>> {
+        Ok(None)
+    }
 
-    let mut connection = connection_guard.lock().unwrap();
+    pub fn delete(_key: &str) -> Result<()> {
+        Ok(())
+    }
 
-    if let Some(ref mut conn) = *connection {
-        let result: bool = redis::cmd("EXISTS")
-            .arg(key)
-            .query(conn)?;
-        return Ok(result);
+    pub fn delete_by_prefix(_prefix: &str) -> Result<usize> {
+        Ok(0)
     }
 
-    Ok(false)
-}
\ No newline at end of file
+    pub fn exists(_key: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_values_over_the_size_limit() {
+        assert!(check_value_size("user:1:profile", 1024, 1024).is_ok());
+        let error = check_value_size("user:1:profile", 1025, 1024).unwrap_err();
+        assert!(error.to_string().contains("user:1:profile"));
+    }
+}
@@ -0,0 +1,295 @@
+//! Opt-in clipboard history.
+//!
+//! Nothing is recorded until the frontend starts the watcher with the vault
+//! password. The watcher polls the clipboard for text and keeps the most
+//! recent entries in a Stronghold snapshot under the application data
+//! directory, encrypted with a key derived from that password. Stopping the
+//! watcher locks the history again; entries stay on disk until cleared.
+//! History needs the `stronghold` cargo feature; without it starting the
+//! watcher reports that history is unavailable.
+
+use crate::credential_store::CredentialStore;
+#[cfg(feature = "stronghold")]
+use crate::credential_store::StrongholdStore;
+use crate::errors::{AppError, AppResult, ErrorCode};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "stronghold")]
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::AppHandle;
+#[cfg(feature = "stronghold")]
+use tauri::Manager;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use uuid::Uuid;
+
+/// Interval between clipboard reads.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Entries kept; older ones are dropped first.
+pub const MAX_ENTRIES: usize = 50;
+
+/// Longest clipboard text recorded, in characters. Larger copies are skipped.
+const MAX_ENTRY_CHARS: usize = 10_000;
+
+/// Snapshot file inside the app data directory.
+#[cfg(feature = "stronghold")]
+const SNAPSHOT_FILE: &str = "clipboard.hold";
+
+/// Stronghold client and store key holding the history.
+#[cfg(feature = "stronghold")]
+const CLIENT: &str = "clipboard";
+const HISTORY_KEY: &str = "history";
+
+/// The unlocked history while the watcher runs.
+static SESSION: Lazy<Mutex<Option<Session>>> = Lazy::new(|| Mutex::new(None));
+
+struct Session {
+    vault: Box<dyn CredentialStore>,
+    entries: Vec<ClipboardEntry>,
+    watcher: JoinHandle<()>,
+}
+
+/// A recorded clipboard text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardEntry {
+    pub id: Uuid,
+    pub text: String,
+    pub copied_at: DateTime<Utc>,
+}
+
+/// Unlocks the history and starts recording clipboard text.
+///
+/// Returns the number of stored entries. Starting while already running
+/// is a no-op.
+pub fn start(app: &AppHandle, password: &str) -> AppResult<usize> {
+    let mut session = lock()?;
+    if let Some(session) = session.as_ref() {
+        return Ok(session.entries.len());
+    }
+    if password.is_empty() {
+        return Err(AppError::invalid_input("password", "Password is required"));
+    }
+
+    let vault = open_vault(app, password)?;
+    let entries = load_entries(vault.as_ref())?;
+    let count = entries.len();
+
+    let watcher_app = app.clone();
+    let watcher = tauri::async_runtime::spawn(async move {
+        let mut last = watcher_app.clipboard().read_text().ok();
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Ok(text) = watcher_app.clipboard().read_text() else {
+                continue;
+            };
+            if last.as_deref() == Some(text.as_str()) {
+                continue;
+            }
+            last = Some(text.clone());
+            if let Err(e) = record(text) {
+                tracing::warn!("Failed to record clipboard entry: {}", e);
+            }
+        }
+    });
+
+    *session = Some(Session {
+        vault,
+        entries,
+        watcher,
+    });
+    tracing::info!("Clipboard history started");
+    Ok(count)
+}
+
+/// Stops recording and locks the history. Returns whether it was running.
+pub fn stop() -> AppResult<bool> {
+    match lock()?.take() {
+        Some(session) => {
+            session.watcher.abort();
+            tracing::info!("Clipboard history stopped");
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Returns whether the watcher is running.
+pub fn is_running() -> bool {
+    lock().map(|session| session.is_some()).unwrap_or(false)
+}
+
+/// Returns recorded entries, most recent first.
+pub fn entries() -> AppResult<Vec<ClipboardEntry>> {
+    with_session(|session| Ok(session.entries.clone()))
+}
+
+/// Removes all entries. Returns how many were removed.
+pub fn clear() -> AppResult<usize> {
+    with_session(|session| {
+        let removed = session.entries.len();
+        session.entries.clear();
+        save_entries(session.vault.as_ref(), &session.entries)?;
+        Ok(removed)
+    })
+}
+
+fn record(text: String) -> AppResult<()> {
+    with_session(|session| {
+        if push_entry(&mut session.entries, text, Utc::now()) {
+            save_entries(session.vault.as_ref(), &session.entries)?;
+        }
+        Ok(())
+    })
+}
+
+/// Adds `text` to the front of `entries`, moving an identical earlier entry
+/// instead of duplicating it. Returns whether `entries` changed.
+fn push_entry(entries: &mut Vec<ClipboardEntry>, text: String, copied_at: DateTime<Utc>) -> bool {
+    if text.trim().is_empty() || text.chars().count() > MAX_ENTRY_CHARS {
+        return false;
+    }
+    if entries.first().is_some_and(|entry| entry.text == text) {
+        return false;
+    }
+
+    entries.retain(|entry| entry.text != text);
+    entries.insert(
+        0,
+        ClipboardEntry {
+            id: Uuid::new_v4(),
+            text,
+            copied_at,
+        },
+    );
+    entries.truncate(MAX_ENTRIES);
+    true
+}
+
+fn lock() -> AppResult<std::sync::MutexGuard<'static, Option<Session>>> {
+    SESSION
+        .lock()
+        .map_err(|_| AppError::internal_error("Clipboard history is unavailable"))
+}
+
+fn with_session<T>(f: impl FnOnce(&mut Session) -> AppResult<T>) -> AppResult<T> {
+    let mut session = lock()?;
+    match session.as_mut() {
+        Some(session) => f(session),
+        None => Err(AppError::new(
+            ErrorCode::Forbidden,
+            "Clipboard history is locked; start it with the vault password first",
+        )),
+    }
+}
+
+#[cfg(feature = "stronghold")]
+fn snapshot_path(app: &AppHandle) -> AppResult<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(SNAPSHOT_FILE))
+        .map_err(|e| AppError::new(ErrorCode::FileRead, format!("No app data directory: {}", e)))
+}
+
+#[cfg(feature = "stronghold")]
+fn open_vault(app: &AppHandle, password: &str) -> AppResult<Box<dyn CredentialStore>> {
+    let store = StrongholdStore::open(&snapshot_path(app)?, CLIENT, password)?;
+    Ok(Box::new(store))
+}
+
+#[cfg(not(feature = "stronghold"))]
+fn open_vault(_app: &AppHandle, _password: &str) -> AppResult<Box<dyn CredentialStore>> {
+    Err(AppError::new(
+        ErrorCode::NotImplemented,
+        "Clipboard history is not enabled in this build; rebuild with the 'stronghold' feature",
+    ))
+}
+
+fn load_entries(vault: &dyn CredentialStore) -> AppResult<Vec<ClipboardEntry>> {
+    match vault.get(HISTORY_KEY)? {
+        Some(bytes) => decode_entries(&bytes),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn save_entries(vault: &dyn CredentialStore, entries: &[ClipboardEntry]) -> AppResult<()> {
+    vault.insert(HISTORY_KEY, encode_entries(entries)?)
+}
+
+fn encode_entries(entries: &[ClipboardEntry]) -> AppResult<Vec<u8>> {
+    serde_json::to_vec(entries).map_err(|e| AppError::internal_error(e.to_string()))
+}
+
+fn decode_entries(bytes: &[u8]) -> AppResult<Vec<ClipboardEntry>> {
+    serde_json::from_slice(bytes).map_err(|e| {
+        AppError::new(
+            ErrorCode::FileRead,
+            format!("Stored clipboard history is corrupt: {}", e),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(entries: &[ClipboardEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.text.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_most_recent_first_without_duplicates() {
+        let mut entries = Vec::new();
+        assert!(push_entry(&mut entries, "a".into(), Utc::now()));
+        assert!(push_entry(&mut entries, "b".into(), Utc::now()));
+        assert!(!push_entry(&mut entries, "b".into(), Utc::now()));
+        assert!(push_entry(&mut entries, "a".into(), Utc::now()));
+        assert_eq!(texts(&entries), ["a", "b"]);
+    }
+
+    #[test]
+    fn skips_blank_and_oversized_text() {
+        let mut entries = Vec::new();
+        assert!(!push_entry(&mut entries, "  \n".into(), Utc::now()));
+        assert!(!push_entry(
+            &mut entries,
+            "x".repeat(MAX_ENTRY_CHARS + 1),
+            Utc::now()
+        ));
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn drops_oldest_entries_beyond_the_limit() {
+        let mut entries = Vec::new();
+        for i in 0..MAX_ENTRIES + 5 {
+            push_entry(&mut entries, i.to_string(), Utc::now());
+        }
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].text, (MAX_ENTRIES + 4).to_string());
+    }
+
+    #[test]
+    fn entries_round_trip_through_the_stored_encoding() {
+        let mut entries = Vec::new();
+        push_entry(&mut entries, "secret".into(), Utc::now());
+        assert_eq!(
+            decode_entries(&encode_entries(&entries).unwrap()).unwrap(),
+            entries
+        );
+        assert!(decode_entries(b"not json").is_err());
+    }
+
+    #[test]
+    fn history_is_locked_until_started() {
+        assert!(!is_running());
+        assert!(entries().is_err());
+        assert!(clear().is_err());
+        assert!(!stop().unwrap());
+    }
+}
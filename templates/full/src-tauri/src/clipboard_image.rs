@@ -0,0 +1,157 @@
+//! Image clipboard access and window screenshots.
+//!
+//! Images cross IPC as base64 PNG, the same encoding feedback reports use
+//! for screenshots, so a captured window can be attached to a report or
+//! handed to an annotation view without another conversion.
+
+use crate::errors::{AppError, AppResult, ErrorCode};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder, ImageFormat};
+use serde::Serialize;
+use tauri::image::Image;
+use tauri::{AppHandle, WebviewWindow};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Largest decoded PNG accepted from the frontend.
+const MAX_PNG_BYTES: usize = 20 * 1024 * 1024;
+
+/// An image read from or written to the clipboard.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardImage {
+    /// Base64-encoded PNG.
+    pub png: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Returns the image on the clipboard, or `None` when it holds no image.
+pub fn read(app: &AppHandle) -> AppResult<Option<ClipboardImage>> {
+    let Ok(image) = app.clipboard().read_image() else {
+        return Ok(None);
+    };
+    encode_png(image.rgba(), image.width(), image.height()).map(Some)
+}
+
+/// Places a base64 PNG, optionally as a `data:` URL, on the clipboard.
+pub fn write(app: &AppHandle, png: &str) -> AppResult<()> {
+    let (rgba, width, height) = decode_png(png)?;
+    set_clipboard(app, &rgba, width, height)
+}
+
+/// Captures `window` and places the screenshot on the clipboard.
+///
+/// Returns the screenshot so callers can also attach it elsewhere.
+pub fn copy_window_screenshot(
+    app: &AppHandle,
+    window: &WebviewWindow,
+) -> AppResult<ClipboardImage> {
+    let title = window
+        .title()
+        .map_err(|e| AppError::internal_error(format!("Failed to read window title: {}", e)))?;
+    let pid = std::process::id();
+
+    let windows = xcap::Window::all().map_err(capture_error)?;
+    let target = windows
+        .iter()
+        .find(|candidate| candidate.pid() == pid && candidate.title() == title)
+        .ok_or_else(|| AppError::not_found("Window"))?;
+    if target.is_minimized() {
+        return Err(AppError::invalid_input(
+            "window",
+            "Cannot capture a minimized window",
+        ));
+    }
+
+    let capture = target.capture_image().map_err(capture_error)?;
+    let (width, height) = capture.dimensions();
+    set_clipboard(app, capture.as_raw(), width, height)?;
+    tracing::debug!(
+        "Copied {}x{} screenshot of '{}' to the clipboard",
+        width,
+        height,
+        window.label()
+    );
+    encode_png(capture.as_raw(), width, height)
+}
+
+fn set_clipboard(app: &AppHandle, rgba: &[u8], width: u32, height: u32) -> AppResult<()> {
+    app.clipboard()
+        .write_image(&Image::new(rgba, width, height))
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::SystemError,
+                format!("Failed to write image to clipboard: {}", e),
+            )
+        })
+}
+
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> AppResult<ClipboardImage> {
+    let mut bytes = Vec::new();
+    PngEncoder::new(&mut bytes)
+        .write_image(rgba, width, height, ExtendedColorType::Rgba8)
+        .map_err(|e| AppError::internal_error(format!("Failed to encode PNG: {}", e)))?;
+    Ok(ClipboardImage {
+        png: BASE64.encode(bytes),
+        width,
+        height,
+    })
+}
+
+/// Decodes a base64 PNG into RGBA pixels, accepting `data:image/png;base64,`
+/// prefixes.
+fn decode_png(encoded: &str) -> AppResult<(Vec<u8>, u32, u32)> {
+    let data = encoded
+        .split_once(',')
+        .filter(|(prefix, _)| prefix.starts_with("data:"))
+        .map(|(_, data)| data)
+        .unwrap_or(encoded);
+
+    let bytes = BASE64
+        .decode(data.trim())
+        .map_err(|e| AppError::invalid_input("png", format!("Invalid base64 image: {}", e)))?;
+    if bytes.len() > MAX_PNG_BYTES {
+        return Err(AppError::invalid_input("png", "Image is too large"));
+    }
+
+    let image = image::load_from_memory_with_format(&bytes, ImageFormat::Png)
+        .map_err(|e| AppError::invalid_input("png", format!("Invalid PNG: {}", e)))?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Ok((image.into_raw(), width, height))
+}
+
+fn capture_error(e: impl std::fmt::Display) -> AppError {
+    AppError::new(
+        ErrorCode::SystemError,
+        format!("Failed to capture window: {}", e),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIXELS: [u8; 8] = [255, 0, 0, 255, 0, 0, 255, 128];
+
+    #[test]
+    fn png_round_trips_through_base64() {
+        let encoded = encode_png(&PIXELS, 2, 1).unwrap();
+        assert_eq!((encoded.width, encoded.height), (2, 1));
+
+        let (rgba, width, height) = decode_png(&encoded.png).unwrap();
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(rgba, PIXELS);
+
+        let data_url = format!("data:image/png;base64,{}", encoded.png);
+        assert_eq!(decode_png(&data_url).unwrap().0, PIXELS);
+    }
+
+    #[test]
+    fn rejects_invalid_images() {
+        assert!(decode_png("not base64!").is_err());
+        assert!(decode_png(&BASE64.encode(b"GIF89a")).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+//! Caller identity for command handlers.
+//!
+//! The frontend sends the session's access token as a bearer token in the
+//! `Authorization` header of each invocation. Rate-limited wrappers of
+//! handlers that take a [`CommandContext`] resolve it once per call, so those
+//! handlers can filter or deny based on the signed-in user and their roles.
+//!
+//! A missing token yields an anonymous context. So does a token that is
+//! invalid or expired, but the context keeps the reason, and
+//! [`CommandContext::require_user`] reports it (e.g. `TOKEN_EXPIRED`) so the
+//! frontend knows to refresh instead of signing in again.
+//!
+//! During impersonation the context is the impersonated user's, with their
+//! roles, and [`CommandContext::actor_id`] names the admin behind it.
+
+use crate::errors::{AppError, AppResult};
+use tauri::http::header::{HeaderMap, AUTHORIZATION};
+use tauri::ipc::Request;
+use uuid::Uuid;
+
+/// Role that may see and manage every user's records.
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Who invoked a command.
+#[derive(Debug, Clone)]
+pub struct CommandContext {
+    /// Identifies this invocation in errors and logs.
+    pub request_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub roles: Vec<String>,
+    /// Admin acting as `user_id` through an impersonation session.
+    pub impersonator_id: Option<Uuid>,
+    /// Set for callers inside the application, which pass every check.
+    internal: bool,
+    /// Why a presented access token was not accepted.
+    auth_error: Option<AppError>,
+}
+
+impl CommandContext {
+    /// A caller without a session.
+    pub fn anonymous() -> Self {
+        Self {
+            request_id: Uuid::new_v4(),
+            user_id: None,
+            roles: Vec::new(),
+            impersonator_id: None,
+            internal: false,
+            auth_error: None,
+        }
+    }
+
+    /// A signed-in caller.
+    pub fn for_user(user_id: Uuid, roles: Vec<String>) -> Self {
+        Self {
+            user_id: Some(user_id),
+            roles,
+            ..Self::anonymous()
+        }
+    }
+
+    /// The application itself, e.g. a paired companion or a test.
+    pub fn internal() -> Self {
+        Self {
+            internal: true,
+            ..Self::anonymous()
+        }
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.internal || self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.has_role(ADMIN_ROLE)
+    }
+
+    /// Who is responsible for the call, for the audit log: the impersonating
+    /// admin if there is one, otherwise the signed-in user.
+    pub fn actor_id(&self) -> Option<Uuid> {
+        self.impersonator_id.or(self.user_id)
+    }
+
+    /// Returns the signed-in user, failing with `UNAUTHORIZED` (or the
+    /// token's error) for anonymous callers.
+    pub fn require_user(&self) -> AppResult<Uuid> {
+        match (self.user_id, &self.auth_error) {
+            (Some(user_id), _) => Ok(user_id),
+            (None, Some(error)) => Err(self.tag(error.clone())),
+            (None, None) => Err(self.tag(AppError::unauthorized("Sign in to continue"))),
+        }
+    }
+
+    /// Fails with `FORBIDDEN` unless the caller has `role`.
+    pub fn require_role(&self, role: &str) -> AppResult<()> {
+        if self.has_role(role) {
+            return Ok(());
+        }
+        self.require_user()?;
+        Err(self.tag(AppError::forbidden(format!("Requires the {} role", role))))
+    }
+
+    /// Allows admins and `user_id` itself.
+    pub fn require_self_or_admin(&self, user_id: Uuid) -> AppResult<()> {
+        if self.is_admin() || self.require_user()? == user_id {
+            Ok(())
+        } else {
+            Err(self.tag(AppError::forbidden("Not allowed for other users")))
+        }
+    }
+
+    /// The only user whose records the caller may see: `None` for admins,
+    /// who see everyone, and the caller for everybody else.
+    pub fn visible_user(&self) -> AppResult<Option<Uuid>> {
+        if self.is_admin() {
+            Ok(None)
+        } else {
+            self.require_user().map(Some)
+        }
+    }
+
+    fn tag(&self, error: AppError) -> AppError {
+        error.with_request_id(self.request_id.to_string())
+    }
+}
+
+/// Reads the bearer token from invocation headers.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// Resolves the caller of an invocation from its access token.
+pub async fn from_request(request: &Request<'_>) -> CommandContext {
+    let Some(token) = bearer_token(request.headers()) else {
+        return CommandContext::anonymous();
+    };
+    resolve(token).await
+}
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+async fn resolve(token: &str) -> CommandContext {
+    match load_user(token).await {
+        Ok((session, roles)) => CommandContext {
+            impersonator_id: session.impersonator_id,
+            ..CommandContext::for_user(session.user_id, roles)
+        },
+        Err(error) => {
+            tracing::debug!("Rejected access token: {}", error);
+            CommandContext {
+                auth_error: Some(error),
+                ..CommandContext::anonymous()
+            }
+        }
+    }
+}
+
+/// Without a database there are no sessions, so tokens are ignored.
+#[cfg(not(any(feature = "database", feature = "sqlite")))]
+async fn resolve(_token: &str) -> CommandContext {
+    CommandContext::anonymous()
+}
+
+/// The context of `user_id` with the roles it has now, for callers acting
+/// on a user's behalf outside an invocation, e.g. a paired companion. Fails
+/// once the account is no longer active.
+#[cfg(any(feature = "database", feature = "sqlite"))]
+pub async fn for_active_user(user_id: Uuid) -> AppResult<CommandContext> {
+    let roles = active_roles(user_id).await?;
+    Ok(CommandContext::for_user(user_id, roles))
+}
+
+/// Without a database there are no roles to load.
+#[cfg(not(any(feature = "database", feature = "sqlite")))]
+pub async fn for_active_user(user_id: Uuid) -> AppResult<CommandContext> {
+    Ok(CommandContext::for_user(user_id, Vec::new()))
+}
+
+/// Validates `token` against the selected database and loads the roles of
+/// its active user.
+#[cfg(any(feature = "database", feature = "sqlite"))]
+async fn load_user(token: &str) -> AppResult<(crate::models::Session, Vec<String>)> {
+    use crate::config::{self, DatabaseBackend};
+    use crate::errors::{ErrorCode, IntoAppError};
+
+    let session = match config::current().database_backend {
+        #[cfg(feature = "database")]
+        DatabaseBackend::Postgres => {
+            let pool =
+                crate::database::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+            crate::session::postgres::validate(pool.as_ref(), token).await?
+        }
+        #[cfg(feature = "sqlite")]
+        DatabaseBackend::Sqlite => {
+            let pool =
+                crate::local_db::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+            crate::session::sqlite::validate(pool.as_ref(), token).await?
+        }
+        #[allow(unreachable_patterns)]
+        backend => {
+            return Err(AppError::new(
+                ErrorCode::DatabaseConnection,
+                format!("{} support is not compiled in", backend.name()),
+            ))
+        }
+    };
+    let roles = active_roles(session.user_id).await?;
+    Ok((session, roles))
+}
+
+/// Loads the roles of `user_id` from the selected database, failing when
+/// the account is missing or inactive.
+#[cfg(any(feature = "database", feature = "sqlite"))]
+async fn active_roles(user_id: Uuid) -> AppResult<Vec<String>> {
+    use crate::config::{self, DatabaseBackend};
+    use crate::errors::{ErrorCode, IntoAppError};
+
+    let roles: Option<Vec<String>> = match config::current().database_backend {
+        #[cfg(feature = "database")]
+        DatabaseBackend::Postgres => {
+            let pool =
+                crate::database::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+            sqlx::query_scalar("SELECT roles FROM users WHERE id = $1 AND is_active")
+                .bind(user_id)
+                .fetch_optional(pool.as_ref())
+                .await
+                .into_app_error(ErrorCode::DatabaseQuery)?
+        }
+        #[cfg(feature = "sqlite")]
+        DatabaseBackend::Sqlite => {
+            let pool =
+                crate::local_db::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+            crate::repository::SqliteUserRepository::new(pool.as_ref())
+                .active_roles(user_id)
+                .await
+                .into_app_error(ErrorCode::DatabaseQuery)?
+        }
+        #[allow(unreachable_patterns)]
+        backend => {
+            return Err(AppError::new(
+                ErrorCode::DatabaseConnection,
+                format!("{} support is not compiled in", backend.name()),
+            ))
+        }
+    };
+    roles.ok_or_else(|| AppError::unauthorized("The account is no longer active"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use tauri::http::HeaderValue;
+
+    #[test]
+    fn reads_bearer_tokens() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc.def"));
+        assert_eq!(bearer_token(&headers), Some("abc.def"));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+        assert_eq!(bearer_token(&headers), None);
+    }
+
+    #[test]
+    fn anonymous_callers_must_sign_in() {
+        let context = CommandContext::anonymous();
+        let error = context.require_user().unwrap_err();
+        assert!(matches!(error.code, ErrorCode::Unauthorized));
+        assert_eq!(error.request_id, Some(context.request_id.to_string()));
+        assert!(context.visible_user().is_err());
+
+        let expired = CommandContext {
+            auth_error: Some(AppError::new(ErrorCode::TokenExpired, "expired")),
+            ..CommandContext::anonymous()
+        };
+        let error = expired.require_user().unwrap_err();
+        assert!(matches!(error.code, ErrorCode::TokenExpired));
+    }
+
+    #[test]
+    fn users_only_see_themselves_unless_admin() {
+        let user_id = Uuid::new_v4();
+        let user = CommandContext::for_user(user_id, Vec::new());
+        assert_eq!(user.visible_user().unwrap(), Some(user_id));
+        assert!(user.require_self_or_admin(user_id).is_ok());
+        let error = user.require_self_or_admin(Uuid::new_v4()).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::Forbidden));
+        assert!(user.require_role(ADMIN_ROLE).is_err());
+
+        let admin = CommandContext::for_user(Uuid::new_v4(), vec![ADMIN_ROLE.to_string()]);
+        assert_eq!(admin.visible_user().unwrap(), None);
+        assert!(admin.require_self_or_admin(user_id).is_ok());
+
+        assert_eq!(CommandContext::internal().visible_user().unwrap(), None);
+    }
+
+    #[test]
+    fn impersonating_admins_are_the_actor() {
+        let user_id = Uuid::new_v4();
+        let admin_id = Uuid::new_v4();
+        let context = CommandContext {
+            impersonator_id: Some(admin_id),
+            ..CommandContext::for_user(user_id, Vec::new())
+        };
+        assert_eq!(context.require_user().unwrap(), user_id);
+        assert_eq!(context.actor_id(), Some(admin_id));
+        assert!(!context.is_admin());
+        assert_eq!(
+            CommandContext::for_user(user_id, Vec::new()).actor_id(),
+            Some(user_id)
+        );
+    }
+}
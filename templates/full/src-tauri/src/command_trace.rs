@@ -0,0 +1,150 @@
+//! Per-command tracing spans with latency and argument size.
+//!
+//! The command registry records the serialized argument size of each `rl_`
+//! invocation as it is dispatched; the rate-limited wrappers then run the
+//! handler inside a `command` span and complete it with the duration and
+//! outcome. Completed commands are fed to the metrics registry and logged,
+//! at `warn` level when they fail or exceed [`SLOW_COMMAND_THRESHOLD`].
+
+use crate::metrics;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::ipc::InvokeBody;
+use tracing::field::Empty;
+use tracing::Span;
+
+/// Commands slower than this are logged as warnings.
+pub const SLOW_COMMAND_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// Argument sizes kept per command while waiting for the wrapper to run.
+///
+/// Invocations rejected during argument deserialization never reach the
+/// wrapper, so the queue is bounded to avoid growing without limit.
+const MAX_PENDING_PER_COMMAND: usize = 64;
+
+/// Argument sizes of dispatched invocations per command, oldest first.
+static PENDING_ARGS: Lazy<Mutex<HashMap<String, VecDeque<usize>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// How a traced command ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    Error,
+    RateLimited,
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Ok => "ok",
+            Outcome::Error => "error",
+            Outcome::RateLimited => "rate_limited",
+        }
+    }
+}
+
+/// Returns the size in bytes of an invocation payload.
+pub fn payload_size(body: &InvokeBody) -> usize {
+    match body {
+        InvokeBody::Json(value) => serde_json::to_vec(value).map(|v| v.len()).unwrap_or(0),
+        InvokeBody::Raw(bytes) => bytes.len(),
+    }
+}
+
+/// Records the argument size of an invocation about to be dispatched.
+pub fn record_args(command: &str, body: &InvokeBody) {
+    let size = payload_size(body);
+    if let Ok(mut pending) = PENDING_ARGS.lock() {
+        let queue = pending.entry(command.to_string()).or_default();
+        if queue.len() == MAX_PENDING_PER_COMMAND {
+            queue.pop_front();
+        }
+        queue.push_back(size);
+    }
+}
+
+/// Takes the oldest recorded argument size for `command`.
+fn take_args(command: &str) -> Option<usize> {
+    PENDING_ARGS
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.get_mut(command).and_then(VecDeque::pop_front))
+}
+
+/// Opens the span for an invocation of the IPC command `invoked`, which runs
+/// the handler `command`.
+pub fn start(invoked: &str, command: &str) -> Span {
+    let span = tracing::info_span!(
+        "command",
+        command,
+        arg_bytes = Empty,
+        duration_ms = Empty,
+        outcome = Empty
+    );
+    if let Some(size) = take_args(invoked) {
+        span.record("arg_bytes", size);
+        metrics::record_command_args(command, size);
+    }
+    span
+}
+
+/// Completes `span` and records the invocation in the metrics registry.
+///
+/// Rate-limited invocations are not recorded as command executions.
+pub fn finish(
+    span: &Span,
+    command: &str,
+    duration: Duration,
+    outcome: Outcome,
+    error: Option<&str>,
+) {
+    let duration_ms = duration.as_secs_f64() * 1000.0;
+    span.record("duration_ms", duration_ms);
+    span.record("outcome", outcome.as_str());
+
+    if outcome != Outcome::RateLimited {
+        metrics::record_command(command, duration, outcome == Outcome::Ok);
+    }
+
+    let _entered = span.enter();
+    match (outcome, error) {
+        (Outcome::Ok, _) if duration >= SLOW_COMMAND_THRESHOLD => {
+            tracing::warn!("Slow command {} took {:.1}ms", command, duration_ms)
+        }
+        (Outcome::Ok, _) => tracing::debug!("Command {} took {:.1}ms", command, duration_ms),
+        (_, Some(error)) => tracing::warn!(
+            "Command {} failed after {:.1}ms: {}",
+            command,
+            duration_ms,
+            error
+        ),
+        (_, None) => tracing::warn!("Command {} failed after {:.1}ms", command, duration_ms),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn measures_json_and_raw_payloads() {
+        assert_eq!(payload_size(&InvokeBody::Json(json!({ "a": 1 }))), 7);
+        assert_eq!(payload_size(&InvokeBody::Raw(vec![0; 16])), 16);
+    }
+
+    #[test]
+    fn pending_argument_sizes_are_bounded_and_ordered() {
+        let command = "command_trace_test";
+        for size in 0..MAX_PENDING_PER_COMMAND + 2 {
+            record_args(command, &InvokeBody::Raw(vec![0; size]));
+        }
+
+        assert_eq!(take_args(command), Some(2));
+        assert_eq!(take_args(command), Some(3));
+        assert_eq!(take_args("command_trace_unknown"), None);
+    }
+}
@@ -0,0 +1,368 @@
+//! WebSocket server for companion apps.
+//!
+//! When `COMPANION_SERVER_PORT` is set, companion apps on the same machine
+//! (or on the LAN with `COMPANION_SERVER_LAN`) can connect and invoke a fixed
+//! whitelist of commands. Each connection must first send the pairing token
+//! created with `create_companion_pairing_token`; the token is kept as an
+//! [`ApiToken`] secret, so companions can only connect while secrets are
+//! unlocked. Commands run as the user who created the token, with the roles
+//! that user has at the time of each request.
+//!
+//! The server speaks plain `ws://` without TLS. With `COMPANION_SERVER_LAN`
+//! the pairing token and every reply cross the network in cleartext, so only
+//! enable it on networks where that is acceptable.
+//!
+//! Messages are JSON text frames. After `{"token": "..."}` is accepted the
+//! server replies `{"authenticated": true}`, then answers each
+//! `{"id": 1, "command": "get_system_info", "args": {}}` with
+//! `{"id": 1, "result": ...}` or `{"id": 1, "error": "..."}`. Arguments use
+//! the same camelCase names as the frontend.
+
+use crate::command_context::{self, CommandContext};
+use crate::errors::{AppError, AppResult};
+use crate::events::{self, AppEvent};
+use crate::handlers;
+use crate::rate_limiter::RateLimiterConfig;
+use crate::secrets::{self, ApiToken};
+use crate::shutdown;
+use anyhow::Result;
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, State};
+use axum::response::Response;
+use axum::Router;
+use once_cell::sync::OnceCell;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+/// Name of the [`ApiToken`] secret holding the pairing token.
+const PAIRING_SECRET: &str = "companion-pairing";
+
+/// Time a new connection has to send the pairing token.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Commands companions may invoke.
+pub const COMMANDS: &[&str] = &[
+    #[cfg(any(debug_assertions, feature = "demo"))]
+    "greet",
+    "get_system_info",
+    "get_locale_info",
+    "list_storage_devices",
+    #[cfg(feature = "database")]
+    "get_all_users",
+    #[cfg(feature = "database")]
+    "get_user_by_id",
+];
+
+/// Address the server is bound to once started.
+static BOUND_ADDRESS: OnceCell<SocketAddr> = OnceCell::new();
+
+/// Current state of the companion server.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionServerStatus {
+    pub running: bool,
+    /// WebSocket URL, e.g. `ws://127.0.0.1:8766`.
+    pub address: Option<String>,
+    /// Whether a pairing token exists. `None` while secrets are locked.
+    pub paired: Option<bool>,
+    pub commands: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AuthMessage {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct Request {
+    id: Value,
+    command: String,
+    #[serde(default)]
+    args: Value,
+}
+
+#[derive(Serialize)]
+struct Reply {
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Creates a new pairing token acting for `user_id`, replacing any previous
+/// one. Companions already connected stay connected. Requires unlocked
+/// secrets.
+pub fn create_pairing_token(user_id: Uuid) -> AppResult<String> {
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    secrets::put(
+        PAIRING_SECRET,
+        &ApiToken {
+            service: "companion".to_string(),
+            token: token.clone(),
+            scopes: COMMANDS.iter().map(|command| command.to_string()).collect(),
+            expires_at: None,
+            user_id: Some(user_id),
+        },
+    )?;
+    tracing::info!("Companion pairing token created for user {}", user_id);
+    Ok(token)
+}
+
+/// Removes the pairing token so no companion can connect. Returns whether
+/// one existed.
+pub fn revoke_pairing() -> AppResult<bool> {
+    secrets::remove::<ApiToken>(PAIRING_SECRET)
+}
+
+/// Returns the running state, address, and pairing state.
+pub fn status() -> CompanionServerStatus {
+    let paired = secrets::is_unlocked()
+        .then(|| secrets::get::<ApiToken>(PAIRING_SECRET).ok())
+        .flatten()
+        .map(|token| token.is_some());
+
+    CompanionServerStatus {
+        running: BOUND_ADDRESS.get().is_some(),
+        address: BOUND_ADDRESS.get().map(|addr| format!("ws://{}", addr)),
+        paired,
+        commands: COMMANDS.iter().map(|command| command.to_string()).collect(),
+    }
+}
+
+/// Binds the server on `port`, on all interfaces when `lan` is set and on
+/// loopback otherwise, and serves connections in the background.
+pub async fn start_server(app: AppHandle, port: u16, lan: bool) -> Result<SocketAddr> {
+    if let Some(addr) = BOUND_ADDRESS.get() {
+        return Ok(*addr);
+    }
+
+    let host = if lan {
+        tracing::warn!(
+            "Companion server is reachable on the LAN; pairing tokens are sent unencrypted"
+        );
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let listener = tokio::net::TcpListener::bind((host, port)).await?;
+    let addr = listener.local_addr()?;
+    BOUND_ADDRESS
+        .set(addr)
+        .map_err(|_| anyhow::anyhow!("Companion server already started"))?;
+
+    let router = Router::new().fallback(upgrade).with_state(app);
+
+    let server = tauri::async_runtime::spawn(async move {
+        let service = router.into_make_service_with_connect_info::<SocketAddr>();
+        if let Err(e) = axum::serve(listener, service).await {
+            tracing::error!("Companion server stopped: {}", e);
+        }
+    });
+    shutdown::track("companion-server", server);
+
+    tracing::info!("Companion server listening on {}", addr);
+    Ok(addr)
+}
+
+async fn upgrade(
+    ws: WebSocketUpgrade,
+    State(app): State<AppHandle>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+) -> Response {
+    ws.on_upgrade(move |socket| serve(socket, app, peer))
+}
+
+/// Authenticates a connection, then answers its requests until it closes.
+async fn serve(mut socket: WebSocket, app: AppHandle, peer: SocketAddr) {
+    let auth = tokio::time::timeout(AUTH_TIMEOUT, socket.recv()).await;
+    let token = match auth {
+        Ok(Some(Ok(Message::Text(text)))) => serde_json::from_str::<AuthMessage>(text.as_str())
+            .map(|message| message.token)
+            .map_err(|_| AppError::unauthorized("Expected a pairing token")),
+        Ok(_) => return,
+        Err(_) => Err(AppError::unauthorized(
+            "Timed out waiting for a pairing token",
+        )),
+    };
+    let user_id = match token.and_then(|token| authenticate(&token)) {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            tracing::warn!("Rejected companion connection from {}: {}", peer, e);
+            let frame = CloseFrame {
+                code: close_code::POLICY,
+                reason: e.message.into(),
+            };
+            let _ = socket.send(Message::Close(Some(frame))).await;
+            return;
+        }
+    };
+
+    let ack = serde_json::json!({ "authenticated": true }).to_string();
+    if socket.send(Message::Text(ack.into())).await.is_err() {
+        return;
+    }
+    tracing::info!("Companion connected from {}", peer);
+    events::publish(AppEvent::CompanionConnected {
+        address: peer.to_string(),
+    });
+
+    let limiter = app
+        .try_state::<Arc<RateLimiterConfig>>()
+        .map(|state| state.inner().clone());
+
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let reply = match serde_json::from_str::<Request>(text.as_str()) {
+            Ok(request) => {
+                let limited = limiter
+                    .as_ref()
+                    .and_then(|limiter| limiter.check_rate_limit_now(None).err());
+                let result = match limited {
+                    Some(e) => Err(format!("Rate limit exceeded: {}", e)),
+                    None => match command_context::for_active_user(user_id).await {
+                        Ok(context) => dispatch(context, &request.command, request.args).await,
+                        Err(e) => Err(e.to_string()),
+                    },
+                };
+                match result {
+                    Ok(result) => Reply {
+                        id: request.id,
+                        result: Some(result),
+                        error: None,
+                    },
+                    Err(error) => Reply {
+                        id: request.id,
+                        result: None,
+                        error: Some(error),
+                    },
+                }
+            }
+            Err(e) => Reply {
+                id: Value::Null,
+                result: None,
+                error: Some(format!("Invalid request: {}", e)),
+            },
+        };
+        let Ok(reply) = serde_json::to_string(&reply) else {
+            continue;
+        };
+        if socket.send(Message::Text(reply.into())).await.is_err() {
+            break;
+        }
+    }
+
+    tracing::info!("Companion disconnected from {}", peer);
+    events::publish(AppEvent::CompanionDisconnected {
+        address: peer.to_string(),
+    });
+}
+
+/// Checks `token` against the stored pairing token, returning the user it
+/// acts for.
+fn authenticate(token: &str) -> AppResult<Uuid> {
+    let paired = secrets::get::<ApiToken>(PAIRING_SECRET)?
+        .ok_or_else(|| AppError::unauthorized("No companion is paired"))?;
+    if !tokens_match(token, &paired.token) {
+        return Err(AppError::unauthorized("Invalid pairing token"));
+    }
+    paired
+        .user_id
+        .ok_or_else(|| AppError::unauthorized("Pairing token has no account; pair again"))
+}
+
+/// Compares tokens in time independent of where they first differ.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Runs a whitelisted command with JSON arguments as `context`.
+async fn dispatch(context: CommandContext, command: &str, args: Value) -> Result<Value, String> {
+    match command {
+        #[cfg(any(debug_assertions, feature = "demo"))]
+        "greet" => {
+            let name: String = arg(&args, "name")?;
+            to_value(crate::greet(&name))
+        }
+        "get_system_info" => to_value(handlers::get_system_info().await?),
+        "get_locale_info" => to_value(handlers::get_locale_info().await?),
+        "list_storage_devices" => {
+            to_value(handlers::list_storage_devices(arg(&args, "removableOnly")?).await?)
+        }
+        #[cfg(feature = "database")]
+        "get_all_users" => to_value(handlers::get_all_users(context).await?),
+        #[cfg(feature = "database")]
+        "get_user_by_id" => {
+            to_value(handlers::get_user_by_id(context, arg(&args, "userId")?).await?)
+        }
+        _ => Err(format!(
+            "Command '{}' is not available to companions",
+            command
+        )),
+    }
+}
+
+/// Reads argument `name`; missing arguments read as null, so optional ones
+/// may be omitted.
+fn arg<T: DeserializeOwned>(args: &Value, name: &str) -> Result<T, String> {
+    let value = args.get(name).cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|e| format!("Invalid argument '{}': {}", name, e))
+}
+
+fn to_value(value: impl Serialize) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn compares_tokens_exactly() {
+        assert!(tokens_match("abc123", "abc123"));
+        assert!(!tokens_match("abc124", "abc123"));
+        assert!(!tokens_match("abc", "abc123"));
+        assert!(!tokens_match("", "abc123"));
+    }
+
+    #[test]
+    fn reads_camel_case_arguments() {
+        let args = json!({ "removableOnly": true });
+        assert_eq!(arg::<Option<bool>>(&args, "removableOnly"), Ok(Some(true)));
+        assert_eq!(arg::<Option<bool>>(&Value::Null, "removableOnly"), Ok(None));
+        assert!(arg::<String>(&args, "name").is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_commands_outside_the_whitelist() {
+        let context = CommandContext::internal();
+        let error = dispatch(context.clone(), "delete_user", json!({ "userId": "x" }))
+            .await
+            .unwrap_err();
+        assert!(error.contains("not available"));
+
+        #[cfg(any(debug_assertions, feature = "demo"))]
+        {
+            let greeting = dispatch(context, "greet", json!({ "name": "Ada" }))
+                .await
+                .unwrap();
+            assert!(greeting.as_str().unwrap().contains("Ada"));
+        }
+    }
+}
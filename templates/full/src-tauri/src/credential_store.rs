@@ -0,0 +1,281 @@
+//! Key-value stores for secret material.
+//!
+//! [`CredentialStore`] hides where secrets live so callers such as
+//! [`crate::secrets`] work the same on every backend. The Stronghold store
+//! keeps entries in an encrypted snapshot file unlocked with the vault
+//! password; the keychain store keeps each entry as a separate item in the
+//! platform keychain, which the OS unlocks with the user's login. Keychains
+//! cannot enumerate items, so that store also maintains an index item
+//! listing its keys. The Stronghold store is compiled only with the
+//! `stronghold` cargo feature.
+
+use crate::config::CredentialBackend;
+use crate::errors::{AppError, AppResult, ErrorCode};
+#[cfg(feature = "stronghold")]
+use crate::stronghold::{derive_key, StrongholdManager};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::fmt;
+#[cfg(feature = "stronghold")]
+use std::path::Path;
+#[cfg(feature = "stronghold")]
+use tauri_plugin_stronghold::stronghold::Stronghold;
+
+/// Keychain item listing the keys of all other items.
+const INDEX_KEY: &str = "__index__";
+
+/// A store of named secret values.
+pub trait CredentialStore: Send {
+    /// Backend the store writes to.
+    fn backend(&self) -> CredentialBackend;
+
+    /// Returns the value stored under `key`.
+    fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>>;
+
+    /// Stores `value` under `key`, replacing any previous value.
+    fn insert(&self, key: &str, value: Vec<u8>) -> AppResult<()>;
+
+    /// Deletes `key`. Returns whether it existed.
+    fn delete(&self, key: &str) -> AppResult<bool>;
+
+    /// Returns every stored key.
+    fn keys(&self) -> AppResult<Vec<String>>;
+}
+
+/// Entries in one client of a Stronghold snapshot.
+#[cfg(feature = "stronghold")]
+pub struct StrongholdStore {
+    manager: StrongholdManager,
+    client: Vec<u8>,
+}
+
+#[cfg(feature = "stronghold")]
+impl StrongholdStore {
+    /// Opens or creates the snapshot at `path` and its client `client`.
+    pub fn open(path: &Path, client: &str, password: &str) -> AppResult<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| AppError::new(ErrorCode::DirectoryCreate, e.to_string()))?;
+        }
+        let stronghold = Stronghold::new(path, derive_key(password)).map_err(|e| {
+            AppError::new(
+                ErrorCode::AuthenticationFailed,
+                format!("Failed to unlock {}: {}", path.display(), e),
+            )
+        })?;
+        stronghold
+            .load_client(client)
+            .or_else(|_| stronghold.create_client(client))
+            .map_err(store_error)?;
+        Ok(Self {
+            manager: StrongholdManager::new(stronghold),
+            client: client.as_bytes().to_vec(),
+        })
+    }
+
+    fn persist(&self) -> AppResult<()> {
+        let stronghold = self.manager.stronghold();
+        stronghold.write_client(&self.client).map_err(store_error)?;
+        stronghold.save().map_err(store_error)
+    }
+}
+
+#[cfg(feature = "stronghold")]
+impl CredentialStore for StrongholdStore {
+    fn backend(&self) -> CredentialBackend {
+        CredentialBackend::Stronghold
+    }
+
+    fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>> {
+        let stronghold = self.manager.stronghold();
+        let client = stronghold.get_client(&self.client).map_err(store_error)?;
+        client.store().get(key.as_bytes()).map_err(store_error)
+    }
+
+    fn insert(&self, key: &str, value: Vec<u8>) -> AppResult<()> {
+        let stronghold = self.manager.stronghold();
+        let client = stronghold.get_client(&self.client).map_err(store_error)?;
+        client
+            .store()
+            .insert(key.as_bytes().to_vec(), value, None)
+            .map_err(store_error)?;
+        self.persist()
+    }
+
+    fn delete(&self, key: &str) -> AppResult<bool> {
+        let stronghold = self.manager.stronghold();
+        let client = stronghold.get_client(&self.client).map_err(store_error)?;
+        let removed = client
+            .store()
+            .delete(key.as_bytes())
+            .map_err(store_error)?
+            .is_some();
+        if removed {
+            self.persist()?;
+        }
+        Ok(removed)
+    }
+
+    fn keys(&self) -> AppResult<Vec<String>> {
+        let stronghold = self.manager.stronghold();
+        let client = stronghold.get_client(&self.client).map_err(store_error)?;
+        Ok(client
+            .store()
+            .keys()
+            .map_err(store_error)?
+            .into_iter()
+            .filter_map(|key| String::from_utf8(key).ok())
+            .collect())
+    }
+}
+
+/// Items of one service in the platform keychain. Values are stored base64
+/// encoded because keychains hold text.
+pub struct KeychainStore {
+    service: String,
+}
+
+impl KeychainStore {
+    /// Opens the keychain items of `service`, checking the keychain is
+    /// reachable by reading the index.
+    pub fn open(service: &str) -> AppResult<Self> {
+        let store = Self {
+            service: service.to_string(),
+        };
+        store.index()?;
+        Ok(store)
+    }
+
+    fn entry(&self, key: &str) -> AppResult<keyring::Entry> {
+        keyring::Entry::new(&self.service, key).map_err(store_error)
+    }
+
+    fn read(&self, key: &str) -> AppResult<Option<String>> {
+        match self.entry(key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(store_error(e)),
+        }
+    }
+
+    fn index(&self) -> AppResult<Vec<String>> {
+        Ok(self
+            .read(INDEX_KEY)?
+            .map(|index| parse_index(&index))
+            .unwrap_or_default())
+    }
+
+    fn write_index(&self, keys: &[String]) -> AppResult<()> {
+        let index =
+            serde_json::to_string(keys).map_err(|e| AppError::internal_error(e.to_string()))?;
+        self.entry(INDEX_KEY)?
+            .set_password(&index)
+            .map_err(store_error)
+    }
+}
+
+impl CredentialStore for KeychainStore {
+    fn backend(&self) -> CredentialBackend {
+        CredentialBackend::Keychain
+    }
+
+    fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>> {
+        self.read(key)?
+            .map(|value| {
+                BASE64
+                    .decode(value)
+                    .map_err(|e| AppError::new(ErrorCode::InvalidFormat, e.to_string()))
+            })
+            .transpose()
+    }
+
+    fn insert(&self, key: &str, value: Vec<u8>) -> AppResult<()> {
+        self.entry(key)?
+            .set_password(&BASE64.encode(value))
+            .map_err(store_error)?;
+        let mut keys = self.index()?;
+        if index_insert(&mut keys, key) {
+            self.write_index(&keys)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> AppResult<bool> {
+        let removed = match self.entry(key)?.delete_password() {
+            Ok(()) => true,
+            Err(keyring::Error::NoEntry) => false,
+            Err(e) => return Err(store_error(e)),
+        };
+        let mut keys = self.index()?;
+        if index_remove(&mut keys, key) {
+            self.write_index(&keys)?;
+        }
+        Ok(removed)
+    }
+
+    fn keys(&self) -> AppResult<Vec<String>> {
+        self.index()
+    }
+}
+
+/// Parses the index item, treating a damaged index as empty so the store
+/// stays usable; entries remain readable by key.
+fn parse_index(index: &str) -> Vec<String> {
+    serde_json::from_str(index).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable keychain index: {}", e);
+        Vec::new()
+    })
+}
+
+/// Adds `key` to a sorted index. Returns whether it was missing.
+fn index_insert(keys: &mut Vec<String>, key: &str) -> bool {
+    match keys.binary_search_by(|existing| existing.as_str().cmp(key)) {
+        Ok(_) => false,
+        Err(position) => {
+            keys.insert(position, key.to_string());
+            true
+        }
+    }
+}
+
+/// Removes `key` from a sorted index. Returns whether it was present.
+fn index_remove(keys: &mut Vec<String>, key: &str) -> bool {
+    match keys.binary_search_by(|existing| existing.as_str().cmp(key)) {
+        Ok(position) => {
+            keys.remove(position);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn store_error(e: impl fmt::Display) -> AppError {
+    AppError::new(
+        ErrorCode::FileWrite,
+        format!("Credential store error: {}", e),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_keychain_index_sorted_and_unique() {
+        let mut keys = Vec::new();
+        assert!(index_insert(&mut keys, "totp:b"));
+        assert!(index_insert(&mut keys, "apiToken:a"));
+        assert!(!index_insert(&mut keys, "totp:b"));
+        assert_eq!(keys, ["apiToken:a", "totp:b"]);
+
+        assert!(index_remove(&mut keys, "apiToken:a"));
+        assert!(!index_remove(&mut keys, "apiToken:a"));
+        assert_eq!(keys, ["totp:b"]);
+    }
+
+    #[test]
+    fn damaged_indexes_read_as_empty() {
+        assert_eq!(parse_index(r#"["totp:a"]"#), ["totp:a"]);
+        assert!(parse_index("not json").is_empty());
+    }
+}
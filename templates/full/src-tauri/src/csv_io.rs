@@ -0,0 +1,260 @@
+//! CSV reading and writing for files in the filesystem scope.
+//!
+//! Parsing streams records from disk and returns one page at a time, so a
+//! large file never has to be held in memory or parsed in JavaScript. Writes
+//! can append, letting callers export large data sets in chunks.
+
+use crate::errors::{AppError, AppResult};
+use crate::handlers::filesystem::{
+    ensure_write_permitted, resolve_existing_path, resolve_relative_path,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter};
+
+/// Rows returned when the caller does not set a limit.
+const DEFAULT_PAGE_SIZE: usize = 1_000;
+
+/// Most rows returned by a single parse call.
+const MAX_PAGE_SIZE: usize = 10_000;
+
+/// Options for [`parse`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvParseOptions {
+    /// Field separator, `,` by default.
+    pub delimiter: Option<char>,
+    /// Whether the first record holds column names, `true` by default.
+    pub has_headers: Option<bool>,
+    /// Data rows to skip before the page starts.
+    pub offset: Option<usize>,
+    /// Rows to return, capped at 10 000.
+    pub limit: Option<usize>,
+    /// Trim whitespace around fields.
+    #[serde(default)]
+    pub trim: bool,
+}
+
+/// One page of parsed rows.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvPage {
+    pub headers: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+    pub offset: usize,
+    /// Offset of the next page, or `None` when the file is exhausted.
+    pub next_offset: Option<usize>,
+}
+
+/// Options for [`write`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvWriteOptions {
+    /// Column names written as the first record and used to order object rows.
+    pub headers: Option<Vec<String>>,
+    /// Field separator, `,` by default.
+    pub delimiter: Option<char>,
+    /// Append to an existing file instead of replacing it. Headers are only
+    /// written when the file is new or empty.
+    #[serde(default)]
+    pub append: bool,
+}
+
+/// Result of a write.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvWriteSummary {
+    pub path: String,
+    pub rows_written: usize,
+}
+
+/// Reads one page of rows from the CSV file at `path`.
+pub fn parse(path: &str, options: CsvParseOptions) -> AppResult<CsvPage> {
+    let context = resolve_existing_path(path).map_err(|e| AppError::file_error("read", path, e))?;
+    let file = File::open(&context.path)
+        .map_err(|e| AppError::file_error("read", context.relative_display(), e.to_string()))?;
+
+    let offset = options.offset.unwrap_or(0);
+    let limit = options
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let has_headers = options.has_headers.unwrap_or(true);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter_byte(options.delimiter)?)
+        .has_headers(has_headers)
+        .flexible(true)
+        .trim(if options.trim {
+            csv::Trim::All
+        } else {
+            csv::Trim::None
+        })
+        .from_reader(BufReader::new(file));
+
+    let headers = if has_headers {
+        Some(
+            reader
+                .headers()
+                .map_err(|e| invalid_csv(&context.relative_display(), e))?
+                .iter()
+                .map(str::to_string)
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let mut rows = Vec::new();
+    let mut next_offset = None;
+    for (index, record) in reader.records().enumerate().skip(offset) {
+        let record = record.map_err(|e| invalid_csv(&context.relative_display(), e))?;
+        if rows.len() == limit {
+            next_offset = Some(index);
+            break;
+        }
+        rows.push(record.iter().map(str::to_string).collect());
+    }
+
+    Ok(CsvPage {
+        headers,
+        rows,
+        offset,
+        next_offset,
+    })
+}
+
+/// Writes `rows` to the CSV file at `path`.
+///
+/// Each row is either an array of cells or an object keyed by column name.
+/// Object rows need `headers`, or take them from the first row's keys.
+/// Strings are written as-is, `null` as an empty field, and other values as
+/// JSON.
+pub fn write(
+    path: &str,
+    rows: Vec<serde_json::Value>,
+    options: CsvWriteOptions,
+) -> AppResult<CsvWriteSummary> {
+    let context =
+        resolve_relative_path(path).map_err(|e| AppError::file_error("write", path, e))?;
+    ensure_write_permitted(&context.path)?;
+    let display = context.relative_display();
+    if let Some(parent) = context.path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::file_error("create", display.clone(), e.to_string()))?;
+    }
+
+    let headers = options.headers.or_else(|| match rows.first() {
+        Some(serde_json::Value::Object(first)) => Some(first.keys().cloned().collect()),
+        _ => None,
+    });
+    let records = rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| to_record(index, row, headers.as_deref()))
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let existing = fs::metadata(&context.path).map(|m| m.len()).unwrap_or(0);
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(options.append)
+        .truncate(!options.append)
+        .open(&context.path)
+        .map_err(|e| AppError::file_error("write", display.clone(), e.to_string()))?;
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter_byte(options.delimiter)?)
+        .flexible(true)
+        .from_writer(BufWriter::new(file));
+
+    let write_error = |e: csv::Error| AppError::file_error("write", display.clone(), e.to_string());
+    if let Some(headers) = headers.filter(|_| !options.append || existing == 0) {
+        writer.write_record(&headers).map_err(write_error)?;
+    }
+    for record in &records {
+        writer.write_record(record).map_err(write_error)?;
+    }
+    writer
+        .flush()
+        .map_err(|e| AppError::file_error("write", display.clone(), e.to_string()))?;
+
+    tracing::debug!("Wrote {} CSV rows to {}", records.len(), display);
+    Ok(CsvWriteSummary {
+        path: display,
+        rows_written: records.len(),
+    })
+}
+
+fn to_record(
+    index: usize,
+    row: &serde_json::Value,
+    headers: Option<&[String]>,
+) -> AppResult<Vec<String>> {
+    match row {
+        serde_json::Value::Array(cells) => Ok(cells.iter().map(cell_text).collect()),
+        serde_json::Value::Object(fields) => {
+            let headers = headers.ok_or_else(|| {
+                AppError::invalid_input("headers", "Headers are required for object rows")
+            })?;
+            Ok(headers
+                .iter()
+                .map(|header| fields.get(header).map(cell_text).unwrap_or_default())
+                .collect())
+        }
+        _ => Err(AppError::invalid_input(
+            "rows",
+            format!("Row {} must be an array or an object", index),
+        )),
+    }
+}
+
+fn cell_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn delimiter_byte(delimiter: Option<char>) -> AppResult<u8> {
+    match delimiter.unwrap_or(',') {
+        c if c.is_ascii() && !matches!(c, '"' | '\r' | '\n') => Ok(c as u8),
+        _ => Err(AppError::invalid_input(
+            "delimiter",
+            "Delimiter must be a single ASCII character other than a quote or newline",
+        )),
+    }
+}
+
+fn invalid_csv(path: &str, e: csv::Error) -> AppError {
+    AppError::file_error("read", path, format!("Invalid CSV: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn converts_rows_to_records() {
+        let headers = vec!["name".to_string(), "age".to_string()];
+        assert_eq!(
+            to_record(0, &json!(["a", 1, null, true]), None).unwrap(),
+            vec!["a", "1", "", "true"]
+        );
+        assert_eq!(
+            to_record(0, &json!({ "age": 3, "name": "b" }), Some(&headers)).unwrap(),
+            vec!["b", "3"]
+        );
+        assert!(to_record(0, &json!({ "name": "b" }), None).is_err());
+        assert!(to_record(0, &json!("plain"), Some(&headers)).is_err());
+    }
+
+    #[test]
+    fn validates_delimiters() {
+        assert_eq!(delimiter_byte(None).unwrap(), b',');
+        assert_eq!(delimiter_byte(Some(';')).unwrap(), b';');
+        assert_eq!(delimiter_byte(Some('\t')).unwrap(), b'\t');
+        assert!(delimiter_byte(Some('"')).is_err());
+        assert!(delimiter_byte(Some('é')).is_err());
+    }
+}
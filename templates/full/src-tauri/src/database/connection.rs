@@ -4,8 +4,7 @@ use anyhow::Result;
 use once_cell::sync::OnceCell;
 use sqlx::PgPool;
 use std::sync::{Arc, RwLock};
-use crate::stronghold::StrongholdManager;
-use crate::config::AppConfig;
+use crate::config;
 
 /// Global connection pool storage using OnceCell for thread-safe initialization.
 static POOL: OnceCell<RwLock<Option<Arc<PgPool>>>> = OnceCell::new();
@@ -15,11 +14,28 @@ fn pool_slot() -> &'static RwLock<Option<Arc<PgPool>>> {
     POOL.get_or_init(|| RwLock::new(None))
 }
 
-/// Initializes the database connection using Stronghold for secure credential storage.
-/// Currently uses direct config access as a fallback.
-pub async fn initialize_database(_stronghold: &mut StrongholdManager) -> Result<()> {
-    let config = AppConfig::from_env();
-    let db_url = config.database_url.clone();
+/// Initializes the database connection from configuration.
+pub async fn initialize_database() -> Result<()> {
+    initialize_database_with_password(None).await
+}
+
+/// Initializes the database connection, optionally with a password read from
+/// the Stronghold vault.
+///
+/// The password is only applied to connections configured through discrete
+/// `DB_*` settings; a full `DATABASE_URL` already carries its credentials.
+/// Configuration is reloaded first so changed connection settings take effect.
+pub async fn initialize_database_with_password(password: Option<&str>) -> Result<()> {
+    let config = config::reload();
+    let db_url = match (password, &config.database_parts) {
+        (Some(password), Some(parts)) => parts.connection_string(Some(password)),
+        (Some(_), None) => {
+            return Err(anyhow::anyhow!(
+                "A vault password requires DB_HOST based configuration instead of DATABASE_URL"
+            ))
+        }
+        (None, _) => config.database_url.clone(),
+    };
 
     let pool = super::create_pool_with_url(&db_url).await?;
     super::test_connection(&pool).await?;
@@ -50,8 +66,27 @@ pub fn get_pool() -> Option<Arc<PgPool>> {
 }
 
 /// Returns the database connection pool or an error if not initialized.
+///
+/// When `DATABASE_URL` selects SQLite the pool is never created, and the
+/// error says the feature needs PostgreSQL.
 pub fn get_pool_ref() -> Result<Arc<PgPool>> {
-    get_pool().ok_or_else(|| anyhow::anyhow!("Database pool not initialized"))
+    get_pool().ok_or_else(|| {
+        if config::current().database_backend == config::DatabaseBackend::Sqlite {
+            anyhow::anyhow!(
+                "Not available with the embedded SQLite database; this needs PostgreSQL"
+            )
+        } else {
+            anyhow::anyhow!("Database pool not initialized")
+        }
+    })
+}
+
+/// Removes the global pool and waits for its connections to close.
+pub async fn close_pool() {
+    let pool = pool_slot().write().ok().and_then(|mut guard| guard.take());
+    if let Some(pool) = pool {
+        pool.close().await;
+    }
 }
 
 /// Resets the connection pool for testing purposes.
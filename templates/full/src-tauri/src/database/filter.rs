@@ -0,0 +1,263 @@
+//! Builder for `SELECT` queries with optional filters.
+//!
+//! [`FilterBuilder`] joins conditions with `WHERE` and `AND`, and adds
+//! ranges, text search, sorting, and paging. Every value is bound as a query
+//! parameter; SQL text only comes from `&'static str` fragments and
+//! [`SortColumn`] whitelists, so filters built from user input cannot inject
+//! SQL.
+
+use crate::models::SortDirection;
+use sqlx::{Encode, Postgres, QueryBuilder, Type};
+
+/// A sort key that maps to a fixed column or expression.
+pub trait SortColumn {
+    fn column(&self) -> &'static str;
+}
+
+impl SortColumn for &'static str {
+    fn column(&self) -> &'static str {
+        self
+    }
+}
+
+/// A `SELECT` query under construction.
+pub struct FilterBuilder<'args> {
+    builder: QueryBuilder<'args, Postgres>,
+    has_condition: bool,
+}
+
+impl<'args> FilterBuilder<'args> {
+    /// Starts from `select`, a query without a `WHERE` clause.
+    pub fn new(select: &'static str) -> Self {
+        Self {
+            builder: QueryBuilder::new(select),
+            has_condition: false,
+        }
+    }
+
+    /// Adds a condition without parameters, e.g. `read_at IS NULL`.
+    pub fn condition(&mut self, sql: &'static str) -> &mut Self {
+        self.next_condition().push(sql);
+        self
+    }
+
+    /// Adds `{column} {operator} value`, e.g. `created_at >= $1`.
+    pub fn compare<T>(
+        &mut self,
+        column: &'static str,
+        operator: &'static str,
+        value: T,
+    ) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        self.next_condition()
+            .push(column)
+            .push(" ")
+            .push(operator)
+            .push(" ")
+            .push_bind(value);
+        self
+    }
+
+    /// Adds `{column} = value`.
+    pub fn eq<T>(&mut self, column: &'static str, value: T) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        self.compare(column, "=", value)
+    }
+
+    /// Adds `{column} = value` when `value` is set.
+    pub fn eq_opt<T>(&mut self, column: &'static str, value: Option<T>) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        if let Some(value) = value {
+            self.eq(column, value);
+        }
+        self
+    }
+
+    /// Adds the half-open range `from <= {column} < to`; either bound may be
+    /// absent.
+    pub fn range<T>(&mut self, column: &'static str, from: Option<T>, to: Option<T>) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        if let Some(from) = from {
+            self.compare(column, ">=", from);
+        }
+        if let Some(to) = to {
+            self.compare(column, "<", to);
+        }
+        self
+    }
+
+    /// Adds a condition around one value, e.g. a subquery opened by
+    /// `prefix` and closed by `suffix`.
+    pub fn wrap<T>(&mut self, prefix: &'static str, value: T, suffix: &'static str) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        self.next_condition()
+            .push(prefix)
+            .push_bind(value)
+            .push(suffix);
+        self
+    }
+
+    /// Matches `text` literally and case-insensitively anywhere in any of
+    /// `expressions`.
+    pub fn search(&mut self, expressions: &[&'static str], text: &str) -> &mut Self {
+        if expressions.is_empty() {
+            return self;
+        }
+
+        let pattern = format!("%{}%", escape_like(text));
+        let builder = self.next_condition();
+        builder.push("(");
+        for (index, expression) in expressions.iter().enumerate() {
+            if index > 0 {
+                builder.push(" OR ");
+            }
+            builder
+                .push(*expression)
+                .push(" ILIKE ")
+                .push_bind(pattern.clone());
+        }
+        builder.push(")");
+        self
+    }
+
+    /// Orders by `key`, then by `tiebreaker` for a stable order across
+    /// pages. Nulls sort last in either direction.
+    pub fn order_by(
+        &mut self,
+        key: &impl SortColumn,
+        direction: SortDirection,
+        tiebreaker: Option<&'static str>,
+    ) -> &mut Self {
+        let direction = match direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        self.builder
+            .push(" ORDER BY ")
+            .push(key.column())
+            .push(" ")
+            .push(direction)
+            .push(" NULLS LAST");
+        if let Some(tiebreaker) = tiebreaker {
+            self.builder.push(", ").push(tiebreaker);
+        }
+        self
+    }
+
+    /// Adds `LIMIT` and `OFFSET`.
+    pub fn paginate(&mut self, limit: i64, offset: i64) -> &mut Self {
+        self.builder
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        self
+    }
+
+    /// The SQL built so far.
+    pub fn sql(&self) -> &str {
+        self.builder.sql()
+    }
+
+    /// Returns the underlying builder for building the query.
+    pub fn into_builder(self) -> QueryBuilder<'args, Postgres> {
+        self.builder
+    }
+
+    fn next_condition(&mut self) -> &mut QueryBuilder<'args, Postgres> {
+        self.builder.push(if self.has_condition {
+            " AND "
+        } else {
+            " WHERE "
+        });
+        self.has_condition = true;
+        &mut self.builder
+    }
+}
+
+/// Escapes `LIKE` wildcards so `text` matches literally.
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Field {
+        Name,
+    }
+
+    impl SortColumn for Field {
+        fn column(&self) -> &'static str {
+            match self {
+                Field::Name => "name",
+            }
+        }
+    }
+
+    #[test]
+    fn joins_conditions_with_where_and_and() {
+        let mut filter = FilterBuilder::new("SELECT * FROM items");
+        filter
+            .eq_opt("kind", Some("book"))
+            .eq_opt::<i32>("owner", None)
+            .range("price", Some(5), Some(10))
+            .condition("deleted_at IS NULL");
+        assert_eq!(
+            filter.sql(),
+            "SELECT * FROM items WHERE kind = $1 AND price >= $2 AND price < $3 \
+             AND deleted_at IS NULL"
+        );
+    }
+
+    #[test]
+    fn leaves_out_the_where_clause_without_conditions() {
+        let mut filter = FilterBuilder::new("SELECT * FROM items");
+        filter
+            .range::<i32>("price", None, None)
+            .order_by(&"created_at", SortDirection::Desc, None)
+            .paginate(10, 0);
+        assert_eq!(
+            filter.sql(),
+            "SELECT * FROM items ORDER BY created_at DESC NULLS LAST LIMIT $1 OFFSET $2"
+        );
+    }
+
+    #[test]
+    fn binds_search_text_and_wrapped_values() {
+        let mut filter = FilterBuilder::new("SELECT * FROM items");
+        filter
+            .search(&["name", "description"], "50% off")
+            .wrap("id IN (SELECT item_id FROM tags WHERE tag = ", "sale", ")")
+            .order_by(&Field::Name, SortDirection::Asc, Some("id"));
+        assert_eq!(
+            filter.sql(),
+            "SELECT * FROM items WHERE (name ILIKE $1 OR description ILIKE $2) \
+             AND id IN (SELECT item_id FROM tags WHERE tag = $3) ORDER BY name ASC NULLS LAST, id"
+        );
+    }
+
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(escape_like("50%_off\\"), "50\\%\\_off\\\\");
+        assert_eq!(escape_like("ada"), "ada");
+    }
+}
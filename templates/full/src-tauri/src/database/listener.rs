@@ -0,0 +1,96 @@
+//! Bridge from PostgreSQL `LISTEN/NOTIFY` to frontend events.
+//!
+//! Notifications on the configured channels are re-published as
+//! `db:notification` events, so changes made by another instance or a server
+//! job update the UI live. JSON payloads are forwarded as objects; anything
+//! else is forwarded as a string.
+
+use crate::database::get_pool_ref;
+use crate::events::{self, AppEvent};
+use crate::shutdown;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+
+/// Delay before reconnecting after the listener fails.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Maximum length of a PostgreSQL identifier.
+const MAX_CHANNEL_LEN: usize = 63;
+
+/// Returns whether `channel` is a plain, unquoted PostgreSQL identifier.
+pub fn is_valid_channel(channel: &str) -> bool {
+    let mut chars = channel.chars();
+    let starts_ok = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+
+    starts_ok
+        && channel.len() <= MAX_CHANNEL_LEN
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Starts listening on `channels` in the background.
+pub fn start_listener(channels: Vec<String>) {
+    let channels: Vec<String> = channels
+        .into_iter()
+        .filter(|channel| {
+            let valid = is_valid_channel(channel);
+            if !valid {
+                tracing::warn!("Ignoring invalid NOTIFY channel '{}'", channel);
+            }
+            valid
+        })
+        .collect();
+
+    if channels.is_empty() {
+        return;
+    }
+
+    let task = tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = listen(&channels).await {
+                tracing::warn!("Database notification listener stopped: {}", e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+    shutdown::track("db-notify-listener", task);
+}
+
+async fn listen(channels: &[String]) -> anyhow::Result<()> {
+    let pool = get_pool_ref()?;
+    let mut listener = PgListener::connect_with(pool.as_ref()).await?;
+    listener
+        .listen_all(channels.iter().map(String::as_str))
+        .await?;
+    tracing::info!(
+        "Listening for database notifications on {}",
+        channels.join(", ")
+    );
+
+    loop {
+        let notification = listener.recv().await?;
+        let payload = serde_json::from_str(notification.payload())
+            .unwrap_or_else(|_| serde_json::Value::String(notification.payload().to_string()));
+
+        events::publish(AppEvent::DatabaseNotification {
+            channel: notification.channel().to_string(),
+            payload,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_plain_identifiers() {
+        assert!(is_valid_channel("user_changes"));
+        assert!(is_valid_channel("_jobs2"));
+        assert!(!is_valid_channel("2fast"));
+        assert!(!is_valid_channel("users; DROP TABLE users"));
+        assert!(!is_valid_channel(""));
+        assert!(!is_valid_channel(&"a".repeat(MAX_CHANNEL_LEN + 1)));
+    }
+}
@@ -0,0 +1,228 @@
+//! Postgres advisory locks shared by every app instance using a database.
+//!
+//! Locks are transaction-scoped: each one holds a pooled connection with an
+//! open transaction, and Postgres releases it when that transaction ends,
+//! so a crashed instance or a dropped guard can never leave a lock behind.
+//! Names are hashed into the 64-bit advisory lock key space under an app
+//! prefix to stay clear of locks taken by other software.
+
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use once_cell::sync::Lazy;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Lock held while schema migrations run.
+pub const MIGRATIONS_LOCK: &str = "migrations";
+
+/// Lock held while a backup is created or restored.
+pub const BACKUP_LOCK: &str = "backup";
+
+/// Prefix hashed together with lock names.
+const KEY_PREFIX: &str = "ez-tauri:";
+
+/// Longest accepted lock name.
+const MAX_NAME_LEN: usize = 64;
+
+/// Delay between attempts while waiting for a busy lock.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Locks taken through the commands, held until released by name.
+static HELD: Lazy<Mutex<HashMap<String, AdvisoryLock>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A held advisory lock. Dropping it rolls back its transaction, which
+/// releases the lock as well.
+pub struct AdvisoryLock {
+    tx: Transaction<'static, Postgres>,
+    name: String,
+}
+
+impl AdvisoryLock {
+    /// Takes the lock `name`, retrying for up to `wait` while another
+    /// session holds it. Without `wait` a busy lock fails immediately.
+    pub async fn acquire(pool: &PgPool, name: &str, wait: Option<Duration>) -> AppResult<Self> {
+        Self::try_acquire(pool, name, wait).await?.ok_or_else(|| {
+            AppError::new(
+                ErrorCode::DatabaseTimeout,
+                format!("Another instance holds the '{}' lock", name),
+            )
+        })
+    }
+
+    /// Like [`AdvisoryLock::acquire`], but returns `None` when the lock is
+    /// still busy once `wait` has passed.
+    pub async fn try_acquire(
+        pool: &PgPool,
+        name: &str,
+        wait: Option<Duration>,
+    ) -> AppResult<Option<Self>> {
+        validate_name(name)?;
+        let deadline = tokio::time::Instant::now() + wait.unwrap_or_default();
+        let mut tx = pool
+            .begin()
+            .await
+            .into_app_error(ErrorCode::DatabaseConnection)?;
+
+        loop {
+            let acquired: bool =
+                sqlx::query_scalar("SELECT pg_try_advisory_xact_lock(hashtextextended($1, 0))")
+                    .bind(lock_key(name))
+                    .fetch_one(&mut *tx)
+                    .await
+                    .into_app_error(ErrorCode::DatabaseQuery)?;
+            if acquired {
+                tracing::debug!("Acquired advisory lock '{}'", name);
+                return Ok(Some(Self {
+                    tx,
+                    name: name.to_string(),
+                }));
+            }
+            if tokio::time::Instant::now() + POLL_INTERVAL > deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Name the lock was taken under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Releases the lock by ending its transaction.
+    pub async fn release(self) -> AppResult<()> {
+        self.tx
+            .commit()
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+        tracing::debug!("Released advisory lock '{}'", self.name);
+        Ok(())
+    }
+}
+
+/// Runs `operation` while holding the lock `name`, so it cannot run
+/// concurrently in another instance. The lock is released afterwards even
+/// when the operation fails.
+pub async fn with_advisory_lock<T, F, Fut>(
+    pool: &PgPool,
+    name: &str,
+    wait: Option<Duration>,
+    operation: F,
+) -> AppResult<T>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = AppResult<T>>,
+{
+    let lock = AdvisoryLock::acquire(pool, name, wait).await?;
+    let result = operation().await;
+    if let Err(e) = lock.release().await {
+        tracing::warn!("Failed to release advisory lock '{}': {}", name, e);
+    }
+    result
+}
+
+/// Takes the lock `name` on behalf of the frontend and keeps it until
+/// [`release_held`]. Returns `false` when another session holds it; taking
+/// a lock this instance already holds succeeds.
+pub async fn hold(pool: &PgPool, name: &str, wait: Option<Duration>) -> AppResult<bool> {
+    let mut held = HELD.lock().await;
+    if held.contains_key(name) {
+        return Ok(true);
+    }
+    match AdvisoryLock::try_acquire(pool, name, wait).await? {
+        Some(lock) => {
+            held.insert(name.to_string(), lock);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Releases a lock taken with [`hold`]; returns whether it was held.
+pub async fn release_held(name: &str) -> AppResult<bool> {
+    let Some(lock) = HELD.lock().await.remove(name) else {
+        return Ok(false);
+    };
+    lock.release().await?;
+    Ok(true)
+}
+
+fn validate_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::invalid_input(
+            "name",
+            "Lock names must be 1-64 characters of letters, digits, '-', '_', '.' or ':'",
+        ))
+    }
+}
+
+fn lock_key(name: &str) -> String {
+    format!("{}{}", KEY_PREFIX, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::pool;
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    #[test]
+    fn validates_lock_names() {
+        assert!(validate_name(MIGRATIONS_LOCK).is_ok());
+        assert!(validate_name("sync:workspace-1").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("has space").is_err());
+        assert!(validate_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+        assert_eq!(lock_key(BACKUP_LOCK), "ez-tauri:backup");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn lock_excludes_other_sessions_until_released() -> AnyResult<()> {
+        let pool = pool().await?;
+
+        let lock = AdvisoryLock::acquire(pool.as_ref(), "test-lock", None).await?;
+        assert_eq!(lock.name(), "test-lock");
+        assert!(AdvisoryLock::try_acquire(pool.as_ref(), "test-lock", None)
+            .await?
+            .is_none());
+        assert!(AdvisoryLock::acquire(pool.as_ref(), "test-lock", None)
+            .await
+            .is_err());
+
+        lock.release().await?;
+        let value =
+            with_advisory_lock(pool.as_ref(), "test-lock", None, || async { Ok(7) }).await?;
+        assert_eq!(value, 7);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn held_locks_are_released_by_name() -> AnyResult<()> {
+        let pool = pool().await?;
+
+        assert!(hold(pool.as_ref(), "held-lock", None).await?);
+        assert!(hold(pool.as_ref(), "held-lock", None).await?);
+        assert!(AdvisoryLock::try_acquire(pool.as_ref(), "held-lock", None)
+            .await?
+            .is_none());
+
+        assert!(release_held("held-lock").await?);
+        assert!(!release_held("held-lock").await?);
+        assert!(AdvisoryLock::try_acquire(pool.as_ref(), "held-lock", None)
+            .await?
+            .is_some());
+        Ok(())
+    }
+}
@@ -1,16 +1,37 @@
 //! Database migration management for creating and maintaining schema.
 
+use super::locks::{with_advisory_lock, MIGRATIONS_LOCK};
+use crate::errors::{AppResult, ErrorCode, IntoAppError};
+use crate::operations;
 use anyhow::Result;
 use sqlx::PgPool;
+use std::time::Duration;
+
+/// How long to wait for another instance to finish migrating.
+const MIGRATIONS_LOCK_WAIT: Duration = Duration::from_secs(120);
 
 /// Runs all database migrations to set up the application schema.
 ///
-/// Creates tables for users, user settings, and application logs along with
-/// necessary indexes for performance. In production, consider using sqlx-cli
-/// for more sophisticated migration management.
+/// Creates tables for users, user settings, application logs, workspaces,
+/// groups, notifications, sync bookkeeping, password history, login
+/// sessions, email verifications, OAuth identities, and the audit log along with necessary indexes for performance. Application logs are partitioned
+/// by month; see [`crate::logging::partitions`]. In production, consider using sqlx-cli for more
+/// sophisticated migration management.
+///
+/// Migrations hold the [`MIGRATIONS_LOCK`] advisory lock, so instances
+/// sharing a database apply them one at a time.
 pub async fn run_migrations(pool: &PgPool) -> Result<()> {
+    with_advisory_lock(pool, MIGRATIONS_LOCK, Some(MIGRATIONS_LOCK_WAIT), || {
+        apply_migrations(pool)
+    })
+    .await?;
+    Ok(())
+}
+
+async fn apply_migrations(pool: &PgPool) -> AppResult<()> {
     let migrations = [
         r#"CREATE EXTENSION IF NOT EXISTS "uuid-ossp""#,
+        r#"CREATE EXTENSION IF NOT EXISTS pg_trgm"#,
 
         r#"CREATE TABLE IF NOT EXISTS users (
             id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
@@ -23,6 +44,10 @@ pub async fn run_migrations(pool: &PgPool) -> Result<()> {
             created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
             updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
         )"#,
+        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS email_verified BOOLEAN NOT NULL DEFAULT false"#,
+        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS roles TEXT[] NOT NULL DEFAULT '{}'"#,
+        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS is_guest BOOLEAN NOT NULL DEFAULT false"#,
+        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_scheduled_at TIMESTAMP WITH TIME ZONE"#,
 
         r#"CREATE TABLE IF NOT EXISTS user_settings (
             id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
@@ -36,26 +61,228 @@ pub async fn run_migrations(pool: &PgPool) -> Result<()> {
             UNIQUE(user_id)
         )"#,
 
+        // Installs created before partitioning keep their rows in a plain
+        // table, which is set aside here and copied into the partitioned one
+        // once the workspace column and partition function exist.
+        r#"DO $$
+        BEGIN
+            IF EXISTS (SELECT 1 FROM pg_class WHERE oid = to_regclass('app_logs') AND relkind = 'r') THEN
+                ALTER TABLE app_logs RENAME TO app_logs_unpartitioned;
+                ALTER TABLE app_logs_unpartitioned RENAME CONSTRAINT app_logs_pkey TO app_logs_unpartitioned_pkey;
+                ALTER TABLE app_logs_unpartitioned ADD COLUMN IF NOT EXISTS workspace_id UUID;
+            END IF;
+        END $$"#,
+
         r#"CREATE TABLE IF NOT EXISTS app_logs (
-            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            id UUID NOT NULL DEFAULT uuid_generate_v4(),
             level VARCHAR(20) NOT NULL,
             message TEXT NOT NULL,
             metadata JSONB DEFAULT '{}',
             user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (id, created_at)
+        ) PARTITION BY RANGE (created_at)"#,
+
+        r#"CREATE TABLE IF NOT EXISTS app_logs_default PARTITION OF app_logs DEFAULT"#,
+
+        r#"CREATE TABLE IF NOT EXISTS workspaces (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            name VARCHAR(100) NOT NULL,
+            slug VARCHAR(100) UNIQUE NOT NULL,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS workspace_members (
+            workspace_id UUID NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            role VARCHAR(20) NOT NULL DEFAULT 'member',
+            joined_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (workspace_id, user_id)
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS groups (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            name VARCHAR(100) NOT NULL,
+            description TEXT,
+            workspace_id UUID REFERENCES workspaces(id) ON DELETE CASCADE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS group_memberships (
+            group_id UUID NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            role VARCHAR(20) NOT NULL DEFAULT 'member',
+            joined_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (group_id, user_id)
+        )"#,
+
+        r#"ALTER TABLE app_logs ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces(id) ON DELETE CASCADE"#,
+        r#"ALTER TABLE app_logs ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}'"#,
+
+        // Creates the monthly partition `app_logs_pYYYYMM` holding `month`
+        // (in UTC), moving any of its rows out of the default partition.
+        // Returns false when the partition already exists.
+        r#"CREATE OR REPLACE FUNCTION app_logs_ensure_partition(month DATE) RETURNS BOOLEAN AS $$
+        DECLARE
+            partition_name TEXT := 'app_logs_p' || to_char(month, 'YYYYMM');
+            start_at TIMESTAMPTZ := date_trunc('month', month::timestamp) AT TIME ZONE 'UTC';
+            end_at TIMESTAMPTZ := (date_trunc('month', month::timestamp) + INTERVAL '1 month') AT TIME ZONE 'UTC';
+        BEGIN
+            IF to_regclass(partition_name) IS NOT NULL THEN
+                RETURN FALSE;
+            END IF;
+            EXECUTE format('CREATE TABLE %I (LIKE app_logs INCLUDING DEFAULTS INCLUDING CONSTRAINTS)', partition_name);
+            EXECUTE format(
+                'WITH moved AS (DELETE FROM app_logs_default WHERE created_at >= $1 AND created_at < $2 RETURNING *)
+                 INSERT INTO %I SELECT * FROM moved',
+                partition_name
+            ) USING start_at, end_at;
+            EXECUTE format(
+                'ALTER TABLE app_logs ATTACH PARTITION %I FOR VALUES FROM (%L) TO (%L)',
+                partition_name, start_at, end_at
+            );
+            RETURN TRUE;
+        END;
+        $$ LANGUAGE plpgsql"#,
+
+        r#"DO $$
+        DECLARE
+            month DATE;
+        BEGIN
+            IF to_regclass('app_logs_unpartitioned') IS NULL THEN
+                RETURN;
+            END IF;
+            FOR month IN
+                SELECT DISTINCT date_trunc('month', created_at AT TIME ZONE 'UTC')::date
+                FROM app_logs_unpartitioned
+                WHERE created_at IS NOT NULL
+            LOOP
+                PERFORM app_logs_ensure_partition(month);
+            END LOOP;
+            INSERT INTO app_logs (id, level, message, metadata, user_id, workspace_id, created_at)
+            SELECT id, level, message, metadata, user_id, workspace_id, COALESCE(created_at, CURRENT_TIMESTAMP)
+            FROM app_logs_unpartitioned;
+            DROP TABLE app_logs_unpartitioned;
+        END $$"#,
+
+        r#"CREATE TABLE IF NOT EXISTS notifications (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+            title VARCHAR(255) NOT NULL DEFAULT '',
+            body TEXT NOT NULL DEFAULT '',
+            payload JSONB DEFAULT '{}',
+            read_at TIMESTAMP WITH TIME ZONE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS sync_changes (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            table_name VARCHAR(100) NOT NULL,
+            record_id UUID NOT NULL,
+            operation TEXT NOT NULL,
+            payload JSONB,
+            changed_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+            synced_at TIMESTAMP WITH TIME ZONE
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS sync_state (
+            key VARCHAR(100) PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS audit_log (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            action VARCHAR(100) NOT NULL,
+            user_id UUID,
+            details JSONB DEFAULT '{}',
             created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
         )"#,
+        r#"ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS actor_id UUID"#,
+
+        r#"CREATE TABLE IF NOT EXISTS password_history (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            password_hash VARCHAR(255) NOT NULL,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS user_sessions (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            refresh_token_hash VARCHAR(64) UNIQUE NOT NULL,
+            created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            last_active TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+        )"#,
+        r#"ALTER TABLE user_sessions ADD COLUMN IF NOT EXISTS impersonator_id UUID REFERENCES users(id) ON DELETE CASCADE"#,
+        r#"ALTER TABLE user_sessions ADD COLUMN IF NOT EXISTS remember_me BOOLEAN NOT NULL DEFAULT FALSE"#,
+
+        r#"CREATE TABLE IF NOT EXISTS retired_refresh_tokens (
+            token_hash VARCHAR(64) PRIMARY KEY,
+            session_id UUID NOT NULL REFERENCES user_sessions(id) ON DELETE CASCADE,
+            retired_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS email_verifications (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            email VARCHAR(255) NOT NULL,
+            token_hash VARCHAR(64) UNIQUE NOT NULL,
+            created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+        )"#,
+
+        r#"CREATE TABLE IF NOT EXISTS oauth_identities (
+            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            provider VARCHAR(50) NOT NULL,
+            subject VARCHAR(255) NOT NULL,
+            created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(provider, subject)
+        )"#,
 
         r#"CREATE INDEX IF NOT EXISTS idx_users_email ON users(email)"#,
         r#"CREATE INDEX IF NOT EXISTS idx_users_username ON users(username)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_users_deletion_scheduled_at ON users(deletion_scheduled_at) WHERE deletion_scheduled_at IS NOT NULL"#,
         r#"CREATE INDEX IF NOT EXISTS idx_users_created_at ON users(created_at)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_users_email_trgm ON users USING gin (email gin_trgm_ops)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_users_username_trgm ON users USING gin (username gin_trgm_ops)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_users_full_name_trgm ON users USING gin ((COALESCE(first_name, '') || ' ' || COALESCE(last_name, '')) gin_trgm_ops)"#,
         r#"CREATE INDEX IF NOT EXISTS idx_user_settings_user_id ON user_settings(user_id)"#,
         r#"CREATE INDEX IF NOT EXISTS idx_app_logs_level ON app_logs(level)"#,
         r#"CREATE INDEX IF NOT EXISTS idx_app_logs_created_at ON app_logs(created_at)"#,
         r#"CREATE INDEX IF NOT EXISTS idx_app_logs_user_id ON app_logs(user_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_app_logs_workspace_id ON app_logs(workspace_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_app_logs_tags ON app_logs USING gin (tags)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications(created_at)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications(user_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_sync_changes_record ON sync_changes(table_name, record_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_sync_changes_synced_at ON sync_changes(synced_at)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_workspace_members_user_id ON workspace_members(user_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_groups_workspace_id ON groups(workspace_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_group_memberships_user_id ON group_memberships(user_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_audit_log_user_id ON audit_log(user_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_audit_log_actor_id ON audit_log(actor_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_password_history_user_id ON password_history(user_id, created_at)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_user_sessions_user_id ON user_sessions(user_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_user_sessions_expires_at ON user_sessions(expires_at)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_retired_refresh_tokens_session_id ON retired_refresh_tokens(session_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_email_verifications_user_id ON email_verifications(user_id)"#,
+        r#"CREATE INDEX IF NOT EXISTS idx_oauth_identities_user_id ON oauth_identities(user_id)"#,
     ];
 
-    for migration in migrations {
-        sqlx::query(migration).execute(pool).await?;
+    let total = migrations.len() as u64;
+    for (index, migration) in migrations.into_iter().enumerate() {
+        operations::check_cancelled()?;
+        sqlx::query(migration)
+            .execute(pool)
+            .await
+            .into_app_error(ErrorCode::DatabaseMigration)?;
+        operations::report(index as u64 + 1, Some(total), None);
     }
 
     Ok(())
@@ -96,7 +323,25 @@ mod tests {
         .map(|row| row.get::<String, _>(0))
         .collect();
 
-        let expected_tables = vec!["app_logs", "user_settings", "users"];
+        let expected_tables = vec![
+            "app_logs",
+            "app_logs_default",
+            "audit_log",
+            "email_verifications",
+            "group_memberships",
+            "groups",
+            "notifications",
+            "oauth_identities",
+            "password_history",
+            "retired_refresh_tokens",
+            "sync_changes",
+            "sync_state",
+            "user_sessions",
+            "user_settings",
+            "users",
+            "workspace_members",
+            "workspaces",
+        ];
         assert_eq!(tables, expected_tables);
 
         Ok(())
@@ -115,10 +360,12 @@ mod tests {
 
         run_migrations(pool.as_ref()).await?;
 
-        // Check that expected indexes exist (filter out auto-created constraint indexes)
+        // Check that expected indexes exist (filter out auto-created constraint
+        // indexes and the copies on the default log partition)
         let indexes: Vec<String> = sqlx::query(
             "SELECT indexname FROM pg_indexes
              WHERE schemaname = 'public'
+               AND tablename <> 'app_logs_default'
                AND indexname NOT LIKE '%_pkey'
                AND indexname NOT LIKE '%_key'
              ORDER BY indexname"
@@ -132,11 +379,31 @@ mod tests {
         let expected_indexes = vec![
             "idx_app_logs_created_at",
             "idx_app_logs_level",
+            "idx_app_logs_tags",
             "idx_app_logs_user_id",
+            "idx_app_logs_workspace_id",
+            "idx_audit_log_actor_id",
+            "idx_audit_log_created_at",
+            "idx_audit_log_user_id",
+            "idx_email_verifications_user_id",
+            "idx_group_memberships_user_id",
+            "idx_groups_workspace_id",
+            "idx_notifications_created_at",
+            "idx_notifications_user_id",
+            "idx_oauth_identities_user_id",
+            "idx_password_history_user_id",
+            "idx_sync_changes_record",
+            "idx_sync_changes_synced_at",
+            "idx_user_sessions_expires_at",
+            "idx_user_sessions_user_id",
             "idx_user_settings_user_id",
             "idx_users_created_at",
             "idx_users_email",
+            "idx_users_email_trgm",
+            "idx_users_full_name_trgm",
             "idx_users_username",
+            "idx_users_username_trgm",
+            "idx_workspace_members_user_id",
         ];
 
         assert_eq!(indexes, expected_indexes);
@@ -169,7 +436,7 @@ mod tests {
         .await?
         .get(0);
 
-        assert_eq!(table_count, 3);
+        assert_eq!(table_count, 17);
 
         Ok(())
     }
@@ -214,6 +481,10 @@ mod tests {
             ("is_active".to_string(), "boolean".to_string(), "YES".to_string()),
             ("created_at".to_string(), "timestamp with time zone".to_string(), "YES".to_string()),
             ("updated_at".to_string(), "timestamp with time zone".to_string(), "YES".to_string()),
+            ("email_verified".to_string(), "boolean".to_string(), "NO".to_string()),
+            ("roles".to_string(), "ARRAY".to_string(), "NO".to_string()),
+            ("is_guest".to_string(), "boolean".to_string(), "NO".to_string()),
+            ("deletion_scheduled_at".to_string(), "timestamp with time zone".to_string(), "YES".to_string()),
         ];
 
         assert_eq!(columns, expected_structure);
@@ -276,6 +547,59 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn unpartitioned_app_logs_are_migrated_into_monthly_partitions() -> AnyResult<()> {
+        let pool = pool().await?;
+        sqlx::query("DROP SCHEMA public CASCADE")
+            .execute(pool.as_ref())
+            .await?;
+        sqlx::query("CREATE SCHEMA public")
+            .execute(pool.as_ref())
+            .await?;
+
+        // The table as created before partitioning
+        sqlx::query(r#"CREATE EXTENSION IF NOT EXISTS "uuid-ossp""#)
+            .execute(pool.as_ref())
+            .await?;
+        sqlx::query(
+            "CREATE TABLE app_logs (
+                id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+                level VARCHAR(20) NOT NULL,
+                message TEXT NOT NULL,
+                metadata JSONB DEFAULT '{}',
+                user_id UUID,
+                created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+            )"
+        )
+        .execute(pool.as_ref())
+        .await?;
+        sqlx::query(
+            "INSERT INTO app_logs (level, message, created_at)
+             VALUES ('info', 'legacy entry', '2024-03-15T12:00:00Z')"
+        )
+        .execute(pool.as_ref())
+        .await?;
+
+        run_migrations(pool.as_ref()).await?;
+
+        let partition: String = sqlx::query(
+            "SELECT tableoid::regclass::text FROM app_logs WHERE message = 'legacy entry'"
+        )
+        .fetch_one(pool.as_ref())
+        .await?
+        .get(0);
+        assert_eq!(partition, "app_logs_p202403");
+
+        let legacy: Option<String> = sqlx::query("SELECT to_regclass('app_logs_unpartitioned')::text")
+            .fetch_one(pool.as_ref())
+            .await?
+            .get(0);
+        assert!(legacy.is_none());
+
+        Ok(())
+    }
+
     #[tokio::test]
     #[serial]
     async fn uuid_extension_is_available() -> AnyResult<()> {
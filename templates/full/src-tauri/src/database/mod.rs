@@ -4,12 +4,19 @@
 //! with environment-aware configuration.
 
 use anyhow::Result;
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions, PgSslMode},
+    PgPool,
+};
 use std::time::Duration;
-use crate::config::AppConfig;
+use crate::config::{self, DatabaseSslMode, DatabaseTls, PoolSettings};
 
 pub mod connection;
+pub mod filter;
+pub mod listener;
+pub mod locks;
 pub mod migrations;
+pub mod query_stats;
 #[cfg(test)]
 pub mod test_utils;
 
@@ -17,29 +24,69 @@ pub use connection::*;
 
 /// Creates a database connection pool using configuration from environment.
 pub async fn create_pool() -> Result<PgPool> {
-    let config = AppConfig::from_env();
+    let config = config::current();
     create_pool_with_url(&config.database_url).await
 }
 
 /// Creates a database connection pool with a specific database URL.
 ///
+/// Pool sizing, timeouts, and the statement cache come from [`PoolSettings`],
+/// which are validated before connecting.
+///
 /// # Arguments
 /// * `database_url` - PostgreSQL connection string
 ///
 /// # Returns
 /// * `Result<PgPool>` - Connection pool or error
 pub async fn create_pool_with_url(database_url: &str) -> Result<PgPool> {
-    let config = AppConfig::from_env();
+    let config = config::current();
+    create_pool_with_settings(database_url, &config.pool, &config.database_tls).await
+}
+
+/// Creates a database connection pool with explicit pool and TLS settings.
+pub async fn create_pool_with_settings(
+    database_url: &str,
+    settings: &PoolSettings,
+    tls: &DatabaseTls,
+) -> Result<PgPool> {
+    settings
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid connection pool settings: {}", e))?;
+    tls.validate()
+        .map_err(|e| anyhow::anyhow!("Invalid database TLS settings: {}", e))?;
+
+    let mut options = database_url
+        .parse::<PgConnectOptions>()?
+        .statement_cache_capacity(settings.statement_cache_size);
+    if let Some(mode) = tls.mode {
+        options = options.ssl_mode(ssl_mode(mode));
+    }
+    if let Some(root_cert) = &tls.root_cert {
+        options = options.ssl_root_cert(root_cert);
+    }
 
     let pool = PgPoolOptions::new()
-        .max_connections(if config.is_production() { 50 } else { 20 })
-        .acquire_timeout(Duration::from_secs(60))
-        .connect(database_url)
+        .max_connections(settings.max_connections)
+        .min_connections(settings.min_connections)
+        .idle_timeout(settings.idle_timeout_seconds.map(Duration::from_secs))
+        .acquire_timeout(Duration::from_secs(settings.acquire_timeout_seconds))
+        .connect_with(options)
         .await?;
 
     Ok(pool)
 }
 
+/// Maps the configured mode onto sqlx's equivalent.
+fn ssl_mode(mode: DatabaseSslMode) -> PgSslMode {
+    match mode {
+        DatabaseSslMode::Disable => PgSslMode::Disable,
+        DatabaseSslMode::Prefer => PgSslMode::Prefer,
+        DatabaseSslMode::Require => PgSslMode::Require,
+        DatabaseSslMode::VerifyCa => PgSslMode::VerifyCa,
+        DatabaseSslMode::VerifyFull => PgSslMode::VerifyFull,
+    }
+}
+
 pub async fn test_connection(pool: &PgPool) -> Result<bool> {
     let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(pool).await?;
 
@@ -0,0 +1,237 @@
+//! Query timing, slow query logging, and per-statement statistics.
+//!
+//! Handlers wrap their sqlx futures in [`timed`]. Statements slower than the
+//! configured threshold are logged with literals redacted, and aggregate
+//! statistics are kept per normalized statement for `get_query_stats`.
+
+use crate::config;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Maximum number of distinct statements tracked.
+const MAX_STATEMENTS: usize = 500;
+
+/// Slow query threshold, read once from the shared configuration.
+static SLOW_THRESHOLD: Lazy<Duration> =
+    Lazy::new(|| Duration::from_millis(config::current().slow_query_threshold_ms));
+
+/// Statistics keyed by redacted statement text.
+static STATS: Lazy<Mutex<HashMap<String, StatementStats>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Aggregated timings for a single statement.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementStats {
+    pub sql: String,
+    pub count: u64,
+    pub errors: u64,
+    pub slow_count: u64,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+impl StatementStats {
+    fn mean_ms(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_ms / self.count as f64
+        }
+    }
+}
+
+/// Summary returned by `get_query_stats`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStats {
+    pub slow_threshold_ms: u64,
+    pub total_queries: u64,
+    pub slow_queries: u64,
+    /// Statements ordered by their slowest execution.
+    pub slowest: Vec<StatementStats>,
+    /// Statements ordered by execution count.
+    pub most_frequent: Vec<StatementStats>,
+}
+
+/// Awaits `query`, recording its duration under `sql`.
+pub async fn timed<T, E, F>(sql: &str, query: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let result = query.await;
+    record(sql, started.elapsed(), result.is_ok());
+    result
+}
+
+/// Records one execution of `sql`.
+pub fn record(sql: &str, elapsed: Duration, success: bool) {
+    let redacted = redact_sql(sql);
+    let slow = elapsed >= *SLOW_THRESHOLD;
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+
+    if slow {
+        tracing::warn!(
+            target: "slow_query",
+            elapsed_ms = elapsed_ms,
+            "Slow query ({:.1} ms): {}",
+            elapsed_ms,
+            redacted
+        );
+    }
+
+    let Ok(mut stats) = STATS.lock() else {
+        return;
+    };
+    if !stats.contains_key(&redacted) && stats.len() >= MAX_STATEMENTS {
+        return;
+    }
+
+    let entry = stats
+        .entry(redacted.clone())
+        .or_insert_with(|| StatementStats {
+            sql: redacted,
+            ..Default::default()
+        });
+    entry.count += 1;
+    entry.total_ms += elapsed_ms;
+    entry.max_ms = entry.max_ms.max(elapsed_ms);
+    if !success {
+        entry.errors += 1;
+    }
+    if slow {
+        entry.slow_count += 1;
+    }
+}
+
+/// Returns the top `limit` statements by worst latency and by frequency.
+pub fn stats(limit: usize) -> QueryStats {
+    let all: Vec<StatementStats> = STATS
+        .lock()
+        .map(|stats| stats.values().cloned().collect())
+        .unwrap_or_default();
+
+    let mut slowest = all.clone();
+    slowest.sort_by(|a, b| b.max_ms.total_cmp(&a.max_ms));
+    slowest.truncate(limit);
+
+    let mut most_frequent = all.clone();
+    most_frequent.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(b.mean_ms().total_cmp(&a.mean_ms()))
+    });
+    most_frequent.truncate(limit);
+
+    QueryStats {
+        slow_threshold_ms: SLOW_THRESHOLD.as_millis() as u64,
+        total_queries: all.iter().map(|s| s.count).sum(),
+        slow_queries: all.iter().map(|s| s.slow_count).sum(),
+        slowest,
+        most_frequent,
+    }
+}
+
+/// Clears all collected statistics.
+pub fn reset() {
+    if let Ok(mut stats) = STATS.lock() {
+        stats.clear();
+    }
+}
+
+/// Collapses whitespace and replaces string and numeric literals with `?`.
+///
+/// Bound parameters (`$1`) are kept, since their values never appear in the SQL.
+fn redact_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // Skip to the closing quote, honouring '' escapes.
+                while let Some(next) = chars.next() {
+                    if next == '\'' {
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                out.push('?');
+                prev = Some('?');
+            }
+            c if c.is_ascii_digit()
+                && !prev.is_some_and(|p| p.is_ascii_alphanumeric() || p == '_' || p == '$') =>
+            {
+                while chars
+                    .peek()
+                    .is_some_and(|n| n.is_ascii_digit() || *n == '.')
+                {
+                    chars.next();
+                }
+                out.push('?');
+                prev = Some('?');
+            }
+            c if c.is_whitespace() => {
+                if prev.is_some_and(|p| p != ' ') {
+                    out.push(' ');
+                    prev = Some(' ');
+                }
+            }
+            c => {
+                out.push(c);
+                prev = Some(c);
+            }
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_literals_but_keeps_placeholders() {
+        let sql = "SELECT id\n   FROM users WHERE email = 'a@b.c' AND age > 42 AND id = $1 AND name = 'O''Brien' LIMIT 10";
+        assert_eq!(
+            redact_sql(sql),
+            "SELECT id FROM users WHERE email = ? AND age > ? AND id = $1 AND name = ? LIMIT ?"
+        );
+        assert_eq!(redact_sql("SELECT * FROM t2"), "SELECT * FROM t2");
+    }
+
+    #[test]
+    fn aggregates_by_redacted_statement() {
+        reset();
+        record(
+            "SELECT 1 FROM stats_test WHERE x = 'a'",
+            Duration::from_millis(5),
+            true,
+        );
+        record(
+            "SELECT 1 FROM stats_test WHERE x = 'b'",
+            Duration::from_millis(15),
+            false,
+        );
+
+        let stats = stats(10);
+        let entry = stats
+            .most_frequent
+            .iter()
+            .find(|s| s.sql == "SELECT ? FROM stats_test WHERE x = ?")
+            .expect("statement should be tracked");
+        assert_eq!(entry.count, 2);
+        assert_eq!(entry.errors, 1);
+        assert!(entry.max_ms >= 15.0);
+    }
+}
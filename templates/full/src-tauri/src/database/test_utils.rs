@@ -89,6 +89,15 @@ pub async fn reset_all_tables(pool: &PgPool) -> Result<()> {
     sqlx::query("TRUNCATE TABLE users RESTART IDENTITY CASCADE")
         .execute(pool)
         .await?;
+    sqlx::query("TRUNCATE TABLE workspaces RESTART IDENTITY CASCADE")
+        .execute(pool)
+        .await?;
+    sqlx::query("TRUNCATE TABLE groups RESTART IDENTITY CASCADE")
+        .execute(pool)
+        .await?;
+    sqlx::query("TRUNCATE TABLE audit_log")
+        .execute(pool)
+        .await?;
 
     Ok(())
 }
@@ -0,0 +1,283 @@
+//! Local network discovery over mDNS / DNS-SD.
+//!
+//! While running, the app advertises itself as an `_eztauri._tcp` service
+//! and browses for other instances on the LAN. Resolved peers are kept in
+//! memory and announced with `discovery:peer-found`; peers that leave are
+//! announced with `discovery:peer-lost`. Nothing is advertised until the
+//! frontend starts discovery, so pairing and local sync stay opt-in. The
+//! [`crate::transfer`] listener runs for as long as discovery does.
+
+use crate::config;
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::events::{self, AppEvent};
+use crate::transfer;
+use chrono::{DateTime, Utc};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use uuid::Uuid;
+
+/// DNS-SD service type shared by all instances.
+pub const SERVICE_TYPE: &str = "_eztauri._tcp.local.";
+
+/// Peers tracked at once; further announcements are ignored.
+const MAX_PEERS: usize = 256;
+
+static SESSION: Lazy<Mutex<Option<Session>>> = Lazy::new(|| Mutex::new(None));
+
+static PEERS: Lazy<Mutex<HashMap<String, Peer>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct Session {
+    daemon: ServiceDaemon,
+    /// Full service name this instance advertises under.
+    fullname: String,
+    transfer_listener: JoinHandle<()>,
+}
+
+/// Another instance found on the local network.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Peer {
+    /// Full DNS-SD service name, unique per running instance.
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    pub addresses: Vec<String>,
+    /// Port of the peer's local server, or 0 when it has none.
+    pub port: u16,
+    /// Port accepting file transfers, when the peer supports them.
+    pub transfer_port: Option<u16>,
+    pub version: Option<String>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Discovery state returned to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveryStatus {
+    pub running: bool,
+    pub service_type: String,
+    /// Name this instance advertises under while running.
+    pub instance: Option<String>,
+    pub peers: Vec<Peer>,
+}
+
+/// Starts advertising this instance and browsing for peers.
+///
+/// Starting while already running returns the current status.
+pub fn start(app_version: &str) -> AppResult<DiscoveryStatus> {
+    let mut session = lock()?;
+    if session.is_none() {
+        let daemon = ServiceDaemon::new().map_err(discovery_error)?;
+        let instance = format!("eztauri-{}", &Uuid::new_v4().simple().to_string()[..12]);
+        let host = format!("{}.local.", instance);
+        let port = config::current().local_server_port.unwrap_or(0);
+        let (transfer_port, transfer_listener) = transfer::start_listener()?;
+        let transfer_port = transfer_port.to_string();
+        let properties = [
+            ("version", app_version),
+            ("transfer", transfer_port.as_str()),
+        ];
+
+        let info = ServiceInfo::new(SERVICE_TYPE, &instance, &host, "", port, &properties[..])
+            .map_err(discovery_error)?
+            .enable_addr_auto();
+        let fullname = info.get_fullname().to_string();
+        daemon.register(info).map_err(discovery_error)?;
+        let receiver = daemon.browse(SERVICE_TYPE).map_err(discovery_error)?;
+
+        // The receiver closes when the daemon shuts down, ending the loop.
+        let own = fullname.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            while let Ok(event) = receiver.recv() {
+                handle_event(event, &own);
+            }
+            tracing::debug!("Discovery browser stopped");
+        });
+
+        tracing::info!("Discovery started as {}", fullname);
+        *session = Some(Session {
+            daemon,
+            fullname,
+            transfer_listener,
+        });
+    }
+    drop(session);
+    status()
+}
+
+/// Stops advertising and browsing, forgetting known peers. Returns whether
+/// discovery was running. Also called on shutdown so peers see us leave.
+pub fn stop() -> AppResult<bool> {
+    let Some(session) = lock()?.take() else {
+        return Ok(false);
+    };
+    session.transfer_listener.abort();
+    if let Err(e) = session.daemon.unregister(&session.fullname) {
+        tracing::warn!("Failed to withdraw discovery announcement: {}", e);
+    }
+    if let Err(e) = session.daemon.shutdown() {
+        tracing::warn!("Failed to stop discovery daemon: {}", e);
+    }
+    if let Ok(mut peers) = PEERS.lock() {
+        peers.clear();
+    }
+    tracing::info!("Discovery stopped");
+    Ok(true)
+}
+
+/// Returns known peers, most recently seen first.
+pub fn peers() -> Vec<Peer> {
+    let mut peers: Vec<Peer> = PEERS
+        .lock()
+        .map(|peers| peers.values().cloned().collect())
+        .unwrap_or_default();
+    peers.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    peers
+}
+
+/// Returns whether discovery is running, this instance's name, and peers.
+pub fn status() -> AppResult<DiscoveryStatus> {
+    let instance = lock()?
+        .as_ref()
+        .map(|session| instance_name(&session.fullname).to_string());
+    Ok(DiscoveryStatus {
+        running: instance.is_some(),
+        service_type: SERVICE_TYPE.to_string(),
+        instance,
+        peers: peers(),
+    })
+}
+
+fn handle_event(event: ServiceEvent, own: &str) {
+    match event {
+        ServiceEvent::ServiceResolved(info) if info.get_fullname() != own => {
+            let mut addresses: Vec<String> = info
+                .get_addresses()
+                .iter()
+                .map(|address| address.to_string())
+                .collect();
+            addresses.sort();
+            let peer = Peer {
+                id: info.get_fullname().to_string(),
+                name: instance_name(info.get_fullname()).to_string(),
+                host: info.get_hostname().trim_end_matches('.').to_string(),
+                addresses,
+                port: info.get_port(),
+                transfer_port: info
+                    .get_property_val_str("transfer")
+                    .and_then(|port| port.parse().ok()),
+                version: info.get_property_val_str("version").map(str::to_string),
+                last_seen: Utc::now(),
+            };
+            let found = PEERS
+                .lock()
+                .map(|mut peers| upsert(&mut peers, peer.clone()))
+                .unwrap_or(false);
+            if found {
+                tracing::debug!("Discovered peer {}", peer.name);
+                events::publish(AppEvent::PeerFound(peer));
+            }
+        }
+        ServiceEvent::ServiceRemoved(_, fullname) => {
+            let removed = PEERS
+                .lock()
+                .map(|mut peers| peers.remove(&fullname).is_some())
+                .unwrap_or(false);
+            if removed {
+                tracing::debug!("Peer left: {}", fullname);
+                events::publish(AppEvent::PeerLost { id: fullname });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Records `peer`, returning whether it is new or its address changed.
+fn upsert(peers: &mut HashMap<String, Peer>, peer: Peer) -> bool {
+    match peers.get_mut(&peer.id) {
+        Some(existing) => {
+            let changed = existing.addresses != peer.addresses || existing.port != peer.port;
+            *existing = peer;
+            changed
+        }
+        None if peers.len() >= MAX_PEERS => false,
+        None => {
+            peers.insert(peer.id.clone(), peer);
+            true
+        }
+    }
+}
+
+/// Strips the service type from a full service name.
+fn instance_name(fullname: &str) -> &str {
+    fullname
+        .strip_suffix(SERVICE_TYPE)
+        .map(|name| name.trim_end_matches('.'))
+        .unwrap_or(fullname)
+}
+
+fn lock() -> AppResult<std::sync::MutexGuard<'static, Option<Session>>> {
+    SESSION
+        .lock()
+        .map_err(|_| AppError::internal_error("Discovery is unavailable"))
+}
+
+fn discovery_error(e: impl std::fmt::Display) -> AppError {
+    AppError::new(
+        ErrorCode::NetworkError,
+        format!("mDNS discovery failed: {}", e),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(id: &str, port: u16) -> Peer {
+        Peer {
+            id: id.to_string(),
+            name: instance_name(id).to_string(),
+            host: "peer.local".to_string(),
+            addresses: vec!["192.168.1.20".to_string()],
+            port,
+            transfer_port: Some(40000),
+            version: Some("1.0.0".to_string()),
+            last_seen: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn extracts_instance_names() {
+        assert_eq!(
+            instance_name("eztauri-abc._eztauri._tcp.local."),
+            "eztauri-abc"
+        );
+        assert_eq!(
+            instance_name("other._http._tcp.local."),
+            "other._http._tcp.local."
+        );
+    }
+
+    #[test]
+    fn reports_new_and_changed_peers_only() {
+        let mut peers = HashMap::new();
+        let id = "eztauri-abc._eztauri._tcp.local.";
+        assert!(upsert(&mut peers, peer(id, 0)));
+        assert!(!upsert(&mut peers, peer(id, 0)));
+        assert!(upsert(&mut peers, peer(id, 8080)));
+        assert_eq!(peers.len(), 1);
+    }
+
+    #[test]
+    fn caps_the_number_of_tracked_peers() {
+        let mut peers = HashMap::new();
+        for i in 0..MAX_PEERS + 3 {
+            upsert(&mut peers, peer(&format!("p{}.{}", i, SERVICE_TYPE), 0));
+        }
+        assert_eq!(peers.len(), MAX_PEERS);
+    }
+}
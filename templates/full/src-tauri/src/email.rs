@@ -0,0 +1,402 @@
+//! Outgoing email over SMTP.
+//!
+//! SMTP credentials live in the Stronghold vault: the frontend reads the
+//! `smtp` record after unlocking the vault and hands it to [`configure`], so
+//! the password is only ever held in memory. Messages are rendered from named
+//! templates and delivered by a background queue that retries transient
+//! failures with exponential backoff. Delivery goes through the pluggable
+//! transports in [`crate::mail`].
+
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::mail::{MailTransport, SmtpTransport};
+use crate::shutdown;
+use crate::validation::validate_email;
+use chrono::{DateTime, Utc};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::Message;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+pub use crate::mail::SmtpSettings;
+
+/// Delivery attempts before a message is marked failed.
+const MAX_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubled for each further attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+/// Number of deliveries kept for status reporting.
+const DELIVERY_HISTORY: usize = 200;
+
+/// Built-in templates as `(name, subject, body)`.
+const BUILTIN_TEMPLATES: [(&str, &str, &str); 2] = [
+    (
+        "password_reset",
+        "Reset your password",
+        "Hello {{name}},\n\nUse the code below to reset your password:\n\n{{token}}\n\nThe code expires in {{expires_in}}. If you did not request a reset, you can ignore this email.\n",
+    ),
+    (
+        "email_verification",
+        "Verify your email address",
+        "Hello {{name}},\n\nUse the code below to verify your email address:\n\n{{token}}\n\nThe code expires in {{expires_in}}.\n",
+    ),
+];
+
+/// Delivery state of a queued message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeliveryStatus {
+    Queued,
+    Retrying,
+    Sent,
+    Failed,
+}
+
+/// Status of a message handed to the queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailDelivery {
+    pub id: Uuid,
+    pub to: String,
+    pub template: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub queued_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+struct Mailer {
+    transport: Arc<dyn MailTransport>,
+    from: Mailbox,
+}
+
+struct QueuedEmail {
+    id: Uuid,
+    message: Message,
+    attempt: u32,
+}
+
+/// Configured transport, if one was set.
+static MAILER: Lazy<RwLock<Option<Mailer>>> = Lazy::new(|| RwLock::new(None));
+
+/// Sender side of the delivery queue, set once the worker is running.
+static QUEUE: OnceCell<mpsc::UnboundedSender<QueuedEmail>> = OnceCell::new();
+
+/// Recent deliveries, oldest first.
+static DELIVERIES: Lazy<Mutex<VecDeque<EmailDelivery>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Templates registered at runtime, keyed by name.
+static TEMPLATES: Lazy<RwLock<HashMap<String, (String, String)>>> = Lazy::new(|| {
+    RwLock::new(
+        BUILTIN_TEMPLATES
+            .iter()
+            .map(|(name, subject, body)| {
+                (name.to_string(), (subject.to_string(), body.to_string()))
+            })
+            .collect(),
+    )
+});
+
+/// Builds the SMTP transport from vault settings.
+pub fn configure(settings: SmtpSettings) -> AppResult<()> {
+    let transport = SmtpTransport::new(&settings)?;
+    let from: Mailbox = settings
+        .from
+        .parse()
+        .map_err(|e| AppError::invalid_input("from", format!("Invalid sender address: {}", e)))?;
+
+    set_transport(Arc::new(transport), from);
+    tracing::info!("SMTP transport configured for {}", settings.host.trim());
+    Ok(())
+}
+
+/// Sends queued and future messages through `transport` from `from`.
+pub fn set_transport(transport: Arc<dyn MailTransport>, from: Mailbox) {
+    tracing::debug!("Using the '{}' mail transport", transport.name());
+    if let Ok(mut mailer) = MAILER.write() {
+        *mailer = Some(Mailer { transport, from });
+    }
+}
+
+/// Registers or replaces a named template.
+pub fn register_template(name: &str, subject: &str, body: &str) -> AppResult<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid_input(
+            "name",
+            "Template name cannot be empty",
+        ));
+    }
+
+    if let Ok(mut templates) = TEMPLATES.write() {
+        templates.insert(name.to_string(), (subject.to_string(), body.to_string()));
+    }
+    Ok(())
+}
+
+/// Renders `template` with `data`, returning the subject and body.
+pub fn render(template: &str, data: &HashMap<String, String>) -> AppResult<(String, String)> {
+    let (subject, body) = TEMPLATES
+        .read()
+        .ok()
+        .and_then(|templates| templates.get(template).cloned())
+        .ok_or_else(|| {
+            AppError::invalid_input("template", format!("Unknown email template '{}'", template))
+        })?;
+
+    Ok((substitute(&subject, data)?, substitute(&body, data)?))
+}
+
+/// Replaces `{{key}}` placeholders; unknown keys are an error.
+fn substitute(text: &str, data: &HashMap<String, String>) -> AppResult<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| AppError::validation_error("Unclosed placeholder in email template"))?;
+        let key = after[..end].trim();
+        let value = data.get(key).ok_or_else(|| {
+            AppError::invalid_input("data", format!("Missing template value '{}'", key))
+        })?;
+        output.push_str(value);
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Renders `template` and queues it for delivery to `to`.
+pub fn send_template(to: &str, template: &str, data: &HashMap<String, String>) -> AppResult<Uuid> {
+    if shutdown::is_shutting_down() {
+        return Err(AppError::new(
+            ErrorCode::ExternalServiceUnavailable,
+            "Email queue is shutting down",
+        ));
+    }
+
+    let queue = QUEUE.get().ok_or_else(|| {
+        AppError::new(
+            ErrorCode::ExternalServiceUnavailable,
+            "Email queue is not running",
+        )
+    })?;
+    let from = MAILER
+        .read()
+        .ok()
+        .and_then(|mailer| mailer.as_ref().map(|mailer| mailer.from.clone()))
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorCode::ConfigurationError,
+                "No mail transport is configured",
+            )
+        })?;
+
+    let to = validate_email(to).map_err(|e| AppError::invalid_input("to", e.to_string()))?;
+    let (subject, body) = render(template, data)?;
+
+    let message = Message::builder()
+        .from(from)
+        .to(to
+            .parse()
+            .map_err(|e| AppError::invalid_input("to", format!("Invalid recipient: {}", e)))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::InvalidFormat,
+                format!("Failed to build email: {}", e),
+            )
+        })?;
+
+    let id = Uuid::new_v4();
+    let now = Utc::now();
+    record(EmailDelivery {
+        id,
+        to,
+        template: template.to_string(),
+        status: DeliveryStatus::Queued,
+        attempts: 0,
+        last_error: None,
+        queued_at: now,
+        updated_at: now,
+    });
+
+    queue
+        .send(QueuedEmail {
+            id,
+            message,
+            attempt: 0,
+        })
+        .map_err(|_| {
+            AppError::new(
+                ErrorCode::ExternalServiceUnavailable,
+                "Email queue is not running",
+            )
+        })?;
+
+    Ok(id)
+}
+
+/// Returns recent deliveries, newest first.
+pub fn deliveries() -> Vec<EmailDelivery> {
+    DELIVERIES
+        .lock()
+        .map(|deliveries| deliveries.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Starts the background delivery worker. Calling it again is a no-op.
+pub fn start_worker() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<QueuedEmail>();
+    if QUEUE.set(sender).is_err() {
+        return;
+    }
+
+    let worker = tauri::async_runtime::spawn(async move {
+        while let Some(job) = receiver.recv().await {
+            deliver(job).await;
+        }
+    });
+    shutdown::track("email-queue", worker);
+}
+
+/// Waits up to `timeout` for queued and retrying messages to finish.
+///
+/// Returns the number of messages still pending when the wait ended.
+pub async fn drain(timeout: Duration) -> usize {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let pending = DELIVERIES
+            .lock()
+            .map(|deliveries| {
+                deliveries
+                    .iter()
+                    .filter(|delivery| {
+                        matches!(delivery.status, DeliveryStatus::Queued | DeliveryStatus::Retrying)
+                    })
+                    .count()
+            })
+            .unwrap_or(0);
+
+        if pending == 0 || tokio::time::Instant::now() >= deadline {
+            return pending;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+async fn deliver(mut job: QueuedEmail) {
+    job.attempt += 1;
+
+    let mailer = MAILER.read().ok().and_then(|mailer| mailer.clone());
+    let result = match mailer {
+        Some(mailer) => mailer.transport.send(job.message.clone()).await,
+        None => Err("No mail transport is configured".to_string()),
+    };
+
+    match result {
+        Ok(()) => {
+            update(job.id, DeliveryStatus::Sent, job.attempt, None);
+            tracing::info!("Email {} delivered", job.id);
+        }
+        Err(error) if job.attempt < MAX_ATTEMPTS => {
+            update(
+                job.id,
+                DeliveryStatus::Retrying,
+                job.attempt,
+                Some(error.clone()),
+            );
+            tracing::warn!("Email {} attempt {} failed: {}", job.id, job.attempt, error);
+
+            let delay = RETRY_BASE_DELAY * 2u32.pow(job.attempt - 1);
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(delay).await;
+                if let Some(queue) = QUEUE.get() {
+                    let _ = queue.send(job);
+                }
+            });
+        }
+        Err(error) => {
+            update(
+                job.id,
+                DeliveryStatus::Failed,
+                job.attempt,
+                Some(error.clone()),
+            );
+            tracing::error!(
+                "Email {} failed after {} attempts: {}",
+                job.id,
+                job.attempt,
+                error
+            );
+        }
+    }
+}
+
+fn record(delivery: EmailDelivery) {
+    if let Ok(mut deliveries) = DELIVERIES.lock() {
+        if deliveries.len() >= DELIVERY_HISTORY {
+            deliveries.pop_front();
+        }
+        deliveries.push_back(delivery);
+    }
+}
+
+fn update(id: Uuid, status: DeliveryStatus, attempts: u32, last_error: Option<String>) {
+    if let Ok(mut deliveries) = DELIVERIES.lock() {
+        if let Some(delivery) = deliveries.iter_mut().find(|delivery| delivery.id == id) {
+            delivery.status = status;
+            delivery.attempts = attempts;
+            delivery.last_error = last_error;
+            delivery.updated_at = Utc::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_builtin_templates() {
+        let (subject, body) = render(
+            "password_reset",
+            &data(&[
+                ("name", "Ada"),
+                ("token", "123456"),
+                ("expires_in", "15 minutes"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(subject, "Reset your password");
+        assert!(body.starts_with("Hello Ada,"));
+        assert!(body.contains("123456"));
+    }
+
+    #[test]
+    fn rejects_missing_values_and_unknown_templates() {
+        assert!(render("password_reset", &data(&[("name", "Ada")])).is_err());
+        assert!(render("does_not_exist", &HashMap::new()).is_err());
+        assert!(substitute("Hi {{name", &data(&[("name", "Ada")])).is_err());
+    }
+}
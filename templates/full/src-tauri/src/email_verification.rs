@@ -0,0 +1,225 @@
+//! Email address verification backed by the `email_verifications` table.
+//!
+//! [`send`] emails a one-time code using the `email_verification` template.
+//! Only a SHA-256 hash of the code is stored, together with the address it
+//! was sent to, so changing the email invalidates outstanding codes. With
+//! `REQUIRE_EMAIL_VERIFICATION` set, new accounts start inactive and
+//! [`verify`] activates them.
+
+use crate::config;
+use crate::email;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::session::{hash_token, random_token};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Template used for verification emails.
+const TEMPLATE: &str = "email_verification";
+
+/// A freshly issued verification code and where to send it.
+struct Issued {
+    email: String,
+    name: String,
+    token: String,
+}
+
+/// Emails a verification code to `user_id`, replacing any code sent
+/// earlier. Returns the id of the queued delivery.
+pub async fn send(pool: &PgPool, user_id: Uuid) -> AppResult<Uuid> {
+    let ttl_hours = config::current().email_verification_ttl_hours;
+    let issued = issue(pool, user_id, ttl_hours).await?;
+
+    let data = HashMap::from([
+        ("name".to_string(), issued.name),
+        ("token".to_string(), issued.token),
+        ("expires_in".to_string(), describe_hours(ttl_hours)),
+    ]);
+    email::send_template(&issued.email, TEMPLATE, &data)
+}
+
+/// Consumes `token` and marks the address it was sent to as verified,
+/// activating the account when verification is required. Returns the user id.
+///
+/// Fails with `TOKEN_EXPIRED` when the code has lapsed and `UNAUTHORIZED`
+/// when it is unknown, was already used, or the address has since changed.
+pub async fn verify(pool: &PgPool, token: &str) -> AppResult<Uuid> {
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    let pending: Option<(Uuid, String, DateTime<Utc>)> = sqlx::query_as(
+        "DELETE FROM email_verifications
+         WHERE token_hash = $1
+         RETURNING user_id, email, expires_at",
+    )
+    .bind(hash_token(token))
+    .fetch_optional(&mut *tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let outcome = match pending {
+        None => Err(AppError::unauthorized("Invalid verification code")),
+        Some((_, _, expires_at)) if expires_at <= Utc::now() => Err(AppError::new(
+            ErrorCode::TokenExpired,
+            "Verification code has expired",
+        )),
+        Some((user_id, email, _)) => {
+            let updated = sqlx::query(
+                "UPDATE users
+                 SET email_verified = true,
+                     is_active = is_active OR $3,
+                     updated_at = CURRENT_TIMESTAMP
+                 WHERE id = $1 AND email = $2",
+            )
+            .bind(user_id)
+            .bind(&email)
+            .bind(config::current().require_email_verification)
+            .execute(&mut *tx)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+
+            if updated.rows_affected() == 0 {
+                Err(AppError::unauthorized(
+                    "Email address changed after the code was sent",
+                ))
+            } else {
+                Ok(user_id)
+            }
+        }
+    };
+
+    // Commit even on failure so expired or stale codes are discarded.
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+    if let Ok(user_id) = outcome {
+        tracing::info!("Verified email address of user {}", user_id);
+    }
+    outcome
+}
+
+async fn issue(pool: &PgPool, user_id: Uuid, ttl_hours: u64) -> AppResult<Issued> {
+    let user: Option<(String, String, Option<String>, bool)> = sqlx::query_as(
+        "SELECT email, username, first_name, email_verified FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+    let (email, username, first_name, verified) =
+        user.ok_or_else(|| AppError::not_found("User"))?;
+    if verified {
+        return Err(AppError::validation_error(
+            "Email address is already verified",
+        ));
+    }
+
+    let token = random_token();
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    sqlx::query("DELETE FROM email_verifications WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    sqlx::query(
+        "INSERT INTO email_verifications (user_id, email, token_hash, expires_at)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(user_id)
+    .bind(&email)
+    .bind(hash_token(&token))
+    .bind(Utc::now() + ChronoDuration::hours(ttl_hours as i64))
+    .execute(&mut *tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+
+    Ok(Issued {
+        email,
+        name: first_name.unwrap_or(username),
+        token,
+    })
+}
+
+/// Formats a code lifetime for the email body, e.g. `24 hours`.
+fn describe_hours(hours: u64) -> String {
+    if hours == 1 {
+        "1 hour".to_string()
+    } else {
+        format!("{} hours", hours)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::test_support::UserFactory;
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    async fn is_verified(pool: &PgPool, user_id: Uuid) -> AnyResult<bool> {
+        let (verified,): (bool,) = sqlx::query_as("SELECT email_verified FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+        Ok(verified)
+    }
+
+    #[test]
+    fn describes_code_lifetimes() {
+        assert_eq!(describe_hours(1), "1 hour");
+        assert_eq!(describe_hours(24), "24 hours");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn codes_verify_once_and_replace_earlier_codes() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+        assert!(!user.email_verified);
+
+        let first = issue(pool.as_ref(), user.id, 24).await?;
+        let second = issue(pool.as_ref(), user.id, 24).await?;
+        assert_eq!(second.email, user.email);
+        let replaced = verify(pool.as_ref(), &first.token).await.unwrap_err();
+        assert!(matches!(replaced.code, ErrorCode::Unauthorized));
+
+        assert_eq!(verify(pool.as_ref(), &second.token).await?, user.id);
+        assert!(is_verified(pool.as_ref(), user.id).await?);
+        let reused = verify(pool.as_ref(), &second.token).await.unwrap_err();
+        assert!(matches!(reused.code, ErrorCode::Unauthorized));
+        assert!(issue(pool.as_ref(), user.id, 24).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn expired_and_stale_codes_are_rejected() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+
+        let expired = issue(pool.as_ref(), user.id, 24).await?;
+        sqlx::query("UPDATE email_verifications SET expires_at = $1")
+            .bind(Utc::now() - ChronoDuration::minutes(1))
+            .execute(pool.as_ref())
+            .await?;
+        let error = verify(pool.as_ref(), &expired.token).await.unwrap_err();
+        assert!(matches!(error.code, ErrorCode::TokenExpired));
+
+        let stale = issue(pool.as_ref(), user.id, 24).await?;
+        sqlx::query("UPDATE users SET email = 'changed@example.com' WHERE id = $1")
+            .bind(user.id)
+            .execute(pool.as_ref())
+            .await?;
+        let error = verify(pool.as_ref(), &stale.token).await.unwrap_err();
+        assert!(matches!(error.code, ErrorCode::Unauthorized));
+        assert!(!is_verified(pool.as_ref(), user.id).await?);
+        Ok(())
+    }
+}
@@ -50,6 +50,8 @@ pub enum ErrorCode {
     // Generic errors
     InternalError,
     NotImplemented,
+    Cancelled,
+    MaintenanceMode,
     Unknown,
 }
 
@@ -85,6 +87,8 @@ impl fmt::Display for ErrorCode {
             ErrorCode::PermissionDenied => "PERMISSION_DENIED",
             ErrorCode::InternalError => "INTERNAL_ERROR",
             ErrorCode::NotImplemented => "NOT_IMPLEMENTED",
+            ErrorCode::Cancelled => "CANCELLED",
+            ErrorCode::MaintenanceMode => "MAINTENANCE_MODE",
             ErrorCode::Unknown => "UNKNOWN",
         };
         write!(f, "{}", code_str)
@@ -225,6 +229,10 @@ impl AppError {
             ErrorCode::NotImplemented => {
                 "This feature is not yet implemented.".to_string()
             }
+            ErrorCode::Cancelled => "The operation was cancelled.".to_string(),
+            ErrorCode::MaintenanceMode => {
+                "Maintenance is in progress. Please try again shortly.".to_string()
+            }
             _ => {
                 "An unexpected error occurred. Please try again later.".to_string()
             }
@@ -241,6 +249,7 @@ impl AppError {
                 | ErrorCode::RequestTimeout
                 | ErrorCode::CacheConnection
                 | ErrorCode::ResourceExhausted
+                | ErrorCode::MaintenanceMode
         )
     }
 
@@ -254,6 +263,8 @@ impl AppError {
                 | ErrorCode::InvalidFormat
                 | ErrorCode::Unauthorized
                 | ErrorCode::Forbidden
+                | ErrorCode::Cancelled
+                | ErrorCode::MaintenanceMode
         )
     }
 
@@ -265,7 +276,9 @@ impl AppError {
             | ErrorCode::MissingField
             | ErrorCode::InvalidFormat
             | ErrorCode::Unauthorized
-            | ErrorCode::Forbidden => tracing::Level::WARN,
+            | ErrorCode::Forbidden
+            | ErrorCode::Cancelled
+            | ErrorCode::MaintenanceMode => tracing::Level::WARN,
 
             ErrorCode::DatabaseTimeout
             | ErrorCode::NetworkError
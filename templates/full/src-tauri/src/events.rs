@@ -0,0 +1,248 @@
+//! Typed event bus shared by backend subsystems and the frontend.
+//!
+//! Modules publish [`AppEvent`] values instead of emitting Tauri events
+//! directly. A single dispatcher forwards every event to the webview, and
+//! Rust-side subscribers can listen on the same channel.
+
+use crate::discovery::Peer;
+use crate::file_drop::RejectedDrop;
+use crate::file_open::OpenRequest;
+use crate::handlers::filesystem::FileInfo;
+use crate::inspector::InvocationRecord;
+use crate::logging::follow::TailedLine;
+use crate::maintenance::MaintenanceStatus;
+use crate::operations::OperationProgress;
+use crate::server::ServerRequestPayload;
+use crate::setup::SetupStep;
+use crate::startup::StartupTaskStatus;
+use crate::storage_devices::StorageDevice;
+#[cfg(feature = "database")]
+use crate::sync::SyncStatus;
+use crate::themes::Theme;
+use crate::transfer::{TransferDirection, TransferOffer};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Capacity of the in-process broadcast channel.
+const EVENT_BUFFER: usize = 256;
+
+/// Global event channel.
+static BUS: Lazy<broadcast::Sender<AppEvent>> = Lazy::new(|| broadcast::channel(EVENT_BUFFER).0);
+
+/// Events published by backend subsystems.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged, rename_all_fields = "camelCase")]
+pub enum AppEvent {
+    DatabaseConnected,
+    DatabaseUnavailable {
+        error: String,
+    },
+    CacheUnavailable {
+        error: String,
+    },
+    JobFinished {
+        job: String,
+        success: bool,
+        message: Option<String>,
+    },
+    UpdateDownloadProgress {
+        version: String,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    UpdateDownloaded {
+        version: String,
+    },
+    ServerRequest {
+        #[serde(skip)]
+        event: String,
+        #[serde(flatten)]
+        request: ServerRequestPayload,
+    },
+    #[cfg(feature = "database")]
+    SyncStatusChanged(SyncStatus),
+    CommandInvoked(InvocationRecord),
+    SetupStepCompleted {
+        step: SetupStep,
+        setup_complete: bool,
+    },
+    StateChanged {
+        key: String,
+        value: Option<serde_json::Value>,
+    },
+    NotificationBadge {
+        unread: i64,
+    },
+    WorkspaceSwitched {
+        workspace_id: Option<Uuid>,
+    },
+    DatabaseNotification {
+        channel: String,
+        payload: serde_json::Value,
+    },
+    LogTailLines {
+        tail_id: Uuid,
+        file: String,
+        lines: Vec<TailedLine>,
+    },
+    LogTailEnded {
+        tail_id: Uuid,
+        error: Option<String>,
+    },
+    ThemeChanged {
+        user_id: Uuid,
+        theme: Theme,
+    },
+    FilesDropped {
+        window: String,
+        files: Vec<FileInfo>,
+        rejected: Vec<RejectedDrop>,
+    },
+    FileOpenRequested(OpenRequest),
+    PeerFound(Peer),
+    PeerLost {
+        id: String,
+    },
+    TransferOffered(TransferOffer),
+    TransferProgress {
+        transfer_id: Uuid,
+        direction: TransferDirection,
+        transferred: u64,
+        total: u64,
+    },
+    TransferFinished {
+        transfer_id: Uuid,
+        direction: TransferDirection,
+        success: bool,
+        /// Scope-relative path of a received file.
+        path: Option<String>,
+        message: Option<String>,
+    },
+    SessionExpired {
+        session_id: Uuid,
+        user_id: Uuid,
+    },
+    StorageMounted(StorageDevice),
+    StorageUnmounted(StorageDevice),
+    CompanionConnected {
+        address: String,
+    },
+    CompanionDisconnected {
+        address: String,
+    },
+    OperationProgress(OperationProgress),
+    MaintenanceChanged(MaintenanceStatus),
+    StartupTask(StartupTaskStatus),
+}
+
+impl AppEvent {
+    /// Returns the Tauri event name the frontend listens on.
+    pub fn name(&self) -> &str {
+        match self {
+            AppEvent::DatabaseConnected => "db:connected",
+            AppEvent::DatabaseUnavailable { .. } => "db:unavailable",
+            AppEvent::CacheUnavailable { .. } => "cache:unavailable",
+            AppEvent::JobFinished { .. } => "job:finished",
+            AppEvent::UpdateDownloadProgress { .. } => "update:progress",
+            AppEvent::UpdateDownloaded { .. } => "update:downloaded",
+            AppEvent::ServerRequest { event, .. } => event,
+            #[cfg(feature = "database")]
+            AppEvent::SyncStatusChanged(_) => "sync:status",
+            AppEvent::CommandInvoked(_) => "inspector:invocation",
+            AppEvent::SetupStepCompleted { .. } => "setup:step-completed",
+            AppEvent::StateChanged { .. } => "state:changed",
+            AppEvent::NotificationBadge { .. } => "notification:badge",
+            AppEvent::WorkspaceSwitched { .. } => "workspace:switched",
+            AppEvent::DatabaseNotification { .. } => "db:notification",
+            AppEvent::LogTailLines { .. } => "log:tail",
+            AppEvent::LogTailEnded { .. } => "log:tail-ended",
+            AppEvent::ThemeChanged { .. } => "theme:changed",
+            AppEvent::FilesDropped { .. } => "files:dropped",
+            AppEvent::FileOpenRequested(_) => "file:open-requested",
+            AppEvent::PeerFound(_) => "discovery:peer-found",
+            AppEvent::PeerLost { .. } => "discovery:peer-lost",
+            AppEvent::TransferOffered(_) => "transfer:offered",
+            AppEvent::TransferProgress { .. } => "transfer:progress",
+            AppEvent::TransferFinished { .. } => "transfer:finished",
+            AppEvent::SessionExpired { .. } => "session:expired",
+            AppEvent::StorageMounted(_) => "storage:mounted",
+            AppEvent::StorageUnmounted(_) => "storage:unmounted",
+            AppEvent::CompanionConnected { .. } => "companion:connected",
+            AppEvent::CompanionDisconnected { .. } => "companion:disconnected",
+            AppEvent::OperationProgress(_) => "operation:progress",
+            AppEvent::MaintenanceChanged(_) => "maintenance:changed",
+            AppEvent::StartupTask(_) => "startup:task",
+        }
+    }
+}
+
+/// Publishes an event to the frontend and any Rust subscribers.
+///
+/// Publishing never fails; events are dropped when nobody is listening.
+pub fn publish(event: AppEvent) {
+    tracing::trace!("Publishing event '{}'", event.name());
+    let _ = BUS.send(event);
+}
+
+/// Subscribes to all events published after this call.
+pub fn subscribe() -> broadcast::Receiver<AppEvent> {
+    BUS.subscribe()
+}
+
+/// Starts the dispatcher that serializes published events to Tauri events.
+pub fn start_dispatcher(app: AppHandle) {
+    let mut receiver = subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if let Err(e) = app.emit(event.name(), &event) {
+                        tracing::warn!("Failed to emit event '{}': {}", event.name(), e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event dispatcher lagged, dropped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_names_are_stable() {
+        assert_eq!(AppEvent::DatabaseConnected.name(), "db:connected");
+        assert_eq!(
+            AppEvent::CacheUnavailable { error: "down".into() }.name(),
+            "cache:unavailable"
+        );
+    }
+
+    #[test]
+    fn events_serialize_without_tags() {
+        let event = AppEvent::JobFinished {
+            job: "migrations".into(),
+            success: true,
+            message: None,
+        };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["job"], "migrations");
+        assert_eq!(value["success"], true);
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_published_events() {
+        let mut receiver = subscribe();
+        publish(AppEvent::DatabaseConnected);
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.name(), "db:connected");
+    }
+}
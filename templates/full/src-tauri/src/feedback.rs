@@ -0,0 +1,290 @@
+//! In-app feedback and bug report packaging.
+//!
+//! A report is a zip archive holding the user's message, an optional health
+//! snapshot, the tail of recent log files, and an optional screenshot captured
+//! by the frontend. Reports are saved inside the filesystem scope, or uploaded
+//! to the configured endpoint when the user explicitly consents.
+
+use crate::build_info::{self, AppVersionInfo};
+use crate::cache;
+use crate::config;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::handlers::filesystem::resolve_relative_path;
+use crate::http_client::{self, HttpRequestOptions};
+use crate::logging;
+use crate::server;
+use crate::setup;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+
+/// Maximum length of a feedback message.
+const MAX_MESSAGE_LEN: usize = 10_000;
+/// Number of most recent log files included in a report.
+const LOG_FILES: usize = 3;
+/// Bytes kept from the end of each included log file.
+const LOG_TAIL_BYTES: usize = 256 * 1024;
+/// Maximum decoded screenshot size.
+const MAX_SCREENSHOT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Options controlling what goes into a report and where it is sent.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackOptions {
+    pub include_logs: bool,
+    pub include_system_info: bool,
+    /// Base64-encoded PNG, optionally as a `data:` URL.
+    pub screenshot: Option<String>,
+    /// Explicit consent to send the report to the feedback endpoint.
+    pub upload: bool,
+}
+
+/// Result of submitting feedback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackReceipt {
+    pub id: Uuid,
+    pub uploaded: bool,
+    /// Path of the saved archive relative to the filesystem scope.
+    pub path: Option<String>,
+    pub size_bytes: usize,
+}
+
+/// Snapshot of application health included in reports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub generated_at: DateTime<Utc>,
+    pub app_version: String,
+    pub environment: String,
+    pub os: String,
+    pub arch: String,
+    pub database_connected: bool,
+    pub cache_available: bool,
+    pub local_server_running: bool,
+    pub build: AppVersionInfo,
+}
+
+/// Builds a feedback report and saves or uploads it.
+pub async fn submit(message: &str, app_version: &str, options: FeedbackOptions) -> AppResult<FeedbackReceipt> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(AppError::invalid_input("message", "Feedback message cannot be empty"));
+    }
+    if message.chars().count() > MAX_MESSAGE_LEN {
+        return Err(AppError::invalid_input(
+            "message",
+            format!("Feedback message cannot exceed {} characters", MAX_MESSAGE_LEN),
+        ));
+    }
+
+    let id = Uuid::new_v4();
+    let health = if options.include_system_info {
+        Some(health_report(app_version).await)
+    } else {
+        None
+    };
+    let screenshot = options.screenshot.as_deref().map(decode_screenshot).transpose()?;
+    let logs = if options.include_logs { recent_log_tails() } else { Vec::new() };
+
+    let archive = build_archive(id, message, health.as_ref(), &logs, screenshot.as_deref())?;
+    let size_bytes = archive.len();
+
+    if options.upload {
+        if let Some(endpoint) = config::current().feedback_endpoint.clone() {
+            upload(&endpoint, id, &archive).await?;
+            tracing::info!("Uploaded feedback report {}", id);
+            return Ok(FeedbackReceipt {
+                id,
+                uploaded: true,
+                path: None,
+                size_bytes,
+            });
+        }
+        tracing::warn!("Feedback upload requested but FEEDBACK_ENDPOINT is not configured; saving locally");
+    }
+
+    let relative = format!("feedback/feedback-{}-{}.zip", Utc::now().format("%Y%m%d%H%M%S"), id);
+    let context = resolve_relative_path(&relative).map_err(|e| AppError::file_error("write", relative.clone(), e))?;
+    if let Some(parent) = context.path.parent() {
+        fs::create_dir_all(parent).into_app_error(ErrorCode::DirectoryCreate)?;
+    }
+    fs::write(&context.path, &archive)
+        .map_err(|e| AppError::file_error("write", context.relative_display(), e.to_string()))?;
+
+    tracing::info!("Saved feedback report to {}", context.relative_display());
+    Ok(FeedbackReceipt {
+        id,
+        uploaded: false,
+        path: Some(context.relative_display()),
+        size_bytes,
+    })
+}
+
+/// Collects a health snapshot of the backend subsystems.
+pub async fn health_report(app_version: &str) -> HealthReport {
+    let config = config::current();
+    let database_connected = setup::database_connected().await;
+
+    HealthReport {
+        generated_at: Utc::now(),
+        app_version: app_version.to_string(),
+        environment: format!("{:?}", config.environment).to_lowercase(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        database_connected,
+        cache_available: cache::is_redis_available(),
+        local_server_running: server::status().running,
+        build: build_info::current(),
+    }
+}
+
+fn build_archive(
+    id: Uuid,
+    message: &str,
+    health: Option<&HealthReport>,
+    logs: &[(String, Vec<u8>)],
+    screenshot: Option<&[u8]>,
+) -> AppResult<Vec<u8>> {
+    let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let mut add = |name: &str, bytes: &[u8]| -> AppResult<()> {
+        archive.start_file(name, options).into_app_error(ErrorCode::FileWrite)?;
+        archive.write_all(bytes).into_app_error(ErrorCode::FileWrite)
+    };
+
+    let report = serde_json::json!({
+        "id": id,
+        "message": message,
+        "submittedAt": Utc::now(),
+    });
+    add("feedback.json", &serde_json::to_vec_pretty(&report).into_app_error(ErrorCode::InternalError)?)?;
+
+    if let Some(health) = health {
+        add("health.json", &serde_json::to_vec_pretty(health).into_app_error(ErrorCode::InternalError)?)?;
+    }
+    for (name, content) in logs {
+        add(&format!("logs/{}", name), content)?;
+    }
+    if let Some(screenshot) = screenshot {
+        add("screenshot.png", screenshot)?;
+    }
+
+    let cursor = archive.finish().into_app_error(ErrorCode::FileWrite)?;
+    Ok(cursor.into_inner())
+}
+
+/// Returns the tail of the most recently modified log files.
+fn recent_log_tails() -> Vec<(String, Vec<u8>)> {
+    let Ok(entries) = fs::read_dir(logging::default_log_dir()) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(PathBuf, std::time::SystemTime)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("log"))
+        .filter_map(|path| {
+            let modified = path.metadata().and_then(|meta| meta.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect();
+    files.sort_by(|a, b| b.1.cmp(&a.1));
+
+    files
+        .into_iter()
+        .take(LOG_FILES)
+        .filter_map(|(path, _)| {
+            let content = fs::read(&path).ok()?;
+            let start = content.len().saturating_sub(LOG_TAIL_BYTES);
+            let name = path.file_name()?.to_string_lossy().to_string();
+            Some((name, content[start..].to_vec()))
+        })
+        .collect()
+}
+
+/// Decodes a base64 screenshot, accepting `data:image/png;base64,` prefixes.
+fn decode_screenshot(encoded: &str) -> AppResult<Vec<u8>> {
+    let data = encoded
+        .split_once(',')
+        .filter(|(prefix, _)| prefix.starts_with("data:"))
+        .map(|(_, data)| data)
+        .unwrap_or(encoded);
+
+    let bytes = BASE64
+        .decode(data.trim())
+        .map_err(|e| AppError::invalid_input("screenshot", format!("Invalid base64 screenshot: {}", e)))?;
+
+    if bytes.len() > MAX_SCREENSHOT_BYTES {
+        return Err(AppError::invalid_input("screenshot", "Screenshot is too large"));
+    }
+
+    Ok(bytes)
+}
+
+async fn upload(endpoint: &str, id: Uuid, archive: &[u8]) -> AppResult<()> {
+    let body = serde_json::json!({
+        "id": id,
+        "archive": BASE64.encode(archive),
+        "contentType": "application/zip",
+    });
+
+    let response = http_client::execute(
+        "POST",
+        endpoint,
+        HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+        Some(body),
+        HttpRequestOptions {
+            retries: Some(2),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    if !(200..300).contains(&response.status) {
+        return Err(AppError::new(
+            ErrorCode::ExternalServiceUnavailable,
+            format!("Feedback endpoint returned status {}", response.status),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_plain_and_data_url_screenshots() {
+        let encoded = BASE64.encode(b"png-bytes");
+        assert_eq!(decode_screenshot(&encoded).unwrap(), b"png-bytes");
+        assert_eq!(
+            decode_screenshot(&format!("data:image/png;base64,{}", encoded)).unwrap(),
+            b"png-bytes"
+        );
+        assert!(decode_screenshot("not base64!").is_err());
+    }
+
+    #[test]
+    fn archive_contains_requested_entries() {
+        let logs = vec![("app.log".to_string(), b"line".to_vec())];
+        let bytes = build_archive(Uuid::new_v4(), "It broke", None, &logs, Some(b"png")).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let names: Vec<String> = archive.file_names().map(String::from).collect();
+        assert!(names.contains(&"feedback.json".to_string()));
+        assert!(names.contains(&"logs/app.log".to_string()));
+        assert!(names.contains(&"screenshot.png".to_string()));
+        assert!(!names.contains(&"health.json".to_string()));
+        assert!(archive.by_name("feedback.json").is_ok());
+    }
+}
@@ -0,0 +1,257 @@
+//! Ingestion of files dropped onto application windows.
+//!
+//! Dropped paths are checked in Rust before the frontend sees them. With
+//! `FILE_DROP_COPY` enabled, files are copied into a directory inside the
+//! filesystem scope; otherwise only paths already inside the scope are
+//! accepted. Either way the frontend receives a `files:dropped` event with
+//! scope-relative [`FileInfo`] entries, never raw OS paths, so the results
+//! can be passed straight to the filesystem commands.
+
+use crate::config;
+use crate::events::{self, AppEvent};
+use crate::handlers::filesystem::{
+    build_file_info, ensure_write_permitted, filesystem_root, FileInfo,
+};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Most paths handled from a single drop; the rest are rejected.
+const MAX_DROPPED_FILES: usize = 100;
+
+/// A dropped path that was not accepted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedDrop {
+    /// File name only, so the event does not expose the source location.
+    pub name: String,
+    pub reason: String,
+}
+
+/// Outcome of handling one drop.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedFiles {
+    pub files: Vec<FileInfo>,
+    pub rejected: Vec<RejectedDrop>,
+}
+
+/// How dropped files are brought into the filesystem scope.
+#[derive(Debug, Clone)]
+struct DropOptions {
+    /// Scope-relative directory files are copied into, or `None` to accept
+    /// only paths already inside the scope.
+    copy_to: Option<String>,
+    max_bytes: u64,
+}
+
+impl DropOptions {
+    fn from_config() -> Self {
+        let config = config::current();
+        Self {
+            copy_to: config.file_drop_copy.then(|| config.file_drop_dir.clone()),
+            max_bytes: config.file_drop_max_mb.saturating_mul(1024 * 1024),
+        }
+    }
+}
+
+/// Handles a drop on `window` and publishes [`AppEvent::FilesDropped`].
+///
+/// Copying can be slow, so this runs on a blocking task.
+pub fn handle_drop(window: &str, paths: Vec<PathBuf>) {
+    let window = window.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = match filesystem_root() {
+            Ok(root) => root,
+            Err(e) => {
+                tracing::warn!("Ignoring dropped files: {}", e);
+                return;
+            }
+        };
+
+        let dropped = ingest(&paths, &root, &DropOptions::from_config());
+        tracing::debug!(
+            "Window '{}' received {} dropped files, rejected {}",
+            window,
+            dropped.files.len(),
+            dropped.rejected.len()
+        );
+        events::publish(AppEvent::FilesDropped {
+            window,
+            files: dropped.files,
+            rejected: dropped.rejected,
+        });
+    });
+}
+
+fn ingest(paths: &[PathBuf], root: &Path, options: &DropOptions) -> DroppedFiles {
+    let mut dropped = DroppedFiles::default();
+
+    for (index, path) in paths.iter().enumerate() {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if index >= MAX_DROPPED_FILES {
+            dropped.rejected.push(RejectedDrop {
+                name,
+                reason: format!("Only {} files can be dropped at once", MAX_DROPPED_FILES),
+            });
+            continue;
+        }
+
+        match accept(path, root, options) {
+            Ok(file) => dropped.files.push(file),
+            Err(reason) => dropped.rejected.push(RejectedDrop { name, reason }),
+        }
+    }
+    dropped
+}
+
+fn accept(path: &Path, root: &Path, options: &DropOptions) -> Result<FileInfo, String> {
+    let source = dunce::canonicalize(path).map_err(|_| "File no longer exists".to_string())?;
+    let metadata = fs::metadata(&source).map_err(|e| format!("Cannot read file: {}", e))?;
+
+    let Some(copy_to) = &options.copy_to else {
+        if !source.starts_with(root) {
+            return Err("File is outside the application data directory".to_string());
+        }
+        return Ok(build_file_info(&source, metadata, root));
+    };
+
+    if !metadata.is_file() {
+        return Err("Only files can be copied into the application".to_string());
+    }
+    if metadata.len() > options.max_bytes {
+        return Err(format!(
+            "File exceeds the {} MB drop limit",
+            options.max_bytes / (1024 * 1024)
+        ));
+    }
+
+    ensure_write_permitted(&source).map_err(|e| e.message)?;
+
+    let dir = root.join(copy_to);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create drop directory: {}", e))?;
+    let destination = unique_destination(&dir, &source);
+    fs::copy(&source, &destination).map_err(|e| format!("Failed to copy file: {}", e))?;
+
+    let metadata =
+        fs::metadata(&destination).map_err(|e| format!("Cannot read copied file: {}", e))?;
+    Ok(build_file_info(&destination, metadata, root))
+}
+
+/// Picks a free name in `dir` for `source`, adding `-1`, `-2`, ... before
+/// the extension when the name is taken. Spaces become dashes because scope
+/// paths cannot contain them.
+pub(crate) fn unique_destination(dir: &Path, source: &Path) -> PathBuf {
+    let clean = |value: &str| value.replace(' ', "-");
+    let stem = source
+        .file_stem()
+        .map(|stem| clean(&stem.to_string_lossy()))
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or_else(|| "file".to_string());
+    let extension = source
+        .extension()
+        .map(|extension| format!(".{}", clean(&extension.to_string_lossy())))
+        .unwrap_or_default();
+
+    let mut candidate = dir.join(format!("{}{}", stem, extension));
+    let mut counter = 1;
+    while candidate.exists() {
+        candidate = dir.join(format!("{}-{}{}", stem, counter, extension));
+        counter += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, PathBuf, TempDir) {
+        let scope = TempDir::new().unwrap();
+        let root = dunce::canonicalize(scope.path()).unwrap();
+        let outside = TempDir::new().unwrap();
+        (scope, root, outside)
+    }
+
+    fn copying() -> DropOptions {
+        DropOptions {
+            copy_to: Some("dropped".to_string()),
+            max_bytes: 16,
+        }
+    }
+
+    #[test]
+    fn copies_files_into_the_scope_with_unique_names() {
+        let (_scope, root, outside) = setup();
+        let source = outside.path().join("my notes.txt");
+        fs::write(&source, "hello").unwrap();
+
+        let first = ingest(&[source.clone()], &root, &copying());
+        let second = ingest(&[source], &root, &copying());
+
+        assert_eq!(first.files[0].name, "my-notes.txt");
+        assert!(first.files[0].path.starts_with("dropped"));
+        assert_eq!(second.files[0].name, "my-notes-1.txt");
+        assert_eq!(second.files[0].size, 5);
+        assert!(root.join("dropped/my-notes-1.txt").is_file());
+    }
+
+    #[test]
+    fn rejects_missing_oversized_and_directory_drops() {
+        let (_scope, root, outside) = setup();
+        let large = outside.path().join("large.bin");
+        fs::write(&large, vec![0u8; 32]).unwrap();
+
+        let dropped = ingest(
+            &[
+                large,
+                outside.path().join("missing.txt"),
+                outside.path().to_path_buf(),
+            ],
+            &root,
+            &copying(),
+        );
+
+        assert!(dropped.files.is_empty());
+        let names: Vec<&str> = dropped.rejected.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names[..2], ["large.bin", "missing.txt"]);
+        assert_eq!(dropped.rejected.len(), 3);
+    }
+
+    #[test]
+    fn without_copying_only_scoped_paths_are_accepted() {
+        let (_scope, root, outside) = setup();
+        fs::write(root.join("inside.txt"), "in").unwrap();
+        fs::write(outside.path().join("outside.txt"), "out").unwrap();
+        let options = DropOptions {
+            copy_to: None,
+            max_bytes: 16,
+        };
+
+        let dropped = ingest(
+            &[root.join("inside.txt"), outside.path().join("outside.txt")],
+            &root,
+            &options,
+        );
+
+        assert_eq!(dropped.files.len(), 1);
+        assert_eq!(dropped.files[0].path, "inside.txt");
+        assert_eq!(dropped.rejected[0].name, "outside.txt");
+    }
+
+    #[test]
+    fn limits_the_number_of_files_per_drop() {
+        let (_scope, root, outside) = setup();
+        let source = outside.path().join("a.txt");
+        fs::write(&source, "a").unwrap();
+
+        let paths = vec![source; MAX_DROPPED_FILES + 2];
+        let dropped = ingest(&paths, &root, &copying());
+        assert_eq!(dropped.files.len(), MAX_DROPPED_FILES);
+        assert_eq!(dropped.rejected.len(), 2);
+    }
+}
@@ -0,0 +1,207 @@
+//! "Open with" handling for associated file types.
+//!
+//! Files reach the app as launch arguments, as arguments forwarded from a
+//! second launch by the single-instance plugin, or as `Opened` run events on
+//! macOS. Paths with an extension registered under `bundle.fileAssociations`
+//! become `file:open-requested` events. Requests that arrive before the
+//! frontend has subscribed are queued until it calls
+//! `take_pending_file_opens`.
+
+use crate::events::{self, AppEvent};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::utils::config::FileAssociation;
+
+/// Requests queued before the frontend is ready.
+const MAX_PENDING: usize = 100;
+
+/// Lowercase extensions from `bundle.fileAssociations`.
+static EXTENSIONS: OnceCell<Vec<String>> = OnceCell::new();
+
+static QUEUE: Lazy<Mutex<OpenQueue>> = Lazy::new(|| Mutex::new(OpenQueue::default()));
+
+/// How a file reached the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenSource {
+    /// Passed when the app was started.
+    Launch,
+    /// Passed to the already running app.
+    Running,
+}
+
+/// A request to open an associated file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenRequest {
+    pub path: String,
+    pub name: String,
+    pub extension: String,
+    pub source: OpenSource,
+}
+
+/// Holds requests until the frontend is ready for events.
+#[derive(Debug, Default)]
+struct OpenQueue {
+    ready: bool,
+    pending: Vec<OpenRequest>,
+}
+
+impl OpenQueue {
+    /// Queues `request`, or returns it when it can be published right away.
+    fn push(&mut self, request: OpenRequest) -> Option<OpenRequest> {
+        if self.ready {
+            return Some(request);
+        }
+        if self.pending.len() >= MAX_PENDING {
+            tracing::warn!(
+                "Dropping file open request for '{}': queue is full",
+                request.name
+            );
+            return None;
+        }
+        self.pending.push(request);
+        None
+    }
+
+    /// Marks the frontend ready and returns the queued requests.
+    fn drain(&mut self) -> Vec<OpenRequest> {
+        self.ready = true;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// Records the associated extensions from the bundle configuration.
+pub fn init(associations: Option<&[FileAssociation]>) {
+    let extensions = associations
+        .into_iter()
+        .flatten()
+        .flat_map(|association| association.ext.iter())
+        .map(|ext| ext.0.trim_start_matches('.').to_lowercase())
+        .collect();
+    let _ = EXTENSIONS.set(extensions);
+}
+
+/// Handles command-line style arguments. The first argument is the binary.
+pub fn open_args(args: impl IntoIterator<Item = String>, cwd: &Path, source: OpenSource) {
+    let extensions = EXTENSIONS.get().map(Vec::as_slice).unwrap_or_default();
+    let paths = associated_paths(args.into_iter().skip(1).map(PathBuf::from), cwd, extensions);
+    open_paths(paths, source);
+}
+
+/// Handles file URLs delivered by the operating system.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub fn open_urls(urls: &[tauri::Url]) {
+    let extensions = EXTENSIONS.get().map(Vec::as_slice).unwrap_or_default();
+    let paths = urls.iter().filter_map(|url| url.to_file_path().ok());
+    let paths = associated_paths(paths, Path::new("/"), extensions);
+    open_paths(paths, OpenSource::Running);
+}
+
+/// Marks the frontend ready and returns requests received before it was.
+pub fn take_pending() -> Vec<OpenRequest> {
+    QUEUE
+        .lock()
+        .map(|mut queue| queue.drain())
+        .unwrap_or_default()
+}
+
+fn open_paths(paths: Vec<PathBuf>, source: OpenSource) {
+    for path in paths {
+        let request = OpenRequest {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            extension: extension_of(&path).unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+            source,
+        };
+        tracing::info!("File open requested: {}", request.name);
+
+        let ready = QUEUE.lock().ok().and_then(|mut queue| queue.push(request));
+        if let Some(request) = ready {
+            events::publish(AppEvent::FileOpenRequested(request));
+        }
+    }
+}
+
+/// Keeps existing files with an associated extension, resolving relative
+/// paths against `cwd`. Flags such as `--verbose` are skipped.
+fn associated_paths(
+    candidates: impl Iterator<Item = PathBuf>,
+    cwd: &Path,
+    extensions: &[String],
+) -> Vec<PathBuf> {
+    candidates
+        .filter(|path| !path.to_string_lossy().starts_with('-'))
+        .map(|path| {
+            if path.is_absolute() {
+                path
+            } else {
+                cwd.join(path)
+            }
+        })
+        .filter(|path| extension_of(path).is_some_and(|extension| extensions.contains(&extension)))
+        .filter_map(|path| dunce::canonicalize(path).ok())
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn request(name: &str) -> OpenRequest {
+        OpenRequest {
+            path: format!("/tmp/{}", name),
+            name: name.to_string(),
+            extension: "ezt".to_string(),
+            source: OpenSource::Launch,
+        }
+    }
+
+    #[test]
+    fn queues_requests_until_the_frontend_is_ready() {
+        let mut queue = OpenQueue::default();
+        assert!(queue.push(request("a.ezt")).is_none());
+        assert!(queue.push(request("b.ezt")).is_none());
+
+        let pending = queue.drain();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(queue.push(request("c.ezt")), Some(request("c.ezt")));
+        assert!(queue.drain().is_empty());
+    }
+
+    #[test]
+    fn keeps_existing_files_with_associated_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("doc.EZT"), "x").unwrap();
+        fs::write(dir.path().join("notes.txt"), "x").unwrap();
+        fs::create_dir(dir.path().join("folder.ezt")).unwrap();
+
+        let args = [
+            "doc.EZT",
+            "notes.txt",
+            "missing.ezt",
+            "folder.ezt",
+            "--flag",
+        ];
+        let paths = associated_paths(
+            args.iter().map(PathBuf::from),
+            dir.path(),
+            &["ezt".to_string()],
+        );
+
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].ends_with("doc.EZT"));
+    }
+}
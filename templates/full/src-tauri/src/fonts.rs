@@ -0,0 +1,180 @@
+//! Installed system font enumeration.
+//!
+//! Fonts are discovered with `fontdb`, which scans the platform font
+//! directories directly, so no native module or webview API is needed.
+//! Faces are grouped into families with one entry per weight and slant.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Slant of a font face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontSlant {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+/// One installed style of a font family.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontStyle {
+    /// Display name such as `Bold Italic`.
+    pub name: String,
+    /// CSS font weight, 1 to 1000.
+    pub weight: u16,
+    pub slant: FontSlant,
+}
+
+/// An installed font family and its styles, sorted by weight then slant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFamily {
+    pub name: String,
+    pub monospace: bool,
+    pub styles: Vec<FontStyle>,
+}
+
+/// A single face as reported by the font database.
+struct Face {
+    family: String,
+    weight: u16,
+    slant: FontSlant,
+    monospace: bool,
+}
+
+/// Lists installed font families sorted case-insensitively by name.
+///
+/// Scanning reads every font file header and can take a moment, so call this
+/// from a blocking task.
+pub fn list_system_fonts() -> Vec<FontFamily> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+
+    let faces = db.faces().filter_map(|face| {
+        let (family, _) = face.families.first()?;
+        Some(Face {
+            family: family.clone(),
+            weight: face.weight.0,
+            slant: match face.style {
+                fontdb::Style::Normal => FontSlant::Normal,
+                fontdb::Style::Italic => FontSlant::Italic,
+                fontdb::Style::Oblique => FontSlant::Oblique,
+            },
+            monospace: face.monospaced,
+        })
+    });
+    group_families(faces)
+}
+
+fn group_families(faces: impl IntoIterator<Item = Face>) -> Vec<FontFamily> {
+    let mut families: BTreeMap<String, FontFamily> = BTreeMap::new();
+
+    for face in faces {
+        let name = face.family.trim();
+        // Names starting with '.' are hidden system UI fonts on macOS.
+        if name.is_empty() || name.starts_with('.') {
+            continue;
+        }
+
+        let family = families
+            .entry(name.to_lowercase())
+            .or_insert_with(|| FontFamily {
+                name: name.to_string(),
+                monospace: face.monospace,
+                styles: Vec::new(),
+            });
+        family.monospace &= face.monospace;
+        if !family
+            .styles
+            .iter()
+            .any(|style| style.weight == face.weight && style.slant == face.slant)
+        {
+            family.styles.push(FontStyle {
+                name: style_name(face.weight, face.slant),
+                weight: face.weight,
+                slant: face.slant,
+            });
+        }
+    }
+
+    families
+        .into_values()
+        .map(|mut family| {
+            family
+                .styles
+                .sort_by_key(|style| (style.weight, style.slant));
+            family
+        })
+        .collect()
+}
+
+fn style_name(weight: u16, slant: FontSlant) -> String {
+    let weight_name = match weight {
+        0..=149 => "Thin",
+        150..=249 => "Extra Light",
+        250..=349 => "Light",
+        350..=449 => "Regular",
+        450..=549 => "Medium",
+        550..=649 => "Semi Bold",
+        650..=749 => "Bold",
+        750..=849 => "Extra Bold",
+        _ => "Black",
+    };
+    match (weight_name, slant) {
+        (name, FontSlant::Normal) => name.to_string(),
+        ("Regular", FontSlant::Italic) => "Italic".to_string(),
+        ("Regular", FontSlant::Oblique) => "Oblique".to_string(),
+        (name, FontSlant::Italic) => format!("{} Italic", name),
+        (name, FontSlant::Oblique) => format!("{} Oblique", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(family: &str, weight: u16, slant: FontSlant, monospace: bool) -> Face {
+        Face {
+            family: family.to_string(),
+            weight,
+            slant,
+            monospace,
+        }
+    }
+
+    #[test]
+    fn groups_faces_into_sorted_families() {
+        let families = group_families([
+            face("Roboto", 700, FontSlant::Italic, false),
+            face("Fira Code", 400, FontSlant::Normal, true),
+            face("Roboto", 400, FontSlant::Normal, false),
+            face("Roboto", 400, FontSlant::Normal, false),
+            face(".SF NS", 400, FontSlant::Normal, false),
+        ]);
+
+        let names: Vec<&str> = families.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["Fira Code", "Roboto"]);
+        assert!(families[0].monospace);
+
+        let styles: Vec<&str> = families[1].styles.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(styles, ["Regular", "Bold Italic"]);
+    }
+
+    #[test]
+    fn family_is_monospace_only_when_every_face_is() {
+        let families = group_families([
+            face("Mixed", 400, FontSlant::Normal, true),
+            face("Mixed", 700, FontSlant::Normal, false),
+        ]);
+        assert!(!families[0].monospace);
+    }
+
+    #[test]
+    fn names_styles_by_weight_and_slant() {
+        assert_eq!(style_name(400, FontSlant::Italic), "Italic");
+        assert_eq!(style_name(300, FontSlant::Normal), "Light");
+        assert_eq!(style_name(900, FontSlant::Oblique), "Black Oblique");
+    }
+}
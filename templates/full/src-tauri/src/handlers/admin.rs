@@ -0,0 +1,204 @@
+//! Admin overview command handlers.
+
+use crate::cache::{self, CacheStats};
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::database::{get_pool_ref, query_stats};
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::password::{self, HashingBenchmark};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Execute, PgPool};
+use std::time::Duration;
+
+/// Number of weeks, including the current one, covered by sign-up counts.
+const SIGNUP_WEEKS: i32 = 8;
+
+/// Hashing time `benchmark_password_hashing` aims for by default.
+const DEFAULT_HASH_TARGET_MS: u64 = 500;
+
+/// Longest hashing time `benchmark_password_hashing` accepts as a target.
+const MAX_HASH_TARGET_MS: u64 = 10_000;
+
+/// User totals for the admin overview.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserStats {
+    pub total: i64,
+    pub active: i64,
+    pub inactive: i64,
+    /// Sign-ups per week, oldest first; weeks without sign-ups are omitted.
+    pub new_per_week: Vec<WeeklyCount>,
+}
+
+/// Number of users created in the week starting at `week_start`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyCount {
+    pub week_start: DateTime<Utc>,
+    pub count: i64,
+}
+
+/// Number of stored log entries at `level`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelCount {
+    pub level: String,
+    pub count: i64,
+}
+
+/// Everything the admin overview screen shows, gathered in one call.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminStats {
+    pub users: UserStats,
+    pub logs_per_level: Vec<LevelCount>,
+    pub database_size_bytes: i64,
+    pub cache: CacheStats,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Returns user, log, database, and cache statistics for the admin overview.
+/// Requires the admin role.
+#[tauri::command]
+pub async fn get_admin_stats(context: CommandContext) -> AppResult<AdminStats> {
+    context.require_role(ADMIN_ROLE)?;
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let pool = pool.as_ref();
+
+    let (users, logs_per_level, database_size_bytes) =
+        tokio::try_join!(user_stats(pool), log_counts(pool), database_size(pool))
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    Ok(AdminStats {
+        users,
+        logs_per_level,
+        database_size_bytes,
+        cache: cache::cache_stats(),
+        generated_at: Utc::now(),
+    })
+}
+
+/// Times password hashing on this machine and recommends the highest
+/// Argon2 cost that hashes within `target_ms`, 500 by default. With `apply`
+/// new passwords are hashed at the recommended cost, persisted across
+/// restarts; existing hashes are upgraded as their users sign in.
+#[tauri::command]
+pub async fn benchmark_password_hashing(
+    context: CommandContext,
+    target_ms: Option<u64>,
+    apply: Option<bool>,
+) -> AppResult<HashingBenchmark> {
+    context.require_role(ADMIN_ROLE)?;
+    let target_ms = target_ms.unwrap_or(DEFAULT_HASH_TARGET_MS);
+    if target_ms == 0 || target_ms > MAX_HASH_TARGET_MS {
+        return Err(AppError::invalid_input(
+            "targetMs",
+            format!("Target must be between 1 and {} ms", MAX_HASH_TARGET_MS),
+        ));
+    }
+
+    let target = Duration::from_millis(target_ms);
+    let mut benchmark = tokio::task::spawn_blocking(move || password::benchmark(target))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Benchmark task failed: {}", e)))??;
+    if apply.unwrap_or(false) {
+        password::apply(benchmark.recommended.clone())?;
+        benchmark.applied = true;
+    }
+    Ok(benchmark)
+}
+
+async fn user_stats(pool: &PgPool) -> sqlx::Result<UserStats> {
+    let query = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "total!",
+               COUNT(*) FILTER (WHERE is_active) AS "active!",
+               COUNT(*) FILTER (WHERE NOT is_active) AS "inactive!"
+        FROM users
+        "#
+    );
+    let totals = query_stats::timed(query.sql(), query.fetch_one(pool)).await?;
+
+    let query = sqlx::query_as!(
+        WeeklyCount,
+        r#"
+        SELECT date_trunc('week', created_at) AS "week_start!",
+               COUNT(*) AS "count!"
+        FROM users
+        WHERE created_at >= date_trunc('week', CURRENT_TIMESTAMP) - make_interval(weeks => $1 - 1)
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        SIGNUP_WEEKS,
+    );
+    let new_per_week = query_stats::timed(query.sql(), query.fetch_all(pool)).await?;
+
+    Ok(UserStats {
+        total: totals.total,
+        active: totals.active,
+        inactive: totals.inactive,
+        new_per_week,
+    })
+}
+
+async fn log_counts(pool: &PgPool) -> sqlx::Result<Vec<LevelCount>> {
+    let query = sqlx::query_as!(
+        LevelCount,
+        r#"
+        SELECT level, COUNT(*) AS "count!"
+        FROM app_logs
+        GROUP BY level
+        ORDER BY level
+        "#
+    );
+    query_stats::timed(query.sql(), query.fetch_all(pool)).await
+}
+
+async fn database_size(pool: &PgPool) -> sqlx::Result<i64> {
+    let query = sqlx::query_scalar!(r#"SELECT pg_database_size(current_database()) AS "bytes!""#);
+    query_stats::timed(query.sql(), query.fetch_one(pool)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::test_support::{LogFactory, UserFactory};
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    #[tokio::test]
+    #[serial]
+    async fn summarizes_users_and_logs() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        UserFactory::new().insert().await?;
+        LogFactory::new()
+            .level("error")
+            .with_new_user()
+            .insert()
+            .await?;
+
+        let user = CommandContext::for_user(uuid::Uuid::new_v4(), Vec::new());
+        assert!(get_admin_stats(user).await.is_err());
+
+        let stats = get_admin_stats(CommandContext::internal()).await?;
+        assert_eq!(stats.users.total, 2);
+        assert_eq!(stats.users.active, 2);
+        assert_eq!(stats.users.inactive, 0);
+        assert_eq!(
+            stats
+                .users
+                .new_per_week
+                .iter()
+                .map(|w| w.count)
+                .sum::<i64>(),
+            2
+        );
+        assert_eq!(stats.logs_per_level.len(), 1);
+        assert_eq!(stats.logs_per_level[0].level, "error");
+        assert!(stats.database_size_bytes > 0);
+        Ok(())
+    }
+}
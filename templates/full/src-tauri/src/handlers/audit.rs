@@ -0,0 +1,19 @@
+//! Audit log command handlers.
+
+use crate::audit;
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::database::get_pool_ref;
+use crate::errors::{AppResult, ErrorCode, IntoAppError};
+use crate::models::{AuditEntry, AuditQuery};
+
+/// Lists audit entries matching `query`, newest first. Requires the admin
+/// role.
+#[tauri::command]
+pub async fn get_audit_log(
+    context: CommandContext,
+    query: AuditQuery,
+) -> AppResult<Vec<AuditEntry>> {
+    context.require_role(ADMIN_ROLE)?;
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    audit::list(pool.as_ref(), query).await
+}
@@ -0,0 +1,79 @@
+//! Backup and restore command handlers.
+
+use crate::backup::{self, BackupInfo, RestoreSummary};
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::config;
+use crate::errors::{AppError, AppResult};
+use crate::maintenance;
+use crate::operations;
+use uuid::Uuid;
+
+/// Creates a backup immediately, applying the configured retention.
+/// Requires the admin role.
+///
+/// Other commands are paused by maintenance mode until it finishes.
+#[tauri::command]
+pub async fn create_backup(
+    context: CommandContext,
+    app: tauri::AppHandle,
+    operation_id: Option<Uuid>,
+) -> AppResult<BackupInfo> {
+    context.require_role(ADMIN_ROLE)?;
+    let version = app.package_info().version.to_string();
+    let vault_path = backup::vault_path(&app);
+    let retention = config::current().backup_retention;
+
+    let _maintenance = maintenance::hold("backup");
+    operations::track(
+        "backup",
+        operation_id,
+        backup::create_backup(&version, vault_path.as_deref(), retention),
+    )
+    .await
+}
+
+/// Lists available backups, newest first.
+#[tauri::command]
+pub async fn list_backups() -> AppResult<Vec<BackupInfo>> {
+    backup::list_backups()
+}
+
+/// Restores a backup; the vault snapshot is only replaced when `include_vault` is set.
+/// Requires the admin role.
+///
+/// Other commands are paused by maintenance mode until it finishes.
+#[tauri::command]
+pub async fn restore_backup(
+    context: CommandContext,
+    app: tauri::AppHandle,
+    name: String,
+    include_vault: Option<bool>,
+    operation_id: Option<Uuid>,
+) -> AppResult<RestoreSummary> {
+    context.require_role(ADMIN_ROLE)?;
+    let version = app.package_info().version.to_string();
+    let vault_path = backup::vault_path(&app).filter(|_| include_vault.unwrap_or(false));
+    let retention = config::current().backup_retention;
+
+    let _maintenance = maintenance::hold("restore");
+    operations::track(
+        "restore",
+        operation_id,
+        backup::restore_backup(&name, &version, vault_path.as_deref(), retention),
+    )
+    .await
+}
+
+/// Copies a backup onto a mounted removable device, returning the written
+/// path on the device. Requires the admin role.
+#[tauri::command]
+pub async fn export_backup_to_device(
+    context: CommandContext,
+    name: String,
+    mount_point: String,
+) -> AppResult<String> {
+    context.require_role(ADMIN_ROLE)?;
+    tokio::task::spawn_blocking(move || backup::export_to_device(&name, &mount_point))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Backup export task failed: {}", e)))?
+}
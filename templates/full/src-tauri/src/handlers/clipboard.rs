@@ -0,0 +1,55 @@
+//! Clipboard image and history command handlers.
+
+use crate::clipboard::{self, ClipboardEntry};
+use crate::clipboard_image::{self, ClipboardImage};
+use crate::errors::{AppError, AppResult};
+use tauri::{AppHandle, WebviewWindow};
+
+/// Returns the clipboard image as base64 PNG, or `None` when there is none.
+#[tauri::command]
+pub async fn read_clipboard_image(app: AppHandle) -> AppResult<Option<ClipboardImage>> {
+    clipboard_image::read(&app)
+}
+
+/// Places a base64 PNG on the clipboard.
+#[tauri::command]
+pub async fn write_clipboard_image(app: AppHandle, png: String) -> AppResult<()> {
+    clipboard_image::write(&app, &png)
+}
+
+/// Captures the calling window, copies it to the clipboard, and returns it.
+#[tauri::command]
+pub async fn copy_window_screenshot_to_clipboard(
+    app: AppHandle,
+    window: WebviewWindow,
+) -> AppResult<ClipboardImage> {
+    tokio::task::spawn_blocking(move || clipboard_image::copy_window_screenshot(&app, &window))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Screenshot task failed: {}", e)))?
+}
+
+/// Unlocks clipboard history with the vault password and starts recording.
+///
+/// Returns the number of entries already stored.
+#[tauri::command]
+pub async fn start_clipboard_history(app: AppHandle, password: String) -> AppResult<usize> {
+    clipboard::start(&app, &password)
+}
+
+/// Stops recording and locks clipboard history.
+#[tauri::command]
+pub async fn stop_clipboard_history() -> AppResult<bool> {
+    clipboard::stop()
+}
+
+/// Lists recorded clipboard entries, most recent first.
+#[tauri::command]
+pub async fn list_clipboard_history() -> AppResult<Vec<ClipboardEntry>> {
+    clipboard::entries()
+}
+
+/// Deletes all recorded clipboard entries.
+#[tauri::command]
+pub async fn clear_clipboard_history() -> AppResult<usize> {
+    clipboard::clear()
+}
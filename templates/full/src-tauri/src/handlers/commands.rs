@@ -0,0 +1,10 @@
+//! Command introspection handlers for dev tools and command palettes.
+
+use crate::registry::{self, CommandInfo};
+
+/// Lists every command the frontend can invoke with its module, rate-limit
+/// class, required permission and arguments.
+#[tauri::command]
+pub async fn list_commands() -> Result<Vec<CommandInfo>, String> {
+    Ok(registry::registered_commands())
+}
@@ -0,0 +1,26 @@
+//! Companion WebSocket server command handlers.
+
+use crate::command_context::CommandContext;
+use crate::companion::{self, CompanionServerStatus};
+use crate::errors::AppResult;
+
+/// Returns whether the companion server is running, its address, and
+/// whether a companion is paired.
+#[tauri::command]
+pub async fn get_companion_server_status() -> AppResult<CompanionServerStatus> {
+    Ok(companion::status())
+}
+
+/// Creates the pairing token companions authenticate with, replacing any
+/// previous one. Companions act as the signed-in caller. Requires unlocked
+/// secrets.
+#[tauri::command]
+pub async fn create_companion_pairing_token(context: CommandContext) -> AppResult<String> {
+    companion::create_pairing_token(context.require_user()?)
+}
+
+/// Removes the pairing token; returns whether one existed.
+#[tauri::command]
+pub async fn revoke_companion_pairing() -> AppResult<bool> {
+    companion::revoke_pairing()
+}
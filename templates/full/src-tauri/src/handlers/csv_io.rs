@@ -0,0 +1,24 @@
+//! CSV command handlers.
+
+use crate::csv_io::{self, CsvPage, CsvParseOptions, CsvWriteOptions, CsvWriteSummary};
+use crate::errors::{AppError, AppResult};
+
+/// Parses one page of rows from a CSV file in the fs scope.
+#[tauri::command]
+pub async fn parse_csv(path: String, options: Option<CsvParseOptions>) -> AppResult<CsvPage> {
+    tokio::task::spawn_blocking(move || csv_io::parse(&path, options.unwrap_or_default()))
+        .await
+        .map_err(|e| AppError::internal_error(format!("CSV parse task failed: {}", e)))?
+}
+
+/// Writes rows to a CSV file in the fs scope, optionally appending.
+#[tauri::command]
+pub async fn write_csv(
+    path: String,
+    rows: Vec<serde_json::Value>,
+    options: Option<CsvWriteOptions>,
+) -> AppResult<CsvWriteSummary> {
+    tokio::task::spawn_blocking(move || csv_io::write(&path, rows, options.unwrap_or_default()))
+        .await
+        .map_err(|e| AppError::internal_error(format!("CSV write task failed: {}", e)))?
+}
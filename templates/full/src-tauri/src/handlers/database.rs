@@ -1,23 +1,24 @@
 //! Database connection and health check handlers.
 
+use crate::config::DatabaseBackend;
+use crate::database::locks;
+use crate::database::query_stats::{self, QueryStats};
 use crate::database::{get_pool_ref, test_connection};
 use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::maintenance;
+use crate::models::DatabaseStatus;
+use crate::operations;
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
-
-/// Database connection status information.
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct DatabaseStatus {
-    pub connected: bool,
-    pub database_name: Option<String>,
-    pub version: Option<String>,
-    pub error: Option<String>,
-}
+use uuid::Uuid;
 
 /// Checks database connectivity and returns connection status information.
 #[tauri::command]
 pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::check_database_connection().await;
+    }
+
     tracing::info!("Checking database connection");
 
     let pool = get_pool_ref()
@@ -35,6 +36,7 @@ pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
                 Ok((db_name, version)) => {
                     tracing::info!("Database connection successful: {} ({})", db_name, version);
                     Ok(DatabaseStatus {
+                        backend: DatabaseBackend::Postgres,
                         connected: true,
                         database_name: Some(db_name),
                         version: Some(version),
@@ -44,6 +46,7 @@ pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
                 Err(e) => {
                     tracing::warn!("Connected to database but failed to get info: {}", e);
                     Ok(DatabaseStatus {
+                        backend: DatabaseBackend::Postgres,
                         connected: true,
                         database_name: None,
                         version: None,
@@ -55,6 +58,7 @@ pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
         Err(e) => {
             tracing::error!("Database connection test failed: {}", e);
             Ok(DatabaseStatus {
+                backend: DatabaseBackend::Postgres,
                 connected: false,
                 database_name: None,
                 version: None,
@@ -64,32 +68,78 @@ pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
     }
 }
 
+/// Connects the database pool, using `password` from the Stronghold vault
+/// for `DB_HOST` based configuration when given.
 #[tauri::command]
-pub async fn initialize_database() -> AppResult<String> {
+pub async fn initialize_database(password: Option<String>) -> AppResult<String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::initialize_database(password).await;
+    }
+
     tracing::info!("Initializing database");
 
-    // Note: This would need access to stronghold, but we can't easily pass it through
-    // For now, we'll return an error indicating it needs to be called differently
-    Err(AppError::new(
-        ErrorCode::InvalidInput,
-        "Database initialization requires stronghold access".to_string()
-    ))
+    crate::database::connection::initialize_database_with_password(password.as_deref())
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+    crate::events::publish(crate::events::AppEvent::DatabaseConnected);
+
+    Ok("Database initialized successfully".to_string())
 }
 
 #[tauri::command]
-pub async fn run_migrations() -> AppResult<String> {
+pub async fn run_migrations(operation_id: Option<Uuid>) -> AppResult<String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::run_migrations(operation_id).await;
+    }
+
     tracing::info!("Running database migrations");
 
     let pool = get_pool_ref()
         .into_app_error(ErrorCode::DatabaseConnection)?;
 
-    crate::database::migrations::run_migrations(pool.as_ref())
-        .await
-        .into_app_error(ErrorCode::DatabaseMigration)
-        .map(|_| {
-            tracing::info!("Migrations completed successfully");
-            "Migrations completed successfully".to_string()
-        })
+    let _maintenance = maintenance::hold("migrations");
+    operations::track("migrations", operation_id, async {
+        crate::database::migrations::run_migrations(pool.as_ref())
+            .await
+            .into_app_error(ErrorCode::DatabaseMigration)
+    })
+    .await
+    .map(|_| {
+        tracing::info!("Migrations completed successfully");
+        "Migrations completed successfully".to_string()
+    })
+}
+
+/// Returns the slowest and most frequent statements recorded since startup.
+#[tauri::command]
+pub async fn get_query_stats(limit: Option<usize>) -> AppResult<QueryStats> {
+    Ok(query_stats::stats(limit.unwrap_or(20).clamp(1, 100)))
+}
+
+/// Clears recorded query statistics.
+#[tauri::command]
+pub async fn reset_query_stats() -> AppResult<()> {
+    query_stats::reset();
+    Ok(())
+}
+
+/// Takes the named advisory lock shared with other instances using the
+/// database, waiting up to `wait_ms` while it is busy. Returns `false` when
+/// another instance still holds it. The lock is kept until released.
+#[tauri::command]
+pub async fn acquire_advisory_lock(name: String, wait_ms: Option<u64>) -> AppResult<bool> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let wait = wait_ms.map(|ms| std::time::Duration::from_millis(ms.min(60_000)));
+    locks::hold(pool.as_ref(), &name, wait).await
+}
+
+/// Releases an advisory lock taken with `acquire_advisory_lock`; returns
+/// whether it was held.
+#[tauri::command]
+pub async fn release_advisory_lock(name: String) -> AppResult<bool> {
+    locks::release_held(&name).await
 }
 #[cfg(test)]
 mod tests {
@@ -121,10 +171,10 @@ mod tests {
         let pool = pool().await?;
         reset_all_tables(pool.as_ref()).await?;
 
-        run_migrations()
+        run_migrations(None)
             .await
             .expect("first migration run should succeed");
-        run_migrations()
+        run_migrations(None)
             .await
             .expect("second migration run should be idempotent");
         Ok(())
@@ -0,0 +1,23 @@
+//! Local network discovery command handlers.
+
+use crate::discovery::{self, DiscoveryStatus, Peer};
+use crate::errors::AppResult;
+use tauri::AppHandle;
+
+/// Starts advertising this instance on the LAN and browsing for peers.
+#[tauri::command]
+pub async fn start_discovery(app: AppHandle) -> AppResult<DiscoveryStatus> {
+    discovery::start(&app.package_info().version.to_string())
+}
+
+/// Stops discovery and forgets known peers.
+#[tauri::command]
+pub async fn stop_discovery() -> AppResult<bool> {
+    discovery::stop()
+}
+
+/// Lists peers found on the LAN, most recently seen first.
+#[tauri::command]
+pub async fn list_peers() -> AppResult<Vec<Peer>> {
+    Ok(discovery::peers())
+}
@@ -0,0 +1,34 @@
+//! Outgoing email command handlers.
+
+use crate::email::{self, EmailDelivery, SmtpSettings};
+use crate::errors::AppResult;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Loads SMTP settings read from the Stronghold vault by the frontend.
+#[tauri::command]
+pub async fn configure_smtp(settings: SmtpSettings) -> AppResult<()> {
+    email::configure(settings)
+}
+
+/// Renders a template and queues it for delivery; returns the delivery id.
+#[tauri::command]
+pub async fn send_email(
+    to: String,
+    template: String,
+    data: HashMap<String, String>,
+) -> AppResult<Uuid> {
+    email::send_template(&to, &template, &data)
+}
+
+/// Registers or replaces a named email template.
+#[tauri::command]
+pub async fn register_email_template(name: String, subject: String, body: String) -> AppResult<()> {
+    email::register_template(&name, &subject, &body)
+}
+
+/// Returns recent email deliveries, newest first.
+#[tauri::command]
+pub async fn get_email_deliveries() -> AppResult<Vec<EmailDelivery>> {
+    Ok(email::deliveries())
+}
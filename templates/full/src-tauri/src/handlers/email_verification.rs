@@ -0,0 +1,33 @@
+//! Email verification command handlers.
+
+use crate::database::get_pool_ref;
+use crate::email_verification;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::models::PublicUser;
+use crate::repository::{PgUserRepository, UserRepository};
+use uuid::Uuid;
+
+/// Emails a verification code to a user; returns the email delivery id.
+#[tauri::command]
+pub async fn send_verification_email(user_id: String) -> AppResult<Uuid> {
+    let user_id = Uuid::parse_str(&user_id)
+        .map_err(|_| AppError::invalid_input("user_id", "Invalid user ID"))?;
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    email_verification::send(pool.as_ref(), user_id).await
+}
+
+/// Verifies the email address a code was sent to and returns the user.
+///
+/// Fails with `TOKEN_EXPIRED` or `UNAUTHORIZED` when the code can no longer
+/// be used.
+#[tauri::command]
+pub async fn verify_email(token: String) -> AppResult<PublicUser> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let user_id = email_verification::verify(pool.as_ref(), &token).await?;
+    PgUserRepository::new(pool.as_ref())
+        .find(user_id)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?
+        .map(PublicUser::from)
+        .ok_or_else(|| AppError::not_found("User"))
+}
@@ -0,0 +1,32 @@
+//! In-app feedback command handlers.
+
+use crate::errors::AppResult;
+use crate::feedback::{self, FeedbackOptions, FeedbackReceipt};
+use tauri::AppHandle;
+
+/// Packages a bug report with optional logs, health snapshot, and screenshot.
+///
+/// The report is uploaded only when `upload` is true and a feedback endpoint
+/// is configured; otherwise it is saved inside the filesystem scope.
+#[tauri::command]
+pub async fn submit_feedback(
+    app: AppHandle,
+    message: String,
+    include_logs: bool,
+    include_system_info: bool,
+    screenshot: Option<String>,
+    upload: Option<bool>,
+) -> AppResult<FeedbackReceipt> {
+    let version = app.package_info().version.to_string();
+    feedback::submit(
+        &message,
+        &version,
+        FeedbackOptions {
+            include_logs,
+            include_system_info,
+            screenshot,
+            upload: upload.unwrap_or(false),
+        },
+    )
+    .await
+}
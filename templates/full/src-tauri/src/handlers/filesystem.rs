@@ -1,21 +1,34 @@
 //! Secure filesystem access handlers with path traversal protection.
 
+use crate::command_context::CommandContext;
+use crate::config;
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::media_protocol::content_type;
+use crate::operations::{self, Operation};
 use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
 use dunce::canonicalize;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
+use std::io::{self, Read, Write};
 use std::path::{Component, Path, PathBuf};
 use std::time::SystemTime;
+use uuid::Uuid;
 
 const ROOT_ENV_OVERRIDE: &str = "TAURI_FS_ROOT";
 const APP_QUALIFIER: &str = "com";
 const APP_ORGANIZATION: &str = "tavuc";
 const APP_NAME: &str = "tavuc-boilerplate";
+/// Upper bound on threads reading metadata for a single listing.
+const MAX_METADATA_WORKERS: usize = 8;
+/// Directories smaller than this are read on the calling thread.
+const PARALLEL_METADATA_THRESHOLD: usize = 256;
+/// Chunk size of copies that report progress.
+const COPY_CHUNK_BYTES: usize = 1024 * 1024;
 
 /// File or directory metadata information.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
     pub name: String,
     pub path: String,
@@ -34,13 +47,13 @@ pub struct DirectoryListing {
 }
 
 /// Internal context for filesystem operations with root path validation.
-struct FsContext {
-    root: PathBuf,
-    path: PathBuf,
+pub(crate) struct FsContext {
+    pub(crate) root: PathBuf,
+    pub(crate) path: PathBuf,
 }
 
 impl FsContext {
-    fn relative_display(&self) -> String {
+    pub(crate) fn relative_display(&self) -> String {
         self.path
             .strip_prefix(&self.root)
             .ok()
@@ -52,6 +65,10 @@ impl FsContext {
 /// Reads the contents of a text file within the allowed filesystem scope.
 #[tauri::command]
 pub async fn read_text_file(path: String) -> Result<String, String> {
+    run_blocking(move || read_text_file_blocking(path)).await
+}
+
+fn read_text_file_blocking(path: String) -> Result<String, String> {
     if path.trim().is_empty() {
         return Err("Path cannot be empty".to_string());
     }
@@ -76,6 +93,10 @@ pub async fn read_text_file(path: String) -> Result<String, String> {
 
 #[tauri::command]
 pub async fn write_text_file(path: String, content: String) -> Result<String, String> {
+    run_blocking(move || write_text_file_blocking(path, content)).await
+}
+
+fn write_text_file_blocking(path: String, content: String) -> Result<String, String> {
     if path.trim().is_empty() {
         return Err("Path cannot be empty".to_string());
     }
@@ -86,6 +107,8 @@ pub async fn write_text_file(path: String, content: String) -> Result<String, St
         return Err("Refusing to overwrite the filesystem root".to_string());
     }
 
+    ensure_write_permitted(&context.path).map_err(|e| e.to_string())?;
+
     if let Some(parent) = context.path.parent() {
         fs::create_dir_all(parent).map_err(|e| {
             format!(
@@ -112,6 +135,10 @@ pub async fn write_text_file(path: String, content: String) -> Result<String, St
 
 #[tauri::command]
 pub async fn append_text_file(path: String, content: String) -> Result<String, String> {
+    run_blocking(move || append_text_file_blocking(path, content)).await
+}
+
+fn append_text_file_blocking(path: String, content: String) -> Result<String, String> {
     use std::fs::OpenOptions;
     use std::io::Write;
 
@@ -125,6 +152,8 @@ pub async fn append_text_file(path: String, content: String) -> Result<String, S
         return Err("Refusing to modify the filesystem root".to_string());
     }
 
+    ensure_write_permitted(&context.path).map_err(|e| e.to_string())?;
+
     if let Some(parent) = context.path.parent() {
         fs::create_dir_all(parent).map_err(|e| {
             format!(
@@ -161,8 +190,29 @@ pub async fn append_text_file(path: String, content: String) -> Result<String, S
     ))
 }
 
+/// Deletes a file or directory in the filesystem scope and records the
+/// deletion in the audit log.
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<String, String> {
+pub async fn delete_file(context: CommandContext, path: String) -> Result<String, String> {
+    let target = path.clone();
+    let message = run_blocking(move || delete_file_blocking(target)).await?;
+    audit_deletion(&context, &path).await;
+    Ok(message)
+}
+
+#[cfg(feature = "database")]
+async fn audit_deletion(context: &CommandContext, path: &str) {
+    use crate::audit;
+
+    let details = serde_json::json!({ "path": path });
+    audit::record_detached(context.actor_id(), audit::FILE_DELETED, None, details).await;
+}
+
+/// Without the database there is no audit log to write to.
+#[cfg(not(feature = "database"))]
+async fn audit_deletion(_context: &CommandContext, _path: &str) {}
+
+fn delete_file_blocking(path: String) -> Result<String, String> {
     if path.trim().is_empty() {
         return Err("Path cannot be empty".to_string());
     }
@@ -209,6 +259,10 @@ pub async fn delete_file(path: String) -> Result<String, String> {
 
 #[tauri::command]
 pub async fn create_directory(path: String) -> Result<String, String> {
+    run_blocking(move || create_directory_blocking(path)).await
+}
+
+fn create_directory_blocking(path: String) -> Result<String, String> {
     if path.trim().is_empty() {
         return Err("Path cannot be empty".to_string());
     }
@@ -233,8 +287,22 @@ pub async fn create_directory(path: String) -> Result<String, String> {
     ))
 }
 
+/// Lists a directory within the filesystem scope.
+///
+/// Set `include_metadata` to `false` for a fast listing that only reports
+/// names and entry types; sizes and timestamps are then omitted.
 #[tauri::command]
-pub async fn list_directory(path: String) -> Result<DirectoryListing, String> {
+pub async fn list_directory(
+    path: String,
+    include_metadata: Option<bool>,
+) -> Result<DirectoryListing, String> {
+    run_blocking(move || list_directory_blocking(path, include_metadata.unwrap_or(true))).await
+}
+
+fn list_directory_blocking(
+    path: String,
+    include_metadata: bool,
+) -> Result<DirectoryListing, String> {
     let context = resolve_relative_path(&path)?;
 
     if !context.path.exists() {
@@ -251,25 +319,34 @@ pub async fn list_directory(path: String) -> Result<DirectoryListing, String> {
         ));
     }
 
-    let entries = fs::read_dir(&context.path).map_err(|e| {
-        format!(
-            "Failed to read directory '{}': {}",
-            context.relative_display(),
-            e
-        )
-    })?;
-
-    let mut file_infos = Vec::new();
-
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        let entry_path = entry.path();
-        let metadata = entry
-            .metadata()
-            .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let entries = fs::read_dir(&context.path)
+        .map_err(|e| {
+            format!(
+                "Failed to read directory '{}': {}",
+                context.relative_display(),
+                e
+            )
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read directory entry: {}", e))?;
 
-        file_infos.push(build_file_info(&entry_path, metadata, &context.root));
-    }
+    let mut file_infos = if include_metadata {
+        collect_metadata(&entries, &context.root)?
+    } else {
+        entries
+            .iter()
+            .map(|entry| {
+                let file_type = entry
+                    .file_type()
+                    .map_err(|e| format!("Failed to read file type: {}", e))?;
+                Ok(build_file_info_without_metadata(
+                    &entry.path(),
+                    file_type,
+                    &context.root,
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?
+    };
 
     file_infos.sort_by(|a, b| match (a.is_dir, b.is_dir) {
         (true, false) => std::cmp::Ordering::Less,
@@ -285,12 +362,20 @@ pub async fn list_directory(path: String) -> Result<DirectoryListing, String> {
 
 #[tauri::command]
 pub async fn file_exists(path: String) -> Result<bool, String> {
+    run_blocking(move || file_exists_blocking(path)).await
+}
+
+fn file_exists_blocking(path: String) -> Result<bool, String> {
     let context = resolve_relative_path(&path)?;
     Ok(context.path.exists())
 }
 
 #[tauri::command]
 pub async fn get_file_info(path: String) -> Result<FileInfo, String> {
+    run_blocking(move || get_file_info_blocking(path)).await
+}
+
+fn get_file_info_blocking(path: String) -> Result<FileInfo, String> {
     let context = resolve_existing_path(&path)?;
     let metadata = context.path.metadata().map_err(|e| {
         format!(
@@ -303,8 +388,28 @@ pub async fn get_file_info(path: String) -> Result<FileInfo, String> {
     Ok(build_file_info(&context.path, metadata, &context.root))
 }
 
+/// Copies a file, reporting bytes copied as operation progress.
 #[tauri::command]
-pub async fn copy_file(source: String, destination: String) -> Result<String, String> {
+pub async fn copy_file(
+    source: String,
+    destination: String,
+    operation_id: Option<Uuid>,
+) -> Result<String, String> {
+    operations::track("file-copy", operation_id, async move {
+        let operation = operations::current();
+        run_blocking(move || copy_file_blocking(source, destination, operation.as_ref()))
+            .await
+            .map_err(|e| AppError::new(ErrorCode::FileWrite, e))
+    })
+    .await
+    .map_err(|e| e.message)
+}
+
+fn copy_file_blocking(
+    source: String,
+    destination: String,
+    operation: Option<&Operation>,
+) -> Result<String, String> {
     if source.trim().is_empty() || destination.trim().is_empty() {
         return Err("Source and destination paths cannot be empty".to_string());
     }
@@ -328,6 +433,8 @@ pub async fn copy_file(source: String, destination: String) -> Result<String, St
         return Err("Destination path cannot be the filesystem root".to_string());
     }
 
+    ensure_write_permitted(&destination_context.path).map_err(|e| e.to_string())?;
+
     if let Some(parent) = destination_context.path.parent() {
         fs::create_dir_all(parent).map_err(|e| {
             format!(
@@ -338,7 +445,13 @@ pub async fn copy_file(source: String, destination: String) -> Result<String, St
         })?;
     }
 
-    fs::copy(&source_context.path, &destination_context.path).map_err(|e| {
+    match operation {
+        Some(operation) => {
+            copy_with_progress(&source_context.path, &destination_context.path, operation)
+        }
+        None => fs::copy(&source_context.path, &destination_context.path).map(|_| ()),
+    }
+    .map_err(|e| {
         format!(
             "Failed to copy '{}' to '{}': {}",
             source_context.relative_display(),
@@ -356,6 +469,10 @@ pub async fn copy_file(source: String, destination: String) -> Result<String, St
 
 #[tauri::command]
 pub async fn move_file(source: String, destination: String) -> Result<String, String> {
+    run_blocking(move || move_file_blocking(source, destination)).await
+}
+
+fn move_file_blocking(source: String, destination: String) -> Result<String, String> {
     if source.trim().is_empty() || destination.trim().is_empty() {
         return Err("Source and destination paths cannot be empty".to_string());
     }
@@ -372,6 +489,8 @@ pub async fn move_file(source: String, destination: String) -> Result<String, St
         return Err("Destination path cannot be the filesystem root".to_string());
     }
 
+    ensure_write_permitted(&destination_context.path).map_err(|e| e.to_string())?;
+
     if let Some(parent) = destination_context.path.parent() {
         fs::create_dir_all(parent).map_err(|e| {
             format!(
@@ -398,7 +517,90 @@ pub async fn move_file(source: String, destination: String) -> Result<String, St
     ))
 }
 
-fn filesystem_root() -> Result<PathBuf, String> {
+/// Reads entry metadata, splitting large directories across a bounded set of
+/// worker threads since each lookup is a separate syscall.
+fn collect_metadata(entries: &[fs::DirEntry], root: &Path) -> Result<Vec<FileInfo>, String> {
+    let read = |entry: &fs::DirEntry| {
+        entry
+            .metadata()
+            .map(|metadata| build_file_info(&entry.path(), metadata, root))
+            .map_err(|e| format!("Failed to read metadata: {}", e))
+    };
+
+    let workers = std::thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+        .min(MAX_METADATA_WORKERS);
+    if workers <= 1 || entries.len() < PARALLEL_METADATA_THRESHOLD {
+        return entries.iter().map(read).collect();
+    }
+
+    let chunk_size = entries.len().div_ceil(workers);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = entries
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || chunk.iter().map(read).collect::<Result<Vec<_>, String>>())
+            })
+            .collect();
+
+        let mut infos = Vec::with_capacity(entries.len());
+        for handle in handles {
+            let chunk = handle
+                .join()
+                .map_err(|_| "Metadata worker panicked".to_string())??;
+            infos.extend(chunk);
+        }
+        Ok(infos)
+    })
+}
+
+/// Copies `source` in chunks, reporting bytes copied to `operation`. A
+/// cancelled copy removes the partial destination file.
+fn copy_with_progress(source: &Path, destination: &Path, operation: &Operation) -> io::Result<()> {
+    let mut reader = fs::File::open(source)?;
+    let metadata = reader.metadata()?;
+    let total = metadata.len();
+    let mut writer = fs::File::create(destination)?;
+    let mut buffer = vec![0; COPY_CHUNK_BYTES];
+    let mut copied = 0;
+
+    loop {
+        if operation.is_cancelled() {
+            drop(writer);
+            let _ = fs::remove_file(destination);
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "copy was cancelled",
+            ));
+        }
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        copied += read as u64;
+        operation.report(copied, Some(total), None);
+    }
+
+    fs::set_permissions(destination, metadata.permissions())
+}
+
+/// Runs blocking filesystem work on the blocking thread pool so slow disks
+/// and network shares don't stall other commands on the async runtime.
+async fn run_blocking<T, F>(work: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("Filesystem task failed: {}", e))?
+}
+
+pub(crate) fn filesystem_root() -> Result<PathBuf, String> {
     let base = if let Ok(override_path) = env::var(ROOT_ENV_OVERRIDE) {
         PathBuf::from(override_path)
     } else if let Some(project_dirs) = ProjectDirs::from(APP_QUALIFIER, APP_ORGANIZATION, APP_NAME)
@@ -425,7 +627,7 @@ fn filesystem_root() -> Result<PathBuf, String> {
     })
 }
 
-fn resolve_relative_path(raw: &str) -> Result<FsContext, String> {
+pub(crate) fn resolve_relative_path(raw: &str) -> Result<FsContext, String> {
     if raw.contains(' ') {
         return Err("Path contains invalid characters".to_string());
     }
@@ -476,7 +678,7 @@ fn resolve_relative_path(raw: &str) -> Result<FsContext, String> {
     })
 }
 
-fn resolve_existing_path(raw: &str) -> Result<FsContext, String> {
+pub(crate) fn resolve_existing_path(raw: &str) -> Result<FsContext, String> {
     let context = resolve_relative_path(raw)?;
 
     if !context.path.exists() {
@@ -489,14 +691,34 @@ fn resolve_existing_path(raw: &str) -> Result<FsContext, String> {
     Ok(context)
 }
 
-fn build_file_info(path: &Path, metadata: fs::Metadata, root: &Path) -> FileInfo {
-    let relative = path.strip_prefix(root).unwrap_or(path);
-    let display_path = relative_path_to_string(relative);
-    let name = relative
+/// Checks the name of `path` against the configured file write policy,
+/// failing with `FILE_PERMISSION` when it may not be written.
+pub(crate) fn ensure_write_permitted(path: &Path) -> AppResult<()> {
+    let name = path
         .file_name()
-        .map(|segment| segment.to_string_lossy().to_string())
-        .filter(|name| !name.is_empty())
-        .unwrap_or_else(|| display_path.clone());
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // Windows ignores trailing dots and spaces, so `run.exe.` is `run.exe`.
+    let effective = Path::new(name.trim_end_matches(|c| c == '.' || c == ' '));
+    let extension = effective
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    config::current()
+        .file_write_policy
+        .check(&extension, content_type(effective))
+        .map_err(|reason| {
+            AppError::file_error(
+                "permission",
+                name.clone(),
+                format!("Writing '{}' is not permitted: {}", name, reason),
+            )
+        })
+}
+
+pub(crate) fn build_file_info(path: &Path, metadata: fs::Metadata, root: &Path) -> FileInfo {
+    let (name, display_path) = display_names(path, root);
 
     FileInfo {
         name,
@@ -509,6 +731,38 @@ fn build_file_info(path: &Path, metadata: fs::Metadata, root: &Path) -> FileInfo
     }
 }
 
+/// Builds an entry from its directory type alone, without a metadata lookup.
+fn build_file_info_without_metadata(
+    path: &Path,
+    file_type: fs::FileType,
+    root: &Path,
+) -> FileInfo {
+    let (name, display_path) = display_names(path, root);
+
+    FileInfo {
+        name,
+        path: display_path,
+        size: 0,
+        is_dir: file_type.is_dir(),
+        is_file: file_type.is_file(),
+        modified: None,
+        created: None,
+    }
+}
+
+/// Returns the display name and root-relative path for `path`.
+fn display_names(path: &Path, root: &Path) -> (String, String) {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let display_path = relative_path_to_string(relative);
+    let name = relative
+        .file_name()
+        .map(|segment| segment.to_string_lossy().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| display_path.clone());
+
+    (name, display_path)
+}
+
 fn relative_path_to_string(path: &Path) -> String {
     let value = path.to_string_lossy();
     if value.is_empty() {
@@ -570,10 +824,113 @@ mod tests {
         });
     }
 
+    #[test]
+    fn large_listing_does_not_block_runtime() {
+        with_temp_root(|root| {
+            let dir = root.join("many");
+            fs::create_dir_all(&dir).unwrap();
+            for i in 0..2_000 {
+                fs::write(dir.join(format!("file-{}.txt", i)), "x").unwrap();
+            }
+
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                let ticks = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+                let counter = ticks.clone();
+                let ticker = tokio::spawn(async move {
+                    loop {
+                        counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        tokio::task::yield_now().await;
+                    }
+                });
+
+                let started = std::time::Instant::now();
+                let listing = list_directory("many".into(), None).await.unwrap();
+                let elapsed = started.elapsed();
+                ticker.abort();
+
+                assert_eq!(listing.entries.len(), 2_000);
+                // On a single-threaded runtime the ticker only runs if the
+                // listing yields instead of blocking the executor.
+                assert!(
+                    ticks.load(std::sync::atomic::Ordering::Relaxed) > 0,
+                    "listing 2000 entries blocked the runtime for {:?}",
+                    elapsed
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn fast_listing_skips_metadata() {
+        with_temp_root(|root| {
+            fs::create_dir_all(root.join("fast/sub")).unwrap();
+            fs::write(root.join("fast/a.txt"), "hello").unwrap();
+
+            let listing = block_on(list_directory("fast".into(), Some(false))).unwrap();
+            assert_eq!(listing.entries.len(), 2);
+            assert!(listing.entries[0].is_dir);
+            assert!(listing.entries[1].is_file);
+            assert_eq!(listing.entries[1].size, 0);
+            assert!(listing.entries[1].modified.is_none());
+
+            let full = block_on(list_directory("fast".into(), Some(true))).unwrap();
+            assert_eq!(full.entries[1].size, 5);
+        });
+    }
+
+    #[test]
+    fn blocks_denied_extensions_on_write_copy_and_move() {
+        with_temp_root(|root| {
+            let error = block_on(write_text_file("tools/run.exe".into(), "MZ".into())).unwrap_err();
+            assert!(error.contains("FILE_PERMISSION"));
+            assert!(!root.join("tools/run.exe").exists());
+
+            block_on(write_text_file("notes.txt".into(), "hello".into())).unwrap();
+            let error =
+                block_on(copy_file("notes.txt".into(), "notes.bat".into(), None)).unwrap_err();
+            assert!(error.contains("not permitted"));
+            let error = block_on(move_file("notes.txt".into(), "notes.exe. ".into())).unwrap_err();
+            assert!(error.contains("not permitted"));
+            assert!(root.join("notes.txt").exists());
+
+            assert!(ensure_write_permitted(Path::new("archive/photo.PNG")).is_ok());
+            assert!(ensure_write_permitted(Path::new("setup.MSI")).is_err());
+        });
+    }
+
+    #[test]
+    fn tracked_copy_reports_bytes_copied() {
+        with_temp_root(|root| {
+            let content = vec![7u8; COPY_CHUNK_BYTES + 10];
+            fs::write(root.join("large.bin"), &content).unwrap();
+            let mut receiver = crate::events::subscribe();
+            let id = Uuid::new_v4();
+
+            block_on(copy_file("large.bin".into(), "copy.bin".into(), Some(id))).unwrap();
+            assert_eq!(fs::read(root.join("copy.bin")).unwrap(), content);
+
+            let mut last = None;
+            while let Ok(event) = receiver.try_recv() {
+                if let crate::events::AppEvent::OperationProgress(progress) = event {
+                    if progress.operation_id == id {
+                        last = Some(progress);
+                    }
+                }
+            }
+            let last = last.expect("progress events");
+            assert_eq!(last.status, operations::OperationStatus::Completed);
+            assert_eq!(last.done, content.len() as u64);
+        });
+    }
+
     #[test]
     fn rejects_root_deletion() {
         with_temp_root(|_| {
-            let error = block_on(delete_file(".".into())).unwrap_err();
+            let error = block_on(delete_file(CommandContext::anonymous(), ".".into())).unwrap_err();
             assert!(error.contains("filesystem root"));
         });
     }
@@ -0,0 +1,12 @@
+//! System font command handlers.
+
+use crate::errors::{AppError, AppResult};
+use crate::fonts::{self, FontFamily};
+
+/// Lists installed font families and their styles for font pickers.
+#[tauri::command]
+pub async fn list_system_fonts() -> AppResult<Vec<FontFamily>> {
+    tokio::task::spawn_blocking(fonts::list_system_fonts)
+        .await
+        .map_err(|e| AppError::internal_error(format!("Font scan task failed: {}", e)))
+}
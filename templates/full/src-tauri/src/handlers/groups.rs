@@ -0,0 +1,436 @@
+//! User group and team membership command handlers.
+//!
+//! Groups created while a workspace is selected belong to it, and listing
+//! groups is scoped to the selected workspace like users are.
+//!
+//! Group owners and admins manage their group; app admins manage every
+//! group. Other users only see the groups they belong to.
+
+use crate::command_context::CommandContext;
+use crate::database::{get_pool_ref, query_stats};
+use crate::errors::AppError;
+use crate::models::{CreateGroup, Group, GroupMembership, UpdateGroup};
+use crate::workspace;
+use sqlx::{Execute, PgPool};
+use uuid::Uuid;
+
+/// Roles a group member may hold.
+const ROLES: [&str; 3] = ["owner", "admin", "member"];
+
+/// Group roles that may change the group and its members.
+const MANAGER_ROLES: [&str; 2] = ["owner", "admin"];
+
+/// Adds `user_id` to `group_id`, updating the role if already a member.
+async fn add_member(
+    pool: &PgPool,
+    group_id: Uuid,
+    user_id: Uuid,
+    role: &str,
+) -> Result<GroupMembership, String> {
+    let query = sqlx::query_as!(
+        GroupMembership,
+        r#"
+        INSERT INTO group_memberships (group_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (group_id, user_id) DO UPDATE SET role = EXCLUDED.role
+        RETURNING group_id,
+                  user_id,
+                  role,
+                  joined_at AS "joined_at!"
+        "#,
+        group_id,
+        user_id,
+        role,
+    );
+    query_stats::timed(query.sql(), query.fetch_one(pool))
+        .await
+        .map_err(|e| format!("Failed to add group member: {}", e))
+}
+
+/// Fails unless the caller is an app admin or holds one of `roles` in
+/// `group_id`.
+async fn require_group_role(
+    context: &CommandContext,
+    pool: &PgPool,
+    group_id: Uuid,
+    roles: &[&str],
+) -> Result<(), String> {
+    if context.is_admin() {
+        return Ok(());
+    }
+    let user_id = context.require_user().map_err(|e| e.to_string())?;
+    let query = sqlx::query_scalar!(
+        "SELECT role FROM group_memberships WHERE group_id = $1 AND user_id = $2",
+        group_id,
+        user_id,
+    );
+    let role = query_stats::timed(query.sql(), query.fetch_optional(pool))
+        .await
+        .map_err(|e| format!("Failed to fetch group membership: {}", e))?;
+    match role {
+        Some(role) if roles.contains(&role.as_str()) => Ok(()),
+        _ => Err(AppError::forbidden("Not allowed for this group").to_string()),
+    }
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err("Invalid name: must be between 1 and 100 characters".to_string());
+    }
+    Ok(name)
+}
+
+fn validate_description(description: Option<String>) -> Result<Option<String>, String> {
+    let description = description
+        .map(|description| description.trim().to_string())
+        .filter(|description| !description.is_empty());
+    if description
+        .as_ref()
+        .is_some_and(|description| description.chars().count() > 1000)
+    {
+        return Err("Invalid description: cannot exceed 1000 characters".to_string());
+    }
+    Ok(description)
+}
+
+fn validate_role(role: Option<String>) -> Result<String, String> {
+    let role = role
+        .unwrap_or_else(|| "member".to_string())
+        .trim()
+        .to_lowercase();
+    if !ROLES.contains(&role.as_str()) {
+        return Err(format!("Invalid role: must be one of {}", ROLES.join(", ")));
+    }
+    Ok(role)
+}
+
+fn parse_uuid(id: &str) -> Result<Uuid, String> {
+    Uuid::parse_str(id).map_err(|e| format!("Invalid UUID: {}", e))
+}
+
+/// Creates a group in the selected workspace, optionally adding an owner.
+/// Users other than admins can only create groups they own themselves.
+#[tauri::command]
+pub async fn create_group(
+    context: CommandContext,
+    group_data: CreateGroup,
+) -> Result<Group, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let CreateGroup {
+        name,
+        description,
+        owner_id,
+    } = group_data;
+    let owner_id = match owner_id {
+        Some(owner_id) => {
+            context
+                .require_self_or_admin(owner_id)
+                .map_err(|e| e.to_string())?;
+            Some(owner_id)
+        }
+        None if context.is_admin() => None,
+        None => Some(context.require_user().map_err(|e| e.to_string())?),
+    };
+    let name = validate_name(&name)?;
+    let description = validate_description(description)?;
+
+    let query = sqlx::query_as!(
+        Group,
+        r#"
+        INSERT INTO groups (name, description, workspace_id)
+        VALUES ($1, $2, $3)
+        RETURNING id,
+                  name,
+                  description,
+                  workspace_id,
+                  created_at AS "created_at!",
+                  updated_at AS "updated_at!"
+        "#,
+        name,
+        description,
+        workspace::current(),
+    );
+    let group = query_stats::timed(query.sql(), query.fetch_one(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to create group: {}", e))?;
+
+    if let Some(owner_id) = owner_id {
+        add_member(pool.as_ref(), group.id, owner_id, "owner").await?;
+    }
+
+    Ok(group)
+}
+
+/// Lists groups in the selected workspace, optionally only those `user_id`
+/// belongs to. Users without the admin role only get their own groups.
+#[tauri::command]
+pub async fn list_groups(
+    context: CommandContext,
+    user_id: Option<String>,
+) -> Result<Vec<Group>, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let user_id = user_id.as_deref().map(parse_uuid).transpose()?;
+    let visible = context.visible_user().map_err(|e| e.to_string())?;
+    if let Some(user_id) = user_id {
+        context
+            .require_self_or_admin(user_id)
+            .map_err(|e| e.to_string())?;
+    }
+    let user_id = user_id.or(visible);
+
+    let query = sqlx::query_as!(
+        Group,
+        r#"
+        SELECT id,
+               name,
+               description,
+               workspace_id,
+               created_at AS "created_at!",
+               updated_at AS "updated_at!"
+        FROM groups
+        WHERE ($1::UUID IS NULL OR workspace_id = $1)
+          AND ($2::UUID IS NULL
+               OR id IN (SELECT group_id FROM group_memberships WHERE user_id = $2))
+        ORDER BY name
+        "#,
+        workspace::current(),
+        user_id,
+    );
+    query_stats::timed(query.sql(), query.fetch_all(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to fetch groups: {}", e))
+}
+
+/// Renames a group or changes its description. Requires a group owner or
+/// admin.
+#[tauri::command]
+pub async fn update_group(
+    context: CommandContext,
+    group_id: String,
+    group_data: UpdateGroup,
+) -> Result<Group, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let group_id = parse_uuid(&group_id)?;
+    require_group_role(&context, pool.as_ref(), group_id, &MANAGER_ROLES).await?;
+    let name = group_data.name.as_deref().map(validate_name).transpose()?;
+    let description = validate_description(group_data.description)?;
+
+    let query = sqlx::query_as!(
+        Group,
+        r#"
+        UPDATE groups
+        SET name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        RETURNING id,
+                  name,
+                  description,
+                  workspace_id,
+                  created_at AS "created_at!",
+                  updated_at AS "updated_at!"
+        "#,
+        group_id,
+        name,
+        description,
+    );
+    query_stats::timed(query.sql(), query.fetch_optional(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to update group: {}", e))?
+        .ok_or_else(|| "Group not found".to_string())
+}
+
+/// Deletes a group and its memberships. Requires a group owner.
+#[tauri::command]
+pub async fn delete_group(context: CommandContext, group_id: String) -> Result<String, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let group_id = parse_uuid(&group_id)?;
+    require_group_role(&context, pool.as_ref(), group_id, &["owner"]).await?;
+
+    let query = sqlx::query!("DELETE FROM groups WHERE id = $1", group_id);
+    let result = query_stats::timed(query.sql(), query.execute(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to delete group: {}", e))?;
+
+    if result.rows_affected() > 0 {
+        Ok("Group deleted successfully".to_string())
+    } else {
+        Err("Group not found".to_string())
+    }
+}
+
+/// Lists the members of a group, owners first. Only members see them.
+#[tauri::command]
+pub async fn list_group_members(
+    context: CommandContext,
+    group_id: String,
+) -> Result<Vec<GroupMembership>, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let group_id = parse_uuid(&group_id)?;
+    require_group_role(&context, pool.as_ref(), group_id, &ROLES).await?;
+
+    let query = sqlx::query_as!(
+        GroupMembership,
+        r#"
+        SELECT group_id,
+               user_id,
+               role,
+               joined_at AS "joined_at!"
+        FROM group_memberships
+        WHERE group_id = $1
+        ORDER BY CASE role WHEN 'owner' THEN 0 WHEN 'admin' THEN 1 ELSE 2 END, joined_at
+        "#,
+        group_id,
+    );
+    query_stats::timed(query.sql(), query.fetch_all(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to fetch group members: {}", e))
+}
+
+/// Adds a user to a group with the given role (defaults to `member`).
+/// Requires a group owner or admin; only owners may add owners.
+#[tauri::command]
+pub async fn add_group_member(
+    context: CommandContext,
+    group_id: String,
+    user_id: String,
+    role: Option<String>,
+) -> Result<GroupMembership, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let group_id = parse_uuid(&group_id)?;
+    let user_id = parse_uuid(&user_id)?;
+    let role = validate_role(role)?;
+    let allowed: &[&str] = if role == "owner" {
+        &["owner"]
+    } else {
+        &MANAGER_ROLES
+    };
+    require_group_role(&context, pool.as_ref(), group_id, allowed).await?;
+
+    add_member(pool.as_ref(), group_id, user_id, &role).await
+}
+
+/// Removes a user from a group. Members may leave on their own; removing
+/// someone else requires a group owner or admin.
+#[tauri::command]
+pub async fn remove_group_member(
+    context: CommandContext,
+    group_id: String,
+    user_id: String,
+) -> Result<String, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let group_id = parse_uuid(&group_id)?;
+    let user_id = parse_uuid(&user_id)?;
+    if context.user_id != Some(user_id) {
+        require_group_role(&context, pool.as_ref(), group_id, &MANAGER_ROLES).await?;
+    }
+
+    let query = sqlx::query!(
+        "DELETE FROM group_memberships WHERE group_id = $1 AND user_id = $2",
+        group_id,
+        user_id,
+    );
+    let result = query_stats::timed(query.sql(), query.execute(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to remove group member: {}", e))?;
+
+    if result.rows_affected() > 0 {
+        Ok("Group member removed successfully".to_string())
+    } else {
+        Err("Group member not found".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::CommandContext;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::handlers::users::search_users;
+    use crate::models::UserSearchFilters;
+    use crate::test_support::UserFactory;
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    #[test]
+    fn validates_group_input() {
+        assert_eq!(validate_name("  Design  ").unwrap(), "Design");
+        assert!(validate_name(" ").is_err());
+        assert_eq!(validate_description(Some("  ".to_string())).unwrap(), None);
+        assert_eq!(validate_role(None).unwrap(), "member");
+        assert_eq!(validate_role(Some("Admin".to_string())).unwrap(), "admin");
+        assert!(validate_role(Some("superuser".to_string())).is_err());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn group_membership_filters_user_search() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let member = UserFactory::new().insert().await?;
+        let outsider = UserFactory::new().insert().await?;
+        let member_context = CommandContext::for_user(member.id, Vec::new());
+        let outsider_context = CommandContext::for_user(outsider.id, Vec::new());
+        let group = create_group(
+            member_context.clone(),
+            CreateGroup {
+                name: "Design".to_string(),
+                description: None,
+                owner_id: None,
+            },
+        )
+        .await
+        .expect("group creation should succeed");
+
+        let filters = UserSearchFilters {
+            group_id: Some(group.id),
+            ..Default::default()
+        };
+        let result = search_users(
+            CommandContext::internal(),
+            None,
+            Some(filters.clone()),
+            None,
+            None,
+        )
+        .await
+        .expect("search should succeed");
+        assert_eq!(result.total, 1);
+        assert_eq!(result.users[0].id, member.id);
+
+        let denied = list_group_members(outsider_context.clone(), group.id.to_string()).await;
+        assert!(denied.is_err());
+        add_group_member(
+            member_context.clone(),
+            group.id.to_string(),
+            outsider.id.to_string(),
+            None,
+        )
+        .await
+        .expect("adding a member should succeed");
+        let members = list_group_members(outsider_context.clone(), group.id.to_string())
+            .await
+            .expect("listing members should succeed");
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].role, "owner");
+
+        let groups = list_groups(outsider_context.clone(), None)
+            .await
+            .expect("listing groups should succeed");
+        assert_eq!(groups.len(), 1);
+
+        assert!(delete_group(outsider_context, group.id.to_string())
+            .await
+            .is_err());
+        delete_group(member_context, group.id.to_string())
+            .await
+            .expect("deleting should succeed");
+        let result = search_users(CommandContext::internal(), None, Some(filters), None, None)
+            .await
+            .expect("search should succeed");
+        assert_eq!(result.total, 0);
+        Ok(())
+    }
+}
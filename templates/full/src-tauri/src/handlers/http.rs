@@ -0,0 +1,42 @@
+//! Outbound HTTP request command handlers.
+
+use crate::errors::{AppError, AppResult};
+use crate::http_client::{self, HttpRequestOptions, HttpResponse};
+use crate::proxy::{self, ProxySettings};
+use std::collections::HashMap;
+
+/// Performs an HTTP request from Rust with optional retries, proxy, and response caching.
+#[tauri::command]
+pub async fn http_request(
+    method: String,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    body: Option<serde_json::Value>,
+    options: Option<HttpRequestOptions>,
+) -> AppResult<HttpResponse> {
+    tracing::debug!("HTTP request: {} {}", method, url);
+
+    http_client::execute(
+        &method,
+        &url,
+        headers.unwrap_or_default(),
+        body,
+        options.unwrap_or_default(),
+    )
+    .await
+}
+
+/// Returns the proxy settings outbound requests use, detecting them again
+/// when `refresh` is true.
+#[tauri::command]
+pub async fn get_proxy_settings(refresh: Option<bool>) -> AppResult<ProxySettings> {
+    tokio::task::spawn_blocking(move || {
+        if refresh.unwrap_or(false) {
+            proxy::refresh()
+        } else {
+            proxy::current()
+        }
+    })
+    .await
+    .map_err(|e| AppError::internal_error(format!("Proxy detection task failed: {}", e)))
+}
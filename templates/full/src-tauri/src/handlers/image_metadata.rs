@@ -0,0 +1,24 @@
+//! Image metadata command handlers.
+
+use crate::errors::{AppError, AppResult};
+use crate::image_metadata::{self, ImageMetadata, StripSummary};
+
+/// Returns the EXIF fields of a JPEG or PNG image in the fs scope.
+#[tauri::command]
+pub async fn get_image_metadata(path: String) -> AppResult<ImageMetadata> {
+    tokio::task::spawn_blocking(move || image_metadata::inspect(&path))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Metadata task failed: {}", e)))?
+}
+
+/// Removes EXIF, XMP, IPTC, and text metadata from an image in the fs scope,
+/// replacing it unless `destination` is given.
+#[tauri::command]
+pub async fn strip_image_metadata(
+    path: String,
+    destination: Option<String>,
+) -> AppResult<StripSummary> {
+    tokio::task::spawn_blocking(move || image_metadata::strip(&path, destination.as_deref()))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Metadata task failed: {}", e)))?
+}
@@ -0,0 +1,18 @@
+//! Command invocation inspector handlers (development builds only).
+
+use crate::inspector::{self, InvocationRecord};
+
+/// Returns the most recent command invocations, newest first.
+///
+/// Always empty in release builds.
+#[tauri::command]
+pub async fn get_recent_invocations(limit: Option<usize>) -> Result<Vec<InvocationRecord>, String> {
+    Ok(inspector::recent(limit))
+}
+
+/// Clears the recorded invocation history.
+#[tauri::command]
+pub async fn clear_recent_invocations() -> Result<(), String> {
+    inspector::clear();
+    Ok(())
+}
@@ -0,0 +1,591 @@
+//! Database handlers for the embedded SQLite backend.
+//!
+//! Registered under the same command names as the PostgreSQL handlers, so
+//! the frontend works unchanged with either backend: a build with only the
+//! `sqlite` feature registers these commands directly, and one with both
+//! features has the PostgreSQL commands hand off to them when `DATABASE_URL`
+//! selects SQLite. User commands share their validation with the PostgreSQL
+//! ones through the `*_with` functions of [`super::users`].
+//!
+//! Sign-in starts a session in the embedded database, and commands check
+//! the caller's [`CommandContext`] as they do with PostgreSQL. The account
+//! with `ADMIN_EMAIL` is the admin. Workspaces, groups, settings,
+//! notifications, the audit log, OAuth, email verification, impersonation,
+//! and account deletion need PostgreSQL.
+
+use crate::auth::throttle;
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::config::{self, DatabaseBackend};
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::events::{self, AppEvent};
+use crate::handlers::users::{self, GuestUpgrade};
+use crate::local_db::{self, timestamp};
+use crate::maintenance;
+use crate::models::{
+    AppLog, CreateAppLog, CreateUser, DatabaseStatus, FrontendError, LogQuery, LoginRequest,
+    LoginResponse, Pagination, PublicUser, Session, SessionTokens, UpdateUser, UserSearchFilters,
+    UserSearchResult, UserSort, MAX_FRONTEND_ERRORS,
+};
+use crate::operations;
+use crate::repository::SqliteUserRepository;
+use crate::session::sqlite as session;
+use crate::validation::{validate_log_level, validate_log_message, validate_log_tags};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Columns read into a [`LogRow`].
+const LOG_COLUMNS: &str = "id, level, message, metadata, user_id, tags, created_at";
+
+/// Checks that the database file is open and returns its status.
+#[tauri::command]
+pub async fn check_database_connection() -> AppResult<DatabaseStatus> {
+    let pool = local_db::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let database_name = Some(local_db::database_path().display().to_string());
+
+    match sqlx::query_as::<_, (String,)>("SELECT sqlite_version()")
+        .fetch_one(pool.as_ref())
+        .await
+    {
+        Ok((version,)) => Ok(DatabaseStatus {
+            backend: DatabaseBackend::Sqlite,
+            connected: true,
+            database_name,
+            version: Some(format!("SQLite {}", version)),
+            error: None,
+        }),
+        Err(e) => {
+            tracing::error!("SQLite connection test failed: {}", e);
+            Ok(DatabaseStatus {
+                backend: DatabaseBackend::Sqlite,
+                connected: false,
+                database_name,
+                version: None,
+                error: Some(e.to_string()),
+            })
+        }
+    }
+}
+
+/// Opens the database file and applies pending migrations.
+///
+/// `password` is accepted for parity with the PostgreSQL handler and
+/// ignored; the database file is protected by the OS user's permissions.
+#[tauri::command]
+pub async fn initialize_database(password: Option<String>) -> AppResult<String> {
+    if password.is_some() {
+        tracing::debug!("Ignoring database password; SQLite needs none");
+    }
+
+    local_db::initialize()
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+    events::publish(AppEvent::DatabaseConnected);
+
+    Ok("Database initialized successfully".to_string())
+}
+
+#[tauri::command]
+pub async fn run_migrations(operation_id: Option<Uuid>) -> AppResult<String> {
+    let pool = local_db::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+
+    let _maintenance = maintenance::hold("migrations");
+    operations::track("migrations", operation_id, async {
+        local_db::migrations::run_migrations(pool.as_ref())
+            .await
+            .into_app_error(ErrorCode::DatabaseMigration)
+    })
+    .await
+    .map(|_| "Migrations completed successfully".to_string())
+}
+
+fn pool() -> Result<Arc<SqlitePool>, String> {
+    local_db::get_pool_ref().map_err(|e| e.to_string())
+}
+
+/// Retrieves all users (excluding password hashes). Callers without the
+/// admin role only get their own user.
+#[tauri::command]
+pub async fn get_all_users(context: CommandContext) -> Result<Vec<PublicUser>, String> {
+    let visible = context.visible_user().map_err(|e| e.to_string())?;
+    let pool = pool()?;
+    users::get_all_users_with(&SqliteUserRepository::new(pool.as_ref()), None, visible).await
+}
+
+/// Searches users by email, username, or name with filters, sorting, and
+/// paging. Requires the admin role.
+#[tauri::command]
+pub async fn search_users(
+    context: CommandContext,
+    query: Option<String>,
+    filters: Option<UserSearchFilters>,
+    sort: Option<UserSort>,
+    pagination: Option<Pagination>,
+) -> Result<UserSearchResult, String> {
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
+    let pool = pool()?;
+    let search = users::build_user_search(
+        query,
+        filters.unwrap_or_default(),
+        sort.unwrap_or_default(),
+        pagination.unwrap_or_default(),
+        None,
+    )?;
+    users::search_users_with(&SqliteUserRepository::new(pool.as_ref()), &search).await
+}
+
+/// Retrieves a user by id. Callers without the admin role may only fetch
+/// themselves.
+#[tauri::command]
+pub async fn get_user_by_id(
+    context: CommandContext,
+    user_id: String,
+) -> Result<Option<PublicUser>, String> {
+    let pool = pool()?;
+    users::get_user_by_id_with(
+        &SqliteUserRepository::new(pool.as_ref()),
+        &user_id,
+        &context,
+    )
+    .await
+}
+
+/// Creates a user account. There is no mail delivery to verify the address
+/// with, so the account is active right away.
+#[tauri::command]
+pub async fn create_user(
+    _context: CommandContext,
+    user_data: CreateUser,
+) -> Result<PublicUser, String> {
+    let pool = pool()?;
+    let repo = SqliteUserRepository::new(pool.as_ref());
+    let config = config::current();
+    let user = users::create_user_with(&repo, user_data, false, &config.password_policy).await?;
+    users::grant_admin_with(&repo, &user.email, config.admin_email.as_deref()).await?;
+    Ok(PublicUser::from(user))
+}
+
+/// Grants the admin role to the existing account with `ADMIN_EMAIL`, if any.
+pub async fn bootstrap_admin() -> Result<(), String> {
+    let Some(email) = config::current().admin_email.clone() else {
+        return Ok(());
+    };
+    let pool = pool()?;
+    let repo = SqliteUserRepository::new(pool.as_ref());
+    if !users::grant_admin_with(&repo, &email, Some(&email)).await? {
+        tracing::warn!("ADMIN_EMAIL does not belong to a regular account yet");
+    }
+    Ok(())
+}
+
+/// Updates a user. Callers without the admin role may only update
+/// themselves, and only admins may activate or deactivate accounts.
+#[tauri::command]
+pub async fn update_user(
+    context: CommandContext,
+    user_id: String,
+    user_data: UpdateUser,
+) -> Result<PublicUser, String> {
+    let pool = pool()?;
+    let user = users::update_user_with(
+        &SqliteUserRepository::new(pool.as_ref()),
+        &user_id,
+        user_data,
+        &context,
+    )
+    .await?;
+    Ok(PublicUser::from(user))
+}
+
+/// Deletes a user. Callers without the admin role may only delete themselves.
+#[tauri::command]
+pub async fn delete_user(context: CommandContext, user_id: String) -> Result<String, String> {
+    let pool = pool()?;
+    users::delete_user_with(
+        &SqliteUserRepository::new(pool.as_ref()),
+        &user_id,
+        &context,
+    )
+    .await?;
+    Ok("User deleted successfully".to_string())
+}
+
+/// Verifies credentials and starts a session for the user, returning its
+/// access and refresh tokens. Failures are throttled like with PostgreSQL.
+#[tauri::command]
+pub async fn authenticate_user(login_data: LoginRequest) -> Result<Option<LoginResponse>, String> {
+    let pool = pool()?;
+    let email = login_data.email.clone();
+    let device_id = login_data.device_id.clone();
+    let remember_me = login_data.remember_me;
+    throttle::check(&email, device_id.as_deref())?;
+
+    let user = users::authenticate_user_with(&SqliteUserRepository::new(pool.as_ref()), login_data)
+        .await?;
+    let Some(user) = user else {
+        throttle::record_failure(&email, device_id.as_deref());
+        return Ok(None);
+    };
+    throttle::record_success(&email, device_id.as_deref());
+
+    let session = session::start(pool.as_ref(), user.id, remember_me)
+        .await
+        .map_err(|e| format!("Failed to start session: {}", e))?;
+    Ok(Some(LoginResponse { user, session }))
+}
+
+/// Changes a user's password after verifying the current one, under the
+/// same access, throttle, policy and history rules as the PostgreSQL backend.
+#[tauri::command]
+pub async fn change_password(
+    context: CommandContext,
+    user_id: String,
+    current_password: String,
+    new_password: String,
+) -> Result<String, String> {
+    let pool = pool()?;
+    let config = config::current();
+    users::change_password_with(
+        &SqliteUserRepository::new(pool.as_ref()),
+        &user_id,
+        &context,
+        &current_password,
+        &new_password,
+        config.password_history_depth,
+        &config.password_policy,
+    )
+    .await?;
+    Ok("Password changed successfully".to_string())
+}
+
+/// Creates a guest account without email or password and starts a session
+/// for it. Requires `ALLOW_GUEST_ACCOUNTS`.
+#[tauri::command]
+pub async fn create_guest_user(_context: CommandContext) -> Result<LoginResponse, String> {
+    if !config::current().allow_guest_accounts {
+        return Err("Guest accounts are disabled".to_string());
+    }
+    let pool = pool()?;
+    let user = users::create_guest_user_with(&SqliteUserRepository::new(pool.as_ref())).await?;
+    let session = session::start(pool.as_ref(), user.id, false)
+        .await
+        .map_err(|e| format!("Failed to start session: {}", e))?;
+    Ok(LoginResponse {
+        user: PublicUser::from(user),
+        session,
+    })
+}
+
+/// Turns a guest into a regular account, or merges it into the existing
+/// account the credentials belong to, and starts a session for the result.
+#[tauri::command]
+pub async fn upgrade_guest_account(
+    context: CommandContext,
+    guest_id: String,
+    credentials: CreateUser,
+) -> Result<Option<LoginResponse>, String> {
+    let pool = pool()?;
+    let repo = SqliteUserRepository::new(pool.as_ref());
+    let config = config::current();
+    let upgrade = users::upgrade_guest_with(
+        &repo,
+        &guest_id,
+        credentials,
+        &context,
+        false,
+        &config.password_policy,
+    )
+    .await?;
+    let user = match upgrade {
+        GuestUpgrade::Attached(user) => {
+            users::grant_admin_with(&repo, &user.email, config.admin_email.as_deref()).await?;
+            user
+        }
+        GuestUpgrade::Merged { guest_id, account } => {
+            tracing::debug!("Merged guest {} into user {}", guest_id, account.id);
+            account
+        }
+    };
+
+    if !user.is_active {
+        return Ok(None);
+    }
+    let session = session::start(pool.as_ref(), user.id, false)
+        .await
+        .map_err(|e| format!("Failed to start session: {}", e))?;
+    Ok(Some(LoginResponse {
+        user: PublicUser::from(user),
+        session,
+    }))
+}
+
+fn session_pool() -> AppResult<Arc<SqlitePool>> {
+    local_db::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)
+}
+
+/// Exchanges a refresh token for a new access token and refresh token.
+#[tauri::command]
+pub async fn refresh_session(refresh_token: String) -> AppResult<SessionTokens> {
+    session::refresh(session_pool()?.as_ref(), &refresh_token).await
+}
+
+/// Returns the session of an access token while it is still active.
+#[tauri::command]
+pub async fn validate_session(access_token: String) -> AppResult<Session> {
+    session::validate(session_pool()?.as_ref(), &access_token).await
+}
+
+/// Extends the signed-in user's session while they are active.
+#[tauri::command]
+pub async fn session_heartbeat(access_token: String) -> AppResult<Session> {
+    session::heartbeat(session_pool()?.as_ref(), &access_token).await
+}
+
+/// Ends the session holding a refresh token; returns whether one was active.
+#[tauri::command]
+pub async fn logout(refresh_token: String) -> AppResult<bool> {
+    session::logout(session_pool()?.as_ref(), &refresh_token).await
+}
+
+/// Revokes every session of a user. Only the user and admins may do this.
+#[tauri::command]
+pub async fn revoke_user_sessions(context: CommandContext, user_id: String) -> AppResult<u64> {
+    let user_id = Uuid::parse_str(&user_id)
+        .map_err(|_| AppError::invalid_input("user_id", "Invalid user ID"))?;
+    context.require_self_or_admin(user_id)?;
+    session::revoke_user(session_pool()?.as_ref(), user_id).await
+}
+
+/// An `app_logs` row as stored, with the ids and JSON still text.
+#[derive(FromRow)]
+struct LogRow {
+    id: String,
+    level: String,
+    message: String,
+    metadata: String,
+    user_id: Option<String>,
+    tags: String,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<LogRow> for AppLog {
+    type Error = sqlx::Error;
+
+    fn try_from(row: LogRow) -> sqlx::Result<Self> {
+        Ok(AppLog {
+            id: Uuid::parse_str(&row.id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            level: row.level,
+            message: row.message,
+            metadata: serde_json::from_str(&row.metadata)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            user_id: row
+                .user_id
+                .as_deref()
+                .map(Uuid::parse_str)
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            tags: serde_json::from_str(&row.tags).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            created_at: row.created_at,
+        })
+    }
+}
+
+/// Inserts a validated log entry and returns it.
+async fn insert_log(
+    connection: &mut SqliteConnection,
+    level: String,
+    message: String,
+    metadata: serde_json::Value,
+    user_id: Option<Uuid>,
+    tags: Vec<String>,
+) -> sqlx::Result<AppLog> {
+    let row: LogRow = sqlx::query_as(&format!(
+        "INSERT INTO app_logs (id, level, message, metadata, user_id, tags, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         RETURNING {}",
+        LOG_COLUMNS
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(level)
+    .bind(message)
+    .bind(metadata.to_string())
+    .bind(user_id.map(|id| id.to_string()))
+    .bind(serde_json::Value::from(tags).to_string())
+    .bind(timestamp(Utc::now()))
+    .fetch_one(connection)
+    .await?;
+    AppLog::try_from(row)
+}
+
+/// Creates a log entry. Admin only, since the entry may name any user.
+#[tauri::command]
+pub async fn create_log(context: CommandContext, log_data: CreateAppLog) -> Result<AppLog, String> {
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
+    let pool = pool()?;
+
+    let level =
+        validate_log_level(&log_data.level).map_err(|e| format!("Invalid log level: {}", e))?;
+    let message = validate_log_message(&log_data.message)
+        .map_err(|e| format!("Invalid log message: {}", e))?;
+    let metadata = log_data.metadata.unwrap_or_else(|| serde_json::json!({}));
+    let tags = validate_log_tags(&log_data.tags.unwrap_or_default())
+        .map_err(|e| format!("Invalid log tags: {}", e))?;
+
+    let mut connection = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to create log: {}", e))?;
+    insert_log(
+        &mut connection,
+        level,
+        message,
+        metadata,
+        log_data.user_id,
+        tags,
+    )
+    .await
+    .map_err(|e| format!("Failed to create log: {}", e))
+}
+
+/// Stores a batch of console errors and unhandled rejections from the
+/// frontend as `error` entries attributed to the signed-in user, skipping
+/// entries whose message cannot be logged. Returns the number stored.
+#[tauri::command]
+pub async fn ingest_frontend_errors(
+    context: CommandContext,
+    errors: Vec<FrontendError>,
+) -> Result<usize, String> {
+    if errors.len() > MAX_FRONTEND_ERRORS {
+        return Err(format!(
+            "Cannot ingest more than {} frontend errors at once",
+            MAX_FRONTEND_ERRORS
+        ));
+    }
+    let pool = pool()?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to store frontend errors: {}", e))?;
+    let mut stored = 0;
+    for error in errors {
+        let message = match validate_log_message(&error.message) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::debug!("Skipping frontend error: {}", e);
+                continue;
+            }
+        };
+        insert_log(
+            &mut tx,
+            "error".to_string(),
+            message,
+            error.metadata(),
+            context.user_id,
+            error.tags(),
+        )
+        .await
+        .map_err(|e| format!("Failed to store frontend errors: {}", e))?;
+        stored += 1;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to store frontend errors: {}", e))?;
+
+    Ok(stored)
+}
+
+/// Lists log entries matching `query`, newest first.
+///
+/// Callers without the admin role only see their own entries.
+#[tauri::command]
+pub async fn get_logs(context: CommandContext, query: LogQuery) -> Result<Vec<AppLog>, String> {
+    let LogQuery {
+        level,
+        user_id,
+        tags,
+        source,
+        limit,
+        offset,
+    } = query;
+
+    let visible = context.visible_user().map_err(|e| e.to_string())?;
+    if let Some(user_id) = user_id {
+        context
+            .require_self_or_admin(user_id)
+            .map_err(|e| e.to_string())?;
+    }
+    let user_id = user_id.or(visible);
+
+    let pool = pool()?;
+
+    let limit = limit.unwrap_or(100).clamp(1, 1_000);
+    let offset = offset.unwrap_or(0).max(0);
+
+    let mut builder =
+        QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM app_logs WHERE TRUE", LOG_COLUMNS));
+    if let Some(level) = level {
+        builder.push(" AND level = ").push_bind(level);
+    }
+    if let Some(user_id) = user_id {
+        builder
+            .push(" AND user_id = ")
+            .push_bind(user_id.to_string());
+    }
+
+    let mut tags = tags.unwrap_or_default();
+    if let Some(source) = source {
+        tags.push(format!("source:{}", source.trim()));
+    }
+    if !tags.is_empty() {
+        let tags = validate_log_tags(&tags).map_err(|e| format!("Invalid log tags: {}", e))?;
+        for tag in tags {
+            builder
+                .push(" AND EXISTS (SELECT 1 FROM json_each(app_logs.tags) WHERE value = ")
+                .push_bind(tag)
+                .push(")");
+        }
+    }
+
+    builder
+        .push(" ORDER BY created_at DESC, rowid DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let rows = builder
+        .build_query_as::<LogRow>()
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to fetch logs: {}", e))?;
+    rows.into_iter()
+        .map(AppLog::try_from)
+        .collect::<sqlx::Result<_>>()
+        .map_err(|e| format!("Failed to fetch logs: {}", e))
+}
+
+/// Deletes log entries older than `days_old` days. Admin only.
+#[tauri::command]
+pub async fn delete_old_logs(context: CommandContext, days_old: i32) -> Result<String, String> {
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
+    let pool = pool()?;
+
+    let cutoff = Utc::now() - chrono::Duration::days(days_old.into());
+    let result = sqlx::query("DELETE FROM app_logs WHERE created_at < ?1")
+        .bind(timestamp(cutoff))
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to delete old logs: {}", e))?;
+
+    Ok(format!(
+        "Deleted {} old log entries",
+        result.rows_affected()
+    ))
+}
@@ -1,138 +1,291 @@
 //! Application log management command handlers.
 
-use crate::database::get_pool_ref;
-use crate::models::{AppLog, CreateAppLog, LogQuery};
-use crate::validation::{validate_log_level, validate_log_message};
-use sqlx::QueryBuilder;
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::config;
+use crate::database::filter::FilterBuilder;
+use crate::database::{get_pool_ref, query_stats};
+use crate::logging::db_sink::{self, PendingLog};
+use crate::logging::retention::{self, RetentionReport};
+use crate::models::{AppLog, CreateAppLog, FrontendError, LogQuery, MAX_FRONTEND_ERRORS};
+use crate::validation::{validate_log_level, validate_log_message, validate_log_tags};
+use crate::workspace;
+use crate::models::SortDirection;
+use sqlx::Execute;
 
-/// Creates a new application log entry in the database.
+/// Creates a new application log entry in the database. Admin only, since
+/// the entry may name any user; the frontend reports its own errors through
+/// [`ingest_frontend_errors`].
+///
+/// The entry is attached to the selected workspace, if any.
 #[tauri::command]
-pub async fn create_log(log_data: CreateAppLog) -> Result<AppLog, String> {
+pub async fn create_log(context: CommandContext, log_data: CreateAppLog) -> Result<AppLog, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::create_log(context, log_data).await;
+    }
+
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
     let pool = get_pool_ref().map_err(|e| e.to_string())?;
 
     let level = validate_log_level(&log_data.level).map_err(|e| format!("Invalid log level: {}", e))?;
     let message = validate_log_message(&log_data.message).map_err(|e| format!("Invalid log message: {}", e))?;
     let metadata = log_data.metadata.unwrap_or_else(|| serde_json::json!({}));
+    let tags = validate_log_tags(&log_data.tags.unwrap_or_default())
+        .map_err(|e| format!("Invalid log tags: {}", e))?;
 
-    let log = sqlx::query_as::<_, AppLog>(
+    let query = sqlx::query_as!(
+        AppLog,
         r#"
-        INSERT INTO app_logs (level, message, metadata, user_id)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO app_logs (level, message, metadata, user_id, workspace_id, tags)
+        VALUES ($1, $2, $3, $4, $5, $6)
         RETURNING id,
                   level,
                   message,
-                  metadata,
+                  metadata AS "metadata!",
                   user_id,
-                  created_at
+                  tags AS "tags!",
+                  created_at AS "created_at!"
         "#,
-    )
-    .bind(level)
-    .bind(message)
-    .bind(metadata)
-    .bind(log_data.user_id)
-    .fetch_one(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to create log: {}", e))?;
+        level,
+        message,
+        metadata,
+        log_data.user_id,
+        workspace::current(),
+        &tags,
+    );
+    let log = query_stats::timed(query.sql(), query.fetch_one(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to create log: {}", e))?;
 
     Ok(log)
 }
 
+/// Queues a batch of log entries for buffered insertion.
+///
+/// Entries are validated up front and written by the log buffer in batched
+/// inserts. Returns the number of entries queued. Admin only, like
+/// [`create_log`].
 #[tauri::command]
-pub async fn get_logs(query: LogQuery) -> Result<Vec<AppLog>, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+pub async fn create_logs_bulk(
+    context: CommandContext,
+    entries: Vec<CreateAppLog>,
+) -> Result<usize, String> {
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
+    if entries.len() > 10_000 {
+        return Err("Cannot queue more than 10000 log entries at once".to_string());
+    }
+
+    let workspace_id = workspace::current();
+    let created_at = chrono::Utc::now();
+    let pending = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let level = validate_log_level(&entry.level)
+                .map_err(|e| format!("Invalid log level in entry {}: {}", index, e))?;
+            let message = validate_log_message(&entry.message)
+                .map_err(|e| format!("Invalid log message in entry {}: {}", index, e))?;
+            let tags = validate_log_tags(&entry.tags.unwrap_or_default())
+                .map_err(|e| format!("Invalid log tags in entry {}: {}", index, e))?;
+            Ok(PendingLog {
+                level,
+                message,
+                metadata: entry.metadata.unwrap_or_else(|| serde_json::json!({})),
+                user_id: entry.user_id,
+                workspace_id,
+                tags,
+                created_at,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(db_sink::enqueue(pending))
+}
+
+/// Queues a batch of console errors and unhandled rejections from the
+/// frontend as `error` entries tagged `source:frontend` and their kind,
+/// attributed to the signed-in user.
+///
+/// Stack traces and source locations go into the entry's metadata. Entries
+/// whose message cannot be logged are skipped. Returns the number queued.
+#[tauri::command]
+pub async fn ingest_frontend_errors(
+    context: CommandContext,
+    errors: Vec<FrontendError>,
+) -> Result<usize, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::ingest_frontend_errors(context, errors).await;
+    }
+
+    if errors.len() > MAX_FRONTEND_ERRORS {
+        return Err(format!(
+            "Cannot ingest more than {} frontend errors at once",
+            MAX_FRONTEND_ERRORS
+        ));
+    }
+
+    let workspace_id = workspace::current();
+    let created_at = chrono::Utc::now();
+    let pending: Vec<PendingLog> = errors
+        .into_iter()
+        .filter_map(|error| {
+            let message = match validate_log_message(&error.message) {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::debug!("Skipping frontend error: {}", e);
+                    return None;
+                }
+            };
+            Some(PendingLog {
+                level: "error".to_string(),
+                message,
+                metadata: error.metadata(),
+                user_id: context.user_id,
+                workspace_id,
+                tags: error.tags(),
+                created_at,
+            })
+        })
+        .collect();
+
+    Ok(db_sink::enqueue(pending))
+}
+
+/// Lists log entries matching `query`, newest first.
+///
+/// Callers without the admin role only see their own entries.
+#[tauri::command]
+pub async fn get_logs(context: CommandContext, query: LogQuery) -> Result<Vec<AppLog>, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::get_logs(context, query).await;
+    }
 
     let LogQuery {
         level,
         user_id,
+        tags,
+        source,
         limit,
         offset,
     } = query;
 
+    let visible = context.visible_user().map_err(|e| e.to_string())?;
+    if let Some(user_id) = user_id {
+        context
+            .require_self_or_admin(user_id)
+            .map_err(|e| e.to_string())?;
+    }
+    let user_id = user_id.or(visible);
+
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+
     let limit = limit.unwrap_or(100).clamp(1, 1_000);
     let offset = offset.unwrap_or(0).max(0);
 
-    let mut builder = QueryBuilder::new(
+    let mut filter = FilterBuilder::new(
         "SELECT id,
                 level,
                 message,
                 metadata,
                 user_id,
+                tags,
                 created_at
          FROM app_logs",
     );
+    filter.eq_opt("level", level).eq_opt("user_id", user_id);
 
-    let mut has_condition = false;
-
-    if let Some(level) = level {
-        builder.push(" WHERE level = ");
-        builder.push_bind(level);
-        has_condition = true;
+    let mut tags = tags.unwrap_or_default();
+    if let Some(source) = source {
+        tags.push(format!("source:{}", source.trim()));
     }
-
-    if let Some(user_id) = user_id {
-        builder.push(if has_condition {
-            " AND user_id = "
-        } else {
-            " WHERE user_id = "
-        });
-        builder.push_bind(user_id);
+    if !tags.is_empty() {
+        let tags = validate_log_tags(&tags).map_err(|e| format!("Invalid log tags: {}", e))?;
+        // Containment is served by the GIN index on `tags`.
+        filter.compare("tags", "@>", tags);
     }
 
-    builder.push(" ORDER BY created_at DESC LIMIT ");
-    builder.push_bind(limit);
-    builder.push(" OFFSET ");
-    builder.push_bind(offset);
+    filter
+        .eq_opt("workspace_id", workspace::current())
+        .order_by(&"created_at", SortDirection::Desc, None)
+        .paginate(limit, offset);
 
-    let logs = builder
-        .build_query_as::<AppLog>()
-        .fetch_all(pool.as_ref())
+    let mut builder = filter.into_builder();
+    let query = builder.build_query_as::<AppLog>();
+    let logs = query_stats::timed(query.sql(), query.fetch_all(pool.as_ref()))
         .await
         .map_err(|e| format!("Failed to fetch logs: {}", e))?;
 
     Ok(logs)
 }
 
+/// Deletes log entries older than `days_old` days. Admin only.
 #[tauri::command]
-pub async fn delete_old_logs(days_old: i32) -> Result<String, String> {
+pub async fn delete_old_logs(context: CommandContext, days_old: i32) -> Result<String, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::delete_old_logs(context, days_old).await;
+    }
+
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
     let pool = get_pool_ref().map_err(|e| e.to_string())?;
 
-    let result = sqlx::query(
+    let query = sqlx::query!(
         r#"
         DELETE FROM app_logs
         WHERE created_at < NOW() - ($1::INT * INTERVAL '1 day')
+          AND ($2::UUID IS NULL OR workspace_id = $2)
         "#,
-    )
-    .bind(days_old)
-    .execute(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to delete old logs: {}", e))?;
+        days_old,
+        workspace::current(),
+    );
+    let result = query_stats::timed(query.sql(), query.execute(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to delete old logs: {}", e))?;
 
     Ok(format!(
         "Deleted {} old log entries",
         result.rows_affected()
     ))
 }
+
+/// Applies the configured log retention policy immediately.
+///
+/// `dry_run` defaults to the policy's own setting; a dry run only counts the
+/// entries that would be deleted. Admin only.
+#[tauri::command]
+pub async fn apply_log_retention(
+    context: CommandContext,
+    dry_run: Option<bool>,
+) -> Result<RetentionReport, String> {
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
+    let policy = config::current().log_retention.clone();
+    if !policy.is_enabled() {
+        return Err("No log retention policy is configured".to_string());
+    }
+
+    retention::apply(&policy, dry_run.unwrap_or(policy.dry_run))
+        .await
+        .map_err(|e| format!("Failed to apply log retention: {}", e))
+}
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::database::test_utils::{pool, reset_all_tables};
-    use crate::handlers::users::create_user;
-    use crate::models::{CreateAppLog, CreateUser, LogQuery};
+    use crate::models::{CreateAppLog, LogQuery, FRONTEND_SOURCE_TAG};
+    use crate::test_support::{LogFactory, UserFactory};
     use anyhow::Result as AnyResult;
     use serde_json::json;
     use serial_test::serial;
-    use uuid::Uuid;
-
-    fn sample_user() -> CreateUser {
-        let suffix = Uuid::new_v4();
-        CreateUser {
-            email: format!("logger+{}@example.com", suffix),
-            username: format!("logger_{}", suffix.simple()),
-            password: "Sup3r$ecret".to_string(),
-            first_name: Some("Log".to_string()),
-            last_name: Some("Tester".to_string()),
-        }
-    }
 
     #[tokio::test]
     #[serial]
@@ -140,29 +293,37 @@ mod tests {
         let pool = pool().await?;
         reset_all_tables(pool.as_ref()).await?;
 
-        let user = create_user(sample_user())
-            .await
-            .expect("user creation must succeed for log tests");
+        let user = UserFactory::new().insert().await?;
 
-        let created_log = create_log(CreateAppLog {
-            level: "info".to_string(),
-            message: "Test log entry".to_string(),
-            metadata: Some(json!({"component": "log_test"})),
-            user_id: Some(user.id),
-        })
+        let created_log = create_log(
+            CommandContext::internal(),
+            CreateAppLog {
+                level: "info".to_string(),
+                message: "Test log entry".to_string(),
+                metadata: Some(json!({"component": "log_test"})),
+                user_id: Some(user.id),
+                tags: Some(vec!["Auth".to_string()]),
+            },
+        )
         .await
         .expect("log creation should succeed");
 
         assert_eq!(created_log.level, "info");
         assert_eq!(created_log.message, "Test log entry");
         assert_eq!(created_log.user_id, Some(user.id));
+        assert_eq!(created_log.tags, vec!["auth".to_string()]);
 
-        let logs = get_logs(LogQuery {
-            level: Some("info".to_string()),
-            user_id: Some(user.id),
-            limit: Some(10),
-            offset: Some(0),
-        })
+        let logs = get_logs(
+            CommandContext::internal(),
+            LogQuery {
+                level: Some("info".to_string()),
+                user_id: Some(user.id),
+                tags: Some(vec!["auth".to_string()]),
+                source: None,
+                limit: Some(10),
+                offset: Some(0),
+            },
+        )
         .await
         .expect("fetching logs should succeed");
 
@@ -170,21 +331,181 @@ mod tests {
         assert_eq!(logs[0].id, created_log.id);
         assert_eq!(logs[0].metadata["component"], json!("log_test"));
 
-        let deletion_message = delete_old_logs(0)
+        let user_context = CommandContext::for_user(user.id, Vec::new());
+        assert!(delete_old_logs(user_context, 0).await.is_err());
+
+        let deletion_message = delete_old_logs(CommandContext::internal(), 0)
             .await
             .expect("deleting old logs should succeed");
         assert!(deletion_message.starts_with("Deleted 1"));
 
-        let remaining_logs = get_logs(LogQuery {
-            level: None,
-            user_id: None,
-            limit: Some(10_000),
-            offset: Some(-5),
-        })
+        let remaining_logs = get_logs(
+            CommandContext::internal(),
+            LogQuery {
+                level: None,
+                user_id: None,
+                tags: None,
+                source: None,
+                limit: Some(10_000),
+                offset: Some(-5),
+            },
+        )
         .await
         .expect("fetch after deletion should succeed");
         assert!(remaining_logs.is_empty());
 
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn bulk_logs_are_written_on_flush() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let entries = (0..3)
+            .map(|i| {
+                LogFactory::new()
+                    .level("debug")
+                    .message(format!("Bulk entry {}", i))
+                    .build()
+            })
+            .collect();
+        let queued = create_logs_bulk(CommandContext::internal(), entries)
+            .await
+            .expect("bulk logs should be queued");
+        assert_eq!(queued, 3);
+
+        let flushed = db_sink::flush().await?;
+        assert_eq!(flushed, 3);
+
+        let logs = get_logs(
+            CommandContext::internal(),
+            LogQuery {
+                level: Some("debug".to_string()),
+                user_id: None,
+                tags: None,
+                source: None,
+                limit: Some(10),
+                offset: None,
+            },
+        )
+        .await
+        .expect("fetching logs should succeed");
+        assert_eq!(logs.len(), 3);
+
+        let invalid = create_logs_bulk(
+            CommandContext::internal(),
+            vec![LogFactory::new().level("loud").build()],
+        )
+        .await;
+        assert!(invalid.is_err());
+
+        Ok(())
+    }
+    #[tokio::test]
+    #[serial]
+    async fn frontend_errors_are_tagged_and_filtered_by_source() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+        LogFactory::new().level("error").insert().await?;
+
+        let errors: Vec<FrontendError> = serde_json::from_value(json!([
+            {
+                "kind": "unhandled_rejection",
+                "message": "Cannot read properties of undefined",
+                "stack": "TypeError: Cannot read properties of undefined\n    at index-4f2a.js:1:2048",
+                "location": { "file": "index-4f2a.js", "line": 1, "column": 2048 },
+                "originalLocation": { "file": "src/App.tsx", "line": 42, "column": 7 }
+            },
+            { "kind": "console", "message": "   " }
+        ]))?;
+        let context = CommandContext::for_user(user.id, Vec::new());
+        assert_eq!(ingest_frontend_errors(context, errors).await.unwrap(), 1);
+        db_sink::flush().await?;
+
+        let logs = get_logs(
+            CommandContext::internal(),
+            LogQuery {
+                level: None,
+                user_id: None,
+                tags: None,
+                source: Some("frontend".to_string()),
+                limit: None,
+                offset: None,
+            },
+        )
+        .await
+        .expect("fetching logs should succeed");
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].user_id, Some(user.id));
+        assert_eq!(
+            logs[0].tags,
+            vec![FRONTEND_SOURCE_TAG, "unhandled_rejection"]
+        );
+        assert_eq!(logs[0].metadata["originalLocation"]["line"], json!(42));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn filters_logs_by_all_requested_tags() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let both = LogFactory::new().tags(&["billing", "sync"]).insert().await?;
+        LogFactory::new().tags(&["billing"]).insert().await?;
+        LogFactory::new().insert().await?;
+
+        let query = |tags: &[&str]| LogQuery {
+            level: None,
+            user_id: None,
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            source: None,
+            limit: None,
+            offset: None,
+        };
+
+        let fetch = |tags: &[&str]| get_logs(CommandContext::internal(), query(tags));
+
+        assert_eq!(fetch(&["billing"]).await.unwrap().len(), 2);
+        let logs = fetch(&["SYNC", "billing"]).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].id, both.id);
+        assert!(fetch(&["bad tag"]).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn non_admins_only_see_their_own_logs() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let own = LogFactory::new().with_new_user().insert().await?;
+        let other = LogFactory::new().with_new_user().insert().await?;
+        let context = || CommandContext::for_user(own.user_id.unwrap(), Vec::new());
+        let query = |user_id| LogQuery {
+            level: None,
+            user_id,
+            tags: None,
+            source: None,
+            limit: None,
+            offset: None,
+        };
+
+        let logs = get_logs(context(), query(None)).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].id, own.id);
+        let error = get_logs(context(), query(other.user_id)).await.unwrap_err();
+        assert!(error.contains("FORBIDDEN"));
+        assert!(get_logs(CommandContext::anonymous(), query(None))
+            .await
+            .is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,39 @@
+//! Maintenance mode command handlers.
+
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::errors::{AppError, AppResult};
+use crate::maintenance::{self, MaintenanceStatus};
+
+/// Pauses non-essential commands until `exit_maintenance_mode`. Requires the
+/// admin role.
+#[tauri::command]
+pub async fn enter_maintenance_mode(
+    context: CommandContext,
+    reason: Option<String>,
+) -> AppResult<MaintenanceStatus> {
+    context.require_role(ADMIN_ROLE)?;
+    let reason = reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty())
+        .unwrap_or_else(|| "maintenance".to_string());
+    if reason.chars().count() > 200 {
+        return Err(AppError::invalid_input(
+            "reason",
+            "Reason must be at most 200 characters",
+        ));
+    }
+    Ok(maintenance::enter(&reason))
+}
+
+/// Resumes paused commands once no backup, restore, or migration is running.
+/// Requires the admin role.
+#[tauri::command]
+pub async fn exit_maintenance_mode(context: CommandContext) -> AppResult<MaintenanceStatus> {
+    context.require_role(ADMIN_ROLE)?;
+    Ok(maintenance::exit())
+}
+
+#[tauri::command]
+pub async fn get_maintenance_status() -> AppResult<MaintenanceStatus> {
+    Ok(maintenance::status())
+}
@@ -0,0 +1,9 @@
+//! Metrics command handlers.
+
+use crate::metrics;
+
+/// Returns command, database pool, and cache metrics in the Prometheus text format.
+#[tauri::command]
+pub async fn get_metrics_prometheus() -> Result<String, String> {
+    Ok(metrics::render_prometheus())
+}
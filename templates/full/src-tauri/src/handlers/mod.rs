@@ -1,20 +1,142 @@
 //! Tauri command handlers for all application features.
 //!
 //! Contains all the backend handlers that respond to frontend requests,
-//! organized by feature area (users, logs, filesystem, etc.).
+//! organized by feature area (users, logs, filesystem, etc.). Handlers that
+//! need PostgreSQL are compiled only with the `database` cargo feature. The
+//! `sqlite` feature adds [`local_db`], which serves the database, user,
+//! session, and log commands when `DATABASE_URL` selects SQLite; without
+//! `database` it registers them directly.
 
+#[cfg(feature = "database")]
+pub mod admin;
+#[cfg(feature = "database")]
+pub mod audit;
+#[cfg(feature = "database")]
+pub mod backup;
 pub mod cache;
+pub mod clipboard;
+pub mod commands;
+pub mod companion;
+pub mod csv_io;
+#[cfg(feature = "database")]
 pub mod database;
+pub mod discovery;
+pub mod email;
+#[cfg(feature = "database")]
+pub mod email_verification;
+pub mod feedback;
 pub mod filesystem;
+pub mod fonts;
+#[cfg(feature = "database")]
+pub mod groups;
+pub mod http;
+pub mod image_metadata;
+pub mod inspector;
+#[cfg(feature = "sqlite")]
+pub mod local_db;
+#[cfg(feature = "database")]
 pub mod logs;
+pub mod maintenance;
+pub mod metrics;
+#[cfg(feature = "database")]
+pub mod notifications;
+#[cfg(feature = "database")]
+pub mod oauth;
+pub mod operations;
+pub mod pdf;
+#[cfg(feature = "database")]
+pub mod portability;
+#[cfg(feature = "database")]
+pub mod privacy;
 pub mod rate_limited;
+pub mod scripts;
+#[cfg(feature = "database")]
+pub mod search;
+pub mod secrets;
+pub mod server;
+#[cfg(feature = "database")]
+pub mod session;
+#[cfg(feature = "database")]
+pub mod settings;
+pub mod setup;
+#[cfg(feature = "database")]
+pub mod sql_console;
+pub mod state_store;
+#[cfg(feature = "database")]
+pub mod sync;
 pub mod system;
+pub mod telemetry;
+pub mod themes;
+pub mod transfer;
+pub mod updater;
+#[cfg(any(feature = "database", feature = "sqlite"))]
 pub mod users;
+#[cfg(feature = "database")]
+pub mod workspaces;
 
+#[cfg(feature = "database")]
+pub use admin::*;
+#[cfg(feature = "database")]
+pub use audit::*;
+#[cfg(feature = "database")]
+pub use backup::*;
 pub use cache::*;
+pub use clipboard::*;
+pub use commands::*;
+pub use companion::*;
+pub use csv_io::*;
+#[cfg(feature = "database")]
 pub use database::*;
+pub use discovery::*;
+pub use email::*;
+#[cfg(feature = "database")]
+pub use email_verification::*;
+pub use feedback::*;
 pub use filesystem::*;
+pub use fonts::*;
+#[cfg(feature = "database")]
+pub use groups::*;
+pub use http::*;
+pub use image_metadata::*;
+pub use inspector::*;
+#[cfg(all(feature = "sqlite", not(feature = "database")))]
+pub use local_db::*;
+#[cfg(feature = "database")]
 pub use logs::*;
+pub use maintenance::*;
+pub use metrics::*;
+#[cfg(feature = "database")]
+pub use notifications::*;
+#[cfg(feature = "database")]
+pub use oauth::*;
+pub use operations::*;
+pub use pdf::*;
+#[cfg(feature = "database")]
+pub use portability::*;
+#[cfg(feature = "database")]
+pub use privacy::*;
 pub use rate_limited::*;
+pub use scripts::*;
+#[cfg(feature = "database")]
+pub use search::*;
+pub use secrets::*;
+pub use server::*;
+#[cfg(feature = "database")]
+pub use session::*;
+#[cfg(feature = "database")]
+pub use settings::*;
+pub use setup::*;
+#[cfg(feature = "database")]
+pub use sql_console::*;
+pub use state_store::*;
+#[cfg(feature = "database")]
+pub use sync::*;
 pub use system::*;
-pub use users::*;
\ No newline at end of file
+pub use telemetry::*;
+pub use themes::*;
+pub use transfer::*;
+pub use updater::*;
+#[cfg(feature = "database")]
+pub use users::*;
+#[cfg(feature = "database")]
+pub use workspaces::*;
@@ -0,0 +1,131 @@
+//! Notification history (in-app inbox) command handlers.
+
+use crate::database::filter::FilterBuilder;
+use crate::database::{get_pool_ref, query_stats};
+use crate::events::{self, AppEvent};
+use crate::models::{Notification, NotificationHistory, NotificationQuery, SortDirection};
+use sqlx::{Execute, PgPool};
+use uuid::Uuid;
+
+/// Persists a sent notification and publishes the new unread badge count.
+pub(crate) async fn record_notification(
+    title: &str,
+    body: &str,
+    payload: Option<serde_json::Value>,
+    user_id: Option<Uuid>,
+) -> Result<Notification, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+
+    let query = sqlx::query_as!(
+        Notification,
+        r#"
+        INSERT INTO notifications (user_id, title, body, payload)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id,
+                  user_id,
+                  title,
+                  body,
+                  payload AS "payload!",
+                  read_at,
+                  created_at AS "created_at!"
+        "#,
+        user_id,
+        title,
+        body,
+        payload.unwrap_or_else(|| serde_json::json!({})),
+    );
+    let notification = query_stats::timed(query.sql(), query.fetch_one(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to record notification: {}", e))?;
+
+    publish_badge_count(pool.as_ref()).await;
+    Ok(notification)
+}
+
+/// Returns notification history, newest first, with the unread count.
+#[tauri::command]
+pub async fn get_notification_history(
+    query: Option<NotificationQuery>,
+) -> Result<NotificationHistory, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+
+    let NotificationQuery {
+        user_id,
+        unread_only,
+        limit,
+        offset,
+    } = query.unwrap_or_default();
+
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    let offset = offset.unwrap_or(0).max(0);
+
+    let mut filter = FilterBuilder::new(
+        "SELECT id,
+                user_id,
+                title,
+                body,
+                payload,
+                read_at,
+                created_at
+         FROM notifications",
+    );
+    filter.eq_opt("user_id", user_id);
+    if unread_only.unwrap_or(false) {
+        filter.condition("read_at IS NULL");
+    }
+    filter
+        .order_by(&"created_at", SortDirection::Desc, None)
+        .paginate(limit, offset);
+
+    let mut builder = filter.into_builder();
+    let query = builder.build_query_as::<Notification>();
+    let notifications = query_stats::timed(query.sql(), query.fetch_all(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to fetch notifications: {}", e))?;
+
+    let unread_count = unread_count(pool.as_ref())
+        .await
+        .map_err(|e| format!("Failed to count unread notifications: {}", e))?;
+
+    Ok(NotificationHistory {
+        notifications,
+        unread_count,
+    })
+}
+
+/// Marks a notification as read and publishes the updated badge count.
+#[tauri::command]
+pub async fn mark_notification_read(notification_id: String) -> Result<String, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let uuid = Uuid::parse_str(&notification_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let query = sqlx::query!(
+        "UPDATE notifications SET read_at = COALESCE(read_at, CURRENT_TIMESTAMP) WHERE id = $1",
+        uuid,
+    );
+    let result = query_stats::timed(query.sql(), query.execute(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to mark notification as read: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err("Notification not found".to_string());
+    }
+
+    publish_badge_count(pool.as_ref()).await;
+    Ok("Notification marked as read".to_string())
+}
+
+async fn unread_count(pool: &PgPool) -> Result<i64, sqlx::Error> {
+    let query = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM notifications WHERE read_at IS NULL"#
+    );
+    query_stats::timed(query.sql(), query.fetch_one(pool))
+        .await
+}
+
+async fn publish_badge_count(pool: &PgPool) {
+    match unread_count(pool).await {
+        Ok(unread) => events::publish(AppEvent::NotificationBadge { unread }),
+        Err(e) => tracing::warn!("Failed to count unread notifications: {}", e),
+    }
+}
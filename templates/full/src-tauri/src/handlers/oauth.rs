@@ -0,0 +1,36 @@
+//! OAuth2 / OpenID Connect sign-in command handlers.
+
+use crate::audit;
+use crate::auth::oauth::{self, OAuthLoginStart};
+use crate::database::get_pool_ref;
+use crate::errors::{AppResult, ErrorCode, IntoAppError};
+use crate::models::LoginResponse;
+use serde_json::json;
+use tauri::AppHandle;
+
+/// Opens the provider's sign-in page in the browser. Pass the returned
+/// `state` to `complete_oauth_login`.
+#[tauri::command]
+pub async fn start_oauth_login(app_handle: AppHandle) -> AppResult<OAuthLoginStart> {
+    oauth::start(&app_handle).await
+}
+
+/// Waits for the browser sign-in to finish and starts a session for the
+/// linked user. Both outcomes are audited.
+#[tauri::command]
+pub async fn complete_oauth_login(state: String) -> AppResult<LoginResponse> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let result = oauth::complete(pool.as_ref(), &state).await;
+    match &result {
+        Ok(response) => {
+            let user_id = Some(response.user.id);
+            let details = json!({ "method": "oauth" });
+            audit::record_detached(user_id, audit::LOGIN_SUCCEEDED, user_id, details).await
+        }
+        Err(e) => {
+            let details = json!({ "method": "oauth", "error": e.code.to_string() });
+            audit::record_detached(None, audit::LOGIN_FAILED, None, details).await
+        }
+    }
+    result
+}
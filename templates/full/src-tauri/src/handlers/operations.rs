@@ -0,0 +1,12 @@
+//! Long-running operation command handlers.
+
+use crate::errors::AppResult;
+use crate::operations;
+use uuid::Uuid;
+
+/// Requests cancellation of a running operation. Returns `false` when no
+/// operation with that id is running, e.g. because it already finished.
+#[tauri::command]
+pub async fn cancel_operation(operation_id: Uuid) -> AppResult<bool> {
+    Ok(operations::cancel(operation_id))
+}
@@ -0,0 +1,16 @@
+//! PDF generation command handlers.
+
+use crate::errors::{AppError, AppResult};
+use crate::pdf::{self, GeneratedPdf, PdfTemplate};
+
+/// Renders an invoice or report PDF into the filesystem scope.
+#[tauri::command]
+pub async fn generate_pdf(
+    template: PdfTemplate,
+    data: serde_json::Value,
+    destination: Option<String>,
+) -> AppResult<GeneratedPdf> {
+    tokio::task::spawn_blocking(move || pdf::generate(template, data, destination))
+        .await
+        .map_err(|e| AppError::internal_error(format!("PDF generation task failed: {}", e)))?
+}
@@ -0,0 +1,40 @@
+//! User data export and import command handlers.
+
+use crate::command_context::CommandContext;
+use crate::errors::{AppError, AppResult};
+use crate::operations;
+use crate::portability::{self, ConflictStrategy, ExportFormat, TransferSummary};
+use uuid::Uuid;
+
+/// Exports a user's account, settings, and logs to a zip archive in the fs scope.
+/// Only the user and admins may export it.
+#[tauri::command]
+pub async fn export_user_data(
+    context: CommandContext,
+    user_id: String,
+    format: Option<ExportFormat>,
+    destination: Option<String>,
+) -> AppResult<TransferSummary> {
+    let user_id = Uuid::parse_str(&user_id)
+        .map_err(|e| AppError::invalid_input("userId", format!("Invalid UUID: {}", e)))?;
+    context.require_self_or_admin(user_id)?;
+
+    portability::export_user_data(user_id, format.unwrap_or(ExportFormat::Json), destination).await
+}
+
+/// Imports a previously exported archive, resolving conflicts with `strategy`.
+/// Non-admins may only import their own account's archive.
+#[tauri::command]
+pub async fn import_user_data(
+    context: CommandContext,
+    path: String,
+    strategy: Option<ConflictStrategy>,
+    operation_id: Option<Uuid>,
+) -> AppResult<TransferSummary> {
+    operations::track(
+        "import",
+        operation_id,
+        portability::import_user_data(&context, &path, strategy.unwrap_or_default()),
+    )
+    .await
+}
@@ -0,0 +1,106 @@
+//! Personal data export and erasure command handlers.
+
+use crate::auth::throttle;
+use crate::command_context::CommandContext;
+use crate::database::get_pool_ref;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::models::{LoginRequest, LoginResponse, PublicUser};
+use crate::portability::TransferSummary;
+use crate::privacy::{self, ErasureSummary};
+use crate::repository::{PgUserRepository, UserRepository};
+use crate::session;
+use crate::validation::validate_email;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Exports everything stored about a user as a JSON archive in the fs scope.
+/// Only the user and admins may export it.
+#[tauri::command]
+pub async fn export_personal_data(
+    context: CommandContext,
+    user_id: String,
+    destination: Option<String>,
+) -> AppResult<TransferSummary> {
+    let user_id = parse_user_id(&user_id)?;
+    context.require_self_or_admin(user_id)?;
+    privacy::export_personal_data(context.actor_id(), user_id, destination).await
+}
+
+/// Permanently erases a user's account, logs, settings, cache entries, and
+/// exports. Only the user and admins may erase it.
+#[tauri::command]
+pub async fn erase_user_data(
+    context: CommandContext,
+    user_id: String,
+) -> AppResult<ErasureSummary> {
+    let user_id = parse_user_id(&user_id)?;
+    context.require_self_or_admin(user_id)?;
+    privacy::erase_user_data(context.actor_id(), user_id).await
+}
+
+/// Deletes the caller's account after `ACCOUNT_DELETION_GRACE_DAYS`,
+/// returning when. The account is deactivated and signed out everywhere
+/// right away; `cancel_account_deletion` restores it until then.
+#[tauri::command]
+pub async fn request_account_deletion(context: CommandContext) -> AppResult<DateTime<Utc>> {
+    require_postgres()?;
+    let user_id = context.require_user()?;
+    if context.impersonator_id.is_some() {
+        return Err(AppError::forbidden(
+            "Accounts cannot be deleted while impersonated",
+        ));
+    }
+    privacy::request_deletion(user_id).await
+}
+
+/// Cancels the pending deletion of the account `credentials` sign in to and
+/// starts a session like `authenticate_user`. Returns `None` when they match
+/// no account awaiting deletion.
+#[tauri::command]
+pub async fn cancel_account_deletion(
+    credentials: LoginRequest,
+) -> AppResult<Option<LoginResponse>> {
+    require_postgres()?;
+    let email = validate_email(&credentials.email)
+        .map_err(|e| AppError::invalid_input("email", e.to_string()))?;
+    let device_id = credentials.device_id.as_deref();
+    throttle::check(&email, device_id)
+        .map_err(|e| AppError::new(ErrorCode::AuthenticationFailed, e))?;
+
+    let Some(user_id) = privacy::cancel_deletion(&email, &credentials.password).await? else {
+        throttle::record_failure(&email, device_id);
+        return Ok(None);
+    };
+    throttle::record_success(&email, device_id);
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let user = PgUserRepository::new(pool.as_ref())
+        .find(user_id)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?
+        .ok_or_else(|| AppError::not_found("User"))?;
+    let session = session::start(pool.as_ref(), user_id, credentials.remember_me).await?;
+    Ok(Some(LoginResponse {
+        user: PublicUser::from(user),
+        session,
+    }))
+}
+
+/// Deletions are scheduled in PostgreSQL, so they are unavailable while
+/// `DATABASE_URL` selects the embedded SQLite database.
+fn require_postgres() -> AppResult<()> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return Err(AppError::new(
+            ErrorCode::NotImplemented,
+            "Account deletion needs PostgreSQL, but DATABASE_URL selects SQLite",
+        ));
+    }
+
+    Ok(())
+}
+
+fn parse_user_id(user_id: &str) -> AppResult<Uuid> {
+    Uuid::parse_str(user_id)
+        .map_err(|e| AppError::invalid_input("userId", format!("Invalid UUID: {}", e)))
+}
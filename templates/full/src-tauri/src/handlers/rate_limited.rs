@@ -1,117 +1,330 @@
 //! Rate-limited wrappers for all Tauri command handlers.
 
+use crate::command_trace::{self, Outcome};
 use crate::rate_limiter::RateLimiterConfig;
 use crate::handlers::*;
-use crate::logging::handlers::{get_log_config, update_log_config, get_log_entries, clear_old_logs, get_log_stats, create_test_log};
+use crate::logging::handlers::{get_log_config, update_log_config, get_log_entries, get_log_page, tail_log_file, stop_log_tail, clear_old_logs, get_log_stats, create_test_log, aggregate_logs};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::State;
+use tracing::Instrument;
+
+/// Runs `handler` behind the rate limiter inside a traced command span.
+///
+/// `invoked` is the IPC command name and `command` the wrapped handler name
+/// used for metrics and the [maintenance](crate::maintenance) check.
+/// Completion is reported to the invocation inspector.
+async fn traced<T, E, F>(
+    invoked: &str,
+    command: &str,
+    rate_limiter: &RateLimiterConfig,
+    handler: F,
+) -> Result<T, String>
+where
+    E: std::fmt::Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let span = command_trace::start(invoked, command);
+
+    if let Err(e) = rate_limiter.check_rate_limit(None).await {
+        let message = format!("Rate limit exceeded: {}", e);
+        command_trace::finish(&span, command, Duration::ZERO, Outcome::RateLimited, Some(&message));
+        crate::inspector::finish(invoked, Some(message.clone()));
+        return Err(message);
+    }
+
+    if let Err(e) = crate::maintenance::check(command) {
+        let message = e.to_string();
+        command_trace::finish(&span, command, Duration::ZERO, Outcome::Error, Some(&message));
+        crate::inspector::finish(invoked, Some(message.clone()));
+        return Err(message);
+    }
+
+    let started = Instant::now();
+    let result = handler.instrument(span.clone()).await.map_err(|e| e.to_string());
+    let outcome = if result.is_ok() { Outcome::Ok } else { Outcome::Error };
+    let error = result.as_ref().err();
+    command_trace::finish(&span, command, started.elapsed(), outcome, error.map(String::as_str));
+    crate::inspector::finish(invoked, error.cloned());
+    result
+}
+
+/// Declares the argument schema of `$command` for `list_commands`.
+///
+/// The schema is a module named after the command. Modules and functions live
+/// in separate namespaces, so the built-in handler list can reach both the
+/// command and its `args()` through the one identifier.
+macro_rules! command_args {
+    ($command:ident, $($param:ident: $param_type:ty),* $(,)?) => {
+        #[doc(hidden)]
+        pub mod $command {
+            pub fn args() -> Vec<$crate::registry::ArgSpec> {
+                vec![$($crate::registry::ArgSpec::new(
+                    stringify!($param),
+                    stringify!($param_type),
+                )),*]
+            }
+        }
+    };
+}
 
 /// Helper macro to create rate-limited wrappers for command handlers.
+///
+/// With `@context` after the handler name, the wrapper resolves the caller's
+/// [`CommandContext`](crate::command_context::CommandContext) from the
+/// invocation and passes it as the handler's first argument.
 macro_rules! create_rate_limited_handler {
-    ($func_name:ident, $original_func:ident, $($param:ident: $param_type:ty),* $(,)?) => {
+    ($func_name:ident, $original_func:ident, @context $(, $param:ident: $param_type:ty)* $(,)?) => {
+        command_args!($func_name, $($param: $param_type),*);
+
         #[tauri::command]
         pub async fn $func_name(
             rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+            ipc_request: tauri::ipc::Request<'_>,
             $($param: $param_type,)*
         ) -> Result<serde_json::Value, String> {
-            if let Err(e) = rate_limiter.check_rate_limit(None).await {
-                tracing::warn!("Rate limit exceeded: {}", e);
-                return Err(format!("Rate limit exceeded: {}", e));
-            }
+            let value = traced(
+                stringify!($func_name),
+                stringify!($original_func),
+                &rate_limiter,
+                async {
+                    let context = crate::command_context::from_request(&ipc_request).await;
+                    $original_func(context, $($param,)*).await
+                },
+            )
+            .await?;
+            serde_json::to_value(value).map_err(|e| format!("Serialization error: {}", e))
+        }
+    };
+    ($func_name:ident, $original_func:ident, $($param:ident: $param_type:ty),* $(,)?) => {
+        command_args!($func_name, $($param: $param_type),*);
 
-            let result = $original_func($($param,)*).await;
-            match result {
-                Ok(value) => serde_json::to_value(value).map_err(|e| format!("Serialization error: {}", e)),
-                Err(e) => Err(format!("{}", e)),
-            }
+        #[tauri::command]
+        pub async fn $func_name(
+            rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+            $($param: $param_type,)*
+        ) -> Result<serde_json::Value, String> {
+            let value = traced(
+                stringify!($func_name),
+                stringify!($original_func),
+                &rate_limiter,
+                $original_func($($param,)*),
+            )
+            .await?;
+            serde_json::to_value(value).map_err(|e| format!("Serialization error: {}", e))
         }
     };
 }
 
 // Create rate-limited wrappers for database commands
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_check_database_connection,
     check_database_connection,
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_initialize_database,
     initialize_database,
+    password: Option<String>
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_run_migrations,
     run_migrations,
+    operation_id: Option<uuid::Uuid>
 );
 
 // Create rate-limited wrappers for user commands
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_get_all_users,
     get_all_users,
+    @context,
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_search_users,
+    search_users,
+    @context,
+    query: Option<String>,
+    filters: Option<crate::models::UserSearchFilters>,
+    sort: Option<crate::models::UserSort>,
+    pagination: Option<crate::models::Pagination>
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_get_user_by_id,
     get_user_by_id,
+    @context,
     user_id: String
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_create_user,
     create_user,
+    @context,
     user: crate::models::CreateUser
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_update_user,
     update_user,
+    @context,
     user_id: String,
     user: crate::models::UpdateUser
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_delete_user,
     delete_user,
+    @context,
     user_id: String
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_authenticate_user,
     authenticate_user,
     credentials: crate::models::LoginRequest
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_change_password,
+    change_password,
+    @context,
+    user_id: String,
+    current_password: String,
+    new_password: String
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_create_guest_user,
+    create_guest_user,
+    @context,
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_upgrade_guest_account,
+    upgrade_guest_account,
+    @context,
+    guest_id: String,
+    credentials: crate::models::CreateUser
+);
+
 // Create rate-limited wrappers for log commands
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_create_log,
     create_log,
+    @context,
     log_data: crate::models::CreateAppLog
 );
 
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_create_logs_bulk,
+    create_logs_bulk,
+    @context,
+    entries: Vec<crate::models::CreateAppLog>
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_ingest_frontend_errors,
+    ingest_frontend_errors,
+    @context,
+    errors: Vec<crate::models::FrontendError>
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_get_logs,
     get_logs,
+    @context,
     query: crate::models::logs::LogQuery
 );
 
+#[cfg(any(feature = "database", feature = "sqlite"))]
 create_rate_limited_handler!(
     rl_delete_old_logs,
     delete_old_logs,
+    @context,
     days: i32
 );
 
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_apply_log_retention,
+    apply_log_retention,
+    @context,
+    dry_run: Option<bool>
+);
+
 // Create rate-limited wrappers for system commands
 create_rate_limited_handler!(
     rl_get_system_info,
     get_system_info,
 );
 
+create_rate_limited_handler!(
+    rl_get_app_version_info,
+    get_app_version_info,
+);
+
+create_rate_limited_handler!(
+    rl_get_startup_status,
+    get_startup_status,
+);
+
+create_rate_limited_handler!(
+    rl_get_locale_info,
+    get_locale_info,
+);
+
+create_rate_limited_handler!(
+    rl_take_pending_file_opens,
+    take_pending_file_opens,
+);
+
+create_rate_limited_handler!(
+    rl_list_storage_devices,
+    list_storage_devices,
+    removable_only: Option<bool>
+);
+
 create_rate_limited_handler!(
     rl_send_notification,
     send_notification,
     app: tauri::AppHandle,
     title: String,
-    body: String
+    body: String,
+    payload: Option<serde_json::Value>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_get_notification_history,
+    get_notification_history,
+    query: Option<crate::models::NotificationQuery>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_mark_notification_read,
+    mark_notification_read,
+    notification_id: String
 );
 
 create_rate_limited_handler!(
@@ -157,7 +370,8 @@ create_rate_limited_handler!(
     rl_execute_command,
     execute_command,
     command: String,
-    args: Vec<String>
+    args: Vec<String>,
+    options: Option<crate::handlers::system::CommandOptions>
 );
 
 create_rate_limited_handler!(
@@ -196,6 +410,7 @@ create_rate_limited_handler!(
 create_rate_limited_handler!(
     rl_delete_file,
     delete_file,
+    @context,
     path: String
 );
 
@@ -208,7 +423,8 @@ create_rate_limited_handler!(
 create_rate_limited_handler!(
     rl_list_directory,
     list_directory,
-    path: String
+    path: String,
+    include_metadata: Option<bool>
 );
 
 create_rate_limited_handler!(
@@ -227,7 +443,8 @@ create_rate_limited_handler!(
     rl_copy_file,
     copy_file,
     src: String,
-    dst: String
+    dst: String,
+    operation_id: Option<uuid::Uuid>
 );
 
 create_rate_limited_handler!(
@@ -239,83 +456,102 @@ create_rate_limited_handler!(
 
 // Create rate-limited wrappers for logging commands
 // Logging commands with correct parameter types
+command_args!(rl_get_log_config,);
 #[tauri::command]
 pub async fn rl_get_log_config(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
 ) -> Result<crate::logging::config::AppLogConfig, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
-
-    get_log_config().await
+    traced("rl_get_log_config", "get_log_config", &rate_limiter, get_log_config()).await
 }
 
+command_args!(rl_update_log_config, config: crate::logging::config::AppLogConfig);
 #[tauri::command]
 pub async fn rl_update_log_config(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
     config: crate::logging::config::AppLogConfig,
 ) -> Result<String, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
-
-    update_log_config(config).await
+    traced("rl_update_log_config", "update_log_config", &rate_limiter, update_log_config(config)).await
 }
 
+command_args!(rl_get_log_entries, params: crate::logging::handlers::LogQueryParams);
 #[tauri::command]
 pub async fn rl_get_log_entries(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
     params: crate::logging::handlers::LogQueryParams,
 ) -> Result<crate::logging::handlers::LogResponse, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
+    traced("rl_get_log_entries", "get_log_entries", &rate_limiter, get_log_entries(params)).await
+}
 
-    get_log_entries(params).await
+command_args!(
+    rl_get_log_page,
+    params: crate::logging::handlers::LogQueryParams,
+    cursor: Option<crate::logging::handlers::LogCursor>
+);
+#[tauri::command]
+pub async fn rl_get_log_page(
+    rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    params: crate::logging::handlers::LogQueryParams,
+    cursor: Option<crate::logging::handlers::LogCursor>,
+) -> Result<crate::logging::handlers::LogPage, String> {
+    traced("rl_get_log_page", "get_log_page", &rate_limiter, get_log_page(params, cursor)).await
 }
 
+command_args!(rl_tail_log_file, prefix: String, follow: bool);
+#[tauri::command]
+pub async fn rl_tail_log_file(
+    rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    prefix: String,
+    follow: bool,
+) -> Result<uuid::Uuid, String> {
+    traced("rl_tail_log_file", "tail_log_file", &rate_limiter, tail_log_file(prefix, follow)).await
+}
+
+command_args!(rl_stop_log_tail, tail_id: uuid::Uuid);
+#[tauri::command]
+pub async fn rl_stop_log_tail(
+    rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    tail_id: uuid::Uuid,
+) -> Result<bool, String> {
+    traced("rl_stop_log_tail", "stop_log_tail", &rate_limiter, stop_log_tail(tail_id)).await
+}
+
+command_args!(rl_clear_old_logs, days_to_keep: u32);
 #[tauri::command]
 pub async fn rl_clear_old_logs(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
     days_to_keep: u32,
 ) -> Result<String, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
-
-    clear_old_logs(days_to_keep).await
+    traced("rl_clear_old_logs", "clear_old_logs", &rate_limiter, clear_old_logs(days_to_keep)).await
 }
 
+command_args!(rl_get_log_stats,);
 #[tauri::command]
 pub async fn rl_get_log_stats(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
 ) -> Result<std::collections::HashMap<String, serde_json::Value>, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
-
-    get_log_stats().await
+    traced("rl_get_log_stats", "get_log_stats", &rate_limiter, get_log_stats()).await
 }
 
+command_args!(rl_create_test_log, level: String, message: String);
 #[tauri::command]
 pub async fn rl_create_test_log(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
     level: String,
     message: String,
 ) -> Result<String, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
-
-    create_test_log(level, message).await
+    traced("rl_create_test_log", "create_test_log", &rate_limiter, create_test_log(level, message)).await
 }
 
+create_rate_limited_handler!(
+    rl_aggregate_logs,
+    aggregate_logs,
+    @context,
+    group_by: Vec<crate::logging::aggregate::LogGroupBy>,
+    interval: Option<String>,
+    range: Option<crate::logging::aggregate::LogRange>,
+    source: Option<crate::logging::aggregate::LogSource>
+);
+
 // Create rate-limited wrappers for cache commands
 create_rate_limited_handler!(
     rl_set_cache_value,
@@ -348,25 +584,851 @@ create_rate_limited_handler!(
     is_cache_available,
 );
 
-// Special handler for greet function
+// Create rate-limited wrappers for HTTP client commands
+create_rate_limited_handler!(
+    rl_http_request,
+    http_request,
+    method: String,
+    url: String,
+    headers: Option<std::collections::HashMap<String, String>>,
+    body: Option<serde_json::Value>,
+    options: Option<crate::http_client::HttpRequestOptions>
+);
+
+// Create rate-limited wrappers for local server commands
+create_rate_limited_handler!(
+    rl_get_local_server_status,
+    get_local_server_status,
+);
+
+create_rate_limited_handler!(
+    rl_register_local_server_route,
+    register_local_server_route,
+    path: String,
+    event: String
+);
+
+// Create rate-limited wrappers for companion server commands
+create_rate_limited_handler!(
+    rl_get_companion_server_status,
+    get_companion_server_status,
+);
+
+create_rate_limited_handler!(
+    rl_create_companion_pairing_token,
+    create_companion_pairing_token,
+    @context
+);
+
+create_rate_limited_handler!(
+    rl_revoke_companion_pairing,
+    revoke_companion_pairing,
+);
+
+// Create rate-limited wrappers for telemetry commands
+create_rate_limited_handler!(
+    rl_get_telemetry_status,
+    get_telemetry_status,
+);
+
+create_rate_limited_handler!(
+    rl_set_telemetry_consent,
+    set_telemetry_consent,
+    consent: bool
+);
+
+create_rate_limited_handler!(
+    rl_record_telemetry_event,
+    record_telemetry_event,
+    feature: String,
+    properties: Option<std::collections::HashMap<String, serde_json::Value>>
+);
+
+create_rate_limited_handler!(
+    rl_get_telemetry_events,
+    get_telemetry_events,
+);
+
+create_rate_limited_handler!(
+    rl_purge_telemetry_data,
+    purge_telemetry_data,
+);
+
+// Create rate-limited wrappers for updater commands
+create_rate_limited_handler!(
+    rl_check_for_updates,
+    check_for_updates,
+    app: tauri::AppHandle
+);
+
+create_rate_limited_handler!(
+    rl_download_update,
+    download_update,
+);
+
+create_rate_limited_handler!(
+    rl_install_update_and_restart,
+    install_update_and_restart,
+    app: tauri::AppHandle
+);
+
+// Create rate-limited wrappers for data portability commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_export_user_data,
+    export_user_data,
+    @context,
+    user_id: String,
+    format: Option<crate::portability::ExportFormat>,
+    destination: Option<String>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_import_user_data,
+    import_user_data,
+    @context,
+    path: String,
+    strategy: Option<crate::portability::ConflictStrategy>,
+    operation_id: Option<uuid::Uuid>
+);
+
+// Create rate-limited wrappers for sync commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_get_sync_status,
+    get_sync_status,
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_sync_now,
+    sync_now,
+);
+
+// Create rate-limited wrappers for metrics commands
+create_rate_limited_handler!(
+    rl_get_metrics_prometheus,
+    get_metrics_prometheus,
+);
+
+// Create rate-limited wrappers for invocation inspector commands
+create_rate_limited_handler!(
+    rl_get_recent_invocations,
+    get_recent_invocations,
+    limit: Option<usize>
+);
+
+create_rate_limited_handler!(
+    rl_clear_recent_invocations,
+    clear_recent_invocations,
+);
+
+// Create rate-limited wrappers for onboarding commands
+create_rate_limited_handler!(
+    rl_get_setup_status,
+    get_setup_status,
+);
+
+create_rate_limited_handler!(
+    rl_complete_setup_step,
+    complete_setup_step,
+    step: crate::setup::SetupStep
+);
+
+// Create rate-limited wrappers for feedback commands
+create_rate_limited_handler!(
+    rl_submit_feedback,
+    submit_feedback,
+    app: tauri::AppHandle,
+    message: String,
+    include_logs: bool,
+    include_system_info: bool,
+    screenshot: Option<String>,
+    upload: Option<bool>
+);
+
+// Create rate-limited wrappers for local state store commands
+create_rate_limited_handler!(
+    rl_get_state,
+    get_state,
+    key: String
+);
+
+create_rate_limited_handler!(
+    rl_set_state,
+    set_state,
+    key: String,
+    value: serde_json::Value
+);
+
+create_rate_limited_handler!(
+    rl_watch_state,
+    watch_state,
+    prefix: String
+);
+
+// Create rate-limited wrappers for search commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_search,
+    search,
+    query: String,
+    sources: Option<Vec<crate::search::SearchSource>>,
+    limit: Option<usize>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_rebuild_search_index,
+    rebuild_search_index,
+);
+
+// Create rate-limited wrappers for workspace commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_create_workspace,
+    create_workspace,
+    @context,
+    workspace_data: crate::models::CreateWorkspace
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_list_workspaces,
+    list_workspaces,
+    @context,
+    user_id: Option<String>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_add_workspace_member,
+    add_workspace_member,
+    @context,
+    workspace_id: String,
+    user_id: String,
+    role: Option<String>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_remove_workspace_member,
+    remove_workspace_member,
+    @context,
+    workspace_id: String,
+    user_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_switch_workspace,
+    switch_workspace,
+    @context,
+    workspace_id: Option<String>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_get_current_workspace,
+    get_current_workspace,
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_get_workspace_settings,
+    get_workspace_settings,
+    @context
+);
+
+// Create rate-limited wrappers for group commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_create_group,
+    create_group,
+    @context,
+    group_data: crate::models::CreateGroup
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_list_groups,
+    list_groups,
+    @context,
+    user_id: Option<String>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_update_group,
+    update_group,
+    @context,
+    group_id: String,
+    group_data: crate::models::UpdateGroup
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_delete_group,
+    delete_group,
+    @context,
+    group_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_list_group_members,
+    list_group_members,
+    @context,
+    group_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_add_group_member,
+    add_group_member,
+    @context,
+    group_id: String,
+    user_id: String,
+    role: Option<String>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_remove_group_member,
+    remove_group_member,
+    @context,
+    group_id: String,
+    user_id: String
+);
+
+// Create rate-limited wrappers for email commands
+create_rate_limited_handler!(
+    rl_configure_smtp,
+    configure_smtp,
+    settings: crate::email::SmtpSettings
+);
+
+create_rate_limited_handler!(
+    rl_send_email,
+    send_email,
+    to: String,
+    template: String,
+    data: std::collections::HashMap<String, String>
+);
+
+create_rate_limited_handler!(
+    rl_register_email_template,
+    register_email_template,
+    name: String,
+    subject: String,
+    body: String
+);
+
+create_rate_limited_handler!(
+    rl_get_email_deliveries,
+    get_email_deliveries,
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_send_verification_email,
+    send_verification_email,
+    user_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_verify_email,
+    verify_email,
+    token: String
+);
+
+// Create rate-limited wrappers for PDF commands
+create_rate_limited_handler!(
+    rl_generate_pdf,
+    generate_pdf,
+    template: crate::pdf::PdfTemplate,
+    data: serde_json::Value,
+    destination: Option<String>
+);
+
+// Create rate-limited wrappers for backup commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_create_backup,
+    create_backup,
+    @context,
+    app: tauri::AppHandle,
+    operation_id: Option<uuid::Uuid>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_list_backups,
+    list_backups,
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_restore_backup,
+    restore_backup,
+    @context,
+    app: tauri::AppHandle,
+    name: String,
+    include_vault: Option<bool>,
+    operation_id: Option<uuid::Uuid>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_export_backup_to_device,
+    export_backup_to_device,
+    @context,
+    name: String,
+    mount_point: String
+);
+
+// Create rate-limited wrappers for query statistics commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_get_query_stats,
+    get_query_stats,
+    limit: Option<usize>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_reset_query_stats,
+    reset_query_stats,
+);
+
+// Create rate-limited wrappers for advisory lock commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_acquire_advisory_lock,
+    acquire_advisory_lock,
+    name: String,
+    wait_ms: Option<u64>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_release_advisory_lock,
+    release_advisory_lock,
+    name: String
+);
+
+// Create rate-limited wrappers for SQL console commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_execute_sql,
+    execute_sql,
+    @context,
+    query: String,
+    params: Option<Vec<serde_json::Value>>,
+    read_only: Option<bool>
+);
+
+// Create rate-limited wrappers for personal data commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_export_personal_data,
+    export_personal_data,
+    @context,
+    user_id: String,
+    destination: Option<String>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_erase_user_data,
+    erase_user_data,
+    @context,
+    user_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_request_account_deletion,
+    request_account_deletion,
+    @context
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_cancel_account_deletion,
+    cancel_account_deletion,
+    credentials: crate::models::LoginRequest
+);
+
+// Create rate-limited wrappers for admin commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_get_admin_stats,
+    get_admin_stats,
+    @context
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_benchmark_password_hashing,
+    benchmark_password_hashing,
+    @context,
+    target_ms: Option<u64>,
+    apply: Option<bool>
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_get_audit_log,
+    get_audit_log,
+    @context,
+    query: crate::models::AuditQuery
+);
+
+// Create rate-limited wrappers for introspection commands
+create_rate_limited_handler!(
+    rl_list_commands,
+    list_commands,
+);
+
+// Create rate-limited wrappers for user settings commands
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_get_user_settings,
+    get_user_settings,
+    @context,
+    user_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_update_user_settings,
+    update_user_settings,
+    @context,
+    user_id: String,
+    changes: crate::models::UpdateUserSettings
+);
+
+// Create rate-limited wrappers for theme commands
+create_rate_limited_handler!(
+    rl_list_themes,
+    list_themes,
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_set_active_theme,
+    set_active_theme,
+    @context,
+    user_id: String,
+    theme_id: String
+);
+
+// Create rate-limited wrappers for font commands
+create_rate_limited_handler!(
+    rl_list_system_fonts,
+    list_system_fonts,
+);
+
+// Create rate-limited wrappers for clipboard image commands
+create_rate_limited_handler!(
+    rl_read_clipboard_image,
+    read_clipboard_image,
+    app: tauri::AppHandle
+);
+
+create_rate_limited_handler!(
+    rl_write_clipboard_image,
+    write_clipboard_image,
+    app: tauri::AppHandle,
+    png: String
+);
+
+create_rate_limited_handler!(
+    rl_copy_window_screenshot_to_clipboard,
+    copy_window_screenshot_to_clipboard,
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow
+);
+
+// Create rate-limited wrappers for clipboard history commands
+create_rate_limited_handler!(
+    rl_start_clipboard_history,
+    start_clipboard_history,
+    app: tauri::AppHandle,
+    password: String
+);
+
+create_rate_limited_handler!(
+    rl_stop_clipboard_history,
+    stop_clipboard_history,
+);
+
+create_rate_limited_handler!(
+    rl_list_clipboard_history,
+    list_clipboard_history,
+);
+
+create_rate_limited_handler!(
+    rl_clear_clipboard_history,
+    clear_clipboard_history,
+);
+
+// Create rate-limited wrappers for script commands
+create_rate_limited_handler!(
+    rl_list_scripts,
+    list_scripts,
+);
+
+create_rate_limited_handler!(
+    rl_run_script,
+    run_script,
+    name: String
+);
+
+create_rate_limited_handler!(
+    rl_schedule_script,
+    schedule_script,
+    name: String,
+    interval_minutes: Option<u64>
+);
+
+// Create rate-limited wrappers for CSV commands
+create_rate_limited_handler!(
+    rl_parse_csv,
+    parse_csv,
+    path: String,
+    options: Option<crate::csv_io::CsvParseOptions>
+);
+
+create_rate_limited_handler!(
+    rl_write_csv,
+    write_csv,
+    path: String,
+    rows: Vec<serde_json::Value>,
+    options: Option<crate::csv_io::CsvWriteOptions>
+);
+
+// Create rate-limited wrappers for proxy commands
+create_rate_limited_handler!(
+    rl_get_proxy_settings,
+    get_proxy_settings,
+    refresh: Option<bool>
+);
+
+// Create rate-limited wrappers for discovery commands
+create_rate_limited_handler!(
+    rl_start_discovery,
+    start_discovery,
+    app: tauri::AppHandle
+);
+
+create_rate_limited_handler!(
+    rl_stop_discovery,
+    stop_discovery,
+);
+
+create_rate_limited_handler!(
+    rl_list_peers,
+    list_peers,
+);
+
+// Create rate-limited wrappers for peer transfer commands
+create_rate_limited_handler!(
+    rl_send_file_to_peer,
+    send_file_to_peer,
+    peer_id: String,
+    path: String
+);
+
+create_rate_limited_handler!(
+    rl_respond_to_transfer,
+    respond_to_transfer,
+    transfer_id: String,
+    accept: bool
+);
+
+create_rate_limited_handler!(
+    rl_list_transfer_offers,
+    list_transfer_offers,
+);
+
+// Create rate-limited wrappers for secret record commands
+create_rate_limited_handler!(
+    rl_unlock_secrets,
+    unlock_secrets,
+    app: tauri::AppHandle,
+    password: String
+);
+
+create_rate_limited_handler!(
+    rl_lock_secrets,
+    lock_secrets,
+);
+
+create_rate_limited_handler!(
+    rl_save_secret,
+    save_secret,
+    name: String,
+    secret: crate::secrets::Secret
+);
+
+create_rate_limited_handler!(
+    rl_get_secret,
+    get_secret,
+    kind: crate::secrets::SecretKind,
+    name: String
+);
+
+create_rate_limited_handler!(
+    rl_delete_secret,
+    delete_secret,
+    kind: crate::secrets::SecretKind,
+    name: String
+);
+
+create_rate_limited_handler!(
+    rl_list_secrets,
+    list_secrets,
+    kind: Option<crate::secrets::SecretKind>
+);
+
+// Create rate-limited wrappers for session commands
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_refresh_session,
+    refresh_session,
+    refresh_token: String
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_validate_session,
+    validate_session,
+    access_token: String
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_session_heartbeat,
+    session_heartbeat,
+    access_token: String
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_logout,
+    logout,
+    refresh_token: String
+);
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+create_rate_limited_handler!(
+    rl_revoke_user_sessions,
+    revoke_user_sessions,
+    @context,
+    user_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_impersonate_user,
+    impersonate_user,
+    @context,
+    admin_id: String,
+    target_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_end_impersonation,
+    end_impersonation,
+    @context,
+    session_id: String
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_start_oauth_login,
+    start_oauth_login,
+    app_handle: tauri::AppHandle
+);
+
+#[cfg(feature = "database")]
+create_rate_limited_handler!(
+    rl_complete_oauth_login,
+    complete_oauth_login,
+    state: String
+);
+
+// Create rate-limited wrappers for operation commands
+create_rate_limited_handler!(
+    rl_cancel_operation,
+    cancel_operation,
+    operation_id: uuid::Uuid
+);
+
+// Create rate-limited wrappers for maintenance mode commands
+create_rate_limited_handler!(
+    rl_enter_maintenance_mode,
+    enter_maintenance_mode,
+    @context,
+    reason: Option<String>
+);
+
+create_rate_limited_handler!(
+    rl_exit_maintenance_mode,
+    exit_maintenance_mode,
+    @context
+);
+
+create_rate_limited_handler!(
+    rl_get_maintenance_status,
+    get_maintenance_status,
+);
+
+// Create rate-limited wrappers for image metadata commands
+create_rate_limited_handler!(
+    rl_get_image_metadata,
+    get_image_metadata,
+    path: String
+);
+
+create_rate_limited_handler!(
+    rl_strip_image_metadata,
+    strip_image_metadata,
+    path: String,
+    destination: Option<String>
+);
+
+// Special handler for greet function, a demo command left out of release
+// builds unless the `demo` feature is enabled
+#[cfg(any(debug_assertions, feature = "demo"))]
+command_args!(rl_greet, name: String);
+#[cfg(any(debug_assertions, feature = "demo"))]
 #[tauri::command]
 pub async fn rl_greet(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
     name: String,
 ) -> Result<String, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded for greet: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
-
-    Ok(format!("Hello, {}! You've been greeted from Rust!", name))
+    traced("rl_greet", "greet", &rate_limiter, async move {
+        Ok::<_, String>(format!("Hello, {}! You've been greeted from Rust!", name))
+    })
+    .await
 }
 
 // Rate limiter status command for monitoring
+command_args!(get_rate_limiter_status,);
 #[tauri::command]
 pub async fn get_rate_limiter_status(
     _rate_limiter: State<'_, Arc<RateLimiterConfig>>,
 ) -> Result<String, String> {
     // This command itself doesn't need rate limiting as it's for monitoring
-    Ok("Rate limiter is active and protecting all commands".to_string())
+    if cfg!(feature = "rate-limiter") {
+        Ok("Rate limiter is active and protecting all commands".to_string())
+    } else {
+        Ok("Rate limiting is not enabled in this build".to_string())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,22 @@
+//! User script command handlers.
+
+use crate::errors::AppResult;
+use crate::scripting::{self, ScriptInfo, ScriptRun};
+
+/// Lists scripts in the scripts directory.
+#[tauri::command]
+pub async fn list_scripts() -> AppResult<Vec<ScriptInfo>> {
+    scripting::list()
+}
+
+/// Runs a script now and returns its outcome.
+#[tauri::command]
+pub async fn run_script(name: String) -> AppResult<ScriptRun> {
+    scripting::run(&name).await
+}
+
+/// Sets or clears the interval a script runs on.
+#[tauri::command]
+pub async fn schedule_script(name: String, interval_minutes: Option<u64>) -> AppResult<ScriptInfo> {
+    scripting::schedule(&name, interval_minutes)
+}
@@ -0,0 +1,20 @@
+//! Full-text search command handlers.
+
+use crate::errors::AppResult;
+use crate::search::{self as search_index, SearchHit, SearchSource};
+
+/// Searches indexed users, logs, and files, returning ranked highlighted hits.
+#[tauri::command]
+pub async fn search(
+    query: String,
+    sources: Option<Vec<SearchSource>>,
+    limit: Option<usize>,
+) -> AppResult<Vec<SearchHit>> {
+    search_index::search(&query, sources, limit)
+}
+
+/// Rebuilds the search index immediately instead of waiting for the background task.
+#[tauri::command]
+pub async fn rebuild_search_index() -> AppResult<usize> {
+    search_index::rebuild_all().await
+}
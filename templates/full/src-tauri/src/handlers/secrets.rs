@@ -0,0 +1,44 @@
+//! Typed secret record command handlers.
+
+use crate::errors::AppResult;
+use crate::secrets::{self, Secret, SecretKind, SecretSummary};
+use tauri::AppHandle;
+
+/// Unlocks stored secrets with the vault password. The keychain backend is
+/// unlocked by the OS and ignores the password.
+///
+/// Returns the number of stored records.
+#[tauri::command]
+pub async fn unlock_secrets(app: AppHandle, password: String) -> AppResult<usize> {
+    secrets::unlock(&app, &password)
+}
+
+/// Locks stored secrets; returns whether they were unlocked.
+#[tauri::command]
+pub async fn lock_secrets() -> AppResult<bool> {
+    secrets::lock()
+}
+
+/// Stores a TOTP secret, API token, or database credential under `name`.
+#[tauri::command]
+pub async fn save_secret(name: String, secret: Secret) -> AppResult<()> {
+    secrets::put_secret(&name, &secret)
+}
+
+/// Returns the record `name` of `kind`, or `None` when there is none.
+#[tauri::command]
+pub async fn get_secret(kind: SecretKind, name: String) -> AppResult<Option<Secret>> {
+    secrets::get_secret(kind, &name)
+}
+
+/// Deletes the record `name` of `kind`; returns whether it existed.
+#[tauri::command]
+pub async fn delete_secret(kind: SecretKind, name: String) -> AppResult<bool> {
+    secrets::remove_kind(kind, &name)
+}
+
+/// Lists stored records without their secret values.
+#[tauri::command]
+pub async fn list_secrets(kind: Option<SecretKind>) -> AppResult<Vec<SecretSummary>> {
+    secrets::list(kind)
+}
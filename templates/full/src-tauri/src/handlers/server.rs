@@ -0,0 +1,18 @@
+//! Embedded local HTTP server command handlers.
+
+use crate::server::{self, LocalServerStatus};
+
+/// Returns whether the local callback server is running and its registered routes.
+#[tauri::command]
+pub async fn get_local_server_status() -> Result<LocalServerStatus, String> {
+    Ok(server::status())
+}
+
+/// Registers a route that forwards incoming requests to the frontend as `event`.
+#[tauri::command]
+pub async fn register_local_server_route(path: String, event: String) -> Result<String, String> {
+    server::register_route(&path, &event)
+        .map_err(|e| format!("Failed to register route: {}", e))?;
+
+    Ok(format!("Route '{}' registered", path))
+}
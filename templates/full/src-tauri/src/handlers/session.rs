@@ -0,0 +1,203 @@
+//! Login session command handlers.
+
+use crate::audit;
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::database::get_pool_ref;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::models::{LoginResponse, PublicUser, Session, SessionTokens};
+use crate::repository::{PgUserRepository, UserRepository};
+use crate::session;
+use serde_json::json;
+use uuid::Uuid;
+
+/// Exchanges a refresh token for a new access token and refresh token.
+#[tauri::command]
+pub async fn refresh_session(refresh_token: String) -> AppResult<SessionTokens> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::refresh_session(refresh_token).await;
+    }
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    session::refresh(pool.as_ref(), &refresh_token).await
+}
+
+/// Returns the session of an access token while it is still active.
+///
+/// Fails with `TOKEN_EXPIRED` or `UNAUTHORIZED` when the token can no longer
+/// be used.
+#[tauri::command]
+pub async fn validate_session(access_token: String) -> AppResult<Session> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::validate_session(access_token).await;
+    }
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    session::validate(pool.as_ref(), &access_token).await
+}
+
+/// Extends the signed-in user's session while they are active.
+#[tauri::command]
+pub async fn session_heartbeat(access_token: String) -> AppResult<Session> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::session_heartbeat(access_token).await;
+    }
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    session::heartbeat(pool.as_ref(), &access_token).await
+}
+
+/// Ends the session holding a refresh token; returns whether one was active.
+#[tauri::command]
+pub async fn logout(refresh_token: String) -> AppResult<bool> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::logout(refresh_token).await;
+    }
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    session::logout(pool.as_ref(), &refresh_token).await
+}
+
+/// Revokes every session of a user, signing them out everywhere. Only the
+/// user and admins may do this.
+#[tauri::command]
+pub async fn revoke_user_sessions(context: CommandContext, user_id: String) -> AppResult<u64> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::revoke_user_sessions(context, user_id).await;
+    }
+
+    let user_id = Uuid::parse_str(&user_id)
+        .map_err(|_| AppError::invalid_input("user_id", "Invalid user ID"))?;
+    context.require_self_or_admin(user_id)?;
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    session::revoke_user(pool.as_ref(), user_id).await
+}
+
+/// Starts a short-lived session as `target_id` so an admin can see the app
+/// as that user does, e.g. to reproduce a support issue.
+///
+/// `admin_id` must be the calling admin. Other admins cannot be
+/// impersonated. The session lapses after
+/// [`session::IMPERSONATION_MINUTES`] and is recorded in the audit log, as is
+/// everything done with it.
+#[tauri::command]
+pub async fn impersonate_user(
+    context: CommandContext,
+    admin_id: String,
+    target_id: String,
+) -> AppResult<LoginResponse> {
+    context.require_role(ADMIN_ROLE)?;
+    let admin_id = Uuid::parse_str(&admin_id)
+        .map_err(|_| AppError::invalid_input("admin_id", "Invalid user ID"))?;
+    let target_id = Uuid::parse_str(&target_id)
+        .map_err(|_| AppError::invalid_input("target_id", "Invalid user ID"))?;
+    if context.user_id.is_some_and(|caller| caller != admin_id) {
+        return Err(
+            AppError::forbidden("Admins can only impersonate as themselves")
+                .with_request_id(context.request_id.to_string()),
+        );
+    }
+    if admin_id == target_id {
+        return Err(AppError::invalid_input(
+            "target_id",
+            "Admins cannot impersonate themselves",
+        ));
+    }
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    if !active_roles(pool.as_ref(), admin_id)
+        .await?
+        .is_some_and(|roles| roles.iter().any(|role| role == ADMIN_ROLE))
+    {
+        return Err(AppError::forbidden("Only active admins can impersonate"));
+    }
+    let target = PgUserRepository::new(pool.as_ref())
+        .find(target_id)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?
+        .ok_or_else(|| AppError::invalid_input("target_id", "User not found"))?;
+    match active_roles(pool.as_ref(), target_id).await? {
+        None => {
+            return Err(AppError::invalid_input(
+                "target_id",
+                "Inactive accounts cannot be impersonated",
+            ))
+        }
+        Some(roles) if roles.iter().any(|role| role == ADMIN_ROLE) => {
+            return Err(AppError::forbidden("Admins cannot be impersonated"))
+        }
+        Some(_) => {}
+    }
+
+    let tokens = session::start_impersonation(pool.as_ref(), admin_id, target_id).await?;
+    tracing::info!("Admin {} is impersonating user {}", admin_id, target_id);
+    audit::record_detached(
+        Some(admin_id),
+        audit::IMPERSONATION_STARTED,
+        Some(target_id),
+        json!({
+            "sessionId": tokens.session_id,
+            "expiresAt": tokens.session_expires_at,
+        }),
+    )
+    .await;
+    Ok(LoginResponse {
+        user: PublicUser::from(target),
+        session: tokens,
+    })
+}
+
+/// Ends an impersonation session; returns whether one was active.
+///
+/// Works from the impersonation session itself, which may only end its own
+/// impersonations, and for admins, who may end any.
+#[tauri::command]
+pub async fn end_impersonation(context: CommandContext, session_id: String) -> AppResult<bool> {
+    let session_id = Uuid::parse_str(&session_id)
+        .map_err(|_| AppError::invalid_input("session_id", "Invalid session ID"))?;
+    let impersonator_id = if context.is_admin() {
+        None
+    } else {
+        context.require_user()?;
+        Some(context.impersonator_id.ok_or_else(|| {
+            AppError::forbidden("Not an impersonation session")
+                .with_request_id(context.request_id.to_string())
+        })?)
+    };
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let Some(ended) =
+        session::end_impersonation(pool.as_ref(), session_id, impersonator_id).await?
+    else {
+        return Ok(false);
+    };
+    tracing::info!(
+        "Impersonation session {} of user {} ended",
+        ended.id,
+        ended.user_id
+    );
+    audit::record_detached(
+        context.actor_id(),
+        audit::IMPERSONATION_ENDED,
+        Some(ended.user_id),
+        json!({
+            "sessionId": ended.id,
+            "impersonatorId": ended.impersonator_id,
+        }),
+    )
+    .await;
+    Ok(true)
+}
+
+/// Roles of `user_id`, or `None` when the account is missing or inactive.
+async fn active_roles(pool: &sqlx::PgPool, user_id: Uuid) -> AppResult<Option<Vec<String>>> {
+    sqlx::query_scalar("SELECT roles FROM users WHERE id = $1 AND is_active")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)
+}
@@ -0,0 +1,370 @@
+//! User settings command handlers.
+//!
+//! `settings_data` follows the typed [`AppSettings`] schema: writes are merged
+//! into the stored value, checked against the schema and validated before
+//! they are saved, and reads fill in defaults for fields added since the
+//! settings were last written. Every update is recorded in the audit log.
+
+use crate::audit;
+use crate::command_context::CommandContext;
+use crate::database::{get_pool_ref, query_stats};
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::events::{self, AppEvent};
+use crate::models::{AppSettings, UpdateUserSettings, UserSettings, SETTINGS_SCHEMA_VERSION};
+use crate::sync::{self, SyncOperation};
+use crate::themes;
+use serde_json::{json, Value};
+use sqlx::{Execute, PgPool};
+use uuid::Uuid;
+
+/// Returns a user's settings, creating the defaults on first access. Only
+/// the user and admins may read them.
+#[tauri::command]
+pub async fn get_user_settings(
+    context: CommandContext,
+    user_id: String,
+) -> AppResult<UserSettings> {
+    let user_id = parse_user_id(&user_id)?;
+    context.require_self_or_admin(user_id)?;
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+
+    ensure_row(pool.as_ref(), user_id).await?;
+    let query = sqlx::query_as!(
+        UserSettings,
+        r#"
+        SELECT id,
+               user_id,
+               theme AS "theme!",
+               language AS "language!",
+               notifications_enabled AS "notifications_enabled!",
+               settings_data AS "settings_data!",
+               created_at AS "created_at!",
+               updated_at AS "updated_at!"
+        FROM user_settings
+        WHERE user_id = $1
+        "#,
+        user_id,
+    );
+    let mut settings = query_stats::timed(query.sql(), query.fetch_one(pool.as_ref()))
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    settings.settings_data = to_value(&AppSettings::from_stored(&settings.settings_data))?;
+    Ok(settings)
+}
+
+/// Applies a partial update to a user's settings. Only the user and admins
+/// may change them.
+#[tauri::command]
+pub async fn update_user_settings(
+    context: CommandContext,
+    user_id: String,
+    changes: UpdateUserSettings,
+) -> AppResult<UserSettings> {
+    let user_id = parse_user_id(&user_id)?;
+    context.require_self_or_admin(user_id)?;
+    let theme = changes
+        .theme
+        .as_deref()
+        .map(themes::find)
+        .transpose()
+        .map_err(|e| AppError::invalid_input("theme", e.message))?;
+    let language = changes
+        .language
+        .as_deref()
+        .map(validate_language)
+        .transpose()?;
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    ensure_row(pool.as_ref(), user_id).await?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    let stored = sqlx::query_scalar!(
+        r#"SELECT settings_data AS "settings_data!" FROM user_settings WHERE user_id = $1 FOR UPDATE"#,
+        user_id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let settings_data = match &changes.settings_data {
+        Some(patch) => merge_settings_data(&stored, patch)?,
+        None => to_value(&AppSettings::from_stored(&stored))?,
+    };
+
+    let settings = sqlx::query_as!(
+        UserSettings,
+        r#"
+        UPDATE user_settings
+        SET theme = COALESCE($2, theme),
+            language = COALESCE($3, language),
+            notifications_enabled = COALESCE($4, notifications_enabled),
+            settings_data = $5,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE user_id = $1
+        RETURNING id,
+                  user_id,
+                  theme AS "theme!",
+                  language AS "language!",
+                  notifications_enabled AS "notifications_enabled!",
+                  settings_data AS "settings_data!",
+                  created_at AS "created_at!",
+                  updated_at AS "updated_at!"
+        "#,
+        user_id,
+        theme.as_ref().map(|theme| theme.id.clone()),
+        language,
+        changes.notifications_enabled,
+        settings_data,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+    audit::record_by(
+        &mut *tx,
+        context.actor_id(),
+        audit::SETTINGS_CHANGED,
+        Some(user_id),
+        json!({ "fields": changed_fields(&changes) }),
+    )
+    .await?;
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+
+    if let Err(e) = sync::track_change("user_settings", settings.id, SyncOperation::Upsert).await {
+        tracing::warn!("Failed to record settings change for sync: {}", e);
+    }
+    if let Some(theme) = theme {
+        events::publish(AppEvent::ThemeChanged { user_id, theme });
+    }
+    Ok(settings)
+}
+
+/// Names of the settings an update sets, for the audit log. Changes to
+/// `settings_data` are listed by their top-level keys.
+fn changed_fields(changes: &UpdateUserSettings) -> Vec<String> {
+    let mut fields: Vec<String> = [
+        ("theme", changes.theme.is_some()),
+        ("language", changes.language.is_some()),
+        (
+            "notificationsEnabled",
+            changes.notifications_enabled.is_some(),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(field, set)| set.then(|| field.to_string()))
+    .collect();
+    if let Some(Value::Object(patch)) = &changes.settings_data {
+        fields.extend(patch.keys().map(|key| format!("settingsData.{}", key)));
+    }
+    fields
+}
+
+/// Creates the default settings row for `user_id` if it has none.
+async fn ensure_row(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
+    let defaults = to_value(&AppSettings::default())?;
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO user_settings (user_id, settings_data)
+        SELECT id, $2 FROM users WHERE id = $1
+        ON CONFLICT (user_id) DO NOTHING
+        "#,
+        user_id,
+        defaults,
+    );
+    query_stats::timed(query.sql(), query.execute(pool))
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM user_settings WHERE user_id = $1) AS "exists!""#,
+        user_id,
+    );
+    if !query_stats::timed(exists.sql(), exists.fetch_one(pool))
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?
+    {
+        return Err(AppError::not_found("User"));
+    }
+    Ok(())
+}
+
+/// Merges `patch` into the stored settings and checks the result.
+///
+/// Stored fields that no longer match the schema are reset to their defaults
+/// first, so a bad value written by another build cannot block updates.
+fn merge_settings_data(stored: &Value, patch: &Value) -> AppResult<Value> {
+    if !patch.is_object() {
+        return Err(AppError::invalid_input(
+            "settingsData",
+            "Settings data must be a JSON object",
+        ));
+    }
+
+    let mut merged = to_value(&AppSettings::from_stored(stored))?;
+    merge_patch(&mut merged, patch);
+
+    let mut settings: AppSettings = serde_json::from_value(merged)
+        .map_err(|e| AppError::invalid_input("settingsData", format!("Invalid settings: {}", e)))?;
+    settings.schema_version = SETTINGS_SCHEMA_VERSION;
+    settings
+        .validate()
+        .map_err(|e| AppError::invalid_input("settingsData", e))?;
+    to_value(&settings)
+}
+
+/// Applies a JSON merge patch (RFC 7386) to `target`.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    let Value::Object(target) = target else {
+        return;
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn to_value(settings: &AppSettings) -> AppResult<Value> {
+    serde_json::to_value(settings).into_app_error(ErrorCode::InternalError)
+}
+
+/// Accepts language tags such as `en` or `pt-BR`.
+fn validate_language(language: &str) -> AppResult<String> {
+    let language = language.trim();
+    let mut parts = language.split('-');
+    let primary_ok = parts
+        .next()
+        .is_some_and(|p| (2..=3).contains(&p.len()) && p.chars().all(|c| c.is_ascii_lowercase()));
+    let rest_ok =
+        parts.all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()));
+    if primary_ok && rest_ok && language.len() <= 10 {
+        Ok(language.to_string())
+    } else {
+        Err(AppError::invalid_input(
+            "language",
+            "Language must be a tag such as 'en' or 'pt-BR'",
+        ))
+    }
+}
+
+fn parse_user_id(user_id: &str) -> AppResult<Uuid> {
+    Uuid::parse_str(user_id)
+        .map_err(|e| AppError::invalid_input("userId", format!("Invalid UUID: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::test_support::UserFactory;
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    #[test]
+    fn merges_partial_updates_over_stored_settings() {
+        let stored = json!({ "auto_save": false, "custom": { "a": 1, "b": 2 } });
+        let merged = merge_settings_data(
+            &stored,
+            &json!({ "page_size": 50, "custom": { "b": null } }),
+        )
+        .unwrap();
+
+        assert_eq!(merged["auto_save"], json!(false));
+        assert_eq!(merged["page_size"], json!(50));
+        assert_eq!(merged["custom"], json!({ "a": 1 }));
+        // Fields missing from older data take their defaults.
+        assert_eq!(merged["auto_save_interval_seconds"], json!(30));
+        assert_eq!(merged["schema_version"], json!(SETTINGS_SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let stored = json!({});
+        assert!(merge_settings_data(&stored, &json!({ "auto_save": "yes" })).is_err());
+        assert!(merge_settings_data(&stored, &json!({ "page_size": 1000 })).is_err());
+        assert!(merge_settings_data(&stored, &json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn resets_corrupt_stored_fields_instead_of_failing() {
+        let stored = json!({ "sidebar_collapsed": "maybe", "page_size": 40, "custom": true });
+        let settings = AppSettings::from_stored(&stored);
+        assert!(!settings.sidebar_collapsed);
+        assert_eq!(settings.page_size, 40);
+        assert_eq!(settings.extra["custom"], json!(true));
+
+        assert!(merge_settings_data(&stored, &json!({ "auto_save": false })).is_ok());
+    }
+
+    #[test]
+    fn validates_language_tags() {
+        assert!(validate_language("pt-BR").is_ok());
+        assert!(validate_language("en").is_ok());
+        assert!(validate_language("english").is_err());
+        assert!(validate_language("en_US").is_err());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn creates_defaults_and_applies_updates() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+
+        let settings = get_user_settings(CommandContext::internal(), user.id.to_string()).await?;
+        assert_eq!(settings.settings_data, to_value(&AppSettings::default())?);
+
+        let stranger = CommandContext::for_user(Uuid::new_v4(), Vec::new());
+        let error = get_user_settings(stranger.clone(), user.id.to_string())
+            .await
+            .unwrap_err();
+        assert!(matches!(error.code, ErrorCode::Forbidden));
+        let denied =
+            update_user_settings(stranger, user.id.to_string(), UpdateUserSettings::default())
+                .await;
+        assert!(denied.is_err());
+
+        let updated = update_user_settings(
+            CommandContext::internal(),
+            user.id.to_string(),
+            UpdateUserSettings {
+                theme: Some("dark".to_string()),
+                settings_data: Some(json!({ "sidebar_collapsed": true })),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(updated.theme, "dark");
+        assert_eq!(updated.settings_data["sidebar_collapsed"], json!(true));
+        assert_eq!(updated.settings_data["auto_save"], json!(true));
+
+        let audited: Value =
+            sqlx::query_scalar("SELECT details FROM audit_log WHERE action = $1 AND user_id = $2")
+                .bind(audit::SETTINGS_CHANGED)
+                .bind(user.id)
+                .fetch_one(pool.as_ref())
+                .await?;
+        assert_eq!(
+            audited["fields"],
+            json!(["theme", "settingsData.sidebar_collapsed"])
+        );
+
+        let missing =
+            get_user_settings(CommandContext::internal(), Uuid::new_v4().to_string()).await;
+        assert!(missing.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+//! First-run onboarding command handlers.
+
+use crate::errors::AppResult;
+use crate::setup::{self, SetupStatus, SetupStep};
+
+/// Returns onboarding progress and the next step the user should complete.
+#[tauri::command]
+pub async fn get_setup_status() -> AppResult<SetupStatus> {
+    setup::status().await
+}
+
+/// Marks an onboarding step complete once its prerequisites are met.
+#[tauri::command]
+pub async fn complete_setup_step(step: SetupStep) -> AppResult<SetupStatus> {
+    setup::complete_step(step).await
+}
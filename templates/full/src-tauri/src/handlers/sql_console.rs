@@ -0,0 +1,211 @@
+//! Development SQL console for inspecting the local database.
+//!
+//! Only available to admins in debug builds running in the development
+//! environment, so a release build never exposes it, whatever `APP_ENV`
+//! says. Statements run inside a read-only transaction unless write access is
+//! explicitly requested, and row-returning statements are serialized with
+//! `row_to_json`.
+
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::config;
+use crate::database::{get_pool_ref, query_stats};
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::{Postgres, Row};
+use std::time::Instant;
+
+/// Maximum number of rows returned by a single statement.
+const MAX_ROWS: usize = 1000;
+
+/// Per-statement timeout applied inside the console transaction.
+const STATEMENT_TIMEOUT: &str = "30s";
+
+/// Result of a console statement.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlResult {
+    /// Rows as JSON objects; empty for statements that return no rows.
+    pub rows: Vec<Value>,
+    pub rows_affected: u64,
+    /// True when the row count hit the limit and later rows were dropped.
+    pub truncated: bool,
+    pub read_only: bool,
+    pub elapsed_ms: u64,
+}
+
+/// Executes a single SQL statement with positional `$n` parameters.
+///
+/// Statements run read-only unless `read_only` is `Some(false)`. Requires the
+/// admin role.
+#[tauri::command]
+pub async fn execute_sql(
+    context: CommandContext,
+    query: String,
+    params: Option<Vec<Value>>,
+    read_only: Option<bool>,
+) -> AppResult<SqlResult> {
+    if !cfg!(debug_assertions) || !config::current().is_development() {
+        return Err(AppError::forbidden(
+            "The SQL console is only available in development",
+        ));
+    }
+    context.require_role(ADMIN_ROLE)?;
+
+    let statement = normalize_statement(&query)?;
+    let params = params.unwrap_or_default();
+    let read_only = read_only.unwrap_or(true);
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let started = Instant::now();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+    if read_only {
+        sqlx::query("SET TRANSACTION READ ONLY")
+            .execute(&mut *tx)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+    }
+    sqlx::query(&format!(
+        "SET LOCAL statement_timeout = '{}'",
+        STATEMENT_TIMEOUT
+    ))
+    .execute(&mut *tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let (rows, rows_affected, truncated) = if returns_rows(statement) {
+        let sql = format!(
+            "SELECT row_to_json(console_row)::jsonb FROM ({}) AS console_row LIMIT {}",
+            statement,
+            MAX_ROWS + 1
+        );
+        let result = query_stats::timed(
+            &sql,
+            bind_all(sqlx::query(&sql), &params)?.fetch_all(&mut *tx),
+        )
+        .await
+        .map_err(|e| AppError::new(ErrorCode::DatabaseQuery, e.to_string()))?;
+
+        let mut rows = result
+            .iter()
+            .map(|row| row.try_get::<Value, _>(0))
+            .collect::<Result<Vec<_>, _>>()
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+        let truncated = rows.len() > MAX_ROWS;
+        rows.truncate(MAX_ROWS);
+        let count = rows.len() as u64;
+        (rows, count, truncated)
+    } else {
+        let result = query_stats::timed(
+            statement,
+            bind_all(sqlx::query(statement), &params)?.execute(&mut *tx),
+        )
+        .await
+        .map_err(|e| AppError::new(ErrorCode::DatabaseQuery, e.to_string()))?;
+        (Vec::new(), result.rows_affected(), false)
+    };
+
+    if read_only {
+        tx.rollback()
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+    } else {
+        tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+    }
+
+    tracing::info!(
+        read_only,
+        rows_affected,
+        "SQL console statement executed in {:?}",
+        started.elapsed()
+    );
+
+    Ok(SqlResult {
+        rows,
+        rows_affected,
+        truncated,
+        read_only,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Trims whitespace and trailing semicolons, rejecting empty input and
+/// multiple statements.
+fn normalize_statement(query: &str) -> AppResult<&str> {
+    let statement = query.trim().trim_end_matches(';').trim_end();
+    if statement.is_empty() {
+        return Err(AppError::invalid_input("query", "Query cannot be empty"));
+    }
+    if statement.contains(';') {
+        return Err(AppError::invalid_input(
+            "query",
+            "Only a single statement can be executed at a time",
+        ));
+    }
+    Ok(statement)
+}
+
+/// Returns true for statements that can be wrapped as a subquery.
+fn returns_rows(statement: &str) -> bool {
+    let keyword = statement
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    matches!(keyword.as_str(), "select" | "with" | "values" | "table")
+}
+
+/// Binds JSON parameters using the closest matching Postgres type.
+fn bind_all<'q>(
+    mut query: Query<'q, Postgres, PgArguments>,
+    params: &'q [Value],
+) -> AppResult<Query<'q, Postgres, PgArguments>> {
+    for (index, param) in params.iter().enumerate() {
+        query = match param {
+            Value::Null => query.bind(None::<String>),
+            Value::Bool(value) => query.bind(*value),
+            Value::Number(number) => {
+                if let Some(value) = number.as_i64() {
+                    query.bind(value)
+                } else if let Some(value) = number.as_f64() {
+                    query.bind(value)
+                } else {
+                    return Err(AppError::invalid_input(
+                        format!("params[{}]", index),
+                        "Number is out of range",
+                    ));
+                }
+            }
+            Value::String(value) => query.bind(value.as_str()),
+            Value::Array(_) | Value::Object(_) => query.bind(param),
+        };
+    }
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_single_statements() {
+        assert_eq!(normalize_statement("  SELECT 1;; \n").unwrap(), "SELECT 1");
+        assert!(normalize_statement(" ; ").is_err());
+        assert!(normalize_statement("SELECT 1; DROP TABLE users").is_err());
+    }
+
+    #[test]
+    fn detects_row_returning_statements() {
+        assert!(returns_rows("SELECT * FROM users"));
+        assert!(returns_rows(
+            "with recent as (select 1) select * from recent"
+        ));
+        assert!(!returns_rows("UPDATE users SET is_active = false"));
+        assert!(!returns_rows("EXPLAIN SELECT 1"));
+    }
+}
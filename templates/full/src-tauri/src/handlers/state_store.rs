@@ -0,0 +1,23 @@
+//! Local key-value state command handlers.
+
+use crate::errors::AppResult;
+use crate::state_store;
+use std::collections::BTreeMap;
+
+/// Returns the value stored under `key`, or `null` when missing.
+#[tauri::command]
+pub async fn get_state(key: String) -> AppResult<Option<serde_json::Value>> {
+    state_store::get(&key)
+}
+
+/// Stores a JSON value under `key`; passing `null` removes the key.
+#[tauri::command]
+pub async fn set_state(key: String, value: serde_json::Value) -> AppResult<()> {
+    state_store::set(&key, value)
+}
+
+/// Emits `state:changed` events for keys under `prefix` and returns current entries.
+#[tauri::command]
+pub async fn watch_state(prefix: String) -> AppResult<BTreeMap<String, serde_json::Value>> {
+    state_store::watch(&prefix)
+}
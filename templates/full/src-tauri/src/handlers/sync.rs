@@ -0,0 +1,16 @@
+//! Offline sync command handlers.
+
+use crate::errors::AppResult;
+use crate::sync::{self, SyncStatus};
+
+/// Returns the sync engine state and the number of pending local changes.
+#[tauri::command]
+pub async fn get_sync_status() -> AppResult<SyncStatus> {
+    sync::status().await
+}
+
+/// Runs a pull/push cycle immediately, emitting `sync:status` events.
+#[tauri::command]
+pub async fn sync_now() -> AppResult<SyncStatus> {
+    sync::sync_now().await
+}
@@ -1,8 +1,23 @@
 //! System information and utility command handlers.
 
+use crate::build_info::{self, AppVersionInfo};
+use crate::config;
+use crate::errors::{AppError, AppResult};
+#[cfg(feature = "database")]
+use crate::handlers::notifications::record_notification;
+use crate::file_open::{self, OpenRequest};
+use crate::handlers::filesystem::{filesystem_root, resolve_existing_path};
+use crate::locale::{self, LocaleInfo};
+use crate::startup::{self, StartupStatus};
+use crate::storage_devices::{self, StorageDevice};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
 use tauri::{AppHandle, Manager, Window};
 use tauri_plugin_notification::{NotificationExt, PermissionState};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 
 /// System information structure.
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,6 +50,52 @@ const ALLOWED_COMMANDS: &[&str] = &[
 const MAX_ARGS: usize = 20;
 /// Maximum length of each command argument.
 const MAX_ARG_LEN: usize = 2048;
+/// Maximum number of environment variables a caller may set.
+const MAX_ENV_VARS: usize = 32;
+/// Variables passed through from the application's environment; commands
+/// see no others unless the caller sets them.
+const INHERITED_ENV: &[&str] = &[
+    "PATH",
+    "PATHEXT",
+    "HOME",
+    "USER",
+    "USERNAME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "TEMP",
+    "TMP",
+    "TMPDIR",
+    "LANG",
+    "LC_ALL",
+    "TERM",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+];
+/// Variables callers may set. Anything else is rejected, since many tools
+/// read variables that change which code runs.
+const SETTABLE_ENV: &[&str] = &[
+    "CI",
+    "NODE_ENV",
+    "DEBUG",
+    "NO_COLOR",
+    "FORCE_COLOR",
+    "TZ",
+    "RUST_LOG",
+    "RUST_BACKTRACE",
+    "CARGO_TERM_COLOR",
+    "PYTHONUNBUFFERED",
+    "PYTHONDONTWRITEBYTECODE",
+];
+/// Scope-relative directory receiving output that exceeds the limit.
+const COMMAND_OUTPUT_DIR: &str = "command-output";
+/// Spilled output files kept before the oldest are deleted.
+const MAX_SPILLED_OUTPUTS: usize = 20;
 
 #[tauri::command]
 pub async fn get_system_info() -> Result<SystemInfo, String> {
@@ -49,11 +110,51 @@ pub async fn get_system_info() -> Result<SystemInfo, String> {
     })
 }
 
+/// Returns the app version, git commit, build date, profile, target, and
+/// enabled cargo features.
+#[tauri::command]
+pub async fn get_app_version_info() -> Result<AppVersionInfo, String> {
+    Ok(build_info::current())
+}
+
+/// Returns the state of each subsystem brought up at startup, including the
+/// error of any that failed to initialize.
+#[tauri::command]
+pub async fn get_startup_status() -> Result<StartupStatus, String> {
+    Ok(startup::status())
+}
+
+/// Returns the system locale, preferred languages, and IANA timezone.
+#[tauri::command]
+pub async fn get_locale_info() -> Result<LocaleInfo, String> {
+    Ok(locale::locale_info())
+}
+
+/// Lists mounted storage devices, or only removable ones such as USB
+/// sticks. Changes arrive as `storage:mounted` and `storage:unmounted`.
+#[tauri::command]
+pub async fn list_storage_devices(
+    removable_only: Option<bool>,
+) -> Result<Vec<StorageDevice>, String> {
+    let removable_only = removable_only.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || storage_devices::list(removable_only))
+        .await
+        .map_err(|e| format!("Failed to list storage devices: {}", e))
+}
+
+/// Returns files opened via file association before the frontend subscribed
+/// to `file:open-requested`; later requests arrive only as events.
+#[tauri::command]
+pub async fn take_pending_file_opens() -> Result<Vec<OpenRequest>, String> {
+    Ok(file_open::take_pending())
+}
+
 #[tauri::command]
 pub async fn send_notification(
     app: AppHandle,
     title: String,
     body: String,
+    payload: Option<serde_json::Value>,
 ) -> Result<String, String> {
     let title = title.trim();
     let body = body.trim();
@@ -105,6 +206,13 @@ pub async fn send_notification(
         .show()
         .map_err(|e| format!("Failed to display notification: {}", e))?;
 
+    #[cfg(feature = "database")]
+    if let Err(e) = record_notification(title, body, payload, None).await {
+        tracing::warn!("Notification shown but not saved to history: {}", e);
+    }
+    #[cfg(not(feature = "database"))]
+    let _ = payload;
+
     Ok("Notification dispatched".to_string())
 }
 
@@ -227,16 +335,23 @@ pub async fn set_window_title_by_app(app: AppHandle, title: String) -> Result<St
     Ok(format!("Window title set to: {}", title))
 }
 
+/// Opens a window on an app route or an external URL. External URLs must
+/// belong to an origin in `EXTERNAL_WINDOW_ORIGINS`; others fail with
+/// `FORBIDDEN`.
 #[tauri::command]
-pub async fn create_new_window(
-    app: AppHandle,
-    label: String,
-    url: String,
-) -> Result<String, String> {
+pub async fn create_new_window(app: AppHandle, label: String, url: String) -> AppResult<String> {
     use tauri::{WebviewUrl, WebviewWindowBuilder};
 
     let webview_url = if url.starts_with("http") {
-        WebviewUrl::External(url.parse().map_err(|e| format!("Invalid URL: {}", e))?)
+        let parsed: tauri::Url = url
+            .parse()
+            .map_err(|e| AppError::invalid_input("url", format!("Invalid URL: {}", e)))?;
+        let origin = parsed.origin().ascii_serialization();
+        if let Err(reason) = config::current().external_origins.check(&origin) {
+            tracing::warn!("Blocked window '{}' for {}: {}", label, url, reason);
+            return Err(AppError::forbidden(reason));
+        }
+        WebviewUrl::External(parsed)
     } else {
         WebviewUrl::App(url.into())
     };
@@ -245,13 +360,33 @@ pub async fn create_new_window(
         .title("New Window")
         .inner_size(800.0, 600.0)
         .build()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AppError::internal_error(e.to_string()))?;
 
     Ok(format!("New window '{}' created", label))
 }
 
+/// Where and with which environment `execute_command` runs a command.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CommandOptions {
+    /// Scope-relative working directory; the application's own when unset.
+    pub cwd: Option<String>,
+    /// Variables from [`SETTABLE_ENV`] set in addition to those inherited from
+    /// [`INHERITED_ENV`].
+    pub env: BTreeMap<String, String>,
+}
+
+/// Runs an allowlisted command. When `options` are given, the environment is
+/// cleared down to [`INHERITED_ENV`] and the variables in `options`, so build
+/// tools behave the same regardless of how the application was launched;
+/// otherwise the command inherits the application's environment.
 #[tauri::command]
-pub async fn execute_command(command: String, args: Vec<String>) -> Result<String, String> {
+pub async fn execute_command(
+    command: String,
+    args: Vec<String>,
+    options: Option<CommandOptions>,
+) -> Result<String, String> {
+    use std::process::Stdio;
     use tokio::process::Command;
 
     let command = command.trim();
@@ -303,23 +438,70 @@ pub async fn execute_command(command: String, args: Vec<String>) -> Result<Strin
         .copied()
         .unwrap_or(command);
 
-    let output = Command::new(resolved_command)
-        .args(&args)
-        .output()
+    let environment = options
+        .as_ref()
+        .map(|options| command_environment(&options.env))
+        .transpose()?;
+    let working_dir = options
+        .as_ref()
+        .and_then(|options| options.cwd.as_deref())
+        .map(resolve_working_dir)
+        .transpose()?;
+
+    let config = config::current();
+    let limit = config.command_output_max_kb.saturating_mul(1024) as usize;
+    let spill_dir = if config.command_output_spill {
+        Some(filesystem_root()?.join(COMMAND_OUTPUT_DIR))
+    } else {
+        None
+    };
+    let spill_target = |stream: &str| {
+        spill_dir.as_ref().map(|dir| SpillTarget {
+            dir: dir.clone(),
+            prefix: format!("{}-{}", resolved_command, stream),
+        })
+    };
+    let (stdout_spill, stderr_spill) = (spill_target("stdout"), spill_target("stderr"));
+
+    let mut process = Command::new(resolved_command);
+    process.args(&args);
+    if let Some(environment) = environment {
+        process.env_clear().envs(environment);
+    }
+    if let Some(dir) = &working_dir {
+        process.current_dir(dir);
+    }
+    let mut child = process
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return Err("Failed to capture command output".to_string());
+    };
+    let (stdout, stderr) = tokio::join!(
+        capture_output(stdout, limit, stdout_spill.as_ref()),
+        capture_output(stderr, limit, stderr_spill.as_ref())
+    );
+    let status = child
+        .wait()
         .await
         .map_err(|e| format!("Failed to execute command: {}", e))?;
+    let stdout = stdout.map_err(|e| format!("Failed to read command output: {}", e))?;
+    let stderr = stderr.map_err(|e| format!("Failed to read command output: {}", e))?;
 
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if status.success() {
+        let stdout = stdout.render();
         if stdout.is_empty() {
             Ok("Command executed successfully.".to_string())
         } else {
             Ok(stdout)
         }
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let code = output
-            .status
+        let stderr = stderr.render();
+        let code = status
             .code()
             .map(|c| c.to_string())
             .unwrap_or_else(|| "terminated by signal".to_string());
@@ -328,6 +510,191 @@ pub async fn execute_command(command: String, args: Vec<String>) -> Result<Strin
     }
 }
 
+/// The inherited variables present in the application's environment plus
+/// `extra`, failing when `extra` sets a variable outside [`SETTABLE_ENV`].
+fn command_environment(
+    extra: &BTreeMap<String, String>,
+) -> Result<Vec<(String, OsString)>, String> {
+    if extra.len() > MAX_ENV_VARS {
+        return Err(format!(
+            "Too many environment variables supplied. Maximum allowed is {}.",
+            MAX_ENV_VARS
+        ));
+    }
+    for (name, value) in extra {
+        if !SETTABLE_ENV.contains(&name.as_str()) {
+            return Err(format!("Environment variable '{}' cannot be set.", name));
+        }
+        if value.len() > MAX_ARG_LEN || value.contains('\0') {
+            return Err(format!(
+                "Environment variable '{}' has an invalid value.",
+                name
+            ));
+        }
+    }
+
+    let mut environment: Vec<(String, OsString)> = INHERITED_ENV
+        .iter()
+        .filter_map(|name| Some((name.to_string(), std::env::var_os(name)?)))
+        .collect();
+    environment.extend(
+        extra
+            .iter()
+            .map(|(name, value)| (name.clone(), OsString::from(value))),
+    );
+    Ok(environment)
+}
+
+/// Resolves a scope-relative working directory, following symlinks only
+/// while they stay inside the scope.
+fn resolve_working_dir(raw: &str) -> Result<PathBuf, String> {
+    let context = resolve_existing_path(raw)?;
+    let dir = dunce::canonicalize(&context.path)
+        .map_err(|e| format!("Failed to resolve working directory: {}", e))?;
+    if !dir.starts_with(&context.root) {
+        return Err(
+            "Path traversal outside the application directory is not permitted.".to_string(),
+        );
+    }
+    if !dir.is_dir() {
+        return Err(format!(
+            "Working directory '{}' is not a directory",
+            context.relative_display()
+        ));
+    }
+    Ok(dir)
+}
+
+/// Output read from one stream of a command, up to the configured limit.
+struct CapturedOutput {
+    /// The first bytes of the output, at most the limit.
+    head: Vec<u8>,
+    /// Bytes the command wrote in total.
+    total: usize,
+    /// File holding the full output, when it was spilled.
+    spilled: Option<PathBuf>,
+}
+
+impl CapturedOutput {
+    /// The kept output, followed by a marker when some of it was cut off.
+    fn render(&self) -> String {
+        let mut text = String::from_utf8_lossy(&self.head).trim().to_string();
+        if self.total > self.head.len() {
+            let saved = self
+                .spilled
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map(|name| {
+                    format!(
+                        "; full output saved to {}/{}",
+                        COMMAND_OUTPUT_DIR,
+                        name.to_string_lossy()
+                    )
+                })
+                .unwrap_or_default();
+            text.push_str(&format!(
+                "\n[output truncated: showing {} of {} bytes{}]",
+                self.head.len(),
+                self.total,
+                saved
+            ));
+        }
+        text
+    }
+}
+
+/// Reads `reader` to the end, keeping the first `limit` bytes. Once the
+/// output exceeds the limit and `spill` is set, everything is written to a
+/// new file in its directory instead of being dropped.
+async fn capture_output<R>(
+    mut reader: R,
+    limit: usize,
+    spill: Option<&SpillTarget>,
+) -> std::io::Result<CapturedOutput>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut head = Vec::new();
+    let mut total = 0;
+    let mut spilled: Option<(PathBuf, tokio::fs::File)> = None;
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        let chunk = &buffer[..read];
+        total += read;
+
+        if let Some((_, file)) = spilled.as_mut() {
+            file.write_all(chunk).await?;
+            continue;
+        }
+        let room = limit - head.len();
+        if read > room {
+            if let Some(target) = spill {
+                let (path, mut file) = target.create().await?;
+                file.write_all(&head).await?;
+                file.write_all(chunk).await?;
+                spilled = Some((path, file));
+            }
+        }
+        head.extend_from_slice(&chunk[..read.min(room)]);
+    }
+
+    let spilled = match spilled {
+        Some((path, mut file)) => {
+            file.flush().await?;
+            Some(path)
+        }
+        None => None,
+    };
+    Ok(CapturedOutput {
+        head,
+        total,
+        spilled,
+    })
+}
+
+/// Where one stream of a command spills output that exceeds the limit.
+struct SpillTarget {
+    /// Directory receiving the file, created on first spill.
+    dir: PathBuf,
+    /// File name prefix naming the command and stream.
+    prefix: String,
+}
+
+impl SpillTarget {
+    /// Creates a new spill file, deleting the oldest spilled files beyond
+    /// [`MAX_SPILLED_OUTPUTS`].
+    async fn create(&self) -> std::io::Result<(PathBuf, tokio::fs::File)> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        let mut spilled = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if let Ok(modified) = entry.metadata().await.and_then(|meta| meta.modified()) {
+                spilled.push((modified, entry.path()));
+            }
+        }
+        spilled.sort();
+        let excess = (spilled.len() + 1).saturating_sub(MAX_SPILLED_OUTPUTS);
+        for (_, path) in spilled.into_iter().take(excess) {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                tracing::warn!("Failed to delete {}: {}", path.display(), e);
+            }
+        }
+
+        let path = self.dir.join(format!(
+            "{}-{}.log",
+            self.prefix,
+            Utc::now().format("%Y%m%dT%H%M%S%.3f")
+        ));
+        let file = tokio::fs::File::create(&path).await?;
+        Ok((path, file))
+    }
+}
+
 #[tauri::command]
 pub async fn get_app_data_dir(app: AppHandle) -> Result<String, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
@@ -358,25 +725,30 @@ mod tests {
 
     #[tokio::test]
     async fn execute_command_rejects_empty_command() {
-        let result = execute_command("".to_string(), vec![]).await;
+        let result = execute_command("".to_string(), vec![], None).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("cannot be empty"));
     }
 
     #[tokio::test]
     async fn execute_command_rejects_unauthorized_commands() {
-        let result = execute_command("rm".to_string(), vec!["-rf".to_string(), "/".to_string()]).await;
+        let result = execute_command(
+            "rm".to_string(),
+            vec!["-rf".to_string(), "/".to_string()],
+            None,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not permitted"));
     }
 
     #[tokio::test]
     async fn execute_command_rejects_commands_with_paths() {
-        let result = execute_command("./malicious".to_string(), vec![]).await;
+        let result = execute_command("./malicious".to_string(), vec![], None).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("invalid characters"));
 
-        let result = execute_command("/usr/bin/rm".to_string(), vec![]).await;
+        let result = execute_command("/usr/bin/rm".to_string(), vec![], None).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("invalid characters"));
     }
@@ -384,7 +756,7 @@ mod tests {
     #[tokio::test]
     async fn execute_command_rejects_too_many_args() {
         let many_args: Vec<String> = (0..25).map(|i| format!("arg{}", i)).collect();
-        let result = execute_command("echo".to_string(), many_args).await;
+        let result = execute_command("echo".to_string(), many_args, None).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Too many arguments"));
     }
@@ -392,21 +764,22 @@ mod tests {
     #[tokio::test]
     async fn execute_command_rejects_oversized_args() {
         let oversized_arg = "x".repeat(3000);
-        let result = execute_command("echo".to_string(), vec![oversized_arg]).await;
+        let result = execute_command("echo".to_string(), vec![oversized_arg], None).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("exceeds the maximum length"));
     }
 
     #[tokio::test]
     async fn execute_command_rejects_null_bytes() {
-        let result = execute_command("echo".to_string(), vec!["hello\0world".to_string()]).await;
+        let result =
+            execute_command("echo".to_string(), vec!["hello\0world".to_string()], None).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("invalid characters"));
     }
 
     #[tokio::test]
     async fn execute_command_works_with_allowed_commands() {
-        let result = execute_command("echo".to_string(), vec!["hello".to_string()]).await;
+        let result = execute_command("echo".to_string(), vec!["hello".to_string()], None).await;
         assert!(result.is_ok());
         let output = result.unwrap();
         assert!(output.contains("hello") || output.contains("executed successfully"));
@@ -414,7 +787,7 @@ mod tests {
 
     #[tokio::test]
     async fn execute_command_handles_case_insensitive_matching() {
-        let result = execute_command("ECHO".to_string(), vec!["test".to_string()]).await;
+        let result = execute_command("ECHO".to_string(), vec!["test".to_string()], None).await;
         assert!(result.is_ok());
     }
 
@@ -426,6 +799,104 @@ mod tests {
         assert!(ALLOWED_COMMANDS.contains(&"cargo"));
     }
 
+    #[test]
+    fn command_environment_keeps_only_inherited_and_requested_variables() {
+        let extra = BTreeMap::from([("NODE_ENV".to_string(), "production".to_string())]);
+        let environment = command_environment(&extra).unwrap();
+        assert!(environment
+            .iter()
+            .all(|(name, _)| INHERITED_ENV.contains(&name.as_str()) || name == "NODE_ENV"));
+        assert!(environment.contains(&("NODE_ENV".to_string(), OsString::from("production"))));
+
+        for name in [
+            "PATH",
+            "LD_PRELOAD",
+            "DYLD_INSERT_LIBRARIES",
+            "NODE_OPTIONS",
+            "npm_config_script_shell",
+            "RUSTFLAGS",
+            "node_env",
+        ] {
+            let extra = BTreeMap::from([(name.to_string(), "x".to_string())]);
+            assert!(command_environment(&extra).is_err());
+        }
+        let nul = BTreeMap::from([("NAME".to_string(), "a\0b".to_string())]);
+        assert!(command_environment(&nul).is_err());
+    }
+
+    #[tokio::test]
+    async fn execute_command_rejects_working_dirs_outside_the_scope() {
+        let options = CommandOptions {
+            cwd: Some("../outside".to_string()),
+            ..Default::default()
+        };
+        let result = execute_command("echo".to_string(), vec![], Some(options)).await;
+        assert!(result
+            .unwrap_err()
+            .contains("outside the application directory"));
+    }
+
+    #[tokio::test]
+    async fn long_output_is_truncated_with_a_marker() {
+        let output = capture_output(&b"0123456789"[..], 4, None).await.unwrap();
+        assert_eq!(output.head, b"0123");
+        assert_eq!(output.total, 10);
+        assert_eq!(
+            output.render(),
+            "0123\n[output truncated: showing 4 of 10 bytes]"
+        );
+
+        let short = capture_output(&b"ok\n"[..], 4, None).await.unwrap();
+        assert_eq!(short.render(), "ok");
+        assert!(short.spilled.is_none());
+    }
+
+    #[tokio::test]
+    async fn long_output_spills_to_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = SpillTarget {
+            dir: dir.path().join(COMMAND_OUTPUT_DIR),
+            prefix: "npm-stdout".to_string(),
+        };
+
+        capture_output(&b"0123"[..], 4, Some(&target))
+            .await
+            .unwrap();
+        assert!(!target.dir.exists());
+
+        let output = capture_output(&b"0123456789"[..], 4, Some(&target))
+            .await
+            .unwrap();
+        let path = output.spilled.clone().unwrap();
+        assert_eq!(path.parent(), Some(target.dir.as_path()));
+        assert_eq!(std::fs::read(&path).unwrap(), b"0123456789");
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("npm-stdout-"));
+        assert!(output
+            .render()
+            .ends_with(&format!("; full output saved to command-output/{}]", name)));
+    }
+
+    #[tokio::test]
+    async fn spilling_deletes_the_oldest_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = SpillTarget {
+            dir: dir.path().to_path_buf(),
+            prefix: "npm-stderr".to_string(),
+        };
+        for i in 0..MAX_SPILLED_OUTPUTS {
+            std::fs::write(dir.path().join(format!("old-{}.log", i)), b"old").unwrap();
+        }
+
+        capture_output(&b"0123456789"[..], 4, Some(&target))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_dir(dir.path()).unwrap().count(),
+            MAX_SPILLED_OUTPUTS
+        );
+    }
+
     #[test]
     fn constants_have_reasonable_values() {
         assert!(MAX_ARGS > 0 && MAX_ARGS <= 100);
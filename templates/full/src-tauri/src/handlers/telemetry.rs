@@ -0,0 +1,40 @@
+//! Usage analytics command handlers.
+
+use crate::telemetry::{self, TelemetryEvent, TelemetryStatus};
+use std::collections::HashMap;
+
+/// Returns consent state and the number of locally collected events.
+#[tauri::command]
+pub async fn get_telemetry_status() -> Result<TelemetryStatus, String> {
+    telemetry::status().map_err(|e| format!("Failed to read telemetry status: {}", e))
+}
+
+/// Grants or revokes telemetry consent. Revoking purges collected data.
+#[tauri::command]
+pub async fn set_telemetry_consent(consent: bool) -> Result<TelemetryStatus, String> {
+    telemetry::set_consent(consent).map_err(|e| format!("Failed to update consent: {}", e))?;
+    telemetry::status().map_err(|e| format!("Failed to read telemetry status: {}", e))
+}
+
+/// Records a feature-usage event from the frontend (no-op without consent).
+#[tauri::command]
+pub async fn record_telemetry_event(
+    feature: String,
+    properties: Option<HashMap<String, serde_json::Value>>,
+) -> Result<(), String> {
+    telemetry::record(&feature, properties.unwrap_or_default())
+        .map_err(|e| format!("Failed to record telemetry event: {}", e))
+}
+
+/// Returns every event collected locally so users can review what would be sent.
+#[tauri::command]
+pub async fn get_telemetry_events() -> Result<Vec<TelemetryEvent>, String> {
+    telemetry::collected_events().map_err(|e| format!("Failed to read telemetry events: {}", e))
+}
+
+/// Deletes all locally collected telemetry events.
+#[tauri::command]
+pub async fn purge_telemetry_data() -> Result<String, String> {
+    let count = telemetry::purge().map_err(|e| format!("Failed to purge telemetry data: {}", e))?;
+    Ok(format!("Purged {} telemetry events", count))
+}
@@ -0,0 +1,35 @@
+//! Theme command handlers.
+
+#[cfg(feature = "database")]
+use crate::command_context::CommandContext;
+use crate::errors::AppResult;
+#[cfg(feature = "database")]
+use crate::handlers::settings::update_user_settings;
+#[cfg(feature = "database")]
+use crate::models::{UpdateUserSettings, UserSettings};
+use crate::themes::{self, ThemeListing};
+
+/// Lists built-in themes, valid user themes, and theme files that failed validation.
+#[tauri::command]
+pub async fn list_themes() -> AppResult<ThemeListing> {
+    themes::list()
+}
+
+/// Makes `theme_id` the user's active theme and emits `theme:changed`.
+#[cfg(feature = "database")]
+#[tauri::command]
+pub async fn set_active_theme(
+    context: CommandContext,
+    user_id: String,
+    theme_id: String,
+) -> AppResult<UserSettings> {
+    update_user_settings(
+        context,
+        user_id,
+        UpdateUserSettings {
+            theme: Some(theme_id),
+            ..Default::default()
+        },
+    )
+    .await
+}
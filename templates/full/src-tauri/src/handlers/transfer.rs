@@ -0,0 +1,27 @@
+//! Peer-to-peer file transfer command handlers.
+
+use crate::errors::{AppError, AppResult};
+use crate::transfer::{self, OutgoingTransfer, TransferOffer};
+use uuid::Uuid;
+
+/// Offers a file in the fs scope to a discovered peer.
+#[tauri::command]
+pub async fn send_file_to_peer(peer_id: String, path: String) -> AppResult<OutgoingTransfer> {
+    transfer::send_to_peer(&peer_id, &path)
+}
+
+/// Accepts or declines an incoming transfer offer.
+///
+/// Returns false when the offer already expired or was answered.
+#[tauri::command]
+pub async fn respond_to_transfer(transfer_id: String, accept: bool) -> AppResult<bool> {
+    let transfer_id = Uuid::parse_str(&transfer_id)
+        .map_err(|e| AppError::invalid_input("transferId", format!("Invalid UUID: {}", e)))?;
+    Ok(transfer::respond(transfer_id, accept))
+}
+
+/// Lists incoming offers waiting for an answer.
+#[tauri::command]
+pub async fn list_transfer_offers() -> AppResult<Vec<TransferOffer>> {
+    Ok(transfer::pending_offers())
+}
@@ -0,0 +1,24 @@
+//! Application update command handlers.
+
+use crate::errors::AppResult;
+use crate::updater::{self, UpdateInfo};
+use tauri::AppHandle;
+
+/// Checks the configured release channel for a newer version.
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> AppResult<UpdateInfo> {
+    updater::check(&app).await
+}
+
+/// Downloads the pending update, emitting `update:progress` events.
+#[tauri::command]
+pub async fn download_update() -> AppResult<String> {
+    let size = updater::download().await?;
+    Ok(format!("Downloaded update ({} bytes)", size))
+}
+
+/// Installs the downloaded update and restarts the application.
+#[tauri::command]
+pub async fn install_update_and_restart(app: AppHandle) -> AppResult<()> {
+    updater::install_and_restart(&app).await
+}
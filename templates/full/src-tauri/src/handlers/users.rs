@@ -1,71 +1,268 @@
 //! User management command handlers.
+//!
+//! Each command resolves the database pool and delegates to a `*_with`
+//! function written against [`UserRepository`], so the validation and control
+//! flow can be unit tested with the in-memory repository. Passwords are
+//! hashed with [`crate::password`]. Commands that take a [`CommandContext`]
+//! limit what the calling user may see or change. Account changes and sign-in
+//! attempts are written to the `audit` log.
+//!
+//! Without the `database` feature only the `*_with` functions are compiled,
+//! and [`super::local_db`] wraps them for the embedded database. With both
+//! features, the commands hand off to it when `DATABASE_URL` selects SQLite.
 
+#[cfg(feature = "database")]
+use crate::audit;
+#[cfg(feature = "database")]
+use crate::auth::provider;
+use crate::auth::throttle;
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+#[cfg(feature = "database")]
+use crate::config;
+use crate::config::PasswordPolicy;
+#[cfg(feature = "database")]
 use crate::database::get_pool_ref;
-use crate::models::{CreateUser, LoginRequest, PublicUser, UpdateUser, User};
-use crate::validation::{validate_email, validate_username, validate_optional_name};
-use bcrypt::{hash, verify, DEFAULT_COST};
+#[cfg(feature = "database")]
+use crate::email_verification;
+#[cfg(feature = "database")]
+use crate::handlers::workspaces;
+#[cfg(feature = "database")]
+use crate::models::LoginResponse;
+use crate::models::{
+    CreateUser, LoginRequest, Pagination, PublicUser, UpdateUser, User, UserSearchFilters,
+    UserSearchResult, UserSort,
+};
+use crate::password;
+#[cfg(feature = "database")]
+use crate::repository::PgUserRepository;
+use crate::repository::{NewUser, UserChanges, UserRepository, UserSearch};
+#[cfg(feature = "database")]
+use crate::session;
+#[cfg(feature = "database")]
+use crate::sync::{self, SyncOperation};
+use crate::validation::{
+    validate_email, validate_optional_name, validate_password, validate_username,
+};
+#[cfg(feature = "database")]
+use crate::workspace;
+#[cfg(feature = "database")]
+use serde_json::json;
 use uuid::Uuid;
 
+/// Reserved domain for the placeholder emails of guest accounts, which can
+/// never receive mail.
+const GUEST_EMAIL_DOMAIN: &str = "guest.invalid";
+
 /// Retrieves all users from the database (excluding password hashes).
+///
+/// When a workspace is selected, only its members are returned. Callers
+/// without the admin role only get their own user.
+#[cfg(feature = "database")]
 #[tauri::command]
-pub async fn get_all_users() -> Result<Vec<PublicUser>, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+pub async fn get_all_users(context: CommandContext) -> Result<Vec<PublicUser>, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::get_all_users(context).await;
+    }
 
-    let users: Vec<User> = sqlx::query_as::<_, User>(
-        r#"
-        SELECT id,
-               email,
-               username,
-               password_hash,
-               first_name,
-               last_name,
-               is_active,
-               created_at,
-               updated_at
-        FROM users
-        ORDER BY created_at DESC
-        "#,
+    let visible = context.visible_user().map_err(|e| e.to_string())?;
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    get_all_users_with(
+        &PgUserRepository::new(pool.as_ref()),
+        workspace::current(),
+        visible,
     )
-    .fetch_all(pool.as_ref())
     .await
+}
+
+/// Lists users, or only `visible` when set.
+pub(crate) async fn get_all_users_with<R: UserRepository>(
+    repo: &R,
+    workspace_id: Option<Uuid>,
+    visible: Option<Uuid>,
+) -> Result<Vec<PublicUser>, String> {
+    let users = match visible {
+        None => repo.list(workspace_id).await,
+        Some(user_id) => repo.find(user_id).await.map(Vec::from_iter),
+    }
     .map_err(|e| format!("Failed to fetch users: {}", e))?;
 
     Ok(users.into_iter().map(PublicUser::from).collect())
 }
 
-/// Retrieves a specific user by their UUID.
+/// Searches users by email, username, or name with filters, sorting, and paging.
+///
+/// Like [`get_all_users`], results are limited to the selected workspace.
+/// Requires the admin role.
+#[cfg(feature = "database")]
 #[tauri::command]
-pub async fn get_user_by_id(user_id: String) -> Result<Option<PublicUser>, String> {
+pub async fn search_users(
+    context: CommandContext,
+    query: Option<String>,
+    filters: Option<UserSearchFilters>,
+    sort: Option<UserSort>,
+    pagination: Option<Pagination>,
+) -> Result<UserSearchResult, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::search_users(context, query, filters, sort, pagination).await;
+    }
+
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
     let pool = get_pool_ref().map_err(|e| e.to_string())?;
-    let uuid = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
-
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        SELECT id,
-               email,
-               username,
-               password_hash,
-               first_name,
-               last_name,
-               is_active,
-               created_at,
-               updated_at
-        FROM users
-        WHERE id = $1
-        "#,
-    )
-    .bind(uuid)
-    .fetch_optional(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to fetch user: {}", e))?;
+    let search = build_user_search(
+        query,
+        filters.unwrap_or_default(),
+        sort.unwrap_or_default(),
+        pagination.unwrap_or_default(),
+        workspace::current(),
+    )?;
+    search_users_with(&PgUserRepository::new(pool.as_ref()), &search).await
+}
+
+/// Validates search input, clamping the page size to 1..=200.
+pub(crate) fn build_user_search(
+    query: Option<String>,
+    filters: UserSearchFilters,
+    sort: UserSort,
+    pagination: Pagination,
+    workspace_id: Option<Uuid>,
+) -> Result<UserSearch, String> {
+    if let (Some(after), Some(before)) = (filters.created_after, filters.created_before) {
+        if after >= before {
+            return Err("Invalid date range: createdAfter must be before createdBefore".to_string());
+        }
+    }
+
+    let text = query
+        .map(|query| query.trim().to_string())
+        .filter(|query| !query.is_empty());
+    if text.as_ref().is_some_and(|text| text.chars().count() > 100) {
+        return Err("Invalid query: Search text cannot exceed 100 characters".to_string());
+    }
+
+    Ok(UserSearch {
+        text,
+        filters,
+        sort,
+        limit: pagination.limit.unwrap_or(50).clamp(1, 200),
+        offset: pagination.offset.unwrap_or(0).max(0),
+        workspace_id,
+    })
+}
+
+pub(crate) async fn search_users_with<R: UserRepository>(
+    repo: &R,
+    search: &UserSearch,
+) -> Result<UserSearchResult, String> {
+    let (users, total) = repo
+        .search(search)
+        .await
+        .map_err(|e| format!("Failed to search users: {}", e))?;
+
+    Ok(UserSearchResult {
+        users: users.into_iter().map(PublicUser::from).collect(),
+        total,
+    })
+}
+
+/// Retrieves a specific user by their UUID. Callers without the admin role
+/// may only fetch themselves.
+#[cfg(feature = "database")]
+#[tauri::command]
+pub async fn get_user_by_id(
+    context: CommandContext,
+    user_id: String,
+) -> Result<Option<PublicUser>, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::get_user_by_id(context, user_id).await;
+    }
+
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    get_user_by_id_with(&PgUserRepository::new(pool.as_ref()), &user_id, &context).await
+}
+
+pub(crate) async fn get_user_by_id_with<R: UserRepository>(
+    repo: &R,
+    user_id: &str,
+    context: &CommandContext,
+) -> Result<Option<PublicUser>, String> {
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    context
+        .require_self_or_admin(uuid)
+        .map_err(|e| e.to_string())?;
+
+    let user = repo
+        .find(uuid)
+        .await
+        .map_err(|e| format!("Failed to fetch user: {}", e))?;
 
     Ok(user.map(PublicUser::from))
 }
 
 /// Creates a new user account with validation and password hashing.
+///
+/// The password must satisfy the configured [`PasswordPolicy`]. The user joins the selected workspace, if any, as a member. With
+/// `REQUIRE_EMAIL_VERIFICATION` set, the account stays inactive until the
+/// emailed verification code is redeemed. An account signing up with
+/// `ADMIN_EMAIL` gets the admin role.
+#[cfg(feature = "database")]
 #[tauri::command]
-pub async fn create_user(user_data: CreateUser) -> Result<PublicUser, String> {
+pub async fn create_user(
+    context: CommandContext,
+    user_data: CreateUser,
+) -> Result<PublicUser, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::create_user(context, user_data).await;
+    }
+
     let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let config = config::current();
+    let user = create_user_with(
+        &PgUserRepository::new(pool.as_ref()),
+        user_data,
+        config.require_email_verification,
+        &config.password_policy,
+    )
+    .await?;
+    grant_admin_with(
+        &PgUserRepository::new(pool.as_ref()),
+        &user.email,
+        config.admin_email.as_deref(),
+    )
+    .await?;
+
+    if let Some(workspace_id) = workspace::current() {
+        workspaces::add_member(pool.as_ref(), workspace_id, user.id, "member").await?;
+    }
+
+    if config.require_email_verification {
+        if let Err(e) = email_verification::send(pool.as_ref(), user.id).await {
+            tracing::warn!("Failed to send verification email to user {}: {}", user.id, e);
+        }
+    }
+
+    track_user_change(user.id, SyncOperation::Upsert).await;
+    audit::record_detached(
+        context.actor_id(),
+        audit::USER_CREATED,
+        Some(user.id),
+        json!({ "email": user.email, "username": user.username }),
+    )
+    .await;
+    Ok(PublicUser::from(user))
+}
+
+pub(crate) async fn create_user_with<R: UserRepository>(
+    repo: &R,
+    user_data: CreateUser,
+    require_verification: bool,
+    policy: &PasswordPolicy,
+) -> Result<User, String> {
     let CreateUser {
         email,
         username,
@@ -78,41 +275,123 @@ pub async fn create_user(user_data: CreateUser) -> Result<PublicUser, String> {
     let username = validate_username(&username).map_err(|e| format!("Invalid username: {}", e))?;
     let first_name = validate_optional_name(first_name.as_deref()).map_err(|e| format!("Invalid first name: {}", e))?;
     let last_name = validate_optional_name(last_name.as_deref()).map_err(|e| format!("Invalid last name: {}", e))?;
+    validate_password(&password, policy, &password_context(&email, &username))
+        .map_err(|e| format!("Invalid password: {}", e))?;
 
-    let password_hash = hash(password.as_str(), DEFAULT_COST)
-        .map_err(|e| format!("Failed to hash password: {}", e))?;
-
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        INSERT INTO users (email, username, password_hash, first_name, last_name)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING id,
-                  email,
-                  username,
-                  password_hash,
-                  first_name,
-                  last_name,
-                  is_active,
-                  created_at,
-                  updated_at
-        "#,
-    )
-    .bind(email)
-    .bind(username)
-    .bind(password_hash)
-    .bind(first_name)
-    .bind(last_name)
-    .fetch_one(pool.as_ref())
+    let password_hash = password::hash(&password).map_err(|e| e.message)?;
+
+    repo.insert(NewUser {
+        email,
+        username,
+        password_hash,
+        first_name,
+        last_name,
+        is_active: !require_verification,
+        is_guest: false,
+    })
     .await
-    .map_err(|e| format!("Failed to create user: {}", e))?;
+    .map_err(|e| format!("Failed to create user: {}", e))
+}
 
-    Ok(PublicUser::from(user))
+/// Grants the admin role to the regular account with `email` when it is the
+/// configured `admin_email`. Returns whether the role was granted.
+pub(crate) async fn grant_admin_with<R: UserRepository>(
+    repo: &R,
+    email: &str,
+    admin_email: Option<&str>,
+) -> Result<bool, String> {
+    if admin_email != Some(email) {
+        return Ok(false);
+    }
+    repo.grant_role(email, ADMIN_ROLE)
+        .await
+        .map_err(|e| format!("Failed to grant admin role: {}", e))
 }
 
+/// Grants the admin role to the existing account with `ADMIN_EMAIL`, if any.
+///
+/// Runs at startup after the migrations, so an account that signed up before
+/// `ADMIN_EMAIL` was set still becomes the admin.
+#[cfg(feature = "database")]
+pub async fn bootstrap_admin() -> Result<(), String> {
+    let Some(email) = config::current().admin_email.clone() else {
+        return Ok(());
+    };
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    if !grant_admin_with(&PgUserRepository::new(pool.as_ref()), &email, Some(&email)).await? {
+        tracing::warn!("ADMIN_EMAIL does not belong to a regular account yet");
+    }
+    Ok(())
+}
+
+/// Updates a user. Callers without the admin role may only update
+/// themselves, and only admins may activate or deactivate accounts.
+#[cfg(feature = "database")]
 #[tauri::command]
-pub async fn update_user(user_id: String, user_data: UpdateUser) -> Result<PublicUser, String> {
+pub async fn update_user(
+    context: CommandContext,
+    user_id: String,
+    user_data: UpdateUser,
+) -> Result<PublicUser, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::update_user(context, user_id, user_data).await;
+    }
+
     let pool = get_pool_ref().map_err(|e| e.to_string())?;
-    let uuid = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    let details = json!({
+        "fields": changed_fields(&user_data),
+        "isActive": user_data.is_active,
+    });
+    let user = update_user_with(
+        &PgUserRepository::new(pool.as_ref()),
+        &user_id,
+        user_data,
+        &context,
+    )
+    .await?;
+
+    track_user_change(user.id, SyncOperation::Upsert).await;
+    audit::record_detached(
+        context.actor_id(),
+        audit::USER_UPDATED,
+        Some(user.id),
+        details,
+    )
+    .await;
+    Ok(PublicUser::from(user))
+}
+
+/// Names of the fields an update sets, for the audit log.
+#[cfg(feature = "database")]
+fn changed_fields(user_data: &UpdateUser) -> Vec<&'static str> {
+    [
+        ("email", user_data.email.is_some()),
+        ("username", user_data.username.is_some()),
+        ("firstName", user_data.first_name.is_some()),
+        ("lastName", user_data.last_name.is_some()),
+        ("isActive", user_data.is_active.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(field, set)| set.then_some(field))
+    .collect()
+}
+
+pub(crate) async fn update_user_with<R: UserRepository>(
+    repo: &R,
+    user_id: &str,
+    user_data: UpdateUser,
+    context: &CommandContext,
+) -> Result<User, String> {
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    context
+        .require_self_or_admin(uuid)
+        .map_err(|e| e.to_string())?;
+    if user_data.is_active.is_some() {
+        context
+            .require_role(ADMIN_ROLE)
+            .map_err(|e| e.to_string())?;
+    }
     let UpdateUser {
         email,
         username,
@@ -133,145 +412,542 @@ pub async fn update_user(user_id: String, user_data: UpdateUser) -> Result<Publi
     let first_name = validate_optional_name(first_name.as_deref()).map_err(|e| format!("Invalid first name: {}", e))?;
     let last_name = validate_optional_name(last_name.as_deref()).map_err(|e| format!("Invalid last name: {}", e))?;
 
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        UPDATE users
-        SET email = COALESCE($2, email),
-            username = COALESCE($3, username),
-            first_name = COALESCE($4, first_name),
-            last_name = COALESCE($5, last_name),
-            is_active = COALESCE($6, is_active),
-            updated_at = CURRENT_TIMESTAMP
-        WHERE id = $1
-        RETURNING id,
-                  email,
-                  username,
-                  password_hash,
-                  first_name,
-                  last_name,
-                  is_active,
-                  created_at,
-                  updated_at
-        "#,
+    repo.update(
+        uuid,
+        UserChanges {
+            email,
+            username,
+            first_name,
+            last_name,
+            is_active,
+        },
     )
-    .bind(uuid)
-    .bind(email)
-    .bind(username)
-    .bind(first_name)
-    .bind(last_name)
-    .bind(is_active)
-    .fetch_one(pool.as_ref())
     .await
-    .map_err(|e| format!("Failed to update user: {}", e))?;
-
-    Ok(PublicUser::from(user))
+    .map_err(|e| format!("Failed to update user: {}", e))
 }
 
+/// Deletes a user. Callers without the admin role may only delete themselves.
+#[cfg(feature = "database")]
 #[tauri::command]
-pub async fn delete_user(user_id: String) -> Result<String, String> {
+pub async fn delete_user(context: CommandContext, user_id: String) -> Result<String, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::delete_user(context, user_id).await;
+    }
+
     let pool = get_pool_ref().map_err(|e| e.to_string())?;
-    let uuid = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    let uuid = delete_user_with(&PgUserRepository::new(pool.as_ref()), &user_id, &context).await?;
 
-    let result = sqlx::query("DELETE FROM users WHERE id = $1")
-        .bind(uuid)
-        .execute(pool.as_ref())
+    track_user_change(uuid, SyncOperation::Delete).await;
+    audit::record_detached(
+        context.actor_id(),
+        audit::USER_DELETED,
+        Some(uuid),
+        json!({}),
+    )
+    .await;
+    Ok("User deleted successfully".to_string())
+}
+
+/// Deletes a user, returning its id, or an error when it does not exist.
+pub(crate) async fn delete_user_with<R: UserRepository>(
+    repo: &R,
+    user_id: &str,
+    context: &CommandContext,
+) -> Result<Uuid, String> {
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    context
+        .require_self_or_admin(uuid)
+        .map_err(|e| e.to_string())?;
+
+    let deleted = repo
+        .delete(uuid)
         .await
         .map_err(|e| format!("Failed to delete user: {}", e))?;
 
-    if result.rows_affected() > 0 {
-        Ok("User deleted successfully".to_string())
+    if deleted {
+        Ok(uuid)
     } else {
         Err("User not found".to_string())
     }
 }
 
+/// Records a user change for offline sync; failures are logged, not returned.
+#[cfg(feature = "database")]
+async fn track_user_change(user_id: Uuid, operation: SyncOperation) {
+    if let Err(e) = sync::track_change("users", user_id, operation).await {
+        tracing::warn!("Failed to record sync change for user {}: {}", user_id, e);
+    }
+}
+
+/// Verifies credentials with the active [`provider::AuthProvider`] and, on
+/// success, starts a login session for the user, returning its access and
+/// refresh tokens.
+///
+/// Both outcomes are audited; a rejected attempt records the email it used.
+/// Repeated failures for an email or device lock sign-in with exponential
+/// backoff, see [`throttle`].
+#[cfg(feature = "database")]
 #[tauri::command]
-pub async fn authenticate_user(login_data: LoginRequest) -> Result<Option<PublicUser>, String> {
+pub async fn authenticate_user(login_data: LoginRequest) -> Result<Option<LoginResponse>, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::authenticate_user(login_data).await;
+    }
+
     let pool = get_pool_ref().map_err(|e| e.to_string())?;
-    let LoginRequest { email, password } = login_data;
+    let email = login_data.email.clone();
+    let device_id = login_data.device_id.clone();
+    let remember_me = login_data.remember_me;
+    if let Err(e) = throttle::check(&email, device_id.as_deref()) {
+        audit::record_detached(
+            None,
+            audit::LOGIN_FAILED,
+            None,
+            json!({ "email": email, "reason": "throttled" }),
+        )
+        .await;
+        return Err(e);
+    }
+    let provider = provider::current()?;
+    let Some(user) = provider
+        .authenticate(pool.as_ref(), &email, &login_data.password)
+        .await?
+    else {
+        throttle::record_failure(&email, device_id.as_deref());
+        audit::record_detached(
+            None,
+            audit::LOGIN_FAILED,
+            None,
+            json!({ "email": email, "provider": provider.name() }),
+        )
+        .await;
+        return Ok(None);
+    };
+    throttle::record_success(&email, device_id.as_deref());
+    audit::record_detached(
+        Some(user.id),
+        audit::LOGIN_SUCCEEDED,
+        Some(user.id),
+        json!({ "method": "password", "provider": provider.name() }),
+    )
+    .await;
+    let session = session::start(pool.as_ref(), user.id, remember_me)
+        .await
+        .map_err(|e| format!("Failed to start session: {}", e))?;
+    Ok(Some(LoginResponse { user, session }))
+}
+
+/// Legacy bcrypt hashes are replaced with Argon2id once the password has
+/// been verified; a failed upgrade is logged and does not fail the login.
+pub(crate) async fn authenticate_user_with<R: UserRepository>(
+    repo: &R,
+    login_data: LoginRequest,
+) -> Result<Option<PublicUser>, String> {
+    let LoginRequest {
+        email, password, ..
+    } = login_data;
 
     // Validate email input
     let email = validate_email(&email).map_err(|e| format!("Invalid email: {}", e))?;
 
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        SELECT id,
-               email,
-               username,
-               password_hash,
-               first_name,
-               last_name,
-               is_active,
-               created_at,
-               updated_at
-        FROM users
-        WHERE email = $1
-          AND is_active = TRUE
-        LIMIT 1
-        "#,
+    let user = repo
+        .find_active_by_email(&email)
+        .await
+        .map_err(|e| format!("Failed to authenticate user: {}", e))?;
+
+    // Guests have no password to sign in with.
+    let Some(user) = user.filter(|user| !user.is_guest) else {
+        return Ok(None);
+    };
+    if !password::verify(&password, &user.password_hash).map_err(|e| e.message)? {
+        return Ok(None);
+    }
+
+    if password::needs_rehash(&user.password_hash) {
+        let upgraded = match password::hash(&password) {
+            Ok(password_hash) => repo
+                .rehash_password(user.id, password_hash)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.message),
+        };
+        if let Err(e) = upgraded {
+            tracing::warn!("Failed to upgrade password hash for user {}: {}", user.id, e);
+        }
+    }
+
+    Ok(Some(PublicUser::from(user)))
+}
+
+/// Changes a user's password after verifying the current one. Only the user
+/// and admins may change it, and wrong current passwords count towards the
+/// sign-in [`throttle`] for the account's email.
+///
+/// The new password must satisfy the configured [`PasswordPolicy`] and may
+/// not match the current password or any of the previous
+/// `PASSWORD_HISTORY_DEPTH` passwords.
+#[cfg(feature = "database")]
+#[tauri::command]
+pub async fn change_password(
+    context: CommandContext,
+    user_id: String,
+    current_password: String,
+    new_password: String,
+) -> Result<String, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::change_password(context, user_id, current_password, new_password).await;
+    }
+
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let repo = PgUserRepository::new(pool.as_ref());
+    let config = config::current();
+    let uuid = change_password_with(
+        &repo,
+        &user_id,
+        &context,
+        &current_password,
+        &new_password,
+        config.password_history_depth,
+        &config.password_policy,
+    )
+    .await?;
+
+    track_user_change(uuid, SyncOperation::Upsert).await;
+    audit::record_detached(
+        context.actor_id(),
+        audit::PASSWORD_CHANGED,
+        Some(uuid),
+        json!({}),
+    )
+    .await;
+    Ok("Password changed successfully".to_string())
+}
+
+pub(crate) async fn change_password_with<R: UserRepository>(
+    repo: &R,
+    user_id: &str,
+    context: &CommandContext,
+    current_password: &str,
+    new_password: &str,
+    history_depth: usize,
+    policy: &PasswordPolicy,
+) -> Result<Uuid, String> {
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    context
+        .require_self_or_admin(uuid)
+        .map_err(|e| e.to_string())?;
+
+    let user = repo
+        .find(uuid)
+        .await
+        .map_err(|e| format!("Failed to fetch user: {}", e))?
+        .ok_or_else(|| "User not found".to_string())?;
+    if user.is_guest {
+        return Err("Guest accounts have no password; upgrade the account instead".to_string());
+    }
+    validate_password(new_password, policy, &password_context(&user.email, &user.username))
+        .map_err(|e| format!("Invalid password: {}", e))?;
+
+    throttle::check(&user.email, None)?;
+    if !password::verify(current_password, &user.password_hash).map_err(|e| e.message)? {
+        throttle::record_failure(&user.email, None);
+        return Err("Current password is incorrect".to_string());
+    }
+    throttle::record_success(&user.email, None);
+
+    let history = repo
+        .password_history(uuid, history_depth)
+        .await
+        .map_err(|e| format!("Failed to fetch password history: {}", e))?;
+    for previous in std::iter::once(&user.password_hash).chain(history.iter()) {
+        if password::verify(new_password, previous).unwrap_or(false) {
+            return Err(format!(
+                "Password was used recently; choose one that is not among your last {} passwords",
+                history_depth + 1
+            ));
+        }
+    }
+
+    let password_hash = password::hash(new_password).map_err(|e| e.message)?;
+    repo.replace_password(uuid, password_hash, history_depth)
+        .await
+        .map_err(|e| format!("Failed to change password: {}", e))?;
+
+    Ok(uuid)
+}
+
+/// Creates a local guest account without email or password and starts a
+/// session for it, so the app can be tried before signing up. Requires
+/// `ALLOW_GUEST_ACCOUNTS`.
+#[cfg(feature = "database")]
+#[tauri::command]
+pub async fn create_guest_user(context: CommandContext) -> Result<LoginResponse, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::create_guest_user(context).await;
+    }
+
+    if !config::current().allow_guest_accounts {
+        return Err("Guest accounts are disabled".to_string());
+    }
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let user = create_guest_user_with(&PgUserRepository::new(pool.as_ref())).await?;
+
+    if let Some(workspace_id) = workspace::current() {
+        workspaces::add_member(pool.as_ref(), workspace_id, user.id, "member").await?;
+    }
+
+    track_user_change(user.id, SyncOperation::Upsert).await;
+    audit::record_detached(
+        context.actor_id(),
+        audit::USER_CREATED,
+        Some(user.id),
+        json!({ "guest": true }),
     )
-    .bind(&email)
-    .fetch_optional(pool.as_ref())
+    .await;
+    let session = session::start(pool.as_ref(), user.id, false)
+        .await
+        .map_err(|e| format!("Failed to start session: {}", e))?;
+    Ok(LoginResponse {
+        user: PublicUser::from(user),
+        session,
+    })
+}
+
+pub(crate) async fn create_guest_user_with<R: UserRepository>(repo: &R) -> Result<User, String> {
+    let token = Uuid::new_v4().simple().to_string();
+    repo.insert(NewUser {
+        email: format!("{}@{}", token, GUEST_EMAIL_DOMAIN),
+        username: format!("guest_{}", &token[..12]),
+        password_hash: String::new(),
+        first_name: None,
+        last_name: None,
+        is_active: true,
+        is_guest: true,
+    })
     .await
-    .map_err(|e| format!("Failed to authenticate user: {}", e))?;
+    .map_err(|e| format!("Failed to create guest user: {}", e))
+}
 
-    if let Some(user) = user {
-        match verify(password.as_str(), &user.password_hash) {
-            Ok(true) => Ok(Some(PublicUser::from(user))),
-            Ok(false) => Ok(None),
-            Err(e) => Err(format!("Failed to verify password: {}", e)),
+/// Outcome of upgrading a guest account.
+#[derive(Debug)]
+pub(crate) enum GuestUpgrade {
+    /// The guest became a regular account and kept its id.
+    Attached(User),
+    /// The credentials belong to an existing account, which took over the
+    /// guest's settings and logs; the guest was deleted.
+    Merged { guest_id: Uuid, account: User },
+}
+
+/// Turns a guest into a regular account once the user signs up.
+///
+/// New credentials are attached to the guest, which keeps its id, settings,
+/// and logs; they must satisfy the same rules as `create_user`. When the
+/// email belongs to an existing account, the password must match it instead:
+/// the guest's logs and any settings the account lacks move to that account
+/// and the guest is deleted. Wrong passwords count towards the sign-in
+/// [`throttle`] for that email.
+///
+/// Returns a session for the resulting account, or `None` when it has to
+/// verify its email address first.
+#[cfg(feature = "database")]
+#[tauri::command]
+pub async fn upgrade_guest_account(
+    context: CommandContext,
+    guest_id: String,
+    credentials: CreateUser,
+) -> Result<Option<LoginResponse>, String> {
+    #[cfg(feature = "sqlite")]
+    if crate::local_db::is_selected() {
+        return super::local_db::upgrade_guest_account(context, guest_id, credentials).await;
+    }
+
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let config = config::current();
+    let upgrade = upgrade_guest_with(
+        &PgUserRepository::new(pool.as_ref()),
+        &guest_id,
+        credentials,
+        &context,
+        config.require_email_verification,
+        &config.password_policy,
+    )
+    .await?;
+
+    let (user, merged) = match upgrade {
+        GuestUpgrade::Attached(user) => {
+            grant_admin_with(
+                &PgUserRepository::new(pool.as_ref()),
+                &user.email,
+                config.admin_email.as_deref(),
+            )
+            .await?;
+            if config.require_email_verification {
+                if let Err(e) = email_verification::send(pool.as_ref(), user.id).await {
+                    tracing::warn!(
+                        "Failed to send verification email to user {}: {}",
+                        user.id,
+                        e
+                    );
+                }
+            }
+            track_user_change(user.id, SyncOperation::Upsert).await;
+            (user, false)
         }
-    } else {
-        Ok(None)
+        GuestUpgrade::Merged { guest_id, account } => {
+            track_user_change(guest_id, SyncOperation::Delete).await;
+            (account, true)
+        }
+    };
+    audit::record_detached(
+        context.actor_id(),
+        audit::GUEST_UPGRADED,
+        Some(user.id),
+        json!({ "guestId": guest_id, "merged": merged }),
+    )
+    .await;
+
+    if !user.is_active {
+        return Ok(None);
+    }
+    let session = session::start(pool.as_ref(), user.id, false)
+        .await
+        .map_err(|e| format!("Failed to start session: {}", e))?;
+    Ok(Some(LoginResponse {
+        user: PublicUser::from(user),
+        session,
+    }))
+}
+
+pub(crate) async fn upgrade_guest_with<R: UserRepository>(
+    repo: &R,
+    guest_id: &str,
+    credentials: CreateUser,
+    context: &CommandContext,
+    require_verification: bool,
+    policy: &PasswordPolicy,
+) -> Result<GuestUpgrade, String> {
+    let uuid = Uuid::parse_str(guest_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    context
+        .require_self_or_admin(uuid)
+        .map_err(|e| e.to_string())?;
+
+    let guest = repo
+        .find(uuid)
+        .await
+        .map_err(|e| format!("Failed to fetch user: {}", e))?
+        .ok_or_else(|| "User not found".to_string())?;
+    if !guest.is_guest {
+        return Err("User is not a guest account".to_string());
+    }
+
+    let CreateUser {
+        email,
+        username,
+        password,
+        first_name,
+        last_name,
+    } = credentials;
+    let email = validate_email(&email).map_err(|e| format!("Invalid email: {}", e))?;
+
+    let existing = repo
+        .find_active_by_email(&email)
+        .await
+        .map_err(|e| format!("Failed to fetch user: {}", e))?
+        .filter(|account| !account.is_guest);
+    if let Some(account) = existing {
+        throttle::check(&email, None)?;
+        if !password::verify(&password, &account.password_hash).map_err(|e| e.message)? {
+            throttle::record_failure(&email, None);
+            return Err(
+                "Email is already registered; enter that account's password to merge into it"
+                    .to_string(),
+            );
+        }
+        throttle::record_success(&email, None);
+        repo.merge_guest(uuid, account.id)
+            .await
+            .map_err(|e| format!("Failed to merge guest account: {}", e))?;
+        return Ok(GuestUpgrade::Merged {
+            guest_id: uuid,
+            account,
+        });
     }
+
+    let username = validate_username(&username).map_err(|e| format!("Invalid username: {}", e))?;
+    let first_name = validate_optional_name(first_name.as_deref())
+        .map_err(|e| format!("Invalid first name: {}", e))?;
+    let last_name = validate_optional_name(last_name.as_deref())
+        .map_err(|e| format!("Invalid last name: {}", e))?;
+    validate_password(&password, policy, &password_context(&email, &username))
+        .map_err(|e| format!("Invalid password: {}", e))?;
+    let password_hash = password::hash(&password).map_err(|e| e.message)?;
+
+    let user = repo
+        .upgrade_guest(
+            uuid,
+            NewUser {
+                email,
+                username,
+                password_hash,
+                first_name,
+                last_name,
+                is_active: !require_verification,
+                is_guest: false,
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to upgrade guest account: {}", e))?;
+    Ok(GuestUpgrade::Attached(user))
+}
+
+/// Account details a password should not be built around.
+fn password_context<'a>(email: &'a str, username: &'a str) -> [&'a str; 2] {
+    [email.split('@').next().unwrap_or_default(), username]
 }
-#[cfg(test)]
+
+#[cfg(all(test, feature = "database"))]
 mod tests {
     use super::*;
     use crate::database::test_utils::{pool, reset_all_tables};
-    use crate::models::{CreateUser, LoginRequest, UpdateUser};
+    use crate::models::{LoginRequest, UpdateUser};
+    use crate::test_support::UserFactory;
     use anyhow::Result as AnyResult;
     use serial_test::serial;
     use uuid::Uuid;
 
-    fn sample_user_payload() -> CreateUser {
-        let unique_suffix = Uuid::new_v4();
-        CreateUser {
-            email: format!("user+{}@example.com", unique_suffix),
-            username: format!("user_{}", unique_suffix.simple()),
-            password: "Sup3r$ecret".to_string(),
-            first_name: Some("Test".to_string()),
-            last_name: Some("User".to_string()),
-        }
-    }
-
     #[tokio::test]
     #[serial]
     async fn full_user_lifecycle_and_authentication() -> AnyResult<()> {
         let pool = pool().await?;
         reset_all_tables(pool.as_ref()).await?;
 
-        let payload = sample_user_payload();
+        let payload = UserFactory::new().build();
         let email = payload.email.clone();
         let password = payload.password.clone();
 
-        let created = create_user(payload)
+        let created = create_user(CommandContext::internal(), payload)
             .await
             .expect("user creation should succeed");
         assert_eq!(created.email, email);
         assert_eq!(created.first_name.as_deref(), Some("Test"));
 
-        let listed = get_all_users().await.expect("listing users should succeed");
+        let listed = get_all_users(CommandContext::internal())
+            .await
+            .expect("listing users should succeed");
         assert_eq!(listed.len(), 1);
         assert_eq!(listed[0].email, email);
 
-        let fetched = get_user_by_id(created.id.to_string())
+        let fetched = get_user_by_id(CommandContext::internal(), created.id.to_string())
             .await
             .expect("fetching user should succeed")
             .expect("user should exist");
         assert_eq!(fetched.username, listed[0].username);
 
         let updated = update_user(
+            CommandContext::internal(),
             created.id.to_string(),
             UpdateUser {
                 email: None,
@@ -290,32 +966,58 @@ mod tests {
         let authenticated = authenticate_user(LoginRequest {
             email: email.clone(),
             password,
+            device_id: None,
+            remember_me: false,
         })
         .await
         .expect("authentication should succeed")
         .expect("credentials should match");
-        assert_eq!(authenticated.id, created.id);
+        assert_eq!(authenticated.user.id, created.id);
 
         let wrong_password = authenticate_user(LoginRequest {
             email: email.clone(),
             password: "badpassword".to_string(),
+            device_id: None,
+            remember_me: false,
         })
         .await
         .expect("authentication should return Ok")
         .is_none();
         assert!(wrong_password);
 
-        let deletion = delete_user(created.id.to_string())
+        let deletion = delete_user(CommandContext::internal(), created.id.to_string())
             .await
             .expect("deleting user should succeed");
         assert_eq!(deletion, "User deleted successfully");
 
-        let missing = get_user_by_id(created.id.to_string())
+        let missing = get_user_by_id(CommandContext::internal(), created.id.to_string())
             .await
             .expect("fetch should succeed")
             .is_none();
         assert!(missing);
 
+        let audited: Vec<String> = sqlx::query_scalar(
+            "SELECT action FROM audit_log WHERE user_id = $1 ORDER BY created_at",
+        )
+        .bind(created.id)
+        .fetch_all(pool.as_ref())
+        .await?;
+        assert_eq!(
+            audited,
+            vec![
+                audit::USER_CREATED,
+                audit::USER_UPDATED,
+                audit::LOGIN_SUCCEEDED,
+                audit::USER_DELETED,
+            ]
+        );
+        let failed: serde_json::Value =
+            sqlx::query_scalar("SELECT details FROM audit_log WHERE action = $1")
+                .bind(audit::LOGIN_FAILED)
+                .fetch_one(pool.as_ref())
+                .await?;
+        assert_eq!(failed["email"], email);
+
         Ok(())
     }
 
@@ -325,8 +1027,570 @@ mod tests {
         let pool = pool().await?;
         reset_all_tables(pool.as_ref()).await?;
 
-        let response = delete_user(Uuid::new_v4().to_string()).await;
+        let response = delete_user(CommandContext::internal(), Uuid::new_v4().to_string()).await;
         assert!(matches!(response, Err(message) if message == "User not found"));
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn only_the_admin_email_gets_the_admin_role() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let repo = PgUserRepository::new(pool.as_ref());
+        let policy = PasswordPolicy::default();
+
+        let first = create_user_with(&repo, UserFactory::new().build(), false, &policy)
+            .await
+            .unwrap();
+        let admin = create_user_with(&repo, UserFactory::new().build(), false, &policy)
+            .await
+            .unwrap();
+        let admin_email = Some(admin.email.as_str());
+        let granted = grant_admin_with(&repo, &first.email, admin_email)
+            .await
+            .unwrap();
+        assert!(!granted);
+        for _ in 0..2 {
+            let granted = grant_admin_with(&repo, &admin.email, admin_email)
+                .await
+                .unwrap();
+            assert!(granted);
+        }
+
+        let roles = |id: Uuid| {
+            sqlx::query_scalar::<_, Vec<String>>("SELECT roles FROM users WHERE id = $1")
+                .bind(id)
+                .fetch_one(pool.as_ref())
+        };
+        assert!(roles(first.id).await?.is_empty());
+        assert_eq!(roles(admin.id).await?, vec![ADMIN_ROLE]);
+        Ok(())
+    }
+
+    mod in_memory {
+        use crate::command_context::CommandContext;
+        use crate::config::PasswordPolicy;
+        use crate::handlers::users::*;
+        use crate::models::{
+            LoginRequest, Pagination, SortDirection, UpdateUser, UserSearchFilters, UserSort,
+            UserSortField,
+        };
+        use crate::repository::memory::InMemoryUserRepository;
+        use crate::repository::UserRepository;
+        use crate::test_support::UserFactory;
+        use uuid::Uuid;
+
+        #[tokio::test]
+        async fn lifecycle_without_database() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let payload = UserFactory::new().build();
+            let email = payload.email.clone();
+            let password = payload.password.clone();
+
+            let created = create_user_with(&repo, payload, false, &policy)
+                .await
+                .expect("user creation should succeed");
+            assert_ne!(created.password_hash, password);
+
+            let updated = update_user_with(
+                &repo,
+                &created.id.to_string(),
+                UpdateUser {
+                    email: None,
+                    username: Some("renamed_user".to_string()),
+                    first_name: None,
+                    last_name: None,
+                    is_active: None,
+                },
+                &CommandContext::internal(),
+            )
+            .await
+            .expect("update should succeed");
+            assert_eq!(updated.username, "renamed_user");
+            assert_eq!(updated.first_name.as_deref(), Some("Test"));
+
+            let authenticated = authenticate_user_with(
+                &repo,
+                LoginRequest {
+                    email: email.clone(),
+                    password: password.clone(),
+                    device_id: None,
+                    remember_me: false,
+                },
+            )
+            .await
+            .expect("authentication should succeed");
+            assert_eq!(authenticated.map(|user| user.id), Some(created.id));
+
+            let rejected = authenticate_user_with(
+                &repo,
+                LoginRequest {
+                    email,
+                    password: "wrong-password".to_string(),
+                    device_id: None,
+                    remember_me: false,
+                },
+            )
+            .await
+            .expect("authentication should not error");
+            assert!(rejected.is_none());
+
+            let context = CommandContext::internal();
+            delete_user_with(&repo, &created.id.to_string(), &context)
+                .await
+                .expect("delete should succeed");
+            let missing = delete_user_with(&repo, &created.id.to_string(), &context).await;
+            assert_eq!(missing, Err("User not found".to_string()));
+        }
+
+        #[tokio::test]
+        async fn users_only_read_and_edit_themselves() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let user = create_user_with(&repo, UserFactory::new().build(), false, &policy)
+                .await
+                .unwrap();
+            let other = create_user_with(&repo, UserFactory::new().build(), false, &policy)
+                .await
+                .unwrap();
+            let context = CommandContext::for_user(user.id, Vec::new());
+            let rename = |username: &str| UpdateUser {
+                email: None,
+                username: Some(username.to_string()),
+                first_name: None,
+                last_name: None,
+                is_active: None,
+            };
+
+            assert!(get_user_by_id_with(&repo, &user.id.to_string(), &context)
+                .await
+                .unwrap()
+                .is_some());
+            assert!(get_user_by_id_with(&repo, &other.id.to_string(), &context)
+                .await
+                .is_err());
+            assert!(
+                update_user_with(&repo, &user.id.to_string(), rename("me_renamed"), &context)
+                    .await
+                    .is_ok()
+            );
+            assert!(update_user_with(
+                &repo,
+                &other.id.to_string(),
+                rename("them_renamed"),
+                &context
+            )
+            .await
+            .is_err());
+
+            let deactivate = UpdateUser {
+                is_active: Some(false),
+                ..rename("me_again")
+            };
+            assert!(
+                update_user_with(&repo, &user.id.to_string(), deactivate, &context)
+                    .await
+                    .is_err()
+            );
+        }
+
+        #[tokio::test]
+        async fn rejects_invalid_and_duplicate_input() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+
+            let invalid = UserFactory::new().email("not-an-email").build();
+            let error = create_user_with(&repo, invalid, false, &policy).await.unwrap_err();
+            assert!(error.starts_with("Invalid email"));
+
+            let first = UserFactory::new().build();
+            let duplicate = UserFactory::new().email(first.email.clone()).build();
+            create_user_with(&repo, first, false, &policy).await.unwrap();
+            let error = create_user_with(&repo, duplicate, false, &policy).await.unwrap_err();
+            assert!(error.contains("unique constraint"));
+
+            let weak = UserFactory::new().password("password1").build();
+            let error = create_user_with(&repo, weak, false, &policy).await.unwrap_err();
+            assert!(error.starts_with("Invalid password"));
+
+            let error = get_user_by_id_with(&repo, "nope", &CommandContext::internal())
+                .await
+                .unwrap_err();
+            assert!(error.starts_with("Invalid UUID"));
+        }
+
+        #[tokio::test]
+        async fn lists_only_workspace_members() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let member = create_user_with(&repo, UserFactory::new().build(), false, &policy).await.unwrap();
+            create_user_with(&repo, UserFactory::new().build(), false, &policy).await.unwrap();
+
+            let workspace_id = Uuid::new_v4();
+            repo.add_member(workspace_id, member.id);
+
+            assert_eq!(get_all_users_with(&repo, None, None).await.unwrap().len(), 2);
+            let scoped = get_all_users_with(&repo, Some(workspace_id), None).await.unwrap();
+            assert_eq!(scoped.len(), 1);
+            assert_eq!(scoped[0].id, member.id);
+        }
+
+        #[tokio::test]
+        async fn limits_non_admins_to_their_own_user() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let own = create_user_with(&repo, UserFactory::new().build(), false, &policy).await.unwrap();
+            let other = create_user_with(&repo, UserFactory::new().build(), false, &policy).await.unwrap();
+
+            let listed = get_all_users_with(&repo, None, Some(own.id)).await.unwrap();
+            assert_eq!(listed.len(), 1);
+            assert_eq!(listed[0].id, own.id);
+
+            let context = CommandContext::for_user(own.id, Vec::new());
+            let error = delete_user_with(&repo, &other.id.to_string(), &context).await.unwrap_err();
+            assert!(error.contains("FORBIDDEN"));
+            delete_user_with(&repo, &own.id.to_string(), &context).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn searches_with_filters_sorting_and_paging() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            for email in ["ada@example.com", "grace@example.com", "alan@example.org"] {
+                create_user_with(&repo, UserFactory::new().email(email).build(), false, &policy)
+                    .await
+                    .unwrap();
+            }
+            let alan = repo.find_active_by_email("alan@example.org").await.unwrap().unwrap();
+            update_user_with(
+                &repo,
+                &alan.id.to_string(),
+                UpdateUser {
+                    email: None,
+                    username: None,
+                    first_name: None,
+                    last_name: None,
+                    is_active: Some(false),
+                },
+                &CommandContext::internal(),
+            )
+            .await
+            .unwrap();
+
+            let search = |query: &str, filters, sort, limit| {
+                build_user_search(
+                    Some(query.to_string()),
+                    filters,
+                    sort,
+                    Pagination {
+                        limit: Some(limit),
+                        offset: None,
+                    },
+                    None,
+                )
+                .unwrap()
+            };
+
+            let by_domain = search(
+                "EXAMPLE.COM",
+                UserSearchFilters::default(),
+                UserSort {
+                    field: UserSortField::Email,
+                    direction: SortDirection::Asc,
+                },
+                1,
+            );
+            let result = search_users_with(&repo, &by_domain).await.unwrap();
+            assert_eq!(result.total, 2);
+            assert_eq!(result.users.len(), 1);
+            assert_eq!(result.users[0].email, "ada@example.com");
+
+            let inactive = search(
+                "a",
+                UserSearchFilters {
+                    is_active: Some(false),
+                    ..Default::default()
+                },
+                UserSort::default(),
+                10,
+            );
+            let result = search_users_with(&repo, &inactive).await.unwrap();
+            assert_eq!(result.total, 1);
+            assert_eq!(result.users[0].id, alan.id);
+
+            let group_id = Uuid::new_v4();
+            repo.add_group_member(group_id, alan.id);
+            let in_group = search(
+                "",
+                UserSearchFilters {
+                    group_id: Some(group_id),
+                    ..Default::default()
+                },
+                UserSort::default(),
+                10,
+            );
+            let result = search_users_with(&repo, &in_group).await.unwrap();
+            assert_eq!(result.total, 1);
+            assert_eq!(result.users[0].id, alan.id);
+        }
+
+        #[test]
+        fn rejects_invalid_search_input() {
+            let now = chrono::Utc::now();
+            let filters = UserSearchFilters {
+                is_active: None,
+                created_after: Some(now),
+                created_before: Some(now),
+                group_id: None,
+            };
+            let error = build_user_search(
+                None,
+                filters,
+                UserSort::default(),
+                Pagination::default(),
+                None,
+            )
+            .unwrap_err();
+            assert!(error.starts_with("Invalid date range"));
+
+            let search = build_user_search(
+                Some("   ".to_string()),
+                UserSearchFilters::default(),
+                UserSort::default(),
+                Pagination {
+                    limit: Some(10_000),
+                    offset: Some(-1),
+                },
+                None,
+            )
+            .unwrap();
+            assert!(search.text.is_none());
+            assert_eq!((search.limit, search.offset), (200, 0));
+        }
+
+        #[tokio::test]
+        async fn change_password_rejects_recent_passwords() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let payload = UserFactory::new().build();
+            let original = payload.password.clone();
+            let user = create_user_with(&repo, payload, false, &policy).await.unwrap();
+            let id = user.id.to_string();
+            let context = CommandContext::for_user(user.id, Vec::new());
+
+            let stranger = CommandContext::for_user(Uuid::new_v4(), Vec::new());
+            let denied =
+                change_password_with(&repo, &id, &stranger, &original, "N3w$ecret", 2, &policy)
+                    .await;
+            assert!(denied.is_err());
+
+            let error =
+                change_password_with(&repo, &id, &context, "wrong", "N3w$ecret", 2, &policy)
+                    .await
+                    .unwrap_err();
+            assert_eq!(error, "Current password is incorrect");
+
+            let error = change_password_with(&repo, &id, &context, &original, "short", 2, &policy)
+                .await
+                .unwrap_err();
+            assert_eq!(error, "Invalid password: Password must be at least 8 characters");
+
+            let error =
+                change_password_with(&repo, &id, &context, &original, &original, 2, &policy)
+                    .await
+                    .unwrap_err();
+            assert!(error.starts_with("Password was used recently"));
+
+            change_password_with(&repo, &id, &context, &original, "Second$1", 2, &policy)
+                .await
+                .unwrap();
+            change_password_with(&repo, &id, &context, "Second$1", "Third$12", 2, &policy)
+                .await
+                .unwrap();
+            let error =
+                change_password_with(&repo, &id, &context, "Third$12", &original, 2, &policy)
+                    .await
+                    .unwrap_err();
+            assert!(error.starts_with("Password was used recently"));
+
+            // Only two previous passwords are kept, so the original ages out.
+            change_password_with(&repo, &id, &context, "Third$12", "Fourth$1", 2, &policy)
+                .await
+                .unwrap();
+            change_password_with(&repo, &id, &context, "Fourth$1", &original, 2, &policy)
+                .await
+                .unwrap();
+        }
+
+        #[tokio::test]
+        async fn login_upgrades_bcrypt_hashes() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let payload = UserFactory::new().build();
+            let (email, password) = (payload.email.clone(), payload.password.clone());
+            let user = create_user_with(&repo, payload, false, &policy).await.unwrap();
+            let legacy = bcrypt::hash(&password, 4).unwrap();
+            repo.rehash_password(user.id, legacy.clone()).await.unwrap();
+
+            let login = |password: &str| LoginRequest {
+                email: email.clone(),
+                password: password.to_string(),
+                device_id: None,
+                remember_me: false,
+            };
+            assert!(authenticate_user_with(&repo, login("wrong")).await.unwrap().is_none());
+            assert_eq!(repo.find(user.id).await.unwrap().unwrap().password_hash, legacy);
+
+            assert!(authenticate_user_with(&repo, login(&password)).await.unwrap().is_some());
+            let upgraded = repo.find(user.id).await.unwrap().unwrap().password_hash;
+            assert!(upgraded.starts_with("$argon2id$"));
+            assert!(authenticate_user_with(&repo, login(&password)).await.unwrap().is_some());
+            assert!(repo.password_history(user.id, 5).await.unwrap().is_empty());
+        }
+
+        #[tokio::test]
+        async fn unverified_users_cannot_sign_in_when_verification_is_required() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let payload = UserFactory::new().build();
+            let login = LoginRequest {
+                email: payload.email.clone(),
+                password: payload.password.clone(),
+                device_id: None,
+                remember_me: false,
+            };
+
+            let user = create_user_with(&repo, payload, true, &policy).await.unwrap();
+            assert!(!user.is_active);
+            assert!(!user.email_verified);
+            assert!(authenticate_user_with(&repo, login).await.unwrap().is_none());
+        }
+
+        #[tokio::test]
+        async fn guests_upgrade_in_place_and_keep_their_id() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let guest = create_guest_user_with(&repo).await.unwrap();
+            let guest_id = guest.id.to_string();
+            assert!(guest.is_guest);
+            let guest_login = LoginRequest {
+                email: guest.email.clone(),
+                password: String::new(),
+                device_id: None,
+                remember_me: false,
+            };
+            assert!(authenticate_user_with(&repo, guest_login)
+                .await
+                .unwrap()
+                .is_none());
+
+            let payload = UserFactory::new().build();
+            let login = LoginRequest {
+                email: payload.email.clone(),
+                password: payload.password.clone(),
+                device_id: None,
+                remember_me: false,
+            };
+            let context = CommandContext::for_user(guest.id, Vec::new());
+            let upgrade = upgrade_guest_with(&repo, &guest_id, payload, &context, false, &policy)
+                .await
+                .unwrap();
+            let GuestUpgrade::Attached(user) = upgrade else {
+                panic!("expected the credentials to be attached to the guest");
+            };
+            assert_eq!(user.id, guest.id);
+            assert!(!user.is_guest);
+            let authenticated = authenticate_user_with(&repo, login).await.unwrap();
+            assert_eq!(authenticated.map(|user| user.id), Some(guest.id));
+
+            let again = UserFactory::new().build();
+            let error = upgrade_guest_with(&repo, &guest_id, again, &context, false, &policy)
+                .await
+                .unwrap_err();
+            assert_eq!(error, "User is not a guest account");
+        }
+
+        #[tokio::test]
+        async fn guests_merge_into_existing_accounts_with_their_password() {
+            let repo = InMemoryUserRepository::new();
+            let policy = PasswordPolicy::default();
+            let payload = UserFactory::new().build();
+            let (email, password) = (payload.email.clone(), payload.password.clone());
+            let account = create_user_with(&repo, payload, false, &policy)
+                .await
+                .unwrap();
+            let guest = create_guest_user_with(&repo).await.unwrap();
+            let guest_id = guest.id.to_string();
+            let context = CommandContext::for_user(guest.id, Vec::new());
+            let credentials = |password: &str| {
+                UserFactory::new()
+                    .email(email.clone())
+                    .password(password)
+                    .build()
+            };
+
+            let wrong = credentials("Wr0ng$pass");
+            let error = upgrade_guest_with(&repo, &guest_id, wrong, &context, false, &policy)
+                .await
+                .unwrap_err();
+            assert!(error.starts_with("Email is already registered"));
+
+            let stranger = CommandContext::for_user(Uuid::new_v4(), Vec::new());
+            let denied = upgrade_guest_with(
+                &repo,
+                &guest_id,
+                credentials(&password),
+                &stranger,
+                false,
+                &policy,
+            )
+            .await;
+            assert!(denied.is_err());
+
+            let upgrade = upgrade_guest_with(
+                &repo,
+                &guest_id,
+                credentials(&password),
+                &context,
+                false,
+                &policy,
+            )
+            .await
+            .unwrap();
+            assert!(matches!(
+                upgrade,
+                GuestUpgrade::Merged { account: ref merged, .. } if merged.id == account.id
+            ));
+            assert!(repo.find(guest.id).await.unwrap().is_none());
+        }
+
+        #[tokio::test]
+        async fn the_admin_role_goes_only_to_the_admin_email() {
+            let repo = InMemoryUserRepository::new();
+            let guest = create_guest_user_with(&repo).await.unwrap();
+            let user = create_user_with(
+                &repo,
+                UserFactory::new().build(),
+                false,
+                &PasswordPolicy::default(),
+            )
+            .await
+            .unwrap();
+
+            let granted = grant_admin_with(&repo, &guest.email, Some(&guest.email))
+                .await
+                .unwrap();
+            assert!(!granted);
+            assert!(!grant_admin_with(&repo, &user.email, None).await.unwrap());
+            assert!(repo.roles(user.id).is_empty());
+
+            let granted = grant_admin_with(&repo, &user.email, Some(&user.email))
+                .await
+                .unwrap();
+            assert!(granted);
+            assert_eq!(repo.roles(user.id), vec![ADMIN_ROLE]);
+        }
+    }
 }
@@ -0,0 +1,417 @@
+//! Workspace (multi-tenant) management command handlers.
+//!
+//! Workspace owners and admins manage their workspace; app admins manage
+//! every workspace. Other users only see and switch to the workspaces they
+//! belong to.
+
+use crate::command_context::{CommandContext, ADMIN_ROLE};
+use crate::database::{get_pool_ref, query_stats};
+use crate::errors::AppError;
+use crate::models::{CreateWorkspace, UserSettings, Workspace, WorkspaceMember};
+use crate::workspace;
+use sqlx::{Execute, PgPool};
+use uuid::Uuid;
+
+/// Roles a workspace member may hold.
+const ROLES: [&str; 3] = ["owner", "admin", "member"];
+
+/// Workspace roles that may change its members.
+const MANAGER_ROLES: [&str; 2] = ["owner", "admin"];
+
+/// Adds `user_id` to `workspace_id`, updating the role if already a member.
+pub(crate) async fn add_member(
+    pool: &PgPool,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    role: &str,
+) -> Result<WorkspaceMember, String> {
+    let query = sqlx::query_as!(
+        WorkspaceMember,
+        r#"
+        INSERT INTO workspace_members (workspace_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (workspace_id, user_id) DO UPDATE SET role = EXCLUDED.role
+        RETURNING workspace_id,
+                  user_id,
+                  role,
+                  joined_at AS "joined_at!"
+        "#,
+        workspace_id,
+        user_id,
+        role,
+    );
+    query_stats::timed(query.sql(), query.fetch_one(pool))
+        .await
+        .map_err(|e| format!("Failed to add workspace member: {}", e))
+}
+
+/// Fails unless the caller is an app admin or holds one of `roles` in
+/// `workspace_id`.
+async fn require_workspace_role(
+    context: &CommandContext,
+    pool: &PgPool,
+    workspace_id: Uuid,
+    roles: &[&str],
+) -> Result<(), String> {
+    if context.is_admin() {
+        return Ok(());
+    }
+    let user_id = context.require_user().map_err(|e| e.to_string())?;
+    let query = sqlx::query_scalar!(
+        "SELECT role FROM workspace_members WHERE workspace_id = $1 AND user_id = $2",
+        workspace_id,
+        user_id,
+    );
+    let role = query_stats::timed(query.sql(), query.fetch_optional(pool))
+        .await
+        .map_err(|e| format!("Failed to fetch workspace membership: {}", e))?;
+    match role {
+        Some(role) if roles.contains(&role.as_str()) => Ok(()),
+        _ => Err(AppError::forbidden("Not allowed for this workspace").to_string()),
+    }
+}
+
+async fn fetch_workspace(pool: &PgPool, workspace_id: Uuid) -> Result<Option<Workspace>, String> {
+    let query = sqlx::query_as!(
+        Workspace,
+        r#"
+        SELECT id,
+               name,
+               slug,
+               created_at AS "created_at!",
+               updated_at AS "updated_at!"
+        FROM workspaces
+        WHERE id = $1
+        "#,
+        workspace_id,
+    );
+    query_stats::timed(query.sql(), query.fetch_optional(pool))
+        .await
+        .map_err(|e| format!("Failed to fetch workspace: {}", e))
+}
+
+/// Creates a workspace, optionally adding an owner. Users other than admins
+/// can only create workspaces they own themselves.
+#[tauri::command]
+pub async fn create_workspace(
+    context: CommandContext,
+    workspace_data: CreateWorkspace,
+) -> Result<Workspace, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let CreateWorkspace {
+        name,
+        slug,
+        owner_id,
+    } = workspace_data;
+    let owner_id = match owner_id {
+        Some(owner_id) => {
+            context
+                .require_self_or_admin(owner_id)
+                .map_err(|e| e.to_string())?;
+            Some(owner_id)
+        }
+        None if context.is_admin() => None,
+        None => Some(context.require_user().map_err(|e| e.to_string())?),
+    };
+
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err("Invalid name: must be between 1 and 100 characters".to_string());
+    }
+    let slug = workspace::slugify(slug.as_deref().unwrap_or(&name));
+    if slug.is_empty() || slug.len() > 100 {
+        return Err("Invalid slug: must contain letters or digits".to_string());
+    }
+
+    let query = sqlx::query_as!(
+        Workspace,
+        r#"
+        INSERT INTO workspaces (name, slug)
+        VALUES ($1, $2)
+        RETURNING id,
+                  name,
+                  slug,
+                  created_at AS "created_at!",
+                  updated_at AS "updated_at!"
+        "#,
+        name,
+        slug,
+    );
+    let workspace = query_stats::timed(query.sql(), query.fetch_one(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to create workspace: {}", e))?;
+
+    if let Some(owner_id) = owner_id {
+        add_member(pool.as_ref(), workspace.id, owner_id, "owner").await?;
+    }
+
+    Ok(workspace)
+}
+
+/// Lists all workspaces, optionally only those `user_id` belongs to. Users
+/// without the admin role only get their own workspaces.
+#[tauri::command]
+pub async fn list_workspaces(
+    context: CommandContext,
+    user_id: Option<String>,
+) -> Result<Vec<Workspace>, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let user_id = user_id
+        .map(|id| Uuid::parse_str(&id).map_err(|e| format!("Invalid UUID: {}", e)))
+        .transpose()?;
+    let visible = context.visible_user().map_err(|e| e.to_string())?;
+    if let Some(user_id) = user_id {
+        context
+            .require_self_or_admin(user_id)
+            .map_err(|e| e.to_string())?;
+    }
+    let user_id = user_id.or(visible);
+
+    let query = sqlx::query_as!(
+        Workspace,
+        r#"
+        SELECT id,
+               name,
+               slug,
+               created_at AS "created_at!",
+               updated_at AS "updated_at!"
+        FROM workspaces
+        WHERE $1::UUID IS NULL
+           OR id IN (SELECT workspace_id FROM workspace_members WHERE user_id = $1)
+        ORDER BY name
+        "#,
+        user_id,
+    );
+    query_stats::timed(query.sql(), query.fetch_all(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to fetch workspaces: {}", e))
+}
+
+/// Adds a user to a workspace with the given role (defaults to `member`).
+/// Requires a workspace owner or admin; only owners may add owners.
+#[tauri::command]
+pub async fn add_workspace_member(
+    context: CommandContext,
+    workspace_id: String,
+    user_id: String,
+    role: Option<String>,
+) -> Result<WorkspaceMember, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let workspace_id =
+        Uuid::parse_str(&workspace_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    let user_id = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let role = role
+        .unwrap_or_else(|| "member".to_string())
+        .trim()
+        .to_lowercase();
+    if !ROLES.contains(&role.as_str()) {
+        return Err(format!("Invalid role: must be one of {}", ROLES.join(", ")));
+    }
+    let allowed: &[&str] = if role == "owner" {
+        &["owner"]
+    } else {
+        &MANAGER_ROLES
+    };
+    require_workspace_role(&context, pool.as_ref(), workspace_id, allowed).await?;
+
+    add_member(pool.as_ref(), workspace_id, user_id, &role).await
+}
+
+/// Removes a user from a workspace. Members may leave on their own;
+/// removing someone else requires a workspace owner or admin.
+#[tauri::command]
+pub async fn remove_workspace_member(
+    context: CommandContext,
+    workspace_id: String,
+    user_id: String,
+) -> Result<String, String> {
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let workspace_id =
+        Uuid::parse_str(&workspace_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    let user_id = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    if context.user_id != Some(user_id) {
+        require_workspace_role(&context, pool.as_ref(), workspace_id, &MANAGER_ROLES).await?;
+    }
+
+    let query = sqlx::query!(
+        "DELETE FROM workspace_members WHERE workspace_id = $1 AND user_id = $2",
+        workspace_id,
+        user_id,
+    );
+    let result = query_stats::timed(query.sql(), query.execute(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to remove workspace member: {}", e))?;
+
+    if result.rows_affected() > 0 {
+        Ok("Workspace member removed successfully".to_string())
+    } else {
+        Err("Workspace member not found".to_string())
+    }
+}
+
+/// Scopes subsequent user, settings, and log commands to a workspace the
+/// caller belongs to.
+///
+/// Passing `None` clears the selection so every record is visible again.
+#[tauri::command]
+pub async fn switch_workspace(
+    context: CommandContext,
+    workspace_id: Option<String>,
+) -> Result<Option<Workspace>, String> {
+    let workspace = match workspace_id {
+        Some(id) => {
+            let pool = get_pool_ref().map_err(|e| e.to_string())?;
+            let uuid = Uuid::parse_str(&id).map_err(|e| format!("Invalid UUID: {}", e))?;
+            require_workspace_role(&context, pool.as_ref(), uuid, &ROLES).await?;
+            let workspace = fetch_workspace(pool.as_ref(), uuid)
+                .await?
+                .ok_or_else(|| "Workspace not found".to_string())?;
+            Some(workspace)
+        }
+        None => None,
+    };
+
+    workspace::switch(workspace.as_ref().map(|w| w.id)).map_err(|e| e.to_string())?;
+    Ok(workspace)
+}
+
+/// Returns the workspace commands are currently scoped to.
+#[tauri::command]
+pub async fn get_current_workspace() -> Result<Option<Workspace>, String> {
+    let Some(workspace_id) = workspace::current() else {
+        return Ok(None);
+    };
+
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    fetch_workspace(pool.as_ref(), workspace_id).await
+}
+
+/// Returns settings for members of the current workspace (all settings when none is selected).
+/// Requires the admin role.
+#[tauri::command]
+pub async fn get_workspace_settings(context: CommandContext) -> Result<Vec<UserSettings>, String> {
+    context
+        .require_role(ADMIN_ROLE)
+        .map_err(|e| e.to_string())?;
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+
+    let query = sqlx::query_as!(
+        UserSettings,
+        r#"
+        SELECT id,
+               user_id,
+               theme AS "theme!",
+               language AS "language!",
+               notifications_enabled AS "notifications_enabled!",
+               settings_data AS "settings_data!",
+               created_at AS "created_at!",
+               updated_at AS "updated_at!"
+        FROM user_settings
+        WHERE $1::UUID IS NULL
+           OR user_id IN (SELECT user_id FROM workspace_members WHERE workspace_id = $1)
+        ORDER BY created_at
+        "#,
+        workspace::current(),
+    );
+    query_stats::timed(query.sql(), query.fetch_all(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to fetch settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::CommandContext;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::handlers::logs::get_logs;
+    use crate::handlers::users::get_all_users;
+    use crate::models::LogQuery;
+    use crate::test_support::{LogFactory, UserFactory};
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    #[tokio::test]
+    #[serial]
+    async fn switching_workspace_scopes_users_and_logs() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let outsider = UserFactory::new().insert().await?;
+        let acme = create_workspace(
+            CommandContext::internal(),
+            CreateWorkspace {
+                name: "Acme Corp".to_string(),
+                slug: None,
+                owner_id: None,
+            },
+        )
+        .await
+        .expect("workspace creation should succeed");
+        assert_eq!(acme.slug, "acme-corp");
+
+        let outsider_context = CommandContext::for_user(outsider.id, Vec::new());
+        let denied = switch_workspace(outsider_context, Some(acme.id.to_string())).await;
+        assert!(denied.is_err());
+        switch_workspace(CommandContext::internal(), Some(acme.id.to_string()))
+            .await
+            .expect("switching should succeed");
+        let log = LogFactory::new()
+            .message("scoped")
+            .with_new_user()
+            .insert()
+            .await?;
+
+        let users = get_all_users(CommandContext::internal())
+            .await
+            .expect("listing users should succeed");
+        assert_eq!(users.len(), 1);
+        assert_eq!(Some(users[0].id), log.user_id);
+        let logs = get_logs(
+            CommandContext::internal(),
+            LogQuery {
+                level: None,
+                user_id: None,
+                tags: None,
+                source: None,
+                limit: None,
+                offset: None,
+            },
+        )
+        .await
+        .expect("listing logs should succeed");
+        assert_eq!(logs.len(), 1);
+
+        switch_workspace(CommandContext::internal(), None)
+            .await
+            .expect("clearing should succeed");
+        let users = get_all_users(CommandContext::internal())
+            .await
+            .expect("listing users should succeed");
+        assert!(users.iter().any(|user| user.id == outsider.id));
+        assert_eq!(users.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn rejects_unknown_roles_and_workspaces() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let result = add_workspace_member(
+            CommandContext::internal(),
+            Uuid::new_v4().to_string(),
+            Uuid::new_v4().to_string(),
+            Some("superuser".to_string()),
+        )
+        .await;
+        assert!(matches!(result, Err(message) if message.starts_with("Invalid role")));
+
+        let switched =
+            switch_workspace(CommandContext::internal(), Some(Uuid::new_v4().to_string())).await;
+        assert!(matches!(switched, Err(message) if message == "Workspace not found"));
+        Ok(())
+    }
+}
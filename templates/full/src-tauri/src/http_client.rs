@@ -0,0 +1,297 @@
+//! Outbound HTTP client executed in Rust with retries, proxy support, and caching.
+//!
+//! Lets the frontend call external APIs without CORS workarounds. Successful GET
+//! responses can be cached through the cache module when a TTL is requested,
+//! unless the request carries credentials.
+//! Requests without an explicit proxy use the detected system proxy.
+
+use crate::cache;
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::proxy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Default request timeout when none is supplied.
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+/// Upper bound on retries to keep a single command from running indefinitely.
+const MAX_RETRIES: u32 = 5;
+/// Prefix for cached HTTP responses.
+const CACHE_PREFIX: &str = "http_cache:";
+/// Headers that carry credentials; responses to requests sending them are
+/// never cached.
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+/// Per-request options controlling timeouts, retries, proxying, and caching.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRequestOptions {
+    pub timeout_ms: Option<u64>,
+    pub retries: Option<u32>,
+    pub retry_backoff_ms: Option<u64>,
+    pub proxy: Option<String>,
+    pub cache_ttl_seconds: Option<u64>,
+}
+
+/// Response returned to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: serde_json::Value,
+    pub from_cache: bool,
+}
+
+/// Executes an HTTP request, retrying transient failures with exponential backoff.
+pub async fn execute(
+    method: &str,
+    url: &str,
+    headers: HashMap<String, String>,
+    body: Option<serde_json::Value>,
+    options: HttpRequestOptions,
+) -> AppResult<HttpResponse> {
+    let method =
+        reqwest::Method::from_bytes(method.trim().to_uppercase().as_bytes()).map_err(|_| {
+            AppError::invalid_input("method", format!("Unsupported HTTP method '{}'", method))
+        })?;
+
+    let parsed_url = reqwest::Url::parse(url)
+        .map_err(|e| AppError::invalid_input("url", format!("Invalid URL: {}", e)))?;
+    if !matches!(parsed_url.scheme(), "http" | "https") {
+        return Err(AppError::invalid_input(
+            "url",
+            "Only http and https URLs are supported",
+        ));
+    }
+
+    let cache_key = cache_key(&method, &parsed_url, &headers);
+    let cacheable = method == reqwest::Method::GET
+        && options.cache_ttl_seconds.is_some()
+        && !has_credentials(&headers);
+
+    if cacheable {
+        if let Ok(Some(mut cached)) = cache::get_cache::<HttpResponse>(&cache_key) {
+            cached.from_cache = true;
+            return Ok(cached);
+        }
+    }
+
+    let client = build_client(&options, &parsed_url)?;
+    let retries = options.retries.unwrap_or(0).min(MAX_RETRIES);
+    let backoff = Duration::from_millis(options.retry_backoff_ms.unwrap_or(250));
+
+    let mut attempt = 0;
+    let response = loop {
+        let mut request = client.request(method.clone(), parsed_url.clone());
+        for (name, value) in &headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        request = match &body {
+            Some(serde_json::Value::String(text)) => request.body(text.clone()),
+            Some(value) => request.json(value),
+            None => request,
+        };
+
+        match request.send().await {
+            Ok(response) if is_retryable_status(response.status()) && attempt < retries => {
+                tracing::debug!(
+                    "HTTP {} {} returned {}, retrying",
+                    method,
+                    parsed_url,
+                    response.status()
+                );
+            }
+            Ok(response) => break response,
+            Err(e) if attempt < retries && (e.is_timeout() || e.is_connect()) => {
+                tracing::debug!("HTTP {} {} failed: {}, retrying", method, parsed_url, e);
+            }
+            Err(e) => return Err(map_request_error(e)),
+        }
+
+        attempt += 1;
+        tokio::time::sleep(backoff * 2u32.pow(attempt - 1)).await;
+    };
+
+    let status = response.status();
+    let response_headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+
+    let text = response.text().await.map_err(|e| {
+        AppError::new(
+            ErrorCode::NetworkError,
+            format!("Failed to read response body: {}", e),
+        )
+    })?;
+    let body = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+
+    let result = HttpResponse {
+        status: status.as_u16(),
+        headers: response_headers,
+        body,
+        from_cache: false,
+    };
+
+    if cacheable && status.is_success() {
+        if let Err(e) = cache::set_cache(&cache_key, &result, options.cache_ttl_seconds) {
+            tracing::warn!("Failed to cache HTTP response for {}: {}", parsed_url, e);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Builds a client honoring the requested timeout and proxy, falling back to
+/// the detected system proxy for `url`.
+fn build_client(options: &HttpRequestOptions, url: &reqwest::Url) -> AppResult<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().timeout(Duration::from_millis(
+        options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+    ));
+
+    if let Some(proxy) = options.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+        let proxy = reqwest::Proxy::all(proxy)
+            .map_err(|e| AppError::invalid_input("proxy", format!("Invalid proxy URL: {}", e)))?;
+        builder = builder.proxy(proxy);
+    } else {
+        match proxy::proxy_for(url).map(|proxy| reqwest::Proxy::all(&proxy)) {
+            Some(Ok(proxy)) => builder = builder.proxy(proxy),
+            Some(Err(e)) => {
+                tracing::warn!("Ignoring invalid system proxy: {}", e);
+                builder = builder.no_proxy();
+            }
+            None => builder = builder.no_proxy(),
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| AppError::internal_error(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// Returns true for statuses worth retrying (rate limiting and server errors).
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn map_request_error(error: reqwest::Error) -> AppError {
+    if error.is_timeout() {
+        AppError::new(
+            ErrorCode::RequestTimeout,
+            format!("HTTP request timed out: {}", error),
+        )
+    } else {
+        AppError::new(
+            ErrorCode::NetworkError,
+            format!("HTTP request failed: {}", error),
+        )
+    }
+}
+
+/// Keys a cached response by method, URL, and a hash of the request headers,
+/// since headers such as `Accept` change the response.
+fn cache_key(
+    method: &reqwest::Method,
+    url: &reqwest::Url,
+    headers: &HashMap<String, String>,
+) -> String {
+    let mut headers: Vec<_> = headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.as_str()))
+        .collect();
+    headers.sort();
+
+    let mut hasher = Sha256::new();
+    for (name, value) in headers {
+        hasher.update(name.as_bytes());
+        hasher.update(b":");
+        hasher.update(value.as_bytes());
+        hasher.update(b"\n");
+    }
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("{}{}:{}:{}", CACHE_PREFIX, method, url, digest)
+}
+
+fn has_credentials(headers: &HashMap<String, String>) -> bool {
+    headers
+        .keys()
+        .any(|name| CREDENTIAL_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_only_transient_statuses() {
+        assert!(is_retryable_status(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(reqwest::StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable_status(reqwest::StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(reqwest::StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn rejects_non_http_schemes() {
+        let result = execute(
+            "GET",
+            "file:///etc/passwd",
+            HashMap::new(),
+            None,
+            HttpRequestOptions::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(e) if matches!(e.code, ErrorCode::InvalidInput)));
+    }
+
+    #[test]
+    fn cache_keys_depend_on_headers_but_not_their_order_or_case() {
+        let url = reqwest::Url::parse("https://example.com/items").unwrap();
+        let headers = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        let key = |pairs: &[(&str, &str)]| cache_key(&reqwest::Method::GET, &url, &headers(pairs));
+
+        assert_eq!(
+            key(&[("Accept", "application/json"), ("X-Tenant", "a")]),
+            key(&[("x-tenant", "a"), ("accept", "application/json")])
+        );
+        assert_ne!(key(&[("X-Tenant", "a")]), key(&[("X-Tenant", "b")]));
+        assert_ne!(key(&[]), key(&[("Accept", "text/html")]));
+    }
+
+    #[test]
+    fn credentialed_requests_are_detected() {
+        let mut headers = HashMap::from([("Accept".to_string(), "*/*".to_string())]);
+        assert!(!has_credentials(&headers));
+        headers.insert("COOKIE".to_string(), "session=1".to_string());
+        assert!(has_credentials(&headers));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_methods() {
+        let result = execute(
+            "NOT A METHOD",
+            "https://example.com",
+            HashMap::new(),
+            None,
+            HttpRequestOptions::default(),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! EXIF inspection and metadata stripping for images in the filesystem scope.
+//!
+//! Stripping works on the container without re-encoding, so pixel data is
+//! untouched. JPEG files lose their EXIF/XMP (APP1), IPTC (APP13), and
+//! comment segments; PNG files lose their `eXIf`, text, and timestamp
+//! chunks. Color profiles are kept. Because the EXIF orientation tag goes
+//! too, viewers show the image in its stored orientation afterwards.
+
+use crate::errors::{AppError, AppResult};
+use crate::handlers::filesystem::{
+    ensure_write_permitted, resolve_existing_path, resolve_relative_path,
+};
+use serde::Serialize;
+use std::fs;
+use std::io::Cursor;
+
+/// Largest image read into memory.
+const MAX_IMAGE_BYTES: u64 = 100 * 1024 * 1024;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// PNG chunks carrying metadata rather than image data.
+const PNG_METADATA_CHUNKS: [&[u8; 4]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// Image container formats metadata can be stripped from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+}
+
+/// One EXIF field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExifField {
+    pub tag: String,
+    /// IFD the field came from, such as `primary` or `thumbnail`.
+    pub ifd: String,
+    pub value: String,
+}
+
+/// Metadata found in an image.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMetadata {
+    pub path: String,
+    pub format: ImageFormat,
+    pub fields: Vec<ExifField>,
+    /// Whether the image records where it was taken.
+    pub has_location: bool,
+}
+
+/// Result of stripping an image.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StripSummary {
+    pub path: String,
+    pub format: ImageFormat,
+    /// Names of the removed segments or chunks, in file order.
+    pub removed: Vec<String>,
+    pub bytes_removed: u64,
+}
+
+/// Reads the EXIF fields of the image at `path`.
+pub fn inspect(path: &str) -> AppResult<ImageMetadata> {
+    let (display, bytes) = read_image(path)?;
+    let format = detect_format(&bytes)?;
+
+    let fields = match exif::Reader::new().read_from_container(&mut Cursor::new(&bytes)) {
+        Ok(exif) => exif
+            .fields()
+            .map(|field| ExifField {
+                tag: field.tag.to_string(),
+                ifd: field.ifd_num.to_string(),
+                value: field.display_value().with_unit(&exif).to_string(),
+            })
+            .collect(),
+        Err(exif::Error::NotFound(_)) => Vec::new(),
+        Err(e) => {
+            return Err(AppError::invalid_input(
+                "path",
+                format!("Invalid EXIF data in {}: {}", display, e),
+            ))
+        }
+    };
+    let has_location = fields.iter().any(|field| field.tag.starts_with("GPS"));
+
+    Ok(ImageMetadata {
+        path: display,
+        format,
+        fields,
+        has_location,
+    })
+}
+
+/// Removes metadata from the image at `path`, writing the result to
+/// `destination` or replacing the original.
+pub fn strip(path: &str, destination: Option<&str>) -> AppResult<StripSummary> {
+    let (display, bytes) = read_image(path)?;
+    let format = detect_format(&bytes)?;
+    let (stripped, removed) = match format {
+        ImageFormat::Jpeg => strip_jpeg(&bytes),
+        ImageFormat::Png => strip_png(&bytes),
+    }
+    .map_err(|message| AppError::invalid_input("path", format!("{}: {}", display, message)))?;
+
+    let target = match destination {
+        Some(destination) => resolve_relative_path(destination)
+            .map_err(|e| AppError::file_error("write", destination, e))?,
+        None => resolve_existing_path(path).map_err(|e| AppError::file_error("write", path, e))?,
+    };
+    ensure_write_permitted(&target.path)?;
+    let target_display = target.relative_display();
+    if let Some(parent) = target.path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::file_error("create", target_display.clone(), e.to_string()))?;
+    }
+    // Write beside the target first so a failure never leaves a torn image.
+    let partial = target.path.with_extension("strip-part");
+    fs::write(&partial, &stripped)
+        .and_then(|_| fs::rename(&partial, &target.path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&partial);
+            AppError::file_error("write", target_display.clone(), e.to_string())
+        })?;
+
+    tracing::debug!(
+        "Stripped {} metadata segments from {}",
+        removed.len(),
+        display
+    );
+    Ok(StripSummary {
+        path: target_display,
+        format,
+        removed,
+        bytes_removed: (bytes.len() - stripped.len()) as u64,
+    })
+}
+
+fn read_image(path: &str) -> AppResult<(String, Vec<u8>)> {
+    let context = resolve_existing_path(path).map_err(|e| AppError::file_error("read", path, e))?;
+    let display = context.relative_display();
+    let size = fs::metadata(&context.path)
+        .map_err(|e| AppError::file_error("read", display.clone(), e.to_string()))?
+        .len();
+    if size > MAX_IMAGE_BYTES {
+        return Err(AppError::invalid_input("path", "Image is too large"));
+    }
+    let bytes = fs::read(&context.path)
+        .map_err(|e| AppError::file_error("read", display.clone(), e.to_string()))?;
+    Ok((display, bytes))
+}
+
+fn detect_format(bytes: &[u8]) -> AppResult<ImageFormat> {
+    if bytes.starts_with(&[0xff, 0xd8]) {
+        Ok(ImageFormat::Jpeg)
+    } else if bytes.starts_with(&PNG_SIGNATURE) {
+        Ok(ImageFormat::Png)
+    } else {
+        Err(AppError::invalid_input(
+            "path",
+            "Only JPEG and PNG images are supported",
+        ))
+    }
+}
+
+/// Copies a JPEG, dropping APP1, APP13, and comment segments.
+fn strip_jpeg(bytes: &[u8]) -> Result<(Vec<u8>, Vec<String>), String> {
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut removed = Vec::new();
+    output.extend_from_slice(&bytes[..2]);
+    let mut pos = 2;
+
+    loop {
+        let start = pos;
+        if bytes.get(pos) != Some(&0xff) {
+            return Err("Corrupt JPEG marker".to_string());
+        }
+        while bytes.get(pos) == Some(&0xff) {
+            pos += 1;
+        }
+        let marker = *bytes.get(pos).ok_or("Truncated JPEG")?;
+        pos += 1;
+
+        match marker {
+            // Start of scan and end of image: the rest is image data.
+            0xda | 0xd9 => {
+                output.extend_from_slice(&bytes[start..]);
+                break;
+            }
+            0x01 | 0xd0..=0xd7 => {
+                output.extend_from_slice(&bytes[start..pos]);
+                continue;
+            }
+            _ => {}
+        }
+
+        let length = bytes
+            .get(pos..pos + 2)
+            .map(|length| u16::from_be_bytes([length[0], length[1]]) as usize)
+            .filter(|length| *length >= 2)
+            .ok_or("Truncated JPEG segment")?;
+        let end = pos + length;
+        if end > bytes.len() {
+            return Err("Truncated JPEG segment".to_string());
+        }
+        match marker {
+            0xe1 => removed.push("APP1".to_string()),
+            0xed => removed.push("APP13".to_string()),
+            0xfe => removed.push("COM".to_string()),
+            _ => output.extend_from_slice(&bytes[start..end]),
+        }
+        pos = end;
+    }
+
+    Ok((output, removed))
+}
+
+/// Copies a PNG, dropping metadata chunks.
+fn strip_png(bytes: &[u8]) -> Result<(Vec<u8>, Vec<String>), String> {
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut removed = Vec::new();
+    output.extend_from_slice(&PNG_SIGNATURE);
+    let mut pos = PNG_SIGNATURE.len();
+
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or("Truncated PNG chunk")?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = &header[4..8];
+        // Length, type, data, and CRC.
+        let end = pos + 12 + length;
+        if end > bytes.len() {
+            return Err("Truncated PNG chunk".to_string());
+        }
+        if PNG_METADATA_CHUNKS.iter().any(|chunk| &chunk[..] == kind) {
+            removed.push(String::from_utf8_lossy(kind).into_owned());
+        } else {
+            output.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+        if kind == b"IEND" {
+            break;
+        }
+    }
+
+    Ok((output, removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xff, marker];
+        segment.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(&[0, 0, 0, 0]);
+        chunk
+    }
+
+    #[test]
+    fn strips_jpeg_metadata_segments() {
+        let jfif = segment(0xe0, b"JFIF\0");
+        let scan = [0xff, 0xda, 0x00, 0x02, 0x12, 0x34, 0xff, 0xd9];
+        let jpeg = [
+            vec![0xff, 0xd8],
+            jfif.clone(),
+            segment(0xe1, b"Exif\0\0GPS"),
+            segment(0xfe, b"comment"),
+            scan.to_vec(),
+        ]
+        .concat();
+
+        let (stripped, removed) = strip_jpeg(&jpeg).unwrap();
+        assert_eq!(removed, ["APP1", "COM"]);
+        assert_eq!(stripped, [vec![0xff, 0xd8], jfif, scan.to_vec()].concat());
+        assert!(strip_jpeg(&jpeg[..jpeg.len() - 12]).is_err());
+    }
+
+    #[test]
+    fn strips_png_metadata_chunks() {
+        let ihdr = chunk(b"IHDR", &[0; 13]);
+        let idat = chunk(b"IDAT", &[1, 2, 3]);
+        let iend = chunk(b"IEND", &[]);
+        let png = [
+            PNG_SIGNATURE.to_vec(),
+            ihdr.clone(),
+            chunk(b"tEXt", b"Author\0me"),
+            chunk(b"eXIf", b"MM\0*"),
+            idat.clone(),
+            iend.clone(),
+        ]
+        .concat();
+
+        let (stripped, removed) = strip_png(&png).unwrap();
+        assert_eq!(removed, ["tEXt", "eXIf"]);
+        assert_eq!(
+            stripped,
+            [PNG_SIGNATURE.to_vec(), ihdr, idat, iend].concat()
+        );
+        assert!(strip_png(&png[..png.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn detects_supported_formats() {
+        assert_eq!(
+            detect_format(&[0xff, 0xd8, 0xff]).unwrap(),
+            ImageFormat::Jpeg
+        );
+        assert_eq!(detect_format(&PNG_SIGNATURE).unwrap(), ImageFormat::Png);
+        assert!(detect_format(b"GIF89a").is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! Development-only inspector for Tauri IPC command invocations.
+//!
+//! Every invocation passing through the command registry is captured with
+//! redacted arguments; the rate-limited wrappers complete the record with its
+//! duration and result. Records are kept in a ring buffer and streamed on the
+//! event bus as `inspector:invocation`. Release builds record nothing.
+
+use crate::events::{self, AppEvent};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Number of invocations kept in the ring buffer.
+const CAPACITY: usize = 500;
+
+/// Argument keys whose values are replaced before recording.
+const REDACTED_KEYS: &[&str] = &["password", "token", "secret", "authorization", "apikey", "api_key", "credential"];
+
+/// Inspector commands themselves, excluded to keep the log readable.
+const IGNORED_COMMANDS: &[&str] = &["rl_get_recent_invocations", "rl_clear_recent_invocations"];
+
+/// Placeholder stored in place of redacted values.
+const REDACTED: &str = "[REDACTED]";
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Recent invocations, oldest first.
+static RECORDS: Lazy<Mutex<VecDeque<InvocationRecord>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(CAPACITY)));
+
+/// In-flight invocations per command awaiting completion, oldest first.
+static IN_FLIGHT: Lazy<Mutex<HashMap<String, VecDeque<(u64, Instant)>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Outcome of a recorded invocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InvocationStatus {
+    /// Handed to a handler that does not report completion.
+    Dispatched,
+    Pending,
+    Ok,
+    Error,
+}
+
+/// A single captured command invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvocationRecord {
+    pub id: u64,
+    pub command: String,
+    pub args: serde_json::Value,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: Option<f64>,
+    pub status: InvocationStatus,
+    pub error: Option<String>,
+}
+
+/// Returns true when invocations are being recorded.
+pub fn enabled() -> bool {
+    cfg!(debug_assertions)
+}
+
+/// Captures the start of an invocation.
+///
+/// `tracked` commands report completion through [`finish`]; any other command
+/// is recorded as dispatched immediately.
+pub fn begin(command: &str, args: Option<&serde_json::Value>, tracked: bool) {
+    if !enabled() || IGNORED_COMMANDS.contains(&command) {
+        return;
+    }
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let record = InvocationRecord {
+        id,
+        command: command.to_string(),
+        args: args.map(redact).unwrap_or(serde_json::Value::Null),
+        started_at: Utc::now(),
+        duration_ms: None,
+        status: if tracked {
+            InvocationStatus::Pending
+        } else {
+            InvocationStatus::Dispatched
+        },
+        error: None,
+    };
+
+    if tracked {
+        if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+            in_flight
+                .entry(command.to_string())
+                .or_default()
+                .push_back((id, Instant::now()));
+        }
+    }
+
+    push_record(record.clone());
+    if !tracked {
+        events::publish(AppEvent::CommandInvoked(record));
+    }
+}
+
+/// Completes the oldest in-flight invocation of `command` with its result.
+pub fn finish(command: &str, error: Option<String>) {
+    if !enabled() {
+        return;
+    }
+
+    let entry = IN_FLIGHT.lock().ok().and_then(|mut in_flight| {
+        let queue = in_flight.get_mut(command)?;
+        let entry = queue.pop_front();
+        if queue.is_empty() {
+            in_flight.remove(command);
+        }
+        entry
+    });
+
+    let Some((id, started)) = entry else {
+        return;
+    };
+
+    let updated = RECORDS.lock().ok().and_then(|mut records| {
+        let record = records.iter_mut().find(|record| record.id == id)?;
+        record.duration_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+        record.status = if error.is_some() {
+            InvocationStatus::Error
+        } else {
+            InvocationStatus::Ok
+        };
+        record.error = error;
+        Some(record.clone())
+    });
+
+    if let Some(record) = updated {
+        events::publish(AppEvent::CommandInvoked(record));
+    }
+}
+
+/// Returns up to `limit` of the most recent invocations, newest first.
+pub fn recent(limit: Option<usize>) -> Vec<InvocationRecord> {
+    let records = match RECORDS.lock() {
+        Ok(records) => records,
+        Err(_) => return Vec::new(),
+    };
+
+    records
+        .iter()
+        .rev()
+        .take(limit.unwrap_or(CAPACITY))
+        .cloned()
+        .collect()
+}
+
+/// Clears recorded invocations.
+pub fn clear() {
+    if let Ok(mut records) = RECORDS.lock() {
+        records.clear();
+    }
+}
+
+fn push_record(record: InvocationRecord) {
+    if let Ok(mut records) = RECORDS.lock() {
+        if records.len() == CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+/// Replaces values of sensitive keys at any depth.
+fn redact(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let lowered = key.to_lowercase();
+                if REDACTED_KEYS.iter().any(|sensitive| lowered.contains(sensitive)) {
+                    (key.clone(), serde_json::Value::String(REDACTED.to_string()))
+                } else {
+                    (key.clone(), redact(value))
+                }
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(redact).collect(),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redacts_sensitive_keys_recursively() {
+        let args = json!({
+            "loginData": { "username": "ada", "password": "hunter2" },
+            "headers": [{ "Authorization": "Bearer x" }],
+            "apiKey": "k",
+        });
+
+        let redacted = redact(&args);
+        assert_eq!(redacted["loginData"]["username"], "ada");
+        assert_eq!(redacted["loginData"]["password"], REDACTED);
+        assert_eq!(redacted["headers"][0]["Authorization"], REDACTED);
+        assert_eq!(redacted["apiKey"], REDACTED);
+    }
+
+    #[test]
+    fn tracked_invocations_complete_with_result() {
+        begin("inspector_test_command", Some(&json!({ "value": 1 })), true);
+        finish("inspector_test_command", Some("boom".to_string()));
+
+        let record = recent(None)
+            .into_iter()
+            .find(|record| record.command == "inspector_test_command")
+            .unwrap();
+        assert_eq!(record.status, InvocationStatus::Error);
+        assert_eq!(record.error.as_deref(), Some("boom"));
+        assert!(record.duration_ms.is_some());
+    }
+}
@@ -1,155 +1,772 @@
 //! Tauri application library with comprehensive feature set including database management,
 //! rate limiting, caching, and secure user authentication.
 
+#[cfg(feature = "stronghold")]
 pub mod stronghold;
+#[cfg(feature = "database")]
+mod audit;
+#[cfg(any(feature = "database", feature = "sqlite"))]
+mod auth;
+#[cfg(feature = "database")]
+mod backup;
+mod build_info;
 mod cache;
+mod clipboard;
+mod clipboard_image;
+mod command_context;
+mod command_trace;
+mod companion;
 mod config;
+mod credential_store;
+mod csv_io;
+#[cfg(feature = "database")]
 mod database;
+mod discovery;
+mod email;
+#[cfg(feature = "database")]
+mod email_verification;
 mod errors;
+mod events;
+mod feedback;
+mod file_drop;
+mod file_open;
+mod fonts;
 mod handlers;
+mod http_client;
+mod image_metadata;
+mod inspector;
+#[cfg(feature = "sqlite")]
+mod local_db;
+mod locale;
 mod logging;
+mod mail;
+mod maintenance;
+mod media_protocol;
+mod metrics;
+#[cfg(any(feature = "database", feature = "sqlite"))]
 mod models;
+mod operations;
+#[cfg(any(feature = "database", feature = "sqlite"))]
+mod password;
+mod pdf;
+#[cfg(feature = "database")]
+mod portability;
+#[cfg(feature = "database")]
+mod privacy;
+mod proxy;
 mod rate_limiter;
-#[cfg(test)]
+#[cfg(all(test, feature = "rate-limiter"))]
 mod rate_limiter_test;
+pub mod registry;
+#[cfg(any(feature = "database", feature = "sqlite"))]
+mod repository;
+mod scripting;
+#[cfg(feature = "database")]
+mod search;
+mod secrets;
+mod server;
+#[cfg(any(feature = "database", feature = "sqlite"))]
+mod session;
+mod setup;
+mod shutdown;
+mod startup;
+mod state_store;
+mod storage_devices;
+#[cfg(feature = "database")]
+mod sync;
+mod telemetry;
+#[cfg(test)]
+mod test_harness;
+#[cfg(all(test, feature = "database"))]
+mod test_support;
+mod themes;
+mod transfer;
+mod updater;
 mod validation;
+#[cfg(feature = "database")]
+mod workspace;
 
-use config::AppConfig;
+#[cfg(any(feature = "database", feature = "sqlite"))]
+use config::DatabaseBackend;
+use events::AppEvent;
 use handlers::*;
 use rate_limiter::RateLimiterConfig;
+use registry::CommandRegistry;
+use startup::StartupTask;
 use std::sync::Arc;
-use tauri::Manager;
+use tauri::{Manager, Wry};
+
+pub use config::AppConfig;
+pub use registry::{CommandModule, RateLimitClass};
+
+/// Deferred `tauri::Builder::plugin` call.
+type PluginHook = Box<dyn FnOnce(tauri::Builder<Wry>) -> tauri::Builder<Wry>>;
+
+/// Setup step run after the built-in setup.
+type SetupHook =
+    Box<dyn FnOnce(&mut tauri::App<Wry>) -> Result<(), Box<dyn std::error::Error>> + Send>;
 
 /// Basic greeting command for testing Tauri functionality.
+///
+/// A demo command: release builds leave it out unless the `demo` feature is
+/// enabled.
+#[cfg(any(debug_assertions, feature = "demo"))]
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-/// Initializes and runs the Tauri application with all configured plugins and handlers.
+/// Composes the application so downstream crates can use this one as a
+/// library, adding plugins, commands, setup steps, and configuration
+/// overrides without copying the built-in setup.
 ///
-/// Sets up the application with:
-/// - File system, dialog, notification, and shell plugins
-/// - Database connection and migrations
-/// - Rate limiting for all commands
-/// - Comprehensive error handling and logging
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_notification::init())
-        .plugin(tauri_plugin_window_state::Builder::default().build())
-        .plugin(tauri_plugin_os::init())
-        .plugin(tauri_plugin_shell::init())
-        .plugin(tauri_plugin_stronghold::Builder::new(|password| {
-            use argon2::{Algorithm, Argon2, Params, Version};
-            let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default());
-            let salt = &[0; 32];
-            let mut output = [0u8; 32];
-            argon2.hash_password_into(password.as_bytes(), salt, &mut output)
-                .expect("failed to hash password");
-            output.to_vec()
-        }).build())
-        .setup(|app| {
-            let config = AppConfig::from_env();
-            tracing::info!("App environment: {:?}", config.environment);
-
-            let rate_limiter = Arc::new(RateLimiterConfig::new());
-            app.manage(rate_limiter.clone());
-            tracing::info!("Rate limiter initialized successfully");
-
-            if let Err(e) = logging::init_logging_from_env() {
-                eprintln!("Failed to initialize logging: {}", e);
-            } else {
-                tracing::info!("Logging system initialized successfully");
-            }
-
-            if let Err(e) = cache::initialize_redis() {
-                tracing::warn!("Failed to initialize Redis: {}. Continuing without caching.", e);
-            }
-
-            tauri::async_runtime::spawn(async move {
-                match database::create_pool().await {
-                    Ok(pool) => {
-                        database::connection::initialize_pool(pool).await;
-                        tracing::info!("Database initialized successfully");
-
-                        if let Ok(pool) = database::get_pool_ref() {
-                            if let Err(e) = database::migrations::run_migrations(pool.as_ref()).await {
-                                tracing::error!("Failed to run migrations: {}", e);
-                            } else {
-                                tracing::info!("Migrations completed successfully");
-                            }
+/// ```ignore
+/// ez_tauri_lib::EzTauriBuilder::new()
+///     .with_config(|config| config.file_drop_copy = true)
+///     .with_plugin(tauri_plugin_store::Builder::new().build())
+///     .with_module(ez_tauri_lib::command_module!("billing", [create_invoice]))
+///     .with_setup(|app| {
+///         app.manage(BillingState::default());
+///         Ok(())
+///     })
+///     .run_with_context(tauri::generate_context!());
+/// ```
+#[derive(Default)]
+pub struct EzTauriBuilder {
+    registry: CommandRegistry,
+    plugins: Vec<PluginHook>,
+    setup_hooks: Vec<SetupHook>,
+}
+
+/// Former name of [`EzTauriBuilder`].
+#[deprecated(note = "renamed to EzTauriBuilder")]
+pub type EzTauriApp = EzTauriBuilder;
+
+impl EzTauriBuilder {
+    /// Creates an application with only the built-in plugins and commands.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an additional command module alongside the built-in
+    /// commands. Module commands take precedence over built-ins of the same
+    /// name; see [`command_module!`].
+    pub fn with_module(mut self, module: CommandModule) -> Self {
+        tracing::debug!("Registering command module '{}'", module.name());
+        self.registry.register(module);
+        self
+    }
+
+    /// Adds a Tauri plugin after the built-in ones.
+    pub fn with_plugin<P>(mut self, plugin: P) -> Self
+    where
+        P: tauri::plugin::Plugin<Wry> + 'static,
+    {
+        self.plugins.push(Box::new(move |builder| builder.plugin(plugin)));
+        self
+    }
+
+    /// Runs `setup` once the built-in setup has finished, e.g. to manage
+    /// state used by module commands. An error aborts startup.
+    pub fn with_setup<F>(mut self, setup: F) -> Self
+    where
+        F: FnOnce(&mut tauri::App<Wry>) -> Result<(), Box<dyn std::error::Error>>
+            + Send
+            + 'static,
+    {
+        self.setup_hooks.push(Box::new(setup));
+        self
+    }
+
+    /// Adjusts the configuration read from the environment. The override
+    /// applies immediately and is reapplied whenever the configuration is
+    /// reloaded.
+    pub fn with_config<F>(self, config_override: F) -> Self
+    where
+        F: Fn(&mut AppConfig) + Send + Sync + 'static,
+    {
+        config::add_override(Box::new(config_override));
+        self
+    }
+
+    /// Initializes and runs the Tauri application with all configured plugins and handlers.
+    ///
+    /// Sets up the application with:
+    /// - File system, dialog, notification, and shell plugins
+    /// - Database connection and migrations for the backend `DATABASE_URL`
+    ///   selects (`database` feature for PostgreSQL, `sqlite` for SQLite)
+    /// - Rate limiting for all commands (`rate-limiter` feature)
+    /// - Comprehensive error handling and logging
+    pub fn run(self) {
+        self.run_with_context(tauri::generate_context!())
+    }
+
+    /// Runs the application with a context generated by the calling crate,
+    /// so its own `tauri.conf.json` (identifier, windows, icons) is used.
+    pub fn run_with_context(self, context: tauri::Context<Wry>) {
+        let Self {
+            registry,
+            plugins,
+            setup_hooks,
+        } = self;
+
+        let builder = tauri::Builder::default()
+            // Must be registered first so a second launch exits before initializing anything.
+            .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+                file_open::open_args(args, std::path::Path::new(&cwd), file_open::OpenSource::Running);
+                if let Some(window) = app.webview_windows().values().next() {
+                    let _ = window.unminimize();
+                    let _ = window.set_focus();
+                }
+            }))
+            .plugin(tauri_plugin_opener::init())
+            .plugin(tauri_plugin_fs::init())
+            .plugin(tauri_plugin_dialog::init())
+            .plugin(tauri_plugin_notification::init())
+            .plugin(tauri_plugin_window_state::Builder::default().build())
+            .plugin(tauri_plugin_os::init())
+            .plugin(tauri_plugin_shell::init())
+            .plugin(tauri_plugin_updater::Builder::new().build())
+            .plugin(tauri_plugin_clipboard_manager::init());
+        #[cfg(feature = "stronghold")]
+        let builder =
+            builder.plugin(tauri_plugin_stronghold::Builder::new(stronghold::derive_key).build());
+        let builder = plugins.into_iter().fold(builder, |builder, plugin| plugin(builder));
+
+        builder
+            .register_asynchronous_uri_scheme_protocol(
+                media_protocol::SCHEME,
+                |_ctx, request, responder| media_protocol::respond(request, responder),
+            )
+            .on_window_event(|window, event| {
+                if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                    file_drop::handle_drop(window.label(), paths.clone());
+                }
+            })
+            .setup(move |app| {
+                let config = config::current();
+                tracing::info!("App environment: {:?}", config.environment);
+
+                events::start_dispatcher(app.handle().clone());
+                file_open::init(app.config().bundle.file_associations.as_deref());
+                file_open::open_args(
+                    std::env::args(),
+                    &std::env::current_dir().unwrap_or_default(),
+                    file_open::OpenSource::Launch,
+                );
+                email::start_worker();
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                session::start_reaper();
+                #[cfg(feature = "database")]
+                if config.database_backend == DatabaseBackend::Postgres {
+                    logging::db_sink::start_flusher();
+                    logging::retention::start_scheduler();
+                    privacy::start_deletion_scheduler();
+                }
+                tauri::async_runtime::spawn_blocking(proxy::refresh);
+                scripting::start_scheduler();
+                storage_devices::start_watcher();
+
+                let rate_limiter = Arc::new(RateLimiterConfig::new());
+                app.manage(rate_limiter.clone());
+                tracing::info!("Rate limiter initialized successfully");
+
+                if let Err(e) = logging::init_logging_from_env() {
+                    eprintln!("Failed to initialize logging: {}", e);
+                } else {
+                    tracing::info!("Logging system initialized successfully");
+                }
+
+                match setup::load_state().map(|state| state.current_step()) {
+                    Ok(Some(step)) => tracing::info!("First-run setup pending at step {:?}", step),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Failed to read setup state: {}", e),
+                }
+
+                #[cfg(feature = "database")]
+                match workspace::restore() {
+                    Ok(Some(workspace_id)) => tracing::info!("Restored workspace {}", workspace_id),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Failed to restore workspace selection: {}", e),
+                }
+
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                match password::restore_tuning() {
+                    Ok(Some(cost)) => tracing::info!("Restored password hashing cost {:?}", cost),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Failed to restore password hashing cost: {}", e),
+                }
+
+                let mut tasks = vec![StartupTask::new("cache", || async {
+                    cache::initialize_redis().map_err(|e| e.to_string())
+                })
+                .on_failure(|error| {
+                    tracing::warn!("Continuing without caching");
+                    events::publish(AppEvent::CacheUnavailable { error: error.to_string() });
+                })];
+
+                if let Some(port) = config.local_server_port {
+                    tasks.push(StartupTask::new("local-server", move || async move {
+                        server::start_server(port).await.map(|_| ()).map_err(|e| e.to_string())
+                    }));
+                }
+
+                if let Some(port) = config.companion_server_port {
+                    let app_handle = app.handle().clone();
+                    let lan = config.companion_server_lan;
+                    tasks.push(StartupTask::new("companion-server", move || {
+                        let app_handle = app_handle.clone();
+                        async move {
+                            companion::start_server(app_handle, port, lan)
+                                .await
+                                .map(|_| ())
+                                .map_err(|e| e.to_string())
                         }
+                    }));
+                }
+
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                match config.database_backend {
+                    #[cfg(feature = "database")]
+                    DatabaseBackend::Postgres => {
+                        let notify_channels = config.notify_channels.clone();
+                        tasks.push(
+                            StartupTask::new("database", || async {
+                                let pool = database::create_pool().await.map_err(|e| e.to_string())?;
+                                database::connection::initialize_pool(pool).await;
+                                tracing::info!("Database initialized successfully");
+                                events::publish(AppEvent::DatabaseConnected);
+                                Ok(())
+                            })
+                            // The database server may still be starting alongside the app.
+                            .retry(3, std::time::Duration::from_secs(2))
+                            .on_failure(|error| {
+                                events::publish(AppEvent::DatabaseUnavailable { error: error.to_string() });
+                            }),
+                        );
+                        tasks.push(
+                            StartupTask::new("migrations", || async {
+                                let pool = database::get_pool_ref().map_err(|e| e.to_string())?;
+                                let result = database::migrations::run_migrations(pool.as_ref()).await;
+                                if result.is_ok() {
+                                    tracing::info!("Migrations completed successfully");
+                                }
+                                events::publish(AppEvent::JobFinished {
+                                    job: "migrations".to_string(),
+                                    success: result.is_ok(),
+                                    message: result.as_ref().err().map(|e| e.to_string()),
+                                });
+                                result.map_err(|e| e.to_string())
+                            })
+                            .after("database")
+                            .timeout(std::time::Duration::from_secs(600)),
+                        );
+                        tasks.push(
+                            StartupTask::new("log-partitions", || async {
+                                let pool = database::get_pool_ref().map_err(|e| e.to_string())?;
+                                logging::partitions::ensure_partitions(pool.as_ref())
+                                    .await
+                                    .map(|_| ())
+                                    .map_err(|e| e.to_string())
+                            })
+                            .after("migrations"),
+                        );
+                        tasks.push(
+                            StartupTask::new("admin-bootstrap", || async {
+                                handlers::users::bootstrap_admin().await
+                            })
+                            .after("migrations"),
+                        );
+                        tasks.push(
+                            StartupTask::new("search-indexer", || async {
+                                search::start_indexer();
+                                Ok(())
+                            })
+                            .after("migrations"),
+                        );
+                        tasks.push(
+                            StartupTask::new("db-listener", move || {
+                                let channels = notify_channels.clone();
+                                async move {
+                                    database::listener::start_listener(channels);
+                                    Ok(())
+                                }
+                            })
+                            .after("database"),
+                        );
                     }
-                    Err(e) => {
-                        tracing::error!("Failed to initialize database: {}", e);
+                    #[cfg(feature = "sqlite")]
+                    DatabaseBackend::Sqlite => {
+                        tasks.push(
+                            StartupTask::new("database", || async {
+                                local_db::initialize().await.map_err(|e| e.to_string())?;
+                                tracing::info!("Database initialized successfully");
+                                events::publish(AppEvent::DatabaseConnected);
+                                Ok(())
+                            })
+                            .on_failure(|error| {
+                                events::publish(AppEvent::DatabaseUnavailable { error: error.to_string() });
+                            }),
+                        );
+                        tasks.push(
+                            StartupTask::new("admin-bootstrap", || async {
+                                handlers::local_db::bootstrap_admin().await
+                            })
+                            .after("database"),
+                        );
                     }
+                    #[allow(unreachable_patterns)]
+                    backend => tasks.push(
+                        StartupTask::new("database", move || async move {
+                            Err(format!(
+                                "DATABASE_URL selects {}, which this build does not include; enable the `{}` feature",
+                                backend.name(),
+                                backend.feature()
+                            ))
+                        })
+                        .on_failure(|error| {
+                            events::publish(AppEvent::DatabaseUnavailable { error: error.to_string() });
+                        }),
+                    ),
                 }
-            });
 
-            let rate_limiter_cleanup = rate_limiter.clone();
-            tauri::async_runtime::spawn(async move {
-                let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
-                loop {
-                    interval.tick().await;
-                    rate_limiter_cleanup.cleanup_old_limiters();
-                    tracing::debug!("Cleaned up old rate limiters");
+                startup::run(tasks);
+
+                let rate_limiter_cleanup = rate_limiter.clone();
+                let task = tauri::async_runtime::spawn(async move {
+                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+                    loop {
+                        interval.tick().await;
+                        rate_limiter_cleanup.cleanup_old_limiters();
+                        tracing::debug!("Cleaned up old rate limiters");
+                    }
+                });
+                shutdown::track("rate-limiter-cleanup", task);
+
+                #[cfg(feature = "database")]
+                if config.database_backend == DatabaseBackend::Postgres && config.sync_endpoint.is_some() {
+                    let sync_interval = config.sync_interval_seconds;
+                    let task = tauri::async_runtime::spawn(async move {
+                        let mut interval = tokio::time::interval(std::time::Duration::from_secs(sync_interval));
+                        loop {
+                            interval.tick().await;
+                            if let Err(e) = sync::sync_now().await {
+                                tracing::debug!("Background sync skipped: {}", e);
+                            }
+                        }
+                    });
+                    shutdown::track("sync", task);
                 }
+
+                #[cfg(feature = "database")]
+                if let Some(hours) = config
+                    .backup_interval_hours
+                    .filter(|_| config.database_backend == DatabaseBackend::Postgres)
+                {
+                    let retention = config.backup_retention;
+                    let version = app.package_info().version.to_string();
+                    let vault_path = backup::vault_path(app.handle());
+                    let task = tauri::async_runtime::spawn(async move {
+                        let mut interval = tokio::time::interval(std::time::Duration::from_secs(hours * 3600));
+                        // The first tick fires immediately, before the database is ready.
+                        interval.tick().await;
+                        loop {
+                            interval.tick().await;
+                            if let Err(e) = backup::create_backup(&version, vault_path.as_deref(), retention).await {
+                                tracing::warn!("Scheduled backup failed: {}", e);
+                            }
+                        }
+                    });
+                    shutdown::track("scheduled-backups", task);
+                }
+
+                let task = tauri::async_runtime::spawn(async move {
+                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(900));
+                    loop {
+                        interval.tick().await;
+                        match telemetry::upload_batch().await {
+                            Ok(0) => {}
+                            Ok(count) => tracing::debug!("Uploaded {} telemetry events", count),
+                            Err(e) => tracing::debug!("Telemetry upload skipped: {}", e),
+                        }
+                    }
+                });
+                shutdown::track("telemetry-upload", task);
+
+                for setup in setup_hooks {
+                    setup(app)?;
+                }
+
+                Ok(())
+            })
+            .invoke_handler(registry.into_handler(registry::builtin_handler![
+                #[cfg(any(debug_assertions, feature = "demo"))]
+                rl_greet,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_check_database_connection,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_initialize_database,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_run_migrations,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_get_all_users,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_search_users,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_get_user_by_id,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_create_user,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_update_user,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_delete_user,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_authenticate_user,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_change_password,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_create_guest_user,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_upgrade_guest_account,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_create_log,
+                #[cfg(feature = "database")]
+                rl_create_logs_bulk,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_ingest_frontend_errors,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_get_logs,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_delete_old_logs,
+                #[cfg(feature = "database")]
+                rl_apply_log_retention,
+                rl_get_system_info,
+                rl_get_app_version_info,
+                rl_get_startup_status,
+                rl_get_locale_info,
+                rl_list_storage_devices,
+                rl_send_notification,
+                #[cfg(feature = "database")]
+                rl_get_notification_history,
+                #[cfg(feature = "database")]
+                rl_mark_notification_read,
+                rl_get_window_info,
+                rl_toggle_window_maximize,
+                rl_minimize_window,
+                rl_center_window,
+                rl_set_window_title,
+                rl_create_new_window,
+                rl_execute_command,
+                rl_get_app_data_dir,
+                rl_get_app_log_dir,
+                rl_read_text_file,
+                rl_write_text_file,
+                rl_append_text_file,
+                rl_delete_file,
+                rl_create_directory,
+                rl_list_directory,
+                rl_file_exists,
+                rl_get_file_info,
+                rl_copy_file,
+                rl_move_file,
+                rl_get_log_config,
+                rl_update_log_config,
+                rl_get_log_entries,
+                rl_get_log_page,
+                rl_tail_log_file,
+                rl_stop_log_tail,
+                rl_clear_old_logs,
+                rl_get_log_stats,
+                rl_create_test_log,
+                rl_aggregate_logs,
+                rl_set_cache_value,
+                rl_get_cache_value,
+                rl_delete_cache_value,
+                rl_cache_key_exists,
+                rl_is_cache_available,
+                rl_http_request,
+                rl_get_local_server_status,
+                rl_register_local_server_route,
+                rl_get_companion_server_status,
+                rl_create_companion_pairing_token,
+                rl_revoke_companion_pairing,
+                rl_get_telemetry_status,
+                rl_set_telemetry_consent,
+                rl_record_telemetry_event,
+                rl_get_telemetry_events,
+                rl_purge_telemetry_data,
+                rl_check_for_updates,
+                rl_download_update,
+                rl_install_update_and_restart,
+                #[cfg(feature = "database")]
+                rl_export_user_data,
+                #[cfg(feature = "database")]
+                rl_import_user_data,
+                #[cfg(feature = "database")]
+                rl_get_sync_status,
+                #[cfg(feature = "database")]
+                rl_sync_now,
+                rl_get_metrics_prometheus,
+                rl_get_recent_invocations,
+                rl_clear_recent_invocations,
+                rl_get_setup_status,
+                rl_complete_setup_step,
+                rl_submit_feedback,
+                rl_get_state,
+                rl_set_state,
+                rl_watch_state,
+                #[cfg(feature = "database")]
+                rl_search,
+                #[cfg(feature = "database")]
+                rl_rebuild_search_index,
+                #[cfg(feature = "database")]
+                rl_create_workspace,
+                #[cfg(feature = "database")]
+                rl_list_workspaces,
+                #[cfg(feature = "database")]
+                rl_add_workspace_member,
+                #[cfg(feature = "database")]
+                rl_remove_workspace_member,
+                #[cfg(feature = "database")]
+                rl_switch_workspace,
+                #[cfg(feature = "database")]
+                rl_get_current_workspace,
+                #[cfg(feature = "database")]
+                rl_get_workspace_settings,
+                #[cfg(feature = "database")]
+                rl_create_group,
+                #[cfg(feature = "database")]
+                rl_list_groups,
+                #[cfg(feature = "database")]
+                rl_update_group,
+                #[cfg(feature = "database")]
+                rl_delete_group,
+                #[cfg(feature = "database")]
+                rl_list_group_members,
+                #[cfg(feature = "database")]
+                rl_add_group_member,
+                #[cfg(feature = "database")]
+                rl_remove_group_member,
+                rl_configure_smtp,
+                rl_send_email,
+                rl_register_email_template,
+                rl_get_email_deliveries,
+                #[cfg(feature = "database")]
+                rl_send_verification_email,
+                #[cfg(feature = "database")]
+                rl_verify_email,
+                rl_generate_pdf,
+                #[cfg(feature = "database")]
+                rl_create_backup,
+                #[cfg(feature = "database")]
+                rl_list_backups,
+                #[cfg(feature = "database")]
+                rl_restore_backup,
+                #[cfg(feature = "database")]
+                rl_export_backup_to_device,
+                #[cfg(feature = "database")]
+                rl_get_query_stats,
+                #[cfg(feature = "database")]
+                rl_reset_query_stats,
+                #[cfg(feature = "database")]
+                rl_acquire_advisory_lock,
+                #[cfg(feature = "database")]
+                rl_release_advisory_lock,
+                #[cfg(feature = "database")]
+                rl_execute_sql,
+                #[cfg(feature = "database")]
+                rl_export_personal_data,
+                #[cfg(feature = "database")]
+                rl_erase_user_data,
+                #[cfg(feature = "database")]
+                rl_request_account_deletion,
+                #[cfg(feature = "database")]
+                rl_cancel_account_deletion,
+                #[cfg(feature = "database")]
+                rl_get_admin_stats,
+                #[cfg(feature = "database")]
+                rl_benchmark_password_hashing,
+                #[cfg(feature = "database")]
+                rl_get_audit_log,
+                rl_list_commands,
+                #[cfg(feature = "database")]
+                rl_get_user_settings,
+                #[cfg(feature = "database")]
+                rl_update_user_settings,
+                rl_list_themes,
+                #[cfg(feature = "database")]
+                rl_set_active_theme,
+                rl_list_system_fonts,
+                rl_read_clipboard_image,
+                rl_write_clipboard_image,
+                rl_copy_window_screenshot_to_clipboard,
+                rl_start_clipboard_history,
+                rl_stop_clipboard_history,
+                rl_list_clipboard_history,
+                rl_clear_clipboard_history,
+                rl_take_pending_file_opens,
+                rl_list_scripts,
+                rl_run_script,
+                rl_schedule_script,
+                rl_parse_csv,
+                rl_write_csv,
+                rl_get_proxy_settings,
+                rl_start_discovery,
+                rl_stop_discovery,
+                rl_list_peers,
+                rl_send_file_to_peer,
+                rl_respond_to_transfer,
+                rl_list_transfer_offers,
+                rl_unlock_secrets,
+                rl_lock_secrets,
+                rl_save_secret,
+                rl_get_secret,
+                rl_delete_secret,
+                rl_list_secrets,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_refresh_session,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_validate_session,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_session_heartbeat,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_logout,
+                #[cfg(any(feature = "database", feature = "sqlite"))]
+                rl_revoke_user_sessions,
+                #[cfg(feature = "database")]
+                rl_impersonate_user,
+                #[cfg(feature = "database")]
+                rl_end_impersonation,
+                #[cfg(feature = "database")]
+                rl_start_oauth_login,
+                #[cfg(feature = "database")]
+                rl_complete_oauth_login,
+                rl_cancel_operation,
+                rl_enter_maintenance_mode,
+                rl_exit_maintenance_mode,
+                rl_get_maintenance_status,
+                rl_get_image_metadata,
+                rl_strip_image_metadata,
+                get_rate_limiter_status
+            ]))
+            .build(context)
+            .expect("error while building tauri application")
+            .run(|app_handle, event| match event {
+                tauri::RunEvent::Exit => {
+                    let rate_limiter = app_handle
+                        .try_state::<Arc<RateLimiterConfig>>()
+                        .map(|state| state.inner().clone());
+                    tauri::async_runtime::block_on(shutdown::run(rate_limiter));
+                }
+                #[cfg(any(target_os = "macos", target_os = "ios"))]
+                tauri::RunEvent::Opened { urls } => file_open::open_urls(&urls),
+                _ => {}
             });
+    }
+}
 
-            Ok(())
-        })
-        .invoke_handler(tauri::generate_handler![
-            rl_greet,
-            rl_check_database_connection,
-            rl_initialize_database,
-            rl_run_migrations,
-            rl_get_all_users,
-            rl_get_user_by_id,
-            rl_create_user,
-            rl_update_user,
-            rl_delete_user,
-            rl_authenticate_user,
-            rl_create_log,
-            rl_get_logs,
-            rl_delete_old_logs,
-            rl_get_system_info,
-            rl_send_notification,
-            rl_get_window_info,
-            rl_toggle_window_maximize,
-            rl_minimize_window,
-            rl_center_window,
-            rl_set_window_title,
-            rl_create_new_window,
-            rl_execute_command,
-            rl_get_app_data_dir,
-            rl_get_app_log_dir,
-            rl_read_text_file,
-            rl_write_text_file,
-            rl_append_text_file,
-            rl_delete_file,
-            rl_create_directory,
-            rl_list_directory,
-            rl_file_exists,
-            rl_get_file_info,
-            rl_copy_file,
-            rl_move_file,
-            rl_get_log_config,
-            rl_update_log_config,
-            rl_get_log_entries,
-            rl_clear_old_logs,
-            rl_get_log_stats,
-            rl_create_test_log,
-            rl_set_cache_value,
-            rl_get_cache_value,
-            rl_delete_cache_value,
-            rl_cache_key_exists,
-            rl_is_cache_available,
-            get_rate_limiter_status
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+/// Initializes and runs the Tauri application with the built-in commands.
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    EzTauriBuilder::new().run()
 }
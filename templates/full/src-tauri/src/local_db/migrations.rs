@@ -0,0 +1,177 @@
+//! Schema for the embedded SQLite database.
+//!
+//! Mirrors the core PostgreSQL tables (users, password history, user
+//! settings, application logs, and login sessions) with SQLite types: ids
+//! are UUID strings, timestamps are ISO-8601 text, and JSON and array
+//! columns are JSON text. The number of applied steps is
+//! kept in `PRAGMA user_version`, so each step runs exactly once; append new
+//! steps instead of editing old ones.
+
+use crate::operations;
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// Schema steps in order; a step's position plus one is its version.
+const MIGRATIONS: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS users (
+        id TEXT PRIMARY KEY NOT NULL,
+        email TEXT NOT NULL UNIQUE,
+        username TEXT NOT NULL UNIQUE,
+        password_hash TEXT NOT NULL,
+        first_name TEXT,
+        last_name TEXT,
+        is_active INTEGER NOT NULL DEFAULT 1,
+        email_verified INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS user_settings (
+        id TEXT PRIMARY KEY NOT NULL,
+        user_id TEXT NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+        theme TEXT NOT NULL DEFAULT 'light',
+        language TEXT NOT NULL DEFAULT 'en',
+        notifications_enabled INTEGER NOT NULL DEFAULT 1,
+        settings_data TEXT NOT NULL DEFAULT '{}',
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )"#,
+    r#"CREATE TABLE IF NOT EXISTS app_logs (
+        id TEXT PRIMARY KEY NOT NULL,
+        level TEXT NOT NULL,
+        message TEXT NOT NULL,
+        metadata TEXT NOT NULL DEFAULT '{}',
+        user_id TEXT REFERENCES users(id) ON DELETE SET NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )"#,
+    r#"CREATE INDEX IF NOT EXISTS idx_app_logs_created_at ON app_logs(created_at)"#,
+    r#"ALTER TABLE users ADD COLUMN is_guest INTEGER NOT NULL DEFAULT 0"#,
+    r#"CREATE TABLE IF NOT EXISTS password_history (
+        id TEXT PRIMARY KEY NOT NULL,
+        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+        password_hash TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )"#,
+    r#"CREATE INDEX IF NOT EXISTS idx_password_history_user
+        ON password_history(user_id, created_at)"#,
+    r#"ALTER TABLE users ADD COLUMN roles TEXT NOT NULL DEFAULT '[]'"#,
+    r#"ALTER TABLE app_logs ADD COLUMN tags TEXT NOT NULL DEFAULT '[]'"#,
+    r#"CREATE TABLE IF NOT EXISTS user_sessions (
+        id TEXT PRIMARY KEY NOT NULL,
+        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+        refresh_token_hash TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL,
+        last_active TEXT NOT NULL,
+        expires_at TEXT NOT NULL,
+        remember_me INTEGER NOT NULL DEFAULT 0
+    )"#,
+    r#"CREATE INDEX IF NOT EXISTS idx_user_sessions_expires_at ON user_sessions(expires_at)"#,
+    r#"CREATE TABLE IF NOT EXISTS retired_refresh_tokens (
+        token_hash TEXT PRIMARY KEY NOT NULL,
+        session_id TEXT NOT NULL REFERENCES user_sessions(id) ON DELETE CASCADE
+    )"#,
+    r#"CREATE INDEX IF NOT EXISTS idx_retired_refresh_tokens_session
+        ON retired_refresh_tokens(session_id)"#,
+    r#"CREATE INDEX IF NOT EXISTS idx_app_logs_user ON app_logs(user_id, created_at)"#,
+];
+
+/// Applies the steps newer than the database's schema version.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+    let current = schema_version(pool).await?;
+    let total = MIGRATIONS.len() as u64;
+
+    for (index, statement) in MIGRATIONS.iter().enumerate().skip(current) {
+        operations::check_cancelled()?;
+        let mut tx = pool.begin().await?;
+        sqlx::query(statement).execute(&mut *tx).await?;
+        // PRAGMA values cannot be bound as parameters.
+        sqlx::query(&format!("PRAGMA user_version = {}", index + 1))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        tracing::debug!("Applied SQLite migration {}", index + 1);
+        operations::report(index as u64 + 1, Some(total), None);
+    }
+
+    Ok(())
+}
+
+/// Number of migration steps applied to the database.
+pub async fn schema_version(pool: &SqlitePool) -> Result<usize> {
+    let (version,): (i64,) = sqlx::query_as("PRAGMA user_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(usize::try_from(version).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::local_db::open;
+
+    async fn table_names(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_as::<_, (String,)>(
+            "SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(name,)| name)
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn migrations_create_the_schema_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open(&dir.path().join("app.db")).await.unwrap();
+
+        run_migrations(&pool).await.unwrap();
+        assert_eq!(schema_version(&pool).await.unwrap(), MIGRATIONS.len());
+        assert_eq!(
+            table_names(&pool).await,
+            vec![
+                "app_logs",
+                "password_history",
+                "retired_refresh_tokens",
+                "user_sessions",
+                "user_settings",
+                "users"
+            ]
+        );
+
+        // A second run finds nothing to apply.
+        run_migrations(&pool).await.unwrap();
+        assert_eq!(schema_version(&pool).await.unwrap(), MIGRATIONS.len());
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn deleting_a_user_cascades_to_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open(&dir.path().join("app.db")).await.unwrap();
+        run_migrations(&pool).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO users (id, email, username, password_hash)
+             VALUES ('u1', 'ada@example.com', 'ada', 'hash')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO user_settings (id, user_id) VALUES ('s1', 'u1')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM users WHERE id = 'u1'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (remaining,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM user_settings")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+        pool.close().await;
+    }
+}
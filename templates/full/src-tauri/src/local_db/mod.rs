@@ -0,0 +1,176 @@
+//! Embedded SQLite database used by the offline profile.
+//!
+//! Compiled with the `sqlite` cargo feature, alone or next to `database`,
+//! and used when `DATABASE_URL` has the `sqlite:` scheme (the default when
+//! PostgreSQL is not compiled in). Data lives in a single file, by default
+//! under the application data directory, created on first start, so the app
+//! runs without PostgreSQL or any other service. SQLite itself is bundled
+//! into the binary by sqlx.
+
+use crate::config;
+#[cfg(feature = "database")]
+use crate::config::DatabaseBackend;
+use crate::logging;
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use once_cell::sync::OnceCell;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+pub mod migrations;
+
+/// File name of the database inside the data directory.
+const DATABASE_FILE: &str = "app.db";
+
+/// Pool size; SQLite serializes writers, so a few connections suffice.
+const MAX_CONNECTIONS: u32 = 4;
+
+/// How long a connection waits for another writer before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Global connection pool, set once the database file is open.
+static POOL: OnceCell<RwLock<Option<Arc<SqlitePool>>>> = OnceCell::new();
+
+fn pool_slot() -> &'static RwLock<Option<Arc<SqlitePool>>> {
+    POOL.get_or_init(|| RwLock::new(None))
+}
+
+/// Whether `DATABASE_URL` selects this database. Only builds with both
+/// features have to ask; the PostgreSQL commands use it to hand off.
+#[cfg(feature = "database")]
+pub fn is_selected() -> bool {
+    config::current().database_backend == DatabaseBackend::Sqlite
+}
+
+/// Location of the database file: the path of a `sqlite:` URL, or
+/// `app.db` in the application data directory for a bare `sqlite:`.
+pub fn database_path() -> PathBuf {
+    url_path(&config::current().database_url).unwrap_or_else(default_path)
+}
+
+/// File path of a `sqlite:` URL such as `sqlite:///var/lib/app.db` or
+/// `sqlite:data/app.db`, ignoring `?` options.
+fn url_path(url: &str) -> Option<PathBuf> {
+    let url = url.trim();
+    if !url
+        .get(..7)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("sqlite:"))
+    {
+        return None;
+    }
+    let rest = url[7..].split('?').next().unwrap_or_default();
+    let path = rest.strip_prefix("//").unwrap_or(rest);
+    (!path.is_empty()).then(|| PathBuf::from(path))
+}
+
+fn default_path() -> PathBuf {
+    logging::app_data_dir(DATABASE_FILE)
+}
+
+/// Opens the database at `path`, creating the file and its directory if
+/// they do not exist yet.
+pub async fn open(path: &Path) -> Result<SqlitePool> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true)
+        .busy_timeout(BUSY_TIMEOUT);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(MAX_CONNECTIONS)
+        .connect_with(options)
+        .await?;
+    Ok(pool)
+}
+
+/// Opens the database file, applies pending migrations, and installs the
+/// global pool.
+pub async fn initialize() -> Result<()> {
+    let path = database_path();
+    let pool = open(&path).await?;
+    migrations::run_migrations(&pool).await?;
+
+    let mut guard = pool_slot()
+        .write()
+        .map_err(|_| anyhow::anyhow!("Failed to lock database pool for initialization"))?;
+    *guard = Some(Arc::new(pool));
+    tracing::info!("Opened SQLite database at {}", path.display());
+    Ok(())
+}
+
+/// Returns the connection pool if the database is open.
+pub fn get_pool() -> Option<Arc<SqlitePool>> {
+    pool_slot()
+        .read()
+        .ok()
+        .and_then(|guard| guard.as_ref().cloned())
+}
+
+/// Returns the connection pool or an error if the database is not open.
+pub fn get_pool_ref() -> Result<Arc<SqlitePool>> {
+    get_pool().ok_or_else(|| anyhow::anyhow!("Database pool not initialized"))
+}
+
+/// Fixed-width RFC 3339 text of `time`, the form timestamps are stored in,
+/// so comparing and sorting them as text follows time.
+pub fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Removes the global pool and waits for its connections to close, which
+/// also checkpoints the write-ahead log into the database file.
+pub async fn close_pool() {
+    let pool = pool_slot().write().ok().and_then(|mut guard| guard.take());
+    if let Some(pool) = pool {
+        pool.close().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_file_path_from_sqlite_urls() {
+        assert_eq!(url_path("sqlite:"), None);
+        assert_eq!(url_path("sqlite://"), None);
+        assert_eq!(
+            url_path("sqlite:///var/lib/app.db?mode=rwc"),
+            Some(PathBuf::from("/var/lib/app.db"))
+        );
+        assert_eq!(
+            url_path("SQLITE:data/app.db"),
+            Some(PathBuf::from("data/app.db"))
+        );
+        assert_eq!(url_path("postgresql://localhost/app"), None);
+    }
+
+    #[tokio::test]
+    async fn creates_the_database_file_and_its_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(DATABASE_FILE);
+
+        let pool = open(&path).await.unwrap();
+        let (version,): (String,) = sqlx::query_as("SELECT sqlite_version()")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(!version.is_empty());
+        assert!(path.exists());
+
+        let (foreign_keys,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(foreign_keys, 1);
+        pool.close().await;
+    }
+}
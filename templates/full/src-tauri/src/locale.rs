@@ -0,0 +1,126 @@
+//! Operating system locale and timezone detection.
+//!
+//! The IANA timezone is read once at first use. Log and user models serialize
+//! their timestamps through [`local_time`], so responses carry the local UTC
+//! offset and display as the user's wall-clock time while still denoting the
+//! same instant when parsed back.
+
+use chrono::{DateTime, Offset, Utc};
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Timezone detected from the operating system, or UTC when unavailable.
+static LOCAL_TIMEZONE: Lazy<Tz> = Lazy::new(|| {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or_else(|| {
+            tracing::warn!("Could not detect the system timezone, using UTC");
+            Tz::UTC
+        })
+});
+
+/// Locale preferences reported by the operating system.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleInfo {
+    /// Primary locale as a BCP 47 tag, such as `en-US`.
+    pub locale: String,
+    /// Preferred languages in order, most preferred first.
+    pub languages: Vec<String>,
+    /// IANA timezone name, such as `Europe/Berlin`.
+    pub timezone: String,
+    /// Current offset from UTC in seconds.
+    pub utc_offset_seconds: i32,
+}
+
+/// Returns the system locale, preferred languages and timezone.
+pub fn locale_info() -> LocaleInfo {
+    let locale = sys_locale::get_locale().unwrap_or_else(|| "en-US".to_string());
+    let mut languages: Vec<String> = Vec::new();
+    for language in sys_locale::get_locales() {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    if languages.is_empty() {
+        languages.push(locale.clone());
+    }
+
+    let timezone = local_timezone();
+    LocaleInfo {
+        locale,
+        languages,
+        timezone: timezone.name().to_string(),
+        utc_offset_seconds: Utc::now()
+            .with_timezone(&timezone)
+            .offset()
+            .fix()
+            .local_minus_utc(),
+    }
+}
+
+/// Timezone used when formatting timestamps for display.
+pub fn local_timezone() -> Tz {
+    *LOCAL_TIMEZONE
+}
+
+/// Formats `time` as RFC 3339 in `timezone`.
+pub fn format_in(time: &DateTime<Utc>, timezone: Tz) -> String {
+    time.with_timezone(&timezone).to_rfc3339()
+}
+
+/// Serde helpers that write UTC timestamps in the system timezone.
+///
+/// Use with `#[serde(with = "crate::locale::local_time")]`. Deserializing
+/// accepts any RFC 3339 offset and converts back to UTC.
+pub mod local_time {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_in(time, super::local_timezone()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        DateTime::<Utc>::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn formats_timestamps_with_the_local_offset() {
+        let time = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            format_in(&time, chrono_tz::Europe::Berlin),
+            "2024-07-01T14:00:00+02:00"
+        );
+        assert_eq!(format_in(&time, Tz::UTC), "2024-07-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn local_timestamps_parse_back_to_the_same_instant() {
+        let time = Utc.with_ymd_and_hms(2024, 1, 15, 23, 30, 0).unwrap();
+        let formatted = format_in(&time, chrono_tz::America::New_York);
+        let parsed: DateTime<Utc> = serde_json::from_value(serde_json::json!(formatted)).unwrap();
+        assert_eq!(parsed, time);
+    }
+
+    #[test]
+    fn reports_locale_info() {
+        let info = locale_info();
+        assert!(!info.locale.is_empty());
+        assert!(!info.languages.is_empty());
+        assert!(info.timezone.parse::<Tz>().is_ok());
+    }
+}
@@ -0,0 +1,404 @@
+//! Log counts grouped by level, target, and time bucket.
+//!
+//! Charts in the log viewer need counts rather than entries, so they are
+//! computed here over the log files or the `app_logs` table, and only the
+//! buckets cross over to the webview. Database entries have no tracing
+//! target; they are grouped by their `target` metadata field instead.
+
+use super::LogEntry;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Most buckets one aggregation may return.
+pub const MAX_BUCKETS: usize = 10_000;
+
+/// Target reported for entries without one.
+const UNKNOWN_TARGET: &str = "unknown";
+
+/// A dimension log counts can be grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogGroupBy {
+    Level,
+    Target,
+    /// Start of the time bucket, `interval` wide.
+    Time,
+}
+
+/// Where aggregated entries are read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    #[default]
+    Files,
+    Database,
+}
+
+/// Half-open time range `start <= timestamp < end`; either end may be open.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl LogRange {
+    pub fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        self.start.map_or(true, |start| timestamp >= start)
+            && self.end.map_or(true, |end| timestamp < end)
+    }
+}
+
+/// Number of entries sharing the grouped values. Dimensions that were not
+/// grouped by are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogBucket {
+    pub time: Option<DateTime<Utc>>,
+    pub level: Option<String>,
+    pub target: Option<String>,
+    pub count: u64,
+}
+
+/// Result of [`Aggregator::finish`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogAggregation {
+    pub source: LogSource,
+    pub group_by: Vec<LogGroupBy>,
+    /// Width of the time buckets, when grouped by time.
+    pub interval_seconds: Option<i64>,
+    /// Ordered by time, then level, then target. Empty buckets are left out.
+    pub buckets: Vec<LogBucket>,
+    pub total: u64,
+}
+
+type BucketKey = (Option<DateTime<Utc>>, Option<String>, Option<String>);
+
+/// Counts entries into buckets.
+#[derive(Debug)]
+pub struct Aggregator {
+    group_by: Vec<LogGroupBy>,
+    interval_seconds: Option<i64>,
+    range: LogRange,
+    counts: BTreeMap<BucketKey, u64>,
+    /// Whether an entry was dropped because it needed a bucket beyond
+    /// [`MAX_BUCKETS`].
+    overflowed: bool,
+}
+
+impl Aggregator {
+    /// Groups by `group_by`, bucketing time by `interval`, e.g. `5m`, which
+    /// defaults to an hour. Entries outside `range` are not counted.
+    pub fn new(
+        group_by: Vec<LogGroupBy>,
+        interval: Option<&str>,
+        range: LogRange,
+    ) -> Result<Self, String> {
+        let interval_seconds = if group_by.contains(&LogGroupBy::Time) {
+            Some(parse_interval(interval.unwrap_or("1h"))?)
+        } else {
+            None
+        };
+        if let (Some(start), Some(end)) = (range.start, range.end) {
+            if start >= end {
+                return Err("The range must start before it ends".to_string());
+            }
+        }
+
+        Ok(Self {
+            group_by,
+            interval_seconds,
+            range,
+            counts: BTreeMap::new(),
+            overflowed: false,
+        })
+    }
+
+    pub fn interval_seconds(&self) -> Option<i64> {
+        self.interval_seconds
+    }
+
+    pub fn range(&self) -> &LogRange {
+        &self.range
+    }
+
+    pub fn groups_by(&self, dimension: LogGroupBy) -> bool {
+        self.group_by.contains(&dimension)
+    }
+
+    /// Counts `entry` when it falls within the range.
+    pub fn add(&mut self, entry: &LogEntry) {
+        if self.range.contains(entry.timestamp) {
+            self.add_count(Some(entry.timestamp), &entry.level, Some(&entry.target), 1);
+        }
+    }
+
+    /// Adds `count` entries with the given values, e.g. a row already
+    /// grouped by the database. Entries that would need a new bucket once
+    /// there are [`MAX_BUCKETS`] are dropped, failing [`Self::finish`].
+    pub fn add_count(
+        &mut self,
+        timestamp: Option<DateTime<Utc>>,
+        level: &str,
+        target: Option<&str>,
+        count: u64,
+    ) {
+        let time = match (self.interval_seconds, timestamp) {
+            (Some(seconds), Some(timestamp)) => bucket_start(timestamp, seconds),
+            _ => None,
+        };
+        let level = self
+            .groups_by(LogGroupBy::Level)
+            .then(|| level.to_lowercase());
+        let target = self.groups_by(LogGroupBy::Target).then(|| {
+            target
+                .filter(|target| !target.is_empty())
+                .unwrap_or(UNKNOWN_TARGET)
+                .to_string()
+        });
+        let key = (time, level, target);
+        if let Some(existing) = self.counts.get_mut(&key) {
+            *existing += count;
+        } else if self.counts.len() < MAX_BUCKETS {
+            self.counts.insert(key, count);
+        } else {
+            self.overflowed = true;
+        }
+    }
+
+    /// Returns the buckets, failing when there would have been more than
+    /// [`MAX_BUCKETS`].
+    pub fn finish(self, source: LogSource) -> Result<LogAggregation, String> {
+        if self.overflowed {
+            return Err(format!(
+                "The aggregation has more than {} buckets; use a wider interval or a shorter range",
+                MAX_BUCKETS
+            ));
+        }
+
+        let buckets: Vec<LogBucket> = self
+            .counts
+            .into_iter()
+            .map(|((time, level, target), count)| LogBucket {
+                time,
+                level,
+                target,
+                count,
+            })
+            .collect();
+        Ok(LogAggregation {
+            source,
+            group_by: self.group_by,
+            interval_seconds: self.interval_seconds,
+            total: buckets.iter().map(|bucket| bucket.count).sum(),
+            buckets,
+        })
+    }
+}
+
+/// Parses an interval such as `30s`, `5m`, `1h`, or `1d` into seconds.
+pub fn parse_interval(interval: &str) -> Result<i64, String> {
+    let interval = interval.trim();
+    let split = interval
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(interval.len());
+    let (amount, unit) = interval.split_at(split);
+    let unit_seconds = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("Invalid interval '{}'", interval)),
+    };
+    amount
+        .parse::<i64>()
+        .ok()
+        .filter(|amount| *amount > 0)
+        .and_then(|amount| amount.checked_mul(unit_seconds))
+        .ok_or_else(|| format!("Invalid interval '{}'", interval))
+}
+
+/// Start of the `seconds` wide bucket holding `timestamp`, aligned to the
+/// Unix epoch.
+fn bucket_start(timestamp: DateTime<Utc>, seconds: i64) -> Option<DateTime<Utc>> {
+    let epoch = timestamp.timestamp();
+    DateTime::from_timestamp(epoch - epoch.rem_euclid(seconds), 0)
+}
+
+/// Counts the `app_logs` entries of `user_id`, or everyone's, into
+/// `aggregator`, grouped in SQL.
+#[cfg(feature = "database")]
+pub async fn aggregate_database(
+    aggregator: &mut Aggregator,
+    user_id: Option<uuid::Uuid>,
+    workspace_id: Option<uuid::Uuid>,
+) -> Result<(), String> {
+    use crate::database::{get_pool_ref, query_stats};
+    use sqlx::{Execute, Postgres, QueryBuilder};
+
+    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let mut builder = QueryBuilder::<Postgres>::new("SELECT ");
+    match aggregator.interval_seconds() {
+        Some(seconds) => builder
+            .push("to_timestamp(floor(extract(epoch FROM created_at) / ")
+            .push_bind(seconds)
+            .push(") * ")
+            .push_bind(seconds)
+            .push(")"),
+        None => builder.push("NULL::timestamptz"),
+    };
+    builder.push(if aggregator.groups_by(LogGroupBy::Level) {
+        ", level"
+    } else {
+        ", ''"
+    });
+    builder.push(if aggregator.groups_by(LogGroupBy::Target) {
+        ", metadata->>'target'"
+    } else {
+        ", NULL::text"
+    });
+    builder.push(", COUNT(*) FROM app_logs WHERE TRUE");
+    if let Some(start) = aggregator.range().start {
+        builder.push(" AND created_at >= ").push_bind(start);
+    }
+    if let Some(end) = aggregator.range().end {
+        builder.push(" AND created_at < ").push_bind(end);
+    }
+    if let Some(user_id) = user_id {
+        builder.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(workspace_id) = workspace_id {
+        builder.push(" AND workspace_id = ").push_bind(workspace_id);
+    }
+    builder.push(" GROUP BY 1, 2, 3");
+
+    let query = builder.build_query_as::<(Option<DateTime<Utc>>, String, Option<String>, i64)>();
+    let rows = query_stats::timed(query.sql(), query.fetch_all(pool.as_ref()))
+        .await
+        .map_err(|e| format!("Failed to aggregate logs: {}", e))?;
+    for (time, level, target, count) in rows {
+        aggregator.add_count(time, &level, target.as_deref(), count.max(0) as u64);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn entry(timestamp: &str, level: &str, target: &str) -> LogEntry {
+        LogEntry {
+            timestamp: timestamp.parse().unwrap(),
+            level: level.to_string(),
+            target: target.to_string(),
+            message: String::new(),
+            fields: HashMap::new(),
+            span: None,
+            thread_name: None,
+            file: None,
+            line: None,
+        }
+    }
+
+    #[test]
+    fn parses_intervals() {
+        assert_eq!(parse_interval("30s"), Ok(30));
+        assert_eq!(parse_interval("5m"), Ok(300));
+        assert_eq!(parse_interval(" 2h "), Ok(7200));
+        assert_eq!(parse_interval("1d"), Ok(86_400));
+        assert!(parse_interval("0m").is_err());
+        assert!(parse_interval("5").is_err());
+        assert!(parse_interval("m").is_err());
+        assert!(parse_interval("5w").is_err());
+    }
+
+    #[test]
+    fn counts_entries_per_time_bucket_and_level() {
+        let range = LogRange {
+            start: Some("2024-03-15T12:00:00Z".parse().unwrap()),
+            end: None,
+        };
+        let group_by = vec![LogGroupBy::Time, LogGroupBy::Level];
+        let mut aggregator = Aggregator::new(group_by, Some("5m"), range).unwrap();
+        aggregator.add(&entry("2024-03-15T11:59:59Z", "INFO", "app"));
+        aggregator.add(&entry("2024-03-15T12:01:00Z", "INFO", "app"));
+        aggregator.add(&entry("2024-03-15T12:04:59Z", "info", "db"));
+        aggregator.add(&entry("2024-03-15T12:03:00Z", "WARN", "app"));
+        aggregator.add(&entry("2024-03-15T12:05:00Z", "INFO", "app"));
+
+        let aggregation = aggregator.finish(LogSource::Files).unwrap();
+        assert_eq!(aggregation.interval_seconds, Some(300));
+        assert_eq!(aggregation.total, 4);
+        assert!(aggregation
+            .buckets
+            .iter()
+            .all(|bucket| bucket.target.is_none()));
+        let counts: Vec<_> = aggregation
+            .buckets
+            .iter()
+            .map(|bucket| {
+                (
+                    bucket.time.unwrap().format("%H:%M").to_string(),
+                    bucket.level.as_deref().unwrap(),
+                    bucket.count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("12:00".to_string(), "info", 2),
+                ("12:00".to_string(), "warn", 1),
+                ("12:05".to_string(), "info", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_by_target_without_time() {
+        let mut aggregator =
+            Aggregator::new(vec![LogGroupBy::Target], None, LogRange::default()).unwrap();
+        assert_eq!(aggregator.interval_seconds(), None);
+        aggregator.add(&entry("2024-03-15T12:00:00Z", "INFO", "app"));
+        aggregator.add_count(None, "error", None, 3);
+        aggregator.add_count(None, "info", Some("app"), 2);
+
+        let buckets = aggregator.finish(LogSource::Database).unwrap().buckets;
+        let targets: Vec<_> = buckets
+            .iter()
+            .map(|bucket| (bucket.target.as_deref().unwrap(), bucket.count))
+            .collect();
+        assert_eq!(targets, vec![("app", 3), ("unknown", 3)]);
+        assert!(buckets.iter().all(|bucket| bucket.time.is_none()));
+    }
+
+    #[test]
+    fn stops_adding_buckets_past_the_limit() {
+        let mut aggregator =
+            Aggregator::new(vec![LogGroupBy::Target], None, LogRange::default()).unwrap();
+        for target in 0..=MAX_BUCKETS {
+            aggregator.add_count(None, "info", Some(&target.to_string()), 1);
+        }
+        aggregator.add_count(None, "info", Some("0"), 1);
+
+        assert_eq!(aggregator.counts.len(), MAX_BUCKETS);
+        assert_eq!(
+            aggregator.counts.values().sum::<u64>(),
+            MAX_BUCKETS as u64 + 1
+        );
+        assert!(aggregator.finish(LogSource::Files).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_ranges() {
+        let at: DateTime<Utc> = "2024-03-15T12:00:00Z".parse().unwrap();
+        let range = LogRange {
+            start: Some(at),
+            end: Some(at),
+        };
+        assert!(Aggregator::new(vec![LogGroupBy::Level], None, range).is_err());
+    }
+}
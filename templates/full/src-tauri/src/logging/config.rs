@@ -33,6 +33,10 @@ pub struct FileLogConfig {
     pub enabled: bool,
     pub directory: String,
     pub filename_prefix: String,
+    /// Format of the lines written to log files. JSON keeps fields, spans,
+    /// thread names and source locations readable by `get_log_entries`.
+    #[serde(default = "default_file_format")]
+    pub format: LogFormat,
     pub rotation: LogRotation,
     pub max_files: usize,
     pub max_size_mb: Option<u64>,
@@ -110,6 +114,7 @@ impl Default for FileLogConfig {
             enabled: true,
             directory: "logs".to_string(),
             filename_prefix: "ez-tauri".to_string(),
+            format: default_file_format(),
             rotation: LogRotation::Daily,
             max_files: 30,
             max_size_mb: Some(100),
@@ -117,6 +122,10 @@ impl Default for FileLogConfig {
     }
 }
 
+fn default_file_format() -> LogFormat {
+    LogFormat::Json
+}
+
 impl Default for StructuredLogConfig {
     fn default() -> Self {
         Self {
@@ -172,6 +181,15 @@ pub fn load_config_from_env() -> AppLogConfig {
         config.file.filename_prefix = prefix;
     }
 
+    if let Ok(file_format) = env::var("LOG_FILE_FORMAT") {
+        config.file.format = match file_format.to_lowercase().as_str() {
+            "pretty" => LogFormat::Pretty,
+            "compact" => LogFormat::Compact,
+            "full" => LogFormat::Full,
+            _ => LogFormat::Json,
+        };
+    }
+
     if let Ok(rotation) = env::var("LOG_ROTATION") {
         config.file.rotation = match rotation.to_lowercase().as_str() {
             "never" => LogRotation::Never,
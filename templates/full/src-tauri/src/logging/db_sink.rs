@@ -0,0 +1,153 @@
+//! Buffered writer for application logs stored in the `app_logs` table.
+//!
+//! Entries are queued in memory and written in batches with a single
+//! `UNNEST` insert, either when the flush interval elapses or when the buffer
+//! reaches [`MAX_BATCH`] entries. Pending entries are flushed on shutdown.
+
+use crate::database::{get_pool_ref, query_stats};
+use crate::shutdown;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use sqlx::Execute;
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Interval between background flushes.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Buffer size that triggers an immediate flush.
+pub const MAX_BATCH: usize = 500;
+
+/// Entries waiting to be written.
+static BUFFER: Lazy<Mutex<Vec<PendingLog>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Serializes flushes so batches are written in order.
+static FLUSH_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+/// A validated log entry waiting to be inserted.
+#[derive(Debug, Clone)]
+pub struct PendingLog {
+    pub level: String,
+    pub message: String,
+    pub metadata: serde_json::Value,
+    pub user_id: Option<Uuid>,
+    pub workspace_id: Option<Uuid>,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Queues entries for the next flush, flushing early when the buffer is full.
+pub fn enqueue(entries: Vec<PendingLog>) -> usize {
+    let count = entries.len();
+    let full = BUFFER
+        .lock()
+        .map(|mut buffer| {
+            buffer.extend(entries);
+            buffer.len() >= MAX_BATCH
+        })
+        .unwrap_or(false);
+
+    if full {
+        tauri::async_runtime::spawn(async {
+            if let Err(e) = flush().await {
+                tracing::warn!("Failed to flush log buffer: {}", e);
+            }
+        });
+    }
+    count
+}
+
+/// Returns the number of entries waiting to be written.
+pub fn pending() -> usize {
+    BUFFER.lock().map(|buffer| buffer.len()).unwrap_or(0)
+}
+
+/// Writes all buffered entries, returning how many were inserted.
+///
+/// Entries are put back at the front of the buffer if the insert fails.
+pub async fn flush() -> Result<usize> {
+    let _guard = FLUSH_LOCK.lock().await;
+    let batch = BUFFER
+        .lock()
+        .map(|mut buffer| std::mem::take(&mut *buffer))
+        .unwrap_or_default();
+    if batch.is_empty() {
+        return Ok(0);
+    }
+
+    let mut inserted = 0;
+    for (index, chunk) in batch.chunks(MAX_BATCH).enumerate() {
+        if let Err(e) = insert_batch(chunk).await {
+            if let Ok(mut buffer) = BUFFER.lock() {
+                let failed = batch[index * MAX_BATCH..].iter().cloned();
+                buffer.splice(0..0, failed);
+            }
+            return Err(e);
+        }
+        inserted += chunk.len();
+    }
+    Ok(inserted)
+}
+
+/// Starts the periodic flush loop.
+pub fn start_flusher() {
+    let task = tauri::async_runtime::spawn(async {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if pending() == 0 {
+                continue;
+            }
+            if let Err(e) = flush().await {
+                tracing::warn!("Failed to flush log buffer: {}", e);
+            }
+        }
+    });
+    shutdown::track("log-buffer", task);
+}
+
+/// Inserts one batch with a single statement.
+async fn insert_batch(batch: &[PendingLog]) -> Result<()> {
+    let pool = get_pool_ref()?;
+
+    let mut levels = Vec::with_capacity(batch.len());
+    let mut messages = Vec::with_capacity(batch.len());
+    let mut metadata = Vec::with_capacity(batch.len());
+    let mut user_ids = Vec::with_capacity(batch.len());
+    let mut workspace_ids = Vec::with_capacity(batch.len());
+    let mut created_at = Vec::with_capacity(batch.len());
+    // Tag lists differ in length, which a two-dimensional array cannot hold,
+    // so each is passed as a JSON array and expanded in the query.
+    let mut tags = Vec::with_capacity(batch.len());
+    for entry in batch {
+        levels.push(entry.level.clone());
+        messages.push(entry.message.clone());
+        metadata.push(entry.metadata.clone());
+        user_ids.push(entry.user_id);
+        workspace_ids.push(entry.workspace_id);
+        created_at.push(entry.created_at);
+        tags.push(serde_json::json!(entry.tags));
+    }
+
+    let query = sqlx::query(
+        r#"
+        INSERT INTO app_logs (level, message, metadata, user_id, workspace_id, created_at, tags)
+        SELECT level, message, metadata, user_id, workspace_id, created_at,
+               ARRAY(SELECT jsonb_array_elements_text(tags))
+        FROM UNNEST($1::text[], $2::text[], $3::jsonb[], $4::uuid[], $5::uuid[], $6::timestamptz[], $7::jsonb[])
+            AS entries(level, message, metadata, user_id, workspace_id, created_at, tags)
+        "#,
+    )
+    .bind(levels)
+    .bind(messages)
+    .bind(metadata)
+    .bind(user_ids)
+    .bind(workspace_ids)
+    .bind(created_at)
+    .bind(tags);
+    query_stats::timed(query.sql(), query.execute(pool.as_ref())).await?;
+
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Live tailing of log files, streamed to the frontend as events.
+//!
+//! A tail session reads the newest file in the log directory whose name starts
+//! with a prefix, publishes its last lines and, when following, keeps
+//! publishing appended lines as [`AppEvent::LogTailLines`] until it is
+//! stopped. Rotation to a newer file and truncation are picked up between
+//! polls.
+
+use super::handlers::parse_log_line;
+use super::tail::ReverseLines;
+use super::LogEntry;
+use crate::events::{self, AppEvent};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use uuid::Uuid;
+
+/// Interval between checks for appended lines.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Lines published when a session starts.
+pub const INITIAL_LINES: usize = 100;
+
+/// Lines per published event, so a burst of output is split into batches.
+const MAX_LINES_PER_EVENT: usize = 500;
+
+/// Sessions that may follow files at the same time.
+const MAX_SESSIONS: usize = 8;
+
+/// Running follow sessions by id.
+static SESSIONS: Lazy<Mutex<HashMap<Uuid, JoinHandle<()>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A tailed line with its parsed entry, when the line is in a known format.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailedLine {
+    pub text: String,
+    pub entry: Option<LogEntry>,
+}
+
+impl TailedLine {
+    fn new(text: String) -> Self {
+        let entry = parse_log_line(&text);
+        Self { text, entry }
+    }
+}
+
+/// Returns the most recently modified file in `dir` whose name starts with
+/// `prefix`.
+pub fn newest_file(dir: &Path, prefix: &str) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata
+                .is_file()
+                .then(|| (metadata.modified().ok(), entry.path()))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, path)| path)
+}
+
+/// Rejects prefixes that could select files outside the log directory.
+fn validate_prefix(prefix: &str) -> Result<(), String> {
+    if prefix.trim().is_empty() {
+        return Err("Log file prefix cannot be empty".to_string());
+    }
+    if prefix.contains(['/', '\\']) || prefix.contains("..") {
+        return Err("Log file prefix cannot contain path separators".to_string());
+    }
+    Ok(())
+}
+
+/// Reads lines appended to a file since the last read.
+pub struct Follower {
+    path: PathBuf,
+    position: u64,
+    /// Bytes after the last newline, completed by a later read.
+    partial: Vec<u8>,
+}
+
+impl Follower {
+    /// Follows `path` from `position`.
+    pub fn new(path: PathBuf, position: u64) -> Self {
+        Self {
+            path,
+            position,
+            partial: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the complete lines appended since the last call.
+    ///
+    /// A file shorter than the position read so far was truncated and is read
+    /// again from the start.
+    pub fn read_new(&mut self) -> io::Result<Vec<String>> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        if len < self.position {
+            self.position = 0;
+            self.partial.clear();
+        }
+        if len == self.position {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.position))?;
+        let mut appended = Vec::with_capacity((len - self.position) as usize);
+        file.take(len - self.position).read_to_end(&mut appended)?;
+        self.position += appended.len() as u64;
+        self.partial.append(&mut appended);
+
+        let Some(end) = self.partial.iter().rposition(|byte| *byte == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+
+        Ok(complete
+            .split(|byte| *byte == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .filter(|line| !line.is_empty())
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+            .collect())
+    }
+}
+
+fn publish_lines(tail_id: Uuid, file: &Path, lines: Vec<String>) {
+    let file = file.to_string_lossy().into_owned();
+    let mut lines = lines.into_iter().map(TailedLine::new).peekable();
+    while lines.peek().is_some() {
+        events::publish(AppEvent::LogTailLines {
+            tail_id,
+            file: file.clone(),
+            lines: lines.by_ref().take(MAX_LINES_PER_EVENT).collect(),
+        });
+    }
+}
+
+fn publish_ended(tail_id: Uuid, error: Option<String>) {
+    events::publish(AppEvent::LogTailEnded { tail_id, error });
+}
+
+/// Starts tailing the newest file in `dir` matching `prefix`.
+///
+/// The last [`INITIAL_LINES`] lines are published first. Without `follow` the
+/// session ends there; otherwise appended lines are published until
+/// [`stop`] is called. Returns the session id carried by every event.
+pub fn start(dir: &Path, prefix: &str, follow: bool) -> Result<Uuid, String> {
+    validate_prefix(prefix)?;
+    let path = newest_file(dir, prefix)
+        .ok_or_else(|| format!("No log file found with prefix '{}'", prefix))?;
+
+    let mut sessions = SESSIONS
+        .lock()
+        .map_err(|_| "Log tail sessions are unavailable".to_string())?;
+    sessions.retain(|_, handle| !handle.inner().is_finished());
+    if follow && sessions.len() >= MAX_SESSIONS {
+        return Err(format!(
+            "Cannot follow more than {} log files at once",
+            MAX_SESSIONS
+        ));
+    }
+
+    let reader =
+        ReverseLines::open(&path).map_err(|e| format!("Failed to open log file: {}", e))?;
+    let position = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    let mut initial: Vec<String> = reader.map_while(Result::ok).take(INITIAL_LINES).collect();
+    initial.reverse();
+
+    let tail_id = Uuid::new_v4();
+    publish_lines(tail_id, &path, initial);
+    if !follow {
+        publish_ended(tail_id, None);
+        return Ok(tail_id);
+    }
+
+    let dir = dir.to_path_buf();
+    let prefix = prefix.to_string();
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut follower = Follower::new(path, position);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let lines = match follower.read_new() {
+                Ok(lines) => lines,
+                Err(e) => {
+                    publish_ended(tail_id, Some(format!("Failed to read log file: {}", e)));
+                    break;
+                }
+            };
+            publish_lines(tail_id, follower.path(), lines);
+
+            // After rotation the old file stops growing; continue with the new one.
+            if let Some(newest) = newest_file(&dir, &prefix) {
+                if newest != follower.path() {
+                    if let Ok(lines) = follower.read_new() {
+                        publish_lines(tail_id, follower.path(), lines);
+                    }
+                    follower = Follower::new(newest, 0);
+                }
+            }
+        }
+        if let Ok(mut sessions) = SESSIONS.lock() {
+            sessions.remove(&tail_id);
+        }
+    });
+    sessions.insert(tail_id, handle);
+
+    Ok(tail_id)
+}
+
+/// Stops a follow session, returning whether it was running.
+pub fn stop(tail_id: Uuid) -> bool {
+    let handle = SESSIONS
+        .lock()
+        .ok()
+        .and_then(|mut sessions| sessions.remove(&tail_id));
+    match handle {
+        Some(handle) => {
+            handle.abort();
+            publish_ended(tail_id, None);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn reads_appended_complete_lines_only() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"old\n").unwrap();
+        let mut follower = Follower::new(file.path().to_path_buf(), 4);
+        assert!(follower.read_new().unwrap().is_empty());
+
+        file.write_all(b"one\r\ntw").unwrap();
+        assert_eq!(follower.read_new().unwrap(), vec!["one"]);
+
+        file.write_all(b"o\nthree\n").unwrap();
+        assert_eq!(follower.read_new().unwrap(), vec!["two", "three"]);
+    }
+
+    #[test]
+    fn restarts_after_truncation() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"first\nsecond\n").unwrap();
+        let mut follower = Follower::new(file.path().to_path_buf(), 13);
+
+        file.as_file().set_len(0).unwrap();
+        file.as_file_mut().seek(SeekFrom::Start(0)).unwrap();
+        file.write_all(b"fresh\n").unwrap();
+        assert_eq!(follower.read_new().unwrap(), vec!["fresh"]);
+    }
+
+    #[test]
+    fn selects_the_newest_matching_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("other.log"), "x\n").unwrap();
+        fs::write(dir.path().join("ez-tauri.log.2024-03-14"), "old\n").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(dir.path().join("ez-tauri.log.2024-03-15"), "new\n").unwrap();
+
+        let newest = newest_file(dir.path(), "ez-tauri").unwrap();
+        assert!(newest.ends_with("ez-tauri.log.2024-03-15"));
+        assert!(newest_file(dir.path(), "missing").is_none());
+    }
+
+    #[test]
+    fn rejects_prefixes_with_path_components() {
+        assert!(validate_prefix("ez-tauri").is_ok());
+        assert!(validate_prefix("").is_err());
+        assert!(validate_prefix("../secrets").is_err());
+        assert!(validate_prefix("logs/app").is_err());
+    }
+}
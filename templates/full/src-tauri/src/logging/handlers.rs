@@ -1,16 +1,20 @@
 //! Tauri command handlers for log management and retrieval.
 
-use crate::logging::{config::AppLogConfig, LogEntry, LogLevel};
+use crate::command_context::CommandContext;
+use crate::logging::aggregate::{Aggregator, LogAggregation, LogGroupBy, LogRange, LogSource};
+use crate::logging::{config::AppLogConfig, tail::ReverseLines, LogEntry, LogLevel};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use tracing::{debug, error, info};
+use uuid::Uuid;
 
 /// Query parameters for filtering log entries.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LogQueryParams {
     pub level: Option<String>,
@@ -20,6 +24,9 @@ pub struct LogQueryParams {
     pub message_contains: Option<String>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
+    /// Reads files backwards and stops once the page is filled. `total_count`
+    /// then only counts the entries read, not every matching entry.
+    pub tail: Option<bool>,
 }
 
 /// Response structure for log queries with pagination info.
@@ -31,6 +38,29 @@ pub struct LogResponse {
     pub has_more: bool,
 }
 
+/// Position between two log entries, identified by file and byte offset.
+///
+/// Files are only appended to, so the entries before a cursor never change
+/// while the application keeps logging.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogCursor {
+    /// Name of the log file within the log directory.
+    pub file: String,
+    /// Offset of the first byte after the position.
+    pub offset: u64,
+}
+
+/// A page of log entries, newest first.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogPage {
+    pub logs: Vec<LogEntry>,
+    /// Where the next, older page starts; `None` once the oldest entry has
+    /// been read. The last page may be empty.
+    pub next_cursor: Option<LogCursor>,
+}
+
 /// Retrieves the current logging configuration from file or environment.
 #[tauri::command]
 pub async fn get_log_config() -> Result<AppLogConfig, String> {
@@ -87,18 +117,14 @@ pub async fn get_log_entries(params: LogQueryParams) -> Result<LogResponse, Stri
     }
 
     let mut log_files = get_log_files(&log_dir)?;
-    log_files.sort_by(|a, b| {
-        let a_metadata = a.metadata().ok();
-        let b_metadata = b.metadata().ok();
+    sort_newest_first(&mut log_files);
 
-        match (a_metadata, b_metadata) {
-            (Some(a_meta), Some(b_meta)) => b_meta
-                .modified()
-                .unwrap_or(std::time::UNIX_EPOCH)
-                .cmp(&a_meta.modified().unwrap_or(std::time::UNIX_EPOCH)),
-            _ => std::cmp::Ordering::Equal,
-        }
-    });
+    let offset = params.offset.unwrap_or(0);
+    let limit = params.limit.unwrap_or(100).min(1000);
+
+    if params.tail.unwrap_or(false) {
+        return Ok(tail_log_entries(&log_files, &params, offset, limit));
+    }
 
     let mut all_logs = Vec::new();
 
@@ -113,8 +139,6 @@ pub async fn get_log_entries(params: LogQueryParams) -> Result<LogResponse, Stri
     all_logs.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
 
     let total_count = all_logs.len();
-    let offset = params.offset.unwrap_or(0);
-    let limit = params.limit.unwrap_or(100).min(1000);
 
     let end_index = (offset + limit).min(total_count);
     let paginated_logs = if offset < total_count {
@@ -132,6 +156,230 @@ pub async fn get_log_entries(params: LogQueryParams) -> Result<LogResponse, Stri
     })
 }
 
+/// Retrieves a page of log entries ending at `cursor`, for infinite scrolling.
+///
+/// Without a cursor the page starts at the newest entry. Passing back the
+/// returned `next_cursor` fetches the next older page; unlike `offset`, it
+/// keeps pointing at the same entry while new ones are written, so pages
+/// never overlap. `offset` and `tail` are ignored.
+#[tauri::command]
+pub async fn get_log_page(
+    params: LogQueryParams,
+    cursor: Option<LogCursor>,
+) -> Result<LogPage, String> {
+    debug!("Getting log page at {:?} with params: {:?}", cursor, params);
+
+    let log_dir = get_log_directory();
+    if !log_dir.exists() {
+        return Ok(LogPage {
+            logs: vec![],
+            next_cursor: None,
+        });
+    }
+
+    let mut log_files = get_log_files(&log_dir)?;
+    sort_newest_first(&mut log_files);
+
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    read_log_page(&log_files, &params, cursor, limit)
+}
+
+/// Reads matching entries backwards from `cursor` through `log_files`,
+/// ordered newest first, until `limit` entries are collected.
+fn read_log_page(
+    log_files: &[PathBuf],
+    params: &LogQueryParams,
+    cursor: Option<LogCursor>,
+    limit: usize,
+) -> Result<LogPage, String> {
+    let (first, mut end) = match cursor {
+        Some(cursor) => {
+            let index = log_files
+                .iter()
+                .position(|path| file_name(path) == cursor.file)
+                .ok_or_else(|| format!("Log file '{}' no longer exists", cursor.file))?;
+            (index, cursor.offset)
+        }
+        None => (0, u64::MAX),
+    };
+
+    let mut logs = Vec::with_capacity(limit);
+    for log_file in &log_files[first..] {
+        let mut lines = match ReverseLines::open_at(log_file, end) {
+            Ok(lines) => lines,
+            Err(e) => {
+                debug!("Skipping unreadable log file {:?}: {}", log_file, e);
+                continue;
+            }
+        };
+        end = u64::MAX;
+
+        while let Some(Ok((offset, line))) = lines.next_with_offset() {
+            let Some(entry) = parse_log_line(&line) else {
+                continue;
+            };
+            logs.extend(filter_logs(vec![entry], params));
+            if logs.len() == limit {
+                return Ok(LogPage {
+                    logs,
+                    next_cursor: Some(LogCursor {
+                        file: file_name(log_file),
+                        offset,
+                    }),
+                });
+            }
+        }
+    }
+
+    Ok(LogPage {
+        logs,
+        next_cursor: None,
+    })
+}
+
+/// Collects the newest matching entries by reading files from their ends.
+///
+/// Reading stops after one entry past the requested page, so the cost depends
+/// on the page size rather than on the size of the log files.
+fn tail_log_entries(
+    log_files: &[PathBuf],
+    params: &LogQueryParams,
+    offset: usize,
+    limit: usize,
+) -> LogResponse {
+    let wanted = offset + limit + 1;
+    let mut matched = Vec::with_capacity(wanted);
+
+    'files: for log_file in log_files.iter().take(5) {
+        let lines = match ReverseLines::open(log_file) {
+            Ok(lines) => lines,
+            Err(e) => {
+                debug!("Skipping unreadable log file {:?}: {}", log_file, e);
+                continue;
+            }
+        };
+
+        for line in lines.map_while(Result::ok) {
+            if let Some(entry) = parse_log_line(&line) {
+                matched.extend(filter_logs(vec![entry], params));
+                if matched.len() >= wanted {
+                    break 'files;
+                }
+            }
+        }
+    }
+
+    let has_more = matched.len() > offset + limit;
+    let total_count = matched.len();
+    let logs = matched.into_iter().skip(offset).take(limit).collect();
+
+    LogResponse {
+        logs,
+        total_count,
+        has_more,
+    }
+}
+
+/// Counts log entries grouped by `group_by`: level, target, and time in
+/// buckets of `interval` (e.g. `5m`, an hour by default), so charts do not
+/// need every entry.
+///
+/// Reads the log files, or the `app_logs` table when `source` is
+/// `database`, where callers without the admin role only count their own
+/// entries.
+#[tauri::command]
+pub async fn aggregate_logs(
+    context: CommandContext,
+    group_by: Vec<LogGroupBy>,
+    interval: Option<String>,
+    range: Option<LogRange>,
+    source: Option<LogSource>,
+) -> Result<LogAggregation, String> {
+    debug!("Aggregating logs by {:?} every {:?}", group_by, interval);
+
+    let range = range.unwrap_or_default();
+    let mut aggregator = Aggregator::new(group_by, interval.as_deref(), range)?;
+    let source = source.unwrap_or_default();
+    match source {
+        LogSource::Files => {
+            let log_dir = get_log_directory();
+            if log_dir.exists() {
+                let log_files = get_log_files(&log_dir)?;
+                aggregator = tokio::task::spawn_blocking(move || {
+                    aggregate_log_files(&log_files, &mut aggregator);
+                    aggregator
+                })
+                .await
+                .map_err(|e| format!("Log aggregation failed: {}", e))?;
+            }
+        }
+        #[cfg(feature = "database")]
+        LogSource::Database => {
+            let visible = context.visible_user().map_err(|e| e.to_string())?;
+            crate::logging::aggregate::aggregate_database(
+                &mut aggregator,
+                visible,
+                crate::workspace::current(),
+            )
+            .await?;
+        }
+        #[cfg(not(feature = "database"))]
+        LogSource::Database => {
+            let _ = context;
+            return Err("Database logs require the database feature".to_string());
+        }
+    }
+    aggregator.finish(source)
+}
+
+/// Counts every entry of `log_files` into `aggregator`, skipping files last
+/// written before the start of its range.
+fn aggregate_log_files(log_files: &[PathBuf], aggregator: &mut Aggregator) {
+    for log_file in log_files {
+        let start = aggregator.range().start;
+        let modified = log_file
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .map(DateTime::<Utc>::from);
+        if let (Some(start), Ok(modified)) = (start, modified) {
+            if modified < start {
+                continue;
+            }
+        }
+
+        let file = match fs::File::open(log_file) {
+            Ok(file) => file,
+            Err(e) => {
+                debug!("Skipping unreadable log file {:?}: {}", log_file, e);
+                continue;
+            }
+        };
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if let Some(entry) = parse_log_line(&line) {
+                aggregator.add(&entry);
+            }
+        }
+    }
+}
+
+/// Tails the newest log file whose name starts with `prefix`.
+///
+/// The last lines are published as `log:tail` events; with `follow`, lines
+/// appended later are published too until `stop_log_tail` is called. A
+/// `log:tail-ended` event marks the end of the session. Returns the session
+/// id carried by the events.
+#[tauri::command]
+pub async fn tail_log_file(prefix: String, follow: bool) -> Result<Uuid, String> {
+    debug!("Tailing log file with prefix '{}' (follow: {})", prefix, follow);
+    crate::logging::follow::start(&get_log_directory(), &prefix, follow)
+}
+
+/// Stops a log tail session started with `follow`.
+#[tauri::command]
+pub async fn stop_log_tail(tail_id: Uuid) -> Result<bool, String> {
+    Ok(crate::logging::follow::stop(tail_id))
+}
+
 /// Clears log files older than the specified number of days.
 #[tauri::command]
 pub async fn clear_old_logs(days_to_keep: u32) -> Result<String, String> {
@@ -278,6 +526,28 @@ fn get_log_config_path() -> PathBuf {
     crate::logging::default_log_config_path()
 }
 
+/// Orders log files by modification time, newest first.
+fn sort_newest_first(log_files: &mut [PathBuf]) {
+    log_files.sort_by(|a, b| {
+        let a_metadata = a.metadata().ok();
+        let b_metadata = b.metadata().ok();
+
+        match (a_metadata, b_metadata) {
+            (Some(a_meta), Some(b_meta)) => b_meta
+                .modified()
+                .unwrap_or(std::time::UNIX_EPOCH)
+                .cmp(&a_meta.modified().unwrap_or(std::time::UNIX_EPOCH)),
+            _ => std::cmp::Ordering::Equal,
+        }
+    });
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
 fn get_log_files(log_dir: &PathBuf) -> Result<Vec<PathBuf>, String> {
     let mut log_files = Vec::new();
 
@@ -302,20 +572,95 @@ fn parse_log_content(content: &str, _params: &LogQueryParams) -> Vec<LogEntry> {
     let mut logs = Vec::new();
 
     for line in content.lines() {
-        // Try to parse as JSON first (structured logs)
-        if let Ok(entry) = serde_json::from_str::<LogEntry>(line) {
+        if let Some(entry) = parse_log_line(line) {
             logs.push(entry);
-        } else {
-            // Try to parse plain text logs (fallback)
-            if let Some(entry) = parse_plain_text_log(line) {
-                logs.push(entry);
-            }
         }
     }
 
     logs
 }
 
+/// Parses one log file line written in any of the supported formats.
+pub(crate) fn parse_log_line(line: &str) -> Option<LogEntry> {
+    serde_json::from_str::<LogEntry>(line)
+        .ok()
+        .or_else(|| parse_tracing_json_log(line))
+        .or_else(|| parse_plain_text_log(line))
+}
+
+/// A line written by the `tracing_subscriber` JSON formatter.
+#[derive(Deserialize)]
+struct TracingJsonLine {
+    timestamp: DateTime<Utc>,
+    level: String,
+    #[serde(default)]
+    target: Option<String>,
+    #[serde(default)]
+    fields: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    span: Option<TracingJsonSpan>,
+    #[serde(default)]
+    spans: Vec<TracingJsonSpan>,
+    #[serde(rename = "threadName", default)]
+    thread_name: Option<String>,
+    #[serde(default)]
+    filename: Option<String>,
+    #[serde(default)]
+    line_number: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct TracingJsonSpan {
+    name: String,
+    #[serde(flatten)]
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Maps a `tracing_subscriber` JSON line onto a [`LogEntry`].
+///
+/// The span is reported as the `outer:inner` path of entered spans. Span
+/// fields are merged into the entry's fields; event fields take precedence.
+fn parse_tracing_json_log(line: &str) -> Option<LogEntry> {
+    let parsed: TracingJsonLine = serde_json::from_str(line).ok()?;
+
+    let mut fields: HashMap<String, serde_json::Value> = parsed.fields.into_iter().collect();
+    let message = match fields.remove("message") {
+        Some(serde_json::Value::String(message)) => message,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+
+    let spans = if parsed.spans.is_empty() {
+        parsed.span.into_iter().collect()
+    } else {
+        parsed.spans
+    };
+    let span = (!spans.is_empty()).then(|| {
+        spans
+            .iter()
+            .map(|span| span.name.as_str())
+            .collect::<Vec<_>>()
+            .join(":")
+    });
+    for span in spans {
+        for (key, value) in span.fields {
+            fields.entry(key).or_insert(value);
+        }
+    }
+
+    Some(LogEntry {
+        timestamp: parsed.timestamp,
+        level: parsed.level,
+        target: parsed.target.unwrap_or_else(|| "unknown".to_string()),
+        message,
+        fields,
+        span,
+        thread_name: parsed.thread_name,
+        file: parsed.filename,
+        line: parsed.line_number,
+    })
+}
+
 fn parse_plain_text_log(line: &str) -> Option<LogEntry> {
     let parts: Vec<&str> = line.splitn(4, ' ').collect();
     if parts.len() < 4 {
@@ -388,3 +733,84 @@ fn filter_logs(mut logs: Vec<LogEntry>, params: &LogQueryParams) -> Vec<LogEntry
 
     logs
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tracing_json_lines_with_spans_and_source() {
+        let line = r#"{"timestamp":"2024-03-15T12:00:00.123456Z","level":"WARN","fields":{"message":"Slow command","duration_ms":1200.5},"target":"ez_tauri_lib::command_trace","filename":"src/command_trace.rs","line_number":115,"threadName":"tokio-runtime-worker","span":{"command":"get_logs","name":"command"},"spans":[{"name":"request","id":7},{"command":"get_logs","name":"command"}]}"#;
+
+        let entry = parse_log_line(line).unwrap();
+        assert_eq!(entry.level, "WARN");
+        assert_eq!(entry.target, "ez_tauri_lib::command_trace");
+        assert_eq!(entry.message, "Slow command");
+        assert_eq!(entry.span.as_deref(), Some("request:command"));
+        assert_eq!(entry.thread_name.as_deref(), Some("tokio-runtime-worker"));
+        assert_eq!(entry.file.as_deref(), Some("src/command_trace.rs"));
+        assert_eq!(entry.line, Some(115));
+        assert_eq!(entry.fields["duration_ms"], serde_json::json!(1200.5));
+        assert_eq!(entry.fields["command"], serde_json::json!("get_logs"));
+        assert!(!entry.fields.contains_key("message"));
+    }
+
+    #[test]
+    fn falls_back_to_serialized_entries_and_plain_text() {
+        let entry = parse_log_line(
+            r#"{"timestamp":"2024-03-15T12:00:00Z","level":"info","target":"app","message":"hello","fields":{},"span":null,"threadName":null,"file":null,"line":null}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.message, "hello");
+
+        let entry = parse_log_line("2024-03-15 12:00:00.000 INFO database: connected").unwrap();
+        assert_eq!(entry.target, "database");
+        assert_eq!(entry.message, "connected");
+
+        assert!(parse_log_line("garbage").is_none());
+    }
+
+    fn append_entries(path: &Path, messages: std::ops::Range<usize>) {
+        use std::io::Write;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        for n in messages {
+            writeln!(file, "2024-03-15 12:00:00.000 INFO app: entry {}", n).unwrap();
+        }
+    }
+
+    fn messages(page: &LogPage) -> Vec<String> {
+        page.logs.iter().map(|log| log.message.clone()).collect()
+    }
+
+    #[test]
+    fn cursor_pages_stay_stable_while_logs_are_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let older = dir.path().join("app.1.log");
+        let newer = dir.path().join("app.2.log");
+        append_entries(&older, 0..2);
+        append_entries(&newer, 2..5);
+        let files = vec![newer.clone(), older];
+        let params = LogQueryParams::default();
+
+        let first = read_log_page(&files, &params, None, 2).unwrap();
+        assert_eq!(messages(&first), ["entry 4", "entry 3"]);
+
+        append_entries(&newer, 5..8);
+        let second = read_log_page(&files, &params, first.next_cursor, 2).unwrap();
+        assert_eq!(messages(&second), ["entry 2", "entry 1"]);
+
+        let third = read_log_page(&files, &params, second.next_cursor, 2).unwrap();
+        assert_eq!(messages(&third), ["entry 0"]);
+        assert!(third.next_cursor.is_none());
+
+        let missing = LogCursor {
+            file: "gone.log".to_string(),
+            offset: 0,
+        };
+        assert!(read_log_page(&files, &params, Some(missing), 2).is_err());
+    }
+}
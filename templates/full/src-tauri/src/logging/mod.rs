@@ -10,6 +10,7 @@ use std::env;
 use std::fs;
 use std::path::PathBuf;
 use tracing::{error, info, warn};
+use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{
     fmt::{self, format::FmtSpan},
@@ -18,12 +19,25 @@ use tracing_subscriber::{
     EnvFilter, Layer,
 };
 
+pub mod aggregate;
 pub mod config;
+#[cfg(feature = "database")]
+pub mod db_sink;
+pub mod follow;
 pub mod handlers;
+#[cfg(feature = "database")]
+pub mod partitions;
+#[cfg(feature = "database")]
+pub mod retention;
+pub mod tail;
 
 /// Ensures logging system is initialized only once.
 static LOG_INITIALIZED: Lazy<std::sync::Mutex<bool>> = Lazy::new(|| std::sync::Mutex::new(false));
 
+/// Keeps the non-blocking file writer alive; dropping it flushes pending lines.
+static FILE_WRITER_GUARD: Lazy<std::sync::Mutex<Option<WorkerGuard>>> =
+    Lazy::new(|| std::sync::Mutex::new(None));
+
 /// Log levels supported by the application.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -95,7 +109,10 @@ pub struct LogConfig {
     pub level: LogLevel,
     pub console_enabled: bool,
     pub file_enabled: bool,
+    /// Writes console output as JSON.
     pub json_format: bool,
+    /// Writes log files as JSON, so entries can be read back with all fields.
+    pub file_json_format: bool,
     pub log_dir: PathBuf,
     pub file_prefix: String,
     pub rotation: Rotation,
@@ -109,6 +126,7 @@ impl Default for LogConfig {
             console_enabled: true,
             file_enabled: true,
             json_format: false,
+            file_json_format: true,
             log_dir: default_log_dir(),
             file_prefix: "ez-tauri".to_string(),
             rotation: Rotation::DAILY,
@@ -160,6 +178,10 @@ pub fn init_logging(config: LogConfig) -> Result<()> {
             &config.log_dir,
             &format!("{}.log", config.file_prefix),
         );
+        let (file_writer, writer_guard) = tracing_appender::non_blocking(file_appender);
+        if let Ok(mut slot) = FILE_WRITER_GUARD.lock() {
+            *slot = Some(writer_guard);
+        }
 
         let file_layer = fmt::layer()
             .with_target(true)
@@ -167,9 +189,9 @@ pub fn init_logging(config: LogConfig) -> Result<()> {
             .with_file(true)
             .with_line_number(true)
             .with_span_events(FmtSpan::CLOSE)
-            .with_writer(file_appender);
+            .with_writer(file_writer);
 
-        if config.json_format {
+        if config.file_json_format {
             layers.push(file_layer.json().boxed());
         } else {
             layers.push(file_layer.boxed());
@@ -184,8 +206,12 @@ pub fn init_logging(config: LogConfig) -> Result<()> {
     *guard = true;
 
     info!(
-        "Logging system initialized - Level: {:?}, Console: {}, File: {}, JSON: {}",
-        config.level, config.console_enabled, config.file_enabled, config.json_format
+        "Logging system initialized - Level: {:?}, Console: {}, File: {}, JSON: {}, File JSON: {}",
+        config.level,
+        config.console_enabled,
+        config.file_enabled,
+        config.json_format,
+        config.file_json_format
     );
 
     if config.file_enabled {
@@ -195,17 +221,32 @@ pub fn init_logging(config: LogConfig) -> Result<()> {
     Ok(())
 }
 
-/// Returns the default log directory for the application.
-pub(crate) fn default_log_dir() -> PathBuf {
+/// Flushes buffered file log lines and stops the background writer.
+///
+/// Called during shutdown; events logged afterwards are only written to the console.
+pub fn flush() {
+    if let Ok(mut slot) = FILE_WRITER_GUARD.lock() {
+        slot.take();
+    }
+}
+
+/// Returns `sub` inside the application's data directory, or inside the
+/// working directory on platforms without one.
+pub(crate) fn app_data_dir(sub: &str) -> PathBuf {
     ProjectDirs::from("com", "tavuc", "eztauri")
-        .map(|dirs| dirs.data_dir().join("logs"))
+        .map(|dirs| dirs.data_dir().join(sub))
         .unwrap_or_else(|| {
             std::env::current_dir()
                 .unwrap_or_else(|_| PathBuf::from("."))
-                .join("logs")
+                .join(sub)
         })
 }
 
+/// Returns the default log directory for the application.
+pub(crate) fn default_log_dir() -> PathBuf {
+    app_data_dir("logs")
+}
+
 /// Returns the default path for logging configuration file.
 pub(crate) fn default_log_config_path() -> PathBuf {
     ProjectDirs::from("com", "tavuc", "eztauri")
@@ -292,6 +333,8 @@ pub fn init_logging_from_env() -> Result<()> {
         .and_then(|value| value.parse::<bool>().ok());
     let json_format = json_format_override
         .unwrap_or(matches!(env_config.console.format, config::LogFormat::Json));
+    let file_json_format = json_format_override
+        .unwrap_or(matches!(env_config.file.format, config::LogFormat::Json));
 
     let log_directory_override = env::var("LOG_DIRECTORY")
         .ok()
@@ -321,6 +364,7 @@ pub fn init_logging_from_env() -> Result<()> {
         console_enabled: env_config.enabled && env_config.console.enabled,
         file_enabled: env_config.enabled && env_config.file.enabled,
         json_format,
+        file_json_format,
         log_dir,
         file_prefix,
         rotation: env_config.file.rotation.clone().into(),
@@ -0,0 +1,191 @@
+//! Monthly partitions of the `app_logs` table.
+//!
+//! The migrations partition `app_logs` by `created_at` with a default
+//! partition and the `app_logs_ensure_partition` SQL function. Partitions
+//! named `app_logs_pYYYYMM` are created here ahead of the months they cover,
+//! and log retention drops whole partitions once every entry in them is past
+//! its retention period.
+
+use crate::database::query_stats;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use sqlx::{Execute, PgPool};
+
+/// Months after the current one to create partitions for.
+pub const PARTITIONS_AHEAD: u32 = 2;
+
+/// Prefix of monthly partition names.
+const PARTITION_PREFIX: &str = "app_logs_p";
+
+/// A monthly partition of `app_logs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    pub name: String,
+    /// First day of the month the partition holds.
+    pub month: NaiveDate,
+}
+
+impl Partition {
+    pub fn for_month(date: NaiveDate) -> Self {
+        let month = first_of_month(date);
+        Self {
+            name: format!("{}{}", PARTITION_PREFIX, month.format("%Y%m")),
+            month,
+        }
+    }
+
+    /// Parses a partition name, ignoring tables that are not monthly partitions.
+    pub fn parse(name: &str) -> Option<Self> {
+        let suffix = name.strip_prefix(PARTITION_PREFIX)?;
+        if suffix.len() != 6 || !suffix.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let year = suffix[..4].parse().ok()?;
+        let month = suffix[4..].parse().ok()?;
+        let month = NaiveDate::from_ymd_opt(year, month, 1)?;
+        Some(Self {
+            name: name.to_string(),
+            month,
+        })
+    }
+
+    /// Exclusive upper bound of the partition's `created_at` range.
+    pub fn end(&self) -> DateTime<Utc> {
+        let next = self.month + Months::new(1);
+        next.and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc()
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("every month has a first day")
+}
+
+/// Creates the partitions for the current month and the following
+/// [`PARTITIONS_AHEAD`] months, returning the names of those created.
+pub async fn ensure_partitions(pool: &PgPool) -> Result<Vec<String>> {
+    let current = Partition::for_month(Utc::now().date_naive());
+    let mut created = Vec::new();
+
+    for offset in 0..=PARTITIONS_AHEAD {
+        let partition = Partition::for_month(current.month + Months::new(offset));
+        let query = sqlx::query_scalar!(
+            r#"SELECT app_logs_ensure_partition($1) AS "created!""#,
+            partition.month,
+        );
+        if query_stats::timed(query.sql(), query.fetch_one(pool)).await? {
+            created.push(partition.name);
+        }
+    }
+
+    if !created.is_empty() {
+        tracing::info!("Created log partitions: {}", created.join(", "));
+    }
+    Ok(created)
+}
+
+/// Lists the monthly partitions attached to `app_logs`, oldest first.
+pub async fn list_partitions(pool: &PgPool) -> Result<Vec<Partition>> {
+    let query = sqlx::query_scalar!(
+        r#"
+        SELECT child.relname::TEXT AS "name!"
+        FROM pg_inherits
+        JOIN pg_class child ON child.oid = pg_inherits.inhrelid
+        WHERE pg_inherits.inhparent = 'app_logs'::regclass
+        "#
+    );
+    let names = query_stats::timed(query.sql(), query.fetch_all(pool)).await?;
+
+    let mut partitions: Vec<Partition> = names
+        .iter()
+        .filter_map(|name| Partition::parse(name))
+        .collect();
+    partitions.sort_by_key(|partition| partition.month);
+    Ok(partitions)
+}
+
+/// Returns the partitions whose whole range is older than `cutoff`.
+pub fn expired(partitions: &[Partition], cutoff: DateTime<Utc>) -> Vec<Partition> {
+    partitions
+        .iter()
+        .filter(|partition| partition.end() <= cutoff)
+        .cloned()
+        .collect()
+}
+
+/// Counts the entries in `partition`.
+pub async fn count_entries(pool: &PgPool, partition: &Partition) -> Result<u64> {
+    // The name is only ever built by `Partition`, so it is safe to inline.
+    let sql = format!("SELECT COUNT(*) FROM \"{}\"", partition.name);
+    let count: i64 = sqlx::query_scalar(&sql).fetch_one(pool).await?;
+    Ok(count as u64)
+}
+
+/// Detaches and drops `partition`, returning the number of entries removed.
+pub async fn drop_partition(pool: &PgPool, partition: &Partition) -> Result<u64> {
+    if Partition::parse(&partition.name).as_ref() != Some(partition) {
+        return Err(anyhow!("Not a log partition: {}", partition.name));
+    }
+
+    let mut tx = pool.begin().await?;
+    let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", partition.name))
+        .fetch_one(&mut *tx)
+        .await?;
+    sqlx::query(&format!(
+        "ALTER TABLE app_logs DETACH PARTITION \"{}\"",
+        partition.name
+    ))
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(&format!("DROP TABLE \"{}\"", partition.name))
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Dropped log partition {} ({} entries)",
+        partition.name,
+        count
+    );
+    Ok(count as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn names_and_parses_monthly_partitions() {
+        let partition = Partition::for_month(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+        assert_eq!(partition.name, "app_logs_p202403");
+        assert_eq!(
+            Partition::parse("app_logs_p202403"),
+            Some(partition.clone())
+        );
+        assert_eq!(
+            partition.end(),
+            Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap()
+        );
+
+        assert_eq!(Partition::parse("app_logs_default"), None);
+        assert_eq!(Partition::parse("app_logs_p202413"), None);
+        assert_eq!(Partition::parse("app_logs_p2024031"), None);
+    }
+
+    #[test]
+    fn partitions_expire_once_their_whole_month_is_past_the_cutoff() {
+        let partitions: Vec<Partition> = [1, 2, 3]
+            .into_iter()
+            .map(|month| Partition::for_month(NaiveDate::from_ymd_opt(2024, month, 1).unwrap()))
+            .collect();
+
+        let cutoff = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let names: Vec<String> = expired(&partitions, cutoff)
+            .into_iter()
+            .map(|partition| partition.name)
+            .collect();
+        assert_eq!(names, vec!["app_logs_p202401", "app_logs_p202402"]);
+    }
+}
@@ -0,0 +1,250 @@
+//! Scheduled pruning of the `app_logs` table by log level.
+//!
+//! The policy comes from [`LogRetention`] in the application config. Each run
+//! drops monthly partitions past every level's retention period, deletes the
+//! remaining entries older than their level's retention period, or only
+//! counts them in dry-run mode, and returns a per-level report.
+
+use super::partitions;
+use crate::config::{self, LogRetention};
+use crate::database::{get_pool_ref, query_stats};
+use crate::shutdown;
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use sqlx::Execute;
+use std::time::Duration;
+
+/// Entries matched by one retention rule.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelRetention {
+    /// Log level, or `None` for the default rule covering unlisted levels.
+    pub level: Option<String>,
+    pub retention_days: u32,
+    /// Entries deleted, or that would be deleted in dry-run mode.
+    pub entries: u64,
+}
+
+/// Outcome of a retention run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    /// Monthly partitions dropped, or that would be dropped in dry-run mode.
+    pub dropped_partitions: Vec<String>,
+    /// Entries in the dropped partitions.
+    pub partition_entries: u64,
+    pub levels: Vec<LevelRetention>,
+    pub total: u64,
+    pub ran_at: DateTime<Utc>,
+}
+
+/// Applies `policy`, deleting expired entries unless `dry_run` is set.
+pub async fn apply(policy: &LogRetention, dry_run: bool) -> Result<RetentionReport> {
+    let pool = get_pool_ref()?;
+
+    let mut dropped_partitions = Vec::new();
+    let mut partition_entries = 0;
+    if let Some(days) = longest_retention_days(policy) {
+        let cutoff = Utc::now() - ChronoDuration::days(i64::from(days));
+        let attached = partitions::list_partitions(pool.as_ref()).await?;
+        for partition in partitions::expired(&attached, cutoff) {
+            partition_entries += if dry_run {
+                partitions::count_entries(pool.as_ref(), &partition).await?
+            } else {
+                partitions::drop_partition(pool.as_ref(), &partition).await?
+            };
+            dropped_partitions.push(partition.name);
+        }
+    }
+
+    let mut levels = Vec::new();
+
+    for (level, days) in &policy.days_per_level {
+        let query = if dry_run {
+            sqlx::query_scalar!(
+                r#"
+                SELECT COUNT(*) AS "count!" FROM app_logs
+                WHERE LOWER(level) = $1
+                  AND created_at < NOW() - make_interval(days => $2)
+                "#,
+                level,
+                *days as i32,
+            )
+        } else {
+            sqlx::query_scalar!(
+                r#"
+                WITH deleted AS (
+                    DELETE FROM app_logs
+                    WHERE LOWER(level) = $1
+                      AND created_at < NOW() - make_interval(days => $2)
+                    RETURNING 1
+                )
+                SELECT COUNT(*) AS "count!" FROM deleted
+                "#,
+                level,
+                *days as i32,
+            )
+        };
+        let entries = query_stats::timed(query.sql(), query.fetch_one(pool.as_ref())).await?;
+        levels.push(LevelRetention {
+            level: Some(level.clone()),
+            retention_days: *days,
+            entries: entries as u64,
+        });
+    }
+
+    if let Some(days) = policy.default_days {
+        let listed: Vec<String> = policy.days_per_level.keys().cloned().collect();
+        let query = if dry_run {
+            sqlx::query_scalar!(
+                r#"
+                SELECT COUNT(*) AS "count!" FROM app_logs
+                WHERE LOWER(level) <> ALL($1)
+                  AND created_at < NOW() - make_interval(days => $2)
+                "#,
+                &listed,
+                days as i32,
+            )
+        } else {
+            sqlx::query_scalar!(
+                r#"
+                WITH deleted AS (
+                    DELETE FROM app_logs
+                    WHERE LOWER(level) <> ALL($1)
+                      AND created_at < NOW() - make_interval(days => $2)
+                    RETURNING 1
+                )
+                SELECT COUNT(*) AS "count!" FROM deleted
+                "#,
+                &listed,
+                days as i32,
+            )
+        };
+        let entries = query_stats::timed(query.sql(), query.fetch_one(pool.as_ref())).await?;
+        levels.push(LevelRetention {
+            level: None,
+            retention_days: days,
+            entries: entries as u64,
+        });
+    }
+
+    let total = partition_entries + levels.iter().map(|level| level.entries).sum::<u64>();
+    Ok(RetentionReport {
+        dry_run,
+        dropped_partitions,
+        partition_entries,
+        levels,
+        total,
+        ran_at: Utc::now(),
+    })
+}
+
+/// Returns the longest retention period when every level is covered by the
+/// policy, so that whole partitions older than it can be dropped.
+fn longest_retention_days(policy: &LogRetention) -> Option<u32> {
+    let default_days = policy.default_days?;
+    Some(
+        policy
+            .days_per_level
+            .values()
+            .copied()
+            .fold(default_days, u32::max),
+    )
+}
+
+/// Starts the periodic task that creates upcoming log partitions and, when a
+/// policy is configured, applies retention.
+pub fn start_scheduler() {
+    let policy = config::current().log_retention.clone();
+
+    let task = tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(policy.interval_hours * 3600));
+        // The first tick fires immediately, before the database is ready.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match get_pool_ref() {
+                Ok(pool) => {
+                    if let Err(e) = partitions::ensure_partitions(pool.as_ref()).await {
+                        tracing::warn!("Failed to create log partitions: {}", e);
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to create log partitions: {}", e),
+            }
+            if !policy.is_enabled() {
+                continue;
+            }
+            match apply(&policy, policy.dry_run).await {
+                Ok(report) if report.dry_run => tracing::info!(
+                    "Log retention dry run: {} entries would be deleted",
+                    report.total
+                ),
+                Ok(report) => tracing::info!("Log retention deleted {} entries", report.total),
+                Err(e) => tracing::warn!("Log retention run failed: {}", e),
+            }
+        }
+    });
+    shutdown::track("log-retention", task);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::test_support::LogFactory;
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    #[serial]
+    async fn prunes_by_level_with_dry_run() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        LogFactory::new().level("debug").insert().await?;
+        LogFactory::new().level("error").insert().await?;
+        LogFactory::new().level("info").insert().await?;
+        sqlx::query("UPDATE app_logs SET created_at = NOW() - INTERVAL '10 days'")
+            .execute(pool.as_ref())
+            .await?;
+
+        let policy = LogRetention {
+            days_per_level: BTreeMap::from([("debug".to_string(), 7), ("error".to_string(), 30)]),
+            default_days: Some(5),
+            interval_hours: 24,
+            dry_run: false,
+        };
+
+        let report = apply(&policy, true).await?;
+        assert_eq!(report.total, 2);
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM app_logs")
+            .fetch_one(pool.as_ref())
+            .await?;
+        assert_eq!(remaining, 3);
+
+        let report = apply(&policy, false).await?;
+        assert_eq!(report.total, 2);
+        let levels: Vec<String> = sqlx::query_scalar("SELECT level FROM app_logs")
+            .fetch_all(pool.as_ref())
+            .await?;
+        assert_eq!(levels, vec!["error".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn partitions_are_dropped_only_when_every_level_is_covered() {
+        let mut policy = LogRetention {
+            days_per_level: BTreeMap::from([("error".to_string(), 365)]),
+            default_days: None,
+            interval_hours: 24,
+            dry_run: false,
+        };
+        assert_eq!(longest_retention_days(&policy), None);
+
+        policy.default_days = Some(30);
+        assert_eq!(longest_retention_days(&policy), Some(365));
+    }
+}
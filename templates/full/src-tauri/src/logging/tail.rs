@@ -0,0 +1,152 @@
+//! Reverse line reader for reading the newest log lines first.
+//!
+//! Files are read backwards in fixed-size chunks, so fetching the last page of
+//! a large log file only touches the end of the file.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes read from the file per step.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Iterator over a file's lines from last to first.
+pub struct ReverseLines {
+    file: File,
+    /// Offset of the first byte not yet read.
+    position: u64,
+    /// Bytes before the earliest complete line found so far.
+    partial: Vec<u8>,
+    /// Complete lines with the offsets they start at.
+    lines: VecDeque<(u64, String)>,
+}
+
+impl ReverseLines {
+    /// Opens `path` for reverse reading.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::open_at(path, u64::MAX)
+    }
+
+    /// Opens `path` for reverse reading of the bytes before offset `end`,
+    /// ignoring anything written after it.
+    pub fn open_at(path: &Path, end: u64) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let position = file.metadata()?.len().min(end);
+        Ok(Self {
+            file,
+            position,
+            partial: Vec::new(),
+            lines: VecDeque::new(),
+        })
+    }
+
+    /// Reads the previous chunk and splits off the complete lines it finishes.
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let size = CHUNK_SIZE.min(self.position);
+        self.position -= size;
+        self.file.seek(SeekFrom::Start(self.position))?;
+
+        let mut chunk = vec![0u8; size as usize];
+        self.file.read_exact(&mut chunk)?;
+        chunk.append(&mut self.partial);
+
+        let mut end = chunk.len();
+        // Lines are pushed newest first, so walk newline positions backwards.
+        for index in (0..chunk.len()).rev() {
+            if chunk[index] == b'\n' {
+                self.push_line(self.position + index as u64 + 1, &chunk[index + 1..end]);
+                end = index;
+            }
+        }
+        chunk.truncate(end);
+        self.partial = chunk;
+
+        if self.position == 0 {
+            let first = std::mem::take(&mut self.partial);
+            self.push_line(0, &first);
+        }
+        Ok(())
+    }
+
+    fn push_line(&mut self, start: u64, bytes: &[u8]) {
+        let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+        if !bytes.is_empty() {
+            self.lines
+                .push_back((start, String::from_utf8_lossy(bytes).into_owned()));
+        }
+    }
+
+    /// Returns the previous line together with the offset it starts at.
+    pub fn next_with_offset(&mut self) -> Option<io::Result<(u64, String)>> {
+        while self.lines.is_empty() && self.position > 0 {
+            if let Err(e) = self.read_chunk() {
+                self.position = 0;
+                return Some(Err(e));
+            }
+        }
+        self.lines.pop_front().map(Ok)
+    }
+}
+
+impl Iterator for ReverseLines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_offset()
+            .map(|line| line.map(|(_, line)| line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn collect(contents: &str) -> Vec<String> {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        ReverseLines::open(file.path())
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn yields_lines_newest_first() {
+        assert_eq!(
+            collect("one\ntwo\r\n\nthree\n"),
+            vec!["three", "two", "one"]
+        );
+        assert_eq!(collect("no newline"), vec!["no newline"]);
+        assert!(collect("").is_empty());
+    }
+
+    #[test]
+    fn handles_lines_spanning_chunks() {
+        let long = "x".repeat(CHUNK_SIZE as usize + 10);
+        let contents = format!("first\n{}\nlast\n", long);
+        let lines = collect(&contents);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "last");
+        assert_eq!(lines[1], long);
+        assert_eq!(lines[2], "first");
+    }
+
+    #[test]
+    fn reports_line_offsets_and_stops_at_the_end_offset() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"one\ntwo\nthree\n").unwrap();
+
+        let mut lines = ReverseLines::open_at(file.path(), 8).unwrap();
+        assert_eq!(
+            lines.next_with_offset().unwrap().unwrap(),
+            (4, "two".to_string())
+        );
+        assert_eq!(
+            lines.next_with_offset().unwrap().unwrap(),
+            (0, "one".to_string())
+        );
+        assert!(lines.next_with_offset().is_none());
+    }
+}
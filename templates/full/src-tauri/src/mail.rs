@@ -0,0 +1,209 @@
+//! Pluggable transports for outgoing mail.
+//!
+//! [`crate::email`] renders and queues messages and hands each one to the
+//! active [`MailTransport`]. [`SmtpTransport`] relays through an SMTP server
+//! configured from vault settings; tests install a `MemoryTransport` to
+//! inspect what was sent. Other transports, such as an HTTP mail API, only
+//! need to implement the trait.
+
+use crate::errors::{AppError, AppResult, ErrorCode};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// Future returned by [`MailTransport::send`].
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Delivers rendered messages.
+pub trait MailTransport: Send + Sync {
+    /// Short name used in logs, e.g. `smtp`.
+    fn name(&self) -> &str;
+
+    /// Delivers one message. Errors are retried by the email queue.
+    fn send(&self, message: Message) -> SendFuture<'_>;
+}
+
+/// Connection security used for the SMTP session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SmtpSecurity {
+    /// Implicit TLS, usually port 465.
+    Tls,
+    /// Plain connection upgraded with STARTTLS, usually port 587.
+    #[default]
+    StartTls,
+    /// Unencrypted; only for local development relays.
+    None,
+}
+
+/// SMTP settings as stored in the vault.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, e.g. `"Acme <no-reply@acme.test>"`.
+    pub from: String,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+impl fmt::Debug for SmtpSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpSettings")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "[redacted]"))
+            .field("from", &self.from)
+            .field("security", &self.security)
+            .finish()
+    }
+}
+
+/// Relays messages through an SMTP server.
+pub struct SmtpTransport {
+    inner: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    /// Builds the transport; no connection is made until the first send.
+    pub fn new(settings: &SmtpSettings) -> AppResult<Self> {
+        let host = settings.host.trim();
+        if host.is_empty() {
+            return Err(AppError::invalid_input("host", "SMTP host cannot be empty"));
+        }
+
+        let mut builder = match settings.security {
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                host,
+            )),
+        }
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::ConfigurationError,
+                format!("Invalid SMTP host: {}", e),
+            )
+        })?;
+
+        if let Some(port) = settings.port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = &settings.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                settings.password.clone().unwrap_or_default(),
+            ));
+        }
+
+        Ok(Self {
+            inner: builder.build(),
+        })
+    }
+}
+
+impl MailTransport for SmtpTransport {
+    fn name(&self) -> &str {
+        "smtp"
+    }
+
+    fn send(&self, message: Message) -> SendFuture<'_> {
+        Box::pin(async move {
+            self.inner
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// Keeps sent messages in memory instead of delivering them.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryTransport {
+    sent: Mutex<Vec<Message>>,
+}
+
+#[cfg(test)]
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the messages sent so far, oldest first.
+    pub fn sent(&self) -> Vec<Message> {
+        self.sent
+            .lock()
+            .map(|sent| sent.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+impl MailTransport for MemoryTransport {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn send(&self, message: Message) -> SendFuture<'_> {
+        Box::pin(async move {
+            self.sent
+                .lock()
+                .map_err(|_| "Mail store is poisoned".to_string())?
+                .push(message);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(host: &str) -> SmtpSettings {
+        SmtpSettings {
+            host: host.to_string(),
+            port: Some(587),
+            username: Some("mailer".to_string()),
+            password: Some("hunter2".to_string()),
+            from: "no-reply@example.com".to_string(),
+            security: SmtpSecurity::StartTls,
+        }
+    }
+
+    #[test]
+    fn debug_output_hides_password() {
+        assert!(!format!("{:?}", settings("smtp.example.com")).contains("hunter2"));
+    }
+
+    #[test]
+    fn rejects_empty_smtp_hosts() {
+        assert!(SmtpTransport::new(&settings("  ")).is_err());
+    }
+
+    #[tokio::test]
+    async fn memory_transport_keeps_sent_messages() {
+        let transport = MemoryTransport::new();
+        let message = Message::builder()
+            .from("no-reply@example.com".parse().unwrap())
+            .to("ada@example.com".parse().unwrap())
+            .subject("Hello")
+            .body("Hi Ada".to_string())
+            .unwrap();
+
+        transport.send(message).await.unwrap();
+        let sent = transport.sent();
+        assert_eq!(sent.len(), 1);
+        assert!(String::from_utf8_lossy(&sent[0].formatted()).contains("Hi Ada"));
+    }
+}
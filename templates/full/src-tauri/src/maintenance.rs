@@ -0,0 +1,211 @@
+//! Maintenance mode, which pauses non-essential commands.
+//!
+//! Writes that land while a backup, restore, or migration runs can leave the
+//! data inconsistent, e.g. a user created mid-restore that the restore then
+//! half overwrites. The rate-limited command wrappers call [`check`] before
+//! running a handler; while maintenance is active it fails every command
+//! outside [`ESSENTIAL_COMMANDS`] with `MAINTENANCE_MODE`.
+//!
+//! Maintenance is active while an operator has entered it with [`enter`] or
+//! while any guard returned by [`hold`] is alive; the destructive operations
+//! hold one for as long as they run. Every change is published as a
+//! `maintenance:changed` event.
+
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::events::{self, AppEvent};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// Commands that keep working during maintenance: maintenance itself,
+/// status checks, and the operations maintenance exists for.
+pub const ESSENTIAL_COMMANDS: &[&str] = &[
+    "enter_maintenance_mode",
+    "exit_maintenance_mode",
+    "get_maintenance_status",
+    "cancel_operation",
+    "check_database_connection",
+    "run_migrations",
+    "create_backup",
+    "list_backups",
+    "restore_backup",
+    "get_system_info",
+    "get_app_version_info",
+    "get_startup_status",
+    "list_commands",
+];
+
+static STATE: Lazy<Mutex<State>> = Lazy::new(|| Mutex::new(State::default()));
+
+#[derive(Default)]
+struct State {
+    /// Entered by an operator; lasts until [`exit`].
+    manual: Option<Reason>,
+    /// Held by running operations, by guard id.
+    holds: HashMap<u64, Reason>,
+    next_hold: u64,
+}
+
+struct Reason {
+    text: String,
+    since: DateTime<Utc>,
+}
+
+impl Reason {
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            since: Utc::now(),
+        }
+    }
+}
+
+impl State {
+    fn status(&self) -> MaintenanceStatus {
+        let mut reasons: Vec<&Reason> = self.manual.iter().chain(self.holds.values()).collect();
+        reasons.sort_by_key(|reason| reason.since);
+        MaintenanceStatus {
+            active: !reasons.is_empty(),
+            since: reasons.first().map(|reason| reason.since),
+            reasons: reasons
+                .into_iter()
+                .map(|reason| reason.text.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Whether maintenance is active, and why.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStatus {
+    pub active: bool,
+    /// One entry per operator request or running operation, oldest first.
+    pub reasons: Vec<String>,
+    /// When the oldest of `reasons` began.
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// Keeps maintenance active until dropped.
+pub struct MaintenanceGuard {
+    id: u64,
+}
+
+impl Drop for MaintenanceGuard {
+    fn drop(&mut self) {
+        update(|state| {
+            state.holds.remove(&self.id);
+        });
+    }
+}
+
+/// Enters maintenance on an operator's behalf, replacing the reason of an
+/// earlier request.
+pub fn enter(reason: &str) -> MaintenanceStatus {
+    tracing::info!("Entering maintenance mode: {}", reason);
+    update(|state| state.manual = Some(Reason::new(reason)))
+}
+
+/// Ends the operator's maintenance request. Maintenance stays active while
+/// operations still hold it.
+pub fn exit() -> MaintenanceStatus {
+    tracing::info!("Leaving maintenance mode");
+    update(|state| state.manual = None)
+}
+
+/// Keeps maintenance active for the lifetime of the returned guard.
+pub fn hold(reason: &str) -> MaintenanceGuard {
+    let mut id = 0;
+    update(|state| {
+        id = state.next_hold;
+        state.next_hold += 1;
+        state.holds.insert(id, Reason::new(reason));
+    });
+    MaintenanceGuard { id }
+}
+
+/// The current maintenance state.
+pub fn status() -> MaintenanceStatus {
+    STATE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .status()
+}
+
+/// Fails with `MAINTENANCE_MODE` when `command` is paused.
+pub fn check(command: &str) -> AppResult<()> {
+    check_against(command, status())
+}
+
+fn check_against(command: &str, status: MaintenanceStatus) -> AppResult<()> {
+    if !status.active || ESSENTIAL_COMMANDS.contains(&command) {
+        return Ok(());
+    }
+    Err(AppError::new(
+        ErrorCode::MaintenanceMode,
+        format!(
+            "'{}' is paused during maintenance ({})",
+            command,
+            status.reasons.join(", ")
+        ),
+    )
+    .with_context(&status))
+}
+
+/// Applies `change` and publishes the new status when it differs.
+fn update(change: impl FnOnce(&mut State)) -> MaintenanceStatus {
+    let (before, after) = {
+        let mut state = STATE.lock().unwrap_or_else(PoisonError::into_inner);
+        let before = state.status();
+        change(&mut state);
+        (before, state.status())
+    };
+    if before != after {
+        events::publish(AppEvent::MaintenanceChanged(after.clone()));
+    }
+    after
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_non_essential_commands_while_active() {
+        let mut state = State::default();
+        assert!(check_against("create_user", state.status()).is_ok());
+
+        state.holds.insert(0, Reason::new("restore"));
+        let error = check_against("create_user", state.status()).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::MaintenanceMode));
+        assert!(check_against("cancel_operation", state.status()).is_ok());
+    }
+
+    #[test]
+    fn reports_every_reason_oldest_first() {
+        let mut state = State {
+            manual: Some(Reason::new("upgrading storage")),
+            ..Default::default()
+        };
+        let first = state.status();
+        state.holds.insert(0, Reason::new("backup"));
+
+        let status = state.status();
+        assert!(status.active);
+        assert_eq!(status.reasons, vec!["upgrading storage", "backup"]);
+        assert_eq!(status.since, first.since);
+
+        state.manual = None;
+        state.holds.clear();
+        assert_eq!(
+            state.status(),
+            MaintenanceStatus {
+                active: false,
+                reasons: Vec::new(),
+                since: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! `media://` URI scheme for streaming files from the filesystem scope.
+//!
+//! The webview loads scoped files directly, e.g. `<video src>` built with
+//! `convertFileSrc(path, "media")`, instead of receiving base64 over IPC.
+//! Paths resolve exactly like the filesystem commands, so nothing outside
+//! the scope can be served. Single `Range` requests are answered with
+//! `206 Partial Content`, which lets media elements seek, and open-ended
+//! ranges are capped so one response never loads a whole large file.
+//! Requests are served on the blocking thread pool, off the main thread.
+
+use crate::handlers::filesystem::resolve_existing_path;
+use percent_encoding::percent_decode_str;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::UriSchemeResponder;
+
+/// Scheme name registered with the webview.
+pub const SCHEME: &str = "media";
+
+/// Largest body returned for one request, in bytes.
+const MAX_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Answers a `media://` request once its file has been read.
+pub fn respond(request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    tauri::async_runtime::spawn_blocking(move || responder.respond(handle(&request)));
+}
+
+/// Handles one `media://` request.
+fn handle(request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    let raw = request.uri().path().trim_start_matches('/');
+    let Ok(relative) = percent_decode_str(raw).decode_utf8() else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let path = match resolve_existing_path(&relative) {
+        Ok(context) => context.path,
+        Err(e) => {
+            tracing::debug!("Rejected media request for '{}': {}", relative, e);
+            return status(StatusCode::NOT_FOUND);
+        }
+    };
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    let mut response = serve(&path, range);
+    if request.method() == Method::HEAD {
+        response.body_mut().clear();
+    }
+    response
+}
+
+/// Builds the response for `path`, honouring an optional `Range` header.
+fn serve(path: &Path, range: Option<&str>) -> Response<Vec<u8>> {
+    let Ok(mut file) = File::open(path) else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let Some(len) = file
+        .metadata()
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+    else {
+        return status(StatusCode::NOT_FOUND);
+    };
+
+    let (start, end, partial) = match range.map(|range| parse_range(range, len)) {
+        Some(Some((start, end))) => (start, end.min(start + MAX_CHUNK_BYTES - 1), true),
+        Some(None) => {
+            return Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Vec::new())
+                .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR));
+        }
+        None if len > MAX_CHUNK_BYTES => (0, MAX_CHUNK_BYTES - 1, true),
+        None => (0, len.saturating_sub(1), false),
+    };
+
+    let mut body = Vec::new();
+    if len > 0 {
+        let read = file
+            .seek(SeekFrom::Start(start))
+            .and_then(|_| file.by_ref().take(end - start + 1).read_to_end(&mut body));
+        if read.is_err() {
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type(path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, body.len());
+    builder = if partial {
+        builder.status(StatusCode::PARTIAL_CONTENT).header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, len),
+        )
+    } else {
+        builder.status(StatusCode::OK)
+    };
+    builder
+        .body(body)
+        .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+/// Parses a single-range `bytes=` header into inclusive offsets.
+///
+/// Returns `None` when the range cannot be satisfied for a file of `len`
+/// bytes. Multiple ranges are not supported and are treated the same way.
+fn parse_range(range: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || len == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok().filter(|suffix| *suffix > 0)?;
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (start, "") => (start.parse().ok()?, len - 1),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(len - 1)),
+    };
+    (start <= end && start < len).then_some((start, end))
+}
+
+/// Returns the MIME type served for `path`, judged by its extension.
+pub(crate) fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "ogv" => "video/ogg",
+        "mov" => "video/quicktime",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "ogg" | "oga" => "audio/ogg",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = code;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn media_file(contents: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".mp4").tempfile().unwrap();
+        file.write_all(contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn parses_single_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some((0, 9)));
+        assert_eq!(parse_range("bytes=90-", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=-10", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=50-500", 100), Some((50, 99)));
+        assert_eq!(parse_range("bytes=100-", 100), None);
+        assert_eq!(parse_range("bytes=9-0", 100), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), None);
+        assert_eq!(parse_range("items=0-1", 100), None);
+    }
+
+    #[test]
+    fn serves_whole_files_and_ranges() {
+        let file = media_file(b"0123456789");
+
+        let full = serve(file.path(), None);
+        assert_eq!(full.status(), StatusCode::OK);
+        assert_eq!(full.body(), b"0123456789");
+        assert_eq!(full.headers()[header::CONTENT_TYPE], "video/mp4");
+
+        let partial = serve(file.path(), Some("bytes=2-4"));
+        assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(partial.body(), b"234");
+        assert_eq!(partial.headers()[header::CONTENT_RANGE], "bytes 2-4/10");
+
+        let invalid = serve(file.path(), Some("bytes=20-"));
+        assert_eq!(invalid.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(invalid.headers()[header::CONTENT_RANGE], "bytes */10");
+    }
+
+    #[test]
+    fn missing_files_are_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let response = serve(&dir.path().join("missing.mp4"), None);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
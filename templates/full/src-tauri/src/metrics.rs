@@ -0,0 +1,183 @@
+//! In-process metrics registry rendered in the Prometheus text format.
+//!
+//! Command invocations and argument sizes are recorded by
+//! [`crate::command_trace`]; database pool and cache statistics are sampled
+//! when metrics are rendered. Pool statistics are only rendered with the
+//! `database` cargo feature.
+
+use crate::cache;
+#[cfg(feature = "database")]
+use crate::database::get_pool_ref;
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the command latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Metric name prefix shared by every exported series.
+const PREFIX: &str = "eztauri";
+
+/// Per-command statistics keyed by command name.
+static COMMANDS: Lazy<Mutex<BTreeMap<String, CommandMetrics>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Invocation counts and latency histogram for a single command.
+#[derive(Debug, Clone, Default)]
+struct CommandMetrics {
+    success: u64,
+    errors: u64,
+    /// Non-cumulative counts per bucket in [`LATENCY_BUCKETS`].
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    duration_sum: f64,
+    /// Invocations with a measured argument payload.
+    arg_samples: u64,
+    arg_bytes_sum: u64,
+}
+
+impl CommandMetrics {
+    fn count(&self) -> u64 {
+        self.success + self.errors
+    }
+}
+
+/// Records a completed command invocation.
+pub fn record_command(name: &str, duration: Duration, success: bool) {
+    let Ok(mut commands) = COMMANDS.lock() else {
+        return;
+    };
+
+    let metrics = commands.entry(name.to_string()).or_default();
+    let seconds = duration.as_secs_f64();
+
+    if success {
+        metrics.success += 1;
+    } else {
+        metrics.errors += 1;
+    }
+    metrics.duration_sum += seconds;
+
+    if let Some(index) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+        metrics.buckets[index] += 1;
+    }
+}
+
+/// Records the serialized argument size of a command invocation.
+pub fn record_command_args(name: &str, bytes: usize) {
+    let Ok(mut commands) = COMMANDS.lock() else {
+        return;
+    };
+
+    let metrics = commands.entry(name.to_string()).or_default();
+    metrics.arg_samples += 1;
+    metrics.arg_bytes_sum += bytes as u64;
+}
+
+/// Renders all metrics in the Prometheus text exposition format.
+pub fn render_prometheus() -> String {
+    let mut out = String::new();
+    render_commands(&mut out);
+    #[cfg(feature = "database")]
+    render_database(&mut out);
+    render_cache(&mut out);
+    out
+}
+
+fn render_commands(out: &mut String) {
+    let commands = COMMANDS.lock().map(|c| c.clone()).unwrap_or_default();
+
+    header(out, "command_invocations_total", "counter", "Total command invocations by result.");
+    for (name, metrics) in &commands {
+        let name = escape_label(name);
+        let _ = writeln!(out, "{PREFIX}_command_invocations_total{{command=\"{name}\",result=\"ok\"}} {}", metrics.success);
+        let _ = writeln!(out, "{PREFIX}_command_invocations_total{{command=\"{name}\",result=\"error\"}} {}", metrics.errors);
+    }
+
+    header(out, "command_duration_seconds", "histogram", "Command execution time in seconds.");
+    for (name, metrics) in &commands {
+        let name = escape_label(name);
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(metrics.buckets.iter()) {
+            cumulative += count;
+            let _ = writeln!(out, "{PREFIX}_command_duration_seconds_bucket{{command=\"{name}\",le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{PREFIX}_command_duration_seconds_bucket{{command=\"{name}\",le=\"+Inf\"}} {}", metrics.count());
+        let _ = writeln!(out, "{PREFIX}_command_duration_seconds_sum{{command=\"{name}\"}} {}", metrics.duration_sum);
+        let _ = writeln!(out, "{PREFIX}_command_duration_seconds_count{{command=\"{name}\"}} {}", metrics.count());
+    }
+
+    header(out, "command_argument_bytes", "summary", "Serialized command argument size in bytes.");
+    for (name, metrics) in commands.iter().filter(|(_, m)| m.arg_samples > 0) {
+        let name = escape_label(name);
+        let _ = writeln!(out, "{PREFIX}_command_argument_bytes_sum{{command=\"{name}\"}} {}", metrics.arg_bytes_sum);
+        let _ = writeln!(out, "{PREFIX}_command_argument_bytes_count{{command=\"{name}\"}} {}", metrics.arg_samples);
+    }
+}
+
+#[cfg(feature = "database")]
+fn render_database(out: &mut String) {
+    let (size, idle) = match get_pool_ref() {
+        Ok(pool) => (pool.size() as u64, pool.num_idle() as u64),
+        Err(_) => (0, 0),
+    };
+
+    header(out, "db_pool_connections", "gauge", "Database pool connections by state.");
+    let _ = writeln!(out, "{PREFIX}_db_pool_connections{{state=\"active\"}} {}", size.saturating_sub(idle));
+    let _ = writeln!(out, "{PREFIX}_db_pool_connections{{state=\"idle\"}} {idle}");
+}
+
+fn render_cache(out: &mut String) {
+    let stats = cache::cache_stats();
+
+    header(out, "cache_available", "gauge", "Whether the Redis cache is available.");
+    let _ = writeln!(out, "{PREFIX}_cache_available {}", u8::from(stats.available));
+
+    header(out, "cache_operations_total", "counter", "Cache operations by outcome.");
+    let _ = writeln!(out, "{PREFIX}_cache_operations_total{{operation=\"hit\"}} {}", stats.hits);
+    let _ = writeln!(out, "{PREFIX}_cache_operations_total{{operation=\"miss\"}} {}", stats.misses);
+    let _ = writeln!(out, "{PREFIX}_cache_operations_total{{operation=\"write\"}} {}", stats.writes);
+    let _ = writeln!(out, "{PREFIX}_cache_operations_total{{operation=\"error\"}} {}", stats.errors);
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} {kind}");
+}
+
+/// Escapes a label value per the exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_command_counters_and_histogram() {
+        record_command("metrics_test_command", Duration::from_millis(20), true);
+        record_command("metrics_test_command", Duration::from_secs(20), false);
+        record_command_args("metrics_test_command", 42);
+
+        let output = render_prometheus();
+        assert!(output.contains("# TYPE eztauri_command_invocations_total counter"));
+        assert!(output.contains("eztauri_command_invocations_total{command=\"metrics_test_command\",result=\"ok\"} 1"));
+        assert!(output.contains("eztauri_command_invocations_total{command=\"metrics_test_command\",result=\"error\"} 1"));
+        assert!(output.contains("eztauri_command_duration_seconds_bucket{command=\"metrics_test_command\",le=\"0.025\"} 1"));
+        assert!(output.contains("eztauri_command_duration_seconds_bucket{command=\"metrics_test_command\",le=\"+Inf\"} 2"));
+        assert!(output.contains("eztauri_command_argument_bytes_sum{command=\"metrics_test_command\"} 42"));
+        #[cfg(feature = "database")]
+        assert!(output.contains("eztauri_db_pool_connections{state=\"idle\"}"));
+        assert!(output.contains("eztauri_cache_available"));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,37 @@
+//! Audit trail models.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A security-sensitive action recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub action: String,
+    /// Who performed the action; `None` for the application itself and for
+    /// anonymous callers such as failed sign-ins.
+    pub actor_id: Option<Uuid>,
+    /// The user the action affected.
+    pub user_id: Option<Uuid>,
+    pub details: serde_json::Value,
+    #[serde(with = "crate::locale::local_time")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters for filtering audit entries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    pub action: Option<String>,
+    pub actor_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    /// Only entries recorded at or after this time.
+    pub from: Option<DateTime<Utc>>,
+    /// Only entries recorded before this time.
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,18 @@
+//! Database status models.
+
+use crate::config::DatabaseBackend;
+use serde::{Deserialize, Serialize};
+
+/// Database connection status information.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseStatus {
+    /// Backend `DATABASE_URL` selects, so the frontend can tell which
+    /// commands are available.
+    pub backend: DatabaseBackend,
+    pub connected: bool,
+    /// Database name, or the file path of the embedded database.
+    pub database_name: Option<String>,
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
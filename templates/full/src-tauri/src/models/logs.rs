@@ -14,6 +14,10 @@ pub struct AppLog {
     pub message: String,
     pub metadata: serde_json::Value,
     pub user_id: Option<Uuid>,
+    /// Feature areas the entry belongs to.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(with = "crate::locale::local_time")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -25,8 +29,18 @@ pub struct CreateAppLog {
     pub message: String,
     pub metadata: Option<serde_json::Value>,
     pub user_id: Option<Uuid>,
+    pub tags: Option<Vec<String>>,
 }
 
+/// Tag marking entries reported by the frontend.
+pub const FRONTEND_SOURCE_TAG: &str = "source:frontend";
+
+/// Most frontend errors accepted in one batch.
+pub const MAX_FRONTEND_ERRORS: usize = 500;
+
+/// Longest stack trace kept per frontend error, in characters.
+const MAX_STACK_CHARS: usize = 16 * 1024;
+
 /// Available log levels for filtering and categorization.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -57,6 +71,89 @@ impl ToString for LogLevel {
 pub struct LogQuery {
     pub level: Option<String>,
     pub user_id: Option<Uuid>,
+    /// Only entries carrying all of these tags.
+    pub tags: Option<Vec<String>>,
+    /// Only entries from this source, e.g. `frontend`, as recorded by their
+    /// `source:` tag.
+    #[serde(default)]
+    pub source: Option<String>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
+
+/// What kind of frontend failure was reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrontendErrorKind {
+    /// An uncaught exception.
+    Error,
+    /// A rejected promise nobody handled.
+    UnhandledRejection,
+    /// A `console.error` call.
+    Console,
+}
+
+impl FrontendErrorKind {
+    /// Tag recorded on the log entry.
+    pub fn as_tag(self) -> &'static str {
+        match self {
+            FrontendErrorKind::Error => "error",
+            FrontendErrorKind::UnhandledRejection => "unhandled_rejection",
+            FrontendErrorKind::Console => "console",
+        }
+    }
+}
+
+/// A position in frontend code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// A console error or unhandled rejection reported by the frontend.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendError {
+    pub kind: FrontendErrorKind,
+    pub message: String,
+    pub stack: Option<String>,
+    /// Where the error was raised in the bundled code.
+    pub location: Option<SourceLocation>,
+    /// `location` mapped back to the original source through its source map.
+    pub original_location: Option<SourceLocation>,
+    /// Page the error occurred on.
+    pub url: Option<String>,
+    pub user_agent: Option<String>,
+    pub occurred_at: Option<DateTime<Utc>>,
+}
+
+impl FrontendError {
+    /// Metadata of the log entry: the stack trace, cut to a bounded length,
+    /// the source locations, and the page it occurred on.
+    pub fn metadata(&self) -> serde_json::Value {
+        let stack = self
+            .stack
+            .as_ref()
+            .map(|stack| stack.chars().take(MAX_STACK_CHARS).collect::<String>());
+        serde_json::json!({
+            "kind": self.kind,
+            "stack": stack,
+            "location": self.location,
+            "originalLocation": self.original_location,
+            "url": self.url,
+            "userAgent": self.user_agent,
+            "occurredAt": self.occurred_at,
+        })
+    }
+
+    /// Tags of the log entry: [`FRONTEND_SOURCE_TAG`] and the error's kind.
+    pub fn tags(&self) -> Vec<String> {
+        vec![
+            FRONTEND_SOURCE_TAG.to_string(),
+            self.kind.as_tag().to_string(),
+        ]
+    }
+}
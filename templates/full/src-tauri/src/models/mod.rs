@@ -1,13 +1,37 @@
 //! Data models for the application.
 //!
 //! Contains all the data structures used throughout the application
-//! including user models, logging structures, and configuration types.
+//! including user models, workspaces, groups, login sessions, logging
+//! structures, audit entries, and configuration types. Models of features
+//! that only PostgreSQL offers are compiled with the `database` feature.
 
+#[cfg(feature = "database")]
+pub mod audit;
+pub mod database;
+#[cfg(feature = "database")]
+pub mod group;
 pub mod logs;
+#[cfg(feature = "database")]
+pub mod notification;
+pub mod session;
+#[cfg(feature = "database")]
 pub mod settings;
 pub mod user;
+#[cfg(feature = "database")]
+pub mod workspace;
 
+#[cfg(feature = "database")]
+pub use audit::*;
+pub use database::*;
+#[cfg(feature = "database")]
+pub use group::*;
 pub use logs::*;
+#[cfg(feature = "database")]
+pub use notification::*;
+pub use session::*;
+#[cfg(feature = "database")]
 #[allow(unused_imports)]
 pub use settings::*;
 pub use user::*;
+#[cfg(feature = "database")]
+pub use workspace::*;
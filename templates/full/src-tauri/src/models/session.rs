@@ -0,0 +1,45 @@
+//! Login session models.
+
+use super::PublicUser;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A persisted login session. The refresh token hash is never loaded.
+#[derive(Debug, Clone, PartialEq, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_active: DateTime<Utc>,
+    /// When the session lapses unless a heartbeat or refresh extends it.
+    pub expires_at: DateTime<Utc>,
+    /// Admin acting as the user, for sessions started by `impersonate_user`.
+    pub impersonator_id: Option<Uuid>,
+    /// Started with "remember me", so it lasts days rather than the idle
+    /// timeout and revokes itself when a used refresh token comes back.
+    pub remember_me: bool,
+}
+
+/// Tokens issued when a session starts or is refreshed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTokens {
+    pub session_id: Uuid,
+    /// Signed JWT sent with requests until it expires.
+    pub access_token: String,
+    pub access_token_expires_at: DateTime<Utc>,
+    /// Opaque token exchanged for new tokens; each refresh replaces it.
+    pub refresh_token: String,
+    pub session_expires_at: DateTime<Utc>,
+}
+
+/// Response payload for a successful sign-in.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginResponse {
+    pub user: PublicUser,
+    pub session: SessionTokens,
+}
@@ -31,8 +31,11 @@ pub struct CreateUserSettings {
 }
 
 /// Request payload for updating existing user settings.
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
+///
+/// `settings_data` is a JSON merge patch: listed keys replace stored ones,
+/// nested objects are merged, and `null` resets a key to its default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UpdateUserSettings {
     pub theme: Option<String>,
     pub language: Option<String>,
@@ -40,22 +43,71 @@ pub struct UpdateUserSettings {
     pub settings_data: Option<serde_json::Value>,
 }
 
-/// General application settings with common UI preferences.
-#[derive(Debug, Serialize, Deserialize)]
-#[allow(dead_code)]
+/// Version of the [`AppSettings`] schema written by this build.
+pub const SETTINGS_SCHEMA_VERSION: u32 = 1;
+
+/// Typed contents of the `settings_data` column.
+///
+/// Missing fields take their defaults, so data written by older builds loads
+/// cleanly. Unknown fields are kept in `extra`, so keys written by newer builds
+/// or by the application itself survive a round trip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
-    pub sidebar_collapsed: Option<bool>,
-    pub auto_save: Option<bool>,
-    pub notifications: Option<bool>,
+    pub schema_version: u32,
+    pub sidebar_collapsed: bool,
+    pub auto_save: bool,
+    pub auto_save_interval_seconds: u32,
+    pub notifications: bool,
+    pub page_size: u32,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl Default for AppSettings {
     /// Creates default application settings with sensible defaults.
     fn default() -> Self {
         AppSettings {
-            sidebar_collapsed: Some(false),
-            auto_save: Some(true),
-            notifications: Some(true),
+            schema_version: SETTINGS_SCHEMA_VERSION,
+            sidebar_collapsed: false,
+            auto_save: true,
+            auto_save_interval_seconds: 30,
+            notifications: true,
+            page_size: 25,
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// Reads stored settings, replacing fields that no longer match the
+    /// schema with their defaults instead of failing.
+    pub fn from_stored(value: &serde_json::Value) -> Self {
+        if let Ok(settings) = serde_json::from_value(value.clone()) {
+            return settings;
+        }
+
+        let valid: serde_json::Map<String, serde_json::Value> = value
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(key, field)| {
+                let single = serde_json::json!({ key.as_str(): field });
+                serde_json::from_value::<AppSettings>(single).is_ok()
+            })
+            .map(|(key, field)| (key.clone(), field.clone()))
+            .collect();
+        serde_json::from_value(serde_json::Value::Object(valid)).unwrap_or_default()
+    }
+
+    /// Checks value ranges the types alone do not enforce.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(5..=3600).contains(&self.auto_save_interval_seconds) {
+            return Err("auto_save_interval_seconds must be between 5 and 3600".to_string());
+        }
+        if !(5..=200).contains(&self.page_size) {
+            return Err("page_size must be between 5 and 200".to_string());
         }
+        Ok(())
     }
 }
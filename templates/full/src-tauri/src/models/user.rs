@@ -16,7 +16,15 @@ pub struct User {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
     pub is_active: bool,
+    /// Missing from exports made before email verification existed.
+    #[serde(default)]
+    pub email_verified: bool,
+    /// Local account without credentials until `upgrade_guest_account`.
+    #[serde(default)]
+    pub is_guest: bool,
+    #[serde(with = "crate::locale::local_time")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::locale::local_time")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -30,6 +38,9 @@ pub struct PublicUser {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
     pub is_active: bool,
+    pub email_verified: bool,
+    pub is_guest: bool,
+    #[serde(with = "crate::locale::local_time")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -61,6 +72,72 @@ pub struct UpdateUser {
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
+    /// Stable id of the signing-in device, throttled like the email.
+    #[serde(default)]
+    pub device_id: Option<String>,
+    /// Keeps the session for `SESSION_REMEMBER_ME_DAYS` instead of the idle
+    /// timeout.
+    #[serde(default)]
+    pub remember_me: bool,
+}
+
+/// Optional filters for user search.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSearchFilters {
+    pub is_active: Option<bool>,
+    /// Only users created at or after this time.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only users created before this time.
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only members of this group.
+    pub group_id: Option<Uuid>,
+}
+
+/// Column user search results are ordered by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UserSortField {
+    #[default]
+    CreatedAt,
+    Email,
+    Username,
+    LastName,
+}
+
+/// Sort direction for list queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Ordering of user search results; newest first by default.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSort {
+    #[serde(default)]
+    pub field: UserSortField,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+/// Page selection for list queries.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pagination {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A page of user search results with the total number of matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSearchResult {
+    pub users: Vec<PublicUser>,
+    pub total: i64,
 }
 
 impl From<User> for PublicUser {
@@ -73,6 +150,8 @@ impl From<User> for PublicUser {
             first_name: user.first_name,
             last_name: user.last_name,
             is_active: user.is_active,
+            email_verified: user.email_verified,
+            is_guest: user.is_guest,
             created_at: user.created_at,
         }
     }
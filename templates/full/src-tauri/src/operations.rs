@@ -0,0 +1,316 @@
+//! Progress reporting and cancellation for long-running operations.
+//!
+//! A command wraps its work in [`track`], which registers an operation and
+//! publishes `operation:progress` events as it starts, advances, and ends.
+//! Code running inside the tracked future calls [`report`] and
+//! [`check_cancelled`] without threading a handle through every signature;
+//! both do nothing outside a tracked operation, so the same code also serves
+//! scheduled jobs. Work moved to a blocking thread takes the handle along via
+//! [`current`].
+//!
+//! The frontend may pass its own operation id to a command so it can match
+//! progress events and call `cancel_operation` before the command returns.
+
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::events::{self, AppEvent};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Minimum time between progress events of one operation; reports in
+/// between only update the position carried by the next event.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Operations currently running, by id.
+static RUNNING: Lazy<Mutex<HashMap<Uuid, Operation>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+tokio::task_local! {
+    static CURRENT: Operation;
+}
+
+/// Lifecycle state carried by each progress event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Payload of the `operation:progress` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationProgress {
+    pub operation_id: Uuid,
+    /// What is running, e.g. `backup` or `migrations`.
+    pub kind: String,
+    pub status: OperationStatus,
+    /// Units of work done so far, in the operation's own unit.
+    pub done: u64,
+    pub total: Option<u64>,
+    /// Current step, or the error once the operation failed.
+    pub message: Option<String>,
+}
+
+/// Handle to a running operation.
+#[derive(Clone)]
+pub struct Operation {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    id: Uuid,
+    kind: String,
+    cancelled: AtomicBool,
+    position: Mutex<Position>,
+}
+
+/// Last reported progress, repeated in the final event.
+#[derive(Default)]
+struct Position {
+    done: u64,
+    total: Option<u64>,
+    published_at: Option<Instant>,
+}
+
+impl Operation {
+    fn new(id: Uuid, kind: &str) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                id,
+                kind: kind.to_string(),
+                cancelled: AtomicBool::new(false),
+                position: Mutex::new(Position::default()),
+            }),
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.inner.id
+    }
+
+    /// Records that `done` of `total` units are finished, publishing an
+    /// event at most every [`REPORT_INTERVAL`] and always for the last unit.
+    pub fn report(&self, done: u64, total: Option<u64>, message: Option<&str>) {
+        let due = match self.inner.position.lock() {
+            Ok(mut position) => {
+                position.done = done;
+                position.total = total;
+                let due = total == Some(done)
+                    || !matches!(position.published_at, Some(at) if at.elapsed() < REPORT_INTERVAL);
+                if due {
+                    position.published_at = Some(Instant::now());
+                }
+                due
+            }
+            Err(_) => false,
+        };
+        if due {
+            self.publish(OperationStatus::Running, message.map(str::to_string));
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fails with `CANCELLED` once cancellation was requested.
+    pub fn check_cancelled(&self) -> AppResult<()> {
+        if self.is_cancelled() {
+            Err(self.cancelled_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn cancelled_error(&self) -> AppError {
+        AppError::new(
+            ErrorCode::Cancelled,
+            format!("The {} operation was cancelled", self.inner.kind),
+        )
+    }
+
+    fn publish(&self, status: OperationStatus, message: Option<String>) {
+        let (done, total) = self
+            .inner
+            .position
+            .lock()
+            .map(|position| (position.done, position.total))
+            .unwrap_or((0, None));
+        events::publish(AppEvent::OperationProgress(OperationProgress {
+            operation_id: self.inner.id,
+            kind: self.inner.kind.clone(),
+            status,
+            done,
+            total,
+            message,
+        }));
+    }
+}
+
+/// Runs `work` as a tracked operation of `kind`, using `id` when the caller
+/// chose one.
+///
+/// Publishes a `running` event first and a `completed`, `failed`, or
+/// `cancelled` event when `work` returns. A cancelled operation fails with
+/// `CANCELLED` even when `work` wrapped the error in another code. Fails
+/// without running `work` when an operation with the same id is still
+/// running.
+pub async fn track<T, F>(kind: &str, id: Option<Uuid>, work: F) -> AppResult<T>
+where
+    F: Future<Output = AppResult<T>>,
+{
+    let operation = Operation::new(id.unwrap_or_else(Uuid::new_v4), kind);
+    {
+        let mut running = RUNNING
+            .lock()
+            .map_err(|_| AppError::internal_error("Operation registry is poisoned"))?;
+        if running.contains_key(&operation.id()) {
+            return Err(AppError::invalid_input(
+                "operationId",
+                "An operation with this id is already running",
+            ));
+        }
+        running.insert(operation.id(), operation.clone());
+    }
+
+    operation.publish(OperationStatus::Running, None);
+    let result = CURRENT.scope(operation.clone(), work).await;
+
+    if let Ok(mut running) = RUNNING.lock() {
+        running.remove(&operation.id());
+    }
+    match result {
+        Ok(value) => {
+            operation.publish(OperationStatus::Completed, None);
+            Ok(value)
+        }
+        Err(_) if operation.is_cancelled() => {
+            operation.publish(OperationStatus::Cancelled, None);
+            Err(operation.cancelled_error())
+        }
+        Err(e) => {
+            operation.publish(OperationStatus::Failed, Some(e.message.clone()));
+            Err(e)
+        }
+    }
+}
+
+/// Requests cancellation of a running operation; returns whether it was
+/// found. The operation stops at its next [`check_cancelled`].
+pub fn cancel(id: Uuid) -> bool {
+    let operation = RUNNING
+        .lock()
+        .ok()
+        .and_then(|running| running.get(&id).cloned());
+    match operation {
+        Some(operation) => {
+            operation.inner.cancelled.store(true, Ordering::SeqCst);
+            tracing::info!("Cancellation requested for operation {}", id);
+            true
+        }
+        None => false,
+    }
+}
+
+/// The operation the current task runs in, if any.
+pub fn current() -> Option<Operation> {
+    CURRENT.try_with(Operation::clone).ok()
+}
+
+/// Reports progress of the current operation, if any.
+pub fn report(done: u64, total: Option<u64>, message: Option<&str>) {
+    if let Some(operation) = current() {
+        operation.report(done, total, message);
+    }
+}
+
+/// Fails with `CANCELLED` when the current operation was cancelled.
+pub fn check_cancelled() -> AppResult<()> {
+    current().map_or(Ok(()), |operation| operation.check_cancelled())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collects the progress events of `id` published so far.
+    fn drain(
+        receiver: &mut tokio::sync::broadcast::Receiver<AppEvent>,
+        id: Uuid,
+    ) -> Vec<OperationProgress> {
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            if let AppEvent::OperationProgress(progress) = event {
+                if progress.operation_id == id {
+                    events.push(progress);
+                }
+            }
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn publishes_progress_until_completion() {
+        let mut receiver = events::subscribe();
+        let id = Uuid::new_v4();
+
+        let value = track("test", Some(id), async {
+            report(1, Some(3), Some("first"));
+            // Too soon after the previous event.
+            report(2, Some(3), Some("second"));
+            report(3, Some(3), Some("last"));
+            check_cancelled()?;
+            Ok(42)
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 42);
+
+        let statuses: Vec<_> = drain(&mut receiver, id)
+            .into_iter()
+            .map(|progress| (progress.status, progress.done, progress.message))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (OperationStatus::Running, 0, None),
+                (OperationStatus::Running, 1, Some("first".to_string())),
+                (OperationStatus::Running, 3, Some("last".to_string())),
+                (OperationStatus::Completed, 3, None),
+            ]
+        );
+        assert!(!cancel(id));
+    }
+
+    #[tokio::test]
+    async fn cancelled_operations_stop_at_the_next_check() {
+        let mut receiver = events::subscribe();
+        let id = Uuid::new_v4();
+
+        let error = track("test", Some(id), async move {
+            assert!(cancel(id));
+            check_cancelled()?;
+            Ok(())
+        })
+        .await
+        .unwrap_err();
+        assert!(matches!(error.code, ErrorCode::Cancelled));
+
+        let last = drain(&mut receiver, id).pop().unwrap();
+        assert_eq!(last.status, OperationStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn reporting_outside_an_operation_is_a_no_op() {
+        assert!(current().is_none());
+        report(1, None, None);
+        assert!(check_cancelled().is_ok());
+    }
+}
@@ -0,0 +1,308 @@
+//! Password hashing for user accounts.
+//!
+//! New hashes are Argon2id PHC strings with a random salt per hash, using
+//! the same algorithm as the Stronghold vault key. Hashes written with bcrypt
+//! by earlier versions, or with a different Argon2 cost, still verify;
+//! [`needs_rehash`] reports them so callers can replace them once the
+//! plaintext is known, i.e. after a successful login.
+//!
+//! The cost comes from [`PasswordHashing`]. Since the defaults can be slow on
+//! low-end devices, `benchmark` times hashing on the running machine and
+//! recommends a cost, which `apply` persists in the state store. Both back
+//! an admin command that needs PostgreSQL, so they are compiled only with
+//! the `database` feature.
+
+use crate::config::{self, PasswordHashing};
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::state_store;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use once_cell::sync::Lazy;
+#[cfg(feature = "database")]
+use serde::Serialize;
+use std::sync::{Once, RwLock};
+#[cfg(feature = "database")]
+use std::time::{Duration, Instant};
+
+/// PHC prefix of hashes produced by [`hash`].
+const ARGON2ID_PREFIX: &str = "$argon2id$";
+
+/// State store key holding the cost saved by `apply`.
+const TUNED_KEY: &str = "password_hashing.tuned";
+
+/// Lowest memory cost `benchmark` recommends, in KiB.
+#[cfg(feature = "database")]
+const MIN_MEMORY_KIB: u32 = 8 * 1024;
+
+/// Most passes `benchmark` recommends.
+#[cfg(feature = "database")]
+const MAX_ITERATIONS: u32 = 10;
+
+/// Hashes timed per measurement; the fastest one counts.
+#[cfg(feature = "database")]
+const SAMPLES: u32 = 3;
+
+/// Cost saved by `apply`, applied over the environment's.
+static TUNED: Lazy<RwLock<Option<PasswordHashing>>> = Lazy::new(|| RwLock::new(None));
+
+static TUNED_OVERRIDE: Once = Once::new();
+
+/// Hashing time on this machine at the current and recommended costs.
+#[cfg(feature = "database")]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashingBenchmark {
+    pub target_ms: u64,
+    pub current: PasswordHashing,
+    pub current_ms: u64,
+    /// Highest cost that hashes within the target, or the lowest cost
+    /// considered when none does.
+    pub recommended: PasswordHashing,
+    pub recommended_ms: u64,
+    /// Whether new hashes now use `recommended`.
+    pub applied: bool,
+}
+
+/// Hashes `password` with Argon2id at the configured cost and a fresh
+/// random salt.
+pub fn hash(password: &str) -> AppResult<String> {
+    hash_with(password, &config::current().password_hashing)
+}
+
+fn hash_with(password: &str, cost: &PasswordHashing) -> AppResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    hasher(cost)?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::internal_error(format!("Failed to hash password: {}", e)))
+}
+
+fn hasher(cost: &PasswordHashing) -> AppResult<Argon2<'static>> {
+    let params =
+        Params::new(cost.memory_kib, cost.iterations, cost.parallelism, None).map_err(|e| {
+            AppError::invalid_input("cost", format!("Invalid password hashing cost: {}", e))
+        })?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+/// Checks `password` against an Argon2id or legacy bcrypt hash.
+pub fn verify(password: &str, hash: &str) -> AppResult<bool> {
+    if is_bcrypt(hash) {
+        return bcrypt::verify(password, hash).map_err(invalid_hash);
+    }
+
+    let parsed = PasswordHash::new(hash).map_err(invalid_hash)?;
+    match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(invalid_hash(e)),
+    }
+}
+
+/// Returns whether `hash` was not produced by [`hash`] at the current cost
+/// and should be replaced.
+pub fn needs_rehash(hash: &str) -> bool {
+    if !hash.starts_with(ARGON2ID_PREFIX) {
+        return true;
+    }
+    let cost = &config::current().password_hashing;
+    let params = PasswordHash::new(hash)
+        .ok()
+        .and_then(|parsed| Params::try_from(&parsed).ok());
+    params.map_or(true, |params| {
+        params.m_cost() != cost.memory_kib
+            || params.t_cost() != cost.iterations
+            || params.p_cost() != cost.parallelism
+    })
+}
+
+/// Times hashing at the configured cost and recommends the highest cost
+/// that hashes within `target`.
+#[cfg(feature = "database")]
+pub fn benchmark(target: Duration) -> AppResult<HashingBenchmark> {
+    let current = config::current().password_hashing.clone();
+    let current_elapsed = time_hash(&current)?;
+    let (recommended, recommended_elapsed) = recommend(&current, target, time_hash)?;
+    tracing::info!(
+        "Password hashing takes {:?} at {:?}; recommending {:?} at {:?}",
+        current_elapsed,
+        current,
+        recommended,
+        recommended_elapsed
+    );
+
+    Ok(HashingBenchmark {
+        target_ms: target.as_millis() as u64,
+        current,
+        current_ms: current_elapsed.as_millis() as u64,
+        recommended,
+        recommended_ms: recommended_elapsed.as_millis() as u64,
+        applied: false,
+    })
+}
+
+/// Keeps the configured memory and parallelism, halving the memory until a
+/// single pass fits `target`, then adds as many passes as fit.
+#[cfg(feature = "database")]
+fn recommend(
+    base: &PasswordHashing,
+    target: Duration,
+    mut time: impl FnMut(&PasswordHashing) -> AppResult<Duration>,
+) -> AppResult<(PasswordHashing, Duration)> {
+    let mut cost = PasswordHashing {
+        iterations: 1,
+        ..base.clone()
+    };
+    let mut single_pass = time(&cost)?;
+    while single_pass > target && cost.memory_kib / 2 >= MIN_MEMORY_KIB {
+        cost.memory_kib /= 2;
+        single_pass = time(&cost)?;
+    }
+
+    let passes = target.as_nanos() / single_pass.as_nanos().max(1);
+    cost.iterations = passes.clamp(1, MAX_ITERATIONS as u128) as u32;
+    if cost.iterations == 1 {
+        return Ok((cost, single_pass));
+    }
+    let elapsed = time(&cost)?;
+    Ok((cost, elapsed))
+}
+
+#[cfg(feature = "database")]
+fn time_hash(cost: &PasswordHashing) -> AppResult<Duration> {
+    let mut fastest = Duration::MAX;
+    for _ in 0..SAMPLES {
+        let started = Instant::now();
+        hash_with("benchmark-password", cost)?;
+        fastest = fastest.min(started.elapsed());
+    }
+    Ok(fastest)
+}
+
+/// Hashes new passwords at `cost` from now on, including after restarts.
+#[cfg(feature = "database")]
+pub fn apply(cost: PasswordHashing) -> AppResult<()> {
+    hasher(&cost)?;
+    let value = serde_json::to_value(&cost)
+        .map_err(|e| AppError::internal_error(format!("Failed to encode cost: {}", e)))?;
+    state_store::set(TUNED_KEY, value)?;
+    tracing::info!("Password hashing cost set to {:?}", cost);
+    set_tuned(cost);
+    Ok(())
+}
+
+/// Restores the cost saved by `apply`, returning it.
+pub fn restore_tuning() -> AppResult<Option<PasswordHashing>> {
+    let cost = state_store::get(TUNED_KEY)?
+        .and_then(|value| serde_json::from_value::<PasswordHashing>(value).ok());
+    if let Some(cost) = &cost {
+        set_tuned(cost.clone());
+    }
+    Ok(cost)
+}
+
+fn set_tuned(cost: PasswordHashing) {
+    if let Ok(mut tuned) = TUNED.write() {
+        *tuned = Some(cost);
+    }
+    TUNED_OVERRIDE.call_once(|| {
+        config::add_override(Box::new(|config| {
+            if let Some(cost) = TUNED.read().ok().and_then(|tuned| tuned.clone()) {
+                config.password_hashing = cost;
+            }
+        }));
+    });
+    config::reload();
+}
+
+fn is_bcrypt(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
+fn invalid_hash(e: impl std::fmt::Display) -> AppError {
+    AppError::new(
+        ErrorCode::InvalidFormat,
+        format!("Failed to verify password: {}", e),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_with_argon2id_and_unique_salts() {
+        let first = hash("S3cret!").unwrap();
+        let second = hash("S3cret!").unwrap();
+        assert!(first.starts_with(ARGON2ID_PREFIX));
+        assert_ne!(first, second);
+        assert!(!needs_rehash(&first));
+
+        assert!(verify("S3cret!", &first).unwrap());
+        assert!(verify("S3cret!", &second).unwrap());
+        assert!(!verify("wrong", &first).unwrap());
+    }
+
+    #[test]
+    fn verifies_legacy_bcrypt_hashes() {
+        let legacy = bcrypt::hash("S3cret!", 4).unwrap();
+        assert!(needs_rehash(&legacy));
+        assert!(verify("S3cret!", &legacy).unwrap());
+        assert!(!verify("wrong", &legacy).unwrap());
+    }
+
+    #[test]
+    fn hashes_at_other_costs_need_rehashing() {
+        let current = config::current().password_hashing.clone();
+        let cheaper = PasswordHashing {
+            memory_kib: MIN_MEMORY_KIB,
+            iterations: 1,
+            ..current.clone()
+        };
+        let hashed = hash_with("S3cret!", &cheaper).unwrap();
+        assert!(verify("S3cret!", &hashed).unwrap());
+        assert_eq!(needs_rehash(&hashed), cheaper != current);
+
+        let invalid = PasswordHashing {
+            parallelism: 0,
+            ..current
+        };
+        assert!(hash_with("S3cret!", &invalid).is_err());
+    }
+
+    #[cfg(feature = "database")]
+    #[test]
+    fn recommends_the_highest_cost_within_the_target() {
+        // Pretend one pass over 1 MiB takes a millisecond.
+        let time = |cost: &PasswordHashing| {
+            Ok(Duration::from_millis(
+                (cost.memory_kib / 1024 * cost.iterations) as u64,
+            ))
+        };
+        let base = PasswordHashing {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        };
+
+        let (cost, elapsed) = recommend(&base, Duration::from_millis(200), time).unwrap();
+        assert_eq!((cost.memory_kib, cost.iterations), (64 * 1024, 3));
+        assert_eq!(elapsed, Duration::from_millis(192));
+
+        let (cost, _) = recommend(&base, Duration::from_millis(20), time).unwrap();
+        assert_eq!((cost.memory_kib, cost.iterations), (16 * 1024, 1));
+
+        let (cost, elapsed) = recommend(&base, Duration::from_millis(1), time).unwrap();
+        assert_eq!((cost.memory_kib, cost.iterations), (MIN_MEMORY_KIB, 1));
+        assert_eq!(elapsed, Duration::from_millis(8));
+    }
+
+    #[test]
+    fn rejects_malformed_hashes() {
+        assert!(verify("S3cret!", "not a hash").is_err());
+        assert!(needs_rehash("not a hash"));
+    }
+}
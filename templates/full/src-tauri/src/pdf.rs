@@ -0,0 +1,469 @@
+//! PDF generation for invoices and reports.
+//!
+//! Documents are laid out from built-in templates with the PDF base fonts, so
+//! no headless browser or font files are needed. Output is written inside the
+//! filesystem scope.
+
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::handlers::filesystem::{ensure_write_permitted, resolve_relative_path};
+use chrono::Utc;
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// A4 page width.
+const PAGE_WIDTH: f32 = 210.0;
+/// A4 page height.
+const PAGE_HEIGHT: f32 = 297.0;
+/// Margin on every side of the page.
+const MARGIN: f32 = 20.0;
+/// Millimetres per typographic point.
+const MM_PER_PT: f32 = 0.3528;
+/// Approximate average glyph width of Helvetica relative to the font size.
+const AVG_GLYPH_WIDTH: f32 = 0.5;
+
+/// Layouts available to `generate_pdf`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PdfTemplate {
+    Invoice,
+    Report,
+}
+
+impl PdfTemplate {
+    fn name(&self) -> &'static str {
+        match self {
+            PdfTemplate::Invoice => "invoice",
+            PdfTemplate::Report => "report",
+        }
+    }
+}
+
+/// Data for the invoice template.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceData {
+    pub number: String,
+    pub date: Option<String>,
+    pub from: Vec<String>,
+    pub bill_to: Vec<String>,
+    pub items: Vec<InvoiceItem>,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Tax rate as a percentage, e.g. `20.0`.
+    pub tax_rate: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// A single invoice line.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceItem {
+    pub description: String,
+    pub quantity: f64,
+    pub unit_price: f64,
+}
+
+/// Data for the report template.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportData {
+    pub title: String,
+    pub subtitle: Option<String>,
+    #[serde(default)]
+    pub sections: Vec<ReportSection>,
+}
+
+/// A heading followed by paragraphs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSection {
+    pub heading: String,
+    #[serde(default)]
+    pub paragraphs: Vec<String>,
+}
+
+/// Result of generating a PDF.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedPdf {
+    /// Path relative to the filesystem scope.
+    pub path: String,
+    pub pages: usize,
+    pub size_bytes: usize,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// Renders `template` with `data` and writes it to `destination` in the fs scope.
+pub fn generate(
+    template: PdfTemplate,
+    data: serde_json::Value,
+    destination: Option<String>,
+) -> AppResult<GeneratedPdf> {
+    let destination = destination.unwrap_or_else(|| {
+        format!(
+            "reports/{}-{}.pdf",
+            template.name(),
+            Utc::now().format("%Y%m%d%H%M%S")
+        )
+    });
+    if !destination.to_lowercase().ends_with(".pdf") {
+        return Err(AppError::invalid_input(
+            "destination",
+            "Destination must end with .pdf",
+        ));
+    }
+
+    let (bytes, pages) = match template {
+        PdfTemplate::Invoice => render_invoice(&parse_data(data)?)?,
+        PdfTemplate::Report => render_report(&parse_data(data)?)?,
+    };
+
+    let context = resolve_relative_path(&destination)
+        .map_err(|e| AppError::file_error("write", destination.clone(), e))?;
+    ensure_write_permitted(&context.path)?;
+    if let Some(parent) = context.path.parent() {
+        fs::create_dir_all(parent).into_app_error(ErrorCode::DirectoryCreate)?;
+    }
+    fs::write(&context.path, &bytes)
+        .map_err(|e| AppError::file_error("write", context.relative_display(), e.to_string()))?;
+
+    tracing::info!(
+        "Generated {} PDF at {}",
+        template.name(),
+        context.relative_display()
+    );
+    Ok(GeneratedPdf {
+        path: context.relative_display(),
+        pages,
+        size_bytes: bytes.len(),
+    })
+}
+
+fn parse_data<T: for<'de> Deserialize<'de>>(data: serde_json::Value) -> AppResult<T> {
+    serde_json::from_value(data)
+        .map_err(|e| AppError::invalid_input("data", format!("Invalid template data: {}", e)))
+}
+
+fn render_invoice(invoice: &InvoiceData) -> AppResult<(Vec<u8>, usize)> {
+    let mut writer = PageWriter::new(&format!("Invoice {}", invoice.number))?;
+
+    writer.text(&format!("Invoice {}", invoice.number), 20.0, true);
+    if let Some(date) = &invoice.date {
+        writer.text(&format!("Date: {}", date), 10.0, false);
+    }
+    writer.space(4.0);
+
+    writer.text("From", 11.0, true);
+    for line in &invoice.from {
+        writer.text(line, 10.0, false);
+    }
+    writer.space(3.0);
+    writer.text("Bill to", 11.0, true);
+    for line in &invoice.bill_to {
+        writer.text(line, 10.0, false);
+    }
+    writer.space(6.0);
+
+    writer.columns(&["Description", "Qty", "Unit price", "Amount"], 10.0, true);
+    for item in &invoice.items {
+        writer.columns(
+            &[
+                &item.description,
+                &format_number(item.quantity),
+                &format_money(item.unit_price, &invoice.currency),
+                &format_money(item.quantity * item.unit_price, &invoice.currency),
+            ],
+            10.0,
+            false,
+        );
+    }
+    writer.space(4.0);
+
+    let totals = invoice_totals(invoice);
+    writer.columns(
+        &[
+            "",
+            "",
+            "Subtotal",
+            &format_money(totals.subtotal, &invoice.currency),
+        ],
+        10.0,
+        false,
+    );
+    if let Some(rate) = invoice.tax_rate {
+        writer.columns(
+            &[
+                "",
+                "",
+                &format!("Tax ({}%)", format_number(rate)),
+                &format_money(totals.tax, &invoice.currency),
+            ],
+            10.0,
+            false,
+        );
+    }
+    writer.columns(
+        &[
+            "",
+            "",
+            "Total",
+            &format_money(totals.total, &invoice.currency),
+        ],
+        11.0,
+        true,
+    );
+
+    if let Some(notes) = &invoice.notes {
+        writer.space(8.0);
+        writer.paragraph(notes, 10.0);
+    }
+
+    writer.finish()
+}
+
+fn render_report(report: &ReportData) -> AppResult<(Vec<u8>, usize)> {
+    let mut writer = PageWriter::new(&report.title)?;
+
+    writer.paragraph_with(&report.title, 20.0, true);
+    if let Some(subtitle) = &report.subtitle {
+        writer.paragraph(subtitle, 12.0);
+    }
+    writer.space(6.0);
+
+    for section in &report.sections {
+        writer.paragraph_with(&section.heading, 14.0, true);
+        writer.space(1.0);
+        for paragraph in &section.paragraphs {
+            writer.paragraph(paragraph, 11.0);
+            writer.space(2.0);
+        }
+        writer.space(4.0);
+    }
+
+    writer.finish()
+}
+
+struct InvoiceTotals {
+    subtotal: f64,
+    tax: f64,
+    total: f64,
+}
+
+fn invoice_totals(invoice: &InvoiceData) -> InvoiceTotals {
+    let subtotal: f64 = invoice
+        .items
+        .iter()
+        .map(|item| item.quantity * item.unit_price)
+        .sum();
+    let tax = subtotal * invoice.tax_rate.unwrap_or(0.0) / 100.0;
+    InvoiceTotals {
+        subtotal,
+        tax,
+        total: subtotal + tax,
+    }
+}
+
+fn format_money(amount: f64, currency: &str) -> String {
+    format!("{} {:.2}", currency, amount)
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+/// Splits `text` into lines of at most `max_chars` characters on word boundaries.
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for source_line in text.lines() {
+        let mut line = String::new();
+        for word in source_line.split_whitespace() {
+            let needed = if line.is_empty() {
+                word.chars().count()
+            } else {
+                line.chars().count() + 1 + word.chars().count()
+            };
+            if needed > max_chars && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Writes lines top to bottom, starting new pages as needed.
+struct PageWriter {
+    doc: PdfDocumentReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    layer: PdfLayerReference,
+    y: f32,
+    pages: usize,
+}
+
+impl PageWriter {
+    fn new(title: &str) -> AppResult<Self> {
+        let (doc, page, layer) =
+            PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Content");
+        let regular = doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .into_app_error(ErrorCode::InternalError)?;
+        let bold = doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .into_app_error(ErrorCode::InternalError)?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        Ok(Self {
+            doc,
+            regular,
+            bold,
+            layer,
+            y: PAGE_HEIGHT - MARGIN,
+            pages: 1,
+        })
+    }
+
+    fn line_height(size: f32) -> f32 {
+        size * 1.4 * MM_PER_PT
+    }
+
+    fn ensure_room(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            let (page, layer) = self
+                .doc
+                .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Content");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+            self.pages += 1;
+        }
+    }
+
+    fn font(&self, bold: bool) -> &IndirectFontRef {
+        if bold {
+            &self.bold
+        } else {
+            &self.regular
+        }
+    }
+
+    fn text(&mut self, text: &str, size: f32, bold: bool) {
+        let height = Self::line_height(size);
+        self.ensure_room(height);
+        self.y -= height;
+        self.layer
+            .use_text(text, size, Mm(MARGIN), Mm(self.y), self.font(bold));
+    }
+
+    fn paragraph(&mut self, text: &str, size: f32) {
+        self.paragraph_with(text, size, false);
+    }
+
+    fn paragraph_with(&mut self, text: &str, size: f32, bold: bool) {
+        let max_chars =
+            ((PAGE_WIDTH - 2.0 * MARGIN) / (size * AVG_GLYPH_WIDTH * MM_PER_PT)) as usize;
+        for line in wrap(text, max_chars.max(1)) {
+            self.text(&line, size, bold);
+        }
+    }
+
+    /// Writes a table row with a wide first column and right-hand value columns.
+    fn columns(&mut self, cells: &[&str], size: f32, bold: bool) {
+        const OFFSETS: [f32; 4] = [0.0, 95.0, 115.0, 145.0];
+
+        let height = Self::line_height(size);
+        self.ensure_room(height);
+        self.y -= height;
+        for (cell, offset) in cells.iter().zip(OFFSETS) {
+            self.layer.use_text(
+                *cell,
+                size,
+                Mm(MARGIN + offset),
+                Mm(self.y),
+                self.font(bold),
+            );
+        }
+    }
+
+    fn space(&mut self, mm: f32) {
+        self.y -= mm;
+    }
+
+    fn finish(self) -> AppResult<(Vec<u8>, usize)> {
+        let pages = self.pages;
+        let bytes = self
+            .doc
+            .save_to_bytes()
+            .into_app_error(ErrorCode::InternalError)?;
+        Ok((bytes, pages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn wraps_on_word_boundaries() {
+        let lines = wrap("the quick brown fox jumps", 10);
+        assert_eq!(lines, vec!["the quick", "brown fox", "jumps"]);
+        assert_eq!(wrap("a\nb", 10), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn computes_invoice_totals() {
+        let invoice: InvoiceData = serde_json::from_value(json!({
+            "number": "INV-1",
+            "from": ["Acme"],
+            "billTo": ["Globex"],
+            "items": [
+                { "description": "Widget", "quantity": 2, "unitPrice": 10.0 },
+                { "description": "Support", "quantity": 1, "unitPrice": 5.5 }
+            ],
+            "taxRate": 10.0
+        }))
+        .unwrap();
+
+        let totals = invoice_totals(&invoice);
+        assert_eq!(totals.subtotal, 25.5);
+        assert!((totals.total - 28.05).abs() < 1e-9);
+        assert_eq!(invoice.currency, "USD");
+    }
+
+    #[test]
+    fn long_reports_span_multiple_pages() {
+        let report: ReportData = serde_json::from_value(json!({
+            "title": "Quarterly report",
+            "sections": [{ "heading": "Summary", "paragraphs": vec!["Lorem ipsum dolor sit amet."; 120] }]
+        }))
+        .unwrap();
+
+        let (bytes, pages) = render_report(&report).unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+        assert!(pages > 1);
+    }
+
+    #[test]
+    fn rejects_non_pdf_destinations() {
+        let result = generate(
+            PdfTemplate::Report,
+            json!({ "title": "x" }),
+            Some("out.txt".to_string()),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,555 @@
+//! User data export and import for moving accounts between installations.
+//!
+//! An export is a zip archive inside the filesystem scope containing a
+//! manifest plus the user's row, settings, and logs. Imports validate the
+//! archive and apply it in a single transaction using a conflict strategy.
+
+use crate::command_context::CommandContext;
+use crate::database::get_pool_ref;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::handlers::filesystem::{resolve_existing_path, resolve_relative_path};
+use crate::models::{AppLog, User, UserSettings};
+use crate::operations;
+use crate::validation::{validate_email, validate_username};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+
+/// Archive layout version written to the manifest.
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Serialization format for the records inside the archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One pretty-printed JSON document per record type.
+    Json,
+    /// Newline-delimited JSON, convenient for streaming large log sets.
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// How to handle records that already exist during import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictStrategy {
+    /// Abort the import if the user already exists.
+    #[default]
+    Fail,
+    /// Keep existing records and only add missing ones.
+    Skip,
+    /// Replace existing records with the archived versions.
+    Overwrite,
+}
+
+/// Manifest describing an export archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportManifest {
+    pub version: u32,
+    pub format: ExportFormat,
+    pub user_id: Uuid,
+    pub exported_at: DateTime<Utc>,
+    pub settings_count: usize,
+    pub log_count: usize,
+}
+
+/// Result of an export or import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferSummary {
+    pub path: String,
+    pub user_id: Uuid,
+    pub users: usize,
+    pub settings: usize,
+    pub logs: usize,
+    pub skipped: usize,
+}
+
+/// Records loaded for a single user.
+pub(crate) struct UserRecords {
+    pub(crate) user: User,
+    pub(crate) settings: Vec<UserSettings>,
+    pub(crate) logs: Vec<AppLog>,
+}
+
+/// Loads the user row, settings, and logs belonging to `user_id`.
+pub(crate) async fn load_user_records(user_id: Uuid) -> AppResult<UserRecords> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        SELECT id, email, username, password_hash, first_name, last_name,
+               is_active, email_verified, is_guest, created_at, updated_at
+        FROM users
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?
+    .ok_or_else(|| AppError::not_found("User"))?;
+
+    let settings = sqlx::query_as::<_, UserSettings>(
+        r#"
+        SELECT id, user_id, theme, language, notifications_enabled, settings_data,
+               created_at, updated_at
+        FROM user_settings
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let logs = sqlx::query_as::<_, AppLog>(
+        r#"
+        SELECT id, level, message, metadata, user_id, tags, created_at
+        FROM app_logs
+        WHERE user_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    Ok(UserRecords {
+        user,
+        settings,
+        logs,
+    })
+}
+
+/// Writes a zip export of the user's data to `destination` inside the fs scope.
+pub async fn export_user_data(
+    user_id: Uuid,
+    format: ExportFormat,
+    destination: Option<String>,
+) -> AppResult<TransferSummary> {
+    let records = load_user_records(user_id).await?;
+
+    let destination = destination.unwrap_or_else(|| {
+        format!(
+            "exports/user-{}-{}.zip",
+            user_id,
+            Utc::now().format("%Y%m%d%H%M%S")
+        )
+    });
+    let context = resolve_relative_path(&destination)
+        .map_err(|e| AppError::file_error("write", destination.clone(), e))?;
+
+    let manifest = ExportManifest {
+        version: ARCHIVE_VERSION,
+        format,
+        user_id,
+        exported_at: Utc::now(),
+        settings_count: records.settings.len(),
+        log_count: records.logs.len(),
+    };
+
+    if let Some(parent) = context.path.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            AppError::file_error("create", context.relative_display(), e.to_string())
+        })?;
+    }
+
+    let file = File::create(&context.path)
+        .map_err(|e| AppError::file_error("write", context.relative_display(), e.to_string()))?;
+    let mut archive = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let write_entry =
+        |archive: &mut zip::ZipWriter<File>, name: &str, bytes: Vec<u8>| -> AppResult<()> {
+            archive
+                .start_file(name, options)
+                .into_app_error(ErrorCode::FileWrite)?;
+            archive
+                .write_all(&bytes)
+                .into_app_error(ErrorCode::FileWrite)
+        };
+
+    let ext = format.extension();
+    write_entry(&mut archive, "manifest.json", to_json_bytes(&manifest)?)?;
+    write_entry(
+        &mut archive,
+        &format!("user.{}", ext),
+        encode(format, &[&records.user])?,
+    )?;
+    write_entry(
+        &mut archive,
+        &format!("settings.{}", ext),
+        encode(format, &records.settings)?,
+    )?;
+    write_entry(
+        &mut archive,
+        &format!("logs.{}", ext),
+        encode(format, &records.logs)?,
+    )?;
+
+    archive.finish().into_app_error(ErrorCode::FileWrite)?;
+
+    tracing::info!(
+        "Exported data for user {} to {}",
+        user_id,
+        context.relative_display()
+    );
+
+    Ok(TransferSummary {
+        path: context.relative_display(),
+        user_id,
+        users: 1,
+        settings: records.settings.len(),
+        logs: records.logs.len(),
+        skipped: 0,
+    })
+}
+
+/// Imports a previously exported archive from `source` inside the fs scope.
+///
+/// Only admins may import another user's archive; the archive is checked
+/// against `context` before anything is written.
+pub async fn import_user_data(
+    context: &CommandContext,
+    source: &str,
+    strategy: ConflictStrategy,
+) -> AppResult<TransferSummary> {
+    let context =
+        resolve_existing_path(source).map_err(|e| AppError::file_error("read", source, e))?;
+
+    let file = File::open(&context.path)
+        .map_err(|e| AppError::file_error("read", context.relative_display(), e.to_string()))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| {
+        AppError::new(
+            ErrorCode::InvalidFormat,
+            format!("Not a valid export archive: {}", e),
+        )
+    })?;
+
+    let manifest: ExportManifest =
+        serde_json::from_slice(&read_entry(&mut archive, "manifest.json")?).map_err(|e| {
+            AppError::new(ErrorCode::InvalidFormat, format!("Invalid manifest: {}", e))
+        })?;
+
+    if manifest.version > ARCHIVE_VERSION {
+        return Err(AppError::new(
+            ErrorCode::InvalidFormat,
+            format!(
+                "Archive version {} is newer than supported version {}",
+                manifest.version, ARCHIVE_VERSION
+            ),
+        ));
+    }
+
+    let ext = manifest.format.extension();
+    let users: Vec<User> = decode(
+        manifest.format,
+        &read_entry(&mut archive, &format!("user.{}", ext))?,
+    )?;
+    let settings: Vec<UserSettings> = decode(
+        manifest.format,
+        &read_entry(&mut archive, &format!("settings.{}", ext))?,
+    )?;
+    let logs: Vec<AppLog> = decode(
+        manifest.format,
+        &read_entry(&mut archive, &format!("logs.{}", ext))?,
+    )?;
+
+    let user = users.into_iter().next().ok_or_else(|| {
+        AppError::new(ErrorCode::InvalidFormat, "Archive does not contain a user")
+    })?;
+
+    if user.id != manifest.user_id
+        || settings.iter().any(|s| s.user_id != user.id)
+        || logs
+            .iter()
+            .any(|l| l.user_id.is_some_and(|id| id != user.id))
+    {
+        return Err(AppError::new(
+            ErrorCode::InvalidFormat,
+            "Archive records reference a different user",
+        ));
+    }
+    context.require_self_or_admin(user.id)?;
+
+    let email =
+        validate_email(&user.email).map_err(|e| AppError::invalid_input("email", e.to_string()))?;
+    let username = validate_username(&user.username)
+        .map_err(|e| AppError::invalid_input("username", e.to_string()))?;
+
+    // Progress counts records; cancelling before the commit rolls the
+    // import back.
+    let total = (1 + settings.len() + logs.len()) as u64;
+    let mut done = 0;
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let existing: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM users WHERE id = $1 OR email = $2 OR username = $3 LIMIT 1")
+            .bind(user.id)
+            .bind(&email)
+            .bind(&username)
+            .fetch_optional(&mut *tx)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let mut skipped = 0;
+    let mut imported_users = 0;
+
+    match (existing, strategy) {
+        (Some(_), ConflictStrategy::Fail) => {
+            return Err(AppError::new(
+                ErrorCode::ValidationError,
+                "A user with the same id, email, or username already exists",
+            )
+            .with_context(serde_json::json!({ "userId": user.id })));
+        }
+        (Some((existing_id,)), _) if existing_id != user.id => {
+            return Err(AppError::new(
+                ErrorCode::ValidationError,
+                "The email or username belongs to a different existing user",
+            ));
+        }
+        (Some(_), ConflictStrategy::Skip) => skipped += 1,
+        (Some(_), ConflictStrategy::Overwrite) => {
+            sqlx::query(
+                r#"
+                UPDATE users
+                SET email = $2, username = $3, password_hash = $4, first_name = $5,
+                    last_name = $6, is_active = $7, email_verified = $8,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1
+                "#,
+            )
+            .bind(user.id)
+            .bind(&email)
+            .bind(&username)
+            .bind(&user.password_hash)
+            .bind(&user.first_name)
+            .bind(&user.last_name)
+            .bind(user.is_active)
+            .bind(user.email_verified)
+            .execute(&mut *tx)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+            imported_users += 1;
+        }
+        (None, _) => {
+            sqlx::query(
+                r#"
+                INSERT INTO users (id, email, username, password_hash, first_name, last_name,
+                                   is_active, email_verified, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                "#,
+            )
+            .bind(user.id)
+            .bind(&email)
+            .bind(&username)
+            .bind(&user.password_hash)
+            .bind(&user.first_name)
+            .bind(&user.last_name)
+            .bind(user.is_active)
+            .bind(user.email_verified)
+            .bind(user.created_at)
+            .bind(user.updated_at)
+            .execute(&mut *tx)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+            imported_users += 1;
+        }
+    }
+
+    done += 1;
+    operations::report(done, Some(total), None);
+
+    let settings_conflict = if strategy == ConflictStrategy::Overwrite {
+        r#"ON CONFLICT (user_id) DO UPDATE SET theme = EXCLUDED.theme,
+               language = EXCLUDED.language,
+               notifications_enabled = EXCLUDED.notifications_enabled,
+               settings_data = EXCLUDED.settings_data,
+               updated_at = CURRENT_TIMESTAMP"#
+    } else {
+        "ON CONFLICT (user_id) DO NOTHING"
+    };
+
+    let mut imported_settings = 0;
+    for setting in &settings {
+        operations::check_cancelled()?;
+        let result = sqlx::query(&format!(
+            r#"
+            INSERT INTO user_settings (id, user_id, theme, language, notifications_enabled,
+                                       settings_data, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            {}
+            "#,
+            settings_conflict
+        ))
+        .bind(setting.id)
+        .bind(setting.user_id)
+        .bind(&setting.theme)
+        .bind(&setting.language)
+        .bind(setting.notifications_enabled)
+        .bind(&setting.settings_data)
+        .bind(setting.created_at)
+        .bind(setting.updated_at)
+        .execute(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+        if result.rows_affected() > 0 {
+            imported_settings += 1;
+        } else {
+            skipped += 1;
+        }
+        done += 1;
+        operations::report(done, Some(total), None);
+    }
+
+    let mut imported_logs = 0;
+    for log in &logs {
+        operations::check_cancelled()?;
+        let result = sqlx::query(
+            r#"
+            INSERT INTO app_logs (id, level, message, metadata, user_id, tags, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id, created_at) DO NOTHING
+            "#,
+        )
+        .bind(log.id)
+        .bind(&log.level)
+        .bind(&log.message)
+        .bind(&log.metadata)
+        .bind(log.user_id)
+        .bind(&log.tags)
+        .bind(log.created_at)
+        .execute(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+        if result.rows_affected() > 0 {
+            imported_logs += 1;
+        } else {
+            skipped += 1;
+        }
+        done += 1;
+        operations::report(done, Some(total), None);
+    }
+
+    operations::check_cancelled()?;
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+
+    tracing::info!(
+        "Imported data for user {} from {}",
+        user.id,
+        context.relative_display()
+    );
+
+    Ok(TransferSummary {
+        path: context.relative_display(),
+        user_id: user.id,
+        users: imported_users,
+        settings: imported_settings,
+        logs: imported_logs,
+        skipped,
+    })
+}
+
+fn to_json_bytes<T: Serialize + ?Sized>(value: &T) -> AppResult<Vec<u8>> {
+    serde_json::to_vec_pretty(value).into_app_error(ErrorCode::InternalError)
+}
+
+/// Encodes a slice of records in the requested format.
+fn encode<T: Serialize>(format: ExportFormat, records: &[T]) -> AppResult<Vec<u8>> {
+    match format {
+        ExportFormat::Json => to_json_bytes(records),
+        ExportFormat::Ndjson => {
+            let mut out = Vec::new();
+            for record in records {
+                serde_json::to_writer(&mut out, record).into_app_error(ErrorCode::InternalError)?;
+                out.push(b'\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Decodes records previously written by [`encode`].
+fn decode<T: for<'de> Deserialize<'de>>(format: ExportFormat, bytes: &[u8]) -> AppResult<Vec<T>> {
+    let invalid = |e: serde_json::Error| {
+        AppError::new(
+            ErrorCode::InvalidFormat,
+            format!("Invalid archive entry: {}", e),
+        )
+    };
+
+    match format {
+        ExportFormat::Json => serde_json::from_slice(bytes).map_err(invalid),
+        ExportFormat::Ndjson => bytes
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(|line| serde_json::from_slice(line).map_err(invalid))
+            .collect(),
+    }
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> AppResult<Vec<u8>> {
+    let mut entry = archive.by_name(name).map_err(|_| {
+        AppError::new(
+            ErrorCode::InvalidFormat,
+            format!("Archive is missing '{}'", name),
+        )
+    })?;
+    let mut bytes = Vec::new();
+    entry
+        .read_to_end(&mut bytes)
+        .into_app_error(ErrorCode::FileRead)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ndjson_round_trips_records() {
+        let records = vec![json!({"a": 1}), json!({"a": 2})];
+        let bytes = encode(ExportFormat::Ndjson, &records).unwrap();
+        assert_eq!(bytes.iter().filter(|b| **b == b'\n').count(), 2);
+
+        let decoded: Vec<serde_json::Value> = decode(ExportFormat::Ndjson, &bytes).unwrap();
+        assert_eq!(decoded, records);
+    }
+
+    #[test]
+    fn json_round_trips_records() {
+        let records = vec![json!({"b": true})];
+        let bytes = encode(ExportFormat::Json, &records).unwrap();
+        let decoded: Vec<serde_json::Value> = decode(ExportFormat::Json, &bytes).unwrap();
+        assert_eq!(decoded, records);
+    }
+
+    #[test]
+    fn decode_reports_invalid_entries() {
+        let result: AppResult<Vec<serde_json::Value>> = decode(ExportFormat::Json, b"not json");
+        assert!(matches!(result, Err(e) if matches!(e.code, ErrorCode::InvalidFormat)));
+    }
+}
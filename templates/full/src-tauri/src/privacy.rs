@@ -0,0 +1,425 @@
+//! Per-user personal data export and erasure.
+//!
+//! Erasure removes the user's rows, cached entries stored under
+//! [`cache::user_key_prefix`], and export archives left in the filesystem
+//! scope. Both operations are written to the audit log.
+//!
+//! Users can also delete their own account with a grace period: the account
+//! is deactivated and signed out at once, and erased by a background task
+//! once `ACCOUNT_DELETION_GRACE_DAYS` have passed unless the user signs in
+//! to cancel.
+
+use crate::audit;
+use crate::cache;
+use crate::config;
+use crate::database::get_pool_ref;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::handlers::filesystem::resolve_relative_path;
+use crate::password;
+use crate::portability::{self, ExportFormat, TransferSummary};
+use crate::session;
+use crate::shutdown;
+use crate::sync::{self, SyncOperation};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Directory inside the filesystem scope where exports are written by default.
+const EXPORT_DIR: &str = "exports";
+
+/// Interval between runs of the task erasing accounts past their grace
+/// period.
+const DELETION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Counts of what was removed for an erased user.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErasureSummary {
+    pub user_id: Uuid,
+    pub settings: u64,
+    pub logs: u64,
+    pub notifications: u64,
+    pub memberships: u64,
+    pub cache_keys: usize,
+    pub files: usize,
+}
+
+/// Writes a JSON archive of the user's account, settings, and logs. The
+/// audit entry names `actor_id` as the one who asked for it.
+pub async fn export_personal_data(
+    actor_id: Option<Uuid>,
+    user_id: Uuid,
+    destination: Option<String>,
+) -> AppResult<TransferSummary> {
+    let summary = portability::export_user_data(user_id, ExportFormat::Json, destination).await?;
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    audit::record_by(
+        pool.as_ref(),
+        actor_id,
+        audit::PERSONAL_DATA_EXPORTED,
+        Some(user_id),
+        json!({ "path": summary.path, "settings": summary.settings, "logs": summary.logs }),
+    )
+    .await?;
+
+    Ok(summary)
+}
+
+/// Permanently deletes the user and everything stored about them.
+///
+/// Database rows are removed in one transaction together with the audit
+/// entry, which names `actor_id` as the one who asked for it; cache keys and
+/// files are cleaned up once it commits.
+pub async fn erase_user_data(actor_id: Option<Uuid>, user_id: Uuid) -> AppResult<ErasureSummary> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    if !exists {
+        return Err(AppError::not_found("User"));
+    }
+
+    let mut summary = ErasureSummary {
+        user_id,
+        ..Default::default()
+    };
+    summary.settings = delete_where_user(&mut tx, "user_settings", user_id).await?;
+    summary.logs = delete_where_user(&mut tx, "app_logs", user_id).await?;
+    summary.notifications = delete_where_user(&mut tx, "notifications", user_id).await?;
+    summary.memberships = delete_where_user(&mut tx, "workspace_members", user_id).await?;
+    summary.memberships += delete_where_user(&mut tx, "group_memberships", user_id).await?;
+
+    // Unsynced changes carry row snapshots with personal data.
+    sqlx::query("DELETE FROM sync_changes WHERE record_id = $1 AND synced_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    audit::record_by(
+        &mut *tx,
+        actor_id,
+        audit::USER_DATA_ERASED,
+        Some(user_id),
+        json!({
+            "settings": summary.settings,
+            "logs": summary.logs,
+            "notifications": summary.notifications,
+            "memberships": summary.memberships,
+        }),
+    )
+    .await?;
+
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+
+    if let Err(e) = sync::track_change("users", user_id, SyncOperation::Delete).await {
+        tracing::warn!(
+            "Failed to record sync change for erased user {}: {}",
+            user_id,
+            e
+        );
+    }
+
+    summary.cache_keys = cache::delete_by_prefix(&cache::user_key_prefix(user_id))
+        .map_err(|e| AppError::new(ErrorCode::CacheOperation, e.to_string()))?;
+    summary.files = remove_exports(user_id)?;
+
+    tracing::info!(
+        "Erased data for user {}: {} logs, {} cache keys, {} files",
+        user_id,
+        summary.logs,
+        summary.cache_keys,
+        summary.files
+    );
+
+    Ok(summary)
+}
+
+/// Deactivates `user_id`, revokes its sessions, and schedules its erasure
+/// after the grace period. Returns when it will be erased; asking again
+/// keeps the first date.
+pub async fn request_deletion(user_id: Uuid) -> AppResult<DateTime<Utc>> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let grace_days = config::current().account_deletion_grace_days;
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+
+    let scheduled: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "UPDATE users
+         SET is_active = FALSE, deletion_scheduled_at = COALESCE(deletion_scheduled_at, $2)
+         WHERE id = $1
+         RETURNING deletion_scheduled_at",
+    )
+    .bind(user_id)
+    .bind(Utc::now() + ChronoDuration::days(grace_days as i64))
+    .fetch_optional(&mut *tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+    let scheduled_at = scheduled.ok_or_else(|| AppError::not_found("User"))?;
+
+    audit::record(
+        &mut *tx,
+        audit::ACCOUNT_DELETION_REQUESTED,
+        Some(user_id),
+        json!({ "scheduledAt": scheduled_at }),
+    )
+    .await?;
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+
+    session::revoke_user(pool.as_ref(), user_id).await?;
+    tracing::info!(
+        "Account {} scheduled for deletion at {}",
+        user_id,
+        scheduled_at
+    );
+    Ok(scheduled_at)
+}
+
+/// Reactivates the account awaiting deletion that `email` and `password`
+/// sign in to, returning its id. `None` means the credentials match no such
+/// account.
+pub async fn cancel_deletion(email: &str, password: &str) -> AppResult<Option<Uuid>> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let pending: Option<(Uuid, String)> = sqlx::query_as(
+        "SELECT id, password_hash FROM users
+         WHERE email = $1 AND deletion_scheduled_at > $2 AND NOT is_guest",
+    )
+    .bind(email)
+    .bind(Utc::now())
+    .fetch_optional(pool.as_ref())
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let Some((user_id, password_hash)) = pending else {
+        return Ok(None);
+    };
+    if !password::verify(password, &password_hash)? {
+        return Ok(None);
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+    let restored = sqlx::query(
+        "UPDATE users SET is_active = TRUE, deletion_scheduled_at = NULL
+         WHERE id = $1 AND deletion_scheduled_at IS NOT NULL",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+    // The account may have been erased in the meantime.
+    if restored.rows_affected() == 0 {
+        return Ok(None);
+    }
+    audit::record(
+        &mut *tx,
+        audit::ACCOUNT_DELETION_CANCELLED,
+        Some(user_id),
+        json!({}),
+    )
+    .await?;
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+
+    tracing::info!("Deletion of account {} cancelled", user_id);
+    Ok(Some(user_id))
+}
+
+/// Erases every account whose grace period ended by `now`, returning their
+/// ids.
+pub async fn purge_due_deletions(now: DateTime<Utc>) -> AppResult<Vec<Uuid>> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let due: Vec<Uuid> =
+        sqlx::query_scalar("SELECT id FROM users WHERE deletion_scheduled_at <= $1")
+            .bind(now)
+            .fetch_all(pool.as_ref())
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let mut erased = Vec::with_capacity(due.len());
+    for user_id in due {
+        match erase_user_data(None, user_id).await {
+            Ok(_) => erased.push(user_id),
+            Err(e) => tracing::warn!("Failed to erase account {}: {}", user_id, e),
+        }
+    }
+    Ok(erased)
+}
+
+/// Starts the periodic task that erases accounts past their grace period.
+pub fn start_deletion_scheduler() {
+    let task = tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(DELETION_INTERVAL);
+        loop {
+            interval.tick().await;
+            // The database may not be configured yet.
+            if get_pool_ref().is_err() {
+                continue;
+            }
+            match purge_due_deletions(Utc::now()).await {
+                Ok(erased) if !erased.is_empty() => {
+                    tracing::info!("Erased {} accounts after their grace period", erased.len())
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Account deletion run failed: {}", e),
+            }
+        }
+    });
+    shutdown::track("account-deletion", task);
+}
+
+/// Deletes rows of `table` owned by `user_id`, returning how many were removed.
+async fn delete_where_user(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: &str,
+    user_id: Uuid,
+) -> AppResult<u64> {
+    let result = sqlx::query(&format!("DELETE FROM {} WHERE user_id = $1", table))
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    Ok(result.rows_affected())
+}
+
+/// Removes export archives written for `user_id` under [`EXPORT_DIR`].
+fn remove_exports(user_id: Uuid) -> AppResult<usize> {
+    let context = resolve_relative_path(EXPORT_DIR)
+        .map_err(|e| AppError::file_error("delete", EXPORT_DIR, e))?;
+    if !context.path.is_dir() {
+        return Ok(0);
+    }
+
+    let prefix = export_file_prefix(user_id);
+    let entries = fs::read_dir(&context.path)
+        .map_err(|e| AppError::file_error("read", EXPORT_DIR, e.to_string()))?;
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        if name.to_string_lossy().starts_with(&prefix) {
+            fs::remove_file(entry.path())
+                .map_err(|e| AppError::file_error("delete", EXPORT_DIR, e.to_string()))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// File name prefix of default export archives for `user_id`.
+fn export_file_prefix(user_id: Uuid) -> String {
+    format!("user-{}-", user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::test_support::{LogFactory, UserFactory, DEFAULT_PASSWORD};
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    #[tokio::test]
+    #[serial]
+    async fn erasure_removes_rows_and_is_audited() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let log = LogFactory::new().with_new_user().insert().await?;
+        let user_id = log.user_id.expect("factory log should have a user");
+
+        let summary = erase_user_data(Some(user_id), user_id).await?;
+        assert_eq!(summary.logs, 1);
+
+        let remaining: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM app_logs WHERE id = $1 OR user_id = $2")
+                .bind(log.id)
+                .bind(user_id)
+                .fetch_one(pool.as_ref())
+                .await?;
+        assert_eq!(remaining, 0);
+
+        let audited: Vec<(String, Option<Uuid>)> =
+            sqlx::query_as("SELECT action, actor_id FROM audit_log WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_all(pool.as_ref())
+                .await?;
+        assert_eq!(
+            audited,
+            vec![(audit::USER_DATA_ERASED.to_string(), Some(user_id))]
+        );
+
+        assert!(erase_user_data(None, user_id).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn deletion_can_be_cancelled_until_the_grace_period_ends() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+        let log = LogFactory::new().user_id(user.id).insert().await?;
+
+        let scheduled_at = request_deletion(user.id).await?;
+        assert!(scheduled_at > Utc::now());
+        assert_eq!(request_deletion(user.id).await?, scheduled_at);
+        let active: bool = sqlx::query_scalar("SELECT is_active FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(pool.as_ref())
+            .await?;
+        assert!(!active);
+
+        assert_eq!(cancel_deletion(&user.email, "wrong").await?, None);
+        assert_eq!(
+            cancel_deletion(&user.email, DEFAULT_PASSWORD).await?,
+            Some(user.id)
+        );
+        assert!(purge_due_deletions(Utc::now() + ChronoDuration::days(3650))
+            .await?
+            .is_empty());
+
+        request_deletion(user.id).await?;
+        assert!(purge_due_deletions(Utc::now()).await?.is_empty());
+        let erased = purge_due_deletions(Utc::now() + ChronoDuration::days(3650)).await?;
+        assert_eq!(erased, vec![user.id]);
+        let remaining: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM app_logs WHERE id = $1 OR user_id = $2")
+                .bind(log.id)
+                .bind(user.id)
+                .fetch_one(pool.as_ref())
+                .await?;
+        assert_eq!(remaining, 0);
+        assert_eq!(cancel_deletion(&user.email, DEFAULT_PASSWORD).await?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn export_prefix_matches_default_export_names() {
+        let user_id = Uuid::new_v4();
+        let default_name = format!("user-{}-20240101120000.zip", user_id);
+        assert!(default_name.starts_with(&export_file_prefix(user_id)));
+        assert!(!default_name.starts_with(&export_file_prefix(Uuid::new_v4())));
+    }
+}
@@ -0,0 +1,448 @@
+//! System proxy detection.
+//!
+//! Settings come from the standard `*_PROXY` environment variables first,
+//! then from the operating system: the Internet Settings registry key on
+//! Windows, `scutil --proxy` on macOS, and GNOME's `org.gnome.system.proxy`
+//! schema on Linux. PAC scripts are not evaluated; a configured PAC URL is
+//! reported so the frontend can explain why no proxy is applied. Detection
+//! runs once and is cached until refreshed. The HTTP client and updater use
+//! the detected proxy unless a request names its own.
+
+use once_cell::sync::Lazy;
+use reqwest::Url;
+use serde::Serialize;
+use std::sync::RwLock;
+
+static DETECTED: Lazy<RwLock<Option<ProxySettings>>> = Lazy::new(|| RwLock::new(None));
+
+/// Where the active proxy settings came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxySource {
+    /// No proxy is configured.
+    #[default]
+    None,
+    /// `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`.
+    Environment,
+    /// Operating system network settings.
+    System,
+}
+
+/// Detected proxy configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySettings {
+    pub source: ProxySource,
+    /// Proxy URL for plain HTTP requests.
+    pub http: Option<String>,
+    /// Proxy URL for HTTPS requests.
+    pub https: Option<String>,
+    /// Hosts and domain suffixes that bypass the proxy.
+    pub bypass: Vec<String>,
+    /// Proxy auto-config script, when the system uses one.
+    pub pac_url: Option<String>,
+}
+
+impl ProxySettings {
+    /// Returns the proxy to use for `url`, or `None` to connect directly.
+    pub fn proxy_for(&self, url: &Url) -> Option<String> {
+        let host = url.host_str()?;
+        if self.bypasses(host) {
+            return None;
+        }
+        match url.scheme() {
+            "https" => self.https.clone(),
+            _ => self.http.clone(),
+        }
+    }
+
+    fn bypasses(&self, host: &str) -> bool {
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_lowercase();
+        if matches!(host.as_str(), "localhost" | "127.0.0.1" | "::1") {
+            return true;
+        }
+
+        self.bypass.iter().any(|rule| {
+            let rule = rule.trim().to_lowercase();
+            match rule.as_str() {
+                "*" => true,
+                "<local>" => !host.contains('.'),
+                _ => {
+                    let suffix = rule.trim_start_matches('*').trim_start_matches('.');
+                    !suffix.is_empty()
+                        && (host == suffix || host.ends_with(&format!(".{}", suffix)))
+                }
+            }
+        })
+    }
+}
+
+/// Returns the cached settings, detecting them on first use.
+pub fn current() -> ProxySettings {
+    if let Some(settings) = DETECTED.read().ok().and_then(|detected| detected.clone()) {
+        return settings;
+    }
+    refresh()
+}
+
+/// Detects the settings again and replaces the cached copy.
+pub fn refresh() -> ProxySettings {
+    let settings = detect();
+    match (&settings.http, &settings.https, &settings.pac_url) {
+        (None, None, Some(pac_url)) => tracing::info!(
+            "System uses proxy auto-config at {}; connecting directly",
+            pac_url
+        ),
+        (None, None, None) => tracing::debug!("No proxy configured"),
+        _ => tracing::info!("Using {:?} proxy settings", settings.source),
+    }
+    if let Ok(mut detected) = DETECTED.write() {
+        *detected = Some(settings.clone());
+    }
+    settings
+}
+
+/// Returns the detected proxy for `url`, if any.
+pub fn proxy_for(url: &Url) -> Option<String> {
+    current().proxy_for(url)
+}
+
+fn detect() -> ProxySettings {
+    let env = from_env(|name| std::env::var(name).ok());
+    if env.source == ProxySource::Environment {
+        return env;
+    }
+    detect_system()
+}
+
+fn from_env(var: impl Fn(&str) -> Option<String>) -> ProxySettings {
+    let get = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| var(name))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let all = get(&["ALL_PROXY", "all_proxy"]);
+    let http = get(&["HTTP_PROXY", "http_proxy"]).or_else(|| all.clone());
+    let https = get(&["HTTPS_PROXY", "https_proxy"]).or(all);
+    if http.is_none() && https.is_none() {
+        return ProxySettings::default();
+    }
+
+    ProxySettings {
+        source: ProxySource::Environment,
+        http: http.map(|proxy| normalize(&proxy)),
+        https: https.map(|proxy| normalize(&proxy)),
+        bypass: get(&["NO_PROXY", "no_proxy"])
+            .map(|list| split_list(&list, ','))
+            .unwrap_or_default(),
+        pac_url: None,
+    }
+}
+
+#[cfg(windows)]
+fn detect_system() -> ProxySettings {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    let Ok(key) = RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(r"Software\Microsoft\Windows\CurrentVersion\Internet Settings")
+    else {
+        return ProxySettings::default();
+    };
+    from_windows(
+        key.get_value::<u32, _>("ProxyEnable").unwrap_or(0) == 1,
+        &key.get_value::<String, _>("ProxyServer")
+            .unwrap_or_default(),
+        &key.get_value::<String, _>("ProxyOverride")
+            .unwrap_or_default(),
+        key.get_value::<String, _>("AutoConfigURL").ok(),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn detect_system() -> ProxySettings {
+    std::process::Command::new("scutil")
+        .arg("--proxy")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| from_scutil(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn detect_system() -> ProxySettings {
+    let gsettings = |schema: &str, key: &str| {
+        std::process::Command::new("gsettings")
+            .args(["get", schema, key])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| unquote(String::from_utf8_lossy(&output.stdout).trim()))
+    };
+
+    let mode = gsettings("org.gnome.system.proxy", "mode").unwrap_or_default();
+    let server = |scheme: &str| {
+        let schema = format!("org.gnome.system.proxy.{}", scheme);
+        let host = gsettings(&schema, "host").filter(|host| !host.is_empty())?;
+        let port = gsettings(&schema, "port").filter(|port| port != "0");
+        Some(normalize(&match port {
+            Some(port) => format!("{}:{}", host, port),
+            None => host,
+        }))
+    };
+
+    let settings = match mode.as_str() {
+        "manual" => ProxySettings {
+            http: server("http"),
+            https: server("https"),
+            bypass: gsettings("org.gnome.system.proxy", "ignore-hosts")
+                .map(|list| parse_gsettings_list(&list))
+                .unwrap_or_default(),
+            ..Default::default()
+        },
+        "auto" => ProxySettings {
+            pac_url: gsettings("org.gnome.system.proxy", "autoconfig-url")
+                .filter(|url| !url.is_empty()),
+            ..Default::default()
+        },
+        _ => return ProxySettings::default(),
+    };
+    ProxySettings {
+        source: ProxySource::System,
+        ..settings
+    }
+}
+
+/// Builds settings from the Windows Internet Settings values. `ProxyServer`
+/// is either `host:port` for every scheme or `http=host:port;https=...`.
+#[cfg(any(windows, test))]
+fn from_windows(
+    enabled: bool,
+    server: &str,
+    overrides: &str,
+    pac_url: Option<String>,
+) -> ProxySettings {
+    let pac_url = pac_url.filter(|url| !url.trim().is_empty());
+    let (mut http, mut https) = (None, None);
+    if enabled {
+        for entry in split_list(server, ';') {
+            match entry.split_once('=') {
+                Some((scheme, proxy)) if scheme.eq_ignore_ascii_case("http") => {
+                    http = Some(normalize(proxy))
+                }
+                Some((scheme, proxy)) if scheme.eq_ignore_ascii_case("https") => {
+                    https = Some(normalize(proxy))
+                }
+                Some(_) => {}
+                None => {
+                    http = Some(normalize(&entry));
+                    https = http.clone();
+                }
+            }
+        }
+    }
+    if http.is_none() && https.is_none() && pac_url.is_none() {
+        return ProxySettings::default();
+    }
+
+    ProxySettings {
+        source: ProxySource::System,
+        http,
+        https,
+        bypass: split_list(overrides, ';'),
+        pac_url,
+    }
+}
+
+/// Builds settings from `scutil --proxy` output.
+#[cfg(any(target_os = "macos", test))]
+fn from_scutil(output: &str) -> ProxySettings {
+    let mut values = std::collections::HashMap::new();
+    let mut bypass = Vec::new();
+    let mut in_exceptions = false;
+    for line in output.lines().map(str::trim) {
+        if line.starts_with("ExceptionsList") {
+            in_exceptions = true;
+        } else if in_exceptions && line == "}" {
+            in_exceptions = false;
+        } else if let Some((key, value)) = line.split_once(" : ") {
+            if in_exceptions {
+                bypass.push(value.trim().to_string());
+            } else {
+                values.insert(key.trim(), value.trim());
+            }
+        }
+    }
+
+    let enabled = |key: &str| values.get(key) == Some(&"1");
+    let server = |prefix: &str| {
+        if !enabled(&format!("{}Enable", prefix)) {
+            return None;
+        }
+        let host = values.get(format!("{}Proxy", prefix).as_str())?;
+        Some(normalize(
+            &match values.get(format!("{}Port", prefix).as_str()) {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            },
+        ))
+    };
+    let http = server("HTTP");
+    let https = server("HTTPS");
+    let pac_url = enabled("ProxyAutoConfigEnable")
+        .then(|| {
+            values
+                .get("ProxyAutoConfigURLString")
+                .map(|url| url.to_string())
+        })
+        .flatten();
+    if http.is_none() && https.is_none() && pac_url.is_none() {
+        return ProxySettings::default();
+    }
+
+    ProxySettings {
+        source: ProxySource::System,
+        http,
+        https,
+        bypass,
+        pac_url,
+    }
+}
+
+/// Parses a GVariant string array such as `['localhost', '*.local']`.
+#[cfg(any(all(unix, not(target_os = "macos")), test))]
+fn parse_gsettings_list(value: &str) -> Vec<String> {
+    value
+        .trim()
+        .trim_start_matches("@as")
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|item| unquote(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(any(all(unix, not(target_os = "macos")), test))]
+fn unquote(value: &str) -> String {
+    value.trim_matches('\'').to_string()
+}
+
+/// Adds `http://` to proxies given as bare `host:port`.
+fn normalize(proxy: &str) -> String {
+    let proxy = proxy.trim();
+    if proxy.contains("://") {
+        proxy.to_string()
+    } else {
+        format!("http://{}", proxy)
+    }
+}
+
+fn split_list(value: &str, separator: char) -> Vec<String> {
+    value
+        .split(separator)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn url(value: &str) -> Url {
+        Url::parse(value).unwrap()
+    }
+
+    #[test]
+    fn reads_environment_variables() {
+        let vars = HashMap::from([
+            ("https_proxy", "proxy.corp:3128"),
+            ("ALL_PROXY", "socks5://fallback:1080"),
+            ("NO_PROXY", "internal.corp, .example.com"),
+        ]);
+        let settings = from_env(|name| vars.get(name).map(|value| value.to_string()));
+
+        assert_eq!(settings.source, ProxySource::Environment);
+        assert_eq!(settings.https.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(settings.http.as_deref(), Some("socks5://fallback:1080"));
+        assert_eq!(settings.bypass, ["internal.corp", ".example.com"]);
+        assert_eq!(from_env(|_| None), ProxySettings::default());
+    }
+
+    #[test]
+    fn bypass_rules_match_hosts_and_suffixes() {
+        let settings = ProxySettings {
+            https: Some("http://proxy:8080".to_string()),
+            bypass: vec!["*.example.com".to_string(), "<local>".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            settings
+                .proxy_for(&url("https://api.github.com"))
+                .as_deref(),
+            Some("http://proxy:8080")
+        );
+        assert!(settings.proxy_for(&url("https://example.com")).is_none());
+        assert!(settings
+            .proxy_for(&url("https://api.example.com"))
+            .is_none());
+        assert!(settings.proxy_for(&url("https://intranet/")).is_none());
+        assert!(settings.proxy_for(&url("https://localhost:1420")).is_none());
+        assert!(settings.proxy_for(&url("http://api.github.com")).is_none());
+    }
+
+    #[test]
+    fn parses_windows_internet_settings() {
+        let single = from_windows(true, "proxy:8080", "<local>;*.corp", None);
+        assert_eq!(single.http.as_deref(), Some("http://proxy:8080"));
+        assert_eq!(single.https, single.http);
+        assert_eq!(single.bypass, ["<local>", "*.corp"]);
+
+        let split = from_windows(true, "http=a:80;https=b:443;ftp=c:21", "", None);
+        assert_eq!(split.http.as_deref(), Some("http://a:80"));
+        assert_eq!(split.https.as_deref(), Some("http://b:443"));
+
+        let pac = from_windows(
+            false,
+            "proxy:8080",
+            "",
+            Some("http://wpad/proxy.pac".into()),
+        );
+        assert!(pac.http.is_none());
+        assert_eq!(pac.pac_url.as_deref(), Some("http://wpad/proxy.pac"));
+        assert_eq!(
+            from_windows(false, "proxy:8080", "", None),
+            ProxySettings::default()
+        );
+    }
+
+    #[test]
+    fn parses_scutil_output() {
+        let output = "<dictionary> {\n  ExceptionsList : <array> {\n    0 : *.local\n    1 : 169.254/16\n  }\n  HTTPEnable : 0\n  HTTPSEnable : 1\n  HTTPSPort : 3128\n  HTTPSProxy : proxy.corp\n  ProxyAutoConfigEnable : 0\n}\n";
+        let settings = from_scutil(output);
+        assert_eq!(settings.source, ProxySource::System);
+        assert!(settings.http.is_none());
+        assert_eq!(settings.https.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(settings.bypass, ["*.local", "169.254/16"]);
+    }
+
+    #[test]
+    fn parses_gsettings_lists() {
+        assert_eq!(
+            parse_gsettings_list("['localhost', '127.0.0.0/8', '*.corp']"),
+            ["localhost", "127.0.0.0/8", "*.corp"]
+        );
+        assert!(parse_gsettings_list("@as []").is_empty());
+    }
+}
@@ -2,17 +2,28 @@
 //!
 //! This module provides both global and per-user rate limiting functionality
 //! to protect the application from abuse and ensure fair resource usage.
+//!
+//! Governor is compiled only with the `rate-limiter` cargo feature. Without
+//! it [`RateLimiterConfig`] keeps the same API but allows every request, so
+//! the rate-limited command wrappers work unchanged.
 
+#[cfg(feature = "rate-limiter")]
 use governor::{Quota, RateLimiter, Jitter};
+#[cfg(feature = "rate-limiter")]
 use governor::state::{InMemoryState, NotKeyed, keyed::DashMapStateStore};
+#[cfg(feature = "rate-limiter")]
 use governor::clock::QuantaClock;
+#[cfg(feature = "rate-limiter")]
 use nonzero_ext::*;
+#[cfg(feature = "rate-limiter")]
 use std::time::Duration;
 
 /// Rate limiter for global application-wide limits.
+#[cfg(feature = "rate-limiter")]
 pub type GlobalRateLimiter = RateLimiter<NotKeyed, InMemoryState, QuantaClock>;
 
 /// Rate limiter for per-user limits, keyed by user ID.
+#[cfg(feature = "rate-limiter")]
 pub type UserRateLimiter = RateLimiter<String, DashMapStateStore<String>, QuantaClock>;
 
 /// Configuration for both global and per-user rate limiting.
@@ -20,12 +31,54 @@ pub type UserRateLimiter = RateLimiter<String, DashMapStateStore<String>, Quanta
 /// Manages two types of rate limits:
 /// - Global: Applies to all requests regardless of user
 /// - Per-user: Applies per individual user to prevent single-user abuse
+#[cfg(feature = "rate-limiter")]
 pub struct RateLimiterConfig {
     global_limiter: GlobalRateLimiter,
     user_limiter: UserRateLimiter,
     jitter: Jitter,
 }
 
+/// Pass-through limiter used when the `rate-limiter` feature is disabled.
+#[cfg(not(feature = "rate-limiter"))]
+pub struct RateLimiterConfig;
+
+#[cfg(not(feature = "rate-limiter"))]
+impl RateLimiterConfig {
+    /// Creates a limiter that allows every request.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Creates a limiter that allows every request; limits are ignored.
+    pub fn new_with_limits(_global_per_minute: u32, _user_per_minute: u32) -> Self {
+        Self
+    }
+
+    /// Always succeeds.
+    pub async fn check_rate_limit(&self, _user_id: Option<&str>) -> Result<(), RateLimitError> {
+        Ok(())
+    }
+
+    /// Always succeeds.
+    pub fn check_rate_limit_now(&self, _user_id: Option<&str>) -> Result<(), RateLimitError> {
+        Ok(())
+    }
+
+    /// Returns immediately.
+    pub async fn wait_for_rate_limit(&self, _user_id: Option<&str>) -> Result<(), RateLimitError> {
+        Ok(())
+    }
+
+    /// Returns zero; no per-user state is kept.
+    pub fn tracked_users(&self) -> usize {
+        0
+    }
+
+    /// Does nothing.
+    pub fn cleanup_old_limiters(&self) {}
+}
+
+#[cfg(feature = "rate-limiter")]
 impl RateLimiterConfig {
     /// Creates a new rate limiter configuration with default limits.
     ///
@@ -78,6 +131,12 @@ impl RateLimiterConfig {
     /// * `Ok(())` if within limits
     /// * `Err(RateLimitError)` if limits exceeded
     pub async fn check_rate_limit(&self, user_id: Option<&str>) -> Result<(), RateLimitError> {
+        self.check_rate_limit_now(user_id)
+    }
+
+    /// Synchronous variant of [`check_rate_limit`](Self::check_rate_limit) for
+    /// callers outside an async context, such as invoke handler dispatch.
+    pub fn check_rate_limit_now(&self, user_id: Option<&str>) -> Result<(), RateLimitError> {
         match self.global_limiter.check() {
             Ok(_) => {},
             Err(_) => {
@@ -116,6 +175,11 @@ impl RateLimiterConfig {
         Ok(())
     }
 
+    /// Returns the number of users with per-user rate limit state.
+    pub fn tracked_users(&self) -> usize {
+        self.user_limiter.len()
+    }
+
     /// Cleanup method for old rate limiter entries.
     ///
     /// Note: DashMapStateStore handles cleanup automatically,
@@ -133,6 +197,7 @@ impl Default for RateLimiterConfig {
 
 /// Errors that can occur during rate limiting operations.
 #[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "rate-limiter"), allow(dead_code))]
 pub enum RateLimitError {
     GlobalLimitExceeded,
     UserLimitExceeded(String),
@@ -180,7 +245,7 @@ macro_rules! rate_limited_command_wait {
     }};
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "rate-limiter"))]
 mod tests {
     use super::*;
     use tokio::time::{sleep, Duration};
@@ -0,0 +1,444 @@
+//! Command module registry for composing handlers from downstream crates.
+//!
+//! Tauri accepts a single invoke handler, so additional command modules are
+//! dispatched by command name in front of the built-in handler. Each module
+//! declares metadata (rate-limit class, required permission, arguments) for
+//! its commands; the built-in commands record theirs through
+//! `builtin_handler!`, and [`registered_commands`] lists both.
+
+use crate::command_trace;
+use crate::inspector;
+use crate::rate_limiter::RateLimiterConfig;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::{Manager, Wry};
+
+/// Boxed invoke handler as produced by `tauri::generate_handler!`.
+pub type InvokeHandler = Box<dyn Fn(Invoke<Wry>) -> bool + Send + Sync>;
+
+/// Metadata of every module registered when the application started.
+static REGISTERED_MODULES: OnceCell<Vec<CommandModuleInfo>> = OnceCell::new();
+
+/// Metadata of the built-in commands.
+static BUILTIN_COMMANDS: OnceCell<Vec<CommandSpec>> = OnceCell::new();
+
+/// Module name reported for the built-in commands.
+pub const BUILTIN_MODULE: &str = "core";
+
+/// How a command is rate limited when invoked through the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RateLimitClass {
+    /// Subject to the global rate limiter.
+    Standard,
+    /// Never rate limited (health checks, monitoring).
+    Exempt,
+}
+
+/// An argument accepted by a command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArgSpec {
+    /// Key of the argument in the invoke payload.
+    pub name: String,
+    /// Rust type the argument is deserialized into.
+    pub rust_type: String,
+    /// Whether the argument may be omitted or null.
+    pub optional: bool,
+}
+
+impl ArgSpec {
+    /// Describes the Rust parameter `param` of type `rust_type`.
+    ///
+    /// Tauri expects arguments under their camelCase names, so `param` is
+    /// converted accordingly.
+    pub fn new(param: &str, rust_type: &str) -> Self {
+        let rust_type: String = rust_type.split_whitespace().collect();
+        Self {
+            name: to_camel_case(param),
+            optional: rust_type.starts_with("Option<"),
+            rust_type,
+        }
+    }
+}
+
+fn to_camel_case(name: &str) -> String {
+    let mut camel = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = !camel.is_empty();
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// Metadata describing a single command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSpec {
+    pub name: String,
+    pub rate_limit: RateLimitClass,
+    pub permission: Option<String>,
+    #[serde(default)]
+    pub args: Vec<ArgSpec>,
+}
+
+impl CommandSpec {
+    /// Describes a built-in command. The `rl_` wrappers go through the rate
+    /// limiter; other built-ins are exempt.
+    pub fn builtin(name: &str, args: Vec<ArgSpec>) -> Self {
+        Self {
+            name: name.to_string(),
+            rate_limit: if name.starts_with("rl_") {
+                RateLimitClass::Standard
+            } else {
+                RateLimitClass::Exempt
+            },
+            permission: None,
+            args,
+        }
+    }
+}
+
+/// A command as listed by [`registered_commands`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandInfo {
+    /// Owning module, or [`BUILTIN_MODULE`] for built-in commands.
+    pub module: String,
+    #[serde(flatten)]
+    pub spec: CommandSpec,
+}
+
+/// Serializable summary of a registered module.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandModuleInfo {
+    pub name: String,
+    pub commands: Vec<CommandSpec>,
+}
+
+/// A named group of commands with its own invoke handler.
+pub struct CommandModule {
+    name: String,
+    commands: Vec<CommandSpec>,
+    handler: InvokeHandler,
+}
+
+impl CommandModule {
+    /// Creates a module from command names and the handler generated for them.
+    ///
+    /// Prefer the [`command_module!`](crate::command_module) macro, which keeps
+    /// the name list and the generated handler in sync.
+    pub fn new<F>(name: impl Into<String>, command_names: Vec<&str>, handler: F) -> Self
+    where
+        F: Fn(Invoke<Wry>) -> bool + Send + Sync + 'static,
+    {
+        let commands = command_names
+            .into_iter()
+            .map(|command| CommandSpec {
+                name: command.to_string(),
+                rate_limit: RateLimitClass::Standard,
+                permission: None,
+                args: Vec::new(),
+            })
+            .collect();
+
+        Self {
+            name: name.into(),
+            commands,
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Sets the rate-limit class for every command in the module.
+    pub fn with_rate_limit(mut self, class: RateLimitClass) -> Self {
+        for command in &mut self.commands {
+            command.rate_limit = class;
+        }
+        self
+    }
+
+    /// Sets the rate-limit class for a single command.
+    pub fn with_command_rate_limit(mut self, command: &str, class: RateLimitClass) -> Self {
+        if let Some(spec) = self.commands.iter_mut().find(|spec| spec.name == command) {
+            spec.rate_limit = class;
+        }
+        self
+    }
+
+    /// Declares the permission required to invoke every command in the module.
+    pub fn with_permission(mut self, permission: impl Into<String>) -> Self {
+        let permission = permission.into();
+        for command in &mut self.commands {
+            command.permission = Some(permission.clone());
+        }
+        self
+    }
+
+    /// Declares the arguments of a single command.
+    pub fn with_args(mut self, command: &str, args: Vec<ArgSpec>) -> Self {
+        if let Some(spec) = self.commands.iter_mut().find(|spec| spec.name == command) {
+            spec.args = args;
+        }
+        self
+    }
+
+    /// Returns the module name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn info(&self) -> CommandModuleInfo {
+        CommandModuleInfo {
+            name: self.name.clone(),
+            commands: self.commands.clone(),
+        }
+    }
+}
+
+/// Collection of command modules dispatched ahead of the built-in handler.
+#[derive(Default)]
+pub struct CommandRegistry {
+    modules: Vec<CommandModule>,
+}
+
+impl CommandRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a module, warning when one of its commands shadows an existing one.
+    pub fn register(&mut self, module: CommandModule) {
+        for spec in &module.commands {
+            if let Some(existing) = self
+                .modules
+                .iter()
+                .find(|m| m.commands.iter().any(|c| c.name == spec.name))
+            {
+                tracing::warn!(
+                    "Command '{}' from module '{}' is already registered by '{}'",
+                    spec.name,
+                    module.name,
+                    existing.name
+                );
+            }
+        }
+
+        self.modules.push(module);
+    }
+
+    /// Returns metadata for every registered module.
+    pub fn modules(&self) -> Vec<CommandModuleInfo> {
+        self.modules.iter().map(CommandModule::info).collect()
+    }
+
+    /// Combines registered modules with the built-in `fallback` handler.
+    ///
+    /// Commands owned by a module are rate limited according to their class
+    /// before being dispatched; everything else goes to `fallback`.
+    pub fn into_handler<F>(self, fallback: F) -> impl Fn(Invoke<Wry>) -> bool + Send + Sync + 'static
+    where
+        F: Fn(Invoke<Wry>) -> bool + Send + Sync + 'static,
+    {
+        let _ = REGISTERED_MODULES.set(self.modules());
+
+        let mut routes: HashMap<String, (usize, RateLimitClass)> = HashMap::new();
+        for (index, module) in self.modules.iter().enumerate() {
+            for spec in &module.commands {
+                routes.insert(spec.name.clone(), (index, spec.rate_limit));
+            }
+        }
+        let handlers: Vec<InvokeHandler> = self.modules.into_iter().map(|m| m.handler).collect();
+
+        move |invoke: Invoke<Wry>| {
+            let route = routes.get(invoke.message.command()).copied();
+
+            if route.is_none() && invoke.message.command().starts_with("rl_") {
+                command_trace::record_args(invoke.message.command(), invoke.message.payload());
+            }
+
+            if inspector::enabled() {
+                let command = invoke.message.command();
+                let args = match invoke.message.payload() {
+                    InvokeBody::Json(value) => Some(value),
+                    _ => None,
+                };
+                // Built-in rl_ wrappers report completion themselves.
+                inspector::begin(command, args, route.is_none() && command.starts_with("rl_"));
+            }
+
+            match route {
+                Some((index, class)) => {
+                    if class == RateLimitClass::Standard {
+                        let limiter = invoke
+                            .message
+                            .webview()
+                            .try_state::<Arc<RateLimiterConfig>>()
+                            .map(|state| state.inner().clone());
+
+                        if let Some(limiter) = limiter {
+                            if let Err(e) = limiter.check_rate_limit_now(None) {
+                                tracing::warn!("Rate limit exceeded: {}", e);
+                                invoke.resolver.reject(format!("Rate limit exceeded: {}", e));
+                                return true;
+                            }
+                        }
+                    }
+
+                    (handlers[index])(invoke)
+                }
+                None => fallback(invoke),
+            }
+        }
+    }
+}
+
+/// Returns metadata of modules registered at startup.
+pub fn registered_modules() -> Vec<CommandModuleInfo> {
+    REGISTERED_MODULES.get().cloned().unwrap_or_default()
+}
+
+/// Records the built-in command metadata; later calls are ignored.
+pub fn set_builtin_commands(commands: Vec<CommandSpec>) {
+    let _ = BUILTIN_COMMANDS.set(commands);
+}
+
+/// Returns every command available to the frontend, built-ins first.
+///
+/// Module commands take precedence at dispatch, so a built-in shadowed by a
+/// module is listed only under that module.
+pub fn registered_commands() -> Vec<CommandInfo> {
+    let modules = registered_modules();
+    let shadowed = |name: &str| {
+        modules
+            .iter()
+            .any(|module| module.commands.iter().any(|spec| spec.name == name))
+    };
+
+    let builtins = BUILTIN_COMMANDS
+        .get()
+        .into_iter()
+        .flatten()
+        .filter(|spec| !shadowed(&spec.name))
+        .map(|spec| CommandInfo {
+            module: BUILTIN_MODULE.to_string(),
+            spec: spec.clone(),
+        });
+    let module_commands = modules.iter().flat_map(|module| {
+        module.commands.iter().map(|spec| CommandInfo {
+            module: module.name.clone(),
+            spec: spec.clone(),
+        })
+    });
+    builtins.chain(module_commands).collect()
+}
+
+/// Builds the invoke handler for the built-in commands and records their
+/// metadata for [`registered_commands`].
+///
+/// Each command needs a schema module of the same name exposing `args()`, as
+/// generated next to the rate-limited wrappers. Commands may carry `#[cfg]`
+/// attributes so feature-gated commands are only listed when compiled in.
+macro_rules! builtin_handler {
+    ($($(#[$meta:meta])* $command:ident),* $(,)?) => {{
+        let mut specs = Vec::new();
+        $(
+            $(#[$meta])*
+            specs.push($crate::registry::CommandSpec::builtin(
+                stringify!($command),
+                $command::args(),
+            ));
+        )*
+        $crate::registry::set_builtin_commands(specs);
+        tauri::generate_handler![$($(#[$meta])* $command),*]
+    }};
+}
+pub(crate) use builtin_handler;
+
+/// Builds a [`CommandModule`] from a list of `#[tauri::command]` functions.
+///
+/// ```ignore
+/// let billing = command_module!("billing", [create_invoice, list_invoices])
+///     .with_permission("billing:write");
+/// ```
+#[macro_export]
+macro_rules! command_module {
+    ($name:expr, [$($command:ident),* $(,)?]) => {
+        $crate::registry::CommandModule::new(
+            $name,
+            vec![$(stringify!($command)),*],
+            tauri::generate_handler![$($command),*],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop_module(name: &str, commands: Vec<&str>) -> CommandModule {
+        CommandModule::new(name, commands, |_invoke| false)
+    }
+
+    #[test]
+    fn module_metadata_can_be_customized() {
+        let module = noop_module("billing", vec!["create_invoice", "health"])
+            .with_permission("billing:write")
+            .with_command_rate_limit("health", RateLimitClass::Exempt);
+
+        let info = module.info();
+        assert_eq!(info.name, "billing");
+        assert!(info
+            .commands
+            .iter()
+            .all(|c| c.permission.as_deref() == Some("billing:write")));
+        assert_eq!(info.commands[0].rate_limit, RateLimitClass::Standard);
+        assert_eq!(info.commands[1].rate_limit, RateLimitClass::Exempt);
+    }
+
+    #[test]
+    fn arguments_use_their_ipc_names() {
+        let arg = ArgSpec::new("days_to_keep", "u32");
+        assert_eq!(arg.name, "daysToKeep");
+        assert!(!arg.optional);
+
+        let arg = ArgSpec::new("include_metadata", "Option < bool >");
+        assert_eq!(arg.name, "includeMetadata");
+        assert_eq!(arg.rust_type, "Option<bool>");
+        assert!(arg.optional);
+    }
+
+    #[test]
+    fn builtin_rate_limit_class_follows_the_wrapper_prefix() {
+        assert_eq!(
+            CommandSpec::builtin("rl_get_logs", Vec::new()).rate_limit,
+            RateLimitClass::Standard
+        );
+        assert_eq!(
+            CommandSpec::builtin("get_rate_limiter_status", Vec::new()).rate_limit,
+            RateLimitClass::Exempt
+        );
+    }
+
+    #[test]
+    fn registry_lists_all_modules() {
+        let mut registry = CommandRegistry::new();
+        registry.register(noop_module("a", vec!["one"]));
+        registry.register(noop_module("b", vec!["two", "three"]));
+
+        let modules = registry.modules();
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[1].commands.len(), 2);
+    }
+}
@@ -0,0 +1,373 @@
+//! In-memory repository implementations for unit tests.
+//!
+//! These mirror the constraints the schema enforces (unique email and
+//! username, defaults for new rows) so handler logic can be tested without a
+//! database container.
+
+use super::{NewUser, UserChanges, UserRepository, UserSearch};
+use crate::models::{SortDirection, User, UserSortField};
+use chrono::Utc;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// User storage backed by a vector.
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    users: Mutex<Vec<User>>,
+    /// Workspace memberships as `(workspace_id, user_id)` pairs.
+    memberships: Mutex<Vec<(Uuid, Uuid)>>,
+    /// Group memberships as `(group_id, user_id)` pairs.
+    group_memberships: Mutex<Vec<(Uuid, Uuid)>>,
+    /// Previous password hashes as `(user_id, hash)` pairs, oldest first.
+    password_history: Mutex<Vec<(Uuid, String)>>,
+    /// Granted roles as `(user_id, role)` pairs.
+    roles: Mutex<Vec<(Uuid, String)>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `user_id` as a member of `workspace_id` for `list` filtering.
+    pub fn add_member(&self, workspace_id: Uuid, user_id: Uuid) {
+        self.memberships
+            .lock()
+            .unwrap()
+            .push((workspace_id, user_id));
+    }
+
+    /// Records `user_id` as a member of `group_id` for search filtering.
+    pub fn add_group_member(&self, group_id: Uuid, user_id: Uuid) {
+        self.group_memberships
+            .lock()
+            .unwrap()
+            .push((group_id, user_id));
+    }
+
+    /// Roles granted to `user_id`.
+    pub fn roles(&self, user_id: Uuid) -> Vec<String> {
+        self.roles
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| *id == user_id)
+            .map(|(_, role)| role.clone())
+            .collect()
+    }
+
+    fn unique_violation(constraint: &str) -> sqlx::Error {
+        sqlx::Error::Protocol(format!(
+            "duplicate key value violates unique constraint \"{}\"",
+            constraint
+        ))
+    }
+
+    fn check_unique(
+        users: &[User],
+        id: Option<Uuid>,
+        email: Option<&str>,
+        username: Option<&str>,
+    ) -> sqlx::Result<()> {
+        let others = users.iter().filter(|user| Some(user.id) != id);
+        for user in others {
+            if email.is_some_and(|email| user.email == email) {
+                return Err(Self::unique_violation("users_email_key"));
+            }
+            if username.is_some_and(|username| user.username == username) {
+                return Err(Self::unique_violation("users_username_key"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl UserRepository for InMemoryUserRepository {
+    async fn list(&self, workspace_id: Option<Uuid>) -> sqlx::Result<Vec<User>> {
+        let memberships = self.memberships.lock().unwrap();
+        let mut users: Vec<User> = self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|user| {
+                workspace_id.map_or(true, |workspace_id| {
+                    memberships.contains(&(workspace_id, user.id))
+                })
+            })
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(users)
+    }
+
+    async fn find(&self, id: Uuid) -> sqlx::Result<Option<User>> {
+        Ok(self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|user| user.id == id)
+            .cloned())
+    }
+
+    async fn search(&self, search: &UserSearch) -> sqlx::Result<(Vec<User>, i64)> {
+        let memberships = self.memberships.lock().unwrap();
+        let group_memberships = self.group_memberships.lock().unwrap();
+        let text = search.text.as_deref().map(str::to_lowercase);
+        let filters = &search.filters;
+
+        let mut matches: Vec<User> = self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|user| {
+                let full_name = format!(
+                    "{} {}",
+                    user.first_name.as_deref().unwrap_or_default(),
+                    user.last_name.as_deref().unwrap_or_default()
+                );
+                text.as_deref().map_or(true, |text| {
+                    [
+                        user.email.as_str(),
+                        user.username.as_str(),
+                        full_name.as_str(),
+                    ]
+                    .iter()
+                    .any(|value| value.to_lowercase().contains(text))
+                })
+            })
+            .filter(|user| {
+                filters
+                    .is_active
+                    .map_or(true, |active| user.is_active == active)
+            })
+            .filter(|user| {
+                filters
+                    .created_after
+                    .map_or(true, |after| user.created_at >= after)
+            })
+            .filter(|user| {
+                filters
+                    .created_before
+                    .map_or(true, |before| user.created_at < before)
+            })
+            .filter(|user| {
+                filters.group_id.map_or(true, |group_id| {
+                    group_memberships.contains(&(group_id, user.id))
+                })
+            })
+            .filter(|user| {
+                search.workspace_id.map_or(true, |workspace_id| {
+                    memberships.contains(&(workspace_id, user.id))
+                })
+            })
+            .cloned()
+            .collect();
+
+        matches.sort_by(|a, b| {
+            let ordering = match search.sort.field {
+                UserSortField::CreatedAt => a.created_at.cmp(&b.created_at),
+                UserSortField::Email => a.email.cmp(&b.email),
+                UserSortField::Username => a.username.cmp(&b.username),
+                UserSortField::LastName => a.last_name.cmp(&b.last_name),
+            };
+            match search.sort.direction {
+                SortDirection::Asc => ordering,
+                SortDirection::Desc => ordering.reverse(),
+            }
+        });
+
+        let total = matches.len() as i64;
+        let page = matches
+            .into_iter()
+            .skip(search.offset as usize)
+            .take(search.limit as usize)
+            .collect();
+        Ok((page, total))
+    }
+
+    async fn find_active_by_email(&self, email: &str) -> sqlx::Result<Option<User>> {
+        Ok(self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|user| user.email == email && user.is_active)
+            .cloned())
+    }
+
+    async fn insert(&self, user: NewUser) -> sqlx::Result<User> {
+        let mut users = self.users.lock().unwrap();
+        Self::check_unique(&users, None, Some(&user.email), Some(&user.username))?;
+
+        let now = Utc::now();
+        let user = User {
+            id: Uuid::new_v4(),
+            email: user.email,
+            username: user.username,
+            password_hash: user.password_hash,
+            first_name: user.first_name,
+            last_name: user.last_name,
+            is_active: user.is_active,
+            email_verified: false,
+            is_guest: user.is_guest,
+            created_at: now,
+            updated_at: now,
+        };
+        users.push(user.clone());
+        Ok(user)
+    }
+
+    async fn update(&self, id: Uuid, changes: UserChanges) -> sqlx::Result<User> {
+        let mut users = self.users.lock().unwrap();
+        Self::check_unique(
+            &users,
+            Some(id),
+            changes.email.as_deref(),
+            changes.username.as_deref(),
+        )?;
+
+        let user = users
+            .iter_mut()
+            .find(|user| user.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if let Some(email) = changes.email {
+            user.email_verified &= user.email == email;
+            user.email = email;
+        }
+        if let Some(username) = changes.username {
+            user.username = username;
+        }
+        if changes.first_name.is_some() {
+            user.first_name = changes.first_name;
+        }
+        if changes.last_name.is_some() {
+            user.last_name = changes.last_name;
+        }
+        if let Some(is_active) = changes.is_active {
+            user.is_active = is_active;
+        }
+        user.updated_at = Utc::now();
+        Ok(user.clone())
+    }
+
+    async fn delete(&self, id: Uuid) -> sqlx::Result<bool> {
+        let mut users = self.users.lock().unwrap();
+        let before = users.len();
+        users.retain(|user| user.id != id);
+        Ok(users.len() != before)
+    }
+
+    async fn password_history(&self, id: Uuid, limit: usize) -> sqlx::Result<Vec<String>> {
+        Ok(self
+            .password_history
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|(user_id, _)| *user_id == id)
+            .take(limit)
+            .map(|(_, hash)| hash.clone())
+            .collect())
+    }
+
+    async fn replace_password(
+        &self,
+        id: Uuid,
+        password_hash: String,
+        history_depth: usize,
+    ) -> sqlx::Result<()> {
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .iter_mut()
+            .find(|user| user.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        let previous = std::mem::replace(&mut user.password_hash, password_hash);
+        user.updated_at = Utc::now();
+
+        let mut history = self.password_history.lock().unwrap();
+        history.push((id, previous));
+        let kept = history.iter().filter(|(user_id, _)| *user_id == id).count();
+        let mut excess = kept.saturating_sub(history_depth);
+        history.retain(|(user_id, _)| {
+            if *user_id == id && excess > 0 {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+        Ok(())
+    }
+
+    async fn rehash_password(&self, id: Uuid, password_hash: String) -> sqlx::Result<()> {
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .iter_mut()
+            .find(|user| user.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        user.password_hash = password_hash;
+        Ok(())
+    }
+
+    async fn upgrade_guest(&self, id: Uuid, upgrade: NewUser) -> sqlx::Result<User> {
+        let mut users = self.users.lock().unwrap();
+        Self::check_unique(
+            &users,
+            Some(id),
+            Some(&upgrade.email),
+            Some(&upgrade.username),
+        )?;
+
+        let user = users
+            .iter_mut()
+            .find(|user| user.id == id && user.is_guest)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        user.email = upgrade.email;
+        user.username = upgrade.username;
+        user.password_hash = upgrade.password_hash;
+        if upgrade.first_name.is_some() {
+            user.first_name = upgrade.first_name;
+        }
+        if upgrade.last_name.is_some() {
+            user.last_name = upgrade.last_name;
+        }
+        user.is_active = upgrade.is_active;
+        user.email_verified = false;
+        user.is_guest = false;
+        user.updated_at = Utc::now();
+        Ok(user.clone())
+    }
+
+    /// Only removes the guest; settings and logs are not stored here.
+    async fn merge_guest(&self, id: Uuid, _into: Uuid) -> sqlx::Result<()> {
+        let mut users = self.users.lock().unwrap();
+        let before = users.len();
+        users.retain(|user| !(user.id == id && user.is_guest));
+        if users.len() == before {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn grant_role(&self, email: &str, role: &str) -> sqlx::Result<bool> {
+        let users = self.users.lock().unwrap();
+        let Some(user) = users
+            .iter()
+            .find(|user| user.email == email && !user.is_guest)
+        else {
+            return Ok(false);
+        };
+        let mut roles = self.roles.lock().unwrap();
+        if !roles
+            .iter()
+            .any(|(id, granted)| *id == user.id && granted == role)
+        {
+            roles.push((user.id, role.to_string()));
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,117 @@
+//! Repository traits separating handler logic from storage.
+//!
+//! Handlers are written against these traits so their validation and
+//! control flow can be unit tested with the in-memory implementations in
+//! [`memory`], while production commands use the PostgreSQL implementations
+//! in `postgres`, or those in `sqlite` for the embedded database.
+
+use crate::models::{User, UserSearchFilters, UserSort};
+use uuid::Uuid;
+
+#[cfg(test)]
+pub mod memory;
+#[cfg(feature = "database")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+#[cfg(feature = "database")]
+pub use postgres::PgUserRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteUserRepository;
+
+/// Validated fields for a new user row.
+#[derive(Debug, Clone)]
+pub struct NewUser {
+    pub email: String,
+    pub username: String,
+    pub password_hash: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    /// False for accounts that must verify their email before signing in.
+    pub is_active: bool,
+    /// Local account with a placeholder email and no usable password.
+    pub is_guest: bool,
+}
+
+/// Validated changes for an existing user; `None` leaves a field unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct UserChanges {
+    pub email: Option<String>,
+    pub username: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// A validated user search.
+#[derive(Debug, Clone, Default)]
+pub struct UserSearch {
+    /// Case-insensitive text matched against email, username, and full name.
+    pub text: Option<String>,
+    pub filters: UserSearchFilters,
+    pub sort: UserSort,
+    pub limit: i64,
+    pub offset: i64,
+    /// Restricts results to members of this workspace.
+    pub workspace_id: Option<Uuid>,
+}
+
+/// Storage operations used by the user handlers.
+#[allow(async_fn_in_trait)]
+pub trait UserRepository {
+    /// Lists users newest first, limited to `workspace_id` members when given.
+    async fn list(&self, workspace_id: Option<Uuid>) -> sqlx::Result<Vec<User>>;
+
+    async fn find(&self, id: Uuid) -> sqlx::Result<Option<User>>;
+
+    /// Returns one page of matching users and the total number of matches.
+    async fn search(&self, search: &UserSearch) -> sqlx::Result<(Vec<User>, i64)>;
+
+    /// Returns the active user with `email`, if any.
+    async fn find_active_by_email(&self, email: &str) -> sqlx::Result<Option<User>>;
+
+    async fn insert(&self, user: NewUser) -> sqlx::Result<User>;
+
+    /// Applies `changes`, failing with `RowNotFound` when the user is missing.
+    async fn update(&self, id: Uuid, changes: UserChanges) -> sqlx::Result<User>;
+
+    /// Deletes a user, returning whether a row was removed.
+    async fn delete(&self, id: Uuid) -> sqlx::Result<bool>;
+
+    /// Returns up to `limit` previous password hashes, newest first.
+    async fn password_history(&self, id: Uuid, limit: usize) -> sqlx::Result<Vec<String>>;
+
+    /// Sets a new password hash, moving the current one into the history and
+    /// keeping at most `history_depth` previous hashes.
+    ///
+    /// Fails with `RowNotFound` when the user is missing.
+    async fn replace_password(
+        &self,
+        id: Uuid,
+        password_hash: String,
+        history_depth: usize,
+    ) -> sqlx::Result<()>;
+
+    /// Replaces the hash of an unchanged password, e.g. to upgrade its
+    /// algorithm, without recording it in the history.
+    ///
+    /// Fails with `RowNotFound` when the user is missing.
+    async fn rehash_password(&self, id: Uuid, password_hash: String) -> sqlx::Result<()>;
+
+    /// Gives a guest the credentials and names of `user`, making it a
+    /// regular account. Its settings and logs stay attached to the same id.
+    ///
+    /// Fails with `RowNotFound` when no guest with `id` exists.
+    async fn upgrade_guest(&self, id: Uuid, user: NewUser) -> sqlx::Result<User>;
+
+    /// Moves the logs of guest `id` to `into`, adds the guest's settings the
+    /// account does not have, and deletes the guest.
+    ///
+    /// Fails with `RowNotFound` when no guest with `id` exists.
+    async fn merge_guest(&self, id: Uuid, into: Uuid) -> sqlx::Result<()>;
+
+    /// Adds `role` to the regular account with `email` unless it has it,
+    /// returning whether such an account exists.
+    async fn grant_role(&self, email: &str, role: &str) -> sqlx::Result<bool>;
+}
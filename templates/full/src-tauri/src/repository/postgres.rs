@@ -0,0 +1,419 @@
+//! PostgreSQL-backed repository implementations.
+
+use super::{NewUser, UserChanges, UserRepository, UserSearch};
+use crate::database::filter::{FilterBuilder, SortColumn};
+use crate::database::query_stats;
+use crate::models::{User, UserSortField};
+use sqlx::postgres::PgRow;
+use sqlx::{Execute, FromRow, PgPool, Row};
+use uuid::Uuid;
+
+/// User storage in the `users` table.
+pub struct PgUserRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> PgUserRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl UserRepository for PgUserRepository<'_> {
+    async fn list(&self, workspace_id: Option<Uuid>) -> sqlx::Result<Vec<User>> {
+        let query = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id,
+                   email,
+                   username,
+                   password_hash,
+                   first_name,
+                   last_name,
+                   is_active AS "is_active!",
+                   email_verified,
+                   is_guest,
+                   created_at AS "created_at!",
+                   updated_at AS "updated_at!"
+            FROM users
+            WHERE $1::UUID IS NULL
+               OR id IN (SELECT user_id FROM workspace_members WHERE workspace_id = $1)
+            ORDER BY created_at DESC
+            "#,
+            workspace_id,
+        );
+        query_stats::timed(query.sql(), query.fetch_all(self.pool)).await
+    }
+
+    async fn find(&self, id: Uuid) -> sqlx::Result<Option<User>> {
+        let query = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id,
+                   email,
+                   username,
+                   password_hash,
+                   first_name,
+                   last_name,
+                   is_active AS "is_active!",
+                   email_verified,
+                   is_guest,
+                   created_at AS "created_at!",
+                   updated_at AS "updated_at!"
+            FROM users
+            WHERE id = $1
+            "#,
+            id,
+        );
+        query_stats::timed(query.sql(), query.fetch_optional(self.pool)).await
+    }
+
+    async fn search(&self, search: &UserSearch) -> sqlx::Result<(Vec<User>, i64)> {
+        let mut filter = FilterBuilder::new(
+            "SELECT id,
+                    email,
+                    username,
+                    password_hash,
+                    first_name,
+                    last_name,
+                    is_active,
+                    email_verified,
+                    is_guest,
+                    created_at,
+                    updated_at,
+                    COUNT(*) OVER () AS total_count
+             FROM users",
+        );
+
+        if let Some(text) = &search.text {
+            // Matches the trigram indexes created by the migrations.
+            filter.search(
+                &[
+                    "email",
+                    "username",
+                    "(COALESCE(first_name, '') || ' ' || COALESCE(last_name, ''))",
+                ],
+                text,
+            );
+        }
+        filter
+            .eq_opt("is_active", search.filters.is_active)
+            .range(
+                "created_at",
+                search.filters.created_after,
+                search.filters.created_before,
+            );
+        if let Some(group_id) = search.filters.group_id {
+            filter.wrap(
+                "id IN (SELECT user_id FROM group_memberships WHERE group_id = ",
+                group_id,
+                ")",
+            );
+        }
+        if let Some(workspace_id) = search.workspace_id {
+            filter.wrap(
+                "id IN (SELECT user_id FROM workspace_members WHERE workspace_id = ",
+                workspace_id,
+                ")",
+            );
+        }
+        filter
+            .order_by(&search.sort.field, search.sort.direction, Some("id"))
+            .paginate(search.limit, search.offset);
+
+        let mut builder = filter.into_builder();
+        let query = builder.build();
+        let rows: Vec<PgRow> = query_stats::timed(query.sql(), query.fetch_all(self.pool)).await?;
+
+        let total = match rows.first() {
+            Some(row) => row.try_get("total_count")?,
+            None => 0,
+        };
+        let users = rows
+            .iter()
+            .map(User::from_row)
+            .collect::<sqlx::Result<Vec<_>>>()?;
+        Ok((users, total))
+    }
+
+    async fn find_active_by_email(&self, email: &str) -> sqlx::Result<Option<User>> {
+        let query = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id,
+                   email,
+                   username,
+                   password_hash,
+                   first_name,
+                   last_name,
+                   is_active AS "is_active!",
+                   email_verified,
+                   is_guest,
+                   created_at AS "created_at!",
+                   updated_at AS "updated_at!"
+            FROM users
+            WHERE email = $1
+              AND is_active = TRUE
+            LIMIT 1
+            "#,
+            email,
+        );
+        query_stats::timed(query.sql(), query.fetch_optional(self.pool)).await
+    }
+
+    async fn insert(&self, user: NewUser) -> sqlx::Result<User> {
+        let query = sqlx::query_as!(
+            User,
+            r#"
+            INSERT INTO users (email, username, password_hash, first_name, last_name, is_active, is_guest)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id,
+                      email,
+                      username,
+                      password_hash,
+                      first_name,
+                      last_name,
+                      is_active AS "is_active!",
+                      email_verified,
+                      is_guest,
+                      created_at AS "created_at!",
+                      updated_at AS "updated_at!"
+            "#,
+            user.email,
+            user.username,
+            user.password_hash,
+            user.first_name,
+            user.last_name,
+            user.is_active,
+            user.is_guest,
+        );
+        query_stats::timed(query.sql(), query.fetch_one(self.pool)).await
+    }
+
+    async fn update(&self, id: Uuid, changes: UserChanges) -> sqlx::Result<User> {
+        let query = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET email = COALESCE($2, email),
+                username = COALESCE($3, username),
+                first_name = COALESCE($4, first_name),
+                last_name = COALESCE($5, last_name),
+                is_active = COALESCE($6, is_active),
+                -- A new address has to be verified again.
+                email_verified = email_verified AND email = COALESCE($2, email),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING id,
+                      email,
+                      username,
+                      password_hash,
+                      first_name,
+                      last_name,
+                      is_active AS "is_active!",
+                      email_verified,
+                      is_guest,
+                      created_at AS "created_at!",
+                      updated_at AS "updated_at!"
+            "#,
+            id,
+            changes.email,
+            changes.username,
+            changes.first_name,
+            changes.last_name,
+            changes.is_active,
+        );
+        query_stats::timed(query.sql(), query.fetch_one(self.pool)).await
+    }
+
+    async fn delete(&self, id: Uuid) -> sqlx::Result<bool> {
+        let query = sqlx::query!("DELETE FROM users WHERE id = $1", id);
+        let result = query_stats::timed(query.sql(), query.execute(self.pool)).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn password_history(&self, id: Uuid, limit: usize) -> sqlx::Result<Vec<String>> {
+        let query = sqlx::query_scalar!(
+            r#"
+            SELECT password_hash
+            FROM password_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            id,
+            limit as i64,
+        );
+        query_stats::timed(query.sql(), query.fetch_all(self.pool)).await
+    }
+
+    async fn replace_password(
+        &self,
+        id: Uuid,
+        password_hash: String,
+        history_depth: usize,
+    ) -> sqlx::Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO password_history (user_id, password_hash)
+            SELECT id, password_hash FROM users WHERE id = $1
+            "#,
+            id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE users
+            SET password_hash = $2,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            id,
+            password_hash,
+        )
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        sqlx::query!(
+            r#"
+            DELETE FROM password_history
+            WHERE user_id = $1
+              AND id NOT IN (
+                  SELECT id FROM password_history
+                  WHERE user_id = $1
+                  ORDER BY created_at DESC
+                  LIMIT $2
+              )
+            "#,
+            id,
+            history_depth as i64,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    async fn rehash_password(&self, id: Uuid, password_hash: String) -> sqlx::Result<()> {
+        let query = sqlx::query!(
+            "UPDATE users SET password_hash = $2 WHERE id = $1",
+            id,
+            password_hash,
+        );
+        let result = query_stats::timed(query.sql(), query.execute(self.pool)).await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn upgrade_guest(&self, id: Uuid, user: NewUser) -> sqlx::Result<User> {
+        let query = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET email = $2,
+                username = $3,
+                password_hash = $4,
+                first_name = COALESCE($5, first_name),
+                last_name = COALESCE($6, last_name),
+                is_active = $7,
+                email_verified = false,
+                is_guest = false,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+              AND is_guest
+            RETURNING id,
+                      email,
+                      username,
+                      password_hash,
+                      first_name,
+                      last_name,
+                      is_active AS "is_active!",
+                      email_verified,
+                      is_guest,
+                      created_at AS "created_at!",
+                      updated_at AS "updated_at!"
+            "#,
+            id,
+            user.email,
+            user.username,
+            user.password_hash,
+            user.first_name,
+            user.last_name,
+            user.is_active,
+        );
+        query_stats::timed(query.sql(), query.fetch_one(self.pool)).await
+    }
+
+    async fn merge_guest(&self, id: Uuid, into: Uuid) -> sqlx::Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // Keys the account already has keep their values.
+        sqlx::query!(
+            r#"
+            INSERT INTO user_settings (user_id, theme, language, notifications_enabled, settings_data)
+            SELECT $2, theme, language, notifications_enabled, settings_data
+            FROM user_settings
+            WHERE user_id = $1
+            ON CONFLICT (user_id) DO UPDATE
+            SET settings_data = EXCLUDED.settings_data || user_settings.settings_data,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+            id,
+            into,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE app_logs SET user_id = $2 WHERE user_id = $1",
+            id,
+            into
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let deleted = sqlx::query!("DELETE FROM users WHERE id = $1 AND is_guest", id)
+            .execute(&mut *tx)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        tx.commit().await
+    }
+
+    async fn grant_role(&self, email: &str, role: &str) -> sqlx::Result<bool> {
+        let query = sqlx::query!(
+            r#"
+            UPDATE users
+            SET roles = CASE WHEN $2 = ANY(roles) THEN roles ELSE array_append(roles, $2) END
+            WHERE email = $1
+              AND NOT is_guest
+            "#,
+            email,
+            role,
+        );
+        let result = query_stats::timed(query.sql(), query.execute(self.pool)).await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl SortColumn for UserSortField {
+    fn column(&self) -> &'static str {
+        match self {
+            UserSortField::CreatedAt => "created_at",
+            UserSortField::Email => "email",
+            UserSortField::Username => "username",
+            UserSortField::LastName => "last_name",
+        }
+    }
+}
@@ -0,0 +1,571 @@
+//! SQLite-backed repository implementations for the embedded database.
+//!
+//! Ids are stored as UUID strings. Timestamps are written from Rust as
+//! fixed-width RFC 3339 text, so comparing and sorting them as text follows
+//! time. Text search uses `LIKE`, which ignores case for ASCII letters only.
+//! The embedded database has no workspaces or groups, so filtering by one
+//! matches no users. Roles are a JSON array of names.
+
+use super::{NewUser, UserChanges, UserRepository, UserSearch};
+use crate::local_db::timestamp;
+use crate::models::{SortDirection, User, UserSortField};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, QueryBuilder, Row, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+/// Columns read into a [`UserRow`].
+const USER_COLUMNS: &str = "id, email, username, password_hash, first_name, last_name, \
+                            is_active, email_verified, is_guest, created_at, updated_at";
+
+/// Expressions matched by the search text, like the PostgreSQL search.
+const SEARCH_COLUMNS: &[&str] = &[
+    "email",
+    "username",
+    "(COALESCE(first_name, '') || ' ' || COALESCE(last_name, ''))",
+];
+
+/// A `users` row as stored, with the id still text.
+#[derive(FromRow)]
+struct UserRow {
+    id: String,
+    email: String,
+    username: String,
+    password_hash: String,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    is_active: bool,
+    email_verified: bool,
+    is_guest: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<UserRow> for User {
+    type Error = sqlx::Error;
+
+    fn try_from(row: UserRow) -> sqlx::Result<Self> {
+        Ok(User {
+            id: Uuid::parse_str(&row.id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            email: row.email,
+            username: row.username,
+            password_hash: row.password_hash,
+            first_name: row.first_name,
+            last_name: row.last_name,
+            is_active: row.is_active,
+            email_verified: row.email_verified,
+            is_guest: row.is_guest,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+/// User storage in the `users` table of the embedded database.
+pub struct SqliteUserRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> SqliteUserRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+impl UserRepository for SqliteUserRepository<'_> {
+    async fn list(&self, workspace_id: Option<Uuid>) -> sqlx::Result<Vec<User>> {
+        if workspace_id.is_some() {
+            return Ok(Vec::new());
+        }
+
+        let sql = format!(
+            "SELECT {} FROM users ORDER BY created_at DESC",
+            USER_COLUMNS
+        );
+        let rows: Vec<UserRow> = sqlx::query_as(&sql).fetch_all(self.pool).await?;
+        rows.into_iter().map(User::try_from).collect()
+    }
+
+    async fn find(&self, id: Uuid) -> sqlx::Result<Option<User>> {
+        let sql = format!("SELECT {} FROM users WHERE id = ?1", USER_COLUMNS);
+        let row: Option<UserRow> = sqlx::query_as(&sql)
+            .bind(id.to_string())
+            .fetch_optional(self.pool)
+            .await?;
+        row.map(User::try_from).transpose()
+    }
+
+    async fn search(&self, search: &UserSearch) -> sqlx::Result<(Vec<User>, i64)> {
+        if search.workspace_id.is_some() || search.filters.group_id.is_some() {
+            return Ok((Vec::new(), 0));
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {}, COUNT(*) OVER () AS total_count FROM users WHERE TRUE",
+            USER_COLUMNS
+        ));
+        if let Some(text) = &search.text {
+            let pattern = format!("%{}%", escape_like(text));
+            for (index, column) in SEARCH_COLUMNS.iter().enumerate() {
+                builder.push(if index == 0 { " AND (" } else { " OR " });
+                builder
+                    .push(column)
+                    .push(" LIKE ")
+                    .push_bind(pattern.clone())
+                    .push(" ESCAPE '\\'");
+            }
+            builder.push(")");
+        }
+        if let Some(is_active) = search.filters.is_active {
+            builder.push(" AND is_active = ").push_bind(is_active);
+        }
+        if let Some(after) = search.filters.created_after {
+            builder
+                .push(" AND created_at >= ")
+                .push_bind(timestamp(after));
+        }
+        if let Some(before) = search.filters.created_before {
+            builder
+                .push(" AND created_at < ")
+                .push_bind(timestamp(before));
+        }
+        let direction = match search.sort.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        builder
+            .push(format!(
+                " ORDER BY {} {}, id {}",
+                sort_column(search.sort.field),
+                direction,
+                direction
+            ))
+            .push(" LIMIT ")
+            .push_bind(search.limit)
+            .push(" OFFSET ")
+            .push_bind(search.offset);
+
+        let rows: Vec<SqliteRow> = builder.build().fetch_all(self.pool).await?;
+        let total = match rows.first() {
+            Some(row) => row.try_get("total_count")?,
+            None => 0,
+        };
+        let users = rows
+            .iter()
+            .map(|row| UserRow::from_row(row).and_then(User::try_from))
+            .collect::<sqlx::Result<Vec<_>>>()?;
+        Ok((users, total))
+    }
+
+    async fn find_active_by_email(&self, email: &str) -> sqlx::Result<Option<User>> {
+        let sql = format!(
+            "SELECT {} FROM users WHERE email = ?1 AND is_active = 1 LIMIT 1",
+            USER_COLUMNS
+        );
+        let row: Option<UserRow> = sqlx::query_as(&sql)
+            .bind(email)
+            .fetch_optional(self.pool)
+            .await?;
+        row.map(User::try_from).transpose()
+    }
+
+    async fn insert(&self, user: NewUser) -> sqlx::Result<User> {
+        let sql = format!(
+            "INSERT INTO users (id, email, username, password_hash, first_name, last_name,
+                                is_active, is_guest, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
+             RETURNING {}",
+            USER_COLUMNS
+        );
+        let row: UserRow = sqlx::query_as(&sql)
+            .bind(Uuid::new_v4().to_string())
+            .bind(user.email)
+            .bind(user.username)
+            .bind(user.password_hash)
+            .bind(user.first_name)
+            .bind(user.last_name)
+            .bind(user.is_active)
+            .bind(user.is_guest)
+            .bind(timestamp(Utc::now()))
+            .fetch_one(self.pool)
+            .await?;
+        User::try_from(row)
+    }
+
+    async fn update(&self, id: Uuid, changes: UserChanges) -> sqlx::Result<User> {
+        let sql = format!(
+            "UPDATE users
+             SET email = COALESCE(?2, email),
+                 username = COALESCE(?3, username),
+                 first_name = COALESCE(?4, first_name),
+                 last_name = COALESCE(?5, last_name),
+                 is_active = COALESCE(?6, is_active),
+                 -- A new address has to be verified again.
+                 email_verified = email_verified AND email = COALESCE(?2, email),
+                 updated_at = ?7
+             WHERE id = ?1
+             RETURNING {}",
+            USER_COLUMNS
+        );
+        let row: UserRow = sqlx::query_as(&sql)
+            .bind(id.to_string())
+            .bind(changes.email)
+            .bind(changes.username)
+            .bind(changes.first_name)
+            .bind(changes.last_name)
+            .bind(changes.is_active)
+            .bind(timestamp(Utc::now()))
+            .fetch_one(self.pool)
+            .await?;
+        User::try_from(row)
+    }
+
+    async fn delete(&self, id: Uuid) -> sqlx::Result<bool> {
+        let result = sqlx::query("DELETE FROM users WHERE id = ?1")
+            .bind(id.to_string())
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn password_history(&self, id: Uuid, limit: usize) -> sqlx::Result<Vec<String>> {
+        sqlx::query_scalar(
+            "SELECT password_hash
+             FROM password_history
+             WHERE user_id = ?1
+             ORDER BY created_at DESC, rowid DESC
+             LIMIT ?2",
+        )
+        .bind(id.to_string())
+        .bind(limit as i64)
+        .fetch_all(self.pool)
+        .await
+    }
+
+    async fn replace_password(
+        &self,
+        id: Uuid,
+        password_hash: String,
+        history_depth: usize,
+    ) -> sqlx::Result<()> {
+        let now = timestamp(Utc::now());
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO password_history (id, user_id, password_hash, created_at)
+             SELECT ?2, id, password_hash, ?3 FROM users WHERE id = ?1",
+        )
+        .bind(id.to_string())
+        .bind(Uuid::new_v4().to_string())
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        let updated =
+            sqlx::query("UPDATE users SET password_hash = ?2, updated_at = ?3 WHERE id = ?1")
+                .bind(id.to_string())
+                .bind(password_hash)
+                .bind(&now)
+                .execute(&mut *tx)
+                .await?;
+        if updated.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        sqlx::query(
+            "DELETE FROM password_history
+             WHERE user_id = ?1
+               AND id NOT IN (
+                   SELECT id FROM password_history
+                   WHERE user_id = ?1
+                   ORDER BY created_at DESC, rowid DESC
+                   LIMIT ?2
+               )",
+        )
+        .bind(id.to_string())
+        .bind(history_depth as i64)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    async fn rehash_password(&self, id: Uuid, password_hash: String) -> sqlx::Result<()> {
+        let result = sqlx::query("UPDATE users SET password_hash = ?2 WHERE id = ?1")
+            .bind(id.to_string())
+            .bind(password_hash)
+            .execute(self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    async fn upgrade_guest(&self, id: Uuid, user: NewUser) -> sqlx::Result<User> {
+        let sql = format!(
+            "UPDATE users
+             SET email = ?2,
+                 username = ?3,
+                 password_hash = ?4,
+                 first_name = COALESCE(?5, first_name),
+                 last_name = COALESCE(?6, last_name),
+                 is_active = ?7,
+                 email_verified = 0,
+                 is_guest = 0,
+                 updated_at = ?8
+             WHERE id = ?1
+               AND is_guest
+             RETURNING {}",
+            USER_COLUMNS
+        );
+        let row: UserRow = sqlx::query_as(&sql)
+            .bind(id.to_string())
+            .bind(user.email)
+            .bind(user.username)
+            .bind(user.password_hash)
+            .bind(user.first_name)
+            .bind(user.last_name)
+            .bind(user.is_active)
+            .bind(timestamp(Utc::now()))
+            .fetch_one(self.pool)
+            .await?;
+        User::try_from(row)
+    }
+
+    async fn merge_guest(&self, id: Uuid, into: Uuid) -> sqlx::Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // Keys the account already has keep their values.
+        sqlx::query(
+            "INSERT INTO user_settings (id, user_id, theme, language, notifications_enabled,
+                                        settings_data, created_at, updated_at)
+             SELECT ?3, ?2, theme, language, notifications_enabled, settings_data, ?4, ?4
+             FROM user_settings
+             WHERE user_id = ?1
+             ON CONFLICT (user_id) DO UPDATE
+             SET settings_data = json_patch(excluded.settings_data, user_settings.settings_data),
+                 updated_at = excluded.updated_at",
+        )
+        .bind(id.to_string())
+        .bind(into.to_string())
+        .bind(Uuid::new_v4().to_string())
+        .bind(timestamp(Utc::now()))
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE app_logs SET user_id = ?2 WHERE user_id = ?1")
+            .bind(id.to_string())
+            .bind(into.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        let deleted = sqlx::query("DELETE FROM users WHERE id = ?1 AND is_guest")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        tx.commit().await
+    }
+
+    async fn grant_role(&self, email: &str, role: &str) -> sqlx::Result<bool> {
+        let result = sqlx::query(
+            "UPDATE users
+             SET roles = CASE WHEN EXISTS (SELECT 1 FROM json_each(roles) WHERE value = ?2)
+                              THEN roles ELSE json_insert(roles, '$[#]', ?2) END
+             WHERE email = ?1
+               AND NOT is_guest",
+        )
+        .bind(email)
+        .bind(role)
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl SqliteUserRepository<'_> {
+    /// Roles of the active user `id`, or `None` when there is no such user.
+    pub async fn active_roles(&self, id: Uuid) -> sqlx::Result<Option<Vec<String>>> {
+        let roles: Option<String> =
+            sqlx::query_scalar("SELECT roles FROM users WHERE id = ?1 AND is_active = 1")
+                .bind(id.to_string())
+                .fetch_optional(self.pool)
+                .await?;
+        roles
+            .map(|roles| serde_json::from_str(&roles))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    }
+}
+
+/// Escapes the `LIKE` wildcards in `text` for `ESCAPE '\'`.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn sort_column(field: UserSortField) -> &'static str {
+    match field {
+        UserSortField::CreatedAt => "created_at",
+        UserSortField::Email => "email",
+        UserSortField::Username => "username",
+        UserSortField::LastName => "last_name",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::local_db::{migrations::run_migrations, open};
+
+    fn new_user(email: &str, username: &str) -> NewUser {
+        NewUser {
+            email: email.to_string(),
+            username: username.to_string(),
+            password_hash: "hash".to_string(),
+            first_name: Some("Ada".to_string()),
+            last_name: None,
+            is_active: true,
+            is_guest: false,
+        }
+    }
+
+    async fn migrated_pool(dir: &tempfile::TempDir) -> SqlitePool {
+        let pool = open(&dir.path().join("app.db")).await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn stores_searches_and_deletes_users() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = migrated_pool(&dir).await;
+        let repo = SqliteUserRepository::new(&pool);
+
+        let ada = repo
+            .insert(new_user("ada@example.com", "ada"))
+            .await
+            .unwrap();
+        let grace = repo
+            .insert(new_user("grace@example.com", "grace_100"))
+            .await
+            .unwrap();
+        assert!(repo
+            .insert(new_user("ada@example.com", "other"))
+            .await
+            .is_err());
+
+        let listed = repo.list(None).await.unwrap();
+        assert_eq!(listed[0].id, grace.id);
+        assert_eq!(repo.find(ada.id).await.unwrap().unwrap().email, ada.email);
+        assert!(repo.list(Some(Uuid::new_v4())).await.unwrap().is_empty());
+
+        let search = UserSearch {
+            text: Some("GRACE_1".to_string()),
+            limit: 10,
+            ..Default::default()
+        };
+        let (found, total) = repo.search(&search).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(found[0].id, grace.id);
+
+        let updated = repo
+            .update(
+                ada.id,
+                UserChanges {
+                    is_active: Some(false),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(!updated.is_active);
+        assert_eq!(updated.username, "ada");
+        assert!(repo
+            .find_active_by_email("ada@example.com")
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(repo.delete(ada.id).await.unwrap());
+        assert!(!repo.delete(ada.id).await.unwrap());
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn roles_are_only_granted_to_regular_accounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = migrated_pool(&dir).await;
+        let repo = SqliteUserRepository::new(&pool);
+
+        let guest = repo
+            .insert(NewUser {
+                is_guest: true,
+                ..new_user("guest@guest.invalid", "guest_1")
+            })
+            .await
+            .unwrap();
+        let ada = repo
+            .insert(new_user("ada@example.com", "ada"))
+            .await
+            .unwrap();
+        let grace = repo
+            .insert(new_user("grace@example.com", "grace"))
+            .await
+            .unwrap();
+        assert_eq!(repo.active_roles(ada.id).await.unwrap(), Some(Vec::new()));
+
+        assert!(repo.grant_role("ada@example.com", "admin").await.unwrap());
+        assert!(repo.grant_role("ada@example.com", "admin").await.unwrap());
+        assert!(!repo
+            .grant_role("guest@guest.invalid", "admin")
+            .await
+            .unwrap());
+        assert!(!repo
+            .grant_role("nobody@example.com", "admin")
+            .await
+            .unwrap());
+
+        assert_eq!(repo.active_roles(guest.id).await.unwrap(), Some(Vec::new()));
+        assert_eq!(
+            repo.active_roles(ada.id).await.unwrap(),
+            Some(vec!["admin".to_string()])
+        );
+        assert_eq!(repo.active_roles(grace.id).await.unwrap(), Some(Vec::new()));
+        assert_eq!(repo.active_roles(Uuid::new_v4()).await.unwrap(), None);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn replacing_a_password_keeps_the_history_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = migrated_pool(&dir).await;
+        let repo = SqliteUserRepository::new(&pool);
+        let user = repo
+            .insert(new_user("ada@example.com", "ada"))
+            .await
+            .unwrap();
+
+        for hash in ["second", "third", "fourth"] {
+            repo.replace_password(user.id, hash.to_string(), 2)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            repo.password_history(user.id, 5).await.unwrap(),
+            vec!["third", "second"]
+        );
+        assert!(matches!(
+            repo.replace_password(Uuid::new_v4(), "hash".to_string(), 2)
+                .await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        pool.close().await;
+    }
+}
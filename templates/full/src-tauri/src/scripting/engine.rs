@@ -0,0 +1,312 @@
+//! Rhai engine setup and the host API exposed to scripts.
+//!
+//! Every host function checks the script's declared capabilities before
+//! doing anything. The engine has no module resolver, so `import` cannot
+//! load other files, and runs under operation, depth, size and wall-clock
+//! limits so a runaway script cannot stall the app.
+
+use super::{Capability, Execution};
+use crate::cache;
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::handlers::filesystem::{
+    ensure_write_permitted, resolve_existing_path, resolve_relative_path,
+};
+use crate::http_client::{self, HttpRequestOptions};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest a script may run.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Operations a script may perform.
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// Largest file `fs_read` returns.
+const MAX_READ_BYTES: u64 = 1024 * 1024;
+
+/// Lines of `print` / `debug` output kept per run.
+const MAX_OUTPUT_LINES: usize = 1_000;
+
+/// Timeout applied to script HTTP requests.
+const HTTP_TIMEOUT_MS: u64 = 15_000;
+
+/// Prefix keeping script cache keys apart from application keys.
+const CACHE_PREFIX: &str = "script:";
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Runs `source` with access to the host APIs in `capabilities`.
+///
+/// Must be called from a blocking thread; HTTP calls block on `runtime`.
+pub(super) fn execute(
+    source: &str,
+    capabilities: &[Capability],
+    runtime: tokio::runtime::Handle,
+) -> AppResult<Execution> {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let engine = build_engine(capabilities.to_vec(), runtime, output.clone());
+
+    let value = engine
+        .eval::<Dynamic>(source)
+        .map_err(|e| AppError::new(ErrorCode::InternalError, format!("Script failed: {}", e)))?;
+    let result = if value.is_unit() {
+        None
+    } else {
+        Some(rhai::serde::from_dynamic(&value).map_err(|e| {
+            AppError::new(
+                ErrorCode::InternalError,
+                format!("Script result is not serializable: {}", e),
+            )
+        })?)
+    };
+
+    let output = output.lock().map(|lines| lines.clone()).unwrap_or_default();
+    Ok(Execution { result, output })
+}
+
+fn build_engine(
+    capabilities: Vec<Capability>,
+    runtime: tokio::runtime::Handle,
+    output: Arc<Mutex<Vec<String>>>,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1024 * 1024);
+    engine.set_max_array_size(10_000);
+    engine.set_max_map_size(10_000);
+
+    let started = Instant::now();
+    engine.on_progress(move |_| {
+        (started.elapsed() > TIMEOUT).then(|| Dynamic::from("Script timed out"))
+    });
+
+    let print_output = output.clone();
+    engine.on_print(move |line| push_output(&print_output, line.to_string()));
+    engine.on_debug(move |line, _, position| {
+        push_output(&output, format!("[debug {}] {}", position, line))
+    });
+
+    register_cache(&mut engine, &capabilities);
+    register_fs(&mut engine, &capabilities);
+    register_http(&mut engine, &capabilities, runtime);
+    engine
+}
+
+fn push_output(output: &Mutex<Vec<String>>, line: String) {
+    if let Ok(mut lines) = output.lock() {
+        if lines.len() < MAX_OUTPUT_LINES {
+            lines.push(line);
+        }
+    }
+}
+
+fn require(
+    capabilities: &[Capability],
+    capability: Capability,
+    function: &str,
+) -> ScriptResult<()> {
+    if capabilities.contains(&capability) {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' requires the '{}' capability; add '// capabilities: {}' to the script header",
+            function,
+            capability_name(capability),
+            capability_name(capability)
+        )
+        .into())
+    }
+}
+
+fn capability_name(capability: Capability) -> &'static str {
+    match capability {
+        Capability::Cache => "cache",
+        Capability::Fs => "fs",
+        Capability::Http => "http",
+    }
+}
+
+fn host_error(e: impl std::fmt::Display) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+fn register_cache(engine: &mut Engine, capabilities: &[Capability]) {
+    let caps = capabilities.to_vec();
+    engine.register_fn("cache_get", move |key: &str| -> ScriptResult<Dynamic> {
+        require(&caps, Capability::Cache, "cache_get")?;
+        match cache::get_cache::<serde_json::Value>(&format!("{}{}", CACHE_PREFIX, key))
+            .map_err(host_error)?
+        {
+            Some(value) => rhai::serde::to_dynamic(value),
+            None => Ok(Dynamic::UNIT),
+        }
+    });
+
+    let caps = capabilities.to_vec();
+    engine.register_fn(
+        "cache_set",
+        move |key: &str, value: Dynamic| -> ScriptResult<()> { cache_set(&caps, key, value, None) },
+    );
+
+    let caps = capabilities.to_vec();
+    engine.register_fn(
+        "cache_set",
+        move |key: &str, value: Dynamic, ttl_seconds: i64| -> ScriptResult<()> {
+            cache_set(&caps, key, value, Some(ttl_seconds.max(1) as u64))
+        },
+    );
+}
+
+fn cache_set(
+    capabilities: &[Capability],
+    key: &str,
+    value: Dynamic,
+    ttl_seconds: Option<u64>,
+) -> ScriptResult<()> {
+    require(capabilities, Capability::Cache, "cache_set")?;
+    let value: serde_json::Value = rhai::serde::from_dynamic(&value)?;
+    cache::set_cache(&format!("{}{}", CACHE_PREFIX, key), &value, ttl_seconds).map_err(host_error)
+}
+
+fn register_fs(engine: &mut Engine, capabilities: &[Capability]) {
+    let caps = capabilities.to_vec();
+    engine.register_fn("fs_read", move |path: &str| -> ScriptResult<String> {
+        require(&caps, Capability::Fs, "fs_read")?;
+        let context = resolve_existing_path(path).map_err(host_error)?;
+        let metadata = fs::metadata(&context.path).map_err(host_error)?;
+        if !metadata.is_file() {
+            return Err(format!("'{}' is not a file", context.relative_display()).into());
+        }
+        if metadata.len() > MAX_READ_BYTES {
+            return Err(format!("'{}' is too large to read", context.relative_display()).into());
+        }
+        fs::read_to_string(&context.path).map_err(host_error)
+    });
+
+    let caps = capabilities.to_vec();
+    engine.register_fn(
+        "fs_write",
+        move |path: &str, content: &str| -> ScriptResult<()> {
+            require(&caps, Capability::Fs, "fs_write")?;
+            let context = resolve_relative_path(path).map_err(host_error)?;
+            if context.path == context.root {
+                return Err("Cannot write to the filesystem root".into());
+            }
+            ensure_write_permitted(&context.path).map_err(host_error)?;
+            if let Some(parent) = context.path.parent() {
+                fs::create_dir_all(parent).map_err(host_error)?;
+            }
+            fs::write(&context.path, content).map_err(host_error)
+        },
+    );
+
+    let caps = capabilities.to_vec();
+    engine.register_fn("fs_list", move |path: &str| -> ScriptResult<Array> {
+        require(&caps, Capability::Fs, "fs_list")?;
+        let context = resolve_existing_path(path).map_err(host_error)?;
+        let mut names: Vec<String> = fs::read_dir(&context.path)
+            .map_err(host_error)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        Ok(names.into_iter().map(Dynamic::from).collect())
+    });
+
+    let caps = capabilities.to_vec();
+    engine.register_fn("fs_exists", move |path: &str| -> ScriptResult<bool> {
+        require(&caps, Capability::Fs, "fs_exists")?;
+        let context = resolve_relative_path(path).map_err(host_error)?;
+        Ok(context.path.exists())
+    });
+}
+
+fn register_http(
+    engine: &mut Engine,
+    capabilities: &[Capability],
+    runtime: tokio::runtime::Handle,
+) {
+    let caps = capabilities.to_vec();
+    let handle = runtime.clone();
+    engine.register_fn("http_get", move |url: &str| -> ScriptResult<Dynamic> {
+        require(&caps, Capability::Http, "http_get")?;
+        http_request(&handle, "GET", url, None)
+    });
+
+    let caps = capabilities.to_vec();
+    engine.register_fn(
+        "http_post",
+        move |url: &str, body: Dynamic| -> ScriptResult<Dynamic> {
+            require(&caps, Capability::Http, "http_post")?;
+            let body: serde_json::Value = rhai::serde::from_dynamic(&body)?;
+            http_request(&runtime, "POST", url, Some(body))
+        },
+    );
+}
+
+fn http_request(
+    runtime: &tokio::runtime::Handle,
+    method: &str,
+    url: &str,
+    body: Option<serde_json::Value>,
+) -> ScriptResult<Dynamic> {
+    let options = HttpRequestOptions {
+        timeout_ms: Some(HTTP_TIMEOUT_MS),
+        ..Default::default()
+    };
+    let response = runtime
+        .block_on(http_client::execute(
+            method,
+            url,
+            HashMap::new(),
+            body,
+            options,
+        ))
+        .map_err(host_error)?;
+    rhai::serde::to_dynamic(&response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str, capabilities: &[Capability]) -> AppResult<Execution> {
+        execute(source, capabilities, tokio::runtime::Handle::current())
+    }
+
+    #[tokio::test]
+    async fn returns_the_final_value_and_printed_output() {
+        let execution = run("print(\"hello\"); #{ answer: 40 + 2 }", &[]).unwrap();
+        assert_eq!(execution.output, vec!["hello"]);
+        assert_eq!(execution.result, Some(serde_json::json!({ "answer": 42 })));
+    }
+
+    #[tokio::test]
+    async fn host_functions_require_declared_capabilities() {
+        let error = run("fs_exists(\"notes.txt\")", &[]).unwrap_err();
+        assert!(error.message.contains("'fs' capability"));
+
+        let error = run("http_get(\"https://example.com\")", &[Capability::Fs]).unwrap_err();
+        assert!(error.message.contains("'http' capability"));
+    }
+
+    #[tokio::test]
+    async fn runaway_scripts_are_stopped() {
+        assert!(run("loop {}", &[]).is_err());
+        assert!(run("fn f(x) { f(x + 1) } f(0)", &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn scripts_cannot_import_or_eval() {
+        assert!(run("import \"other\" as other;", &[]).is_err());
+        assert!(run("eval(\"40 + 2\")", &[]).is_err());
+    }
+}
@@ -0,0 +1,385 @@
+//! User automation scripts.
+//!
+//! Scripts are [Rhai](https://rhai.rs) files in a `scripts` directory under
+//! the application data directory. A script declares the capabilities it
+//! needs in a header comment, and the engine refuses API calls outside them:
+//!
+//! ```text
+//! // capabilities: cache, fs, http
+//! let body = http_get("https://example.com/status").body;
+//! fs_write("status.json", body);
+//! ```
+//!
+//! Scripts can be run on demand or on a fixed interval; schedules persist in
+//! `schedules.json` next to the scripts. The engine is compiled only with
+//! the `scripting` cargo feature. Without it scripts can still be listed and
+//! scheduled, but running one reports that scripting is unavailable.
+
+#[cfg(feature = "scripting")]
+mod engine;
+
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::events::{self, AppEvent};
+use crate::logging;
+use crate::shutdown;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Script file extension.
+const SCRIPT_EXTENSION: &str = "rhai";
+
+/// File holding script schedules, inside the scripts directory.
+const SCHEDULES_FILE: &str = "schedules.json";
+
+/// How often the scheduler checks for due scripts.
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// Largest script file accepted.
+const MAX_SCRIPT_BYTES: u64 = 256 * 1024;
+
+/// Header prefix declaring a script's capabilities.
+const CAPABILITIES_HEADER: &str = "// capabilities:";
+
+/// Latest run of each script since startup.
+static LAST_RUNS: Lazy<Mutex<HashMap<String, ScriptRun>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Host APIs a script may be granted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Capability {
+    /// `cache_get` / `cache_set`, limited to a script-only key prefix.
+    Cache,
+    /// `fs_read` / `fs_write` / `fs_list` / `fs_exists` within the filesystem scope.
+    Fs,
+    /// `http_get` / `http_post` through the outbound HTTP client.
+    Http,
+}
+
+impl Capability {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "cache" => Some(Self::Cache),
+            "fs" => Some(Self::Fs),
+            "http" => Some(Self::Http),
+            _ => None,
+        }
+    }
+}
+
+/// A script available to run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptInfo {
+    pub name: String,
+    pub capabilities: Vec<Capability>,
+    /// Minutes between scheduled runs, when scheduled.
+    pub interval_minutes: Option<u64>,
+    pub last_run: Option<ScriptRun>,
+}
+
+/// Outcome of running a script.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptRun {
+    pub name: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub success: bool,
+    /// Value of the script's final expression.
+    pub result: Option<serde_json::Value>,
+    /// Lines written with `print` and `debug`.
+    pub output: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// What a script produced when it ran to completion.
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+pub(crate) struct Execution {
+    pub(crate) result: Option<serde_json::Value>,
+    pub(crate) output: Vec<String>,
+}
+
+/// Directory holding user scripts.
+pub fn scripts_dir() -> PathBuf {
+    logging::app_data_dir("scripts")
+}
+
+/// Lists scripts with their capabilities, schedules and last runs.
+pub fn list() -> AppResult<Vec<ScriptInfo>> {
+    let dir = scripts_dir();
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let schedules = load_schedules()?;
+    let last_runs = LAST_RUNS
+        .lock()
+        .map(|runs| runs.clone())
+        .unwrap_or_default();
+    let mut scripts: Vec<ScriptInfo> = fs::read_dir(&dir)
+        .into_app_error(ErrorCode::FileRead)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == SCRIPT_EXTENSION))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            validate_name(&name).ok()?;
+            let source = read_source(&name).ok()?;
+            Some(ScriptInfo {
+                capabilities: parse_capabilities(&source),
+                interval_minutes: schedules.get(&name).copied(),
+                last_run: last_runs.get(&name).cloned(),
+                name,
+            })
+        })
+        .collect();
+    scripts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(scripts)
+}
+
+/// Runs the named script to completion and records the outcome.
+///
+/// A script that fails still returns `Ok` with `success: false`; errors
+/// are reserved for scripts that cannot be started.
+pub async fn run(name: &str) -> AppResult<ScriptRun> {
+    validate_name(name)?;
+    let source = read_source(name)?;
+    let capabilities = parse_capabilities(&source);
+
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let runtime = tokio::runtime::Handle::current();
+    let outcome = tokio::task::spawn_blocking(move || execute(&source, &capabilities, runtime))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Script task failed: {}", e)))?;
+
+    let run = match outcome {
+        Ok(execution) => ScriptRun {
+            name: name.to_string(),
+            started_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+            success: true,
+            result: execution.result,
+            output: execution.output,
+            error: None,
+        },
+        Err(e) if matches!(e.code, ErrorCode::NotImplemented) => return Err(e),
+        Err(e) => ScriptRun {
+            name: name.to_string(),
+            started_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+            success: false,
+            result: None,
+            output: Vec::new(),
+            error: Some(e.message),
+        },
+    };
+
+    if let Ok(mut runs) = LAST_RUNS.lock() {
+        runs.insert(run.name.clone(), run.clone());
+    }
+    Ok(run)
+}
+
+/// Schedules the named script every `interval_minutes`, or unschedules it
+/// when `None`.
+pub fn schedule(name: &str, interval_minutes: Option<u64>) -> AppResult<ScriptInfo> {
+    validate_name(name)?;
+    let source = read_source(name)?;
+    if interval_minutes == Some(0) {
+        return Err(AppError::invalid_input(
+            "intervalMinutes",
+            "Interval must be at least one minute",
+        ));
+    }
+
+    let mut schedules = load_schedules()?;
+    match interval_minutes {
+        Some(minutes) => schedules.insert(name.to_string(), minutes),
+        None => schedules.remove(name),
+    };
+    let contents = serde_json::to_string_pretty(&schedules).into_app_error(ErrorCode::FileWrite)?;
+    fs::write(scripts_dir().join(SCHEDULES_FILE), contents).into_app_error(ErrorCode::FileWrite)?;
+
+    Ok(ScriptInfo {
+        name: name.to_string(),
+        capabilities: parse_capabilities(&source),
+        interval_minutes,
+        last_run: LAST_RUNS
+            .lock()
+            .ok()
+            .and_then(|runs| runs.get(name).cloned()),
+    })
+}
+
+/// Starts the background loop that runs scheduled scripts when due.
+///
+/// Does nothing when the engine is not compiled in.
+pub fn start_scheduler() {
+    if !cfg!(feature = "scripting") {
+        return;
+    }
+
+    let task = tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULER_TICK);
+        let mut last_started: HashMap<String, DateTime<Utc>> = HashMap::new();
+        loop {
+            interval.tick().await;
+            let schedules = match load_schedules() {
+                Ok(schedules) => schedules,
+                Err(e) => {
+                    tracing::warn!("Failed to read script schedules: {}", e);
+                    continue;
+                }
+            };
+
+            let now = Utc::now();
+            for (name, minutes) in schedules {
+                if !is_due(last_started.get(&name), minutes, now) {
+                    continue;
+                }
+                last_started.insert(name.clone(), now);
+
+                let result = run(&name).await;
+                let (success, message) = match &result {
+                    Ok(run) => (run.success, run.error.clone()),
+                    Err(e) => (false, Some(e.message.clone())),
+                };
+                if !success {
+                    tracing::warn!("Scheduled script '{}' failed: {:?}", name, message);
+                }
+                events::publish(AppEvent::JobFinished {
+                    job: format!("script:{}", name),
+                    success,
+                    message,
+                });
+            }
+        }
+    });
+    shutdown::track("script-scheduler", task);
+}
+
+/// A scheduled script is due when it has not run since startup or its
+/// interval has elapsed since it last started.
+fn is_due(last_started: Option<&DateTime<Utc>>, minutes: u64, now: DateTime<Utc>) -> bool {
+    last_started.map_or(true, |last| {
+        now.signed_duration_since(*last).num_minutes() >= minutes as i64
+    })
+}
+
+#[cfg(feature = "scripting")]
+fn execute(
+    source: &str,
+    capabilities: &[Capability],
+    runtime: tokio::runtime::Handle,
+) -> AppResult<Execution> {
+    engine::execute(source, capabilities, runtime)
+}
+
+#[cfg(not(feature = "scripting"))]
+fn execute(
+    _source: &str,
+    _capabilities: &[Capability],
+    _runtime: tokio::runtime::Handle,
+) -> AppResult<Execution> {
+    Err(AppError::new(
+        ErrorCode::NotImplemented,
+        "Scripting is not enabled in this build; rebuild with the 'scripting' feature",
+    ))
+}
+
+/// Reads capabilities from the `// capabilities:` header lines at the top
+/// of the script. Unknown names are ignored.
+fn parse_capabilities(source: &str) -> Vec<Capability> {
+    let mut capabilities: Vec<Capability> = source
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.starts_with("//") || line.is_empty())
+        .filter_map(|line| line.strip_prefix(CAPABILITIES_HEADER))
+        .flat_map(|list| list.split(','))
+        .filter_map(Capability::parse)
+        .collect();
+    capabilities.sort();
+    capabilities.dedup();
+    capabilities
+}
+
+fn read_source(name: &str) -> AppResult<String> {
+    let path = scripts_dir().join(format!("{}.{}", name, SCRIPT_EXTENSION));
+    let metadata = fs::metadata(&path).map_err(|_| AppError::not_found("Script"))?;
+    if metadata.len() > MAX_SCRIPT_BYTES {
+        return Err(AppError::invalid_input(
+            "name",
+            format!("Scripts cannot exceed {} bytes", MAX_SCRIPT_BYTES),
+        ));
+    }
+    fs::read_to_string(path).into_app_error(ErrorCode::FileRead)
+}
+
+fn load_schedules() -> AppResult<BTreeMap<String, u64>> {
+    match fs::read_to_string(scripts_dir().join(SCHEDULES_FILE)) {
+        Ok(contents) => serde_json::from_str(&contents).map_err(|e| {
+            AppError::new(
+                ErrorCode::FileRead,
+                format!("Invalid script schedules: {}", e),
+            )
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(AppError::new(ErrorCode::FileRead, e.to_string())),
+    }
+}
+
+fn validate_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::invalid_input(
+            "name",
+            "Script names may only contain letters, digits, '-' and '_'",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    #[test]
+    fn reads_capabilities_from_the_header_only() {
+        let source = "// Sync status\n// capabilities: http, FS, bogus\n// capabilities: fs\n\nlet x = 1;\n// capabilities: cache\n";
+        assert_eq!(
+            parse_capabilities(source),
+            vec![Capability::Fs, Capability::Http]
+        );
+        assert!(parse_capabilities("let x = 1;").is_empty());
+    }
+
+    #[test]
+    fn validates_script_names() {
+        assert!(validate_name("daily-report_2").is_ok());
+        assert!(validate_name("../secrets").is_err());
+        assert!(validate_name("").is_err());
+        assert!(validate_name(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn schedules_are_due_after_their_interval() {
+        let now = Utc::now();
+        assert!(is_due(None, 60, now));
+        assert!(!is_due(Some(&(now - ChronoDuration::minutes(30))), 60, now));
+        assert!(is_due(Some(&(now - ChronoDuration::minutes(60))), 60, now));
+    }
+}
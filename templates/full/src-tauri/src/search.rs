@@ -0,0 +1,420 @@
+//! Full-text search over users, logs, and text files in the filesystem scope.
+//!
+//! Documents are indexed with tantivy under the application data directory.
+//! A background task rebuilds each source periodically; queries return ranked
+//! hits with HTML-highlighted snippets.
+
+use crate::database::get_pool_ref;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::handlers::filesystem::filesystem_root;
+use crate::logging;
+use crate::shutdown;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value, STORED, STRING, TEXT};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+/// Interval between background index rebuilds.
+const REINDEX_INTERVAL: Duration = Duration::from_secs(600);
+/// Memory budget for the index writer.
+const WRITER_HEAP_BYTES: usize = 50_000_000;
+/// Maximum number of log rows indexed.
+const MAX_INDEXED_LOGS: i64 = 5_000;
+/// Text files larger than this are skipped.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// File extensions treated as indexable text.
+const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "markdown", "json", "csv", "log", "yaml", "yml", "toml"];
+/// Directories inside the filesystem scope that hold internal app data.
+const SKIPPED_DIRS: &[&str] = &["logs", "telemetry", "state", "setup", "search"];
+/// Maximum number of results returned by a query.
+const MAX_LIMIT: usize = 100;
+
+static SEARCH_INDEX: OnceCell<SearchIndex> = OnceCell::new();
+
+/// Kinds of indexed content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSource {
+    Users,
+    Logs,
+    Files,
+}
+
+impl SearchSource {
+    pub const ALL: [SearchSource; 3] = [SearchSource::Users, SearchSource::Logs, SearchSource::Files];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SearchSource::Users => "users",
+            SearchSource::Logs => "logs",
+            SearchSource::Files => "files",
+        }
+    }
+}
+
+/// A ranked search result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub source: String,
+    /// User or log id, or the file path relative to the filesystem scope.
+    pub id: String,
+    pub title: String,
+    /// Matching excerpt with query terms wrapped in `<b>` tags.
+    pub snippet: String,
+    pub score: f32,
+}
+
+#[derive(Clone, Copy)]
+struct Fields {
+    id: Field,
+    source: Field,
+    title: Field,
+    body: Field,
+}
+
+struct SearchIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    fields: Fields,
+}
+
+/// A document prepared for indexing.
+struct IndexDocument {
+    id: String,
+    title: String,
+    body: String,
+}
+
+fn index_dir() -> PathBuf {
+    logging::app_data_dir("search")
+}
+
+fn build_schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        id: builder.add_text_field("id", STRING | STORED),
+        source: builder.add_text_field("source", STRING | STORED),
+        title: builder.add_text_field("title", TEXT | STORED),
+        body: builder.add_text_field("body", TEXT | STORED),
+    };
+    (builder.build(), fields)
+}
+
+fn search_index() -> AppResult<&'static SearchIndex> {
+    SEARCH_INDEX.get_or_try_init(|| {
+        let dir = index_dir();
+        std::fs::create_dir_all(&dir).into_app_error(ErrorCode::DirectoryCreate)?;
+
+        let (schema, fields) = build_schema();
+        let directory = MmapDirectory::open(&dir).into_app_error(ErrorCode::FileRead)?;
+        let index = Index::open_or_create(directory, schema).into_app_error(ErrorCode::InternalError)?;
+        let writer = index.writer(WRITER_HEAP_BYTES).into_app_error(ErrorCode::InternalError)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()
+            .into_app_error(ErrorCode::InternalError)?;
+
+        Ok(SearchIndex {
+            index,
+            reader,
+            writer: Mutex::new(writer),
+            fields,
+        })
+    })
+}
+
+/// Runs a query against the selected sources (all when `None`).
+pub fn search(query: &str, sources: Option<Vec<SearchSource>>, limit: Option<usize>) -> AppResult<Vec<SearchHit>> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let search_index = search_index()?;
+    let fields = search_index.fields;
+    let searcher = search_index.reader.searcher();
+
+    let parser = QueryParser::for_index(&search_index.index, vec![fields.title, fields.body]);
+    let (text_query, _) = parser.parse_query_lenient(query);
+    let full_query = with_source_filter(text_query.box_clone(), fields.source, sources.as_deref());
+
+    let limit = limit.unwrap_or(20).clamp(1, MAX_LIMIT);
+    let top_docs = searcher
+        .search(&full_query, &TopDocs::with_limit(limit))
+        .into_app_error(ErrorCode::InternalError)?;
+
+    let mut snippets = SnippetGenerator::create(&searcher, &*text_query, fields.body)
+        .into_app_error(ErrorCode::InternalError)?;
+    snippets.set_max_num_chars(200);
+
+    top_docs
+        .into_iter()
+        .map(|(score, address)| {
+            let document: TantivyDocument = searcher.doc(address).into_app_error(ErrorCode::InternalError)?;
+            let text = |field: Field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+
+            Ok(SearchHit {
+                source: text(fields.source),
+                id: text(fields.id),
+                title: text(fields.title),
+                snippet: snippets.snippet_from_doc(&document).to_html(),
+                score,
+            })
+        })
+        .collect()
+}
+
+/// Restricts a query to documents from `sources`.
+fn with_source_filter(query: Box<dyn Query>, source_field: Field, sources: Option<&[SearchSource]>) -> Box<dyn Query> {
+    let Some(sources) = sources.filter(|sources| !sources.is_empty()) else {
+        return query;
+    };
+
+    let source_clauses: Vec<(Occur, Box<dyn Query>)> = sources
+        .iter()
+        .map(|source| {
+            let term = Term::from_field_text(source_field, source.as_str());
+            (
+                Occur::Should,
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic)) as Box<dyn Query>,
+            )
+        })
+        .collect();
+
+    Box::new(BooleanQuery::new(vec![
+        (Occur::Must, query),
+        (Occur::Must, Box::new(BooleanQuery::new(source_clauses))),
+    ]))
+}
+
+/// Rebuilds every source, logging failures per source.
+pub async fn rebuild_all() -> AppResult<usize> {
+    let mut indexed = 0;
+    for source in SearchSource::ALL {
+        match rebuild_source(source).await {
+            Ok(count) => indexed += count,
+            Err(e) => tracing::warn!("Failed to index {}: {}", source.as_str(), e),
+        }
+    }
+    Ok(indexed)
+}
+
+/// Replaces all documents of `source` with freshly collected ones.
+pub async fn rebuild_source(source: SearchSource) -> AppResult<usize> {
+    let documents = match source {
+        SearchSource::Users => collect_users().await?,
+        SearchSource::Logs => collect_logs().await?,
+        SearchSource::Files => tokio::task::spawn_blocking(collect_files)
+            .await
+            .map_err(|e| AppError::internal_error(format!("File indexing task failed: {}", e)))??,
+    };
+
+    let count = documents.len();
+    tokio::task::spawn_blocking(move || write_documents(source, documents))
+        .await
+        .map_err(|e| AppError::internal_error(format!("Index writer task failed: {}", e)))??;
+
+    tracing::debug!("Indexed {} {} documents", count, source.as_str());
+    Ok(count)
+}
+
+fn write_documents(source: SearchSource, documents: Vec<IndexDocument>) -> AppResult<()> {
+    let search_index = search_index()?;
+    let fields = search_index.fields;
+    let mut writer = search_index
+        .writer
+        .lock()
+        .map_err(|_| AppError::internal_error("Search index writer lock poisoned"))?;
+
+    writer.delete_term(Term::from_field_text(fields.source, source.as_str()));
+    for document in documents {
+        writer
+            .add_document(doc!(
+                fields.id => document.id,
+                fields.source => source.as_str(),
+                fields.title => document.title,
+                fields.body => document.body,
+            ))
+            .into_app_error(ErrorCode::InternalError)?;
+    }
+    writer.commit().into_app_error(ErrorCode::InternalError)?;
+    Ok(())
+}
+
+async fn collect_users() -> AppResult<Vec<IndexDocument>> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let rows: Vec<(uuid::Uuid, String, String, Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT id, username, email, first_name, last_name FROM users")
+            .fetch_all(pool.as_ref())
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, username, email, first_name, last_name)| {
+            let full_name = [first_name, last_name].into_iter().flatten().collect::<Vec<_>>().join(" ");
+            IndexDocument {
+                id: id.to_string(),
+                title: username,
+                body: format!("{} {}", full_name, email).trim().to_string(),
+            }
+        })
+        .collect())
+}
+
+async fn collect_logs() -> AppResult<Vec<IndexDocument>> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let rows: Vec<(uuid::Uuid, String, String)> =
+        sqlx::query_as("SELECT id, level, message FROM app_logs ORDER BY created_at DESC LIMIT $1")
+            .bind(MAX_INDEXED_LOGS)
+            .fetch_all(pool.as_ref())
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, level, message)| IndexDocument {
+            id: id.to_string(),
+            title: level,
+            body: message,
+        })
+        .collect())
+}
+
+fn collect_files() -> AppResult<Vec<IndexDocument>> {
+    let root = filesystem_root().map_err(|e| AppError::file_error("read", ".", e))?;
+    let mut documents = Vec::new();
+    let mut pending = vec![root.clone()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                let skipped = dir == root
+                    && path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| SKIPPED_DIRS.contains(&name));
+                if !skipped {
+                    pending.push(path);
+                }
+            } else if file_type.is_file() && is_indexable(&path) {
+                let too_large = entry.metadata().map(|meta| meta.len() > MAX_FILE_BYTES).unwrap_or(true);
+                if too_large {
+                    continue;
+                }
+                if let Ok(content) = std::fs::read_to_string(&path) {
+                    let relative = path
+                        .strip_prefix(&root)
+                        .unwrap_or(&path)
+                        .to_string_lossy()
+                        .replace('\\', "/");
+                    documents.push(IndexDocument {
+                        id: relative.clone(),
+                        title: relative,
+                        body: content,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(documents)
+}
+
+fn is_indexable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| TEXT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Starts the background task that periodically rebuilds the index.
+pub fn start_indexer() {
+    let indexer = tauri::async_runtime::spawn(async {
+        let mut interval = tokio::time::interval(REINDEX_INTERVAL);
+        loop {
+            interval.tick().await;
+            match rebuild_all().await {
+                Ok(count) => tracing::debug!("Search index rebuilt with {} documents", count),
+                Err(e) => tracing::warn!("Search index rebuild failed: {}", e),
+            }
+        }
+    });
+    shutdown::track("search-indexer", indexer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_index() -> (Index, Fields) {
+        let (schema, fields) = build_schema();
+        let index = Index::create_in_ram(schema);
+        let mut writer: IndexWriter = index.writer(15_000_000).unwrap();
+        writer
+            .add_document(doc!(
+                fields.id => "1",
+                fields.source => "logs",
+                fields.title => "error",
+                fields.body => "database connection refused",
+            ))
+            .unwrap();
+        writer
+            .add_document(doc!(
+                fields.id => "notes.md",
+                fields.source => "files",
+                fields.title => "notes.md",
+                fields.body => "remember the database backup",
+            ))
+            .unwrap();
+        writer.commit().unwrap();
+        (index, fields)
+    }
+
+    #[test]
+    fn source_filter_restricts_results() {
+        let (index, fields) = test_index();
+        let searcher = index.reader().unwrap().searcher();
+        let parser = QueryParser::for_index(&index, vec![fields.title, fields.body]);
+        let (query, _) = parser.parse_query_lenient("database");
+
+        let all = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+        assert_eq!(all.len(), 2);
+
+        let filtered = with_source_filter(query, fields.source, Some(&[SearchSource::Files]));
+        let files = searcher.search(&filtered, &TopDocs::with_limit(10)).unwrap();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn only_text_files_are_indexable() {
+        assert!(is_indexable(Path::new("notes/todo.MD")));
+        assert!(!is_indexable(Path::new("image.png")));
+        assert!(!is_indexable(Path::new("README")));
+    }
+}
@@ -0,0 +1,616 @@
+//! Typed secret records kept in a credential store.
+//!
+//! Each record type declares its kind and schema version through
+//! [`SecretRecord`] and is stored under `<kind>:<name>` inside a versioned
+//! envelope, so subsystems share one naming and encoding convention instead
+//! of inventing their own. Records written by an older schema are upgraded
+//! when read; records from a newer schema are rejected rather than guessed
+//! at. Records live in the [`CredentialStore`] selected by
+//! `CREDENTIAL_BACKEND`: by default a Stronghold snapshot in the application
+//! data directory unlocked with the vault password, like clipboard history,
+//! or else the platform keychain.
+
+use crate::config::{self, CredentialBackend};
+#[cfg(feature = "stronghold")]
+use crate::credential_store::StrongholdStore;
+use crate::credential_store::{CredentialStore, KeychainStore};
+use crate::errors::{AppError, AppResult, ErrorCode};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+#[cfg(feature = "stronghold")]
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+/// Snapshot file inside the app data directory.
+#[cfg(feature = "stronghold")]
+const SNAPSHOT_FILE: &str = "secrets.hold";
+
+/// Stronghold client holding every record.
+#[cfg(feature = "stronghold")]
+const CLIENT: &str = "secrets";
+
+/// Suffix of the keychain service, after the app identifier.
+const KEYCHAIN_SERVICE: &str = "secrets";
+
+/// Longest accepted record name, in characters.
+const MAX_NAME_CHARS: usize = 128;
+
+/// The unlocked store.
+static VAULT: Lazy<Mutex<Option<Box<dyn CredentialStore>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Kinds of records stored in the snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SecretKind {
+    Totp,
+    ApiToken,
+    DbCredential,
+}
+
+impl SecretKind {
+    pub const ALL: [SecretKind; 3] = [Self::Totp, Self::ApiToken, Self::DbCredential];
+
+    /// Prefix used in store keys.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Totp => "totp",
+            Self::ApiToken => "apiToken",
+            Self::DbCredential => "dbCredential",
+        }
+    }
+}
+
+/// A record type stored in the secrets snapshot.
+pub trait SecretRecord: Serialize + DeserializeOwned {
+    const KIND: SecretKind;
+    /// Schema version written with new records. Bump it when the stored
+    /// shape changes and teach [`SecretRecord::upgrade`] the old one.
+    const VERSION: u32;
+
+    /// Converts data stored under schema `version` to the current schema.
+    fn upgrade(version: u32, _data: serde_json::Value) -> AppResult<serde_json::Value> {
+        Err(AppError::new(
+            ErrorCode::InvalidFormat,
+            format!(
+                "Unsupported {} record version {}",
+                Self::KIND.as_str(),
+                version
+            ),
+        ))
+    }
+
+    /// Rejects records that cannot be used.
+    fn validate(&self) -> AppResult<()> {
+        Ok(())
+    }
+}
+
+/// HMAC algorithm used to derive TOTP codes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TotpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// Shared secret of a time-based one-time password.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpSecret {
+    /// Base32-encoded shared secret.
+    pub secret: String,
+    pub account: String,
+    pub issuer: Option<String>,
+    #[serde(default)]
+    pub algorithm: TotpAlgorithm,
+    #[serde(default = "default_totp_digits")]
+    pub digits: u32,
+    /// Seconds each code is valid for.
+    #[serde(default = "default_totp_period")]
+    pub period: u64,
+}
+
+fn default_totp_digits() -> u32 {
+    6
+}
+
+fn default_totp_period() -> u64 {
+    30
+}
+
+impl fmt::Debug for TotpSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TotpSecret")
+            .field("secret", &"<redacted>")
+            .field("account", &self.account)
+            .field("issuer", &self.issuer)
+            .field("algorithm", &self.algorithm)
+            .field("digits", &self.digits)
+            .field("period", &self.period)
+            .finish()
+    }
+}
+
+impl SecretRecord for TotpSecret {
+    const KIND: SecretKind = SecretKind::Totp;
+    const VERSION: u32 = 1;
+
+    fn validate(&self) -> AppResult<()> {
+        let secret = self.secret.trim_end_matches('=');
+        let valid = !secret.is_empty()
+            && secret
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || ('2'..='7').contains(&c));
+        if !valid {
+            return Err(AppError::invalid_input(
+                "secret",
+                "TOTP secrets must be uppercase base32",
+            ));
+        }
+        if !(6..=8).contains(&self.digits) {
+            return Err(AppError::invalid_input("digits", "Digits must be 6 to 8"));
+        }
+        if self.period == 0 {
+            return Err(AppError::invalid_input("period", "Period must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Token for an external API.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub service: String,
+    pub token: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Account the token acts for, if it is bound to one.
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
+}
+
+impl fmt::Debug for ApiToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiToken")
+            .field("service", &self.service)
+            .field("token", &"<redacted>")
+            .field("scopes", &self.scopes)
+            .field("expires_at", &self.expires_at)
+            .field("user_id", &self.user_id)
+            .finish()
+    }
+}
+
+impl SecretRecord for ApiToken {
+    const KIND: SecretKind = SecretKind::ApiToken;
+    const VERSION: u32 = 1;
+
+    fn validate(&self) -> AppResult<()> {
+        if self.token.is_empty() {
+            return Err(AppError::invalid_input("token", "Token is required"));
+        }
+        Ok(())
+    }
+}
+
+/// Credentials for a database server.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbCredential {
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+    pub user: String,
+    pub password: String,
+}
+
+impl fmt::Debug for DbCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DbCredential")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("database", &self.database)
+            .field("user", &self.user)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+impl SecretRecord for DbCredential {
+    const KIND: SecretKind = SecretKind::DbCredential;
+    const VERSION: u32 = 1;
+
+    fn validate(&self) -> AppResult<()> {
+        if self.host.trim().is_empty() || self.user.trim().is_empty() {
+            return Err(AppError::invalid_input(
+                "credential",
+                "Host and user are required",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Any record, tagged with its kind, as exchanged with the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "record", rename_all = "camelCase")]
+pub enum Secret {
+    Totp(TotpSecret),
+    ApiToken(ApiToken),
+    DbCredential(DbCredential),
+}
+
+/// Stored record metadata, without the secret itself.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretSummary {
+    pub kind: SecretKind,
+    pub name: String,
+    pub version: u32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Stored form of every record.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    kind: SecretKind,
+    version: u32,
+    updated_at: DateTime<Utc>,
+    data: serde_json::Value,
+}
+
+/// Unlocks the store, creating it on first use. The Stronghold backend needs
+/// the vault password; the keychain is unlocked by the OS and ignores it.
+///
+/// Returns the number of stored records. Unlocking while unlocked is a no-op.
+pub fn unlock(app: &AppHandle, password: &str) -> AppResult<usize> {
+    let mut vault = lock_vault()?;
+    if vault.is_none() {
+        let store = open_store(app, config::current().credential_backend, password)?;
+        tracing::info!("Secrets unlocked ({:?} backend)", store.backend());
+        *vault = Some(store);
+    }
+    let store = vault.as_ref().expect("vault was just unlocked");
+    Ok(store.keys()?.len())
+}
+
+/// Locks the snapshot. Returns whether it was unlocked.
+pub fn lock() -> AppResult<bool> {
+    let unlocked = lock_vault()?.take().is_some();
+    if unlocked {
+        tracing::info!("Secrets vault locked");
+    }
+    Ok(unlocked)
+}
+
+/// Returns whether the snapshot is unlocked.
+pub fn is_unlocked() -> bool {
+    lock_vault().map(|vault| vault.is_some()).unwrap_or(false)
+}
+
+/// Stores `record` under `name`, replacing any record of the same kind.
+pub fn put<R: SecretRecord>(name: &str, record: &R) -> AppResult<()> {
+    validate_name(name)?;
+    record.validate()?;
+    let bytes = encode(record)?;
+    with_vault(|vault| vault.insert(&key(R::KIND, name), bytes))
+}
+
+/// Returns the record `name`, upgrading it from an older schema if needed.
+pub fn get<R: SecretRecord>(name: &str) -> AppResult<Option<R>> {
+    validate_name(name)?;
+    let bytes = with_vault(|vault| vault.get(&key(R::KIND, name)))?;
+    bytes.map(|bytes| decode(&bytes)).transpose()
+}
+
+/// Deletes the record `name`. Returns whether it existed.
+pub fn remove<R: SecretRecord>(name: &str) -> AppResult<bool> {
+    remove_kind(R::KIND, name)
+}
+
+/// Stores a record received from the frontend.
+pub fn put_secret(name: &str, secret: &Secret) -> AppResult<()> {
+    match secret {
+        Secret::Totp(record) => put(name, record),
+        Secret::ApiToken(record) => put(name, record),
+        Secret::DbCredential(record) => put(name, record),
+    }
+}
+
+/// Returns the record `name` of `kind`.
+pub fn get_secret(kind: SecretKind, name: &str) -> AppResult<Option<Secret>> {
+    Ok(match kind {
+        SecretKind::Totp => get::<TotpSecret>(name)?.map(Secret::Totp),
+        SecretKind::ApiToken => get::<ApiToken>(name)?.map(Secret::ApiToken),
+        SecretKind::DbCredential => get::<DbCredential>(name)?.map(Secret::DbCredential),
+    })
+}
+
+/// Deletes the record `name` of `kind`. Returns whether it existed.
+pub fn remove_kind(kind: SecretKind, name: &str) -> AppResult<bool> {
+    validate_name(name)?;
+    with_vault(|vault| vault.delete(&key(kind, name)))
+}
+
+/// Lists stored records, optionally of one kind, sorted by kind and name.
+pub fn list(kind: Option<SecretKind>) -> AppResult<Vec<SecretSummary>> {
+    with_vault(|vault| {
+        let mut summaries = Vec::new();
+        for raw in vault.keys()? {
+            let Some((record_kind, name)) = parse_key(&raw) else {
+                continue;
+            };
+            if kind.is_some_and(|kind| kind != record_kind) {
+                continue;
+            }
+            let Some(bytes) = vault.get(&raw)? else {
+                continue;
+            };
+            let envelope = decode_envelope(&bytes)?;
+            summaries.push(SecretSummary {
+                kind: record_kind,
+                name,
+                version: envelope.version,
+                updated_at: envelope.updated_at,
+            });
+        }
+        summaries.sort_by(|a, b| (a.kind.as_str(), &a.name).cmp(&(b.kind.as_str(), &b.name)));
+        Ok(summaries)
+    })
+}
+
+fn encode<R: SecretRecord>(record: &R) -> AppResult<Vec<u8>> {
+    let envelope = Envelope {
+        kind: R::KIND,
+        version: R::VERSION,
+        updated_at: Utc::now(),
+        data: serde_json::to_value(record).map_err(|e| AppError::internal_error(e.to_string()))?,
+    };
+    serde_json::to_vec(&envelope).map_err(|e| AppError::internal_error(e.to_string()))
+}
+
+fn decode<R: SecretRecord>(bytes: &[u8]) -> AppResult<R> {
+    let envelope = decode_envelope(bytes)?;
+    if envelope.kind != R::KIND {
+        return Err(corrupt(format!(
+            "expected a {} record, found {}",
+            R::KIND.as_str(),
+            envelope.kind.as_str()
+        )));
+    }
+    let data = match envelope.version {
+        version if version == R::VERSION => envelope.data,
+        version if version < R::VERSION => R::upgrade(version, envelope.data)?,
+        version => {
+            return Err(AppError::new(
+                ErrorCode::InvalidFormat,
+                format!(
+                    "{} record version {} is newer than supported version {}",
+                    R::KIND.as_str(),
+                    version,
+                    R::VERSION
+                ),
+            ))
+        }
+    };
+    serde_json::from_value(data).map_err(|e| corrupt(e.to_string()))
+}
+
+fn decode_envelope(bytes: &[u8]) -> AppResult<Envelope> {
+    serde_json::from_slice(bytes).map_err(|e| corrupt(e.to_string()))
+}
+
+fn key(kind: SecretKind, name: &str) -> String {
+    format!("{}:{}", kind.as_str(), name)
+}
+
+fn parse_key(raw: &str) -> Option<(SecretKind, String)> {
+    let (prefix, name) = raw.split_once(':')?;
+    let kind = SecretKind::ALL
+        .into_iter()
+        .find(|kind| kind.as_str() == prefix)?;
+    Some((kind, name.to_string()))
+}
+
+fn validate_name(name: &str) -> AppResult<()> {
+    let chars = name.chars().count();
+    if chars == 0 || chars > MAX_NAME_CHARS || name.chars().any(char::is_control) {
+        return Err(AppError::invalid_input(
+            "name",
+            "Names must be 1-128 characters without control characters",
+        ));
+    }
+    Ok(())
+}
+
+fn lock_vault() -> AppResult<std::sync::MutexGuard<'static, Option<Box<dyn CredentialStore>>>> {
+    VAULT
+        .lock()
+        .map_err(|_| AppError::internal_error("Secrets vault is unavailable"))
+}
+
+fn with_vault<T>(f: impl FnOnce(&dyn CredentialStore) -> AppResult<T>) -> AppResult<T> {
+    match lock_vault()?.as_ref() {
+        Some(vault) => f(vault.as_ref()),
+        None => Err(AppError::new(
+            ErrorCode::Forbidden,
+            "Secrets are locked; unlock them with the vault password first",
+        )),
+    }
+}
+
+#[cfg(feature = "stronghold")]
+fn snapshot_path(app: &AppHandle) -> AppResult<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(SNAPSHOT_FILE))
+        .map_err(|e| AppError::new(ErrorCode::FileRead, format!("No app data directory: {}", e)))
+}
+
+#[cfg_attr(not(feature = "stronghold"), allow(unused_variables))]
+fn open_store(
+    app: &AppHandle,
+    backend: CredentialBackend,
+    password: &str,
+) -> AppResult<Box<dyn CredentialStore>> {
+    match backend {
+        #[cfg(not(feature = "stronghold"))]
+        CredentialBackend::Stronghold => Err(AppError::new(
+            ErrorCode::NotImplemented,
+            "Stronghold is not enabled in this build; set CREDENTIAL_BACKEND=keychain",
+        )),
+        #[cfg(feature = "stronghold")]
+        CredentialBackend::Stronghold => {
+            if password.is_empty() {
+                return Err(AppError::invalid_input("password", "Password is required"));
+            }
+            Ok(Box::new(StrongholdStore::open(
+                &snapshot_path(app)?,
+                CLIENT,
+                password,
+            )?))
+        }
+        CredentialBackend::Keychain => {
+            let service = format!("{}.{}", app.config().identifier, KEYCHAIN_SERVICE);
+            Ok(Box::new(KeychainStore::open(&service)?))
+        }
+    }
+}
+
+fn corrupt(message: impl fmt::Display) -> AppError {
+    AppError::new(
+        ErrorCode::InvalidFormat,
+        format!("Stored secret is corrupt: {}", message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn totp() -> TotpSecret {
+        TotpSecret {
+            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            account: "ada@example.com".to_string(),
+            issuer: Some("Example".to_string()),
+            algorithm: TotpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+        }
+    }
+
+    /// Version 2 of a record whose version 1 stored `key` instead of `token`.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct RenamedToken {
+        token: String,
+    }
+
+    impl SecretRecord for RenamedToken {
+        const KIND: SecretKind = SecretKind::ApiToken;
+        const VERSION: u32 = 2;
+
+        fn upgrade(version: u32, data: serde_json::Value) -> AppResult<serde_json::Value> {
+            match version {
+                1 => Ok(json!({ "token": data["key"] })),
+                _ => Err(corrupt("unknown version")),
+            }
+        }
+    }
+
+    fn stored(kind: SecretKind, version: u32, data: serde_json::Value) -> Vec<u8> {
+        serde_json::to_vec(&Envelope {
+            kind,
+            version,
+            updated_at: Utc::now(),
+            data,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn records_round_trip_through_envelopes() {
+        let bytes = encode(&totp()).unwrap();
+        let envelope = decode_envelope(&bytes).unwrap();
+        assert_eq!(envelope.kind, SecretKind::Totp);
+        assert_eq!(envelope.version, 1);
+        assert_eq!(decode::<TotpSecret>(&bytes).unwrap(), totp());
+        assert!(decode::<ApiToken>(&bytes).is_err());
+    }
+
+    #[test]
+    fn upgrades_older_and_rejects_newer_versions() {
+        let old = stored(SecretKind::ApiToken, 1, json!({ "key": "abc" }));
+        assert_eq!(
+            decode::<RenamedToken>(&old).unwrap(),
+            RenamedToken {
+                token: "abc".to_string()
+            }
+        );
+
+        let newer = stored(SecretKind::ApiToken, 3, json!({ "token": "abc" }));
+        assert!(decode::<RenamedToken>(&newer).is_err());
+        assert!(decode::<RenamedToken>(b"not json").is_err());
+    }
+
+    #[test]
+    fn keys_are_namespaced_by_kind() {
+        let raw = key(SecretKind::DbCredential, "primary:replica");
+        assert_eq!(raw, "dbCredential:primary:replica");
+        assert_eq!(
+            parse_key(&raw),
+            Some((SecretKind::DbCredential, "primary:replica".to_string()))
+        );
+        assert_eq!(parse_key("other:name"), None);
+        assert!(validate_name("").is_err());
+        assert!(validate_name("line\nbreak").is_err());
+    }
+
+    #[test]
+    fn validates_totp_secrets() {
+        assert!(totp().validate().is_ok());
+        assert!(TotpSecret {
+            secret: "not base32!".to_string(),
+            ..totp()
+        }
+        .validate()
+        .is_err());
+        assert!(TotpSecret {
+            digits: 4,
+            ..totp()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn debug_output_redacts_secrets() {
+        let credential = DbCredential {
+            host: "db.local".to_string(),
+            port: 5432,
+            database: "app".to_string(),
+            user: "svc".to_string(),
+            password: "hunter2".to_string(),
+        };
+        assert!(!format!("{:?}", credential).contains("hunter2"));
+        assert!(!format!("{:?}", totp()).contains("JBSWY3DPEHPK3PXP"));
+    }
+
+    #[test]
+    fn secrets_are_locked_until_unlocked() {
+        assert!(!is_unlocked());
+        assert!(get::<TotpSecret>("github").is_err());
+        assert!(list(None).is_err());
+        assert!(!lock().unwrap());
+    }
+}
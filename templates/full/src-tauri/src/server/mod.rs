@@ -0,0 +1,239 @@
+//! Embedded loopback HTTP server for OAuth redirects and local webhooks.
+//!
+//! Routes are registered from Rust and every request that hits a registered
+//! route is published on the event bus under the route's event name. The
+//! server only ever binds to 127.0.0.1.
+
+use crate::config;
+use crate::events::{self, AppEvent};
+use crate::metrics;
+use crate::shutdown;
+use anyhow::Result;
+use axum::body::Bytes;
+use axum::extract::Query;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{Html, IntoResponse, Response};
+use axum::{Json, Router};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::RwLock;
+
+/// Default route used to capture OAuth authorization-code redirects.
+pub const OAUTH_CALLBACK_ROUTE: &str = "/oauth/callback";
+/// Default route for generic local webhooks.
+pub const WEBHOOK_ROUTE: &str = "/webhook";
+/// Prometheus scrape route, served only when `LOCAL_SERVER_METRICS` is enabled.
+pub const METRICS_ROUTE: &str = "/metrics";
+
+/// Registered routes mapped to the event name emitted when they are hit.
+static ROUTES: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| {
+    let mut routes = HashMap::new();
+    routes.insert(OAUTH_CALLBACK_ROUTE.to_string(), "server:oauth-callback".to_string());
+    routes.insert(WEBHOOK_ROUTE.to_string(), "server:webhook".to_string());
+    RwLock::new(routes)
+});
+
+/// Address the server is bound to once started.
+static BOUND_ADDRESS: OnceCell<SocketAddr> = OnceCell::new();
+
+/// Request payload forwarded to the frontend for a registered route.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerRequestPayload {
+    pub route: String,
+    pub method: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Option<serde_json::Value>,
+    pub received_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Current state of the embedded server.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+    pub routes: HashMap<String, String>,
+}
+
+/// Registers a route whose requests are forwarded as `event` to the frontend.
+///
+/// Routes can be registered before or after the server starts.
+pub fn register_route(path: &str, event: &str) -> Result<()> {
+    let path = normalize_route(path);
+    if event.trim().is_empty() {
+        return Err(anyhow::anyhow!("Event name cannot be empty"));
+    }
+
+    let mut routes = ROUTES
+        .write()
+        .map_err(|_| anyhow::anyhow!("Failed to lock route registry"))?;
+    routes.insert(path, event.trim().to_string());
+    Ok(())
+}
+
+/// Removes a previously registered route.
+pub fn unregister_route(path: &str) -> Result<bool> {
+    let mut routes = ROUTES
+        .write()
+        .map_err(|_| anyhow::anyhow!("Failed to lock route registry"))?;
+    Ok(routes.remove(&normalize_route(path)).is_some())
+}
+
+/// Returns the base URL of the running server, e.g. `http://127.0.0.1:8765`.
+pub fn base_url() -> Option<String> {
+    BOUND_ADDRESS.get().map(|addr| format!("http://{}", addr))
+}
+
+/// Returns the running state, bound address, and registered routes.
+pub fn status() -> LocalServerStatus {
+    let routes = ROUTES
+        .read()
+        .map(|routes| routes.clone())
+        .unwrap_or_default();
+
+    LocalServerStatus {
+        running: BOUND_ADDRESS.get().is_some(),
+        address: base_url(),
+        routes,
+    }
+}
+
+/// Binds the loopback server on `port` and serves requests in the background.
+pub async fn start_server(port: u16) -> Result<SocketAddr> {
+    if let Some(addr) = BOUND_ADDRESS.get() {
+        return Ok(*addr);
+    }
+
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+    let addr = listener.local_addr()?;
+    BOUND_ADDRESS
+        .set(addr)
+        .map_err(|_| anyhow::anyhow!("Local server already started"))?;
+
+    let router = Router::new().fallback(handle_request);
+
+    let server = tauri::async_runtime::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            tracing::error!("Local HTTP server stopped: {}", e);
+        }
+    });
+    shutdown::track("local-server", server);
+
+    tracing::info!("Local HTTP server listening on {}", addr);
+    Ok(addr)
+}
+
+/// Dispatches every request through the route registry.
+async fn handle_request(
+    method: Method,
+    uri: Uri,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let route = normalize_route(uri.path());
+
+    if route == METRICS_ROUTE && method == Method::GET && config::current().local_server_metrics {
+        return (
+            [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            metrics::render_prometheus(),
+        )
+            .into_response();
+    }
+
+    let event = ROUTES
+        .read()
+        .ok()
+        .and_then(|routes| routes.get(&route).cloned());
+
+    let Some(event) = event else {
+        return (StatusCode::NOT_FOUND, "Not found").into_response();
+    };
+
+    let payload = ServerRequestPayload {
+        route,
+        method: method.to_string(),
+        query,
+        headers: headers
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_string(), value.to_string()))
+            })
+            .collect(),
+        body: parse_body(&body),
+        received_at: chrono::Utc::now(),
+    };
+
+    events::publish(AppEvent::ServerRequest {
+        event,
+        request: payload,
+    });
+
+    if method == Method::GET {
+        Html("<html><body><p>Request received. You can close this window and return to the app.</p></body></html>")
+            .into_response()
+    } else {
+        (StatusCode::ACCEPTED, Json(serde_json::json!({ "received": true }))).into_response()
+    }
+}
+
+/// Parses a request body as JSON, falling back to a string value.
+fn parse_body(body: &[u8]) -> Option<serde_json::Value> {
+    if body.is_empty() {
+        return None;
+    }
+
+    serde_json::from_slice(body)
+        .ok()
+        .or_else(|| Some(serde_json::Value::String(String::from_utf8_lossy(body).to_string())))
+}
+
+/// Normalizes a route path to a leading slash without a trailing slash.
+fn normalize_route(path: &str) -> String {
+    let trimmed = path.trim().trim_end_matches('/');
+    if trimmed.starts_with('/') {
+        trimmed.to_string()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_route_paths() {
+        assert_eq!(normalize_route("oauth/callback/"), "/oauth/callback");
+        assert_eq!(normalize_route("/webhook"), "/webhook");
+        assert_eq!(normalize_route(""), "/");
+    }
+
+    #[test]
+    fn parses_json_and_text_bodies() {
+        assert_eq!(parse_body(b""), None);
+        assert_eq!(parse_body(br#"{"a":1}"#), Some(serde_json::json!({"a": 1})));
+        assert_eq!(
+            parse_body(b"plain text"),
+            Some(serde_json::Value::String("plain text".to_string()))
+        );
+    }
+
+    #[test]
+    fn registers_and_removes_routes() {
+        register_route("integrations/github", "server:github").unwrap();
+        assert_eq!(
+            status().routes.get("/integrations/github").map(String::as_str),
+            Some("server:github")
+        );
+        assert!(unregister_route("/integrations/github/").unwrap());
+        assert!(register_route("/empty", " ").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Login sessions persisted in the `user_sessions` table of the selected
+//! database, through `postgres` or `sqlite`.
+//!
+//! Signing in creates a session and issues a short-lived JWT access token
+//! plus an opaque refresh token. Only a SHA-256 hash of the refresh token is
+//! stored, and each refresh replaces it. Access tokens name their session,
+//! so deleting the row (logout, expiry, or server-side revocation) rejects
+//! them even before their `exp`.
+//!
+//! Sessions slide: a heartbeat or refresh pushes expiry back by the
+//! configured idle timeout. A background reaper deletes lapsed sessions and
+//! publishes `session:expired` so the frontend can log the user out.
+//!
+//! With PostgreSQL, an admin can impersonate a user with
+//! `start_impersonation`. That session belongs to the user but records the
+//! admin as its impersonator, and it lapses after `IMPERSONATION_MINUTES` no
+//! matter how active it is. The embedded database has no impersonation.
+//!
+//! A "remember me" session lasts `SESSION_REMEMBER_ME_DAYS` instead of the
+//! idle timeout. Because its refresh token lives that long on the device,
+//! every token it retires is kept, and presenting one again revokes the
+//! session: either the legitimate client or a thief holds a stale copy.
+
+use crate::config::{self, DatabaseBackend};
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::events::{self, AppEvent};
+use crate::models::{Session, SessionTokens};
+use crate::shutdown;
+use chrono::{Duration as ChronoDuration, Utc};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use uuid::Uuid;
+
+#[cfg(feature = "database")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+#[cfg(feature = "database")]
+pub use postgres::*;
+
+/// Interval between reaper runs.
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Signing key used when `SESSION_JWT_SECRET` is unset.
+static EPHEMERAL_KEY: Lazy<String> = Lazy::new(|| {
+    tracing::warn!("SESSION_JWT_SECRET is not set; access tokens will not survive a restart");
+    random_token()
+});
+
+/// Claims carried by an access token.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Claims {
+    /// User the token was issued to.
+    sub: Uuid,
+    /// Session the token belongs to.
+    sid: Uuid,
+    iat: i64,
+    exp: i64,
+}
+
+/// Starts the periodic task that deletes lapsed sessions from the selected
+/// database.
+pub fn start_reaper() {
+    let backend = config::current().database_backend;
+    let task = tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REAP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let now = Utc::now();
+            let reaped = match backend {
+                #[cfg(feature = "database")]
+                DatabaseBackend::Postgres => match crate::database::get_pool_ref() {
+                    Ok(pool) => postgres::reap(pool.as_ref(), now).await,
+                    // The database may not be configured yet.
+                    Err(_) => continue,
+                },
+                #[cfg(feature = "sqlite")]
+                DatabaseBackend::Sqlite => match crate::local_db::get_pool_ref() {
+                    Ok(pool) => sqlite::reap(pool.as_ref(), now).await,
+                    Err(_) => continue,
+                },
+                #[allow(unreachable_patterns)]
+                _ => return,
+            };
+            match reaped {
+                Ok(stale) => {
+                    for (session_id, user_id) in stale {
+                        expired(session_id, user_id);
+                    }
+                }
+                Err(e) => tracing::warn!("Session reaper skipped: {}", e),
+            }
+        }
+    });
+    shutdown::track("session-reaper", task);
+}
+
+fn issue(session: &Session, refresh_token: String) -> AppResult<SessionTokens> {
+    let issued_at = Utc::now();
+    let access_token_expires_at = issued_at + access_token_lifetime();
+    let access_token = encode_access_token(
+        &Claims {
+            sub: session.user_id,
+            sid: session.id,
+            iat: issued_at.timestamp(),
+            exp: access_token_expires_at.timestamp(),
+        },
+        &signing_key(),
+    )?;
+
+    Ok(SessionTokens {
+        session_id: session.id,
+        access_token,
+        access_token_expires_at,
+        refresh_token,
+        session_expires_at: session.expires_at,
+    })
+}
+
+fn encode_access_token(claims: &Claims, key: &[u8]) -> AppResult<String> {
+    jsonwebtoken::encode(&Header::default(), claims, &EncodingKey::from_secret(key))
+        .map_err(|e| AppError::internal_error(format!("Failed to sign access token: {}", e)))
+}
+
+fn decode_access_token(token: &str) -> AppResult<Claims> {
+    decode_with_key(token, &signing_key())
+}
+
+fn decode_with_key(token: &str, key: &[u8]) -> AppResult<Claims> {
+    let mut validation = Validation::default();
+    validation.leeway = 0;
+    jsonwebtoken::decode::<Claims>(token, &DecodingKey::from_secret(key), &validation)
+        .map(|data| data.claims)
+        .map_err(|e| match e.kind() {
+            ErrorKind::ExpiredSignature => {
+                AppError::new(ErrorCode::TokenExpired, "Access token has expired")
+            }
+            _ => AppError::new(ErrorCode::Unauthorized, "Invalid access token"),
+        })
+}
+
+fn signing_key() -> Vec<u8> {
+    let config = config::current();
+    match &config.session_tokens.signing_secret {
+        Some(secret) => secret.as_bytes().to_vec(),
+        None => EPHEMERAL_KEY.as_bytes().to_vec(),
+    }
+}
+
+/// 244 random bits from two v4 UUIDs, hex encoded.
+pub(crate) fn random_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn expired(session_id: Uuid, user_id: Uuid) {
+    tracing::info!("Session {} expired for user {}", session_id, user_id);
+    events::publish(AppEvent::SessionExpired {
+        session_id,
+        user_id,
+    });
+}
+
+fn idle_timeout() -> ChronoDuration {
+    ChronoDuration::minutes(config::current().session_idle_timeout_minutes as i64)
+}
+
+fn remember_me_lifetime() -> ChronoDuration {
+    ChronoDuration::days(config::current().session_tokens.remember_me_days as i64)
+}
+
+fn access_token_lifetime() -> ChronoDuration {
+    ChronoDuration::minutes(config::current().session_tokens.access_token_minutes as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn claims(exp: DateTime<Utc>) -> Claims {
+        Claims {
+            sub: Uuid::new_v4(),
+            sid: Uuid::new_v4(),
+            iat: Utc::now().timestamp(),
+            exp: exp.timestamp(),
+        }
+    }
+
+    #[test]
+    fn access_tokens_round_trip_and_reject_tampering() {
+        let claims = claims(Utc::now() + ChronoDuration::minutes(5));
+        let token = encode_access_token(&claims, b"secret").unwrap();
+        assert_eq!(decode_with_key(&token, b"secret").unwrap(), claims);
+
+        let error = decode_with_key(&token, b"other").unwrap_err();
+        assert!(matches!(error.code, ErrorCode::Unauthorized));
+        assert!(decode_with_key("not-a-token", b"secret").is_err());
+    }
+
+    #[test]
+    fn expired_access_tokens_are_rejected() {
+        let token =
+            encode_access_token(&claims(Utc::now() - ChronoDuration::minutes(1)), b"secret")
+                .unwrap();
+        let error = decode_with_key(&token, b"secret").unwrap_err();
+        assert!(matches!(error.code, ErrorCode::TokenExpired));
+    }
+
+    #[test]
+    fn refresh_tokens_are_stored_hashed() {
+        let token = random_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, random_token());
+        assert_eq!(hash_token(&token), hash_token(&token));
+        assert_ne!(hash_token(&token), token);
+    }
+}
@@ -0,0 +1,431 @@
+//! Session store in the PostgreSQL `user_sessions` table.
+//!
+//! A refresh rotates the token and retires the old one in a single
+//! statement, so concurrent refreshes with the same token cannot both
+//! succeed.
+
+use super::{
+    decode_access_token, expired, hash_token, idle_timeout, issue, random_token,
+    remember_me_lifetime,
+};
+use crate::audit;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::models::{Session, SessionTokens};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Lifetime of an impersonation session, which is never extended.
+pub const IMPERSONATION_MINUTES: i64 = 15;
+
+const SESSION_COLUMNS: &str =
+    "id, user_id, created_at, last_active, expires_at, impersonator_id, remember_me";
+
+/// New expiry for an activity at `$3`, given the idle (`$4`) and remember-me
+/// (`$5`) expiries. Impersonation sessions keep theirs.
+const EXTENDED_EXPIRY: &str = "CASE WHEN impersonator_id IS NOT NULL THEN expires_at
+                     WHEN remember_me THEN $5 ELSE $4 END";
+
+/// Starts a session for `user_id` and issues its first tokens. With
+/// `remember_me` the session lasts the configured number of days.
+pub async fn start(pool: &PgPool, user_id: Uuid, remember_me: bool) -> AppResult<SessionTokens> {
+    let refresh_token = random_token();
+    let now = Utc::now();
+    let expires_at = if remember_me {
+        now + remember_me_lifetime()
+    } else {
+        now + idle_timeout()
+    };
+    let session: Session = sqlx::query_as(&format!(
+        "INSERT INTO user_sessions (user_id, refresh_token_hash, expires_at, remember_me)
+         VALUES ($1, $2, $3, $4)
+         RETURNING {}",
+        SESSION_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(hash_token(&refresh_token))
+    .bind(expires_at)
+    .bind(remember_me)
+    .fetch_one(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    tracing::debug!("Started session {} for user {}", session.id, user_id);
+    issue(&session, refresh_token)
+}
+
+/// Starts a session as `user_id` on behalf of `admin_id`, expiring after
+/// [`IMPERSONATION_MINUTES`].
+pub async fn start_impersonation(
+    pool: &PgPool,
+    admin_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<SessionTokens> {
+    let refresh_token = random_token();
+    let session: Session = sqlx::query_as(&format!(
+        "INSERT INTO user_sessions (user_id, refresh_token_hash, expires_at, impersonator_id)
+         VALUES ($1, $2, $3, $4)
+         RETURNING {}",
+        SESSION_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(hash_token(&refresh_token))
+    .bind(Utc::now() + ChronoDuration::minutes(IMPERSONATION_MINUTES))
+    .bind(admin_id)
+    .fetch_one(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    tracing::debug!(
+        "Started impersonation session {} of user {} by {}",
+        session.id,
+        user_id,
+        admin_id
+    );
+    issue(&session, refresh_token)
+}
+
+/// Ends the impersonation session `session_id`, returning it when one was
+/// active. With `impersonator_id`, only a session of that admin is ended.
+pub async fn end_impersonation(
+    pool: &PgPool,
+    session_id: Uuid,
+    impersonator_id: Option<Uuid>,
+) -> AppResult<Option<Session>> {
+    sqlx::query_as(&format!(
+        "DELETE FROM user_sessions
+         WHERE id = $1 AND impersonator_id IS NOT NULL
+           AND ($2::uuid IS NULL OR impersonator_id = $2)
+         RETURNING {}",
+        SESSION_COLUMNS
+    ))
+    .bind(session_id)
+    .bind(impersonator_id)
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)
+}
+
+/// Exchanges `refresh_token` for new tokens, replacing the refresh token and
+/// extending the session unless it is an impersonation. A remember-me
+/// session keeps the replaced token to detect its reuse.
+///
+/// Fails with `TOKEN_EXPIRED` when the session has lapsed and
+/// `UNAUTHORIZED` when the token is unknown or was already used; reusing a
+/// remember-me token also revokes its session.
+pub async fn refresh(pool: &PgPool, refresh_token: &str) -> AppResult<SessionTokens> {
+    let token_hash = hash_token(refresh_token);
+    let next_token = random_token();
+    let now = Utc::now();
+    let session: Option<Session> = sqlx::query_as(&format!(
+        "WITH rotated AS (
+             UPDATE user_sessions
+             SET refresh_token_hash = $2, last_active = $3, expires_at = {}
+             WHERE refresh_token_hash = $1 AND expires_at > $3
+             RETURNING {}
+         ), retired AS (
+             INSERT INTO retired_refresh_tokens (token_hash, session_id)
+             SELECT $1, id FROM rotated WHERE remember_me
+         )
+         SELECT * FROM rotated",
+        EXTENDED_EXPIRY, SESSION_COLUMNS
+    ))
+    .bind(&token_hash)
+    .bind(hash_token(&next_token))
+    .bind(now)
+    .bind(now + idle_timeout())
+    .bind(now + remember_me_lifetime())
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    if let Some(session) = session {
+        return issue(&session, next_token);
+    }
+    if let Some(error) = revoke_on_reuse(pool, &token_hash).await? {
+        return Err(error);
+    }
+    Err(lapsed(pool, None, Some(&token_hash)).await)
+}
+
+/// Revokes the session that retired `token_hash`, if any, since the token
+/// has been presented twice.
+async fn revoke_on_reuse(pool: &PgPool, token_hash: &str) -> AppResult<Option<AppError>> {
+    let revoked: Option<(Uuid, Uuid)> = sqlx::query_as(
+        "DELETE FROM user_sessions
+         WHERE id = (SELECT session_id FROM retired_refresh_tokens WHERE token_hash = $1)
+         RETURNING id, user_id",
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let Some((session_id, user_id)) = revoked else {
+        return Ok(None);
+    };
+    tracing::warn!(
+        "Refresh token of session {} was reused; revoking the session",
+        session_id
+    );
+    audit::record_detached(
+        None,
+        audit::REFRESH_TOKEN_REUSED,
+        Some(user_id),
+        json!({ "sessionId": session_id }),
+    )
+    .await;
+    expired(session_id, user_id);
+    Ok(Some(AppError::new(
+        ErrorCode::Unauthorized,
+        "Refresh token was already used; the session has been revoked",
+    )))
+}
+
+/// Checks `access_token` and returns its session while it is still active.
+pub async fn validate(pool: &PgPool, access_token: &str) -> AppResult<Session> {
+    let claims = decode_access_token(access_token)?;
+    let session: Option<Session> = sqlx::query_as(&format!(
+        "SELECT {} FROM user_sessions WHERE id = $1 AND user_id = $2 AND expires_at > $3",
+        SESSION_COLUMNS
+    ))
+    .bind(claims.sid)
+    .bind(claims.sub)
+    .bind(Utc::now())
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    match session {
+        Some(session) => Ok(session),
+        None => Err(lapsed(pool, Some(claims.sid), None).await),
+    }
+}
+
+/// Extends the session of `access_token` by the idle timeout, or by the
+/// remember-me lifetime. Impersonation sessions only record the activity.
+pub async fn heartbeat(pool: &PgPool, access_token: &str) -> AppResult<Session> {
+    let claims = decode_access_token(access_token)?;
+    let now = Utc::now();
+    let session: Option<Session> = sqlx::query_as(&format!(
+        "UPDATE user_sessions
+         SET last_active = $3, expires_at = {}
+         WHERE id = $1 AND user_id = $2 AND expires_at > $3
+         RETURNING {}",
+        EXTENDED_EXPIRY, SESSION_COLUMNS
+    ))
+    .bind(claims.sid)
+    .bind(claims.sub)
+    .bind(now)
+    .bind(now + idle_timeout())
+    .bind(now + remember_me_lifetime())
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    match session {
+        Some(session) => Ok(session),
+        None => Err(lapsed(pool, Some(claims.sid), None).await),
+    }
+}
+
+/// Ends the session holding `refresh_token`. Returns whether one was active.
+pub async fn logout(pool: &PgPool, refresh_token: &str) -> AppResult<bool> {
+    let ended: Option<Uuid> =
+        sqlx::query_scalar("DELETE FROM user_sessions WHERE refresh_token_hash = $1 RETURNING id")
+            .bind(hash_token(refresh_token))
+            .fetch_optional(pool)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+    if let Some(session_id) = ended {
+        tracing::debug!("Ended session {}", session_id);
+    }
+    Ok(ended.is_some())
+}
+
+/// Revokes every session of `user_id`, returning how many were ended.
+pub async fn revoke_user(pool: &PgPool, user_id: Uuid) -> AppResult<u64> {
+    let result = sqlx::query("DELETE FROM user_sessions WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    Ok(result.rows_affected())
+}
+
+/// Deletes sessions that expired by `now`, returning their ids and users.
+pub(super) async fn reap(pool: &PgPool, now: DateTime<Utc>) -> AppResult<Vec<(Uuid, Uuid)>> {
+    sqlx::query_as("DELETE FROM user_sessions WHERE expires_at <= $1 RETURNING id, user_id")
+        .bind(now)
+        .fetch_all(pool)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)
+}
+
+/// Builds the error for a token whose session could not be used, deleting
+/// the session when it has merely expired.
+async fn lapsed(
+    pool: &PgPool,
+    session_id: Option<Uuid>,
+    refresh_token_hash: Option<&str>,
+) -> AppError {
+    let stale: Result<Option<(Uuid, Uuid)>, _> = sqlx::query_as(
+        "DELETE FROM user_sessions
+         WHERE (id = $1 OR refresh_token_hash = $2) AND expires_at <= $3
+         RETURNING id, user_id",
+    )
+    .bind(session_id)
+    .bind(refresh_token_hash)
+    .bind(Utc::now())
+    .fetch_optional(pool)
+    .await;
+
+    match stale {
+        Ok(Some((session_id, user_id))) => {
+            expired(session_id, user_id);
+            AppError::new(ErrorCode::TokenExpired, "Session has expired")
+        }
+        Ok(None) => AppError::new(ErrorCode::Unauthorized, "No active session"),
+        Err(e) => AppError::new(ErrorCode::DatabaseQuery, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::test_support::UserFactory;
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    #[tokio::test]
+    #[serial]
+    async fn refresh_rotates_tokens_until_logout() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+
+        let tokens = start(pool.as_ref(), user.id, false).await?;
+        let session = validate(pool.as_ref(), &tokens.access_token).await?;
+        assert_eq!(session.user_id, user.id);
+
+        let refreshed = refresh(pool.as_ref(), &tokens.refresh_token).await?;
+        assert_eq!(refreshed.session_id, tokens.session_id);
+        assert_ne!(refreshed.refresh_token, tokens.refresh_token);
+        let reused = refresh(pool.as_ref(), &tokens.refresh_token)
+            .await
+            .unwrap_err();
+        assert!(matches!(reused.code, ErrorCode::Unauthorized));
+
+        assert!(logout(pool.as_ref(), &refreshed.refresh_token).await?);
+        let revoked = validate(pool.as_ref(), &refreshed.access_token)
+            .await
+            .unwrap_err();
+        assert!(matches!(revoked.code, ErrorCode::Unauthorized));
+        assert!(!logout(pool.as_ref(), &refreshed.refresh_token).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn reusing_a_remember_me_token_revokes_the_session() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+
+        let tokens = start(pool.as_ref(), user.id, true).await?;
+        assert!(tokens.session_expires_at > Utc::now() + idle_timeout());
+        let session = heartbeat(pool.as_ref(), &tokens.access_token).await?;
+        assert!(session.remember_me);
+        assert!(session.expires_at >= tokens.session_expires_at);
+
+        let refreshed = refresh(pool.as_ref(), &tokens.refresh_token).await?;
+        let rotated = refresh(pool.as_ref(), &refreshed.refresh_token).await?;
+        let reused = refresh(pool.as_ref(), &tokens.refresh_token)
+            .await
+            .unwrap_err();
+        assert!(matches!(reused.code, ErrorCode::Unauthorized));
+
+        let revoked = refresh(pool.as_ref(), &rotated.refresh_token)
+            .await
+            .unwrap_err();
+        assert!(matches!(revoked.code, ErrorCode::Unauthorized));
+        assert!(validate(pool.as_ref(), &rotated.access_token)
+            .await
+            .is_err());
+        let retired: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM retired_refresh_tokens")
+            .fetch_one(pool.as_ref())
+            .await?;
+        assert_eq!(retired, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn lapsed_sessions_expire_and_are_reaped() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let user = UserFactory::new().insert().await?;
+        let stale = start(pool.as_ref(), user.id, false).await?;
+        let active = start(pool.as_ref(), user.id, false).await?;
+        sqlx::query("UPDATE user_sessions SET expires_at = $2 WHERE id = $1")
+            .bind(stale.session_id)
+            .bind(Utc::now() - ChronoDuration::minutes(1))
+            .execute(pool.as_ref())
+            .await?;
+
+        let error = heartbeat(pool.as_ref(), &stale.access_token)
+            .await
+            .unwrap_err();
+        assert!(matches!(error.code, ErrorCode::TokenExpired));
+        let session = heartbeat(pool.as_ref(), &active.access_token).await?;
+        assert!(session.expires_at > active.session_expires_at);
+
+        sqlx::query("UPDATE user_sessions SET expires_at = $1")
+            .bind(Utc::now() - ChronoDuration::minutes(1))
+            .execute(pool.as_ref())
+            .await?;
+        let reaped = reap(pool.as_ref(), Utc::now()).await?;
+        assert_eq!(reaped, vec![(active.session_id, user.id)]);
+        assert_eq!(revoke_user(pool.as_ref(), user.id).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn impersonation_sessions_are_not_extended() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        let admin = UserFactory::new().insert().await?;
+        let user = UserFactory::new().insert().await?;
+
+        let tokens = start_impersonation(pool.as_ref(), admin.id, user.id).await?;
+        let session = validate(pool.as_ref(), &tokens.access_token).await?;
+        assert_eq!(session.user_id, user.id);
+        assert_eq!(session.impersonator_id, Some(admin.id));
+        assert!(session.expires_at <= Utc::now() + ChronoDuration::minutes(IMPERSONATION_MINUTES));
+
+        let session = heartbeat(pool.as_ref(), &tokens.access_token).await?;
+        assert_eq!(session.expires_at, tokens.session_expires_at);
+        let refreshed = refresh(pool.as_ref(), &tokens.refresh_token).await?;
+        assert_eq!(refreshed.session_expires_at, tokens.session_expires_at);
+
+        let own = start(pool.as_ref(), user.id, false).await?;
+        assert_eq!(
+            end_impersonation(pool.as_ref(), own.session_id, None).await?,
+            None
+        );
+        let other_admin = Some(user.id);
+        assert_eq!(
+            end_impersonation(pool.as_ref(), tokens.session_id, other_admin).await?,
+            None
+        );
+        let ended = end_impersonation(pool.as_ref(), tokens.session_id, Some(admin.id)).await?;
+        assert_eq!(ended.map(|session| session.id), Some(tokens.session_id));
+        assert!(validate(pool.as_ref(), &refreshed.access_token)
+            .await
+            .is_err());
+        Ok(())
+    }
+}
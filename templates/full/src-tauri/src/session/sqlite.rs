@@ -0,0 +1,377 @@
+//! Session store in the `user_sessions` table of the embedded database.
+//!
+//! Issues the same tokens under the same rules as the PostgreSQL store, but
+//! there is no impersonation. A refresh rotates the token and retires the
+//! old one in a transaction, which SQLite runs one writer at a time.
+
+use super::{
+    decode_access_token, expired, hash_token, idle_timeout, issue, random_token,
+    remember_me_lifetime,
+};
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::local_db::timestamp;
+use crate::models::{Session, SessionTokens};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, SqlitePool};
+use uuid::Uuid;
+
+const SESSION_COLUMNS: &str = "id, user_id, created_at, last_active, expires_at, remember_me";
+
+/// New expiry for an activity at `?3`, given the idle (`?4`) and remember-me
+/// (`?5`) expiries.
+const EXTENDED_EXPIRY: &str = "CASE WHEN remember_me THEN ?5 ELSE ?4 END";
+
+/// A `user_sessions` row as stored, with the ids still text.
+#[derive(FromRow)]
+struct SessionRow {
+    id: String,
+    user_id: String,
+    created_at: DateTime<Utc>,
+    last_active: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    remember_me: bool,
+}
+
+impl TryFrom<SessionRow> for Session {
+    type Error = AppError;
+
+    fn try_from(row: SessionRow) -> AppResult<Self> {
+        Ok(Session {
+            id: parse_id(&row.id)?,
+            user_id: parse_id(&row.user_id)?,
+            created_at: row.created_at,
+            last_active: row.last_active,
+            expires_at: row.expires_at,
+            impersonator_id: None,
+            remember_me: row.remember_me,
+        })
+    }
+}
+
+/// Starts a session for `user_id` and issues its first tokens. With
+/// `remember_me` the session lasts the configured number of days.
+pub async fn start(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    remember_me: bool,
+) -> AppResult<SessionTokens> {
+    let refresh_token = random_token();
+    let now = Utc::now();
+    let expires_at = if remember_me {
+        now + remember_me_lifetime()
+    } else {
+        now + idle_timeout()
+    };
+    let row: SessionRow = sqlx::query_as(&format!(
+        "INSERT INTO user_sessions (id, user_id, refresh_token_hash, created_at, last_active,
+                                    expires_at, remember_me)
+         VALUES (?1, ?2, ?3, ?4, ?4, ?5, ?6)
+         RETURNING {}",
+        SESSION_COLUMNS
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(user_id.to_string())
+    .bind(hash_token(&refresh_token))
+    .bind(timestamp(now))
+    .bind(timestamp(expires_at))
+    .bind(remember_me)
+    .fetch_one(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let session = Session::try_from(row)?;
+    tracing::debug!("Started session {} for user {}", session.id, user_id);
+    issue(&session, refresh_token)
+}
+
+/// Exchanges `refresh_token` for new tokens, replacing the refresh token and
+/// extending the session. A remember-me session keeps the replaced token to
+/// detect its reuse.
+///
+/// Fails with `TOKEN_EXPIRED` when the session has lapsed and
+/// `UNAUTHORIZED` when the token is unknown or was already used; reusing a
+/// remember-me token also revokes its session.
+pub async fn refresh(pool: &SqlitePool, refresh_token: &str) -> AppResult<SessionTokens> {
+    let token_hash = hash_token(refresh_token);
+    let next_token = random_token();
+    let now = Utc::now();
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    let row: Option<SessionRow> = sqlx::query_as(&format!(
+        "UPDATE user_sessions
+         SET refresh_token_hash = ?2, last_active = ?3, expires_at = {}
+         WHERE refresh_token_hash = ?1 AND expires_at > ?3
+         RETURNING {}",
+        EXTENDED_EXPIRY, SESSION_COLUMNS
+    ))
+    .bind(&token_hash)
+    .bind(hash_token(&next_token))
+    .bind(timestamp(now))
+    .bind(timestamp(now + idle_timeout()))
+    .bind(timestamp(now + remember_me_lifetime()))
+    .fetch_optional(&mut *tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    if let Some(row) = row {
+        let session = Session::try_from(row)?;
+        if session.remember_me {
+            sqlx::query(
+                "INSERT INTO retired_refresh_tokens (token_hash, session_id) VALUES (?1, ?2)",
+            )
+            .bind(&token_hash)
+            .bind(session.id.to_string())
+            .execute(&mut *tx)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+        }
+        tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+        return issue(&session, next_token);
+    }
+    drop(tx);
+
+    if let Some(error) = revoke_on_reuse(pool, &token_hash).await? {
+        return Err(error);
+    }
+    Err(lapsed(pool, None, Some(&token_hash)).await)
+}
+
+/// Revokes the session that retired `token_hash`, if any, since the token
+/// has been presented twice.
+async fn revoke_on_reuse(pool: &SqlitePool, token_hash: &str) -> AppResult<Option<AppError>> {
+    let revoked: Option<(String, String)> = sqlx::query_as(
+        "DELETE FROM user_sessions
+         WHERE id = (SELECT session_id FROM retired_refresh_tokens WHERE token_hash = ?1)
+         RETURNING id, user_id",
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let Some((session_id, user_id)) = revoked else {
+        return Ok(None);
+    };
+    let (session_id, user_id) = (parse_id(&session_id)?, parse_id(&user_id)?);
+    tracing::warn!(
+        "Refresh token of session {} was reused; revoking the session",
+        session_id
+    );
+    expired(session_id, user_id);
+    Ok(Some(AppError::new(
+        ErrorCode::Unauthorized,
+        "Refresh token was already used; the session has been revoked",
+    )))
+}
+
+/// Checks `access_token` and returns its session while it is still active.
+pub async fn validate(pool: &SqlitePool, access_token: &str) -> AppResult<Session> {
+    let claims = decode_access_token(access_token)?;
+    let row: Option<SessionRow> = sqlx::query_as(&format!(
+        "SELECT {} FROM user_sessions WHERE id = ?1 AND user_id = ?2 AND expires_at > ?3",
+        SESSION_COLUMNS
+    ))
+    .bind(claims.sid.to_string())
+    .bind(claims.sub.to_string())
+    .bind(timestamp(Utc::now()))
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    match row {
+        Some(row) => Session::try_from(row),
+        None => Err(lapsed(pool, Some(claims.sid), None).await),
+    }
+}
+
+/// Extends the session of `access_token` by the idle timeout, or by the
+/// remember-me lifetime.
+pub async fn heartbeat(pool: &SqlitePool, access_token: &str) -> AppResult<Session> {
+    let claims = decode_access_token(access_token)?;
+    let now = Utc::now();
+    let row: Option<SessionRow> = sqlx::query_as(&format!(
+        "UPDATE user_sessions
+         SET last_active = ?3, expires_at = {}
+         WHERE id = ?1 AND user_id = ?2 AND expires_at > ?3
+         RETURNING {}",
+        EXTENDED_EXPIRY, SESSION_COLUMNS
+    ))
+    .bind(claims.sid.to_string())
+    .bind(claims.sub.to_string())
+    .bind(timestamp(now))
+    .bind(timestamp(now + idle_timeout()))
+    .bind(timestamp(now + remember_me_lifetime()))
+    .fetch_optional(pool)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    match row {
+        Some(row) => Session::try_from(row),
+        None => Err(lapsed(pool, Some(claims.sid), None).await),
+    }
+}
+
+/// Ends the session holding `refresh_token`. Returns whether one was active.
+pub async fn logout(pool: &SqlitePool, refresh_token: &str) -> AppResult<bool> {
+    let ended: Option<String> =
+        sqlx::query_scalar("DELETE FROM user_sessions WHERE refresh_token_hash = ?1 RETURNING id")
+            .bind(hash_token(refresh_token))
+            .fetch_optional(pool)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+    if let Some(session_id) = &ended {
+        tracing::debug!("Ended session {}", session_id);
+    }
+    Ok(ended.is_some())
+}
+
+/// Revokes every session of `user_id`, returning how many were ended.
+pub async fn revoke_user(pool: &SqlitePool, user_id: Uuid) -> AppResult<u64> {
+    let result = sqlx::query("DELETE FROM user_sessions WHERE user_id = ?1")
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    Ok(result.rows_affected())
+}
+
+/// Deletes sessions that expired by `now`, returning their ids and users.
+pub(super) async fn reap(pool: &SqlitePool, now: DateTime<Utc>) -> AppResult<Vec<(Uuid, Uuid)>> {
+    let stale: Vec<(String, String)> =
+        sqlx::query_as("DELETE FROM user_sessions WHERE expires_at <= ?1 RETURNING id, user_id")
+            .bind(timestamp(now))
+            .fetch_all(pool)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+    stale
+        .iter()
+        .map(|(session_id, user_id)| Ok((parse_id(session_id)?, parse_id(user_id)?)))
+        .collect()
+}
+
+/// Builds the error for a token whose session could not be used, deleting
+/// the session when it has merely expired.
+async fn lapsed(
+    pool: &SqlitePool,
+    session_id: Option<Uuid>,
+    refresh_token_hash: Option<&str>,
+) -> AppError {
+    let stale: Result<Option<(String, String)>, _> = sqlx::query_as(
+        "DELETE FROM user_sessions
+         WHERE (id = ?1 OR refresh_token_hash = ?2) AND expires_at <= ?3
+         RETURNING id, user_id",
+    )
+    .bind(session_id.map(|id| id.to_string()))
+    .bind(refresh_token_hash)
+    .bind(timestamp(Utc::now()))
+    .fetch_optional(pool)
+    .await;
+
+    match stale {
+        Ok(Some((session_id, user_id))) => match (parse_id(&session_id), parse_id(&user_id)) {
+            (Ok(session_id), Ok(user_id)) => {
+                expired(session_id, user_id);
+                AppError::new(ErrorCode::TokenExpired, "Session has expired")
+            }
+            (Err(e), _) | (_, Err(e)) => e,
+        },
+        Ok(None) => AppError::new(ErrorCode::Unauthorized, "No active session"),
+        Err(e) => AppError::new(ErrorCode::DatabaseQuery, e.to_string()),
+    }
+}
+
+fn parse_id(id: &str) -> AppResult<Uuid> {
+    Uuid::parse_str(id).into_app_error(ErrorCode::DatabaseQuery)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::local_db::{migrations::run_migrations, open};
+    use chrono::Duration as ChronoDuration;
+
+    async fn pool_with_user(dir: &tempfile::TempDir) -> (SqlitePool, Uuid) {
+        let pool = open(&dir.path().join("app.db")).await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        let user_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO users (id, email, username, password_hash)
+             VALUES (?1, 'ada@example.com', 'ada', 'hash')",
+        )
+        .bind(user_id.to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+        (pool, user_id)
+    }
+
+    #[tokio::test]
+    async fn refresh_rotates_tokens_until_logout() {
+        let dir = tempfile::tempdir().unwrap();
+        let (pool, user_id) = pool_with_user(&dir).await;
+
+        let tokens = start(&pool, user_id, false).await.unwrap();
+        let session = validate(&pool, &tokens.access_token).await.unwrap();
+        assert_eq!(session.user_id, user_id);
+        assert_eq!(session.id, tokens.session_id);
+
+        let refreshed = refresh(&pool, &tokens.refresh_token).await.unwrap();
+        assert_eq!(refreshed.session_id, tokens.session_id);
+        let reused = refresh(&pool, &tokens.refresh_token).await.unwrap_err();
+        assert!(matches!(reused.code, ErrorCode::Unauthorized));
+
+        assert!(logout(&pool, &refreshed.refresh_token).await.unwrap());
+        let revoked = validate(&pool, &refreshed.access_token).await.unwrap_err();
+        assert!(matches!(revoked.code, ErrorCode::Unauthorized));
+        assert!(!logout(&pool, &refreshed.refresh_token).await.unwrap());
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn reusing_a_remember_me_token_revokes_the_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let (pool, user_id) = pool_with_user(&dir).await;
+
+        let tokens = start(&pool, user_id, true).await.unwrap();
+        let session = heartbeat(&pool, &tokens.access_token).await.unwrap();
+        assert!(session.remember_me);
+
+        let refreshed = refresh(&pool, &tokens.refresh_token).await.unwrap();
+        let reused = refresh(&pool, &tokens.refresh_token).await.unwrap_err();
+        assert!(matches!(reused.code, ErrorCode::Unauthorized));
+        assert!(validate(&pool, &refreshed.access_token).await.is_err());
+
+        let (retired,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM retired_refresh_tokens")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(retired, 0);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn lapsed_sessions_expire_and_are_reaped() {
+        let dir = tempfile::tempdir().unwrap();
+        let (pool, user_id) = pool_with_user(&dir).await;
+        let stale = start(&pool, user_id, false).await.unwrap();
+        let active = start(&pool, user_id, false).await.unwrap();
+        sqlx::query("UPDATE user_sessions SET expires_at = ?2 WHERE id = ?1")
+            .bind(stale.session_id.to_string())
+            .bind(timestamp(Utc::now() - ChronoDuration::minutes(1)))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let error = heartbeat(&pool, &stale.access_token).await.unwrap_err();
+        assert!(matches!(error.code, ErrorCode::TokenExpired));
+
+        let reaped = reap(&pool, Utc::now() + ChronoDuration::days(365))
+            .await
+            .unwrap();
+        assert_eq!(reaped, vec![(active.session_id, user_id)]);
+        assert_eq!(revoke_user(&pool, user_id).await.unwrap(), 0);
+        pool.close().await;
+    }
+}
@@ -0,0 +1,277 @@
+//! First-run onboarding state machine.
+//!
+//! Onboarding steps must be completed in order and are persisted under the
+//! application data directory, so a restart resumes where the user left off.
+//! Steps that depend on backend state (database reachable, admin user present)
+//! are verified before being marked complete against the backend
+//! `DATABASE_URL` selects. Without a database cargo feature those steps have
+//! nothing to verify and always pass.
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+use crate::config::{self, DatabaseBackend};
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::events::{self, AppEvent};
+use crate::logging;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Onboarding steps in the order they must be completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SetupStep {
+    VaultPassword,
+    DatabaseConfig,
+    AdminUser,
+}
+
+impl SetupStep {
+    /// Every step, in completion order.
+    pub const ALL: [SetupStep; 3] = [
+        SetupStep::VaultPassword,
+        SetupStep::DatabaseConfig,
+        SetupStep::AdminUser,
+    ];
+}
+
+/// Persisted onboarding progress.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupState {
+    pub completed: BTreeMap<SetupStep, DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl SetupState {
+    /// Returns the first step that has not been completed yet.
+    pub fn current_step(&self) -> Option<SetupStep> {
+        SetupStep::ALL
+            .into_iter()
+            .find(|step| !self.completed.contains_key(step))
+    }
+}
+
+/// Progress of a single step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupStepStatus {
+    pub step: SetupStep,
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Onboarding summary returned to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupStatus {
+    pub complete: bool,
+    pub current_step: Option<SetupStep>,
+    pub steps: Vec<SetupStepStatus>,
+    pub database_connected: bool,
+}
+
+fn setup_dir() -> PathBuf {
+    logging::app_data_dir("setup")
+}
+
+fn state_path() -> PathBuf {
+    setup_dir().join("state.json")
+}
+
+/// Loads persisted onboarding progress, defaulting to a fresh install.
+pub fn load_state() -> AppResult<SetupState> {
+    let path = state_path();
+    if !path.exists() {
+        return Ok(SetupState::default());
+    }
+
+    let content = fs::read_to_string(&path).into_app_error(ErrorCode::FileRead)?;
+    serde_json::from_str(&content).into_app_error(ErrorCode::InvalidFormat)
+}
+
+fn save_state(state: &SetupState) -> AppResult<()> {
+    fs::create_dir_all(setup_dir()).into_app_error(ErrorCode::DirectoryCreate)?;
+    let content = serde_json::to_string_pretty(state).into_app_error(ErrorCode::InternalError)?;
+    fs::write(state_path(), content).into_app_error(ErrorCode::FileWrite)
+}
+
+/// Returns onboarding progress along with live backend checks.
+pub async fn status() -> AppResult<SetupStatus> {
+    let state = load_state()?;
+    Ok(build_status(&state, database_connected().await))
+}
+
+/// Marks `step` complete after verifying its prerequisites.
+pub async fn complete_step(step: SetupStep) -> AppResult<SetupStatus> {
+    let mut state = load_state()?;
+
+    if state.completed.contains_key(&step) {
+        return Ok(build_status(&state, database_connected().await));
+    }
+
+    if let Some(current) = state.current_step() {
+        if current != step {
+            return Err(AppError::validation_error(format!(
+                "Setup step {:?} must be completed before {:?}",
+                current, step
+            )));
+        }
+    }
+
+    verify_step(step).await?;
+
+    let now = Utc::now();
+    state.completed.insert(step, now);
+    if state.current_step().is_none() {
+        state.completed_at = Some(now);
+    }
+    save_state(&state)?;
+
+    tracing::info!("Setup step {:?} completed", step);
+    events::publish(AppEvent::SetupStepCompleted {
+        step,
+        setup_complete: state.completed_at.is_some(),
+    });
+
+    Ok(build_status(&state, database_connected().await))
+}
+
+/// Checks backend state required before a step may be marked complete.
+async fn verify_step(step: SetupStep) -> AppResult<()> {
+    match step {
+        // The vault is created by the frontend through the Stronghold plugin.
+        SetupStep::VaultPassword => Ok(()),
+        #[cfg(not(any(feature = "database", feature = "sqlite")))]
+        SetupStep::DatabaseConfig | SetupStep::AdminUser => Ok(()),
+        #[cfg(any(feature = "database", feature = "sqlite"))]
+        SetupStep::DatabaseConfig => {
+            if database_connected().await {
+                Ok(())
+            } else {
+                Err(AppError::new(
+                    ErrorCode::DatabaseConnection,
+                    "Database is not reachable. Check DATABASE_URL and try again.",
+                ))
+            }
+        }
+        #[cfg(any(feature = "database", feature = "sqlite"))]
+        SetupStep::AdminUser => {
+            if active_users().await? > 0 {
+                Ok(())
+            } else {
+                Err(AppError::validation_error("Create an administrator account first"))
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "database", feature = "sqlite"))]
+async fn active_users() -> AppResult<i64> {
+    const QUERY: &str = "SELECT COUNT(*) FROM users WHERE is_active = true";
+    match config::current().database_backend {
+        #[cfg(feature = "database")]
+        DatabaseBackend::Postgres => {
+            let pool =
+                crate::database::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+            sqlx::query_scalar(QUERY)
+                .fetch_one(pool.as_ref())
+                .await
+                .into_app_error(ErrorCode::DatabaseQuery)
+        }
+        #[cfg(feature = "sqlite")]
+        DatabaseBackend::Sqlite => {
+            let pool =
+                crate::local_db::get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+            sqlx::query_scalar(QUERY)
+                .fetch_one(pool.as_ref())
+                .await
+                .into_app_error(ErrorCode::DatabaseQuery)
+        }
+        #[allow(unreachable_patterns)]
+        backend => Err(AppError::new(
+            ErrorCode::DatabaseConnection,
+            format!("{} support is not compiled in", backend.name()),
+        )),
+    }
+}
+
+/// Whether the database `DATABASE_URL` selects is reachable.
+#[cfg(any(feature = "database", feature = "sqlite"))]
+pub(crate) async fn database_connected() -> bool {
+    match config::current().database_backend {
+        #[cfg(feature = "database")]
+        DatabaseBackend::Postgres => match crate::database::get_pool_ref() {
+            Ok(pool) => crate::database::test_connection(pool.as_ref())
+                .await
+                .unwrap_or(false),
+            Err(_) => false,
+        },
+        #[cfg(feature = "sqlite")]
+        DatabaseBackend::Sqlite => crate::local_db::get_pool().is_some(),
+        #[allow(unreachable_patterns)]
+        _ => false,
+    }
+}
+
+#[cfg(not(any(feature = "database", feature = "sqlite")))]
+pub(crate) async fn database_connected() -> bool {
+    false
+}
+
+fn build_status(state: &SetupState, database_connected: bool) -> SetupStatus {
+    SetupStatus {
+        complete: state.current_step().is_none(),
+        current_step: state.current_step(),
+        steps: SetupStep::ALL
+            .into_iter()
+            .map(|step| SetupStepStatus {
+                step,
+                completed: state.completed.contains_key(&step),
+                completed_at: state.completed.get(&step).copied(),
+            })
+            .collect(),
+        database_connected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_step_follows_declared_order() {
+        let mut state = SetupState::default();
+        assert_eq!(state.current_step(), Some(SetupStep::VaultPassword));
+
+        state.completed.insert(SetupStep::VaultPassword, Utc::now());
+        assert_eq!(state.current_step(), Some(SetupStep::DatabaseConfig));
+
+        state.completed.insert(SetupStep::DatabaseConfig, Utc::now());
+        state.completed.insert(SetupStep::AdminUser, Utc::now());
+        assert_eq!(state.current_step(), None);
+    }
+
+    #[test]
+    fn status_reports_every_step() {
+        let mut state = SetupState::default();
+        state.completed.insert(SetupStep::VaultPassword, Utc::now());
+
+        let status = build_status(&state, false);
+        assert!(!status.complete);
+        assert_eq!(status.steps.len(), 3);
+        assert!(status.steps[0].completed);
+        assert_eq!(status.current_step, Some(SetupStep::DatabaseConfig));
+    }
+
+    #[test]
+    fn state_round_trips_through_json() {
+        let mut state = SetupState::default();
+        state.completed.insert(SetupStep::DatabaseConfig, Utc::now());
+
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: SetupState = serde_json::from_str(&json).unwrap();
+        assert!(restored.completed.contains_key(&SetupStep::DatabaseConfig));
+    }
+}
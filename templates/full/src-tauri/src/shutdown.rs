@@ -0,0 +1,111 @@
+//! Graceful shutdown sequence run when the application exits.
+//!
+//! Background loops register their task handles with [`track`]. On exit the
+//! email queue is drained, tracked tasks are stopped, the mDNS announcement
+//! is withdrawn, a snapshot of rate
+//! limiter and cache counters is persisted to the state store, buffered
+//! database log entries are written, the database pool is closed, and
+//! buffered log lines are flushed.
+
+use crate::cache;
+#[cfg(feature = "database")]
+use crate::database;
+#[cfg(feature = "sqlite")]
+use crate::local_db;
+use crate::discovery;
+use crate::email;
+use crate::logging;
+use crate::rate_limiter::RateLimiterConfig;
+use crate::state_store;
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+
+/// Maximum time spent waiting for queued emails to be delivered.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// State store key holding the last shutdown snapshot.
+const SNAPSHOT_KEY: &str = "shutdown.snapshot";
+
+/// Background tasks stopped on shutdown.
+static TASKS: Lazy<Mutex<Vec<(&'static str, JoinHandle<()>)>>> =
+    Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Set once shutdown has started.
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Registers a background task to be stopped on shutdown.
+pub fn track(name: &'static str, handle: JoinHandle<()>) {
+    if let Ok(mut tasks) = TASKS.lock() {
+        tasks.push((name, handle));
+    }
+}
+
+/// Returns whether shutdown has started.
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+/// Runs the shutdown sequence once; later calls return immediately.
+pub async fn run(rate_limiter: Option<Arc<RateLimiterConfig>>) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tracing::info!("Shutting down");
+
+    let pending = email::drain(DRAIN_TIMEOUT).await;
+    if pending > 0 {
+        tracing::warn!("Abandoning {} undelivered emails", pending);
+    }
+
+    let tasks = TASKS
+        .lock()
+        .map(|mut tasks| std::mem::take(&mut *tasks))
+        .unwrap_or_default();
+    for (name, handle) in tasks {
+        handle.abort();
+        tracing::debug!("Stopped background task {}", name);
+    }
+
+    if let Err(e) = discovery::stop() {
+        tracing::warn!("Failed to stop discovery: {}", e);
+    }
+
+    persist_snapshot(rate_limiter.as_deref());
+
+    #[cfg(feature = "database")]
+    {
+        match logging::db_sink::flush().await {
+            Ok(0) => {}
+            Ok(count) => tracing::debug!("Flushed {} buffered log entries", count),
+            Err(e) => tracing::warn!("Failed to flush buffered log entries: {}", e),
+        }
+
+        database::connection::close_pool().await;
+    }
+    #[cfg(feature = "sqlite")]
+    local_db::close_pool().await;
+    tracing::info!("Shutdown complete");
+    logging::flush();
+}
+
+/// Records counters that would otherwise be lost with the process.
+///
+/// Governor quotas are in-memory and refill within a minute, so only the
+/// number of tracked users is kept alongside the cache counters.
+fn persist_snapshot(rate_limiter: Option<&RateLimiterConfig>) {
+    let stats = cache::cache_stats();
+    let snapshot = serde_json::json!({
+        "at": chrono::Utc::now(),
+        "rateLimiter": {
+            "trackedUsers": rate_limiter.map(RateLimiterConfig::tracked_users),
+        },
+        "cache": stats,
+    });
+
+    if let Err(e) = state_store::set(SNAPSHOT_KEY, snapshot) {
+        tracing::warn!("Failed to persist shutdown snapshot: {}", e);
+    }
+}
@@ -0,0 +1,478 @@
+//! Startup work run in the background once the window is up.
+//!
+//! `setup()` describes each subsystem it brings up as a [`StartupTask`]: a
+//! name, the tasks it depends on, a timeout, and a retry policy. [`run`]
+//! starts every task as soon as its dependencies have succeeded and skips
+//! tasks whose dependencies failed, so a database outage skips migrations
+//! instead of running them against a missing pool. The outcome of each task
+//! is kept for `get_startup_status`, letting the frontend show which
+//! subsystem failed to initialize, and every change is published as a
+//! `startup:task` event.
+
+use crate::events::{self, AppEvent};
+use crate::shutdown;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+/// Time an attempt may take unless the task sets its own.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Status of the tasks passed to the last [`run`].
+static BOARD: Lazy<Arc<Board>> = Lazy::new(|| Arc::new(Board::default()));
+
+type TaskFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+/// One subsystem brought up at startup.
+pub struct StartupTask {
+    name: &'static str,
+    depends_on: Vec<&'static str>,
+    timeout: Duration,
+    retry: RetryPolicy,
+    run: Box<dyn Fn() -> TaskFuture + Send + Sync>,
+    on_failure: Option<Box<dyn Fn(&str) + Send + Sync>>,
+}
+
+/// How often a failed task is tried again.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first.
+    pub attempts: u32,
+    /// Wait before the second attempt, doubled before each further one.
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+impl StartupTask {
+    /// Creates a task that runs `run` once, with no dependencies and the
+    /// default timeout.
+    pub fn new<F, Fut>(name: &'static str, run: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        Self {
+            name,
+            depends_on: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            retry: RetryPolicy::default(),
+            run: Box::new(move || Box::pin(run())),
+            on_failure: None,
+        }
+    }
+
+    /// Starts the task only after `dependency` has succeeded.
+    pub fn after(mut self, dependency: &'static str) -> Self {
+        self.depends_on.push(dependency);
+        self
+    }
+
+    /// Fails an attempt that has not finished within `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Makes up to `attempts` attempts, waiting `delay` after the first
+    /// failure and twice as long after each further one.
+    pub fn retry(mut self, attempts: u32, delay: Duration) -> Self {
+        self.retry = RetryPolicy {
+            attempts: attempts.max(1),
+            delay,
+        };
+        self
+    }
+
+    /// Calls `on_failure` with the error once the last attempt has failed.
+    pub fn on_failure<F>(mut self, on_failure: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_failure = Some(Box::new(on_failure));
+        self
+    }
+
+    /// Runs one attempt on its own task, so a panic or timeout fails the
+    /// attempt instead of the runner.
+    async fn attempt(&self) -> Result<(), String> {
+        let mut handle = tokio::spawn((self.run)());
+        match tokio::time::timeout(self.timeout, &mut handle).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) if e.is_panic() => Err("task panicked".to_string()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => {
+                handle.abort();
+                Err(format!("timed out after {}s", self.timeout.as_secs_f32()))
+            }
+        }
+    }
+}
+
+/// Where a task is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    /// Not run because a dependency did not succeed.
+    Skipped,
+}
+
+impl TaskState {
+    fn is_finished(self) -> bool {
+        matches!(
+            self,
+            TaskState::Succeeded | TaskState::Failed | TaskState::Skipped
+        )
+    }
+}
+
+/// Payload of the `startup:task` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupTaskStatus {
+    pub name: String,
+    pub state: TaskState,
+    pub depends_on: Vec<String>,
+    /// Attempts started so far.
+    pub attempts: u32,
+    /// Error of the last failed attempt, or why the task was skipped.
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Status of all startup tasks, in the order `setup()` declared them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupStatus {
+    /// Whether every task has succeeded, failed, or been skipped.
+    pub complete: bool,
+    pub tasks: Vec<StartupTaskStatus>,
+}
+
+#[derive(Default)]
+struct Board {
+    tasks: Mutex<Vec<StartupTaskStatus>>,
+}
+
+impl Board {
+    fn reset(&self, tasks: &[StartupTask]) {
+        *self.tasks.lock().unwrap_or_else(PoisonError::into_inner) = tasks
+            .iter()
+            .map(|task| StartupTaskStatus {
+                name: task.name.to_string(),
+                state: TaskState::Pending,
+                depends_on: task.depends_on.iter().map(|dep| dep.to_string()).collect(),
+                attempts: 0,
+                error: None,
+                started_at: None,
+                finished_at: None,
+            })
+            .collect();
+    }
+
+    /// Applies `change` to the status of `name` and publishes the result.
+    fn update(&self, name: &str, change: impl FnOnce(&mut StartupTaskStatus)) {
+        let status = {
+            let mut tasks = self.tasks.lock().unwrap_or_else(PoisonError::into_inner);
+            let Some(status) = tasks.iter_mut().find(|status| status.name == name) else {
+                return;
+            };
+            change(status);
+            status.clone()
+        };
+        events::publish(AppEvent::StartupTask(status));
+    }
+
+    fn finish(&self, name: &str, state: TaskState, error: Option<String>) {
+        self.update(name, |status| {
+            status.state = state;
+            status.error = error;
+            status.finished_at = Some(Utc::now());
+        });
+    }
+
+    fn status(&self) -> StartupStatus {
+        let tasks = self
+            .tasks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        StartupStatus {
+            complete: tasks.iter().all(|task| task.state.is_finished()),
+            tasks,
+        }
+    }
+}
+
+/// Runs `tasks` in the background, each as soon as its dependencies have
+/// succeeded.
+pub fn run(tasks: Vec<StartupTask>) {
+    BOARD.reset(&tasks);
+    let board = BOARD.clone();
+    let handle = tauri::async_runtime::spawn(execute(board, tasks));
+    shutdown::track("startup", handle);
+}
+
+/// The status of the tasks started by [`run`].
+pub fn status() -> StartupStatus {
+    BOARD.status()
+}
+
+async fn execute(board: Arc<Board>, tasks: Vec<StartupTask>) {
+    let cyclic = cyclic_tasks(&tasks);
+    let mut senders = HashMap::new();
+    let mut outcomes = HashMap::new();
+    for task in &tasks {
+        let (sender, receiver) = watch::channel(None::<bool>);
+        senders.insert(task.name, sender);
+        outcomes.insert(task.name, receiver);
+    }
+
+    let mut running = JoinSet::new();
+    for task in tasks {
+        let Some(sender) = senders.remove(task.name) else {
+            tracing::warn!("Ignoring duplicate startup task '{}'", task.name);
+            continue;
+        };
+        let dependencies: Vec<_> = task
+            .depends_on
+            .iter()
+            .map(|dep| (*dep, outcomes.get(dep).cloned()))
+            .collect();
+        let cyclic = cyclic.contains(task.name);
+        let board = board.clone();
+        running.spawn(async move {
+            let succeeded = if cyclic {
+                board.finish(
+                    task.name,
+                    TaskState::Skipped,
+                    Some("dependency cycle".to_string()),
+                );
+                false
+            } else {
+                run_task(&board, &task, dependencies).await
+            };
+            let _ = sender.send(Some(succeeded));
+        });
+    }
+    while running.join_next().await.is_some() {}
+}
+
+/// Waits for the dependencies of `task`, then runs it. Returns whether it
+/// succeeded.
+async fn run_task(
+    board: &Board,
+    task: &StartupTask,
+    dependencies: Vec<(&'static str, Option<watch::Receiver<Option<bool>>>)>,
+) -> bool {
+    for (dependency, outcome) in dependencies {
+        let succeeded = match outcome {
+            Some(mut outcome) => outcome
+                .wait_for(Option::is_some)
+                .await
+                .map(|outcome| *outcome == Some(true))
+                .unwrap_or(false),
+            None => false,
+        };
+        if !succeeded {
+            tracing::warn!(
+                "Skipping startup task '{}': '{}' did not succeed",
+                task.name,
+                dependency
+            );
+            board.finish(
+                task.name,
+                TaskState::Skipped,
+                Some(format!("dependency '{}' did not succeed", dependency)),
+            );
+            return false;
+        }
+    }
+
+    board.update(task.name, |status| {
+        status.state = TaskState::Running;
+        status.started_at = Some(Utc::now());
+    });
+
+    let mut delay = task.retry.delay;
+    for attempt in 1..=task.retry.attempts {
+        board.update(task.name, |status| status.attempts = attempt);
+        match task.attempt().await {
+            Ok(()) => {
+                tracing::debug!("Startup task '{}' succeeded", task.name);
+                board.finish(task.name, TaskState::Succeeded, None);
+                return true;
+            }
+            Err(e) if attempt < task.retry.attempts => {
+                tracing::warn!(
+                    "Startup task '{}' failed (attempt {}/{}), retrying in {:?}: {}",
+                    task.name,
+                    attempt,
+                    task.retry.attempts,
+                    delay,
+                    e
+                );
+                board.update(task.name, |status| status.error = Some(e));
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => {
+                tracing::error!("Startup task '{}' failed: {}", task.name, e);
+                if let Some(on_failure) = &task.on_failure {
+                    on_failure(&e);
+                }
+                board.finish(task.name, TaskState::Failed, Some(e));
+                return false;
+            }
+        }
+    }
+    false
+}
+
+/// Names of tasks that depend on themselves, directly or through others.
+fn cyclic_tasks(tasks: &[StartupTask]) -> HashSet<&'static str> {
+    let graph: HashMap<&str, &[&'static str]> = tasks
+        .iter()
+        .map(|task| (task.name, task.depends_on.as_slice()))
+        .collect();
+    tasks
+        .iter()
+        .map(|task| task.name)
+        .filter(|&name| {
+            let mut stack = graph[name].to_vec();
+            let mut seen = HashSet::new();
+            while let Some(next) = stack.pop() {
+                if next == name {
+                    return true;
+                }
+                if seen.insert(next) {
+                    stack.extend(graph.get(next).copied().unwrap_or_default());
+                }
+            }
+            false
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn panics() -> Result<(), String> {
+        panic!("boom")
+    }
+
+    async fn execute_all(tasks: Vec<StartupTask>) -> HashMap<String, StartupTaskStatus> {
+        let board = Arc::new(Board::default());
+        board.reset(&tasks);
+        execute(board.clone(), tasks).await;
+        let status = board.status();
+        assert!(status.complete);
+        status
+            .tasks
+            .into_iter()
+            .map(|task| (task.name.clone(), task))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn skips_tasks_whose_dependencies_failed() {
+        let tasks = execute_all(vec![
+            StartupTask::new("migrations", || async { Ok(()) }).after("database"),
+            StartupTask::new("database", || async { Err("refused".to_string()) }),
+            StartupTask::new("cache", || async { Ok(()) }),
+            StartupTask::new("indexer", || async { Ok(()) }).after("missing"),
+        ])
+        .await;
+
+        assert_eq!(tasks["database"].state, TaskState::Failed);
+        assert_eq!(tasks["database"].error.as_deref(), Some("refused"));
+        assert_eq!(tasks["migrations"].state, TaskState::Skipped);
+        assert_eq!(tasks["migrations"].attempts, 0);
+        assert_eq!(tasks["cache"].state, TaskState::Succeeded);
+        assert_eq!(tasks["indexer"].state, TaskState::Skipped);
+    }
+
+    #[tokio::test]
+    async fn retries_until_an_attempt_succeeds() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let task = StartupTask::new("database", move || {
+            let attempt = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                if attempt < 3 {
+                    Err(format!("attempt {} refused", attempt))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .retry(3, Duration::from_millis(1));
+
+        let tasks = execute_all(vec![task]).await;
+        assert_eq!(tasks["database"].state, TaskState::Succeeded);
+        assert_eq!(tasks["database"].attempts, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn fails_attempts_that_time_out_or_panic() {
+        let failures = Arc::new(AtomicU32::new(0));
+        let counter = failures.clone();
+        let tasks = execute_all(vec![
+            StartupTask::new("slow", || async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(())
+            })
+            .timeout(Duration::from_millis(10))
+            .on_failure(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }),
+            StartupTask::new("broken", panics),
+        ])
+        .await;
+
+        assert_eq!(tasks["slow"].state, TaskState::Failed);
+        assert!(tasks["slow"]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("timed out"));
+        assert_eq!(failures.load(Ordering::SeqCst), 1);
+        assert_eq!(tasks["broken"].state, TaskState::Failed);
+    }
+
+    #[tokio::test]
+    async fn skips_dependency_cycles() {
+        let tasks = execute_all(vec![
+            StartupTask::new("a", || async { Ok(()) }).after("b"),
+            StartupTask::new("b", || async { Ok(()) }).after("a"),
+            StartupTask::new("c", || async { Ok(()) }).after("a"),
+        ])
+        .await;
+
+        assert_eq!(tasks["a"].state, TaskState::Skipped);
+        assert_eq!(tasks["b"].state, TaskState::Skipped);
+        assert_eq!(tasks["c"].state, TaskState::Skipped);
+    }
+}
@@ -0,0 +1,134 @@
+//! Persistent key-value store for local UI state.
+//!
+//! Backed by an embedded sled database under the application data directory,
+//! independent of PostgreSQL and Redis. Values are stored as JSON. Watched
+//! key prefixes publish `state:changed` events whenever a matching key is
+//! written or removed.
+
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::events::{self, AppEvent};
+use crate::logging;
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Maximum key length in bytes.
+const MAX_KEY_LEN: usize = 256;
+
+/// Lazily opened store database.
+static STORE: OnceCell<sled::Db> = OnceCell::new();
+
+/// Prefixes that already have a watcher task.
+static WATCHED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+fn store_dir() -> PathBuf {
+    logging::app_data_dir("state")
+}
+
+fn store() -> AppResult<&'static sled::Db> {
+    STORE.get_or_try_init(|| {
+        sled::open(store_dir()).map_err(|e| {
+            AppError::new(ErrorCode::FileRead, format!("Failed to open state store: {}", e))
+        })
+    })
+}
+
+fn validate_key(key: &str) -> AppResult<&str> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(AppError::invalid_input("key", "Key cannot be empty"));
+    }
+    if key.len() > MAX_KEY_LEN {
+        return Err(AppError::invalid_input(
+            "key",
+            format!("Key cannot exceed {} bytes", MAX_KEY_LEN),
+        ));
+    }
+    Ok(key)
+}
+
+/// Returns the value stored under `key`.
+pub fn get(key: &str) -> AppResult<Option<serde_json::Value>> {
+    let key = validate_key(key)?;
+    let bytes = store()?.get(key).into_app_error(ErrorCode::FileRead)?;
+
+    bytes
+        .map(|bytes| serde_json::from_slice(&bytes).into_app_error(ErrorCode::InvalidFormat))
+        .transpose()
+}
+
+/// Stores `value` under `key`; a JSON `null` removes the key.
+pub fn set(key: &str, value: serde_json::Value) -> AppResult<()> {
+    let key = validate_key(key)?;
+    let db = store()?;
+
+    if value.is_null() {
+        db.remove(key).into_app_error(ErrorCode::FileWrite)?;
+    } else {
+        let bytes = serde_json::to_vec(&value).into_app_error(ErrorCode::InternalError)?;
+        db.insert(key, bytes).into_app_error(ErrorCode::FileWrite)?;
+    }
+
+    db.flush().into_app_error(ErrorCode::FileWrite)?;
+    Ok(())
+}
+
+/// Returns every entry whose key starts with `prefix`.
+pub fn entries(prefix: &str) -> AppResult<BTreeMap<String, serde_json::Value>> {
+    store()?
+        .scan_prefix(prefix)
+        .map(|entry| {
+            let (key, value) = entry.into_app_error(ErrorCode::FileRead)?;
+            let value = serde_json::from_slice(&value).into_app_error(ErrorCode::InvalidFormat)?;
+            Ok((String::from_utf8_lossy(&key).to_string(), value))
+        })
+        .collect()
+}
+
+/// Starts publishing change events for keys under `prefix`.
+///
+/// Watching the same prefix twice is a no-op. Returns the current entries so
+/// callers can render initial state without a separate request.
+pub fn watch(prefix: &str) -> AppResult<BTreeMap<String, serde_json::Value>> {
+    let db = store()?;
+    let current = entries(prefix)?;
+
+    let newly_watched = WATCHED
+        .lock()
+        .map(|mut watched| watched.insert(prefix.to_string()))
+        .unwrap_or(false);
+
+    if newly_watched {
+        let mut subscriber = db.watch_prefix(prefix);
+        tauri::async_runtime::spawn(async move {
+            while let Some(event) = (&mut subscriber).await {
+                let (key, value) = match event {
+                    sled::Event::Insert { key, value } => {
+                        (key, serde_json::from_slice(&value).ok())
+                    }
+                    sled::Event::Remove { key } => (key, None),
+                };
+
+                events::publish(AppEvent::StateChanged {
+                    key: String::from_utf8_lossy(&key).to_string(),
+                    value,
+                });
+            }
+        });
+    }
+
+    Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(validate_key("  ").is_err());
+        assert!(validate_key(&"k".repeat(MAX_KEY_LEN + 1)).is_err());
+        assert_eq!(validate_key(" sidebar.width ").unwrap(), "sidebar.width");
+    }
+}
@@ -0,0 +1,164 @@
+//! Storage device enumeration and mount notifications.
+//!
+//! Devices are read from the OS mount table. A background watcher polls it
+//! and publishes `storage:mounted` and `storage:unmounted` as drives come and
+//! go, so backup and export screens can offer a USB stick as soon as it is
+//! plugged in. Devices are identified by their mount point.
+
+use crate::errors::{AppError, AppResult};
+use crate::events::{self, AppEvent};
+use crate::shutdown;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use sysinfo::{DiskKind, Disks};
+
+/// Interval between mount table polls.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// A mounted storage device.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDevice {
+    pub name: String,
+    pub mount_point: String,
+    pub file_system: String,
+    /// `hdd`, `ssd`, or `unknown`.
+    pub kind: String,
+    /// Whether the OS reports the device as removable, such as a USB stick.
+    pub removable: bool,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+/// Returns mounted devices sorted by mount point, optionally only the
+/// removable ones.
+pub fn list(removable_only: bool) -> Vec<StorageDevice> {
+    let disks = Disks::new_with_refreshed_list();
+    let mut devices: Vec<StorageDevice> = disks
+        .list()
+        .iter()
+        .filter(|disk| !removable_only || disk.is_removable())
+        .map(|disk| StorageDevice {
+            name: disk.name().to_string_lossy().to_string(),
+            mount_point: disk.mount_point().to_string_lossy().to_string(),
+            file_system: disk.file_system().to_string_lossy().to_string(),
+            kind: match disk.kind() {
+                DiskKind::HDD => "hdd",
+                DiskKind::SSD => "ssd",
+                DiskKind::Unknown(_) => "unknown",
+            }
+            .to_string(),
+            removable: disk.is_removable(),
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+        })
+        .collect();
+    devices.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    devices.dedup_by(|a, b| a.mount_point == b.mount_point);
+    devices
+}
+
+/// Resolves `mount_point` to a currently mounted removable device, so
+/// callers never write to an arbitrary absolute path.
+pub fn removable_mount(mount_point: &str) -> AppResult<PathBuf> {
+    list(true)
+        .into_iter()
+        .find(|device| device.mount_point == mount_point)
+        .map(|device| PathBuf::from(device.mount_point))
+        .ok_or_else(|| {
+            AppError::invalid_input(
+                "mount_point",
+                format!("'{}' is not a mounted removable device", mount_point),
+            )
+        })
+}
+
+/// Starts the task that publishes mount and unmount events.
+pub fn start_watcher() {
+    let task = tauri::async_runtime::spawn(async move {
+        let mut known = poll().await;
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let current = poll().await;
+            let (mounted, unmounted) = changes(&known, &current);
+            for device in unmounted {
+                tracing::info!("Storage device unmounted: {}", device.mount_point);
+                events::publish(AppEvent::StorageUnmounted(device));
+            }
+            for device in mounted {
+                tracing::info!("Storage device mounted: {}", device.mount_point);
+                events::publish(AppEvent::StorageMounted(device));
+            }
+            known = current;
+        }
+    });
+    shutdown::track("storage-watcher", task);
+}
+
+/// Reads the mount table off the async runtime.
+async fn poll() -> Vec<StorageDevice> {
+    tauri::async_runtime::spawn_blocking(|| list(false))
+        .await
+        .unwrap_or_default()
+}
+
+/// Splits the difference between two listings into mounted and unmounted
+/// devices.
+fn changes(
+    previous: &[StorageDevice],
+    current: &[StorageDevice],
+) -> (Vec<StorageDevice>, Vec<StorageDevice>) {
+    let absent = |devices: &[StorageDevice], device: &StorageDevice| {
+        !devices
+            .iter()
+            .any(|other| other.mount_point == device.mount_point)
+    };
+    let mounted = current
+        .iter()
+        .filter(|device| absent(previous, device))
+        .cloned()
+        .collect();
+    let unmounted = previous
+        .iter()
+        .filter(|device| absent(current, device))
+        .cloned()
+        .collect();
+    (mounted, unmounted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(mount_point: &str, available_bytes: u64) -> StorageDevice {
+        StorageDevice {
+            name: "USB".to_string(),
+            mount_point: mount_point.to_string(),
+            file_system: "vfat".to_string(),
+            kind: "unknown".to_string(),
+            removable: true,
+            total_bytes: 8 << 30,
+            available_bytes,
+        }
+    }
+
+    #[test]
+    fn reports_mount_changes_by_mount_point() {
+        let previous = vec![device("/media/a", 10), device("/media/b", 10)];
+        let current = vec![device("/media/b", 5), device("/media/c", 10)];
+
+        let (mounted, unmounted) = changes(&previous, &current);
+        assert_eq!(mounted, vec![device("/media/c", 10)]);
+        assert_eq!(unmounted, vec![device("/media/a", 10)]);
+        assert_eq!(changes(&current, &current), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn rejects_unknown_mount_points() {
+        assert!(removable_mount("/definitely/not/mounted").is_err());
+        assert!(list(true).iter().all(|device| device.removable));
+    }
+}
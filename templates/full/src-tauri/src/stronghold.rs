@@ -15,6 +15,21 @@ pub enum Error {
     Stronghold(#[from] tauri_plugin_stronghold::stronghold::Error),
 }
 
+/// Derives the snapshot encryption key from a vault password.
+///
+/// Shared by the Stronghold plugin and backend-owned snapshots so one
+/// password unlocks both.
+pub fn derive_key(password: &str) -> Vec<u8> {
+    use argon2::{Algorithm, Argon2, Params, Version};
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default());
+    let salt = &[0; 32];
+    let mut output = [0u8; 32];
+    argon2
+        .hash_password_into(password.as_bytes(), salt, &mut output)
+        .expect("failed to hash password");
+    output.to_vec()
+}
+
 /// Wrapper around Stronghold for managing encrypted storage operations.
 pub struct StrongholdManager(Stronghold);
 
@@ -33,4 +48,4 @@ impl StrongholdManager {
     pub fn stronghold_mut(&mut self) -> &mut Stronghold {
         &mut self.0
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,622 @@
+//! Offline-first synchronization with a remote HTTP API.
+//!
+//! Local writes to synced tables are recorded in the `sync_changes` log. A sync
+//! run pulls remote changes since the stored cursor, resolves conflicts with
+//! pending local changes (last-write-wins unless a hook decides otherwise),
+//! then pushes whatever is still pending. Status changes are published on the
+//! event bus as `sync:status`.
+
+use crate::config;
+use crate::database::get_pool_ref;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::events::{self, AppEvent};
+use crate::http_client::{self, HttpRequestOptions};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Tables kept in sync and the columns written when applying remote upserts.
+const SYNCED_TABLES: &[(&str, &[&str])] = &[
+    (
+        "users",
+        &[
+            "email",
+            "username",
+            "password_hash",
+            "first_name",
+            "last_name",
+            "is_active",
+            "created_at",
+            "updated_at",
+        ],
+    ),
+    (
+        "user_settings",
+        &[
+            "user_id",
+            "theme",
+            "language",
+            "notifications_enabled",
+            "settings_data",
+            "created_at",
+            "updated_at",
+        ],
+    ),
+];
+
+/// Maximum number of local changes pushed per request.
+const PUSH_BATCH_SIZE: i64 = 200;
+
+/// `sync_state` key holding the pull cursor returned by the remote.
+const CURSOR_KEY: &str = "pull_cursor";
+
+/// Conflict hooks keyed by table name.
+static CONFLICT_HOOKS: Lazy<RwLock<HashMap<String, ConflictHook>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Status of the most recent sync run.
+static STATUS: Lazy<Mutex<SyncStatus>> = Lazy::new(|| Mutex::new(SyncStatus::default()));
+
+/// Serializes sync runs so the background task and manual triggers never overlap.
+static RUN_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Kind of change recorded for a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum SyncOperation {
+    Upsert,
+    Delete,
+}
+
+/// A single row change exchanged with the remote.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncChange {
+    pub id: Uuid,
+    pub table_name: String,
+    pub record_id: Uuid,
+    pub operation: SyncOperation,
+    /// Full row as JSON for upserts; `None` for deletes.
+    pub payload: Option<serde_json::Value>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Outcome chosen for a conflicting pair of changes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictResolution {
+    KeepLocal,
+    KeepRemote,
+    /// Apply this row locally and push it as the winning version.
+    Merge(serde_json::Value),
+}
+
+/// Custom conflict handler; returning `None` falls back to last-write-wins.
+pub type ConflictHook =
+    Arc<dyn Fn(&SyncChange, &SyncChange) -> Option<ConflictResolution> + Send + Sync>;
+
+/// Current phase of the sync engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncState {
+    #[default]
+    Idle,
+    Syncing,
+    Error,
+    Disabled,
+}
+
+/// Summary of sync progress reported to the frontend.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub state: SyncState,
+    pub pending: i64,
+    pub pushed: usize,
+    pub pulled: usize,
+    pub conflicts: usize,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Response body of the remote pull endpoint.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullResponse {
+    #[serde(default)]
+    changes: Vec<SyncChange>,
+    cursor: Option<String>,
+}
+
+/// Registers a conflict hook for `table`, replacing any previous hook.
+pub fn register_conflict_hook(table: impl Into<String>, hook: ConflictHook) {
+    if let Ok(mut hooks) = CONFLICT_HOOKS.write() {
+        hooks.insert(table.into(), hook);
+    }
+}
+
+/// Records a local change to a synced table.
+///
+/// Upserts snapshot the current row so the pushed payload reflects what was
+/// written. Changes to tables outside [`SYNCED_TABLES`] are ignored.
+pub async fn track_change(table: &str, record_id: Uuid, operation: SyncOperation) -> AppResult<()> {
+    if synced_columns(table).is_none() {
+        return Ok(());
+    }
+
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+
+    let payload: Option<serde_json::Value> = match operation {
+        SyncOperation::Upsert => sqlx::query_scalar(&format!(
+            "SELECT to_jsonb(t) FROM {} t WHERE id = $1",
+            table
+        ))
+        .bind(record_id)
+        .fetch_optional(pool.as_ref())
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?,
+        SyncOperation::Delete => None,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO sync_changes (table_name, record_id, operation, payload)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(table)
+    .bind(record_id)
+    .bind(operation)
+    .bind(payload)
+    .execute(pool.as_ref())
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    Ok(())
+}
+
+/// Returns the last reported status with a fresh pending-change count.
+pub async fn status() -> AppResult<SyncStatus> {
+    let mut status = STATUS.lock().await.clone();
+
+    if config::current().sync_endpoint.is_none() {
+        status.state = SyncState::Disabled;
+    }
+
+    if let Ok(pool) = get_pool_ref() {
+        status.pending = pending_count(pool.as_ref()).await?;
+    }
+
+    Ok(status)
+}
+
+/// Runs a full pull/resolve/push cycle against the configured remote.
+pub async fn sync_now() -> AppResult<SyncStatus> {
+    let endpoint = config::current().sync_endpoint.clone().ok_or_else(|| {
+        AppError::new(
+            ErrorCode::ConfigurationError,
+            "SYNC_ENDPOINT is not configured",
+        )
+    })?;
+
+    let _guard = RUN_LOCK
+        .try_lock()
+        .map_err(|_| AppError::validation_error("A sync is already in progress"))?;
+
+    update_status(|status| {
+        status.state = SyncState::Syncing;
+        status.last_error = None;
+    })
+    .await;
+
+    match run_cycle(&endpoint).await {
+        Ok((pulled, conflicts, pushed, pending)) => {
+            update_status(|status| {
+                status.state = SyncState::Idle;
+                status.pulled = pulled;
+                status.conflicts = conflicts;
+                status.pushed = pushed;
+                status.pending = pending;
+                status.last_synced_at = Some(Utc::now());
+            })
+            .await;
+            Ok(STATUS.lock().await.clone())
+        }
+        Err(e) => {
+            tracing::warn!("Sync failed: {}", e);
+            update_status(|status| {
+                status.state = SyncState::Error;
+                status.last_error = Some(e.message.clone());
+            })
+            .await;
+            Err(e)
+        }
+    }
+}
+
+async fn run_cycle(endpoint: &str) -> AppResult<(usize, usize, usize, i64)> {
+    let pool = get_pool_ref().into_app_error(ErrorCode::DatabaseConnection)?;
+    let (pulled, conflicts) = pull(pool.as_ref(), endpoint).await?;
+    let pushed = push(pool.as_ref(), endpoint).await?;
+    let pending = pending_count(pool.as_ref()).await?;
+    Ok((pulled, conflicts, pushed, pending))
+}
+
+/// Fetches remote changes since the stored cursor and applies them locally.
+async fn pull(pool: &PgPool, endpoint: &str) -> AppResult<(usize, usize)> {
+    let cursor: Option<String> = sqlx::query_scalar("SELECT value FROM sync_state WHERE key = $1")
+        .bind(CURSOR_KEY)
+        .fetch_optional(pool)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+    let url = match &cursor {
+        Some(cursor) => format!("{}/pull?since={}", endpoint, urlencode(cursor)),
+        None => format!("{}/pull", endpoint),
+    };
+
+    let response =
+        http_client::execute("GET", &url, HashMap::new(), None, remote_options()).await?;
+    if !(200..300).contains(&response.status) {
+        return Err(remote_error("pull", response.status));
+    }
+
+    let body: PullResponse = serde_json::from_value(response.body).map_err(|e| {
+        AppError::new(
+            ErrorCode::InvalidFormat,
+            format!("Invalid pull response: {}", e),
+        )
+    })?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    let mut applied = 0;
+    let mut conflicts = 0;
+
+    for remote in &body.changes {
+        if synced_columns(&remote.table_name).is_none() {
+            tracing::debug!(
+                "Ignoring remote change for unsynced table '{}'",
+                remote.table_name
+            );
+            continue;
+        }
+
+        let local: Option<SyncChange> = sqlx::query_as(
+            r#"
+            SELECT id, table_name, record_id, operation, payload, changed_at
+            FROM sync_changes
+            WHERE table_name = $1 AND record_id = $2 AND synced_at IS NULL
+            ORDER BY changed_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(&remote.table_name)
+        .bind(remote.record_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+        let Some(local) = local else {
+            apply_change(&mut tx, remote, remote.payload.as_ref()).await?;
+            applied += 1;
+            continue;
+        };
+
+        conflicts += 1;
+        match resolve_conflict(&local, remote) {
+            ConflictResolution::KeepLocal => {}
+            ConflictResolution::KeepRemote => {
+                apply_change(&mut tx, remote, remote.payload.as_ref()).await?;
+                mark_record_synced(&mut tx, &local).await?;
+                applied += 1;
+            }
+            ConflictResolution::Merge(merged) => {
+                let merged_change = SyncChange {
+                    operation: SyncOperation::Upsert,
+                    ..remote.clone()
+                };
+                apply_change(&mut tx, &merged_change, Some(&merged)).await?;
+                sqlx::query(
+                    r#"
+                    UPDATE sync_changes
+                    SET operation = $2, payload = $3, changed_at = CURRENT_TIMESTAMP
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(local.id)
+                .bind(SyncOperation::Upsert)
+                .bind(&merged)
+                .execute(&mut *tx)
+                .await
+                .into_app_error(ErrorCode::DatabaseQuery)?;
+                applied += 1;
+            }
+        }
+    }
+
+    if let Some(cursor) = &body.cursor {
+        sqlx::query(
+            r#"
+            INSERT INTO sync_state (key, value) VALUES ($1, $2)
+            ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(CURSOR_KEY)
+        .bind(cursor)
+        .execute(&mut *tx)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+    }
+
+    tx.commit().await.into_app_error(ErrorCode::DatabaseQuery)?;
+    Ok((applied, conflicts))
+}
+
+/// Sends pending local changes in batches and marks accepted ones as synced.
+async fn push(pool: &PgPool, endpoint: &str) -> AppResult<usize> {
+    let url = format!("{}/push", endpoint);
+    let mut pushed = 0;
+
+    loop {
+        let changes: Vec<SyncChange> = sqlx::query_as(
+            r#"
+            SELECT id, table_name, record_id, operation, payload, changed_at
+            FROM sync_changes
+            WHERE synced_at IS NULL
+            ORDER BY changed_at ASC
+            LIMIT $1
+            "#,
+        )
+        .bind(PUSH_BATCH_SIZE)
+        .fetch_all(pool)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)?;
+
+        if changes.is_empty() {
+            return Ok(pushed);
+        }
+
+        let response = http_client::execute(
+            "POST",
+            &url,
+            HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+            Some(serde_json::json!({ "changes": changes })),
+            remote_options(),
+        )
+        .await?;
+
+        if !(200..300).contains(&response.status) {
+            return Err(remote_error("push", response.status));
+        }
+
+        let ids: Vec<Uuid> = changes.iter().map(|change| change.id).collect();
+        sqlx::query("UPDATE sync_changes SET synced_at = CURRENT_TIMESTAMP WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(pool)
+            .await
+            .into_app_error(ErrorCode::DatabaseQuery)?;
+
+        pushed += changes.len();
+        if (changes.len() as i64) < PUSH_BATCH_SIZE {
+            return Ok(pushed);
+        }
+    }
+}
+
+/// Picks a winner between a pending local change and an incoming remote one.
+///
+/// A hook registered for the table is consulted first; otherwise the change
+/// with the later `changed_at` wins, with ties going to the remote.
+pub fn resolve_conflict(local: &SyncChange, remote: &SyncChange) -> ConflictResolution {
+    let hook = CONFLICT_HOOKS
+        .read()
+        .ok()
+        .and_then(|hooks| hooks.get(&local.table_name).cloned());
+
+    if let Some(resolution) = hook.and_then(|hook| hook(local, remote)) {
+        return resolution;
+    }
+
+    if local.changed_at > remote.changed_at {
+        ConflictResolution::KeepLocal
+    } else {
+        ConflictResolution::KeepRemote
+    }
+}
+
+/// Writes a remote change to its table without recording it as a local change.
+async fn apply_change(
+    tx: &mut Transaction<'_, Postgres>,
+    change: &SyncChange,
+    payload: Option<&serde_json::Value>,
+) -> AppResult<()> {
+    let columns = synced_columns(&change.table_name).ok_or_else(|| {
+        AppError::validation_error(format!("Table '{}' is not synced", change.table_name))
+    })?;
+
+    match (change.operation, payload) {
+        (SyncOperation::Delete, _) => {
+            sqlx::query(&format!("DELETE FROM {} WHERE id = $1", change.table_name))
+                .bind(change.record_id)
+                .execute(&mut **tx)
+                .await
+                .into_app_error(ErrorCode::DatabaseQuery)?;
+        }
+        (SyncOperation::Upsert, Some(payload)) => {
+            sqlx::query(&upsert_sql(&change.table_name, columns))
+                .bind(payload)
+                .execute(&mut **tx)
+                .await
+                .into_app_error(ErrorCode::DatabaseQuery)?;
+        }
+        (SyncOperation::Upsert, None) => {
+            return Err(AppError::new(
+                ErrorCode::InvalidFormat,
+                format!(
+                    "Upsert for {} {} has no payload",
+                    change.table_name, change.record_id
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+async fn mark_record_synced(
+    tx: &mut Transaction<'_, Postgres>,
+    local: &SyncChange,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE sync_changes SET synced_at = CURRENT_TIMESTAMP
+        WHERE table_name = $1 AND record_id = $2 AND synced_at IS NULL
+        "#,
+    )
+    .bind(&local.table_name)
+    .bind(local.record_id)
+    .execute(&mut **tx)
+    .await
+    .into_app_error(ErrorCode::DatabaseQuery)?;
+    Ok(())
+}
+
+async fn pending_count(pool: &PgPool) -> AppResult<i64> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM sync_changes WHERE synced_at IS NULL")
+        .fetch_one(pool)
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)
+}
+
+async fn update_status(apply: impl FnOnce(&mut SyncStatus)) {
+    let mut status = STATUS.lock().await;
+    apply(&mut status);
+    events::publish(AppEvent::SyncStatusChanged(status.clone()));
+}
+
+fn synced_columns(table: &str) -> Option<&'static [&'static str]> {
+    SYNCED_TABLES
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, columns)| *columns)
+}
+
+/// Builds an upsert that populates a row from a JSON payload bound as `$1`.
+fn upsert_sql(table: &str, columns: &[&str]) -> String {
+    let updates = columns
+        .iter()
+        .map(|column| format!("{} = EXCLUDED.{}", column, column))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "INSERT INTO {table} SELECT * FROM jsonb_populate_record(NULL::{table}, $1) \
+         ON CONFLICT (id) DO UPDATE SET {updates}"
+    )
+}
+
+fn remote_options() -> HttpRequestOptions {
+    HttpRequestOptions {
+        retries: Some(2),
+        timeout_ms: Some(30_000),
+        ..Default::default()
+    }
+}
+
+fn remote_error(operation: &str, status: u16) -> AppError {
+    AppError::new(
+        ErrorCode::ExternalServiceUnavailable,
+        format!("Sync {} failed with status {}", operation, status),
+    )
+}
+
+fn urlencode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn change(table: &str, changed_at: DateTime<Utc>) -> SyncChange {
+        SyncChange {
+            id: Uuid::new_v4(),
+            table_name: table.to_string(),
+            record_id: Uuid::nil(),
+            operation: SyncOperation::Upsert,
+            payload: Some(serde_json::json!({ "theme": "dark" })),
+            changed_at,
+        }
+    }
+
+    #[test]
+    fn last_write_wins_without_hook() {
+        let now = Utc::now();
+        let older = change("users", now - Duration::seconds(10));
+        let newer = change("users", now);
+
+        assert_eq!(
+            resolve_conflict(&newer, &older),
+            ConflictResolution::KeepLocal
+        );
+        assert_eq!(
+            resolve_conflict(&older, &newer),
+            ConflictResolution::KeepRemote
+        );
+    }
+
+    #[test]
+    fn hooks_override_last_write_wins() {
+        register_conflict_hook(
+            "user_settings",
+            Arc::new(|_, _| {
+                Some(ConflictResolution::Merge(
+                    serde_json::json!({ "merged": true }),
+                ))
+            }),
+        );
+
+        let now = Utc::now();
+        let local = change("user_settings", now);
+        let remote = change("user_settings", now - Duration::seconds(10));
+
+        assert!(matches!(
+            resolve_conflict(&local, &remote),
+            ConflictResolution::Merge(_)
+        ));
+    }
+
+    #[test]
+    fn upsert_sql_updates_every_synced_column() {
+        let sql = upsert_sql("users", synced_columns("users").unwrap());
+        assert!(sql
+            .starts_with("INSERT INTO users SELECT * FROM jsonb_populate_record(NULL::users, $1)"));
+        assert!(sql.contains("email = EXCLUDED.email"));
+        assert!(synced_columns("app_logs").is_none());
+    }
+
+    #[test]
+    fn cursor_is_percent_encoded() {
+        assert_eq!(
+            urlencode("2024-01-01T00:00:00+00:00"),
+            "2024-01-01T00%3A00%3A00%2B00%3A00"
+        );
+    }
+}
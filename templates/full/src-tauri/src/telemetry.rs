@@ -0,0 +1,321 @@
+//! Opt-in, anonymized usage analytics with local batching.
+//!
+//! Nothing is recorded until the user grants consent. Events are buffered in
+//! memory, flushed to a local JSON-lines file, and uploaded in batches to the
+//! configured endpoint. Users can inspect and purge everything collected.
+
+use crate::config;
+use crate::logging;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Number of buffered events that triggers a flush to disk.
+const FLUSH_THRESHOLD: usize = 50;
+/// Maximum length of string property values kept after anonymization.
+const MAX_PROPERTY_LEN: usize = 100;
+/// Property keys that are always dropped because they tend to carry personal data.
+const SENSITIVE_KEYS: &[&str] = &["email", "username", "name", "password", "path", "token", "ip"];
+
+/// In-memory buffer of events not yet written to disk.
+static BUFFER: Lazy<Mutex<Vec<TelemetryEvent>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Persisted consent state and anonymous installation identifier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryState {
+    pub consent: bool,
+    pub install_id: Uuid,
+    pub consent_changed_at: Option<DateTime<Utc>>,
+    pub last_upload_at: Option<DateTime<Utc>>,
+}
+
+impl Default for TelemetryState {
+    fn default() -> Self {
+        Self {
+            consent: false,
+            install_id: Uuid::new_v4(),
+            consent_changed_at: None,
+            last_upload_at: None,
+        }
+    }
+}
+
+/// A single anonymized feature-usage event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryEvent {
+    pub feature: String,
+    pub properties: HashMap<String, serde_json::Value>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Summary of telemetry state returned to the frontend.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryStatus {
+    pub consent: bool,
+    pub install_id: Uuid,
+    pub pending_events: usize,
+    pub upload_endpoint_configured: bool,
+    pub last_upload_at: Option<DateTime<Utc>>,
+}
+
+/// Returns the directory holding telemetry state and event batches.
+fn telemetry_dir() -> PathBuf {
+    logging::app_data_dir("telemetry")
+}
+
+fn state_path() -> PathBuf {
+    telemetry_dir().join("state.json")
+}
+
+fn events_path() -> PathBuf {
+    telemetry_dir().join("events.jsonl")
+}
+
+/// Loads the persisted telemetry state, creating a default one if missing.
+pub fn load_state() -> Result<TelemetryState> {
+    let path = state_path();
+    if !path.exists() {
+        let state = TelemetryState::default();
+        save_state(&state)?;
+        return Ok(state);
+    }
+
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn save_state(state: &TelemetryState) -> Result<()> {
+    fs::create_dir_all(telemetry_dir())?;
+    fs::write(state_path(), serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+/// Grants or revokes consent. Revoking consent purges all collected data.
+pub fn set_consent(consent: bool) -> Result<TelemetryState> {
+    let mut state = load_state()?;
+    state.consent = consent;
+    state.consent_changed_at = Some(Utc::now());
+    save_state(&state)?;
+
+    if !consent {
+        purge()?;
+    }
+
+    Ok(state)
+}
+
+/// Records a feature-usage event if the user has opted in.
+pub fn record(feature: &str, properties: HashMap<String, serde_json::Value>) -> Result<()> {
+    if !load_state()?.consent {
+        return Ok(());
+    }
+
+    let feature = feature.trim();
+    if feature.is_empty() {
+        return Err(anyhow::anyhow!("Feature name cannot be empty"));
+    }
+
+    let event = TelemetryEvent {
+        feature: feature.chars().take(MAX_PROPERTY_LEN).collect(),
+        properties: anonymize(properties),
+        recorded_at: Utc::now(),
+    };
+
+    let should_flush = {
+        let mut buffer = BUFFER
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Telemetry buffer poisoned"))?;
+        buffer.push(event);
+        buffer.len() >= FLUSH_THRESHOLD
+    };
+
+    if should_flush {
+        flush()?;
+    }
+
+    Ok(())
+}
+
+/// Writes buffered events to the local batch file.
+pub fn flush() -> Result<()> {
+    let events: Vec<TelemetryEvent> = {
+        let mut buffer = BUFFER
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Telemetry buffer poisoned"))?;
+        buffer.drain(..).collect()
+    };
+
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(telemetry_dir())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(events_path())?;
+
+    for event in events {
+        writeln!(file, "{}", serde_json::to_string(&event)?)?;
+    }
+
+    Ok(())
+}
+
+/// Returns every event collected locally, including buffered ones.
+pub fn collected_events() -> Result<Vec<TelemetryEvent>> {
+    let mut events = Vec::new();
+
+    let path = events_path();
+    if path.exists() {
+        for line in fs::read_to_string(path)?.lines() {
+            if let Ok(event) = serde_json::from_str::<TelemetryEvent>(line) {
+                events.push(event);
+            }
+        }
+    }
+
+    if let Ok(buffer) = BUFFER.lock() {
+        events.extend(buffer.iter().cloned());
+    }
+
+    Ok(events)
+}
+
+/// Deletes all collected events, both buffered and on disk.
+pub fn purge() -> Result<usize> {
+    let count = collected_events()?.len();
+
+    if let Ok(mut buffer) = BUFFER.lock() {
+        buffer.clear();
+    }
+
+    let path = events_path();
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    Ok(count)
+}
+
+/// Returns the current consent and batching state.
+pub fn status() -> Result<TelemetryStatus> {
+    let state = load_state()?;
+    let config = config::current();
+
+    Ok(TelemetryStatus {
+        consent: state.consent,
+        install_id: state.install_id,
+        pending_events: collected_events()?.len(),
+        upload_endpoint_configured: config.telemetry_endpoint.is_some(),
+        last_upload_at: state.last_upload_at,
+    })
+}
+
+/// Uploads collected events to the configured endpoint when consent is granted.
+///
+/// Returns the number of events uploaded. Events are only removed locally
+/// after the endpoint acknowledges the batch.
+pub async fn upload_batch() -> Result<usize> {
+    let mut state = load_state()?;
+    let Some(endpoint) = config::current().telemetry_endpoint.clone() else {
+        return Ok(0);
+    };
+
+    if !state.consent {
+        return Ok(0);
+    }
+
+    flush()?;
+    let events = collected_events()?;
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let body = serde_json::json!({
+        "installId": state.install_id,
+        "events": events,
+    });
+
+    let response = crate::http_client::execute(
+        "POST",
+        &endpoint,
+        HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+        Some(body),
+        crate::http_client::HttpRequestOptions {
+            retries: Some(2),
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+    if !(200..300).contains(&response.status) {
+        return Err(anyhow::anyhow!("Telemetry endpoint returned status {}", response.status));
+    }
+
+    let uploaded = events.len();
+    let path = events_path();
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    state.last_upload_at = Some(Utc::now());
+    save_state(&state)?;
+
+    Ok(uploaded)
+}
+
+/// Removes personal-looking keys, nested structures, and long strings.
+fn anonymize(properties: HashMap<String, serde_json::Value>) -> HashMap<String, serde_json::Value> {
+    properties
+        .into_iter()
+        .filter(|(key, _)| {
+            let key = key.to_lowercase();
+            !SENSITIVE_KEYS.iter().any(|sensitive| key.contains(sensitive))
+        })
+        .filter_map(|(key, value)| match value {
+            serde_json::Value::String(text) => Some((
+                key,
+                serde_json::Value::String(text.chars().take(MAX_PROPERTY_LEN).collect()),
+            )),
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => Some((key, value)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn anonymize_drops_sensitive_and_nested_values() {
+        let properties = HashMap::from([
+            ("userEmail".to_string(), json!("a@example.com")),
+            ("filePath".to_string(), json!("/home/me")),
+            ("count".to_string(), json!(3)),
+            ("nested".to_string(), json!({"a": 1})),
+            ("label".to_string(), json!("x".repeat(500))),
+        ]);
+
+        let cleaned = anonymize(properties);
+
+        assert!(!cleaned.contains_key("userEmail"));
+        assert!(!cleaned.contains_key("filePath"));
+        assert!(!cleaned.contains_key("nested"));
+        assert_eq!(cleaned["count"], json!(3));
+        assert_eq!(cleaned["label"].as_str().unwrap().len(), MAX_PROPERTY_LEN);
+    }
+}
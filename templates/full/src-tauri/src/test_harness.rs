@@ -0,0 +1,228 @@
+//! End-to-end harness for invoking rate-limited commands over mock IPC.
+//!
+//! Builds a `tauri::Builder` on the mock runtime with the rate limiter managed
+//! as in the application, so tests exercise argument deserialization, the
+//! rate-limited wrappers and response serialization together. Commands that
+//! take a Wry-specific `AppHandle` or `Window` cannot run on the mock runtime
+//! and are not registered here.
+
+use crate::handlers::*;
+use crate::rate_limiter::RateLimiterConfig;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::ipc::{CallbackFn, InvokeBody};
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime, INVOKE_KEY};
+use tauri::webview::InvokeRequest;
+use tauri::{App, Manager, WebviewWindow, WebviewWindowBuilder};
+
+/// A mock application with a single webview to invoke commands from.
+pub struct Harness {
+    _app: App<MockRuntime>,
+    webview: WebviewWindow<MockRuntime>,
+}
+
+impl Harness {
+    /// Creates a harness with the default rate limits.
+    pub fn new() -> Self {
+        Self::with_rate_limiter(RateLimiterConfig::new())
+    }
+
+    /// Creates a harness with custom global and per-user limits per minute.
+    pub fn with_rate_limits(global_per_minute: u32, user_per_minute: u32) -> Self {
+        Self::with_rate_limiter(RateLimiterConfig::new_with_limits(
+            global_per_minute,
+            user_per_minute,
+        ))
+    }
+
+    fn with_rate_limiter(rate_limiter: RateLimiterConfig) -> Self {
+        let app = mock_builder()
+            .setup(move |app| {
+                app.manage(Arc::new(rate_limiter));
+                Ok(())
+            })
+            .invoke_handler(crate::registry::builtin_handler![
+                #[cfg(any(debug_assertions, feature = "demo"))]
+                rl_greet,
+                #[cfg(feature = "database")]
+                rl_get_all_users,
+                #[cfg(feature = "database")]
+                rl_get_user_by_id,
+                #[cfg(feature = "database")]
+                rl_create_user,
+                #[cfg(feature = "database")]
+                rl_authenticate_user,
+                rl_get_log_config,
+                rl_get_log_entries,
+                rl_get_log_stats,
+                rl_get_metrics_prometheus,
+                rl_get_recent_invocations,
+                rl_clear_recent_invocations,
+                #[cfg(feature = "database")]
+                rl_get_query_stats,
+                #[cfg(feature = "database")]
+                rl_reset_query_stats,
+                rl_list_commands,
+                get_rate_limiter_status
+            ])
+            .build(mock_context(noop_assets()))
+            .expect("failed to build mock application");
+        let webview = WebviewWindowBuilder::new(&app, "main", Default::default())
+            .build()
+            .expect("failed to create mock webview");
+
+        Self { _app: app, webview }
+    }
+
+    /// Invokes `command` with JSON `args`, returning the raw response or error.
+    pub fn invoke(&self, command: &str, args: Value) -> Result<Value, Value> {
+        let request = InvokeRequest {
+            cmd: command.to_string(),
+            callback: CallbackFn(0),
+            error: CallbackFn(1),
+            url: "http://tauri.localhost"
+                .parse()
+                .expect("invalid mock webview url"),
+            body: InvokeBody::Json(args),
+            headers: Default::default(),
+            invoke_key: INVOKE_KEY.to_string(),
+        };
+
+        tauri::test::get_ipc_response(&self.webview, request).map(|body| {
+            body.deserialize::<Value>()
+                .expect("command response is not valid JSON")
+        })
+    }
+
+    /// Invokes `command` and deserializes a successful response into `T`.
+    pub fn invoke_ok<T: DeserializeOwned>(&self, command: &str, args: Value) -> T {
+        let value = self
+            .invoke(command, args)
+            .unwrap_or_else(|e| panic!("{} failed: {}", command, e));
+        serde_json::from_value(value)
+            .unwrap_or_else(|e| panic!("{} returned an unexpected shape: {}", command, e))
+    }
+
+    /// Invokes `command` and returns its error message.
+    pub fn invoke_err(&self, command: &str, args: Value) -> String {
+        match self.invoke(command, args) {
+            Ok(value) => panic!("{} unexpectedly succeeded with {}", command, value),
+            Err(Value::String(message)) => message,
+            Err(other) => other.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspector::InvocationRecord;
+    use crate::registry::{CommandInfo, RateLimitClass};
+    use serde_json::json;
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "demo"))]
+    fn greets_through_the_rate_limited_wrapper() {
+        let harness = Harness::new();
+        let greeting: String = harness.invoke_ok("rl_greet", json!({ "name": "Ada" }));
+        assert_eq!(greeting, "Hello, Ada! You've been greeted from Rust!");
+    }
+
+    #[test]
+    fn serializes_structured_responses() {
+        let harness = Harness::new();
+        let records: Vec<InvocationRecord> =
+            harness.invoke_ok("rl_get_recent_invocations", json!({ "limit": 5 }));
+        assert!(records.len() <= 5);
+
+        #[cfg(feature = "database")]
+        {
+            let stats: Value = harness.invoke_ok("rl_get_query_stats", json!({}));
+            assert!(stats.is_object() || stats.is_array());
+        }
+
+        let metrics: String = harness.invoke_ok("rl_get_metrics_prometheus", json!({}));
+        assert!(metrics.contains("get_recent_invocations"));
+    }
+
+    #[test]
+    #[cfg(feature = "database")]
+    fn reports_argument_errors_by_camel_case_key() {
+        let harness = Harness::new();
+
+        let error = harness.invoke_err("rl_get_user_by_id", json!({}));
+        assert!(error.contains("userId"), "unexpected error: {}", error);
+
+        let error = harness.invoke_err(
+            "rl_authenticate_user",
+            json!({ "credentials": { "email": "user@example.com" } }),
+        );
+        assert!(error.contains("credentials"), "unexpected error: {}", error);
+    }
+
+    #[test]
+    #[cfg(feature = "database")]
+    fn rejects_anonymous_callers_of_user_scoped_commands() {
+        let harness = Harness::new();
+        let error = harness.invoke_err("rl_get_all_users", json!({}));
+        assert!(
+            error.contains("UNAUTHORIZED"),
+            "unexpected error: {}",
+            error
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "database")]
+    fn surfaces_handler_errors_as_strings() {
+        let harness = Harness::new();
+        let error = harness.invoke_err("rl_get_user_by_id", json!({ "userId": "not-a-uuid" }));
+        assert!(!error.is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "rate-limiter", any(debug_assertions, feature = "demo")))]
+    fn enforces_the_managed_rate_limiter() {
+        let harness = Harness::with_rate_limits(1, 1);
+        let _: String = harness.invoke_ok("rl_greet", json!({ "name": "first" }));
+
+        let error = harness.invoke_err("rl_greet", json!({ "name": "second" }));
+        assert!(
+            error.starts_with("Rate limit exceeded"),
+            "unexpected error: {}",
+            error
+        );
+
+        // The status command is not rate limited.
+        let _: String = harness.invoke_ok("get_rate_limiter_status", json!({}));
+    }
+
+    #[test]
+    fn lists_commands_with_argument_schemas() {
+        let harness = Harness::new();
+        let commands: Vec<CommandInfo> = harness.invoke_ok("rl_list_commands", json!({}));
+
+        let greet = commands
+            .iter()
+            .find(|command| command.spec.name == "rl_greet")
+            .expect("rl_greet is listed");
+        assert_eq!(greet.module, "core");
+        assert_eq!(greet.spec.rate_limit, RateLimitClass::Standard);
+        assert_eq!(greet.spec.args.len(), 1);
+        assert_eq!(greet.spec.args[0].name, "name");
+        assert_eq!(greet.spec.args[0].rust_type, "String");
+
+        let status = commands
+            .iter()
+            .find(|command| command.spec.name == "get_rate_limiter_status")
+            .expect("get_rate_limiter_status is listed");
+        assert_eq!(status.spec.rate_limit, RateLimitClass::Exempt);
+    }
+
+    #[test]
+    fn rejects_unregistered_commands() {
+        let harness = Harness::new();
+        assert!(harness.invoke("rl_does_not_exist", json!({})).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Builders for test data.
+//!
+//! Factories produce valid payloads with unique emails and usernames, and can
+//! insert them through the command handlers so rows pass the same validation
+//! and workspace scoping as in the application. Inserting requires the test
+//! database from [`crate::database::test_utils`].
+
+use crate::command_context::CommandContext;
+use crate::handlers::logs::create_log;
+use crate::handlers::users::create_user;
+use crate::models::{AppLog, CreateAppLog, CreateUser, PublicUser};
+use anyhow::{anyhow, Result};
+use uuid::Uuid;
+
+/// Password used by factory-built users.
+pub const DEFAULT_PASSWORD: &str = "Sup3r$ecret";
+
+/// Builder for user accounts.
+pub struct UserFactory {
+    user: CreateUser,
+}
+
+impl UserFactory {
+    /// Starts from a valid user with a unique email and username.
+    pub fn new() -> Self {
+        let suffix = Uuid::new_v4();
+        Self {
+            user: CreateUser {
+                email: format!("user+{}@example.com", suffix),
+                username: format!("user_{}", suffix.simple()),
+                password: DEFAULT_PASSWORD.to_string(),
+                first_name: Some("Test".to_string()),
+                last_name: Some("User".to_string()),
+            },
+        }
+    }
+
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.user.email = email.into();
+        self
+    }
+
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.user.password = password.into();
+        self
+    }
+
+    /// Returns the payload without inserting it.
+    pub fn build(self) -> CreateUser {
+        self.user
+    }
+
+    /// Creates the user, joining the current workspace if one is selected.
+    pub async fn insert(self) -> Result<PublicUser> {
+        create_user(CommandContext::internal(), self.user)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+}
+
+/// Who a factory-built log entry belongs to.
+enum LogOwner {
+    None,
+    Existing(Uuid),
+    /// A user created when the entry is inserted.
+    New,
+}
+
+/// Builder for application log entries.
+pub struct LogFactory {
+    level: String,
+    message: String,
+    tags: Vec<String>,
+    owner: LogOwner,
+}
+
+impl LogFactory {
+    /// Starts from an `info` entry without a user.
+    pub fn new() -> Self {
+        Self {
+            level: "info".to_string(),
+            message: "Test log entry".to_string(),
+            tags: Vec::new(),
+            owner: LogOwner::None,
+        }
+    }
+
+    pub fn level(mut self, level: impl Into<String>) -> Self {
+        self.level = level.into();
+        self
+    }
+
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+
+    /// Attributes the entry to an existing user.
+    pub fn user_id(mut self, user_id: Uuid) -> Self {
+        self.owner = LogOwner::Existing(user_id);
+        self
+    }
+
+    /// Attributes the entry to a user created by [`LogFactory::insert`].
+    pub fn with_new_user(mut self) -> Self {
+        self.owner = LogOwner::New;
+        self
+    }
+
+    /// Returns the payload without inserting it.
+    ///
+    /// Panics when the entry should belong to a new user, which only
+    /// [`LogFactory::insert`] can create.
+    pub fn build(self) -> CreateAppLog {
+        let user_id = match self.owner {
+            LogOwner::None => None,
+            LogOwner::Existing(user_id) => Some(user_id),
+            LogOwner::New => panic!("LogFactory::with_new_user requires insert()"),
+        };
+        CreateAppLog {
+            level: self.level,
+            message: self.message,
+            metadata: None,
+            user_id,
+            tags: Some(self.tags),
+        }
+    }
+
+    /// Creates the entry, inserting its user first when requested.
+    pub async fn insert(mut self) -> Result<AppLog> {
+        if let LogOwner::New = self.owner {
+            let user = UserFactory::new().insert().await?;
+            self.owner = LogOwner::Existing(user.id);
+        }
+        create_log(CommandContext::internal(), self.build())
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+}
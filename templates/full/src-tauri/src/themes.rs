@@ -0,0 +1,325 @@
+//! Built-in and user-provided UI themes.
+//!
+//! User themes are JSON files in a `themes` directory under the application
+//! data directory, one theme per `<id>.json` file. Only that directory is read,
+//! and file names double as theme ids, so a theme can never point the backend
+//! at another path. Files that fail validation are reported alongside the
+//! valid themes instead of failing the whole listing.
+
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::logging;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest theme id; matches the `user_settings.theme` column width.
+const MAX_THEME_ID_LEN: usize = 20;
+
+/// Largest theme file read from disk.
+const MAX_THEME_FILE_BYTES: u64 = 64 * 1024;
+
+/// Maximum number of colors a theme may define.
+const MAX_THEME_COLORS: usize = 100;
+
+/// Theme ids: lowercase letters, digits and dashes.
+static THEME_ID_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z0-9][a-z0-9-]*$").unwrap());
+
+/// Color names, used as CSS custom properties by the frontend.
+static COLOR_NAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z][a-z0-9-]{0,63}$").unwrap());
+
+/// Hex colors: `#rgb`, `#rrggbb` or `#rrggbbaa`.
+static COLOR_VALUE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^#([0-9a-fA-F]{3}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$").unwrap());
+
+/// Themes shipped with the application. Their colors come from the frontend.
+const BUILTIN_THEMES: [(&str, &str, ThemeBase); 3] = [
+    ("light", "Light", ThemeBase::Light),
+    ("dark", "Dark", ThemeBase::Dark),
+    ("system", "System", ThemeBase::System),
+];
+
+/// Where a theme was loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeSource {
+    Builtin,
+    User,
+}
+
+/// Built-in palette a theme starts from before applying its own colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeBase {
+    Light,
+    Dark,
+    System,
+}
+
+/// A theme available for selection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Theme {
+    pub id: String,
+    pub name: String,
+    pub source: ThemeSource,
+    pub base: ThemeBase,
+    pub colors: BTreeMap<String, String>,
+}
+
+/// A theme file that failed validation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidTheme {
+    pub file: String,
+    pub error: String,
+}
+
+/// Result of scanning for themes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeListing {
+    pub directory: String,
+    pub themes: Vec<Theme>,
+    pub invalid: Vec<InvalidTheme>,
+}
+
+/// On-disk format of a user theme file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    name: String,
+    #[serde(default = "default_base")]
+    base: ThemeBase,
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+}
+
+fn default_base() -> ThemeBase {
+    ThemeBase::Light
+}
+
+/// Directory scanned for user theme files.
+pub fn themes_dir() -> PathBuf {
+    logging::app_data_dir("themes")
+}
+
+/// Lists built-in themes followed by the valid user themes.
+pub fn list() -> AppResult<ThemeListing> {
+    list_in(&themes_dir())
+}
+
+/// Looks up a theme by id.
+pub fn find(id: &str) -> AppResult<Theme> {
+    find_in(&themes_dir(), id)
+}
+
+fn builtin_themes() -> impl Iterator<Item = Theme> {
+    BUILTIN_THEMES.into_iter().map(|(id, name, base)| Theme {
+        id: id.to_string(),
+        name: name.to_string(),
+        source: ThemeSource::Builtin,
+        base,
+        colors: BTreeMap::new(),
+    })
+}
+
+fn list_in(dir: &Path) -> AppResult<ThemeListing> {
+    let mut themes: Vec<Theme> = builtin_themes().collect();
+    let mut invalid = Vec::new();
+
+    if dir.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .into_app_error(ErrorCode::FileRead)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+
+        for path in files {
+            let file = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            match load_theme(&path) {
+                Ok(theme) => themes.push(theme),
+                Err(e) => invalid.push(InvalidTheme {
+                    file,
+                    error: e.message,
+                }),
+            }
+        }
+    }
+
+    Ok(ThemeListing {
+        directory: dir.to_string_lossy().to_string(),
+        themes,
+        invalid,
+    })
+}
+
+fn find_in(dir: &Path, id: &str) -> AppResult<Theme> {
+    let id = validate_theme_id(id)?;
+    if let Some(theme) = builtin_themes().find(|theme| theme.id == id) {
+        return Ok(theme);
+    }
+
+    let path = dir.join(format!("{}.json", id));
+    if !path.is_file() {
+        return Err(AppError::not_found("Theme"));
+    }
+    load_theme(&path)
+}
+
+/// Reads and validates a user theme file.
+fn load_theme(path: &Path) -> AppResult<Theme> {
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let id = validate_theme_id(&id)?;
+    if builtin_themes().any(|theme| theme.id == id) {
+        return Err(AppError::invalid_input(
+            "id",
+            format!("'{}' is reserved for a built-in theme", id),
+        ));
+    }
+
+    let metadata = fs::metadata(path).into_app_error(ErrorCode::FileRead)?;
+    if metadata.len() > MAX_THEME_FILE_BYTES {
+        return Err(AppError::invalid_input(
+            "file",
+            format!("Theme files cannot exceed {} bytes", MAX_THEME_FILE_BYTES),
+        ));
+    }
+    let contents = fs::read_to_string(path).into_app_error(ErrorCode::FileRead)?;
+    parse_theme(&id, &contents)
+}
+
+fn parse_theme(id: &str, contents: &str) -> AppResult<Theme> {
+    let file: ThemeFile = serde_json::from_str(contents)
+        .map_err(|e| AppError::invalid_input("file", format!("Invalid theme file: {}", e)))?;
+
+    let name = file.name.trim();
+    if name.is_empty() || name.chars().count() > 50 {
+        return Err(AppError::invalid_input(
+            "name",
+            "Theme name must be between 1 and 50 characters",
+        ));
+    }
+    if file.colors.len() > MAX_THEME_COLORS {
+        return Err(AppError::invalid_input(
+            "colors",
+            format!(
+                "A theme cannot define more than {} colors",
+                MAX_THEME_COLORS
+            ),
+        ));
+    }
+    for (color, value) in &file.colors {
+        if !COLOR_NAME_REGEX.is_match(color) {
+            return Err(AppError::invalid_input(
+                "colors",
+                format!("Invalid color name '{}'", color),
+            ));
+        }
+        if !COLOR_VALUE_REGEX.is_match(value) {
+            return Err(AppError::invalid_input(
+                "colors",
+                format!("Color '{}' must be a hex value such as #1e293b", color),
+            ));
+        }
+    }
+
+    Ok(Theme {
+        id: id.to_string(),
+        name: name.to_string(),
+        source: ThemeSource::User,
+        base: file.base,
+        colors: file.colors,
+    })
+}
+
+fn validate_theme_id(id: &str) -> AppResult<String> {
+    let id = id.trim();
+    if id.len() > MAX_THEME_ID_LEN || !THEME_ID_REGEX.is_match(id) {
+        return Err(AppError::invalid_input(
+            "themeId",
+            format!(
+                "Theme ids must be up to {} lowercase letters, digits or dashes",
+                MAX_THEME_ID_LEN
+            ),
+        ));
+    }
+    Ok(id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, file: &str, contents: &str) {
+        fs::write(dir.join(file), contents).unwrap();
+    }
+
+    #[test]
+    fn lists_builtin_and_valid_user_themes() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "ocean.json",
+            r##"{ "name": "Ocean", "base": "dark", "colors": { "primary": "#0ea5e9" } }"##,
+        );
+        write(dir.path(), "broken.json", r#"{ "name": "#);
+        write(dir.path(), "dark.json", r#"{ "name": "Shadow" }"#);
+        write(dir.path(), "notes.txt", "ignored");
+
+        let listing = list_in(dir.path()).unwrap();
+        let ids: Vec<&str> = listing.themes.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["light", "dark", "system", "ocean"]);
+        assert_eq!(listing.themes[3].base, ThemeBase::Dark);
+
+        let invalid: Vec<&str> = listing.invalid.iter().map(|t| t.file.as_str()).collect();
+        assert_eq!(invalid, ["broken.json", "dark.json"]);
+    }
+
+    #[test]
+    fn missing_directory_lists_builtins_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let listing = list_in(&dir.path().join("missing")).unwrap();
+        assert_eq!(listing.themes.len(), BUILTIN_THEMES.len());
+        assert!(listing.invalid.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_colors_and_names() {
+        assert!(parse_theme("a", r#"{ "name": "A", "colors": { "primary": "red" } }"#).is_err());
+        assert!(parse_theme("a", r##"{ "name": "A", "colors": { "Primary": "#fff" } }"##).is_err());
+        assert!(parse_theme("a", r#"{ "name": " " }"#).is_err());
+        assert!(parse_theme("a", r#"{ "name": "A", "script": "x" }"#).is_err());
+        assert!(parse_theme(
+            "a",
+            r##"{ "name": "A", "colors": { "bg-muted": "#11223344" } }"##
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn find_is_confined_to_the_themes_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "ocean.json", r#"{ "name": "Ocean" }"#);
+
+        assert_eq!(
+            find_in(dir.path(), "ocean").unwrap().source,
+            ThemeSource::User
+        );
+        assert_eq!(
+            find_in(dir.path(), "dark").unwrap().source,
+            ThemeSource::Builtin
+        );
+        assert!(find_in(dir.path(), "../ocean").is_err());
+        assert!(find_in(dir.path(), "missing").is_err());
+    }
+}
@@ -0,0 +1,723 @@
+//! Encrypted file transfer between peers found by [`crate::discovery`].
+//!
+//! While discovery runs, a TCP listener accepts transfers on the port
+//! advertised in the service's `transfer` TXT record. Each connection does an
+//! X25519 key exchange and then exchanges length-prefixed frames sealed with
+//! ChaCha20-Poly1305, so file contents are never readable on the wire. The
+//! exchange is not authenticated: the receiving user accepting or declining
+//! the offer (`transfer:offered`, answered with `respond_to_transfer`) is
+//! what decides whether a file is written.
+//!
+//! Senders can only offer files inside the filesystem scope, and accepted
+//! files are written to the `received` directory inside it. Progress and
+//! completion are published as `transfer:progress` and `transfer:finished`
+//! on both sides.
+
+use crate::discovery;
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::events::{self, AppEvent};
+use crate::file_drop::unique_destination;
+use crate::handlers::filesystem::{
+    build_file_info, ensure_write_permitted, filesystem_root, resolve_existing_path,
+};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+/// Handshake preamble; also mixed into the session key.
+const PROTOCOL: &[u8] = b"eztauri-transfer-v1";
+
+/// Scope-relative directory accepted files are written to.
+const RECEIVE_DIR: &str = "received";
+
+/// Plaintext bytes per data frame.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Largest encrypted frame accepted; bounds memory per connection.
+const MAX_FRAME_BYTES: usize = CHUNK_BYTES + 1024;
+
+/// Largest file that can be offered or accepted.
+const MAX_TRANSFER_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// How long an offer waits for the receiving user to answer.
+const DECISION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Timeout for connecting and for each frame once the transfer is running.
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Offers awaiting an answer at once; further offers are declined.
+const MAX_PENDING_OFFERS: usize = 10;
+
+/// Bytes between progress events.
+const PROGRESS_STEP_BYTES: u64 = 1024 * 1024;
+
+static PENDING: Lazy<Mutex<HashMap<Uuid, PendingOffer>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct PendingOffer {
+    offer: TransferOffer,
+    respond: oneshot::Sender<bool>,
+}
+
+/// Which side of a transfer an event describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Send,
+    Receive,
+}
+
+/// An incoming file waiting for the user to accept or decline it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferOffer {
+    pub id: Uuid,
+    pub name: String,
+    pub size: u64,
+    /// Host name the sender reported.
+    pub from: String,
+    pub address: String,
+}
+
+/// A transfer started by [`send_to_peer`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutgoingTransfer {
+    pub id: Uuid,
+    pub peer_id: String,
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Offer {
+    id: Uuid,
+    name: String,
+    size: u64,
+    from: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Reply {
+    ok: bool,
+    reason: Option<String>,
+}
+
+/// Starts accepting transfers on an ephemeral port. Returns the port and
+/// the listener task, which the caller aborts to stop listening.
+pub fn start_listener() -> AppResult<(u16, JoinHandle<()>)> {
+    let listener = std::net::TcpListener::bind(("0.0.0.0", 0))
+        .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        .map_err(|e| network_error("Failed to open transfer listener", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| network_error("Failed to open transfer listener", e))?
+        .port();
+
+    let task = tauri::async_runtime::spawn(async move {
+        let listener = match tokio::net::TcpListener::from_std(listener) {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::warn!("Transfer listener failed: {}", e);
+                return;
+            }
+        };
+        loop {
+            match listener.accept().await {
+                Ok((stream, address)) => {
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = receive(stream, address).await {
+                            tracing::warn!("Incoming transfer from {} failed: {}", address, e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept transfer connection: {}", e),
+            }
+        }
+    });
+    tracing::debug!("Accepting transfers on port {}", port);
+    Ok((port, task))
+}
+
+/// Offers the scoped file at `path` to a discovered peer.
+///
+/// Returns once the transfer is started; the outcome arrives as
+/// `transfer:finished`.
+pub fn send_to_peer(peer_id: &str, path: &str) -> AppResult<OutgoingTransfer> {
+    let peer = discovery::peers()
+        .into_iter()
+        .find(|peer| peer.id == peer_id)
+        .ok_or_else(|| AppError::not_found("Peer"))?;
+    let port = peer
+        .transfer_port
+        .ok_or_else(|| AppError::invalid_input("peerId", "Peer does not accept file transfers"))?;
+
+    let context = resolve_existing_path(path).map_err(|e| AppError::file_error("read", path, e))?;
+    let metadata = std::fs::metadata(&context.path)
+        .map_err(|e| AppError::file_error("read", context.relative_display(), e.to_string()))?;
+    if !metadata.is_file() {
+        return Err(AppError::invalid_input("path", "Only files can be sent"));
+    }
+    if metadata.len() > MAX_TRANSFER_BYTES {
+        return Err(AppError::invalid_input(
+            "path",
+            format!(
+                "Files larger than {} bytes cannot be sent",
+                MAX_TRANSFER_BYTES
+            ),
+        ));
+    }
+
+    let transfer = OutgoingTransfer {
+        id: Uuid::new_v4(),
+        peer_id: peer.id.clone(),
+        name: context
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size: metadata.len(),
+    };
+    let addresses: Vec<SocketAddr> = peer
+        .addresses
+        .iter()
+        .filter_map(|address| address.parse().ok())
+        .map(|ip| SocketAddr::new(ip, port))
+        .collect();
+
+    let outgoing = transfer.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = send(&outgoing, &addresses, &context.path).await;
+        if let Err(e) = &result {
+            tracing::warn!(
+                "Transfer {} to {} failed: {}",
+                outgoing.id,
+                outgoing.peer_id,
+                e
+            );
+        }
+        finish(outgoing.id, TransferDirection::Send, None, result.err());
+    });
+    Ok(transfer)
+}
+
+/// Answers a pending offer. Returns whether the offer was still waiting.
+pub fn respond(transfer_id: Uuid, accept: bool) -> bool {
+    let pending = PENDING
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.remove(&transfer_id));
+    match pending {
+        Some(pending) => pending.respond.send(accept).is_ok(),
+        None => false,
+    }
+}
+
+/// Returns offers waiting for an answer.
+pub fn pending_offers() -> Vec<TransferOffer> {
+    PENDING
+        .lock()
+        .map(|pending| pending.values().map(|p| p.offer.clone()).collect())
+        .unwrap_or_default()
+}
+
+async fn send(transfer: &OutgoingTransfer, addresses: &[SocketAddr], path: &Path) -> AppResult<()> {
+    let stream = connect(addresses).await?;
+    let mut channel = SecureChannel::handshake(stream, true).await?;
+    channel
+        .send_json(&Offer {
+            id: transfer.id,
+            name: transfer.name.clone(),
+            size: transfer.size,
+            from: hostname::get()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        })
+        .await?;
+
+    let decision: Reply = tokio::time::timeout(DECISION_TIMEOUT + IO_TIMEOUT, channel.recv_json())
+        .await
+        .map_err(|_| network_error("Peer did not answer", "timed out"))??;
+    if !decision.ok {
+        return Err(AppError::new(
+            ErrorCode::Forbidden,
+            decision
+                .reason
+                .unwrap_or_else(|| "Peer declined the transfer".to_string()),
+        ));
+    }
+
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| AppError::file_error("read", transfer.name.clone(), e.to_string()))?;
+    let mut buffer = vec![0u8; CHUNK_BYTES];
+    let mut progress = Progress::new(transfer.id, TransferDirection::Send, transfer.size);
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| AppError::file_error("read", transfer.name.clone(), e.to_string()))?;
+        if read == 0 {
+            break;
+        }
+        channel.send(&buffer[..read]).await?;
+        progress.advance(read as u64);
+    }
+    channel.send(&[]).await?;
+    progress.finish();
+
+    let receipt: Reply = channel.recv_json().await?;
+    if receipt.ok {
+        Ok(())
+    } else {
+        Err(network_error(
+            "Peer rejected the file",
+            receipt.reason.unwrap_or_default(),
+        ))
+    }
+}
+
+async fn connect(addresses: &[SocketAddr]) -> AppResult<TcpStream> {
+    let mut last_error = "peer has no addresses".to_string();
+    for address in addresses {
+        match tokio::time::timeout(IO_TIMEOUT, TcpStream::connect(address)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => last_error = e.to_string(),
+            Err(_) => last_error = format!("timed out connecting to {}", address),
+        }
+    }
+    Err(network_error("Failed to reach peer", last_error))
+}
+
+async fn receive(stream: TcpStream, address: SocketAddr) -> AppResult<()> {
+    let mut channel = tokio::time::timeout(IO_TIMEOUT, SecureChannel::handshake(stream, false))
+        .await
+        .map_err(|_| network_error("Handshake failed", "timed out"))??;
+    let offer: Offer = channel.recv_json().await?;
+
+    let name = match sanitize_name(&offer.name) {
+        Some(name) if offer.size <= MAX_TRANSFER_BYTES => name,
+        _ => {
+            let reason = "Offer has an invalid name or is too large";
+            channel.send_json(&Reply::declined(reason)).await?;
+            return Err(AppError::invalid_input("offer", reason));
+        }
+    };
+    if let Err(e) = ensure_write_permitted(Path::new(&name)) {
+        channel.send_json(&Reply::declined(&e.message)).await?;
+        return Err(e);
+    }
+    let offer = TransferOffer {
+        id: offer.id,
+        name,
+        size: offer.size,
+        from: offer.from.chars().take(255).collect(),
+        address: address.ip().to_string(),
+    };
+
+    let accepted = match queue_offer(offer.clone()) {
+        Some(answer) => {
+            events::publish(AppEvent::TransferOffered(offer.clone()));
+            let answer = tokio::time::timeout(DECISION_TIMEOUT, answer).await;
+            if let Ok(mut pending) = PENDING.lock() {
+                pending.remove(&offer.id);
+            }
+            matches!(answer, Ok(Ok(true)))
+        }
+        None => false,
+    };
+    if !accepted {
+        channel
+            .send_json(&Reply::declined("The transfer was declined"))
+            .await?;
+        finish(
+            offer.id,
+            TransferDirection::Receive,
+            None,
+            Some(AppError::new(ErrorCode::Forbidden, "Transfer declined")),
+        );
+        return Ok(());
+    }
+    channel.send_json(&Reply::accepted()).await?;
+
+    let result = write_incoming(&mut channel, &offer).await;
+    let reply = match &result {
+        Ok(_) => Reply::accepted(),
+        Err(e) => Reply::declined(&e.message),
+    };
+    let _ = channel.send_json(&reply).await;
+    match result {
+        Ok(path) => finish(offer.id, TransferDirection::Receive, Some(path), None),
+        Err(e) => finish(offer.id, TransferDirection::Receive, None, Some(e)),
+    }
+    Ok(())
+}
+
+/// Registers `offer`, returning the channel its answer arrives on, or
+/// `None` when too many offers are already waiting.
+fn queue_offer(offer: TransferOffer) -> Option<oneshot::Receiver<bool>> {
+    let mut pending = PENDING.lock().ok()?;
+    if pending.len() >= MAX_PENDING_OFFERS {
+        tracing::warn!(
+            "Declining transfer '{}': too many pending offers",
+            offer.name
+        );
+        return None;
+    }
+    let (respond, answer) = oneshot::channel();
+    pending.insert(offer.id, PendingOffer { offer, respond });
+    Some(answer)
+}
+
+/// Streams the file into a `.part` file and moves it into place once the
+/// announced size has arrived. Returns the scope-relative path.
+async fn write_incoming<S>(
+    channel: &mut SecureChannel<S>,
+    offer: &TransferOffer,
+) -> AppResult<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let root = filesystem_root().map_err(|e| AppError::file_error("write", RECEIVE_DIR, e))?;
+    let dir = root.join(RECEIVE_DIR);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| AppError::file_error("create", RECEIVE_DIR, e.to_string()))?;
+    let destination = unique_destination(&dir, Path::new(&offer.name));
+    let partial = PathBuf::from(format!("{}.part", destination.display()));
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&partial)
+            .await
+            .map_err(|e| AppError::file_error("write", offer.name.clone(), e.to_string()))?;
+        let mut progress = Progress::new(offer.id, TransferDirection::Receive, offer.size);
+        let mut received = 0u64;
+        loop {
+            let chunk = channel.recv().await?;
+            if chunk.is_empty() {
+                break;
+            }
+            received += chunk.len() as u64;
+            if received > offer.size {
+                return Err(network_error(
+                    "Transfer failed",
+                    "sender exceeded the offered size",
+                ));
+            }
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| AppError::file_error("write", offer.name.clone(), e.to_string()))?;
+            progress.advance(chunk.len() as u64);
+        }
+        if received != offer.size {
+            return Err(network_error("Transfer failed", "file ended early"));
+        }
+        file.sync_all()
+            .await
+            .map_err(|e| AppError::file_error("write", offer.name.clone(), e.to_string()))?;
+        progress.finish();
+        tokio::fs::rename(&partial, &destination)
+            .await
+            .map_err(|e| AppError::file_error("write", offer.name.clone(), e.to_string()))
+    }
+    .await;
+
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e);
+    }
+    let metadata = std::fs::metadata(&destination)
+        .map_err(|e| AppError::file_error("read", offer.name.clone(), e.to_string()))?;
+    Ok(build_file_info(&destination, metadata, &root).path)
+}
+
+fn finish(id: Uuid, direction: TransferDirection, path: Option<String>, error: Option<AppError>) {
+    events::publish(AppEvent::TransferFinished {
+        transfer_id: id,
+        direction,
+        success: error.is_none(),
+        path,
+        message: error.map(|e| e.message),
+    });
+}
+
+/// Keeps only the final path component of an offered name, with spaces
+/// replaced because scope paths cannot contain them.
+fn sanitize_name(name: &str) -> Option<String> {
+    let name = name
+        .rsplit(|c| c == '/' || c == '\\')
+        .next()?
+        .trim()
+        .replace(' ', "-");
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && name.len() <= 255
+        && !name.chars().any(char::is_control);
+    valid.then_some(name)
+}
+
+fn network_error(context: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::new(ErrorCode::NetworkError, format!("{}: {}", context, e))
+}
+
+impl Reply {
+    fn accepted() -> Self {
+        Self {
+            ok: true,
+            reason: None,
+        }
+    }
+
+    fn declined(reason: &str) -> Self {
+        Self {
+            ok: false,
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Publishes throttled progress events.
+struct Progress {
+    id: Uuid,
+    direction: TransferDirection,
+    total: u64,
+    transferred: u64,
+    reported: u64,
+}
+
+impl Progress {
+    fn new(id: Uuid, direction: TransferDirection, total: u64) -> Self {
+        Self {
+            id,
+            direction,
+            total,
+            transferred: 0,
+            reported: 0,
+        }
+    }
+
+    fn advance(&mut self, bytes: u64) {
+        self.transferred += bytes;
+        if self.transferred - self.reported >= PROGRESS_STEP_BYTES {
+            self.publish();
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.reported != self.transferred || self.total == 0 {
+            self.publish();
+        }
+    }
+
+    fn publish(&mut self) {
+        self.reported = self.transferred;
+        events::publish(AppEvent::TransferProgress {
+            transfer_id: self.id,
+            direction: self.direction,
+            transferred: self.transferred,
+            total: self.total,
+        });
+    }
+}
+
+/// Length-prefixed frames sealed with a key agreed over X25519.
+struct SecureChannel<S> {
+    stream: S,
+    cipher: ChaCha20Poly1305,
+    initiator: bool,
+    sent: u64,
+    received: u64,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SecureChannel<S> {
+    async fn handshake(mut stream: S, initiator: bool) -> AppResult<Self> {
+        let secret = EphemeralSecret::random();
+        let public = PublicKey::from(&secret);
+
+        let mut hello = PROTOCOL.to_vec();
+        hello.extend_from_slice(public.as_bytes());
+        stream
+            .write_all(&hello)
+            .await
+            .map_err(|e| network_error("Handshake failed", e))?;
+
+        let mut reply = vec![0u8; PROTOCOL.len() + 32];
+        stream
+            .read_exact(&mut reply)
+            .await
+            .map_err(|e| network_error("Handshake failed", e))?;
+        if !reply.starts_with(PROTOCOL) {
+            return Err(network_error("Handshake failed", "unsupported protocol"));
+        }
+        let mut peer = [0u8; 32];
+        peer.copy_from_slice(&reply[PROTOCOL.len()..]);
+
+        let shared = secret.diffie_hellman(&PublicKey::from(peer));
+        let (first, second) = if initiator {
+            (*public.as_bytes(), peer)
+        } else {
+            (peer, *public.as_bytes())
+        };
+        let key = Sha256::new()
+            .chain_update(PROTOCOL)
+            .chain_update(shared.as_bytes())
+            .chain_update(first)
+            .chain_update(second)
+            .finalize();
+
+        Ok(Self {
+            stream,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+            initiator,
+            sent: 0,
+            received: 0,
+        })
+    }
+
+    /// Each direction has its own counter, and the first nonce byte says
+    /// which side sealed the frame, so nonces never repeat under one key.
+    fn nonce(from_initiator: bool, counter: u64) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[0] = u8::from(from_initiator);
+        nonce[4..].copy_from_slice(&counter.to_be_bytes());
+        nonce
+    }
+
+    async fn send(&mut self, plaintext: &[u8]) -> AppResult<()> {
+        let nonce = Self::nonce(self.initiator, self.sent);
+        self.sent += 1;
+        let frame = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| AppError::internal_error("Failed to encrypt transfer frame"))?;
+
+        let write = async {
+            self.stream
+                .write_all(&(frame.len() as u32).to_be_bytes())
+                .await?;
+            self.stream.write_all(&frame).await?;
+            self.stream.flush().await
+        };
+        tokio::time::timeout(IO_TIMEOUT, write)
+            .await
+            .map_err(|_| network_error("Transfer stalled", "timed out"))?
+            .map_err(|e| network_error("Connection lost", e))
+    }
+
+    async fn recv(&mut self) -> AppResult<Vec<u8>> {
+        let read = async {
+            let mut length = [0u8; 4];
+            self.stream.read_exact(&mut length).await?;
+            let length = u32::from_be_bytes(length) as usize;
+            if length > MAX_FRAME_BYTES {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "frame too large",
+                ));
+            }
+            let mut frame = vec![0u8; length];
+            self.stream.read_exact(&mut frame).await?;
+            Ok::<_, std::io::Error>(frame)
+        };
+        let frame = tokio::time::timeout(IO_TIMEOUT, read)
+            .await
+            .map_err(|_| network_error("Transfer stalled", "timed out"))?
+            .map_err(|e| network_error("Connection lost", e))?;
+
+        let nonce = Self::nonce(!self.initiator, self.received);
+        self.received += 1;
+        self.cipher
+            .decrypt(Nonce::from_slice(&nonce), frame.as_slice())
+            .map_err(|_| network_error("Transfer failed", "frame could not be authenticated"))
+    }
+
+    async fn send_json<T: Serialize>(&mut self, value: &T) -> AppResult<()> {
+        let bytes =
+            serde_json::to_vec(value).map_err(|e| AppError::internal_error(e.to_string()))?;
+        self.send(&bytes).await
+    }
+
+    async fn recv_json<T: DeserializeOwned>(&mut self) -> AppResult<T> {
+        let bytes = self.recv().await?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| network_error("Transfer failed", format!("invalid message: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn connected_pair() -> (
+        SecureChannel<tokio::io::DuplexStream>,
+        SecureChannel<tokio::io::DuplexStream>,
+    ) {
+        let (a, b) = tokio::io::duplex(MAX_FRAME_BYTES * 2);
+        let (sender, receiver) = tokio::join!(
+            SecureChannel::handshake(a, true),
+            SecureChannel::handshake(b, false)
+        );
+        (sender.unwrap(), receiver.unwrap())
+    }
+
+    #[tokio::test]
+    async fn frames_round_trip_in_both_directions() {
+        let (mut sender, mut receiver) = connected_pair().await;
+
+        sender.send(b"hello").await.unwrap();
+        sender.send(&[]).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), b"hello");
+        assert!(receiver.recv().await.unwrap().is_empty());
+
+        receiver.send_json(&Reply::declined("busy")).await.unwrap();
+        let reply: Reply = sender.recv_json().await.unwrap();
+        assert!(!reply.ok);
+        assert_eq!(reply.reason.as_deref(), Some("busy"));
+    }
+
+    #[tokio::test]
+    async fn tampered_frames_are_rejected() {
+        let (mut sender, mut receiver) = connected_pair().await;
+        let nonce = SecureChannel::<tokio::io::DuplexStream>::nonce(true, 0);
+        let mut frame = sender
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), b"secret".as_slice())
+            .unwrap();
+        frame[0] ^= 1;
+        sender
+            .stream
+            .write_all(&(frame.len() as u32).to_be_bytes())
+            .await
+            .unwrap();
+        sender.stream.write_all(&frame).await.unwrap();
+
+        assert!(receiver.recv().await.is_err());
+    }
+
+    #[test]
+    fn offered_names_are_reduced_to_a_file_name() {
+        assert_eq!(sanitize_name("report.pdf").as_deref(), Some("report.pdf"));
+        assert_eq!(sanitize_name("../../etc/passwd").as_deref(), Some("passwd"));
+        assert_eq!(
+            sanitize_name("C:\\Users\\me\\my file.txt").as_deref(),
+            Some("my-file.txt")
+        );
+        assert!(sanitize_name("..").is_none());
+        assert!(sanitize_name("dir/").is_none());
+        assert!(sanitize_name("bad\u{0}name").is_none());
+    }
+}
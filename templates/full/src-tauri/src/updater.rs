@@ -0,0 +1,146 @@
+//! Auto-update integration built on the Tauri updater plugin.
+//!
+//! Updates are checked against the channel selected in configuration,
+//! downloaded with progress published on the event bus, and installed on
+//! request followed by an application restart.
+
+use crate::config::{self, UpdateChannel};
+use crate::errors::{AppError, AppResult, ErrorCode};
+use crate::events::{self, AppEvent};
+use crate::proxy;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tokio::sync::Mutex;
+
+/// Update found by the most recent check, if any.
+static PENDING_UPDATE: Lazy<Mutex<Option<Update>>> = Lazy::new(|| Mutex::new(None));
+
+/// Downloaded update bundle waiting to be installed.
+static DOWNLOADED_BYTES: Lazy<Mutex<Option<Vec<u8>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Information about an available update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub channel: UpdateChannel,
+    pub current_version: String,
+    pub version: Option<String>,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+/// Checks the configured channel for a newer version.
+pub async fn check(app: &AppHandle) -> AppResult<UpdateInfo> {
+    let config = config::current();
+    let mut builder = app.updater_builder();
+    let mut detected_proxy = proxy::current().https;
+
+    if let Some(template) = &config.update_endpoint {
+        let endpoint = template.replace("{{channel}}", config.update_channel.as_str());
+        let url: reqwest::Url = endpoint
+            .parse()
+            .map_err(|e| AppError::new(ErrorCode::ConfigurationError, format!("Invalid update endpoint: {}", e)))?;
+        detected_proxy = proxy::proxy_for(&url);
+        builder = builder
+            .endpoints(vec![url])
+            .map_err(|e| AppError::new(ErrorCode::ConfigurationError, e.to_string()))?;
+    }
+
+    if let Some(proxy) = detected_proxy.and_then(|proxy| proxy.parse().ok()) {
+        builder = builder.proxy(proxy);
+    }
+
+    let updater = builder
+        .build()
+        .map_err(|e| AppError::new(ErrorCode::ConfigurationError, format!("Failed to build updater: {}", e)))?;
+
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| AppError::new(ErrorCode::NetworkError, format!("Failed to check for updates: {}", e)))?;
+
+    let current_version = app.package_info().version.to_string();
+    let info = match &update {
+        Some(update) => UpdateInfo {
+            available: true,
+            channel: config.update_channel.clone(),
+            current_version,
+            version: Some(update.version.clone()),
+            notes: update.body.clone(),
+            date: update.date.map(|date| date.to_string()),
+        },
+        None => UpdateInfo {
+            available: false,
+            channel: config.update_channel.clone(),
+            current_version,
+            version: None,
+            notes: None,
+            date: None,
+        },
+    };
+
+    *PENDING_UPDATE.lock().await = update;
+    *DOWNLOADED_BYTES.lock().await = None;
+
+    Ok(info)
+}
+
+/// Downloads the pending update, publishing progress events as chunks arrive.
+pub async fn download() -> AppResult<usize> {
+    let guard = PENDING_UPDATE.lock().await;
+    let update = guard
+        .as_ref()
+        .ok_or_else(|| AppError::validation_error("No update available. Check for updates first."))?;
+
+    let version = update.version.clone();
+    let mut downloaded: u64 = 0;
+
+    let bytes = update
+        .download(
+            |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                events::publish(AppEvent::UpdateDownloadProgress {
+                    version: version.clone(),
+                    downloaded,
+                    total: content_length,
+                });
+            },
+            || {
+                tracing::info!("Update download finished");
+            },
+        )
+        .await
+        .map_err(|e| AppError::new(ErrorCode::NetworkError, format!("Failed to download update: {}", e)))?;
+
+    let size = bytes.len();
+    *DOWNLOADED_BYTES.lock().await = Some(bytes);
+    events::publish(AppEvent::UpdateDownloaded {
+        version: update.version.clone(),
+    });
+
+    Ok(size)
+}
+
+/// Installs the downloaded update and restarts the application.
+pub async fn install_and_restart(app: &AppHandle) -> AppResult<()> {
+    let update_guard = PENDING_UPDATE.lock().await;
+    let update = update_guard
+        .as_ref()
+        .ok_or_else(|| AppError::validation_error("No update available. Check for updates first."))?;
+
+    let bytes = DOWNLOADED_BYTES
+        .lock()
+        .await
+        .take()
+        .ok_or_else(|| AppError::validation_error("Update has not been downloaded yet."))?;
+
+    update
+        .install(bytes)
+        .map_err(|e| AppError::new(ErrorCode::SystemError, format!("Failed to install update: {}", e)))?;
+
+    tracing::info!("Update {} installed, restarting", update.version);
+    app.restart()
+}
@@ -1,3 +1,4 @@
+use crate::config::PasswordPolicy;
 use regex::Regex;
 use std::sync::LazyLock;
 
@@ -20,6 +21,27 @@ static NAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^[a-zA-Z\s'-]{1,100}$").unwrap()
 });
 
+/// Log tag validation regex pattern (lowercase alphanumerics and `_-.:`).
+static LOG_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[a-z0-9][a-z0-9_.:-]{0,49}$").unwrap()
+});
+
+/// Maximum number of tags on one log entry.
+pub const MAX_LOG_TAGS: usize = 10;
+
+/// Maximum password length; longer input only slows down hashing.
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// Frequently used passwords, which score 0 however they are decorated.
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456", "12345678", "123456789", "1234567890", "111111", "000000",
+    "password", "qwerty", "qwertyuiop", "asdfgh", "zxcvbn", "abc123",
+    "letmein", "welcome", "monkey", "dragon", "iloveyou", "admin",
+    "administrator", "login", "master", "sunshine", "princess", "football",
+    "baseball", "shadow", "trustno1", "superman", "starwars", "whatever",
+    "freedom", "changeme", "secret", "hello", "qazwsx", "passphrase",
+];
+
 /// Dangerous patterns that indicate potential XSS or injection attacks.
 static DANGEROUS_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
     vec![
@@ -39,6 +61,8 @@ pub enum ValidationError {
     InvalidEmail,
     InvalidUsername,
     InvalidName,
+    InvalidTag,
+    WeakPassword(String),
     TooLong(usize),
     ContainsDangerousContent,
     Empty,
@@ -50,6 +74,8 @@ impl std::fmt::Display for ValidationError {
             ValidationError::InvalidEmail => write!(f, "Invalid email format"),
             ValidationError::InvalidUsername => write!(f, "Username must be 3-50 chars, alphanumeric and underscores only"),
             ValidationError::InvalidName => write!(f, "Name contains invalid characters"),
+            ValidationError::InvalidTag => write!(f, "Tags must be 1-50 chars: letters, digits, '_', '-', '.' or ':'"),
+            ValidationError::WeakPassword(reason) => write!(f, "{}", reason),
             ValidationError::TooLong(max) => write!(f, "Input exceeds maximum length of {}", max),
             ValidationError::ContainsDangerousContent => write!(f, "Input contains potentially dangerous content"),
             ValidationError::Empty => write!(f, "Required field cannot be empty"),
@@ -154,6 +180,174 @@ pub fn validate_log_message(message: &str) -> Result<String, ValidationError> {
     }
 }
 
+/// Validate log tags, lowercasing them and dropping duplicates
+pub fn validate_log_tags(tags: &[String]) -> Result<Vec<String>, ValidationError> {
+    let mut validated: Vec<String> = Vec::with_capacity(tags.len());
+
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !LOG_TAG_REGEX.is_match(&tag) {
+            return Err(ValidationError::InvalidTag);
+        }
+        if !validated.contains(&tag) {
+            validated.push(tag);
+        }
+    }
+
+    if validated.len() > MAX_LOG_TAGS {
+        return Err(ValidationError::TooLong(MAX_LOG_TAGS));
+    }
+
+    Ok(validated)
+}
+
+/// Validate a new password against `policy`.
+///
+/// `user_inputs` are values such as the username that make a password easy
+/// to guess when it is built around them.
+pub fn validate_password(
+    password: &str,
+    policy: &PasswordPolicy,
+    user_inputs: &[&str],
+) -> Result<(), ValidationError> {
+    let length = password.chars().count();
+    if length == 0 {
+        return Err(ValidationError::Empty);
+    }
+    if length > MAX_PASSWORD_LENGTH {
+        return Err(ValidationError::TooLong(MAX_PASSWORD_LENGTH));
+    }
+    if length < policy.min_length {
+        return Err(ValidationError::WeakPassword(format!(
+            "Password must be at least {} characters",
+            policy.min_length
+        )));
+    }
+
+    let missing: Vec<&str> = [
+        (policy.require_lowercase, password.chars().any(char::is_lowercase), "a lowercase letter"),
+        (policy.require_uppercase, password.chars().any(char::is_uppercase), "an uppercase letter"),
+        (policy.require_digit, password.chars().any(|c| c.is_ascii_digit()), "a digit"),
+        (policy.require_symbol, password.chars().any(|c| !c.is_alphanumeric()), "a symbol"),
+    ]
+    .into_iter()
+    .filter(|(required, present, _)| *required && !*present)
+    .map(|(_, _, name)| name)
+    .collect();
+    if !missing.is_empty() {
+        return Err(ValidationError::WeakPassword(format!(
+            "Password must contain {}",
+            missing.join(", ")
+        )));
+    }
+
+    if password_strength(password, user_inputs) < policy.min_score {
+        return Err(ValidationError::WeakPassword(
+            "Password is too easy to guess; try a longer or less predictable one".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Estimates how hard a password is to guess, from 0 (trivial) to 4 (very hard).
+///
+/// Like zxcvbn, the score bands an estimated number of guesses at 10^3,
+/// 10^6, 10^8, and 10^10. Each character counts for the character classes in
+/// use, except characters that repeat or continue a run (`aaa`, `abc`, `321`)
+/// and occurrences of `user_inputs`, which are nearly free to guess. Common
+/// passwords score 0, including with letter substitutions (`p@ssw0rd`) or
+/// trailing digits and symbols (`password1!`).
+pub fn password_strength(password: &str, user_inputs: &[&str]) -> u8 {
+    // ASCII lowercasing keeps byte offsets aligned with `password`.
+    let lower = password.to_ascii_lowercase();
+    if is_common_password(&lower) {
+        return 0;
+    }
+
+    let mut bits = 0.0;
+    let mut covered = vec![false; lower.len()];
+    for input in user_inputs {
+        let input = input.trim().to_ascii_lowercase();
+        if input.len() < 3 {
+            continue;
+        }
+        for (start, _) in lower.match_indices(input.as_str()) {
+            covered[start..start + input.len()].fill(true);
+            bits += 1.0;
+        }
+    }
+    let remaining: String = password
+        .char_indices()
+        .filter(|(index, _)| !covered[*index])
+        .map(|(_, c)| c)
+        .collect();
+
+    let bits_per_char = (charset_size(&remaining) as f64).log2();
+    let mut previous: Option<char> = None;
+    for c in remaining.chars().map(|c| c.to_ascii_lowercase()) {
+        let continues_run = previous.is_some_and(|p| (c as i64 - p as i64).abs() <= 1);
+        bits += if continues_run { 1.0 } else { bits_per_char };
+        previous = Some(c);
+    }
+
+    match bits * std::f64::consts::LOG10_2 {
+        guesses if guesses < 3.0 => 0,
+        guesses if guesses < 6.0 => 1,
+        guesses if guesses < 8.0 => 2,
+        guesses if guesses < 10.0 => 3,
+        _ => 4,
+    }
+}
+
+/// Checks `lower` against [`COMMON_PASSWORDS`], also after dropping
+/// trailing digits and symbols and undoing letter substitutions.
+fn is_common_password(lower: &str) -> bool {
+    let stem = lower.trim_end_matches(|c: char| c.is_ascii_digit() || c.is_ascii_punctuation());
+
+    [lower, stem].into_iter().any(|candidate| {
+        COMMON_PASSWORDS.contains(&candidate)
+            || COMMON_PASSWORDS.contains(&unsubstitute(candidate).as_str())
+    })
+}
+
+/// Maps digits and symbols commonly used as letters back to those letters.
+fn unsubstitute(password: &str) -> String {
+    password
+        .chars()
+        .map(|c| match c {
+            '@' | '4' => 'a',
+            '3' => 'e',
+            '1' | '!' => 'i',
+            '0' => 'o',
+            '$' | '5' => 's',
+            '7' => 't',
+            other => other,
+        })
+        .collect()
+}
+
+/// Size of the character classes used in `password`.
+fn charset_size(password: &str) -> usize {
+    let mut size = 0;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        size += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        size += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        size += 10;
+    }
+    if password.chars().any(|c| c.is_ascii_punctuation() || c == ' ') {
+        size += 33;
+    }
+    if password.chars().any(|c| !c.is_ascii()) {
+        size += 100;
+    }
+    size.max(1)
+}
+
 /// Checks if input contains potentially dangerous content patterns.
 ///
 /// Scans for common XSS and injection patterns including script tags,
@@ -222,6 +416,51 @@ mod tests {
         assert!(validate_log_message("").is_err());
     }
 
+    #[test]
+    fn test_log_tag_validation() {
+        let tags = vec!["Billing".to_string(), " sync:push ".to_string(), "billing".to_string()];
+        assert_eq!(validate_log_tags(&tags).unwrap(), vec!["billing", "sync:push"]);
+
+        assert!(validate_log_tags(&["".to_string()]).is_err());
+        assert!(validate_log_tags(&["has space".to_string()]).is_err());
+        assert!(validate_log_tags(&["a".repeat(51)]).is_err());
+
+        let too_many: Vec<String> = (0..=MAX_LOG_TAGS).map(|i| format!("tag{}", i)).collect();
+        assert!(validate_log_tags(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_password_validation() {
+        let policy = PasswordPolicy::default();
+        assert!(validate_password("Sup3r$ecret", &policy, &[]).is_ok());
+        assert!(validate_password("correct horse battery", &policy, &[]).is_ok());
+        assert!(validate_password("", &policy, &[]).is_err());
+        assert!(validate_password("Sh0rt!", &policy, &[]).is_err());
+        assert!(validate_password(&"x".repeat(MAX_PASSWORD_LENGTH + 1), &policy, &[]).is_err());
+        assert!(validate_password("aaaaaaaaaa", &policy, &[]).is_err());
+        assert!(validate_password("P@ssw0rd123", &policy, &[]).is_err());
+        assert!(validate_password("alice2024", &policy, &["alice"]).is_err());
+
+        let strict = PasswordPolicy {
+            require_uppercase: true,
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+        let error = validate_password("plain words here", &strict, &[]).unwrap_err();
+        assert_eq!(error.to_string(), "Password must contain an uppercase letter");
+        assert!(validate_password("Plain words here", &strict, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_password_strength() {
+        assert_eq!(password_strength("password", &[]), 0);
+        assert_eq!(password_strength("Qwerty1!", &[]), 0);
+        assert_eq!(password_strength("abcdefgh", &[]), 1);
+        assert_eq!(password_strength("12341234", &[]), 1);
+        assert_eq!(password_strength("Sup3r$ecret", &[]), 4);
+        assert!(password_strength("grace_hopper_42", &["grace_hopper"]) < password_strength("grace_hopper_42", &[]));
+    }
+
     #[test]
     fn test_dangerous_content_detection() {
         let dangerous_inputs = vec![
@@ -0,0 +1,75 @@
+//! Current workspace selection.
+//!
+//! Commands that read or write users, settings, and logs scope their queries
+//! to the selected workspace. With no workspace selected every record is
+//! visible, matching single-tenant behaviour. The selection is persisted in
+//! the state store and restored on startup.
+
+use crate::errors::AppResult;
+use crate::events::{self, AppEvent};
+use crate::state_store;
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// State store key holding the selected workspace id.
+const CURRENT_KEY: &str = "workspace.current";
+
+/// Workspace subsequent commands are scoped to.
+static CURRENT: Lazy<RwLock<Option<Uuid>>> = Lazy::new(|| RwLock::new(None));
+
+/// Returns the selected workspace, if any.
+pub fn current() -> Option<Uuid> {
+    CURRENT.read().map(|current| *current).unwrap_or(None)
+}
+
+/// Selects `workspace_id` for subsequent commands and persists the choice.
+pub fn switch(workspace_id: Option<Uuid>) -> AppResult<()> {
+    if let Ok(mut current) = CURRENT.write() {
+        *current = workspace_id;
+    }
+
+    let value = workspace_id
+        .map(|id| serde_json::Value::String(id.to_string()))
+        .unwrap_or(serde_json::Value::Null);
+    state_store::set(CURRENT_KEY, value)?;
+
+    events::publish(AppEvent::WorkspaceSwitched { workspace_id });
+    Ok(())
+}
+
+/// Restores the persisted selection.
+pub fn restore() -> AppResult<Option<Uuid>> {
+    let workspace_id = state_store::get(CURRENT_KEY)?
+        .and_then(|value| value.as_str().and_then(|id| Uuid::parse_str(id).ok()));
+
+    if let Ok(mut current) = CURRENT.write() {
+        *current = workspace_id;
+    }
+    Ok(workspace_id)
+}
+
+/// Derives a URL-safe slug from a workspace name.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for ch in name.trim().chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_collapses_separators() {
+        assert_eq!(slugify("  Acme Corp "), "acme-corp");
+        assert_eq!(slugify("R&D -- Team!"), "r-d-team");
+        assert_eq!(slugify("***"), "");
+    }
+}
@@ -5,13 +5,20 @@
  * - Database connection management
  * - User CRUD operations with input validation
  * - Application logging with XSS prevention
- * - Authentication with bcrypt password handling
+ * - Authentication with Argon2id password hashing
  *
  * All user inputs are sanitized before being sent to the backend,
- * and errors are handled gracefully with context information.
+ * and errors are handled gracefully with context information. Features
+ * only the PostgreSQL backend offers fail with a clear error when the app
+ * runs on the embedded SQLite database.
  */
 
-import { safeInvoke } from '../utils/api-wrapper'
+import {
+  safeInvoke,
+  setAccessToken,
+  silentInvoke,
+} from '../utils/api-wrapper'
+import { AppError, ErrorCodes } from '../utils/error-handling'
 import {
   sanitizeEmail,
   sanitizeUsername,
@@ -20,39 +27,92 @@ import {
   sanitizeLogLevel,
 } from '../utils/sanitization'
 import type {
+  DatabaseBackend,
   DatabaseStatus,
   User,
   CreateUser,
   UpdateUser,
   LoginRequest,
+  LoginResponse,
+  OAuthLoginStart,
+  Session,
+  SessionTokens,
   AppLog,
   CreateAppLog,
+  FrontendError,
   LogQuery,
+  AuditEntry,
+  AuditQuery,
+  HashingBenchmark,
 } from '../types/database'
 
 // ==================== Database Management ====================
+
+// Backend DATABASE_URL selects, known after the first connection check
+let databaseBackend: DatabaseBackend | undefined
+
 export const checkDatabaseConnection = async (): Promise<DatabaseStatus> => {
-  return await safeInvoke<DatabaseStatus>(
+  const status = await safeInvoke<DatabaseStatus>(
     'check_database_connection',
     undefined,
     {
       context: { component: 'database', action: 'check_connection' },
     }
   )
+  databaseBackend = status.backend
+  return status
+}
+
+/**
+ * Rejects features whose commands only the PostgreSQL backend registers, so
+ * apps on the embedded SQLite database get a clear error instead of an
+ * unknown command. When the backend cannot be determined, the command runs
+ * and reports its own error.
+ */
+const requirePostgres = async (feature: string): Promise<void> => {
+  if (databaseBackend === undefined) {
+    try {
+      const status = await silentInvoke<DatabaseStatus>(
+        'check_database_connection',
+        undefined,
+        { retries: 0 }
+      )
+      databaseBackend = status.backend
+    } catch {
+      return
+    }
+  }
+  if (databaseBackend === 'sqlite') {
+    throw new AppError(
+      `${feature} needs PostgreSQL; this app uses the embedded SQLite database`,
+      ErrorCodes.CONFIGURATION_ERROR,
+      { component: 'database', action: 'require_postgres' }
+    )
+  }
 }
 
-/** Initializes the database with required tables and indexes. */
-export const initializeDatabase = async (): Promise<string> => {
-  return await safeInvoke<string>('initialize_database', undefined, {
+/**
+ * Connects the database pool. A password read from the Stronghold vault can be
+ * passed when the backend is configured with discrete DB_* settings.
+ */
+export const initializeDatabase = async (password?: string): Promise<string> => {
+  return await safeInvoke<string>('initialize_database', password ? { password } : undefined, {
     context: { component: 'database', action: 'initialize' },
   })
 }
 
-/** Runs database migrations to update schema to the latest version. */
-export const runMigrations = async (): Promise<string> => {
-  return await safeInvoke<string>('run_migrations', undefined, {
-    context: { component: 'database', action: 'run_migrations' },
-  })
+/**
+ * Runs database migrations to update schema to the latest version. Pass an
+ * `operationId` to follow progress and allow cancellation.
+ */
+export const runMigrations = async (operationId?: string): Promise<string> => {
+  return await safeInvoke<string>(
+    'run_migrations',
+    operationId ? { operationId } : undefined,
+    {
+      context: { component: 'database', action: 'run_migrations' },
+    }
+  )
 }
 
 // ==================== User Management ====================
@@ -80,7 +140,7 @@ export const createUser = async (userData: CreateUser): Promise<User> => {
   const sanitizedUserData: CreateUser = {
     email: sanitizeEmail(userData.email),
     username: sanitizeUsername(userData.username),
-    password: userData.password, // Don't sanitize password - handled by bcrypt
+    password: userData.password, // Don't sanitize password - hashed by the backend
     firstName: userData.firstName
       ? sanitizeName(userData.firstName)
       : undefined,
@@ -134,23 +194,269 @@ export const deleteUser = async (userId: string): Promise<string> => {
 
 export const authenticateUser = async (
   loginData: LoginRequest
-): Promise<User | null> => {
+): Promise<LoginResponse | null> => {
   // Sanitize login input
   const sanitizedLoginData: LoginRequest = {
     email: sanitizeEmail(loginData.email),
     password: loginData.password, // Don't sanitize password
+    deviceId: loginData.deviceId,
+    rememberMe: loginData.rememberMe,
   }
 
-  return await safeInvoke<User | null>(
+  const response = await safeInvoke<LoginResponse | null>(
     'authenticate_user',
     { loginData: sanitizedLoginData },
     {
       context: { component: 'auth', action: 'authenticate' },
     }
   )
+  if (response) {
+    setAccessToken(response.session.accessToken)
+  }
+  return response
+}
+
+/**
+ * Deletes the signed-in account once the grace period has passed, returning
+ * when. The account is signed out everywhere right away.
+ */
+export const requestAccountDeletion = async (): Promise<string> => {
+  await requirePostgres('Account deletion')
+  const scheduledAt = await safeInvoke<string>(
+    'request_account_deletion',
+    {},
+    {
+      context: { component: 'auth', action: 'request_account_deletion' },
+    }
+  )
+  setAccessToken(null)
+  return scheduledAt
+}
+
+/**
+ * Signs in to an account awaiting deletion and cancels the deletion.
+ * Resolves to `null` when the credentials match no such account.
+ */
+export const cancelAccountDeletion = async (
+  credentials: LoginRequest
+): Promise<LoginResponse | null> => {
+  await requirePostgres('Account deletion')
+  const response = await safeInvoke<LoginResponse | null>(
+    'cancel_account_deletion',
+    {
+      credentials: {
+        ...credentials,
+        email: sanitizeEmail(credentials.email),
+      },
+    },
+    {
+      context: { component: 'auth', action: 'cancel_account_deletion' },
+    }
+  )
+  if (response) {
+    setAccessToken(response.session.accessToken)
+  }
+  return response
+}
+
+/**
+ * Creates a local guest account and signs in as it, for trying the app
+ * before signing up. Requires `ALLOW_GUEST_ACCOUNTS` on the backend.
+ */
+export const createGuestUser = async (): Promise<LoginResponse> => {
+  const response = await safeInvoke<LoginResponse>(
+    'create_guest_user',
+    {},
+    {
+      context: { component: 'auth', action: 'create_guest' },
+    }
+  )
+  setAccessToken(response.session.accessToken)
+  return response
+}
+
+/**
+ * Attaches credentials to a guest account, keeping its settings and logs.
+ * When the email belongs to an existing account, the password must match it
+ * and the guest's data is merged into that account. Resolves to `null` when
+ * the account must verify its email before signing in.
+ */
+export const upgradeGuestAccount = async (
+  guestId: string,
+  credentials: CreateUser
+): Promise<LoginResponse | null> => {
+  const sanitizedCredentials: CreateUser = {
+    email: sanitizeEmail(credentials.email),
+    username: sanitizeUsername(credentials.username),
+    password: credentials.password,
+    firstName: credentials.firstName
+      ? sanitizeName(credentials.firstName)
+      : undefined,
+    lastName: credentials.lastName
+      ? sanitizeName(credentials.lastName)
+      : undefined,
+  }
+
+  const response = await safeInvoke<LoginResponse | null>(
+    'upgrade_guest_account',
+    { guestId, credentials: sanitizedCredentials },
+    {
+      context: { component: 'auth', action: 'upgrade_guest', userId: guestId },
+    }
+  )
+  if (response) {
+    setAccessToken(response.session.accessToken)
+  }
+  return response
+}
+
+export const refreshSession = async (
+  refreshToken: string
+): Promise<SessionTokens> => {
+  const tokens = await safeInvoke<SessionTokens>(
+    'refresh_session',
+    { refreshToken },
+    {
+      context: { component: 'auth', action: 'refresh_session' },
+    }
+  )
+  setAccessToken(tokens.accessToken)
+  return tokens
+}
+
+export const validateSession = async (
+  accessToken: string
+): Promise<Session> => {
+  return await safeInvoke<Session>(
+    'validate_session',
+    { accessToken },
+    {
+      context: { component: 'auth', action: 'validate_session' },
+      silent: true,
+    }
+  )
+}
+
+export const logout = async (refreshToken: string): Promise<boolean> => {
+  setAccessToken(null)
+  return await safeInvoke<boolean>(
+    'logout',
+    { refreshToken },
+    {
+      context: { component: 'auth', action: 'logout' },
+    }
+  )
+}
+
+/**
+ * Starts a 15-minute session as another user for the signed-in admin. The
+ * admin's own tokens stay active; pass the returned access token to
+ * `setAccessToken` to act as the user.
+ */
+export const impersonateUser = async (
+  adminId: string,
+  targetId: string
+): Promise<LoginResponse> => {
+  await requirePostgres('Impersonation')
+  return await safeInvoke<LoginResponse>(
+    'impersonate_user',
+    { adminId, targetId },
+    {
+      context: {
+        component: 'auth',
+        action: 'impersonate_user',
+        userId: adminId,
+      },
+    }
+  )
+}
+
+/** Ends an impersonation session, from itself or as an admin. */
+export const endImpersonation = async (
+  sessionId: string
+): Promise<boolean> => {
+  await requirePostgres('Impersonation')
+  return await safeInvoke<boolean>(
+    'end_impersonation',
+    { sessionId },
+    {
+      context: { component: 'auth', action: 'end_impersonation' },
+    }
+  )
+}
+
+/**
+ * Times password hashing on this machine and recommends an Argon2 cost that
+ * hashes within `targetMs`; with `apply`, new hashes use it. Admins only.
+ */
+export const benchmarkPasswordHashing = async (
+  targetMs?: number,
+  apply = false
+): Promise<HashingBenchmark> => {
+  await requirePostgres('Password hashing benchmarks')
+  return await safeInvoke<HashingBenchmark>(
+    'benchmark_password_hashing',
+    { targetMs, apply },
+    {
+      context: { component: 'auth', action: 'benchmark_password_hashing' },
+    }
+  )
+}
+
+export const sendVerificationEmail = async (
+  userId: string
+): Promise<string> => {
+  await requirePostgres('Email verification')
+  return await safeInvoke<string>(
+    'send_verification_email',
+    { userId },
+    {
+      context: { component: 'auth', action: 'send_verification_email' },
+    }
+  )
+}
+
+export const verifyEmail = async (token: string): Promise<User> => {
+  await requirePostgres('Email verification')
+  return await safeInvoke<User>(
+    'verify_email',
+    { token: token.trim() },
+    {
+      context: { component: 'auth', action: 'verify_email' },
+    }
+  )
+}
+
+/**
+ * Opens the configured OAuth provider's sign-in page in the browser.
+ * Pass the returned state to {@link completeOAuthLogin}.
+ */
+export const startOAuthLogin = async (): Promise<OAuthLoginStart> => {
+  await requirePostgres('OAuth sign-in')
+  return await safeInvoke<OAuthLoginStart>('start_oauth_login', undefined, {
+    context: { component: 'auth', action: 'start_oauth_login' },
+  })
+}
+
+/** Waits for the browser sign-in to finish and starts a session. */
+export const completeOAuthLogin = async (
+  state: string
+): Promise<LoginResponse> => {
+  await requirePostgres('OAuth sign-in')
+  const response = await safeInvoke<LoginResponse>(
+    'complete_oauth_login',
+    { state },
+    {
+      context: { component: 'auth', action: 'complete_oauth_login' },
+    }
+  )
+  setAccessToken(response.session.accessToken)
+  return response
 }
 
 // Logging
+
+/** Writes a log entry on behalf of any user. Requires the admin role. */
 export const createLog = async (logData: CreateAppLog): Promise<AppLog> => {
   // Sanitize log data to prevent XSS in log viewing interfaces
   const sanitizedLogData: CreateAppLog = {
@@ -173,6 +479,20 @@ export const createLog = async (logData: CreateAppLog): Promise<AppLog> => {
   )
 }
 
+/** Records a batch of frontend errors, returning how many were accepted. */
+export const ingestFrontendErrors = async (
+  errors: FrontendError[]
+): Promise<number> => {
+  return await safeInvoke<number>(
+    'ingest_frontend_errors',
+    { errors },
+    {
+      context: { component: 'logs', action: 'ingest_frontend_errors' },
+      silent: true, // Reporting errors must not raise more of them
+    }
+  )
+}
+
 export const getLogs = async (query: LogQuery = {}): Promise<AppLog[]> => {
   return await safeInvoke<AppLog[]>(
     'get_logs',
@@ -183,6 +503,7 @@ export const getLogs = async (query: LogQuery = {}): Promise<AppLog[]> => {
   )
 }
 
+/** Deletes entries older than `daysOld` days. Requires the admin role. */
 export const deleteOldLogs = async (daysOld: number): Promise<string> => {
   return await safeInvoke<string>(
     'delete_old_logs',
@@ -193,6 +514,22 @@ export const deleteOldLogs = async (daysOld: number): Promise<string> => {
   )
 }
 
+// Audit log
+
+/** Lists security-sensitive actions, newest first. Requires the admin role. */
+export const getAuditLog = async (
+  query: AuditQuery = {}
+): Promise<AuditEntry[]> => {
+  await requirePostgres('The audit log')
+  return await safeInvoke<AuditEntry[]>(
+    'get_audit_log',
+    { query },
+    {
+      context: { component: 'audit', action: 'get_all' },
+    }
+  )
+}
+
 // Utility functions
 export const logError = async (
   message: string,
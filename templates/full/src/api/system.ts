@@ -12,11 +12,18 @@
  */
 
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 import type {
   SystemInfo,
+  AppVersionInfo,
   WindowInfo,
   DirectoryListing,
   FileInfo,
+  OperationProgress,
+  MaintenanceStatus,
+  StartupStatus,
+  StartupTaskStatus,
+  CommandOptions,
 } from '../types/system'
 
 // ==================== System Information ====================
@@ -25,6 +32,25 @@ export const getSystemInfo = async (): Promise<SystemInfo> => {
   return await invoke('get_system_info')
 }
 
+/** Retrieves the app version and build details for the about screen. */
+export const getAppVersionInfo = async (): Promise<AppVersionInfo> => {
+  return await invoke('get_app_version_info')
+}
+
+/** Reports which subsystems have initialized and which failed at startup. */
+export const getStartupStatus = async (): Promise<StartupStatus> => {
+  return await invoke('get_startup_status')
+}
+
+/** Subscribes to state changes of startup tasks. */
+export const onStartupTask = async (
+  handler: (task: StartupTaskStatus) => void
+): Promise<UnlistenFn> => {
+  return await listen<StartupTaskStatus>('startup:task', event =>
+    handler(event.payload)
+  )
+}
+
 /** Gets the application's data directory path. */
 export const getAppDataDir = async (): Promise<string> => {
   return await invoke('get_app_data_dir')
@@ -73,6 +99,11 @@ export const setWindowTitle = async (title: string): Promise<string> => {
 }
 
 /** Creates a new application window with the specified label and URL. */
+/**
+ * Opens a window on an app route or an external URL. External origins must
+ * be listed in `EXTERNAL_WINDOW_ORIGINS`, otherwise this fails with
+ * `FORBIDDEN`.
+ */
 export const createNewWindow = async (
   label: string,
   url: string
@@ -82,12 +113,21 @@ export const createNewWindow = async (
 
 // ==================== Command Execution ====================
 
-/** Executes a system command from the allowlist with specified arguments. */
+/**
+ * Executes a system command from the allowlist with specified arguments.
+ * With `options`, the command sees only a small set of inherited environment
+ * variables plus `options.env`, and runs in `options.cwd` when given; without
+ * them it inherits the application's environment.
+ * Output beyond `COMMAND_OUTPUT_MAX_KB` ends in an `[output truncated: ...]`
+ * marker naming the scope-relative file that holds the full output when
+ * `COMMAND_OUTPUT_SPILL` is enabled.
+ */
 export const executeCommand = async (
   command: string,
-  args: string[] = []
+  args: string[] = [],
+  options?: CommandOptions
 ): Promise<string> => {
-  return await invoke('execute_command', { command, args })
+  return await invoke('execute_command', { command, args, options })
 }
 
 // ==================== File System Operations ====================
@@ -123,11 +163,15 @@ export const createDirectory = async (path: string): Promise<string> => {
   return await invoke('create_directory', { path })
 }
 
-/** Lists all files and directories at the specified path. */
+/**
+ * Lists all files and directories at the specified path. Pass
+ * `includeMetadata: false` to skip sizes and timestamps for faster listings.
+ */
 export const listDirectory = async (
-  path: string
+  path: string,
+  includeMetadata = true
 ): Promise<DirectoryListing> => {
-  return await invoke('list_directory', { path })
+  return await invoke('list_directory', { path, includeMetadata })
 }
 
 /** Checks if a file or directory exists at the specified path. */
@@ -140,12 +184,16 @@ export const getFileInfo = async (path: string): Promise<FileInfo> => {
   return await invoke('get_file_info', { path })
 }
 
-/** Copies a file from source to destination path. */
+/**
+ * Copies a file from source to destination path. Pass an `operationId` to
+ * follow progress with {@link onOperationProgress} and cancel the copy.
+ */
 export const copyFile = async (
   source: string,
-  destination: string
+  destination: string,
+  operationId?: string
 ): Promise<string> => {
-  return await invoke('copy_file', { source, destination })
+  return await invoke('copy_file', { source, destination, operationId })
 }
 
 /** Moves a file from source to destination path. */
@@ -156,6 +204,61 @@ export const moveFile = async (
   return await invoke('move_file', { source, destination })
 }
 
+// ==================== Long-running Operations ====================
+
+/**
+ * Requests cancellation of a running operation. Resolves to `false` when
+ * the operation is not running, e.g. because it already finished.
+ */
+export const cancelOperation = async (operationId: string): Promise<boolean> => {
+  return await invoke('cancel_operation', { operationId })
+}
+
+/** Subscribes to progress of one operation, or of all when no id is given. */
+export const onOperationProgress = async (
+  handler: (progress: OperationProgress) => void,
+  operationId?: string
+): Promise<UnlistenFn> => {
+  return await listen<OperationProgress>('operation:progress', event => {
+    if (!operationId || event.payload.operationId === operationId) {
+      handler(event.payload)
+    }
+  })
+}
+
+// ==================== Maintenance Mode ====================
+
+/**
+ * Pauses non-essential commands, which fail with `MAINTENANCE_MODE` until
+ * {@link exitMaintenanceMode} is called. Requires the admin role.
+ */
+export const enterMaintenanceMode = async (
+  reason?: string
+): Promise<MaintenanceStatus> => {
+  return await invoke('enter_maintenance_mode', { reason })
+}
+
+/**
+ * Resumes commands once no backup, restore, or migration is running.
+ * Requires the admin role.
+ */
+export const exitMaintenanceMode = async (): Promise<MaintenanceStatus> => {
+  return await invoke('exit_maintenance_mode')
+}
+
+export const getMaintenanceStatus = async (): Promise<MaintenanceStatus> => {
+  return await invoke('get_maintenance_status')
+}
+
+/** Subscribes to maintenance mode changes. */
+export const onMaintenanceChanged = async (
+  handler: (status: MaintenanceStatus) => void
+): Promise<UnlistenFn> => {
+  return await listen<MaintenanceStatus>('maintenance:changed', event =>
+    handler(event.payload)
+  )
+}
+
 // ==================== Utility Functions ====================
 
 /** Formats a file size in bytes to a human-readable string. */
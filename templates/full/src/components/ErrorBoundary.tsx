@@ -1,5 +1,5 @@
 ﻿import { Component, type ErrorInfo, type ReactNode } from 'react'
-import { logError } from '../api'
+import { ingestFrontendErrors } from '../api'
 
 interface Props {
   children: ReactNode
@@ -39,21 +39,18 @@ export class ErrorBoundary extends Component<Props, State> {
 
     // Log error to backend for analysis
     try {
-      await logError(`UI Error in ${this.props.name || 'Unknown Component'}`, {
-        error: {
-          message: error.message,
-          stack: error.stack,
-          name: error.name,
+      await ingestFrontendErrors([
+        {
+          kind: 'error',
+          message: `UI Error in ${this.props.name || 'Unknown Component'}: ${error.message}`,
+          stack: [error.stack, errorInfo.componentStack]
+            .filter(Boolean)
+            .join('\n'),
+          url: window.location.href,
+          userAgent: navigator.userAgent,
+          occurredAt: new Date().toISOString(),
         },
-        errorInfo: {
-          componentStack: errorInfo.componentStack,
-        },
-        errorId: this.state.errorId,
-        retryCount: this.state.retryCount,
-        userAgent: navigator.userAgent,
-        url: window.location.href,
-        timestamp: new Date().toISOString(),
-      })
+      ])
     } catch (loggingError) {
       console.error('Failed to log error to backend:', loggingError)
     }
@@ -68,7 +68,10 @@ export const registerSchema = z
       .string()
       .min(1, 'Last name is required')
       .max(100, 'Last name is too long'),
-    password: z.string().min(6, 'Password must be at least 6 characters'),
+    password: z
+      .string()
+      .min(8, 'Password must be at least 8 characters')
+      .max(128, 'Password must be at most 128 characters'),
     confirmPassword: z.string(),
     acceptTerms: z
       .boolean()
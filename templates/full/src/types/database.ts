@@ -7,6 +7,9 @@ export interface User {
   firstName?: string
   lastName?: string
   isActive: boolean
+  emailVerified: boolean
+  /** Local account without credentials until upgraded. */
+  isGuest: boolean
   createdAt: string
 }
 
@@ -29,6 +32,40 @@ export interface UpdateUser {
 export interface LoginRequest {
   email: string
   password: string
+  /** Stable id of this device; failed sign-ins are throttled per device. */
+  deviceId?: string
+  /** Keeps the session for days instead of the idle timeout. */
+  rememberMe?: boolean
+}
+
+export interface Session {
+  id: string
+  userId: string
+  createdAt: string
+  lastActive: string
+  expiresAt: string
+  /** Admin acting as the user, for impersonation sessions. */
+  impersonatorId: string | null
+  /** Started with "remember me"; reusing a spent refresh token revokes it. */
+  rememberMe: boolean
+}
+
+export interface SessionTokens {
+  sessionId: string
+  accessToken: string
+  accessTokenExpiresAt: string
+  refreshToken: string
+  sessionExpiresAt: string
+}
+
+export interface LoginResponse {
+  user: User
+  session: SessionTokens
+}
+
+export interface OAuthLoginStart {
+  state: string
+  authorizationUrl: string
 }
 
 export interface UserSettings {
@@ -54,6 +91,7 @@ export interface UpdateUserSettings {
   theme?: string
   language?: string
   notificationsEnabled?: boolean
+  /** JSON merge patch; `null` resets a key to its default. */
   settingsData?: Record<string, unknown>
 }
 
@@ -63,6 +101,7 @@ export interface AppLog {
   message: string
   metadata: Record<string, unknown>
   userId?: string
+  tags: string[]
   createdAt: string
 }
 
@@ -71,16 +110,85 @@ export interface CreateAppLog {
   message: string
   metadata?: Record<string, unknown>
   userId?: string
+  tags?: string[]
 }
 
 export interface LogQuery {
   level?: string
   userId?: string
+  /** Only entries carrying all of these tags. */
+  tags?: string[]
+  /** Only entries from this source, e.g. `frontend`. */
+  source?: string
   limit?: number
   offset?: number
 }
 
+export interface SourceLocation {
+  file: string
+  line?: number
+  column?: number
+}
+
+export interface FrontendError {
+  kind: 'error' | 'unhandled_rejection' | 'console'
+  message: string
+  stack?: string
+  /** Where the error was raised in the bundled code. */
+  location?: SourceLocation
+  /** `location` mapped back to the original source. */
+  originalLocation?: SourceLocation
+  url?: string
+  userAgent?: string
+  occurredAt?: string
+}
+
+export interface AuditEntry {
+  id: string
+  action: string
+  /** Who performed the action; absent for the app itself and failed sign-ins. */
+  actorId?: string
+  /** The user the action affected. */
+  userId?: string
+  details: Record<string, unknown>
+  createdAt: string
+}
+
+export interface AuditQuery {
+  action?: string
+  actorId?: string
+  userId?: string
+  /** Only entries recorded at or after this ISO 8601 time. */
+  from?: string
+  /** Only entries recorded before this ISO 8601 time. */
+  to?: string
+  limit?: number
+  offset?: number
+}
+
+/** Argon2id cost of new password hashes. */
+export interface PasswordHashing {
+  memoryKib: number
+  iterations: number
+  parallelism: number
+}
+
+export interface HashingBenchmark {
+  targetMs: number
+  current: PasswordHashing
+  currentMs: number
+  /** Highest cost that hashes within the target on this machine. */
+  recommended: PasswordHashing
+  recommendedMs: number
+  /** Whether new hashes now use `recommended`. */
+  applied: boolean
+}
+
+/** Database engine selected by the scheme of `DATABASE_URL`. */
+export type DatabaseBackend = 'postgres' | 'sqlite'
+
 export interface DatabaseStatus {
+  backend: DatabaseBackend
   connected: boolean
   databaseName?: string
   version?: string
@@ -7,6 +7,27 @@ export interface SystemInfo {
   hostname: string
 }
 
+/** How and from what the running binary was built. */
+export interface AppVersionInfo {
+  /** Semantic version from Cargo.toml. */
+  version: string
+  /** Abbreviated commit hash; absent outside a git checkout. */
+  gitCommit?: string
+  buildDate?: string
+  /** `debug` or `release`. */
+  profile: string
+  target: string
+  /** Enabled cargo features, e.g. `database` or `scripting`. */
+  features: string[]
+}
+
+export interface LocaleInfo {
+  locale: string
+  languages: string[]
+  timezone: string
+  utcOffsetSeconds: number
+}
+
 export interface WindowInfo {
   label: string
   title: string
@@ -39,8 +60,64 @@ export interface NotificationOptions {
   icon?: string
 }
 
+/** Working directory and extra environment for `executeCommand`. */
+export interface CommandOptions {
+  /** Directory relative to the application data directory. */
+  cwd?: string
+  /**
+   * Added to the few variables inherited from the app, e.g. `PATH`. Only
+   * harmless settings such as `NODE_ENV`, `CI`, or `RUST_LOG` are accepted.
+   */
+  env?: Record<string, string>
+}
+
 export interface CommandResult {
   success: boolean
   output?: string
   error?: string
 }
+
+export type OperationStatus = 'running' | 'completed' | 'failed' | 'cancelled'
+
+/** Payload of the `operation:progress` event. */
+export interface OperationProgress {
+  operationId: string
+  kind: string
+  status: OperationStatus
+  done: number
+  total?: number
+  message?: string
+}
+
+/** Whether maintenance mode pauses commands; payload of `maintenance:changed`. */
+export interface MaintenanceStatus {
+  active: boolean
+  /** One per operator request or running backup, restore, or migration. */
+  reasons: string[]
+  since?: string
+}
+
+export type StartupTaskState =
+  | 'pending'
+  | 'running'
+  | 'succeeded'
+  | 'failed'
+  | 'skipped'
+
+/** A subsystem brought up at startup; payload of `startup:task`. */
+export interface StartupTaskStatus {
+  name: string
+  state: StartupTaskState
+  dependsOn: string[]
+  attempts: number
+  /** Error of the last failed attempt, or why the task was skipped. */
+  error?: string
+  startedAt?: string
+  finishedAt?: string
+}
+
+export interface StartupStatus {
+  /** Whether every task has succeeded, failed, or been skipped. */
+  complete: boolean
+  tasks: StartupTaskStatus[]
+}
@@ -32,6 +32,17 @@ const DEFAULT_RETRY_OPTIONS: RetryOptions = {
   maxDelay: 10000,
 }
 
+// Access token of the signed-in session, sent with every command
+let accessToken: string | null = null
+
+/**
+ * Sets the access token sent as a bearer token with every command, so the
+ * backend knows who is calling. Pass `null` once signed out.
+ */
+export const setAccessToken = (token: string | null): void => {
+  accessToken = token
+}
+
 export class ApiWrapper {
   private static instance: ApiWrapper
   private errorHandler: ErrorHandler
@@ -77,7 +88,13 @@ export class ApiWrapper {
       // Tauri's invoke doesn't support AbortController directly,
       // so we'll implement timeout via Promise.race
       const result = await Promise.race([
-        invoke<T>(command, args),
+        invoke<T>(
+          command,
+          args,
+          accessToken
+            ? { headers: { Authorization: `Bearer ${accessToken}` } }
+            : undefined
+        ),
         this.createTimeoutPromise(options.timeout),
       ])
 
@@ -191,7 +208,9 @@ export class ApiWrapper {
       // Convert generic errors to AppErrors with appropriate codes
       let errorCode: ErrorCode = ErrorCodes.API_ERROR
 
-      if (error.message.includes('timeout')) {
+      if (error.message.includes('[MAINTENANCE_MODE]')) {
+        errorCode = ErrorCodes.MAINTENANCE_MODE
+      } else if (error.message.includes('timeout')) {
         errorCode = ErrorCodes.TIMEOUT_ERROR
       } else if (
         error.message.includes('network') ||
@@ -1,4 +1,4 @@
-import { logError } from '../api'
+import { ingestFrontendErrors } from '../api'
 
 export interface ErrorContext {
   component?: string
@@ -74,6 +74,7 @@ export const ErrorCodes = {
   // General errors
   UNKNOWN_ERROR: 'UNKNOWN_ERROR',
   CONFIGURATION_ERROR: 'CONFIGURATION_ERROR',
+  MAINTENANCE_MODE: 'MAINTENANCE_MODE',
 } as const
 
 export type ErrorCode = (typeof ErrorCodes)[keyof typeof ErrorCodes]
@@ -114,15 +115,16 @@ export class ErrorHandler {
     // Log to backend if enabled
     if (logToBackend) {
       try {
-        await logError(appError.message, {
-          code: appError.code,
-          context: appError.context,
-          timestamp: appError.timestamp.toISOString(),
-          stack: appError.stack,
-          isOperational: appError.isOperational,
-          userAgent: navigator.userAgent,
-          url: window.location.href,
-        })
+        await ingestFrontendErrors([
+          {
+            kind: 'error',
+            message: `[${appError.code}] ${appError.message}`,
+            stack: appError.stack,
+            url: window.location.href,
+            userAgent: navigator.userAgent,
+            occurredAt: appError.timestamp.toISOString(),
+          },
+        ])
       } catch (loggingError) {
         console.error('Failed to log error to backend:', loggingError)
       }
@@ -190,6 +192,8 @@ export class ErrorHandler {
         return 'Data storage error. Please try again.'
       case ErrorCodes.FILE_ERROR:
         return 'File operation failed. Please try again.'
+      case ErrorCodes.MAINTENANCE_MODE:
+        return 'Maintenance is in progress. Please try again shortly.'
       default:
         return 'An unexpected error occurred. Please try again.'
     }
@@ -36,6 +36,8 @@ export interface LogQueryParams {
   messageContains?: string
   limit?: number
   offset?: number
+  /** Read newest entries first and stop once the page is filled. */
+  tail?: boolean
 }
 
 export interface LogResponse {
@@ -44,6 +46,52 @@ export interface LogResponse {
   hasMore: boolean
 }
 
+/** Position between two log entries, stable while logs are written. */
+export interface LogCursor {
+  file: string
+  offset: number
+}
+
+export interface LogPage {
+  logs: LogEntry[]
+  /** Cursor of the next older page; null once the oldest entry was read. */
+  nextCursor: LogCursor | null
+}
+
+export type LogGroupBy = 'level' | 'target' | 'time'
+
+export type LogSource = 'files' | 'database'
+
+/** Half-open range; either end may be left open. */
+export interface LogRange {
+  start?: string
+  end?: string
+}
+
+export interface LogAggregateParams {
+  groupBy: LogGroupBy[]
+  /** Width of the time buckets, e.g. `5m`; defaults to `1h`. */
+  interval?: string
+  range?: LogRange
+  source?: LogSource
+}
+
+/** Dimensions that were not grouped by are null. */
+export interface LogBucket {
+  time: string | null
+  level: string | null
+  target: string | null
+  count: number
+}
+
+export interface LogAggregation {
+  source: LogSource
+  groupBy: LogGroupBy[]
+  intervalSeconds: number | null
+  buckets: LogBucket[]
+  total: number
+}
+
 export interface LogConfig {
   enabled: boolean
   level: LogLevel
@@ -56,6 +104,7 @@ export interface LogConfig {
     enabled: boolean
     directory: string
     filenamePrefix: string
+    format: string
     rotation: string
     maxFiles: number
     maxSizeMb?: number
@@ -274,6 +323,35 @@ class Logger {
     }
   }
 
+  /**
+   * Get a page of logs, newest first, ending at `cursor`
+   */
+  async getLogPage(params: LogQueryParams, cursor?: LogCursor | null): Promise<LogPage> {
+    try {
+      return await invoke('get_log_page', { params, cursor: cursor ?? null })
+    } catch (error) {
+      console.error('Failed to get log page:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Count logs grouped by level, target, and time bucket
+   */
+  async aggregateLogs(params: LogAggregateParams): Promise<LogAggregation> {
+    try {
+      return await invoke('aggregate_logs', {
+        groupBy: params.groupBy,
+        interval: params.interval ?? null,
+        range: params.range ?? null,
+        source: params.source ?? null,
+      })
+    } catch (error) {
+      console.error('Failed to aggregate logs:', error)
+      throw error
+    }
+  }
+
   /**
    * Get log configuration
    */